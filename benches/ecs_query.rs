@@ -0,0 +1,153 @@
+//! Benchmark of ECS query iteration over 100k entities.
+//!
+//! Runs natively with `cargo bench --bench ecs_query`.
+//!
+//! Iterations over [`EntityManager`] storing components in contiguous columns
+//! are compared with iterations over [`BoxedChunk`],
+//! which reproduces the previous chunk layout storing boxed components of entities one after another.
+
+use std::{
+    any::Any,
+    time::{Duration, Instant},
+};
+
+use atoy::anewthing::ecs::{
+    component::{Component, ComponentSet},
+    manager::EntityManager,
+    query::{QuerySimple, QuerySimple2, With},
+};
+
+const ENTITIES: usize = 100_000;
+const ROUNDS: u32 = 100;
+
+struct Position([f64; 3]);
+
+impl Component for Position {}
+
+struct Velocity([f64; 3]);
+
+impl Component for Velocity {}
+
+/// Previous chunk layout, components of an entity are boxed and stored next to each other,
+/// so a chunk of `n` components per entity stores component `i` of entity `e` at `e * n + i`.
+/// Queries look up component positions in the archetype once per chunk
+/// and downcast every component when iterating.
+struct BoxedChunk {
+    components_len: usize,
+    components: Vec<Box<dyn Any>>,
+}
+
+impl BoxedChunk {
+    fn new() -> Self {
+        let mut components: Vec<Box<dyn Any>> = Vec::with_capacity(ENTITIES * 2);
+        for i in 0..ENTITIES {
+            components.push(Box::new(Position([i as f64, 0.0, 0.0])));
+            components.push(Box::new(Velocity([1.0, 2.0, 3.0])));
+        }
+        Self {
+            components_len: 2,
+            components,
+        }
+    }
+
+    fn query<C: 'static>(&mut self, index: usize) -> impl Iterator<Item = &mut C> {
+        self.components
+            .iter_mut()
+            .skip(index)
+            .step_by(self.components_len)
+            .map(|component| component.downcast_mut::<C>().unwrap())
+    }
+
+    fn query2<A: 'static, B: 'static>(
+        &mut self,
+        a: usize,
+        b: usize,
+    ) -> impl Iterator<Item = (&mut A, &mut B)> {
+        let components_len = self.components_len;
+        self.components
+            .chunks_exact_mut(components_len)
+            .map(move |entity| {
+                let (head, tail) = entity.split_at_mut(a.max(b));
+                let (a, b) = if a < b {
+                    (&mut head[a], &mut tail[0])
+                } else {
+                    (&mut tail[0], &mut head[b])
+                };
+                (
+                    a.downcast_mut::<A>().unwrap(),
+                    b.downcast_mut::<B>().unwrap(),
+                )
+            })
+    }
+}
+
+fn create_manager() -> EntityManager {
+    let mut manager = EntityManager::new();
+    for i in 0..ENTITIES {
+        let mut components = ComponentSet::with_component(Position([i as f64, 0.0, 0.0]));
+        components.add(Velocity([1.0, 2.0, 3.0])).unwrap();
+        manager.create_entity(components).unwrap();
+    }
+    manager
+}
+
+fn report(name: &str, columns: Duration, boxed: Duration) {
+    let per_round = columns / ROUNDS;
+    let per_entity = per_round.as_nanos() as f64 / ENTITIES as f64;
+    let boxed_per_round = boxed / ROUNDS;
+    let boxed_per_entity = boxed_per_round.as_nanos() as f64 / ENTITIES as f64;
+    println!(
+        "{name:<24} columns {per_round:>12.3?}/round {per_entity:>8.3} ns/entity, \
+         boxed {boxed_per_round:>12.3?}/round {boxed_per_entity:>8.3} ns/entity, \
+         speedup {:.2}x",
+        boxed.as_secs_f64() / columns.as_secs_f64()
+    );
+}
+
+fn main() {
+    let start = Instant::now();
+    let mut manager = create_manager();
+    println!("{:<24} {:>12.3?}", "create entities", start.elapsed());
+    let mut chunk = BoxedChunk::new();
+
+    let mut sum = 0.0;
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for position in <With<Position>>::query_simple(&mut manager) {
+            sum += position.0[0];
+        }
+    }
+    let columns = start.elapsed();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for position in chunk.query::<Position>(0) {
+            sum += position.0[0];
+        }
+    }
+    report("query 1 component", columns, start.elapsed());
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for (mut position, velocity) in
+            <(With<Position>, With<Velocity>)>::query_simple(&mut manager)
+        {
+            position.0[0] += velocity.0[0];
+            position.0[1] += velocity.0[1];
+            position.0[2] += velocity.0[2];
+        }
+    }
+    let columns = start.elapsed();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for (position, velocity) in chunk.query2::<Position, Velocity>(0, 1) {
+            position.0[0] += velocity.0[0];
+            position.0[1] += velocity.0[1];
+            position.0[2] += velocity.0[2];
+        }
+    }
+    report("query 2 components", columns, start.elapsed());
+
+    // prevents the loops from being optimized away
+    std::hint::black_box(sum);
+    std::hint::black_box(&chunk.components);
+}
@@ -1,13 +1,26 @@
 use std::any::{Any, TypeId};
 
 use hashbrown::HashMap;
+use log::warn;
 
-use super::{ecs::manager::EntityManager, plugin::Plugin, renderer::Renderer};
+use super::{
+    ecs::manager::EntityManager,
+    event::Events,
+    plugin::Plugin,
+    renderer::Renderer,
+    resource::{FrameTime, Resource, Resources},
+    system::{Error, Stage, SystemDescriptor, SystemScheduler},
+};
 
 pub struct App {
     entity_manager: EntityManager,
-    renderer: Box<dyn Renderer>,
+    // renderer is taken out temporarily during rendering
+    renderer: Option<Box<dyn Renderer>>,
     plugins: HashMap<TypeId, Box<dyn Any>>,
+    resources: Resources,
+    event_updaters: HashMap<TypeId, fn(&mut Resources)>,
+    scheduler: SystemScheduler,
+    ticking: bool,
 }
 
 impl App {
@@ -18,10 +31,174 @@ impl App {
         Self {
             entity_manager: EntityManager::new(),
             plugins: HashMap::new(),
-            renderer: Box::new(renderer),
+            resources: {
+                let mut resources = Resources::new();
+                resources.insert(FrameTime::new());
+                resources
+            },
+            event_updaters: HashMap::new(),
+            renderer: Some(Box::new(renderer)),
+            scheduler: SystemScheduler::new(),
+            ticking: false,
         }
     }
 
+    pub fn entity_manager(&self) -> &EntityManager {
+        &self.entity_manager
+    }
+
+    pub fn entity_manager_mut(&mut self) -> &mut EntityManager {
+        &mut self.entity_manager
+    }
+
+    /// Returns the resource storage.
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    /// Returns the resource storage mutably.
+    pub fn resources_mut(&mut self) -> &mut Resources {
+        &mut self.resources
+    }
+
+    /// Returns a resource of type `R`.
+    pub fn resource<R>(&self) -> Option<&R>
+    where
+        R: Resource + 'static,
+    {
+        self.resources.get::<R>()
+    }
+
+    /// Returns a resource of type `R` mutably.
+    pub fn resource_mut<R>(&mut self) -> Option<&mut R>
+    where
+        R: Resource + 'static,
+    {
+        self.resources.get_mut::<R>()
+    }
+
+    /// Inserts a resource, returning the previous one of the same type if any.
+    pub fn insert_resource<R>(&mut self, resource: R) -> Option<R>
+    where
+        R: Resource + 'static,
+    {
+        self.resources.insert(resource)
+    }
+
+    /// Removes a resource of type `R`.
+    pub fn remove_resource<R>(&mut self) -> Option<R>
+    where
+        R: Resource + 'static,
+    {
+        self.resources.remove::<R>()
+    }
+
+    /// Adds an event channel of event type `E` as a resource,
+    /// which is updated at the end of each tick.
+    /// Does nothing if event channel exists already.
+    pub fn add_event<E>(&mut self)
+    where
+        E: 'static,
+    {
+        fn update<E: 'static>(resources: &mut Resources) {
+            if let Some(events) = resources.get_mut::<Events<E>>() {
+                events.update();
+            }
+        }
+
+        self.resources.get_or_insert_with(Events::<E>::new);
+        self.event_updaters
+            .entry(TypeId::of::<E>())
+            .or_insert(update::<E>);
+    }
+
+    /// Returns the event channel of event type `E`.
+    pub fn events<E>(&self) -> Option<&Events<E>>
+    where
+        E: 'static,
+    {
+        self.resources.get::<Events<E>>()
+    }
+
+    /// Returns the event channel of event type `E` mutably.
+    pub fn events_mut<E>(&mut self) -> Option<&mut Events<E>>
+    where
+        E: 'static,
+    {
+        self.resources.get_mut::<Events<E>>()
+    }
+
+    /// Sends an event.
+    /// Event is dropped with a warning if event channel is not added by [`App::add_event`].
+    pub fn send_event<E>(&mut self, event: E)
+    where
+        E: 'static,
+    {
+        match self.resources.get_mut::<Events<E>>() {
+            Some(events) => events.send(event),
+            None => warn!(
+                target: "App",
+                "event channel of {} not added, event dropped",
+                std::any::type_name::<E>()
+            ),
+        }
+    }
+
+    /// Returns the system scheduler.
+    ///
+    /// During [`App::tick`], the returned scheduler only holds systems added in current tick.
+    pub fn scheduler(&self) -> &SystemScheduler {
+        &self.scheduler
+    }
+
+    /// Adds a system to a stage.
+    ///
+    /// Systems added during [`App::tick`] are scheduled from next tick.
+    /// In that case, duplicated or cyclic systems are dropped with a warning when current tick finishes.
+    pub fn add_system(&mut self, stage: Stage, system: SystemDescriptor) -> Result<(), Error> {
+        self.scheduler.add_system(stage, system)
+    }
+
+    /// Removes a system by label.
+    /// Systems could not be removed during [`App::tick`], except those added in current tick.
+    pub fn remove_system(&mut self, label: &str) -> Option<SystemDescriptor> {
+        self.scheduler.remove_system(label)
+    }
+
+    /// Ticks the app, runs all system stages and then renders.
+    /// Startup stage runs only once, in the first tick.
+    ///
+    /// [`FrameTime`] is updated at the beginning of each tick,
+    /// while entity manager and event channels are maintained at the end of each tick.
+    pub fn tick(&mut self, timestamp: f64) {
+        if self.ticking {
+            warn!(target: "App", "app is ticking, nested tick skipped");
+            return;
+        }
+        self.ticking = true;
+        self.resources
+            .get_or_insert_with(FrameTime::new)
+            .advance(timestamp);
+
+        let mut scheduler = std::mem::replace(&mut self.scheduler, SystemScheduler::new());
+        scheduler.run(self, timestamp);
+        let added = std::mem::replace(&mut self.scheduler, scheduler);
+        for err in self.scheduler.merge(added) {
+            warn!(target: "App", "failed to add system: {err}");
+        }
+
+        if let Some(mut renderer) = self.renderer.take() {
+            renderer.render(self, timestamp);
+            self.renderer = Some(renderer);
+        }
+        self.entity_manager.maintain();
+        for update in self.event_updaters.values() {
+            update(&mut self.resources);
+        }
+
+        self.ticking = false;
+    }
+
     pub fn plugin<P>(&self) -> Option<&P>
     where
         P: Plugin + 'static,
@@ -84,14 +84,16 @@ impl Archetype {
     where
         C: Component + 'static,
     {
+        let key = ComponentKey::new::<C>();
+
         let mut components = self.0.clone();
-        components.push(ComponentKey::new::<C>());
+        components.push(key);
         components.sort();
         components.dedup();
         if components.len() == self.0.len() {
-            Ok(Self(components, self.1.clone()))
+            Err(Error::DuplicateComponent(None, key))
         } else {
-            Err(Error::DuplicateComponent)
+            Ok(Self(components, self.1.clone()))
         }
     }
 
@@ -104,7 +106,7 @@ impl Archetype {
         let mut components = self.0.clone();
         components.retain(|k| k != &key);
         if components.len() == self.0.len() {
-            Err(Error::NoSuchComponent)
+            Err(Error::NoSuchComponent(None, key))
         } else {
             Ok(Self(components, self.1.clone()))
         }
@@ -115,14 +117,16 @@ impl Archetype {
         C: Component + 'static,
         T: 'static,
     {
+        let key = SharedComponentKey::new::<C, T>();
+
         let mut components = self.1.clone();
-        components.push(SharedComponentKey::new::<C, T>());
+        components.push(key);
         components.sort();
         components.dedup();
-        if components.len() == self.0.len() {
-            Ok(Self(self.0.clone(), components))
+        if components.len() == self.1.len() {
+            Err(Error::DuplicateSharedComponent(key))
         } else {
-            Err(Error::DuplicateComponent)
+            Ok(Self(self.0.clone(), components))
         }
     }
 
@@ -135,10 +139,93 @@ impl Archetype {
 
         let mut components = self.1.clone();
         components.retain(|k| k != &key);
-        if components.len() == self.0.len() {
-            Err(Error::NoSuchComponent)
+        if components.len() == self.1.len() {
+            Err(Error::NoSuchSharedComponent(key))
         } else {
             Ok(Self(self.0.clone(), components))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anewthing::ecs::component::ComponentSet;
+
+    struct Position;
+
+    impl Component for Position {}
+
+    struct Velocity;
+
+    impl Component for Velocity {}
+
+    struct Material;
+
+    impl Component for Material {}
+
+    #[test]
+    fn test_archetype_transitions() {
+        let archetype = Archetype::with_component::<Position>()
+            .add_component::<Velocity>()
+            .unwrap();
+        assert!(archetype.has_component::<Position>());
+        assert!(archetype.has_component::<Velocity>());
+        assert!(matches!(
+            archetype.add_component::<Velocity>(),
+            Err(Error::DuplicateComponent(None, key)) if key == ComponentKey::new::<Velocity>()
+        ));
+
+        let archetype = archetype.remove_component::<Position>().unwrap();
+        assert_eq!(archetype, Archetype::with_component::<Velocity>());
+        assert!(matches!(
+            archetype.remove_component::<Position>(),
+            Err(Error::NoSuchComponent(None, key)) if key == ComponentKey::new::<Position>()
+        ));
+
+        // shared components are checked against shared ones only
+        let shared = archetype.add_shared_component::<Material, ()>().unwrap();
+        assert!(shared.has_shared_component::<Material, ()>());
+        assert!(matches!(
+            shared.add_shared_component::<Material, ()>(),
+            Err(Error::DuplicateSharedComponent(_))
+        ));
+        assert!(shared.add_shared_component::<Material, u32>().is_ok());
+        assert_eq!(
+            shared.remove_shared_component::<Material, ()>().unwrap(),
+            archetype
+        );
+        assert!(matches!(
+            archetype.remove_shared_component::<Material, ()>(),
+            Err(Error::NoSuchSharedComponent(_))
+        ));
+    }
+
+    #[test]
+    fn test_component_set_archetype() {
+        let mut components = ComponentSet::with_component(Position);
+        components.add(Velocity).unwrap();
+        components.add_shared::<Material, ()>().unwrap();
+        assert!(matches!(
+            components.add_shared::<Material, ()>(),
+            Err(Error::DuplicateSharedComponent(_))
+        ));
+        assert!(matches!(
+            components.add_shared_instance::<Material, ()>(Material),
+            Err(Error::DuplicateSharedComponent(_))
+        ));
+
+        let archetype = components.archetype();
+        assert_eq!(archetype.components_len(), 2);
+        assert!(archetype.has_shared_component::<Material, ()>());
+
+        components.remove_shared::<Material, ()>().unwrap();
+        assert!(!components
+            .archetype()
+            .has_shared_component::<Material, ()>());
+        assert!(matches!(
+            components.remove::<Material>(),
+            Err(Error::NoSuchComponent(None, _))
+        ));
+    }
+}
@@ -0,0 +1,251 @@
+use std::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use super::{
+    component::{Component, ComponentKey},
+    entity::EntityKey,
+    manager::EntityManager,
+};
+
+/// A mutable reference to a component with change detection.
+///
+/// Component is marked as changed at current change tick of [`EntityManager`]
+/// only when it is dereferenced mutably.
+pub struct Mut<'a, C> {
+    component: &'a mut C,
+    changed_tick: Option<&'a mut u64>,
+    change_tick: u64,
+}
+
+impl<'a, C> Mut<'a, C> {
+    pub(super) fn new(
+        component: &'a mut C,
+        changed_tick: Option<&'a mut u64>,
+        change_tick: u64,
+    ) -> Self {
+        Self {
+            component,
+            changed_tick,
+            change_tick,
+        }
+    }
+
+    /// Marks the component as changed.
+    pub fn set_changed(&mut self) {
+        if let Some(changed_tick) = self.changed_tick.as_mut() {
+            **changed_tick = self.change_tick;
+        }
+    }
+
+    /// Returns the component mutably without marking it as changed.
+    pub fn bypass_change_detection(&mut self) -> &mut C {
+        self.component
+    }
+
+    /// Converts into the inner mutable reference, marking the component as changed.
+    pub fn into_inner(mut self) -> &'a mut C {
+        self.set_changed();
+        self.component
+    }
+}
+
+impl<'a, C> Deref for Mut<'a, C> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        self.component
+    }
+}
+
+impl<'a, C> DerefMut for Mut<'a, C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.set_changed();
+        self.component
+    }
+}
+
+/// Removed components of a single component type, double buffered in [`EntityManager`].
+///
+/// Records survive for two [`EntityManager::maintain`] calls,
+/// so a reader running once per frame never misses any removal.
+pub(super) struct RemovedComponentsBuffer {
+    previous: Vec<(u64, EntityKey)>,
+    current: Vec<(u64, EntityKey)>,
+}
+
+impl RemovedComponentsBuffer {
+    pub(super) fn new() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+        }
+    }
+
+    pub(super) fn push(&mut self, id: u64, entity_key: EntityKey) {
+        self.current.push((id, entity_key));
+    }
+
+    pub(super) fn swap(&mut self) {
+        std::mem::swap(&mut self.previous, &mut self.current);
+        self.current.clear();
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &(u64, EntityKey)> {
+        self.previous.iter().chain(self.current.iter())
+    }
+}
+
+/// A reader of entities whose component `C` has been removed,
+/// either by [`EntityManager::remove_component`] or [`EntityManager::remove_entity`].
+///
+/// Each reader tracks its own cursor, so every system should keep its own reader.
+pub struct RemovedComponents<C> {
+    cursor: u64,
+    _component: PhantomData<C>,
+}
+
+impl<C> RemovedComponents<C>
+where
+    C: Component + 'static,
+{
+    /// Constructs a new reader reading removals happen from now on.
+    pub fn new(manager: &EntityManager) -> Self {
+        Self {
+            cursor: manager.next_removal_id(),
+            _component: PhantomData,
+        }
+    }
+
+    /// Reads entity keys whose component `C` has been removed since last read.
+    pub fn read<'a>(&mut self, manager: &'a EntityManager) -> impl Iterator<Item = EntityKey> + 'a {
+        let cursor = self.cursor;
+        self.cursor = manager.next_removal_id();
+        manager
+            .removed_components(&ComponentKey::new::<C>())
+            .into_iter()
+            .flat_map(|buffer| buffer.iter())
+            .filter(move |(id, _)| *id >= cursor)
+            .map(|(_, entity_key)| *entity_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anewthing::ecs::{
+        component::ComponentSet,
+        query::{Added, Changed, QuerySimple, QuerySimple2, With},
+    };
+
+    #[derive(Debug, PartialEq)]
+    struct Position(f64);
+
+    impl Component for Position {}
+
+    #[derive(Debug, PartialEq)]
+    struct Velocity(f64);
+
+    impl Component for Velocity {}
+
+    fn spawn(manager: &mut EntityManager, position: f64) -> EntityKey {
+        let mut components = ComponentSet::with_component(Position(position));
+        components.add(Velocity(1.0)).unwrap();
+        manager.create_entity(components).unwrap()
+    }
+
+    fn added(manager: &mut EntityManager) -> Vec<f64> {
+        let mut positions = <Added<Position>>::query_simple(manager)
+            .map(|position| position.0)
+            .collect::<Vec<_>>();
+        positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        positions
+    }
+
+    fn changed(manager: &mut EntityManager) -> Vec<f64> {
+        let mut positions = <Changed<Position>>::query_simple(manager)
+            .map(|position| position.0)
+            .collect::<Vec<_>>();
+        positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        positions
+    }
+
+    #[test]
+    fn test_added_and_changed() {
+        let mut manager = EntityManager::new();
+        let a = spawn(&mut manager, 0.0);
+        spawn(&mut manager, 1.0);
+
+        // first run sees everything as added and changed
+        manager.set_last_change_tick(0);
+        assert_eq!(added(&mut manager), vec![0.0, 1.0]);
+        assert_eq!(changed(&mut manager), vec![0.0, 1.0]);
+        let last_run = manager.increment_change_tick();
+
+        // reading mutably without writing does not mark as changed
+        for (position, velocity) in <(With<Position>, With<Velocity>)>::query_simple(&mut manager) {
+            if position.0 > 0.5 {
+                continue;
+            }
+            let velocity = velocity.0;
+            let mut position = position;
+            position.0 += velocity;
+        }
+        spawn(&mut manager, 2.0);
+        manager.increment_change_tick();
+
+        manager.set_last_change_tick(last_run);
+        assert_eq!(added(&mut manager), vec![2.0]);
+        assert_eq!(changed(&mut manager), vec![1.0, 2.0]);
+        let last_run = manager.increment_change_tick();
+
+        manager.get_mut::<Position>(&a).unwrap().0 = 10.0;
+        manager.add_component(&a, Velocity(0.0)).unwrap_err();
+        manager.increment_change_tick();
+
+        manager.set_last_change_tick(last_run);
+        assert_eq!(added(&mut manager), Vec::<f64>::new());
+        assert_eq!(changed(&mut manager), vec![10.0]);
+    }
+
+    #[test]
+    fn test_changes_survive_archetype_moves() {
+        let mut manager = EntityManager::new();
+        let a = manager
+            .create_entity(ComponentSet::with_component(Position(0.0)))
+            .unwrap();
+        let last_run = manager.increment_change_tick();
+
+        manager.add_component(&a, Velocity(0.0)).unwrap();
+        manager.set_last_change_tick(last_run);
+        // position is moved to another chunk, but it is neither added nor changed
+        assert_eq!(added(&mut manager), Vec::<f64>::new());
+        assert_eq!(<Added<Velocity>>::query_simple(&mut manager).count(), 1);
+    }
+
+    #[test]
+    fn test_removed_components() {
+        let mut manager = EntityManager::new();
+        let a = spawn(&mut manager, 0.0);
+        let b = spawn(&mut manager, 1.0);
+
+        let mut reader = RemovedComponents::<Velocity>::new(&manager);
+        let mut late_reader = RemovedComponents::<Velocity>::new(&manager);
+        manager.remove_component::<Velocity>(&a).unwrap();
+        manager.remove_entity(&b).unwrap();
+
+        assert_eq!(reader.read(&manager).collect::<Vec<_>>(), vec![a, b]);
+        assert_eq!(reader.read(&manager).count(), 0);
+
+        // records survive one maintenance
+        manager.maintain();
+        assert_eq!(late_reader.read(&manager).collect::<Vec<_>>(), vec![a, b]);
+
+        let mut late_reader = RemovedComponents::<Position>::new(&manager);
+        manager.remove_entity(&a).unwrap();
+        manager.maintain();
+        manager.maintain();
+        assert_eq!(late_reader.read(&manager).count(), 0);
+    }
+}
@@ -0,0 +1,359 @@
+use std::{
+    alloc::{self, Layout},
+    any::Any,
+    mem::ManuallyDrop,
+    ptr::{self, NonNull},
+};
+
+use super::component::{Component, ComponentInfo, ComponentKey};
+
+/// A tightly packed and type-erased column storing components of the same type.
+///
+/// Components are stored contiguously in a raw byte allocation described by [`ComponentInfo`],
+/// and they are dropped using the drop function from [`ComponentInfo`] as well.
+/// Change ticks of each component are stored alongside, at the same index.
+pub(super) struct Column {
+    info: ComponentInfo,
+    data: NonNull<u8>,
+    len: usize,
+    capacity: usize,
+    added_ticks: Vec<u64>,
+    changed_ticks: Vec<u64>,
+}
+
+impl Column {
+    /// Constructs a new empty column.
+    pub(super) fn new(info: ComponentInfo) -> Self {
+        let layout = info.layout();
+        Self {
+            info,
+            // a dangling but well aligned pointer
+            data: NonNull::new(layout.align() as *mut u8).unwrap(),
+            len: 0,
+            // zero-sized components never allocate
+            capacity: if layout.size() == 0 { usize::MAX } else { 0 },
+            added_ticks: Vec::new(),
+            changed_ticks: Vec::new(),
+        }
+    }
+
+    /// Returns the component information of this column.
+    pub(super) fn info(&self) -> &ComponentInfo {
+        &self.info
+    }
+
+    /// Returns the number of components.
+    pub(super) fn len(&self) -> usize {
+        self.len
+    }
+
+    fn array_layout(&self, capacity: usize) -> Layout {
+        let layout = self.info.layout();
+        let size = layout
+            .size()
+            .checked_mul(capacity)
+            .expect("column capacity overflow");
+        Layout::from_size_align(size, layout.align()).expect("column capacity overflow")
+    }
+
+    /// Reserves capacity for at least `additional` more components.
+    pub(super) fn reserve(&mut self, additional: usize) {
+        let required = self
+            .len
+            .checked_add(additional)
+            .expect("column capacity overflow");
+        if required <= self.capacity {
+            return;
+        }
+
+        let capacity = required.max(self.capacity.saturating_mul(2)).max(4);
+        let layout = self.array_layout(capacity);
+        let data = unsafe {
+            if self.capacity == 0 {
+                alloc::alloc(layout)
+            } else {
+                alloc::realloc(
+                    self.data.as_ptr(),
+                    self.array_layout(self.capacity),
+                    layout.size(),
+                )
+            }
+        };
+        self.data = NonNull::new(data).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        self.capacity = capacity;
+    }
+
+    /// Returns pointer to the component at `index`.
+    /// Index is not checked.
+    #[inline]
+    pub(super) unsafe fn get_ptr(&self, index: usize) -> *mut u8 {
+        self.data.as_ptr().add(index * self.info.layout().size())
+    }
+
+    /// Returns a component at `index`.
+    /// Component type and index are not checked.
+    #[inline]
+    pub(super) unsafe fn get_unchecked<C>(&self, index: usize) -> &C {
+        &*(self.get_ptr(index) as *const C)
+    }
+
+    /// Returns a component at `index` mutably.
+    /// Component type and index are not checked.
+    #[inline]
+    pub(super) unsafe fn get_unchecked_mut<C>(&mut self, index: usize) -> &mut C {
+        &mut *(self.get_ptr(index) as *mut C)
+    }
+
+    /// Returns a component at `index` as [`Any`].
+    pub(super) fn get_any(&self, index: usize) -> Option<&(dyn Any + 'static)> {
+        if index >= self.len {
+            return None;
+        }
+        unsafe { Some(&*(self.info.as_any())(self.get_ptr(index))) }
+    }
+
+    /// Returns a component at `index` as mutable [`Any`].
+    pub(super) fn get_any_mut(&mut self, index: usize) -> Option<&mut (dyn Any + 'static)> {
+        if index >= self.len {
+            return None;
+        }
+        unsafe { Some(&mut *(self.info.as_any())(self.get_ptr(index))) }
+    }
+
+    /// Returns the tick when component at `index` was added.
+    pub(super) fn added_tick(&self, index: usize) -> u64 {
+        self.added_ticks[index]
+    }
+
+    /// Returns the tick when component at `index` was last changed.
+    pub(super) fn changed_tick(&self, index: usize) -> u64 {
+        self.changed_ticks[index]
+    }
+
+    /// Returns a component at `index` along with its changed tick mutably.
+    /// Component type and index are not checked.
+    pub(super) unsafe fn get_with_tick_unchecked_mut<C>(
+        &mut self,
+        index: usize,
+    ) -> (&mut C, &mut u64) {
+        (
+            &mut *(self.get_ptr(index) as *mut C),
+            self.changed_ticks.get_unchecked_mut(index),
+        )
+    }
+
+    /// Returns a component at `index` as mutable [`Any`] along with its changed tick mutably.
+    pub(super) fn get_any_with_tick_mut(
+        &mut self,
+        index: usize,
+    ) -> Option<(&mut (dyn Any + 'static), &mut u64)> {
+        if index >= self.len {
+            return None;
+        }
+        unsafe {
+            Some((
+                &mut *(self.info.as_any())(self.get_ptr(index)),
+                self.changed_ticks.get_unchecked_mut(index),
+            ))
+        }
+    }
+
+    /// Returns all components as a slice.
+    /// Component type is not checked.
+    pub(super) unsafe fn as_slice<C>(&self) -> &[C] {
+        std::slice::from_raw_parts(self.data.as_ptr() as *const C, self.len)
+    }
+
+    /// Returns all components as a mutable slice.
+    /// Component type is not checked.
+    pub(super) unsafe fn as_mut_slice<C>(&mut self) -> &mut [C] {
+        std::slice::from_raw_parts_mut(self.data.as_ptr() as *mut C, self.len)
+    }
+
+    /// Pushes a component by copying bytes from `src`, taking the ownership of it.
+    /// Caller must not drop the source afterward.
+    pub(super) unsafe fn push_raw(&mut self, src: *const u8, added_tick: u64, changed_tick: u64) {
+        self.reserve(1);
+        ptr::copy_nonoverlapping(src, self.get_ptr(self.len), self.info.layout().size());
+        self.added_ticks.push(added_tick);
+        self.changed_ticks.push(changed_tick);
+        self.len += 1;
+    }
+
+    /// Pushes a component added at `tick`.
+    pub(super) fn push<C>(&mut self, component: C, tick: u64)
+    where
+        C: Component + 'static,
+    {
+        assert_eq!(self.info.key(), ComponentKey::new::<C>());
+        let component = ManuallyDrop::new(component);
+        unsafe { self.push_raw(&*component as *const C as *const u8, tick, tick) }
+    }
+
+    /// Pushes a boxed component added at `tick`, unboxing it into the column.
+    /// Component type is not checked.
+    pub(super) unsafe fn push_boxed(&mut self, component: Box<dyn Any>, tick: u64) {
+        let layout = self.info.layout();
+        let raw = Box::into_raw(component) as *mut u8;
+        self.push_raw(raw, tick, tick);
+        // frees the box allocation without dropping the moved component
+        if layout.size() != 0 {
+            alloc::dealloc(raw, layout);
+        }
+    }
+
+    /// Fills the hole at `index` with the last component, without dropping anything.
+    unsafe fn fill_hole(&mut self, index: usize) {
+        let last = self.len - 1;
+        if index != last {
+            ptr::copy_nonoverlapping(
+                self.get_ptr(last),
+                self.get_ptr(index),
+                self.info.layout().size(),
+            );
+        }
+        self.added_ticks.swap_remove(index);
+        self.changed_ticks.swap_remove(index);
+        self.len -= 1;
+    }
+
+    /// Removes a component at `index` by swapping it with the last one and drops it.
+    pub(super) fn swap_remove_and_drop(&mut self, index: usize) {
+        assert!(index < self.len);
+        unsafe {
+            (self.info.drop())(self.get_ptr(index));
+            self.fill_hole(index);
+        }
+    }
+
+    /// Removes a component at `index` by swapping it with the last one and returns it.
+    /// Component type is not checked.
+    pub(super) unsafe fn swap_remove_unchecked<C>(&mut self, index: usize) -> C {
+        assert!(index < self.len);
+        let component = ptr::read(self.get_ptr(index) as *const C);
+        self.fill_hole(index);
+        component
+    }
+
+    /// Removes a component at `index` by swapping it with the last one and returns it boxed.
+    pub(super) fn swap_remove_boxed(&mut self, index: usize) -> Box<dyn Any> {
+        assert!(index < self.len);
+        unsafe {
+            let component = (self.info.into_boxed())(self.get_ptr(index));
+            self.fill_hole(index);
+            component
+        }
+    }
+
+    /// Removes a component at `index` by swapping it with the last one
+    /// and moves it to the end of another column of the same component type, without boxing.
+    /// Change ticks are moved as well.
+    pub(super) fn swap_remove_to(&mut self, index: usize, other: &mut Column) {
+        assert!(index < self.len);
+        assert_eq!(self.info.key(), other.info.key());
+        unsafe {
+            other.push_raw(
+                self.get_ptr(index),
+                self.added_ticks[index],
+                self.changed_ticks[index],
+            );
+            self.fill_hole(index);
+        }
+    }
+}
+
+impl Drop for Column {
+    fn drop(&mut self) {
+        unsafe {
+            for index in 0..self.len {
+                (self.info.drop())(self.get_ptr(index));
+            }
+            if self.info.layout().size() != 0 && self.capacity != 0 {
+                alloc::dealloc(self.data.as_ptr(), self.array_layout(self.capacity));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    struct Counted(u64, Rc<RefCell<usize>>);
+
+    impl Component for Counted {}
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            *self.1.borrow_mut() += 1;
+        }
+    }
+
+    struct Marker;
+
+    impl Component for Marker {}
+
+    #[test]
+    fn test_column() {
+        let drops = Rc::new(RefCell::new(0));
+        let mut column = Column::new(ComponentInfo::new::<Counted>());
+        for i in 0..10 {
+            column.push(Counted(i, Rc::clone(&drops)), i);
+        }
+        assert_eq!(column.len(), 10);
+
+        column.swap_remove_and_drop(2);
+        assert_eq!(*drops.borrow(), 1);
+        let removed = unsafe { column.swap_remove_unchecked::<Counted>(0) };
+        assert_eq!(removed.0, 0);
+        drop(removed);
+        assert_eq!(*drops.borrow(), 2);
+
+        let mut other = Column::new(ComponentInfo::new::<Counted>());
+        column.swap_remove_to(1, &mut other);
+        assert_eq!(*drops.borrow(), 2);
+        assert_eq!(unsafe { other.get_unchecked::<Counted>(0) }.0, 1);
+        assert_eq!(other.added_tick(0), 1);
+
+        let boxed = column.swap_remove_boxed(0);
+        assert_eq!(boxed.downcast_ref::<Counted>().unwrap().0, 8);
+        unsafe { other.push_boxed(boxed, 10) };
+        assert_eq!(other.added_tick(1), 10);
+        assert_eq!(*drops.borrow(), 2);
+
+        let values = unsafe { column.as_slice::<Counted>() }
+            .iter()
+            .map(|c| c.0)
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![6, 7, 9, 3, 4, 5]);
+        let ticks = (0..column.len())
+            .map(|index| column.changed_tick(index))
+            .collect::<Vec<_>>();
+        assert_eq!(ticks, values);
+
+        drop(column);
+        drop(other);
+        assert_eq!(*drops.borrow(), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "column capacity overflow")]
+    fn test_column_capacity_overflow() {
+        let mut column = Column::new(ComponentInfo::new::<Counted>());
+        column.reserve(usize::MAX / 2);
+    }
+
+    #[test]
+    fn test_zero_sized_column() {
+        let mut column = Column::new(ComponentInfo::new::<Marker>());
+        for _ in 0..100 {
+            column.push(Marker, 0);
+        }
+        column.swap_remove_and_drop(50);
+        assert_eq!(column.len(), 99);
+        assert!(column.get_any(98).unwrap().is::<Marker>());
+        assert!(column.get_any(99).is_none());
+    }
+}
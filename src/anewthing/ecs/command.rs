@@ -0,0 +1,205 @@
+use std::{cell::Cell, rc::Rc};
+
+use super::{
+    component::{Component, ComponentSet},
+    entity::EntityKey,
+    error::Error,
+    manager::EntityManager,
+};
+
+type Command = Box<dyn FnOnce(&mut EntityManager) -> Result<(), Error>>;
+
+/// A buffer recording structural changes of an [`EntityManager`],
+/// which could be used where the entity manager is borrowed, e.g. when iterating a query.
+///
+/// Commands are deferred until [`Commands::apply`] is called at a sync point,
+/// and then applied in the order they are recorded.
+/// A command buffer must always be applied, or entities reserved by it are leaked.
+pub struct Commands {
+    next_index: Rc<Cell<u32>>,
+    commands: Vec<Command>,
+}
+
+impl Commands {
+    /// Constructs a new command buffer for an entity manager.
+    pub fn new(manager: &EntityManager) -> Self {
+        Self {
+            next_index: manager.reserver(),
+            commands: Vec::new(),
+        }
+    }
+
+    /// Returns the number of recorded commands.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Returns `true` if no command is recorded.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Records a custom command.
+    pub fn add<F>(&mut self, command: F)
+    where
+        F: FnOnce(&mut EntityManager) -> Result<(), Error> + 'static,
+    {
+        self.commands.push(Box::new(command));
+    }
+
+    /// Records creating an entity and returns its entity key immediately.
+    ///
+    /// The entity key is reserved and becomes valid once commands are applied.
+    /// Commands recorded afterward could refer to it even before that.
+    pub fn spawn(&mut self, components: ComponentSet) -> EntityKey {
+        let index = self.next_index.get();
+        self.next_index.set(index + 1);
+        // a newly reserved slot is never used, so generation is always 0
+        let entity_key = EntityKey::new(index, 0);
+        self.add(move |manager| {
+            manager
+                .create_reserved_entity(entity_key, components)
+                .map(|_| ())
+        });
+        entity_key
+    }
+
+    /// Records removing an entity.
+    pub fn despawn(&mut self, entity_key: EntityKey) {
+        self.add(move |manager| manager.remove_entity(&entity_key).map(|_| ()));
+    }
+
+    /// Records removing an entity along with all its descendants.
+    pub fn despawn_recursive(&mut self, entity_key: EntityKey) {
+        self.add(move |manager| manager.remove_entity_recursive(&entity_key).map(|_| ()));
+    }
+
+    /// Records adding a component to an entity.
+    pub fn insert<C>(&mut self, entity_key: EntityKey, component: C)
+    where
+        C: Component + 'static,
+    {
+        self.add(move |manager| manager.add_component(&entity_key, component));
+    }
+
+    /// Records removing a component from an entity.
+    /// Removed component is dropped.
+    pub fn remove<C>(&mut self, entity_key: EntityKey)
+    where
+        C: Component + 'static,
+    {
+        self.add(move |manager| manager.remove_component::<C>(&entity_key).map(|_| ()));
+    }
+
+    /// Records attaching an entity to a parent.
+    pub fn set_parent(&mut self, entity_key: EntityKey, parent: EntityKey) {
+        self.add(move |manager| manager.set_parent(&entity_key, &parent));
+    }
+
+    /// Applies all recorded commands in order and clears the buffer.
+    ///
+    /// A failed command does not stop the rest from applying.
+    /// Returns errors of all failed commands.
+    pub fn apply(&mut self, manager: &mut EntityManager) -> Vec<Error> {
+        assert!(
+            Rc::ptr_eq(&self.next_index, &manager.reserver()),
+            "commands applied to a different entity manager"
+        );
+
+        self.commands
+            .drain(..)
+            .filter_map(|command| command(manager).err())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anewthing::ecs::query::{QuerySimple, With};
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+
+    impl Component for Health {}
+
+    struct Dead;
+
+    impl Component for Dead {}
+
+    #[test]
+    fn test_commands_while_iterating() {
+        let mut manager = EntityManager::new();
+        let a = manager
+            .create_entity(ComponentSet::with_component(Health(0)))
+            .unwrap();
+        manager
+            .create_entity(ComponentSet::with_component(Health(10)))
+            .unwrap();
+
+        let mut commands = Commands::new(&manager);
+        let mut spawned = Vec::new();
+        for health in <With<Health>>::query_simple(&mut manager) {
+            let key = commands.spawn(ComponentSet::with_component(Health(health.0 + 1)));
+            spawned.push(key);
+        }
+        commands.insert(a, Dead);
+        commands.insert(spawned[0], Dead);
+        commands.set_parent(spawned[1], spawned[0]);
+        assert_eq!(commands.len(), 5);
+
+        // reserved keys are not valid until applied
+        assert!(spawned.iter().all(|key| !manager.has_entity(key)));
+        assert!(commands.apply(&mut manager).is_empty());
+        assert!(commands.is_empty());
+        assert!(spawned.iter().all(|key| manager.has_entity(key)));
+        assert_eq!(manager.entity_keys().len(), 4);
+        assert!(manager.has_component::<Dead>(&a));
+        assert!(manager.has_component::<Dead>(&spawned[0]));
+        assert_eq!(manager.parent(&spawned[1]), Some(spawned[0]));
+
+        // entities created directly never collide with reserved ones
+        let reserved = commands.spawn(ComponentSet::with_component(Health(100)));
+        let created = manager
+            .create_entity(ComponentSet::with_component(Health(200)))
+            .unwrap();
+        assert_ne!(reserved, created);
+        assert!(commands.apply(&mut manager).is_empty());
+        assert_eq!(manager.get::<Health>(&reserved), Some(&Health(100)));
+        assert_eq!(manager.get::<Health>(&created), Some(&Health(200)));
+    }
+
+    #[test]
+    fn test_failed_commands() {
+        let mut manager = EntityManager::new();
+        let a = manager
+            .create_entity(ComponentSet::with_component(Health(0)))
+            .unwrap();
+
+        let mut commands = Commands::new(&manager);
+        let empty = commands.spawn(ComponentSet::new());
+        commands.despawn(a);
+        commands.remove::<Health>(a);
+        commands.despawn_recursive(a);
+        let b = commands.spawn(ComponentSet::with_component(Health(1)));
+
+        let errors = commands.apply(&mut manager);
+        assert_eq!(errors.len(), 3);
+        assert!(matches!(errors[0], Error::EmptyComponents));
+        assert!(matches!(errors[1], Error::NoSuchEntity(key) if key == a));
+        assert_eq!(
+            errors[2].to_string(),
+            format!("entity {}:{} does not exist", a.index(), a.generation())
+        );
+        assert!(!manager.has_entity(&empty));
+        assert!(!manager.has_entity(&a));
+        assert_eq!(manager.entity_keys(), vec![b]);
+
+        // failed reservation is released and its key never becomes valid
+        let c = manager
+            .create_entity(ComponentSet::with_component(Health(2)))
+            .unwrap();
+        assert_ne!(c, empty);
+        assert!(!manager.has_entity(&empty));
+    }
+}
@@ -1,40 +1,207 @@
-use std::any::{Any, TypeId};
+use std::{
+    alloc::Layout,
+    any::{type_name, Any, TypeId},
+    cmp::Ordering,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+};
 
 use super::{archetype::Archetype, error::Error};
 
 pub trait Component {}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct ComponentKey(TypeId);
+/// A key identifying a component type.
+///
+/// Keys are compared by type id only, type name is kept for reporting.
+#[derive(Clone, Copy)]
+pub struct ComponentKey(TypeId, &'static str);
 
 impl ComponentKey {
-    /// Constructs a new shared component key by a component type and a key.
+    /// Constructs a new component key by a component type.
     #[inline]
     pub fn new<C>() -> Self
     where
         C: Component + 'static,
     {
-        Self(TypeId::of::<C>())
+        Self(TypeId::of::<C>(), type_name::<C>())
+    }
+
+    /// Returns type name of the component.
+    pub fn name(&self) -> &'static str {
+        self.1
+    }
+}
+
+impl PartialEq for ComponentKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for ComponentKey {}
+
+impl PartialOrd for ComponentKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct SharedComponentKey(TypeId, TypeId);
+impl Ord for ComponentKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl Hash for ComponentKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl Debug for ComponentKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.1)
+    }
+}
+
+/// A key identifying a shared component by a component type and a tag type.
+///
+/// Keys are compared by type ids only, type names are kept for reporting.
+#[derive(Clone, Copy)]
+pub struct SharedComponentKey(TypeId, TypeId, &'static str, &'static str);
 
 impl SharedComponentKey {
-    /// Constructs a new shared component key by a component type and a key.
+    /// Constructs a new shared component key by a component type and a tag type.
     #[inline]
     pub fn new<C, T>() -> Self
     where
         C: Component + 'static,
         T: 'static,
     {
-        Self(TypeId::of::<C>(), TypeId::of::<T>())
+        Self(
+            TypeId::of::<C>(),
+            TypeId::of::<T>(),
+            type_name::<C>(),
+            type_name::<T>(),
+        )
+    }
+
+    /// Returns type name of the component.
+    pub fn name(&self) -> &'static str {
+        self.2
+    }
+
+    /// Returns type name of the tag.
+    pub fn tag_name(&self) -> &'static str {
+        self.3
+    }
+}
+
+impl PartialEq for SharedComponentKey {
+    fn eq(&self, other: &Self) -> bool {
+        (self.0, self.1) == (other.0, other.1)
+    }
+}
+
+impl Eq for SharedComponentKey {}
+
+impl PartialOrd for SharedComponentKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SharedComponentKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.0, self.1).cmp(&(other.0, other.1))
+    }
+}
+
+impl Hash for SharedComponentKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+        self.1.hash(state);
+    }
+}
+
+impl Debug for SharedComponentKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}<{}>", self.2, self.3)
+    }
+}
+
+/// Type-erased information of a component type,
+/// describing how to store, drop and restore components in raw bytes.
+#[derive(Clone, Copy)]
+pub struct ComponentInfo {
+    key: ComponentKey,
+    layout: Layout,
+    drop: unsafe fn(*mut u8),
+    into_boxed: unsafe fn(*mut u8) -> Box<dyn Any>,
+    as_any: unsafe fn(*mut u8) -> *mut dyn Any,
+}
+
+impl ComponentInfo {
+    /// Constructs a new component information by a component type.
+    pub fn new<C>() -> Self
+    where
+        C: Component + 'static,
+    {
+        unsafe fn drop<C>(ptr: *mut u8) {
+            std::ptr::drop_in_place(ptr as *mut C);
+        }
+
+        unsafe fn into_boxed<C>(ptr: *mut u8) -> Box<dyn Any>
+        where
+            C: 'static,
+        {
+            Box::new(std::ptr::read(ptr as *const C))
+        }
+
+        unsafe fn as_any<C>(ptr: *mut u8) -> *mut dyn Any
+        where
+            C: 'static,
+        {
+            ptr as *mut C as *mut dyn Any
+        }
+
+        Self {
+            key: ComponentKey::new::<C>(),
+            layout: Layout::new::<C>(),
+            drop: drop::<C>,
+            into_boxed: into_boxed::<C>,
+            as_any: as_any::<C>,
+        }
+    }
+
+    /// Returns component key.
+    pub fn key(&self) -> ComponentKey {
+        self.key
+    }
+
+    /// Returns memory layout of the component.
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Returns a function dropping a component in place.
+    pub(super) fn drop(&self) -> unsafe fn(*mut u8) {
+        self.drop
+    }
+
+    /// Returns a function moving a component out into a [`Box`].
+    pub(super) fn into_boxed(&self) -> unsafe fn(*mut u8) -> Box<dyn Any> {
+        self.into_boxed
+    }
+
+    /// Returns a function casting a component pointer into [`Any`].
+    pub(super) fn as_any(&self) -> unsafe fn(*mut u8) -> *mut dyn Any {
+        self.as_any
     }
 }
 
 pub struct ComponentSet(
-    pub(super) Vec<(ComponentKey, Box<dyn Any>)>, // non-shared components
+    pub(super) Vec<(ComponentInfo, Box<dyn Any>)>, // non-shared components
     pub(super) Vec<SharedComponentKey>,           // shared components with only type id
     pub(super) Vec<(SharedComponentKey, Box<dyn Any>)>, // shared components with instance as well
 );
@@ -49,7 +216,7 @@ impl ComponentSet {
         C: Component + 'static,
     {
         Self(
-            vec![(ComponentKey::new::<C>(), Box::new(component))],
+            vec![(ComponentInfo::new::<C>(), Box::new(component))],
             Vec::new(),
             Vec::new(),
         )
@@ -94,7 +261,7 @@ impl ComponentSet {
             .chain(self.2.iter().map(|(k, _)| k.clone()))
             .collect::<Vec<_>>();
         shard_keys.sort_by(|a, b| a.cmp(b));
-        Archetype(self.0.iter().map(|(id, _)| *id).collect(), shard_keys)
+        Archetype(self.0.iter().map(|(info, _)| info.key()).collect(), shard_keys)
     }
 
     pub fn add<C>(&mut self, component: C) -> Result<(), Error>
@@ -102,13 +269,13 @@ impl ComponentSet {
         C: Component + 'static,
     {
         let key = ComponentKey::new::<C>();
-        let has_component = self.0.iter().any(|(k, _)| k == &key);
+        let has_component = self.0.iter().any(|(info, _)| info.key() == key);
         if has_component {
-            return Err(Error::DuplicateComponent);
+            return Err(Error::DuplicateComponent(None, key));
         }
 
-        self.0.push((key, Box::new(component)));
-        self.0.sort_by(|(a, _), (b, _)| a.cmp(b));
+        self.0.push((ComponentInfo::new::<C>(), Box::new(component)));
+        self.0.sort_by(|(a, _), (b, _)| a.key().cmp(&b.key()));
 
         Ok(())
     }
@@ -117,8 +284,8 @@ impl ComponentSet {
     where
         C: Component + 'static,
     {
-        self.0.push((ComponentKey::new::<C>(), Box::new(component)));
-        self.0.sort_by(|(a, _), (b, _)| a.cmp(b));
+        self.0.push((ComponentInfo::new::<C>(), Box::new(component)));
+        self.0.sort_by(|(a, _), (b, _)| a.key().cmp(&b.key()));
     }
 
     pub fn remove<C>(&mut self) -> Result<C, Error>
@@ -126,8 +293,8 @@ impl ComponentSet {
         C: Component + 'static,
     {
         let key = ComponentKey::new::<C>();
-        let Some(index) = self.0.iter().position(|(k, _)| k == &key) else {
-            return Err(Error::NoSuchComponent);
+        let Some(index) = self.0.iter().position(|(info, _)| info.key() == key) else {
+            return Err(Error::NoSuchComponent(None, key));
         };
 
         let removed = *self.0.remove(index).1.downcast::<C>().unwrap();
@@ -147,9 +314,10 @@ impl ComponentSet {
             .chain(self.2.iter().map(|(k, _)| k))
             .any(|k| k == &key);
         if has_component {
-            return Err(Error::DuplicateComponent);
+            return Err(Error::DuplicateSharedComponent(key));
         }
 
+        self.1.push(key);
         self.1.sort_by(|a, b| a.cmp(b));
 
         Ok(())
@@ -171,7 +339,7 @@ impl ComponentSet {
     {
         let key = SharedComponentKey::new::<C, T>();
         let Some(index) = self.1.iter().position(|k| k == &key) else {
-            return Err(Error::NoSuchComponent);
+            return Err(Error::NoSuchSharedComponent(key));
         };
 
         self.1.remove(index);
@@ -191,7 +359,7 @@ impl ComponentSet {
             .chain(self.2.iter().map(|(k, _)| k))
             .any(|k| k == &key);
         if has_component {
-            return Err(Error::DuplicateComponent);
+            return Err(Error::DuplicateSharedComponent(key));
         }
 
         self.2.push((key, Box::new(shared_component)));
@@ -219,7 +387,7 @@ impl ComponentSet {
     {
         let key = SharedComponentKey::new::<C, T>();
         let Some(index) = self.2.iter().position(|(k, _)| k == &key) else {
-            return Err(Error::NoSuchComponent);
+            return Err(Error::NoSuchSharedComponent(key));
         };
 
         let removed = *self.2.remove(index).1.downcast::<C>().unwrap();
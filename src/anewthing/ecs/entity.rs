@@ -1,11 +1,29 @@
-use uuid::Uuid;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct EntityKey(Uuid);
+/// A generational entity key.
+///
+/// Index locates the entity slot in [`EntityManager`](super::manager::EntityManager),
+/// while generation tells whether the slot has been reused since this key was issued.
+/// A key referring to a removed entity never matches any living entity again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct EntityKey {
+    index: u32,
+    generation: u32,
+}
 
 impl EntityKey {
     /// Constructs a new entity key.
-    pub fn new() -> Self {
-        Self(Uuid::new_v4())
+    pub(super) fn new(index: u32, generation: u32) -> Self {
+        Self { index, generation }
+    }
+
+    /// Returns slot index of the entity.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns generation of the entity slot.
+    pub fn generation(&self) -> u32 {
+        self.generation
     }
 }
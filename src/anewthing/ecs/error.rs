@@ -1,22 +1,75 @@
 use std::fmt::{Debug, Display};
 
+use uuid::Uuid;
+
+use super::{
+    component::{ComponentKey, SharedComponentKey},
+    entity::EntityKey,
+};
+
 pub enum Error {
-    DuplicateComponent,
+    /// Component exists already, in an entity or in a component set or archetype if entity is `None`.
+    DuplicateComponent(Option<EntityKey>, ComponentKey),
+    /// Component does not exist, in an entity or in a component set or archetype if entity is `None`.
+    NoSuchComponent(Option<EntityKey>, ComponentKey),
+    DuplicateSharedComponent(SharedComponentKey),
+    NoSuchSharedComponent(SharedComponentKey),
+    /// Shared component is still used by some entities.
+    SharedComponentInUse(SharedComponentKey, usize),
     EmptyComponents,
-    NoSuchEntity,
-    NoSuchComponent,
-    ComponentInUsed,
+    NoSuchEntity(EntityKey),
+    DuplicateEntityUuid(Uuid),
+    /// Entity could not be attached to the parent, which is the entity itself or a descendant of it.
+    CyclicHierarchy(EntityKey, EntityKey),
+}
+
+/// Formats an entity key as `index:generation`.
+struct Entity<'a>(&'a EntityKey);
+
+impl<'a> Display for Entity<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "entity {}:{}", self.0.index(), self.0.generation())
+    }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        match self {
+            Error::DuplicateComponent(Some(entity), key) => {
+                write!(f, "{} has component {} already", Entity(entity), key.name())
+            }
+            Error::DuplicateComponent(None, key) => {
+                write!(f, "duplicate component {}", key.name())
+            }
+            Error::NoSuchComponent(Some(entity), key) => {
+                write!(f, "{} has no component {}", Entity(entity), key.name())
+            }
+            Error::NoSuchComponent(None, key) => write!(f, "no component {}", key.name()),
+            Error::DuplicateSharedComponent(key) => {
+                write!(f, "shared component {:?} exists already", key)
+            }
+            Error::NoSuchSharedComponent(key) => write!(f, "no shared component {:?}", key),
+            Error::SharedComponentInUse(key, count) => write!(
+                f,
+                "shared component {:?} is still used by {} entities",
+                key, count
+            ),
+            Error::EmptyComponents => f.write_str("entity requires at least one component"),
+            Error::NoSuchEntity(entity) => write!(f, "{} does not exist", Entity(entity)),
+            Error::DuplicateEntityUuid(uuid) => write!(f, "entity uuid {uuid} exists already"),
+            Error::CyclicHierarchy(entity, parent) => write!(
+                f,
+                "{} could not be attached to its descendant {}",
+                Entity(entity),
+                Entity(parent)
+            ),
+        }
     }
 }
 
 impl Debug for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        Display::fmt(self, f)
     }
 }
 
@@ -0,0 +1,307 @@
+use super::{component::Component, entity::EntityKey, error::Error, manager::EntityManager};
+
+/// Parent of an entity.
+///
+/// Hierarchy components are maintained by [`EntityManager::set_parent`]
+/// and [`EntityManager::remove_parent`], never add or remove them directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parent(EntityKey);
+
+impl Parent {
+    /// Returns entity key of the parent.
+    pub fn get(&self) -> EntityKey {
+        self.0
+    }
+}
+
+impl Component for Parent {}
+
+/// Children of an entity, in the order they are attached.
+///
+/// Hierarchy components are maintained by [`EntityManager::set_parent`]
+/// and [`EntityManager::remove_parent`], never add or remove them directly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Children(Vec<EntityKey>);
+
+impl Children {
+    /// Returns entity keys of the children.
+    pub fn as_slice(&self) -> &[EntityKey] {
+        &self.0
+    }
+
+    /// Returns the number of children.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if there is no child.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns an iterator over entity keys of the children.
+    pub fn iter(&self) -> std::slice::Iter<'_, EntityKey> {
+        self.0.iter()
+    }
+}
+
+impl Component for Children {}
+
+impl EntityManager {
+    /// Returns the parent of an entity if any.
+    pub fn parent(&self, entity_key: &EntityKey) -> Option<EntityKey> {
+        self.get::<Parent>(entity_key).map(|parent| parent.get())
+    }
+
+    /// Returns children of an entity.
+    /// Returns an empty slice if entity has no child or entity does not exist.
+    pub fn children(&self, entity_key: &EntityKey) -> &[EntityKey] {
+        self.get::<Children>(entity_key)
+            .map(|children| children.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Returns `true` if `ancestor` is a proper ancestor of an entity.
+    pub fn is_ancestor_of(&self, ancestor: &EntityKey, entity_key: &EntityKey) -> bool {
+        let mut current = self.parent(entity_key);
+        while let Some(parent) = current {
+            if &parent == ancestor {
+                return true;
+            }
+            current = self.parent(&parent);
+        }
+        false
+    }
+
+    /// Returns all descendants of an entity in depth-first pre-order.
+    pub fn descendants(&self, entity_key: &EntityKey) -> Vec<EntityKey> {
+        let mut descendants = Vec::new();
+        let mut stack = self
+            .children(entity_key)
+            .iter()
+            .rev()
+            .copied()
+            .collect::<Vec<_>>();
+        while let Some(key) = stack.pop() {
+            descendants.push(key);
+            stack.extend(self.children(&key).iter().rev().copied());
+        }
+        descendants
+    }
+
+    /// Attaches an entity to a parent, detaching it from the previous parent if any.
+    /// The entity is appended to the end of children of the new parent.
+    ///
+    /// Returns [`Error::CyclicHierarchy`] if parent is the entity itself or a descendant of it.
+    pub fn set_parent(&mut self, entity_key: &EntityKey, parent: &EntityKey) -> Result<(), Error> {
+        for key in [entity_key, parent] {
+            if !self.has_entity(key) {
+                return Err(Error::NoSuchEntity(*key));
+            }
+        }
+        if entity_key == parent || self.is_ancestor_of(entity_key, parent) {
+            return Err(Error::CyclicHierarchy(*entity_key, *parent));
+        }
+        if self.parent(entity_key).as_ref() == Some(parent) {
+            return Ok(());
+        }
+
+        self.detach_from_parent(entity_key);
+
+        match self.get_mut::<Parent>(entity_key) {
+            Some(mut previous) => previous.0 = *parent,
+            None => self.add_component(entity_key, Parent(*parent))?,
+        }
+        match self.get_mut::<Children>(parent) {
+            Some(mut children) => children.0.push(*entity_key),
+            None => self.add_component(parent, Children(vec![*entity_key]))?,
+        }
+
+        Ok(())
+    }
+
+    /// Detaches an entity from its parent, making it a root entity.
+    /// Returns the previous parent if any.
+    pub fn remove_parent(&mut self, entity_key: &EntityKey) -> Result<Option<EntityKey>, Error> {
+        if !self.has_entity(entity_key) {
+            return Err(Error::NoSuchEntity(*entity_key));
+        }
+
+        let parent = self.detach_from_parent(entity_key);
+        if parent.is_some() {
+            self.remove_component::<Parent>(entity_key)?;
+        }
+        Ok(parent)
+    }
+
+    /// Removes an entity along with all its descendants.
+    /// Returns entity keys of all removed entities, starting from the entity itself.
+    pub fn remove_entity_recursive(
+        &mut self,
+        entity_key: &EntityKey,
+    ) -> Result<Vec<EntityKey>, Error> {
+        if !self.has_entity(entity_key) {
+            return Err(Error::NoSuchEntity(*entity_key));
+        }
+
+        let mut removed = vec![*entity_key];
+        removed.extend(self.descendants(entity_key));
+        // removes from leaves, so that no descendant is orphaned in between
+        for key in removed.iter().skip(1).rev() {
+            self.remove_entity(key)?;
+        }
+        self.remove_entity(entity_key)?;
+
+        Ok(removed)
+    }
+
+    /// Detaches an entity from the hierarchy before it is removed.
+    /// The entity is removed from children of its parent, and its children become root entities.
+    pub(super) fn detach_hierarchy(&mut self, entity_key: &EntityKey) {
+        self.detach_from_parent(entity_key);
+        let children = self.children(entity_key).to_vec();
+        for child in children {
+            let _ = self.remove_component::<Parent>(&child);
+        }
+    }
+
+    /// Removes an entity from children of its parent, without touching [`Parent`] of the entity.
+    /// Returns the parent if any.
+    fn detach_from_parent(&mut self, entity_key: &EntityKey) -> Option<EntityKey> {
+        let parent = self.parent(entity_key)?;
+        let mut children = self.get_mut::<Children>(&parent)?;
+        children.0.retain(|child| child != entity_key);
+        if children.0.is_empty() {
+            let _ = self.remove_component::<Children>(&parent);
+        }
+        Some(parent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anewthing::ecs::component::ComponentSet;
+
+    struct Name(&'static str);
+
+    impl Component for Name {}
+
+    fn spawn(manager: &mut EntityManager, name: &'static str) -> EntityKey {
+        manager
+            .create_entity(ComponentSet::with_component(Name(name)))
+            .unwrap()
+    }
+
+    /// Checks that every parent lists its child exactly once and every child points back to its parent.
+    fn assert_consistent(manager: &EntityManager) {
+        for key in manager.entity_keys() {
+            if let Some(parent) = manager.parent(&key) {
+                assert!(manager.has_entity(&parent));
+                let count = manager
+                    .children(&parent)
+                    .iter()
+                    .filter(|c| *c == &key)
+                    .count();
+                assert_eq!(count, 1);
+            }
+            if let Some(children) = manager.get::<Children>(&key) {
+                assert!(!children.is_empty());
+                for child in children.iter() {
+                    assert_eq!(manager.parent(child), Some(key));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_parent() {
+        let mut manager = EntityManager::new();
+        let root = spawn(&mut manager, "root");
+        let a = spawn(&mut manager, "a");
+        let b = spawn(&mut manager, "b");
+        let c = spawn(&mut manager, "c");
+
+        manager.set_parent(&a, &root).unwrap();
+        manager.set_parent(&b, &root).unwrap();
+        manager.set_parent(&c, &a).unwrap();
+        assert_consistent(&manager);
+        assert_eq!(manager.children(&root), &[a, b]);
+        assert_eq!(manager.descendants(&root), vec![a, c, b]);
+        assert!(manager.is_ancestor_of(&root, &c));
+        assert!(!manager.is_ancestor_of(&b, &c));
+
+        // setting the same parent again changes nothing
+        manager.set_parent(&a, &root).unwrap();
+        assert_eq!(manager.children(&root), &[a, b]);
+
+        // reparenting
+        manager.set_parent(&c, &b).unwrap();
+        assert_consistent(&manager);
+        assert_eq!(manager.children(&a), &[]);
+        assert!(!manager.has_component::<Children>(&a));
+        assert_eq!(manager.children(&b), &[c]);
+
+        // detaching
+        assert_eq!(manager.remove_parent(&b).unwrap(), Some(root));
+        assert_eq!(manager.remove_parent(&b).unwrap(), None);
+        assert_consistent(&manager);
+        assert_eq!(manager.children(&root), &[a]);
+        assert_eq!(manager.get::<Name>(&c).unwrap().0, "c");
+    }
+
+    #[test]
+    fn test_cyclic_hierarchy() {
+        let mut manager = EntityManager::new();
+        let a = spawn(&mut manager, "a");
+        let b = spawn(&mut manager, "b");
+        let c = spawn(&mut manager, "c");
+        manager.set_parent(&b, &a).unwrap();
+        manager.set_parent(&c, &b).unwrap();
+
+        assert!(matches!(
+            manager.set_parent(&a, &a),
+            Err(Error::CyclicHierarchy(entity, parent)) if entity == a && parent == a
+        ));
+        assert!(matches!(
+            manager.set_parent(&a, &c),
+            Err(Error::CyclicHierarchy(entity, parent)) if entity == a && parent == c
+        ));
+        assert_eq!(manager.parent(&a), None);
+        assert_consistent(&manager);
+    }
+
+    #[test]
+    fn test_remove_entity_recursive() {
+        let mut manager = EntityManager::new();
+        let root = spawn(&mut manager, "root");
+        let a = spawn(&mut manager, "a");
+        let b = spawn(&mut manager, "b");
+        let c = spawn(&mut manager, "c");
+        let d = spawn(&mut manager, "d");
+        manager.set_parent(&a, &root).unwrap();
+        manager.set_parent(&b, &a).unwrap();
+        manager.set_parent(&c, &b).unwrap();
+        manager.set_parent(&d, &root).unwrap();
+
+        let removed = manager.remove_entity_recursive(&a).unwrap();
+        assert_eq!(removed, vec![a, b, c]);
+        assert!(removed.iter().all(|key| !manager.has_entity(key)));
+        assert_eq!(manager.children(&root), &[d]);
+        assert_consistent(&manager);
+
+        // removing a single entity orphans its children
+        let e = spawn(&mut manager, "e");
+        manager.set_parent(&e, &d).unwrap();
+        manager.remove_entity(&d).unwrap();
+        assert_eq!(manager.parent(&e), None);
+        assert!(!manager.has_component::<Children>(&root));
+        assert_consistent(&manager);
+
+        // stale keys never get attached to
+        assert!(matches!(
+            manager.set_parent(&e, &d),
+            Err(Error::NoSuchEntity(key)) if key == d
+        ));
+    }
+}
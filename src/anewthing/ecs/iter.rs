@@ -2,10 +2,9 @@ use std::{any::Any, marker::PhantomData};
 
 use hashbrown::HashMap;
 
-use crate::anewthing::ecs::manager::ComponentItem;
-
 use super::{
     archetype::Archetype,
+    change::Mut,
     component::{Component, ComponentKey, SharedComponentKey},
     entity::EntityKey,
     manager::{ChunkItem, EntityManager, SharedComponentItem},
@@ -14,8 +13,9 @@ use super::{
 pub struct EntityComponentsMut<'a> {
     entity_key: *const EntityKey,
     archetype: *const Archetype,
-    components: HashMap<ComponentKey, *mut Box<dyn Any>>,
+    components: HashMap<ComponentKey, (*mut dyn Any, *mut u64)>,
     shared_components: HashMap<SharedComponentKey, *mut Box<dyn Any>>,
+    change_tick: u64,
     _lifetime: PhantomData<&'a ()>,
 }
 
@@ -29,48 +29,50 @@ impl<'a> EntityComponentsMut<'a> {
     }
 
     /// Returns a component by a specific component key.
-    pub fn component_by_key<'b>(&mut self, key: &ComponentKey) -> Option<&'b mut Box<dyn Any>> {
-        unsafe { Some(self.components.get_mut(key)?.as_mut().unwrap()) }
+    /// Component is marked as changed immediately, since it is not tracked after returned.
+    pub fn component_by_key<'b>(
+        &mut self,
+        key: &ComponentKey,
+    ) -> Option<&'b mut (dyn Any + 'static)> {
+        let (component, changed_tick) = self.components.get(key)?;
+        unsafe {
+            **changed_tick = self.change_tick;
+            Some(&mut **component)
+        }
     }
 
     /// Returns a component by a specific component key.
+    /// Component is marked as changed immediately, since it is not tracked after returned.
     /// Panic if no such component.
-    pub fn component_by_key_unchcecked<'b>(&mut self, key: &ComponentKey) -> &'b mut Box<dyn Any> {
-        unsafe { self.components.get_mut(key).unwrap().as_mut().unwrap() }
+    pub fn component_by_key_unchcecked<'b>(
+        &mut self,
+        key: &ComponentKey,
+    ) -> &'b mut (dyn Any + 'static) {
+        self.component_by_key(key).unwrap()
     }
 
     /// Returns a component by a specific component type.
-    pub fn component<'b, C>(&mut self) -> Option<&'b mut C>
+    pub fn component<'b, C>(&mut self) -> Option<Mut<'b, C>>
     where
         C: Component + 'static,
     {
+        let (component, changed_tick) = self.components.get(&ComponentKey::new::<C>())?;
         unsafe {
-            Some(
-                self.components
-                    .get_mut(&ComponentKey::new::<C>())?
-                    .as_mut()
-                    .unwrap()
-                    .downcast_mut::<C>()
-                    .unwrap(),
-            )
+            Some(Mut::new(
+                (**component).downcast_mut::<C>().unwrap(),
+                Some(&mut **changed_tick),
+                self.change_tick,
+            ))
         }
     }
 
     /// Returns a component by a specific component type.
     /// Panic if no such component.
-    pub fn component_unchecked<'b, C>(&mut self) -> &'b mut C
+    pub fn component_unchecked<'b, C>(&mut self) -> Mut<'b, C>
     where
         C: Component + 'static,
     {
-        unsafe {
-            self.components
-                .get_mut(&ComponentKey::new::<C>())
-                .unwrap()
-                .as_mut()
-                .unwrap()
-                .downcast_mut::<C>()
-                .unwrap()
-        }
+        self.component::<C>().unwrap()
     }
 
     /// Returns a shared component by a specific shared component key.
@@ -138,15 +140,18 @@ pub struct EntityComponentsIterMut<'a> {
     chunks: hashbrown::hash_map::IterMut<'a, Archetype, ChunkItem>,
     chunk: Option<(&'a Archetype, &'a mut ChunkItem)>,
     chunk_index: usize,
+    change_tick: u64,
 }
 
 impl<'a> EntityComponentsIterMut<'a> {
     pub(super) fn new(manager: &'a mut EntityManager) -> Self {
+        let change_tick = manager.change_tick();
         Self {
             shared_components: &mut manager.shared_components,
             chunks: manager.chunks.iter_mut(),
             chunk: None,
             chunk_index: 0,
+            change_tick,
         }
     }
 }
@@ -155,10 +160,11 @@ impl<'a> Iterator for EntityComponentsIterMut<'a> {
     type Item = EntityComponentsMut<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.chunk.is_none() {
-            self.chunk = self.chunks.next();
-            if self.chunk.is_none() {
-                return None;
+        // skips empty chunks
+        while self.chunk.is_none() {
+            let (archetype, chunk_item) = self.chunks.next()?;
+            if !chunk_item.entity_keys.is_empty() {
+                self.chunk = Some((archetype, chunk_item));
             }
         }
 
@@ -166,12 +172,17 @@ impl<'a> Iterator for EntityComponentsIterMut<'a> {
 
         let entity_key: *const EntityKey = &chunk_item.entity_keys[self.chunk_index];
 
-        let components_len = archetype.components_len();
-        let components_start_index = self.chunk_index * components_len;
-        let components_end_index = components_start_index + components_len;
-        let components = chunk_item.components[components_start_index..components_end_index]
+        let chunk_index = self.chunk_index;
+        let components = chunk_item
+            .columns
             .iter_mut()
-            .map(|ComponentItem { key, component }| (*key, component as *mut _))
+            .map(|column| {
+                let key = column.info().key();
+                let (component, changed_tick) = column.get_any_with_tick_mut(chunk_index).unwrap();
+                let component: *mut dyn Any = component;
+                let changed_tick: *mut u64 = changed_tick;
+                (key, (component, changed_tick))
+            })
             .collect::<HashMap<_, _>>();
 
         let mut shared_components = HashMap::with_capacity(archetype.shared_components_len());
@@ -194,6 +205,7 @@ impl<'a> Iterator for EntityComponentsIterMut<'a> {
             archetype,
             components,
             shared_components,
+            change_tick: self.change_tick,
             _lifetime: PhantomData,
         })
     }
@@ -202,7 +214,7 @@ impl<'a> Iterator for EntityComponentsIterMut<'a> {
 pub struct EntityComponents<'a> {
     entity_key: &'a EntityKey,
     archetype: &'a Archetype,
-    components: HashMap<ComponentKey, &'a Box<dyn Any>>,
+    components: HashMap<ComponentKey, &'a (dyn Any + 'static)>,
     shared_components: HashMap<SharedComponentKey, &'a Box<dyn Any>>,
     _lifetime: PhantomData<&'a ()>,
 }
@@ -293,10 +305,11 @@ impl<'a> Iterator for EntityComponentsIter<'a> {
     type Item = EntityComponents<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.chunk.is_none() {
-            self.chunk = self.chunks.next();
-            if self.chunk.is_none() {
-                return None;
+        // skips empty chunks
+        while self.chunk.is_none() {
+            let (archetype, chunk_item) = self.chunks.next()?;
+            if !chunk_item.entity_keys.is_empty() {
+                self.chunk = Some((archetype, chunk_item));
             }
         }
 
@@ -304,12 +317,15 @@ impl<'a> Iterator for EntityComponentsIter<'a> {
 
         let entity_key = &chunk_item.entity_keys[self.chunk_index];
 
-        let components_len = archetype.components_len();
-        let components_start_index = self.chunk_index * components_len;
-        let components_end_index = components_start_index + components_len;
-        let components = chunk_item.components[components_start_index..components_end_index]
+        let components = chunk_item
+            .columns
             .iter()
-            .map(|ComponentItem { key, component }| (*key, component))
+            .map(|column| {
+                (
+                    column.info().key(),
+                    column.get_any(self.chunk_index).unwrap(),
+                )
+            })
             .collect::<HashMap<_, _>>();
 
         let mut shared_components = HashMap::with_capacity(archetype.shared_components_len());
@@ -1,50 +1,211 @@
-use std::any::Any;
+use std::{any::Any, cell::Cell, rc::Rc};
 
 use hashbrown::HashMap;
 use tokio::sync::broadcast::{self, Sender};
+use uuid::Uuid;
 
 use super::{
     archetype::Archetype,
-    component::{Component, ComponentKey, ComponentSet, SharedComponentKey},
+    change::{Mut, RemovedComponentsBuffer},
+    column::Column,
+    component::{Component, ComponentInfo, ComponentKey, ComponentSet, SharedComponentKey},
     entity::EntityKey,
     error::Error,
     iter::{EntityComponentsIter, EntityComponentsIterMut},
 };
 
 pub struct EntityManager {
-    entities: HashMap<EntityKey, EntityItem>,
+    entities: Vec<EntitySlot>,
+    free_indices: Vec<u32>,
+    // next never used slot index, shared with command buffers reserving entities
+    next_index: Rc<Cell<u32>>,
+    uuids: HashMap<Uuid, EntityKey>,
     pub(super) chunks: HashMap<Archetype, ChunkItem>,
     pub(super) shared_components: HashMap<SharedComponentKey, SharedComponentItem>,
 
+    change_tick: u64,
+    last_change_tick: u64,
+    removed_components: HashMap<ComponentKey, RemovedComponentsBuffer>,
+    next_removal_id: u64,
+
     sender: Sender<EntityManagerMessage>,
 }
 
 impl EntityManager {
     pub fn new() -> Self {
         Self {
-            entities: HashMap::new(),
+            entities: Vec::new(),
+            free_indices: Vec::new(),
+            next_index: Rc::new(Cell::new(0)),
+            uuids: HashMap::new(),
             chunks: HashMap::new(),
             shared_components: HashMap::new(),
 
+            // starts from 1, so everything is newer than a never run reader
+            change_tick: 1,
+            last_change_tick: 0,
+            removed_components: HashMap::new(),
+            next_removal_id: 0,
+
             sender: broadcast::channel(5).0,
         }
     }
 
-    fn get_or_create_chunk<'a: 'b, 'b>(&'a mut self, archetype: Archetype) -> &'b mut ChunkItem {
+    /// Returns current change tick.
+    /// Components added or changed are stamped with current change tick.
+    pub fn change_tick(&self) -> u64 {
+        self.change_tick
+    }
+
+    /// Increments change tick and returns the previous one.
+    ///
+    /// A reader (e.g. a system) should store the returned tick after it runs
+    /// and set it back by [`EntityManager::set_last_change_tick`] before it runs next time.
+    pub fn increment_change_tick(&mut self) -> u64 {
+        let tick = self.change_tick;
+        self.change_tick += 1;
+        tick
+    }
+
+    /// Returns the change tick that [`Added`](super::query::Added)
+    /// and [`Changed`](super::query::Changed) queries compare against.
+    pub fn last_change_tick(&self) -> u64 {
+        self.last_change_tick
+    }
+
+    /// Sets the change tick that [`Added`](super::query::Added)
+    /// and [`Changed`](super::query::Changed) queries compare against.
+    /// Components added or changed after this tick are considered as added or changed.
+    pub fn set_last_change_tick(&mut self, tick: u64) {
+        self.last_change_tick = tick;
+    }
+
+    /// Maintains removed components buffers, dropping records older than last maintenance.
+    /// Should be called once per frame.
+    pub fn maintain(&mut self) {
+        self.removed_components
+            .values_mut()
+            .for_each(|buffer| buffer.swap());
+    }
+
+    pub(super) fn next_removal_id(&self) -> u64 {
+        self.next_removal_id
+    }
+
+    pub(super) fn removed_components(
+        &self,
+        key: &ComponentKey,
+    ) -> Option<&RemovedComponentsBuffer> {
+        self.removed_components.get(key)
+    }
+
+    fn record_removed_component(&mut self, key: ComponentKey, entity_key: EntityKey) {
+        self.removed_components
+            .entry(key)
+            .or_insert_with(RemovedComponentsBuffer::new)
+            .push(self.next_removal_id, entity_key);
+        self.next_removal_id += 1;
+    }
+
+    fn get_or_create_chunk<'a: 'b, 'b, F>(
+        &'a mut self,
+        archetype: Archetype,
+        infos: F,
+    ) -> &'b mut ChunkItem
+    where
+        F: FnOnce() -> Vec<ComponentInfo>,
+    {
         self.chunks.entry(archetype).or_insert_with(|| ChunkItem {
             entity_keys: Vec::new(),
-            components: Vec::new(),
+            columns: infos().into_iter().map(Column::new).collect(),
         })
     }
 
-    unsafe fn swap_and_remove_entity(&mut self, key: &EntityKey) -> ComponentSet {
+    fn entity(&self, key: &EntityKey) -> Option<&EntityItem> {
+        let slot = self.entities.get(key.index() as usize)?;
+        if slot.generation != key.generation() {
+            return None;
+        }
+        slot.item.as_ref()
+    }
+
+    fn entity_mut(&mut self, key: &EntityKey) -> Option<&mut EntityItem> {
+        let slot = self.entities.get_mut(key.index() as usize)?;
+        if slot.generation != key.generation() {
+            return None;
+        }
+        slot.item.as_mut()
+    }
+
+    /// Allocates an entity key, reusing a free slot if any.
+    fn allocate_entity(&mut self, entity: EntityItem) -> EntityKey {
+        match self.free_indices.pop() {
+            Some(index) => {
+                let slot = &mut self.entities[index as usize];
+                slot.item = Some(entity);
+                EntityKey::new(index, slot.generation)
+            }
+            None => {
+                let index = self.next_index.get();
+                self.next_index.set(index + 1);
+                self.fill_reserved_entity(EntityKey::new(index, 0), entity);
+                EntityKey::new(index, 0)
+            }
+        }
+    }
+
+    /// Returns the shared slot index counter for reserving entities.
+    pub(super) fn reserver(&self) -> Rc<Cell<u32>> {
+        Rc::clone(&self.next_index)
+    }
+
+    /// Puts an entity into a reserved slot.
+    /// Slots between the last used one and the reserved one stay empty until they are filled.
+    fn fill_reserved_entity(&mut self, key: EntityKey, entity: EntityItem) {
+        let index = key.index() as usize;
+        if self.entities.len() <= index {
+            self.entities.resize_with(index + 1, || EntitySlot {
+                generation: 0,
+                item: None,
+            });
+        }
+        let slot = &mut self.entities[index];
+        assert!(slot.item.is_none() && slot.generation == key.generation());
+        slot.item = Some(entity);
+    }
+
+    /// Releases a reserved slot which is never filled, making it reusable.
+    fn release_reserved_entity(&mut self, key: &EntityKey) {
+        let index = key.index() as usize;
+        if self.entities.len() <= index {
+            self.entities.resize_with(index + 1, || EntitySlot {
+                generation: 0,
+                item: None,
+            });
+        }
+        let slot = &mut self.entities[index];
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_indices.push(key.index());
+    }
+
+    /// Frees an entity slot and bumps the generation,
+    /// making all the existing keys of the slot stale.
+    fn free_entity(&mut self, key: &EntityKey) -> EntityItem {
+        let slot = &mut self.entities[key.index() as usize];
+        let entity = slot.item.take().unwrap();
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_indices.push(key.index());
+        entity
+    }
+
+    /// Removes an entity from its chunk and returns its components.
+    fn swap_and_remove_entity(&mut self, key: &EntityKey) -> ComponentSet {
         let EntityItem {
             archetype,
             chunk_index,
             ..
-        } = self.entities.get(key).unwrap();
+        } = self.entities[key.index() as usize].item.as_ref().unwrap();
         let chunk_index = *chunk_index;
-        let chunk_size = archetype.components_len();
         let chunk = self.chunks.get_mut(archetype).unwrap();
 
         // reduces count of shared components
@@ -57,53 +218,63 @@ impl EntityManager {
             }
         }
 
-        // swaps and removes components
-        let components = if chunk.components.len() == chunk_size {
-            chunk.entity_keys.clear();
-            chunk.components.drain(..).collect::<Vec<_>>()
-        } else {
-            // swaps components and removes last components
-            let from_components_index = chunk_index * chunk_size;
-            let swap_components_index = chunk.components.len() - chunk_size;
-            for i in 0..chunk_size {
-                chunk
-                    .components
-                    .swap(from_components_index + i, swap_components_index + i);
-            }
-            let components = chunk
-                .components
-                .drain(swap_components_index..)
-                .collect::<Vec<_>>();
+        // swaps components with the last entity and removes them
+        let components = chunk
+            .columns
+            .iter_mut()
+            .map(|column| (*column.info(), column.swap_remove_boxed(chunk_index)))
+            .collect();
+        if let Some(swap_entity_key) = chunk.swap_remove_entity_key(chunk_index) {
+            self.entities[swap_entity_key.index() as usize]
+                .item
+                .as_mut()
+                .unwrap()
+                .chunk_index = chunk_index;
+        }
 
-            // swaps entity id and remove last one
-            let swap_entity_id = *chunk.entity_keys.last().unwrap();
-            chunk
-                .entity_keys
-                .swap(chunk_index, chunk.components.len() - 1);
-            chunk.entity_keys.truncate(chunk.entity_keys.len() - 1);
+        ComponentSet(components, Vec::new(), Vec::new())
+    }
 
-            // updates swap entity
-            self.entities
-                .get_mut(&swap_entity_id)
-                .unwrap()
-                .set_chunk_index(chunk_index);
+    /// Moves an entity to another archetype, moving components column by column without boxing.
+    ///
+    /// Every component of the entity must either exist in the target archetype,
+    /// or have been taken out from its column already.
+    /// Components existing only in the target archetype have to be pushed by caller afterward.
+    fn move_entity<F>(&mut self, key: &EntityKey, to: Archetype, infos: F)
+    where
+        F: FnOnce() -> Vec<ComponentInfo>,
+    {
+        let EntityItem {
+            archetype: from,
+            chunk_index,
+            ..
+        } = self.entity(key).unwrap();
+        let from = from.clone();
+        let chunk_index = *chunk_index;
 
-            components
-        };
+        self.get_or_create_chunk(to.clone(), infos);
+        let [from_chunk, to_chunk] = self.chunks.get_many_mut([&from, &to]).unwrap();
+
+        for column in from_chunk.columns.iter_mut() {
+            let to_column = to_chunk
+                .columns
+                .iter_mut()
+                .find(|to_column| to_column.info().key() == column.info().key());
+            // column not in target archetype has been taken out by caller
+            if let Some(to_column) = to_column {
+                column.swap_remove_to(chunk_index, to_column);
+            }
+        }
+        let swap_entity_key = from_chunk.swap_remove_entity_key(chunk_index);
+        to_chunk.entity_keys.push(*key);
+        let to_chunk_index = to_chunk.entity_keys.len() - 1;
 
-        ComponentSet(
-            components
-                .into_iter()
-                .map(
-                    |ComponentItem {
-                         component,
-                         key: type_id,
-                     }| (type_id, component),
-                )
-                .collect(),
-            Vec::new(),
-            Vec::new(),
-        )
+        if let Some(swap_entity_key) = swap_entity_key {
+            self.entity_mut(&swap_entity_key).unwrap().chunk_index = chunk_index;
+        }
+        let entity = self.entity_mut(key).unwrap();
+        entity.archetype = to;
+        entity.chunk_index = to_chunk_index;
     }
 
     pub fn archetypes(&self) -> Vec<Archetype> {
@@ -111,18 +282,102 @@ impl EntityManager {
     }
 
     pub fn entity_keys(&self) -> Vec<EntityKey> {
-        self.entities.keys().copied().collect()
+        self.entities
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.item.is_some())
+            .map(|(index, slot)| EntityKey::new(index as u32, slot.generation))
+            .collect()
     }
 
     pub fn shared_components_keys(&self) -> Vec<SharedComponentKey> {
         self.shared_components.keys().copied().collect()
     }
 
+    /// Returns `true` if entity key refers to a living entity.
+    /// Always returns `false` for a stale key whose entity has been removed.
     pub fn has_entity(&self, entity_key: &EntityKey) -> bool {
-        self.entities.contains_key(entity_key)
+        self.entity(entity_key).is_some()
+    }
+
+    /// Returns the persistent UUID of an entity if any.
+    pub fn entity_uuid(&self, entity_key: &EntityKey) -> Option<&Uuid> {
+        self.entity(entity_key)?.uuid.as_ref()
+    }
+
+    /// Returns the entity key of an entity with a persistent UUID.
+    pub fn entity_by_uuid(&self, uuid: &Uuid) -> Option<EntityKey> {
+        self.uuids.get(uuid).copied()
+    }
+
+    /// Sets or unsets the persistent UUID of an entity.
+    pub fn set_entity_uuid(
+        &mut self,
+        entity_key: &EntityKey,
+        uuid: Option<Uuid>,
+    ) -> Result<(), Error> {
+        if !self.has_entity(entity_key) {
+            return Err(Error::NoSuchEntity(*entity_key));
+        }
+        if let Some(uuid) = uuid.as_ref() {
+            match self.uuids.get(uuid) {
+                Some(key) if key == entity_key => return Ok(()),
+                Some(_) => return Err(Error::DuplicateEntityUuid(*uuid)),
+                None => {}
+            }
+        }
+
+        let entity = self.entity_mut(entity_key).unwrap();
+        let previous = std::mem::replace(&mut entity.uuid, uuid);
+        if let Some(previous) = previous {
+            self.uuids.remove(&previous);
+        }
+        if let Some(uuid) = uuid {
+            self.uuids.insert(uuid, *entity_key);
+        }
+
+        Ok(())
+    }
+
+    /// Creates a new entity with a persistent UUID,
+    /// which could be used to find the entity after serialization.
+    pub fn create_entity_with_uuid(
+        &mut self,
+        components: ComponentSet,
+        uuid: Uuid,
+    ) -> Result<EntityKey, Error> {
+        if self.uuids.contains_key(&uuid) {
+            return Err(Error::DuplicateEntityUuid(uuid));
+        }
+
+        let entity_key = self.create_entity(components)?;
+        self.set_entity_uuid(&entity_key, Some(uuid))?;
+        Ok(entity_key)
     }
 
     pub fn create_entity(&mut self, components: ComponentSet) -> Result<EntityKey, Error> {
+        self.spawn_entity(components, None)
+    }
+
+    /// Creates an entity with an entity key reserved by [`Commands`](super::command::Commands).
+    /// Reserved slot is released if entity could not be created.
+    pub(super) fn create_reserved_entity(
+        &mut self,
+        key: EntityKey,
+        components: ComponentSet,
+    ) -> Result<EntityKey, Error> {
+        let result = self.spawn_entity(components, Some(key));
+        if result.is_err() {
+            self.release_reserved_entity(&key);
+        }
+        result
+    }
+
+    fn spawn_entity(
+        &mut self,
+        components: ComponentSet,
+        reserved: Option<EntityKey>,
+    ) -> Result<EntityKey, Error> {
         let archetype = components.archetype();
         let size = archetype.components_len();
         if size == 0 {
@@ -131,36 +386,46 @@ impl EntityManager {
 
         let ComponentSet(components, shared_component_types, shared_components) = components;
 
-        // checks the duplication of shared components
-        for shared in shared_component_types
-            .iter()
-            .chain(shared_components.iter().map(|(id, _)| id))
-        {
+        // shared components referred by type must exist already,
+        // while shared components with instances must not
+        for shared in shared_component_types.iter() {
+            if !self.shared_components.contains_key(shared) {
+                return Err(Error::NoSuchSharedComponent(*shared));
+            }
+        }
+        for (shared, _) in shared_components.iter() {
             if self.shared_components.contains_key(shared) {
-                return Err(Error::DuplicateComponent);
+                return Err(Error::DuplicateSharedComponent(*shared));
             }
         }
 
-        let entity_key = EntityKey::new();
-
-        let chunk = self.get_or_create_chunk(archetype.clone());
-        let chunk_index = chunk.components.len() / size;
-        // pushes entity id
-        chunk.entity_keys.push(entity_key);
-        // pushes components
-        chunk.components.extend(
-            components
-                .into_iter()
-                .map(|(type_id, component)| ComponentItem {
-                    component,
-                    key: type_id,
-                }),
-        );
         let entity = EntityItem {
-            version: 0,
-            archetype,
-            chunk_index,
+            archetype: archetype.clone(),
+            chunk_index: 0,
+            uuid: None,
         };
+        let entity_key = match reserved {
+            Some(key) => {
+                self.fill_reserved_entity(key, entity);
+                key
+            }
+            None => self.allocate_entity(entity),
+        };
+
+        let tick = self.change_tick;
+        let chunk = self.get_or_create_chunk(archetype.clone(), || {
+            components.iter().map(|(info, _)| *info).collect()
+        });
+        let chunk_index = chunk.entity_keys.len();
+        // pushes entity key
+        chunk.entity_keys.push(entity_key);
+        // pushes components, components are sorted in the same order as columns
+        for ((_, component), column) in components.into_iter().zip(chunk.columns.iter_mut()) {
+            unsafe {
+                column.push_boxed(component, tick);
+            }
+        }
+        self.entity_mut(&entity_key).unwrap().chunk_index = chunk_index;
         // pushes shared components
         shared_components.into_iter().for_each(|(id, component)| {
             self.shared_components.insert_unique_unchecked(
@@ -172,74 +437,130 @@ impl EntityManager {
                 },
             );
         });
-
-        let (entity_key, _) = self.entities.insert_unique_unchecked(entity_key, entity);
+        // counts entities using shared components, they are removed by count
+        for key in archetype.1.iter() {
+            self.shared_components.get_mut(key).unwrap().count += 1;
+        }
 
         let _ = self
             .sender
-            .send(EntityManagerMessage::CreateEntity(*entity_key));
+            .send(EntityManagerMessage::CreateEntity(entity_key));
 
-        Ok(*entity_key)
+        Ok(entity_key)
     }
 
     pub fn remove_entity(&mut self, key: &EntityKey) -> Result<ComponentSet, Error> {
         if !self.has_entity(&key) {
-            return Err(Error::NoSuchEntity);
+            return Err(Error::NoSuchEntity(*key));
         }
 
         let _ = self.sender.send(EntityManagerMessage::RemoveEntity(*key));
 
-        unsafe { Ok(self.swap_and_remove_entity(&key)) }
+        self.detach_hierarchy(key);
+        let components = self.swap_and_remove_entity(&key);
+        for (info, _) in components.0.iter() {
+            self.record_removed_component(info.key(), *key);
+        }
+        if let Some(uuid) = self.free_entity(key).uuid {
+            self.uuids.remove(&uuid);
+        }
+
+        Ok(components)
     }
 
-    unsafe fn set_components(&mut self, key: &EntityKey, components: ComponentSet) {
-        let archetype = components.archetype();
-        let chunk = self.get_or_create_chunk(archetype.clone());
+    /// Returns the archetype of an entity.
+    pub fn entity_archetype(&self, entity_key: &EntityKey) -> Option<&Archetype> {
+        self.entity(entity_key).map(|entity| &entity.archetype)
+    }
+
+    /// Returns all non-shared components of an entity as [`Any`], in the same order as the archetype.
+    pub(super) fn entity_components_any(
+        &self,
+        entity_key: &EntityKey,
+    ) -> Option<Vec<(ComponentKey, &(dyn Any + 'static))>> {
+        let entity = self.entity(entity_key)?;
+        let chunk = self.chunks.get(&entity.archetype)?;
         chunk
-            .components
-            .extend(
-                components
-                    .0
-                    .into_iter()
-                    .map(|(type_id, component)| ComponentItem {
-                        component,
-                        key: type_id,
-                    }),
-            );
-        chunk.entity_keys.push(*key);
-        let chunk_index = chunk.entity_keys.len() - 1;
-        self.entities
-            .get_mut(key)
-            .unwrap()
-            .set_archetype(archetype, chunk_index);
+            .columns
+            .iter()
+            .map(|column| Some((column.info().key(), column.get_any(entity.chunk_index)?)))
+            .collect()
     }
 
-    pub fn has_component<C>(&mut self, entity_key: &EntityKey) -> bool
+    pub fn has_component<C>(&self, entity_key: &EntityKey) -> bool
     where
         C: Component + 'static,
     {
-        let Some(entity) = self.entities.get(entity_key) else {
+        let Some(entity) = self.entity(entity_key) else {
             return false;
         };
         entity.archetype.has_component::<C>()
     }
 
+    /// Returns a component of an entity.
+    /// Returns `None` if entity key is stale or entity has no such component.
+    pub fn get<C>(&self, entity_key: &EntityKey) -> Option<&C>
+    where
+        C: Component + 'static,
+    {
+        let entity = self.entity(entity_key)?;
+        let component_index = entity.archetype.component_index::<C>()?;
+        let chunk = self.chunks.get(&entity.archetype)?;
+        unsafe { Some(chunk.columns[component_index].get_unchecked::<C>(entity.chunk_index)) }
+    }
+
+    /// Returns a component of an entity mutably.
+    /// Returns `None` if entity key is stale or entity has no such component.
+    pub fn get_mut<C>(&mut self, entity_key: &EntityKey) -> Option<Mut<'_, C>>
+    where
+        C: Component + 'static,
+    {
+        let entity = self.entities.get(entity_key.index() as usize)?;
+        if entity.generation != entity_key.generation() {
+            return None;
+        }
+        let entity = entity.item.as_ref()?;
+        let component_index = entity.archetype.component_index::<C>()?;
+        let chunk = self.chunks.get_mut(&entity.archetype)?;
+        let (component, changed_tick) = unsafe {
+            chunk.columns[component_index].get_with_tick_unchecked_mut::<C>(entity.chunk_index)
+        };
+        Some(Mut::new(component, Some(changed_tick), self.change_tick))
+    }
+
     pub fn add_component<C>(&mut self, entity_key: &EntityKey, component: C) -> Result<(), Error>
     where
         C: Component + 'static,
     {
         if !self.has_entity(entity_key) {
-            return Err(Error::NoSuchEntity);
+            return Err(Error::NoSuchEntity(*entity_key));
         }
         if self.has_component::<C>(entity_key) {
-            return Err(Error::DuplicateComponent);
+            return Err(Error::DuplicateComponent(
+                Some(*entity_key),
+                ComponentKey::new::<C>(),
+            ));
         }
 
-        unsafe {
-            let mut components = self.swap_and_remove_entity(entity_key);
-            components.add_unique_unchecked(component);
-            self.set_components(entity_key, components)
-        };
+        let info = ComponentInfo::new::<C>();
+        let from = self.entity(entity_key).unwrap().archetype.clone();
+        let mut keys = from.0.clone();
+        keys.push(info.key());
+        keys.sort();
+        let to = Archetype(keys, from.1.clone());
+
+        let mut infos = self.chunks[&from]
+            .columns
+            .iter()
+            .map(|column| *column.info())
+            .collect::<Vec<_>>();
+        infos.push(info);
+        infos.sort_by(|a, b| a.key().cmp(&b.key()));
+
+        self.move_entity(entity_key, to.clone(), || infos);
+        let component_index = to.component_index::<C>().unwrap();
+        let tick = self.change_tick;
+        self.chunks.get_mut(&to).unwrap().columns[component_index].push(component, tick);
 
         let _ = self.sender.send(EntityManagerMessage::AddComponent(
             *entity_key,
@@ -254,23 +575,39 @@ impl EntityManager {
         C: Component + 'static,
     {
         if !self.has_entity(entity_key) {
-            return Err(Error::NoSuchEntity);
+            return Err(Error::NoSuchEntity(*entity_key));
         }
         if !self.has_component::<C>(entity_key) {
-            return Err(Error::NoSuchComponent);
+            return Err(Error::NoSuchComponent(
+                Some(*entity_key),
+                ComponentKey::new::<C>(),
+            ));
         }
 
-        let removed = unsafe {
-            let mut components = self.swap_and_remove_entity(entity_key);
-            let removed = components.remove::<C>().unwrap();
-            self.set_components(entity_key, components);
-            removed
-        };
+        let EntityItem {
+            archetype: from,
+            chunk_index,
+            ..
+        } = self.entity(entity_key).unwrap();
+        let from = from.clone();
+        let chunk_index = *chunk_index;
+        let key = ComponentKey::new::<C>();
+        let mut keys = from.0.clone();
+        keys.retain(|k| k != &key);
+        let to = Archetype(keys, from.1.clone());
 
-        let _ = self.sender.send(EntityManagerMessage::RemoveComponent(
-            *entity_key,
-            ComponentKey::new::<C>(),
-        ));
+        let from_chunk = self.chunks.get_mut(&from).unwrap();
+        let component_index = from.component_index::<C>().unwrap();
+        let removed =
+            unsafe { from_chunk.columns[component_index].swap_remove_unchecked::<C>(chunk_index) };
+        let infos = from_chunk
+            .columns
+            .iter()
+            .map(|column| *column.info())
+            .filter(|info| info.key() != key)
+            .collect::<Vec<_>>();
+        self.move_entity(entity_key, to, || infos);
+        self.record_removed_component(key, *entity_key);
 
         Ok(removed)
     }
@@ -289,12 +626,13 @@ impl EntityManager {
         C: Component + 'static,
         T: 'static,
     {
-        if self.has_shared_component::<C, T>() {
-            return Err(Error::DuplicateComponent);
+        let key = SharedComponentKey::new::<C, T>();
+        if self.shared_components.contains_key(&key) {
+            return Err(Error::DuplicateSharedComponent(key));
         }
 
         self.shared_components.insert_unique_unchecked(
-            SharedComponentKey::new::<C, T>(),
+            key,
             SharedComponentItem {
                 component: Box::new(component),
                 count: 0,
@@ -302,9 +640,9 @@ impl EntityManager {
             },
         );
 
-        let _ = self.sender.send(EntityManagerMessage::AddSharedComponent(
-            SharedComponentKey::new::<C, T>(),
-        ));
+        let _ = self
+            .sender
+            .send(EntityManagerMessage::AddSharedComponent(key));
 
         Ok(())
     }
@@ -314,23 +652,17 @@ impl EntityManager {
         C: Component + 'static,
         T: 'static,
     {
-        if !self.has_shared_component::<C, T>() {
-            return Err(Error::NoSuchComponent);
-        }
-
-        if self
-            .shared_components
-            .get(&SharedComponentKey::new::<C, T>())
-            .unwrap()
-            .count
-            != 0
-        {
-            return Err(Error::ComponentInUsed);
+        let key = SharedComponentKey::new::<C, T>();
+        let Some(item) = self.shared_components.get(&key) else {
+            return Err(Error::NoSuchSharedComponent(key));
+        };
+        if item.count != 0 {
+            return Err(Error::SharedComponentInUse(key, item.count));
         }
 
         let removed = *self
             .shared_components
-            .remove(&SharedComponentKey::new::<C, T>())
+            .remove(&key)
             .unwrap()
             .component
             .downcast::<C>()
@@ -338,9 +670,7 @@ impl EntityManager {
 
         let _ = self
             .sender
-            .send(EntityManagerMessage::RemoveSharedComponent(
-                SharedComponentKey::new::<C, T>(),
-            ));
+            .send(EntityManagerMessage::RemoveSharedComponent(key));
 
         Ok(removed)
     }
@@ -354,23 +684,15 @@ impl EntityManager {
     }
 }
 
+struct EntitySlot {
+    generation: u32,
+    item: Option<EntityItem>,
+}
+
 struct EntityItem {
-    version: usize,
     archetype: Archetype,
     chunk_index: usize,
-}
-
-impl EntityItem {
-    fn set_archetype(&mut self, archetype: Archetype, chunk_index: usize) {
-        self.archetype = archetype;
-        self.chunk_index = chunk_index;
-        self.version = self.version.wrapping_add(1);
-    }
-
-    fn set_chunk_index(&mut self, chunk_index: usize) {
-        self.chunk_index = chunk_index;
-        self.version = self.version.wrapping_add(1);
-    }
+    uuid: Option<Uuid>,
 }
 
 pub(super) struct SharedComponentItem {
@@ -379,14 +701,20 @@ pub(super) struct SharedComponentItem {
     auto_remove: bool,
 }
 
-pub(super) struct ComponentItem {
-    pub(super) component: Box<dyn Any>,
-    pub(super) key: ComponentKey,
-}
-
+/// A chunk storing entities of the same archetype.
+/// Components are stored in columns, in the same order as component keys of the archetype.
 pub(super) struct ChunkItem {
     pub(super) entity_keys: Vec<EntityKey>,
-    pub(super) components: Vec<ComponentItem>,
+    pub(super) columns: Vec<Column>,
+}
+
+impl ChunkItem {
+    /// Removes an entity key at `chunk_index` by swapping it with the last one.
+    /// Returns the swapped entity key now at `chunk_index`, if any.
+    fn swap_remove_entity_key(&mut self, chunk_index: usize) -> Option<EntityKey> {
+        self.entity_keys.swap_remove(chunk_index);
+        self.entity_keys.get(chunk_index).copied()
+    }
 }
 
 /// Entity manager messages.
@@ -395,7 +723,371 @@ pub enum EntityManagerMessage {
     CreateEntity(EntityKey),
     RemoveEntity(EntityKey),
     AddComponent(EntityKey, ComponentKey),
-    RemoveComponent(EntityKey, ComponentKey),
     AddSharedComponent(SharedComponentKey),
     RemoveSharedComponent(SharedComponentKey),
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::anewthing::ecs::query::{QuerySimple, QuerySimple2, With, WithShared};
+
+    #[derive(Debug, PartialEq)]
+    struct Position(f64, f64);
+
+    impl Component for Position {}
+
+    #[derive(Debug, PartialEq)]
+    struct Velocity(f64, f64);
+
+    impl Component for Velocity {}
+
+    #[test]
+    fn test_generational_keys() {
+        let mut manager = EntityManager::new();
+        let a = manager
+            .create_entity(ComponentSet::with_component(Position(0.0, 0.0)))
+            .unwrap();
+        manager.remove_entity(&a).unwrap();
+        assert!(!manager.has_entity(&a));
+
+        // slot is reused, but the stale key does not refer to the new entity
+        let b = manager
+            .create_entity(ComponentSet::with_component(Position(1.0, 1.0)))
+            .unwrap();
+        assert_eq!(a.index(), b.index());
+        assert_ne!(a.generation(), b.generation());
+        assert!(manager.get::<Position>(&a).is_none());
+        assert!(matches!(
+            manager.remove_entity(&a),
+            Err(Error::NoSuchEntity(key)) if key == a
+        ));
+        assert_eq!(manager.get::<Position>(&b), Some(&Position(1.0, 1.0)));
+        assert_eq!(manager.entity_keys(), vec![b]);
+    }
+
+    #[test]
+    fn test_get_after_swap_remove() {
+        let mut manager = EntityManager::new();
+        let keys = (0..4)
+            .map(|i| {
+                manager
+                    .create_entity(ComponentSet::with_component(Position(i as f64, 0.0)))
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        manager.remove_entity(&keys[1]).unwrap();
+        manager.add_component(&keys[2], Velocity(2.0, 2.0)).unwrap();
+        manager.get_mut::<Position>(&keys[3]).unwrap().1 = 3.0;
+
+        assert_eq!(manager.get::<Position>(&keys[0]), Some(&Position(0.0, 0.0)));
+        assert_eq!(manager.get::<Position>(&keys[2]), Some(&Position(2.0, 0.0)));
+        assert_eq!(manager.get::<Velocity>(&keys[2]), Some(&Velocity(2.0, 2.0)));
+        assert_eq!(manager.get::<Position>(&keys[3]), Some(&Position(3.0, 3.0)));
+        assert_eq!(manager.get::<Velocity>(&keys[3]), None);
+    }
+
+    #[test]
+    fn test_entity_uuid() {
+        let mut manager = EntityManager::new();
+        let uuid = Uuid::new_v4();
+        let a = manager
+            .create_entity_with_uuid(ComponentSet::with_component(Position(0.0, 0.0)), uuid)
+            .unwrap();
+        assert_eq!(manager.entity_by_uuid(&uuid), Some(a));
+        assert_eq!(manager.entity_uuid(&a), Some(&uuid));
+        assert!(matches!(
+            manager.create_entity_with_uuid(ComponentSet::with_component(Position(0.0, 0.0)), uuid),
+            Err(Error::DuplicateEntityUuid(duplicate)) if duplicate == uuid
+        ));
+
+        manager.remove_entity(&a).unwrap();
+        assert_eq!(manager.entity_by_uuid(&uuid), None);
+    }
+
+    /// Components of the randomized test, each holding a unique value.
+    trait Value: Component + 'static {
+        const INDEX: usize;
+
+        fn new(value: u32) -> Self;
+
+        fn value(&self) -> u32;
+    }
+
+    macro_rules! values {
+        ($($name: ident => $index: expr),+) => {
+            $(
+                #[derive(Debug, PartialEq)]
+                struct $name(u32);
+
+                impl Component for $name {}
+
+                impl Value for $name {
+                    const INDEX: usize = $index;
+
+                    fn new(value: u32) -> Self {
+                        Self(value)
+                    }
+
+                    fn value(&self) -> u32 {
+                        self.0
+                    }
+                }
+            )+
+        };
+    }
+
+    values!(A => 0, B => 1, C => 2);
+
+    struct Shared;
+
+    impl Component for Shared {}
+
+    /// Reference model of an entity.
+    #[derive(Debug, Default)]
+    struct Model {
+        values: [Option<u32>; 3],
+        shared: bool,
+    }
+
+    /// Picks a living or a stale entity key.
+    fn random_key(
+        rng: &mut StdRng,
+        model: &HashMap<EntityKey, Model>,
+        stale: &[EntityKey],
+    ) -> Option<EntityKey> {
+        let mut keys = model.keys().copied().collect::<Vec<_>>();
+        // hash map iterates randomly, sorts to keep seeds reproducible
+        keys.sort();
+        keys.extend_from_slice(stale);
+        if keys.is_empty() {
+            None
+        } else {
+            Some(keys[rng.gen_range(0..keys.len())])
+        }
+    }
+
+    fn create(
+        rng: &mut StdRng,
+        manager: &mut EntityManager,
+        model: &mut HashMap<EntityKey, Model>,
+        value: u32,
+    ) {
+        let mut components = ComponentSet::new();
+        let mut expected = Model::default();
+        for index in 0..3 {
+            if rng.gen_bool(0.5) {
+                match index {
+                    0 => components.add(A(value)).unwrap(),
+                    1 => components.add(B(value)).unwrap(),
+                    _ => components.add(C(value)).unwrap(),
+                }
+                expected.values[index] = Some(value);
+            }
+        }
+        if rng.gen_bool(0.3) {
+            components.add_shared::<Shared, ()>().unwrap();
+            expected.shared = true;
+        }
+
+        match manager.create_entity(components) {
+            Ok(key) => {
+                assert!(expected.values.iter().any(Option::is_some));
+                model.insert(key, expected);
+            }
+            Err(err) => {
+                assert!(expected.values.iter().all(Option::is_none));
+                assert!(matches!(err, Error::EmptyComponents));
+            }
+        }
+    }
+
+    fn remove_entity(
+        manager: &mut EntityManager,
+        model: &mut HashMap<EntityKey, Model>,
+        stale: &mut Vec<EntityKey>,
+        key: EntityKey,
+    ) {
+        let result = manager.remove_entity(&key);
+        match model.remove(&key) {
+            Some(entity) => {
+                let removed = result.unwrap();
+                assert_eq!(removed.len(), entity.values.iter().flatten().count());
+                stale.push(key);
+            }
+            None => assert!(matches!(result, Err(Error::NoSuchEntity(k)) if k == key)),
+        }
+    }
+
+    fn add<V: Value>(
+        manager: &mut EntityManager,
+        model: &mut HashMap<EntityKey, Model>,
+        key: EntityKey,
+        value: u32,
+    ) {
+        let result = manager.add_component(&key, V::new(value));
+        match model.get_mut(&key) {
+            None => assert!(matches!(result, Err(Error::NoSuchEntity(k)) if k == key)),
+            Some(entity) if entity.values[V::INDEX].is_some() => assert!(matches!(
+                result,
+                Err(Error::DuplicateComponent(Some(k), c)) if k == key && c == ComponentKey::new::<V>()
+            )),
+            Some(entity) => {
+                result.unwrap();
+                entity.values[V::INDEX] = Some(value);
+            }
+        }
+    }
+
+    fn remove<V: Value>(
+        manager: &mut EntityManager,
+        model: &mut HashMap<EntityKey, Model>,
+        key: EntityKey,
+    ) {
+        let result = manager.remove_component::<V>(&key);
+        match model
+            .get_mut(&key)
+            .map(|entity| entity.values[V::INDEX].take())
+        {
+            None => assert!(matches!(result, Err(Error::NoSuchEntity(k)) if k == key)),
+            Some(None) => assert!(matches!(
+                result,
+                Err(Error::NoSuchComponent(Some(k), c)) if k == key && c == ComponentKey::new::<V>()
+            )),
+            Some(Some(value)) => assert_eq!(result.unwrap().value(), value),
+        }
+    }
+
+    fn modify<V: Value>(
+        manager: &mut EntityManager,
+        model: &mut HashMap<EntityKey, Model>,
+        key: EntityKey,
+        value: u32,
+    ) {
+        let component = manager.get_mut::<V>(&key);
+        match model.get_mut(&key) {
+            Some(entity) if entity.values[V::INDEX].is_some() => {
+                let mut component = component.unwrap();
+                *component = V::new(value);
+                entity.values[V::INDEX] = Some(value);
+            }
+            _ => assert!(component.is_none()),
+        }
+    }
+
+    fn remove_shared(manager: &mut EntityManager, model: &HashMap<EntityKey, Model>) {
+        let users = model.values().filter(|entity| entity.shared).count();
+        match manager.remove_shared_component::<Shared, ()>() {
+            Ok(_) => {
+                assert_eq!(users, 0);
+                manager.add_shared_component::<Shared, ()>(Shared).unwrap();
+            }
+            Err(Error::SharedComponentInUse(key, count)) => {
+                assert_eq!(key, SharedComponentKey::new::<Shared, ()>());
+                assert_eq!(count, users);
+            }
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    fn queried<V: Value>(manager: &mut EntityManager) -> Vec<u32> {
+        let mut values = <With<V>>::query_simple(manager)
+            .map(|component| component.value())
+            .collect::<Vec<_>>();
+        values.sort();
+        values
+    }
+
+    fn check(manager: &mut EntityManager, model: &HashMap<EntityKey, Model>) {
+        let mut keys = model.keys().copied().collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(manager.entity_keys(), keys);
+
+        for (key, entity) in model.iter() {
+            assert_eq!(manager.get::<A>(key).map(A::value), entity.values[0]);
+            assert_eq!(manager.get::<B>(key).map(B::value), entity.values[1]);
+            assert_eq!(manager.get::<C>(key).map(C::value), entity.values[2]);
+            let archetype = manager.entity_archetype(key).unwrap();
+            assert_eq!(
+                archetype.has_shared_component::<Shared, ()>(),
+                entity.shared
+            );
+        }
+
+        for index in 0..3 {
+            let mut expected = model
+                .values()
+                .filter_map(|entity| entity.values[index])
+                .collect::<Vec<_>>();
+            expected.sort();
+            let values = match index {
+                0 => queried::<A>(manager),
+                1 => queried::<B>(manager),
+                _ => queried::<C>(manager),
+            };
+            assert_eq!(values, expected);
+        }
+
+        let mut pairs = <(With<A>, With<C>)>::query_simple(manager)
+            .map(|(a, c)| (a.value(), c.value()))
+            .collect::<Vec<_>>();
+        pairs.sort();
+        let mut expected = model
+            .values()
+            .filter_map(|entity| Some((entity.values[0]?, entity.values[2]?)))
+            .collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(pairs, expected);
+
+        let shared = <(With<B>, WithShared<Shared, ()>)>::query_simple(manager).count();
+        let expected = model
+            .values()
+            .filter(|entity| entity.shared && entity.values[1].is_some())
+            .count();
+        assert_eq!(shared, expected);
+    }
+
+    /// Drives the entity manager with random operations and compares it against a reference model,
+    /// including errors returned on stale keys and absent components.
+    #[test]
+    fn test_random_operations() {
+        for seed in 0..16 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut manager = EntityManager::new();
+            manager.add_shared_component::<Shared, ()>(Shared).unwrap();
+            let mut model = HashMap::new();
+            let mut stale = Vec::new();
+
+            for value in 0..256 {
+                let operation = rng.gen_range(0..12);
+                match operation {
+                    0..=2 => create(&mut rng, &mut manager, &mut model, value),
+                    3 => remove_shared(&mut manager, &model),
+                    4 => check(&mut manager, &model),
+                    _ => {
+                        let Some(key) = random_key(&mut rng, &model, &stale) else {
+                            continue;
+                        };
+                        let component = rng.gen_range(0..3);
+                        match (operation, component) {
+                            (5, _) => remove_entity(&mut manager, &mut model, &mut stale, key),
+                            (6 | 7, 0) => add::<A>(&mut manager, &mut model, key, value),
+                            (6 | 7, 1) => add::<B>(&mut manager, &mut model, key, value),
+                            (6 | 7, _) => add::<C>(&mut manager, &mut model, key, value),
+                            (8 | 9, 0) => remove::<A>(&mut manager, &mut model, key),
+                            (8 | 9, 1) => remove::<B>(&mut manager, &mut model, key),
+                            (8 | 9, _) => remove::<C>(&mut manager, &mut model, key),
+                            (_, 0) => modify::<A>(&mut manager, &mut model, key, value),
+                            (_, 1) => modify::<B>(&mut manager, &mut model, key, value),
+                            (_, _) => modify::<C>(&mut manager, &mut model, key, value),
+                        }
+                    }
+                }
+            }
+            check(&mut manager, &model);
+        }
+    }
+}
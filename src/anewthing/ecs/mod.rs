@@ -1,7 +1,13 @@
 pub mod archetype;
+pub mod change;
+pub mod command;
+mod column;
 pub mod component;
 pub mod entity;
 pub mod error;
+pub mod hierarchy;
 pub mod iter;
 pub mod manager;
+pub mod prefab;
 pub mod query;
+pub mod scene;
@@ -0,0 +1,758 @@
+use hashbrown::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::{
+    component::{Component, ComponentKey, ComponentSet},
+    entity::EntityKey,
+    error::Error as EntityError,
+    manager::EntityManager,
+    scene::{ComponentRegistry, EntityData, Error, SceneData},
+};
+
+/// Registered name of [`PrefabInstance`] in [`ComponentRegistry`].
+pub(super) const PREFAB_INSTANCE_NAME: &str = "prefab_instance";
+
+/// A component marking an entity as an instance of a prefab node,
+/// so that prefab edits could be re-applied by [`Prefab::apply`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrefabInstance {
+    prefab: String,
+    path: Vec<usize>,
+    overrides: Vec<String>,
+}
+
+impl PrefabInstance {
+    /// Returns name of the source prefab.
+    pub fn prefab(&self) -> &str {
+        &self.prefab
+    }
+
+    /// Returns path of the source node, as child indices from the prefab root.
+    /// Path of the root node is empty.
+    pub fn path(&self) -> &[usize] {
+        &self.path
+    }
+
+    /// Returns names of components overridden when instantiated.
+    pub fn overrides(&self) -> &[String] {
+        &self.overrides
+    }
+}
+
+impl Component for PrefabInstance {}
+
+/// A node of a [`Prefab`], templating an entity by serialized components.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefabNode {
+    // original entity key, for remapping entity keys in components
+    key: Option<EntityKey>,
+    components: Map<String, Value>,
+    shared_components: Vec<String>,
+    children: Vec<PrefabNode>,
+}
+
+impl PrefabNode {
+    /// Constructs a new prefab node from a component set.
+    ///
+    /// Components and shared components not registered are skipped.
+    /// Shared components with instances are not supported,
+    /// since a prefab is instantiated many times.
+    pub fn from_component_set(
+        registry: &ComponentRegistry,
+        components: &ComponentSet,
+    ) -> Result<Self, Error> {
+        if !components.2.is_empty() {
+            return Err(Error::InvalidPrefab(
+                "shared component with instance is not supported".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            key: None,
+            components: registry.serialize_components(
+                components
+                    .0
+                    .iter()
+                    .filter(|(info, _)| info.key() != ComponentKey::new::<PrefabInstance>())
+                    .map(|(info, component)| (info.key(), component.as_ref())),
+            )?,
+            shared_components: components
+                .1
+                .iter()
+                .filter_map(|key| registry.shared_component_name(key))
+                .map(|name| name.to_string())
+                .collect(),
+            children: Vec::new(),
+        })
+    }
+
+    /// Appends a child node.
+    pub fn with_child(mut self, child: PrefabNode) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Appends a child node.
+    pub fn add_child(&mut self, child: PrefabNode) {
+        self.children.push(child);
+    }
+
+    /// Returns child nodes.
+    pub fn children(&self) -> &[PrefabNode] {
+        &self.children
+    }
+
+    /// Returns names of components.
+    pub fn component_names(&self) -> impl Iterator<Item = &str> {
+        self.components.keys().map(|name| name.as_str())
+    }
+
+    /// Sets a component, replacing the existing one if any.
+    pub fn set_component<C>(
+        &mut self,
+        registry: &ComponentRegistry,
+        component: &C,
+    ) -> Result<(), Error>
+    where
+        C: Component + Serialize + 'static,
+    {
+        let name = registered_name::<C>(registry)?;
+        self.components
+            .insert(name.to_string(), serde_json::to_value(component)?);
+        Ok(())
+    }
+
+    /// Removes a component. Returns `true` if the component exists.
+    pub fn remove_component<C>(&mut self, registry: &ComponentRegistry) -> bool
+    where
+        C: Component + 'static,
+    {
+        match registry.component_name(&ComponentKey::new::<C>()) {
+            Some(name) => self.components.remove(name).is_some(),
+            None => false,
+        }
+    }
+}
+
+/// Per-instance component overrides when instantiating a [`Prefab`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PrefabOverrides(HashMap<Vec<usize>, Map<String, Value>>);
+
+impl PrefabOverrides {
+    /// Constructs a new empty overrides.
+    pub fn new() -> Self {
+        Self(HashMap::new())
+    }
+
+    /// Overrides a component of the root entity.
+    pub fn with<C>(self, registry: &ComponentRegistry, component: &C) -> Result<Self, Error>
+    where
+        C: Component + Serialize + 'static,
+    {
+        self.with_at(registry, &[], component)
+    }
+
+    /// Overrides a component of the entity instantiated from node at `path`,
+    /// which is child indices from the prefab root.
+    pub fn with_at<C>(
+        mut self,
+        registry: &ComponentRegistry,
+        path: &[usize],
+        component: &C,
+    ) -> Result<Self, Error>
+    where
+        C: Component + Serialize + 'static,
+    {
+        let name = registered_name::<C>(registry)?;
+        self.0
+            .entry(path.to_vec())
+            .or_default()
+            .insert(name.to_string(), serde_json::to_value(component)?);
+        Ok(self)
+    }
+}
+
+/// A named and reusable entity template, possibly with a child hierarchy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prefab {
+    name: String,
+    root: PrefabNode,
+}
+
+impl Prefab {
+    /// Constructs a new prefab.
+    pub fn new<N>(name: N, root: PrefabNode) -> Self
+    where
+        N: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            root,
+        }
+    }
+
+    /// Constructs a new prefab from an entity and all its descendants.
+    /// Components and shared components not registered are skipped.
+    pub fn from_entity<N>(
+        name: N,
+        manager: &EntityManager,
+        registry: &ComponentRegistry,
+        entity_key: &EntityKey,
+    ) -> Result<Self, Error>
+    where
+        N: Into<String>,
+    {
+        fn capture(
+            manager: &EntityManager,
+            registry: &ComponentRegistry,
+            entity_key: &EntityKey,
+        ) -> Result<PrefabNode, Error> {
+            let Some(components) = manager.entity_components_any(entity_key) else {
+                return Err(Error::Entity(EntityError::NoSuchEntity(*entity_key)));
+            };
+            let instance_key = ComponentKey::new::<PrefabInstance>();
+            let components = registry.serialize_components(
+                components
+                    .into_iter()
+                    .filter(|(key, _)| key != &instance_key),
+            )?;
+            let shared_components = manager
+                .entity_archetype(entity_key)
+                .unwrap()
+                .shared_component_keys()
+                .iter()
+                .filter_map(|key| registry.shared_component_name(key))
+                .map(|name| name.to_string())
+                .collect();
+            let children = manager
+                .children(entity_key)
+                .iter()
+                .map(|child| capture(manager, registry, child))
+                .collect::<Result<_, _>>()?;
+
+            Ok(PrefabNode {
+                key: Some(*entity_key),
+                components,
+                shared_components,
+                children,
+            })
+        }
+
+        Ok(Self::new(name, capture(manager, registry, entity_key)?))
+    }
+
+    /// Constructs a new prefab from JSON in the same format as [`serialize_world`](super::scene::serialize_world).
+    ///
+    /// The scene must have exactly one root entity, which becomes the prefab root,
+    /// and all other entities must be descendants of it.
+    /// UUIDs and shared component instances in the scene are ignored.
+    pub fn from_scene<N>(name: N, json: &str) -> Result<Self, Error>
+    where
+        N: Into<String>,
+    {
+        fn build(
+            key: &EntityKey,
+            entities: &HashMap<EntityKey, &EntityData>,
+            visited: &mut HashSet<EntityKey>,
+        ) -> Result<PrefabNode, Error> {
+            if !visited.insert(*key) {
+                return Err(Error::InvalidPrefab(format!(
+                    "entity {:?} linked more than once",
+                    key
+                )));
+            }
+            let Some(entity) = entities.get(key) else {
+                return Err(Error::NoSuchEntity(*key));
+            };
+            let children = entity
+                .children
+                .iter()
+                .map(|child| build(child, entities, visited))
+                .collect::<Result<_, _>>()?;
+
+            Ok(PrefabNode {
+                key: Some(*key),
+                components: entity.components.clone(),
+                shared_components: entity.shared_components.clone(),
+                children,
+            })
+        }
+
+        let SceneData { entities, .. } = serde_json::from_str::<SceneData>(json)?;
+        let children = entities
+            .iter()
+            .flat_map(|entity| entity.children.iter().copied())
+            .collect::<HashSet<_>>();
+        let roots = entities
+            .iter()
+            .filter(|entity| !children.contains(&entity.key))
+            .map(|entity| entity.key)
+            .collect::<Vec<_>>();
+        let [root] = roots.as_slice() else {
+            return Err(Error::InvalidPrefab(format!(
+                "prefab scene has {} root entities",
+                roots.len()
+            )));
+        };
+
+        let entities = entities
+            .iter()
+            .map(|entity| (entity.key, entity))
+            .collect::<HashMap<_, _>>();
+        let mut visited = HashSet::new();
+        let root = build(root, &entities, &mut visited)?;
+        // entities linked in a cycle apart from the root are never visited
+        if visited.len() != entities.len() {
+            return Err(Error::InvalidPrefab(format!(
+                "{} entities not reachable from root entity",
+                entities.len() - visited.len()
+            )));
+        }
+        Ok(Self::new(name, root))
+    }
+
+    /// Serializes the prefab into JSON in the same format as [`serialize_world`](super::scene::serialize_world).
+    /// Original entity keys of nodes are kept, so entity keys in components are still remapped when loaded.
+    pub fn to_scene(&self) -> Result<String, Error> {
+        let mut used = HashSet::new();
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            used.extend(node.key);
+            stack.extend(node.children.iter());
+        }
+        let mut next_index = 0;
+
+        let mut entities: Vec<EntityData> = Vec::new();
+        let mut stack = vec![(&self.root, None)];
+        while let Some((node, parent)) = stack.pop() {
+            let key = match node.key {
+                Some(key) => key,
+                None => loop {
+                    let key = EntityKey::new(next_index, 0);
+                    next_index += 1;
+                    if !used.contains(&key) {
+                        break key;
+                    }
+                },
+            };
+            if let Some(parent) = parent {
+                entities[parent].children.push(key);
+            }
+            let index = entities.len();
+            entities.push(EntityData {
+                key,
+                uuid: None,
+                components: node.components.clone(),
+                shared_components: node.shared_components.clone(),
+                children: Vec::new(),
+            });
+            stack.extend(node.children.iter().rev().map(|child| (child, Some(index))));
+        }
+
+        let scene = SceneData {
+            shared_components: Map::new(),
+            entities,
+        };
+        Ok(serde_json::to_string(&scene)?)
+    }
+
+    /// Returns name of the prefab.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the root node.
+    pub fn root(&self) -> &PrefabNode {
+        &self.root
+    }
+
+    /// Returns the root node mutably.
+    pub fn root_mut(&mut self) -> &mut PrefabNode {
+        &mut self.root
+    }
+
+    /// Returns the node at `path`, which is child indices from the root.
+    pub fn node(&self, path: &[usize]) -> Option<&PrefabNode> {
+        path.iter()
+            .try_fold(&self.root, |node, index| node.children.get(*index))
+    }
+
+    /// Returns the node at `path` mutably, which is child indices from the root.
+    pub fn node_mut(&mut self, path: &[usize]) -> Option<&mut PrefabNode> {
+        path.iter()
+            .try_fold(&mut self.root, |node, index| node.children.get_mut(*index))
+    }
+
+    /// Instantiates the prefab with per-instance overrides, returning the root entity.
+    ///
+    /// Every instantiated entity tracks its source node by a [`PrefabInstance`].
+    /// Entity keys in components referring to other nodes are remapped to the instantiated entities.
+    /// The prefab is validated before anything is created,
+    /// so the entity manager is unchanged if instantiation fails.
+    pub fn instantiate(
+        &self,
+        manager: &mut EntityManager,
+        registry: &ComponentRegistry,
+        overrides: &PrefabOverrides,
+    ) -> Result<EntityKey, Error> {
+        if let Some(path) = overrides.0.keys().find(|path| self.node(path).is_none()) {
+            return Err(Error::InvalidPrefab(format!(
+                "overridden node {:?} not found",
+                path
+            )));
+        }
+
+        // (node, path, parent index, components)
+        let mut items = Vec::new();
+        let mut stack = vec![(&self.root, Vec::new(), None)];
+        while let Some((node, path, parent)) = stack.pop() {
+            let mut components = node.components.clone();
+            components.remove(PREFAB_INSTANCE_NAME);
+            let mut overridden = Vec::new();
+            if let Some(values) = overrides.0.get(&path) {
+                for (name, value) in values {
+                    components.insert(name.clone(), value.clone());
+                    overridden.push(name.clone());
+                }
+            }
+
+            let mut set = registry.deserialize_components(&components, &node.shared_components)?;
+            if set.0.is_empty() {
+                return Err(Error::Entity(EntityError::EmptyComponents));
+            }
+            if let Some(key) = set
+                .1
+                .iter()
+                .find(|key| !manager.shared_components.contains_key(*key))
+            {
+                return Err(Error::Entity(EntityError::NoSuchSharedComponent(*key)));
+            }
+            set.add(PrefabInstance {
+                prefab: self.name.clone(),
+                path: path.clone(),
+                overrides: overridden,
+            })?;
+
+            let index = items.len();
+            stack.extend(node.children.iter().enumerate().rev().map(|(i, child)| {
+                let mut path = path.clone();
+                path.push(i);
+                (child, path, Some(index))
+            }));
+            items.push((node, components, parent, set));
+        }
+
+        let mut created: Vec<EntityKey> = Vec::with_capacity(items.len());
+        let mut mapping = HashMap::new();
+        for (node, _, parent, set) in items.iter_mut() {
+            let set = std::mem::replace(set, ComponentSet::new());
+            let key = manager.create_entity(set)?;
+            if let Some(parent) = parent {
+                manager.set_parent(&key, &created[*parent])?;
+            }
+            if let Some(original) = node.key {
+                mapping.insert(original, key);
+            }
+            created.push(key);
+        }
+
+        let mut mapper = |key: EntityKey| mapping.get(&key).copied().unwrap_or(key);
+        for ((_, components, _, _), key) in items.iter().zip(created.iter()) {
+            for name in components.keys() {
+                registry.map_entities(manager, key, name, &mut mapper);
+            }
+        }
+
+        Ok(created[0])
+    }
+
+    /// Returns root entities of all instances of this prefab.
+    pub fn instances(&self, manager: &EntityManager) -> Vec<EntityKey> {
+        manager
+            .entity_keys()
+            .into_iter()
+            .filter(|key| {
+                manager
+                    .get::<PrefabInstance>(key)
+                    .map(|instance| instance.prefab == self.name && instance.path.is_empty())
+                    .unwrap_or(false)
+            })
+            .collect()
+    }
+
+    /// Re-applies components of the prefab to all instantiated entities,
+    /// except components overridden when instantiated.
+    /// Returns the number of entities updated.
+    ///
+    /// Only components existing in the prefab are re-applied.
+    /// Entity keys in re-applied components referring to other nodes are remapped
+    /// to the entities of the same instance, the same as [`Prefab::instantiate`].
+    /// Components removed from the prefab and nodes added to the prefab after instantiation
+    /// are not reflected, and instances of removed nodes are left untouched.
+    /// Entities detached from the hierarchy of their instance root are not updated.
+    pub fn apply(
+        &self,
+        manager: &mut EntityManager,
+        registry: &ComponentRegistry,
+    ) -> Result<usize, Error> {
+        let mut updated = 0;
+        for root in self.instances(manager) {
+            // (entity, node, overrides) of the instance, and original node keys to entities
+            let mut entities = Vec::new();
+            let mut mapping = HashMap::new();
+            let mut stack = vec![root];
+            while let Some(key) = stack.pop() {
+                let Some(instance) = manager.get::<PrefabInstance>(&key) else {
+                    continue;
+                };
+                if instance.prefab != self.name {
+                    continue;
+                }
+                stack.extend(manager.children(&key).iter().rev().copied());
+                let Some(node) = self.node(&instance.path) else {
+                    continue;
+                };
+                if let Some(original) = node.key {
+                    mapping.insert(original, key);
+                }
+                entities.push((key, node, instance.overrides.clone()));
+            }
+
+            let mut mapper = |key: EntityKey| mapping.get(&key).copied().unwrap_or(key);
+            for (key, node, overrides) in entities {
+                for (name, value) in node.components.iter() {
+                    if name == PREFAB_INSTANCE_NAME || overrides.contains(name) {
+                        continue;
+                    }
+                    registry.replace_component(manager, &key, name, value.clone())?;
+                    registry.map_entities(manager, &key, name, &mut mapper);
+                }
+                updated += 1;
+            }
+        }
+
+        Ok(updated)
+    }
+}
+
+fn registered_name<C>(registry: &ComponentRegistry) -> Result<&str, Error>
+where
+    C: Component + 'static,
+{
+    registry
+        .component_name(&ComponentKey::new::<C>())
+        .ok_or_else(|| Error::UnregisteredComponent(std::any::type_name::<C>().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anewthing::ecs::scene::{serialize_world, MapEntities};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Name(String);
+
+    impl Component for Name {}
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Health(u32);
+
+    impl Component for Health {}
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Owner(EntityKey);
+
+    impl Component for Owner {}
+
+    impl MapEntities for Owner {
+        fn map_entities(&mut self, mapper: &mut dyn FnMut(EntityKey) -> EntityKey) {
+            self.0 = mapper(self.0);
+        }
+    }
+
+    fn registry() -> ComponentRegistry {
+        let mut registry = ComponentRegistry::new();
+        registry.register::<Name>("name").unwrap();
+        registry.register::<Health>("health").unwrap();
+        registry.register_with_entities::<Owner>("owner").unwrap();
+        registry
+    }
+
+    fn node(registry: &ComponentRegistry, name: &str, health: u32) -> PrefabNode {
+        let mut components = ComponentSet::with_component(Name(name.to_string()));
+        components.add(Health(health)).unwrap();
+        PrefabNode::from_component_set(registry, &components).unwrap()
+    }
+
+    #[test]
+    fn test_instantiate_and_apply() {
+        let registry = registry();
+        let mut prefab = Prefab::new(
+            "tank",
+            node(&registry, "body", 100)
+                .with_child(node(&registry, "turret", 50).with_child(node(&registry, "gun", 10)))
+                .with_child(node(&registry, "track", 20)),
+        );
+
+        let mut manager = EntityManager::new();
+        let plain = prefab
+            .instantiate(&mut manager, &registry, &PrefabOverrides::new())
+            .unwrap();
+        let overrides = PrefabOverrides::new()
+            .with(&registry, &Health(200))
+            .unwrap()
+            .with_at(&registry, &[0, 0], &Name("cannon".to_string()))
+            .unwrap();
+        let custom = prefab
+            .instantiate(&mut manager, &registry, &overrides)
+            .unwrap();
+        assert_eq!(manager.entity_keys().len(), 8);
+        assert_eq!(prefab.instances(&manager), vec![plain, custom]);
+
+        let turret = manager.children(&custom)[0];
+        let gun = manager.children(&turret)[0];
+        assert_eq!(manager.get::<Health>(&custom), Some(&Health(200)));
+        assert_eq!(manager.get::<Name>(&gun), Some(&Name("cannon".to_string())));
+        assert_eq!(
+            manager.get::<PrefabInstance>(&gun).unwrap().path(),
+            &[0usize, 0]
+        );
+        assert_eq!(manager.children(&plain).len(), 2);
+
+        // edits are re-applied, except overridden components
+        prefab
+            .root_mut()
+            .set_component(&registry, &Health(150))
+            .unwrap();
+        prefab
+            .node_mut(&[0, 0])
+            .unwrap()
+            .set_component(&registry, &Name("laser".to_string()))
+            .unwrap();
+        assert_eq!(prefab.apply(&mut manager, &registry).unwrap(), 8);
+        assert_eq!(manager.get::<Health>(&plain), Some(&Health(150)));
+        assert_eq!(manager.get::<Health>(&custom), Some(&Health(200)));
+        assert_eq!(manager.get::<Name>(&gun), Some(&Name("cannon".to_string())));
+        let plain_gun = manager.children(&manager.children(&plain)[0])[0];
+        assert_eq!(
+            manager.get::<Name>(&plain_gun),
+            Some(&Name("laser".to_string()))
+        );
+
+        let invalid = PrefabOverrides::new()
+            .with_at(&registry, &[5], &Health(0))
+            .unwrap();
+        assert!(matches!(
+            prefab.instantiate(&mut manager, &registry, &invalid),
+            Err(Error::InvalidPrefab(_))
+        ));
+        assert_eq!(manager.entity_keys().len(), 8);
+    }
+
+    #[test]
+    fn test_prefab_from_scene() {
+        let registry = registry();
+        let mut manager = EntityManager::new();
+        let spawn = |manager: &mut EntityManager, name: &str| {
+            manager
+                .create_entity(ComponentSet::with_component(Name(name.to_string())))
+                .unwrap()
+        };
+        let ship = spawn(&mut manager, "ship");
+        let pilot = spawn(&mut manager, "pilot");
+        manager.set_parent(&pilot, &ship).unwrap();
+        manager.add_component(&pilot, Owner(ship)).unwrap();
+
+        // a world with a single root is a valid prefab
+        let json = serialize_world(&manager, &registry).unwrap();
+        let prefab = Prefab::from_scene("ship", &json).unwrap();
+        assert_eq!(
+            prefab,
+            Prefab::from_entity("ship", &manager, &registry, &ship).unwrap()
+        );
+        let prefab = Prefab::from_scene("ship", &prefab.to_scene().unwrap()).unwrap();
+
+        let instance = prefab
+            .instantiate(&mut manager, &registry, &PrefabOverrides::new())
+            .unwrap();
+        let instance_pilot = manager.children(&instance)[0];
+        assert_eq!(
+            manager.get::<Owner>(&instance_pilot),
+            Some(&Owner(instance))
+        );
+        assert_eq!(manager.get::<Owner>(&pilot), Some(&Owner(ship)));
+
+        // entity keys are remapped to the same instance when re-applied
+        let other = prefab
+            .instantiate(&mut manager, &registry, &PrefabOverrides::new())
+            .unwrap();
+        let other_pilot = manager.children(&other)[0];
+        assert_eq!(prefab.apply(&mut manager, &registry).unwrap(), 4);
+        assert_eq!(
+            manager.get::<Owner>(&instance_pilot),
+            Some(&Owner(instance))
+        );
+        assert_eq!(manager.get::<Owner>(&other_pilot), Some(&Owner(other)));
+        assert_eq!(manager.get::<Owner>(&pilot), Some(&Owner(ship)));
+
+        // instances could be captured again, without tracking the previous prefab
+        let captured = Prefab::from_entity("copy", &manager, &registry, &instance).unwrap();
+        assert!(captured
+            .root()
+            .component_names()
+            .all(|name| name != PREFAB_INSTANCE_NAME));
+
+        // more than one root now
+        let json = serialize_world(&manager, &registry).unwrap();
+        assert!(matches!(
+            Prefab::from_scene("ships", &json),
+            Err(Error::InvalidPrefab(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_scene_rejects_detached_cycle() {
+        let registry = registry();
+        let mut manager = EntityManager::new();
+        let mut spawn = |name: &str| {
+            manager
+                .create_entity(ComponentSet::with_component(Name(name.to_string())))
+                .unwrap()
+        };
+        let root = spawn("root");
+        let b = spawn("b");
+        let c = spawn("c");
+        let json = serialize_world(&manager, &registry).unwrap();
+
+        // b and c are children of each other, leaving root the only root entity
+        let mut scene = serde_json::from_str::<SceneData>(&json).unwrap();
+        for entity in scene.entities.iter_mut() {
+            if entity.key == b {
+                entity.children.push(c);
+            } else if entity.key == c {
+                entity.children.push(b);
+            }
+        }
+        let json = serde_json::to_string(&scene).unwrap();
+        assert!(matches!(
+            Prefab::from_scene("detached", &json),
+            Err(Error::InvalidPrefab(_))
+        ));
+
+        // attaching the cycle to root is still linked more than once
+        let mut scene = serde_json::from_str::<SceneData>(&json).unwrap();
+        scene
+            .entities
+            .iter_mut()
+            .find(|entity| entity.key == root)
+            .unwrap()
+            .children
+            .push(b);
+        let json = serde_json::to_string(&scene).unwrap();
+        assert!(matches!(
+            Prefab::from_scene("attached", &json),
+            Err(Error::InvalidPrefab(_))
+        ));
+    }
+}
@@ -4,6 +4,8 @@ use hashbrown::HashMap;
 
 use super::{
     archetype::Archetype,
+    change::Mut,
+    column::Column,
     component::{Component, ComponentKey, SharedComponentKey},
     manager::{ChunkItem, EntityManager, SharedComponentItem},
 };
@@ -19,6 +21,59 @@ pub enum QueryType {
     WithShared(SharedComponentKey),
     /// Archetype contains no specific component.
     Without,
+    /// Archetype contains specific component at specif chunk index,
+    /// and only entities whose component is added after last change tick are matched.
+    Added((ComponentKey, usize)),
+    /// Archetype contains specific component at specif chunk index,
+    /// and only entities whose component is changed after last change tick are matched.
+    Changed((ComponentKey, usize)),
+}
+
+impl QueryType {
+    /// Returns `true` if entity at `index` of a chunk passes change detection.
+    fn filter(&self, chunk_item: &ChunkItem, index: usize, last_change_tick: u64) -> bool {
+        match self {
+            QueryType::Added((_, position)) => {
+                chunk_item.columns[*position].added_tick(index) > last_change_tick
+            }
+            QueryType::Changed((_, position)) => {
+                chunk_item.columns[*position].changed_tick(index) > last_change_tick
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Fetches a component for simple queries.
+unsafe fn fetch_simple<'a, C>(
+    query_type: &QueryType,
+    chunk_item: *mut ChunkItem,
+    shared_components: *mut HashMap<SharedComponentKey, SharedComponentItem>,
+    index: usize,
+    change_tick: u64,
+) -> Mut<'a, C>
+where
+    C: 'static,
+{
+    match query_type {
+        QueryType::With((_, position))
+        | QueryType::Added((_, position))
+        | QueryType::Changed((_, position)) => {
+            let (component, changed_tick) =
+                (*chunk_item).columns[*position].get_with_tick_unchecked_mut::<C>(index);
+            Mut::new(component, Some(changed_tick), change_tick)
+        }
+        QueryType::WithShared(key) => {
+            let component = (*shared_components)
+                .get_mut(key)
+                .unwrap()
+                .component
+                .downcast_mut::<C>()
+                .unwrap();
+            Mut::new(component, None, change_tick)
+        }
+        _ => unreachable!(),
+    }
 }
 
 /// A query operator returns a [`QueryType`].
@@ -28,8 +83,8 @@ pub trait QueryOp {
         Self: Sized;
 }
 
-/// A simple query operator MUST returns neither
-/// [`QueryType::NotMatched`], [`QueryType::With`] or [`QueryType::WithShared`].
+/// A simple query operator MUST returns either [`QueryType::NotMatched`], [`QueryType::With`],
+/// [`QueryType::WithShared`], [`QueryType::Added`] or [`QueryType::Changed`].
 pub trait QueryOpSimple<C>: QueryOp {}
 
 /// Queries components with a specific component type.
@@ -70,6 +125,48 @@ where
     }
 }
 
+/// Queries components with a specific component type,
+/// which are added after last change tick of [`EntityManager`].
+pub struct Added<C>(PhantomData<C>);
+
+impl<C> QueryOp for Added<C>
+where
+    C: Component + 'static,
+{
+    fn query(archetype: &Archetype) -> QueryType
+    where
+        Self: Sized,
+    {
+        match archetype.component_index::<C>() {
+            Some(position) => QueryType::Added((ComponentKey::new::<C>(), position)),
+            None => QueryType::NotMatched,
+        }
+    }
+}
+
+impl<C> QueryOpSimple<C> for Added<C> where C: Component + 'static {}
+
+/// Queries components with a specific component type,
+/// which are added or changed after last change tick of [`EntityManager`].
+pub struct Changed<C>(PhantomData<C>);
+
+impl<C> QueryOp for Changed<C>
+where
+    C: Component + 'static,
+{
+    fn query(archetype: &Archetype) -> QueryType
+    where
+        Self: Sized,
+    {
+        match archetype.component_index::<C>() {
+            Some(position) => QueryType::Changed((ComponentKey::new::<C>(), position)),
+            None => QueryType::NotMatched,
+        }
+    }
+}
+
+impl<C> QueryOpSimple<C> for Changed<C> where C: Component + 'static {}
+
 /// Queries components with or without a specific component type.
 /// Do not query entities with only [`Maybe`] operators, which is meaningless.
 pub struct Maybe<C>(PhantomData<C>);
@@ -184,15 +281,21 @@ pub struct QuerySimpleIter<'a, A, S> {
     shared_components: &'a mut HashMap<SharedComponentKey, SharedComponentItem>,
     chunks: hashbrown::hash_map::IterMut<'a, Archetype, ChunkItem>,
     chunk: Option<(&'a Archetype, &'a mut ChunkItem, QueryType, usize)>,
+    change_tick: u64,
+    last_change_tick: u64,
     _k: PhantomData<(A, S)>,
 }
 
 impl<'a, A, S> QuerySimpleIter<'a, A, S> {
     fn new(manager: &'a mut EntityManager) -> Self {
+        let change_tick = manager.change_tick();
+        let last_change_tick = manager.last_change_tick();
         Self {
             shared_components: &mut manager.shared_components,
             chunks: manager.chunks.iter_mut(),
             chunk: None,
+            change_tick,
+            last_change_tick,
             _k: PhantomData,
         }
     }
@@ -203,45 +306,55 @@ where
     A: Component + 'static,
     S: QueryOpSimple<A> + 'static,
 {
-    type Item = &'a mut A;
+    type Item = Mut<'a, A>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some((_, chunk_item, _, index)) = self.chunk.as_mut() {
-            *index += 1;
+        loop {
+            if let Some((_, chunk_item, _, index)) = self.chunk.as_mut() {
+                *index += 1;
 
-            if *index >= chunk_item.entity_keys.len() {
-                self.chunk = None;
+                if *index >= chunk_item.entity_keys.len() {
+                    self.chunk = None;
+                }
             }
-        }
 
-        if self.chunk.is_none() {
-            // finds next chunk that matches the query
-            while let Some((archetype, chunk_item)) = self.chunks.next() {
-                let query_type = S::query(archetype);
-                match &query_type {
-                    QueryType::NotMatched => continue,
-                    QueryType::With(_) | QueryType::WithShared(_) => {
-                        self.chunk = Some((archetype, chunk_item, query_type, 0));
+            if self.chunk.is_none() {
+                // finds next non-empty chunk that matches the query
+                while let Some((archetype, chunk_item)) = self.chunks.next() {
+                    if chunk_item.entity_keys.is_empty() {
+                        continue;
+                    }
+
+                    let query_type = S::query(archetype);
+                    match &query_type {
+                        QueryType::NotMatched => continue,
+                        QueryType::With(_)
+                        | QueryType::WithShared(_)
+                        | QueryType::Added(_)
+                        | QueryType::Changed(_) => {
+                            self.chunk = Some((archetype, chunk_item, query_type, 0));
+                            break;
+                        }
+                        _ => unreachable!(),
                     }
-                    _ => unreachable!(),
                 }
             }
-        }
 
-        let (archetype, chunk_item, query_type, index) = self.chunk.as_mut()?;
-
-        let component: *mut Box<dyn Any> = match query_type {
-            QueryType::With((_, position)) => {
-                &mut chunk_item.components[*index * archetype.components_len() + *position]
-                    .component
-            }
-            QueryType::WithShared(key) => {
-                &mut self.shared_components.get_mut(key).unwrap().component
+            let (_, chunk_item, query_type, index) = self.chunk.as_mut()?;
+            if !query_type.filter(chunk_item, *index, self.last_change_tick) {
+                continue;
             }
-            _ => unreachable!(),
-        };
 
-        unsafe { Some((*component).downcast_mut::<A>().unwrap()) }
+            unsafe {
+                return Some(fetch_simple::<A>(
+                    query_type,
+                    &mut **chunk_item,
+                    &mut *self.shared_components,
+                    *index,
+                    self.change_tick,
+                ));
+            }
+        }
     }
 }
 
@@ -281,15 +394,21 @@ macro_rules! query_simple {
             shared_components: &'a mut HashMap<SharedComponentKey, SharedComponentItem>,
             chunks: hashbrown::hash_map::IterMut<'a, Archetype, ChunkItem>,
             chunk: Option<(&'a Archetype, &'a mut ChunkItem, [QueryType; $count], usize)>,
+            change_tick: u64,
+            last_change_tick: u64,
             _k: PhantomData<&'a ($($c, $q,)+)>,
         }
 
         impl<'a, $($c, $q,)+> $iter<'a, $($c, $q,)+> {
             fn new(manager: &'a mut EntityManager) -> Self {
+                let change_tick = manager.change_tick();
+                let last_change_tick = manager.last_change_tick();
                 Self {
                     shared_components: &mut manager.shared_components,
                     chunks: manager.chunks.iter_mut(),
                     chunk: None,
+                    change_tick,
+                    last_change_tick,
                     _k: PhantomData,
                 }
             }
@@ -302,57 +421,70 @@ macro_rules! query_simple {
                 $q: QueryOpSimple<$c> + 'static,
             )+
         {
-            type Item = ($(&'a mut $c,)+);
+            type Item = ($(Mut<'a, $c>,)+);
 
             fn next(&mut self) -> Option<Self::Item> {
-                if let Some((_, chunk_item, _, index)) = self.chunk.as_mut() {
-                    *index += 1;
+                loop {
+                    if let Some((_, chunk_item, _, index)) = self.chunk.as_mut() {
+                        *index += 1;
 
-                    if *index >= chunk_item.entity_keys.len() {
-                        self.chunk = None;
+                        if *index >= chunk_item.entity_keys.len() {
+                            self.chunk = None;
+                        }
                     }
-                }
 
-                if self.chunk.is_none() {
-                    while let Some((archetype, chunk_item)) = self.chunks.next() {
-                        let mut query_types = [QueryType::NotMatched; $count];
-                        $(
-                            {
-                                let query_type = $q::query(archetype);
-                                match &query_type {
-                                    QueryType::NotMatched => continue,
-                                    QueryType::With(_) | QueryType::WithShared(_) => {
-                                        query_types[$i] = query_type;
-                                    }
-                                    _ => unreachable!(),
-                                };
+                    if self.chunk.is_none() {
+                        // finds next non-empty chunk that matches the query
+                        'chunks: while let Some((archetype, chunk_item)) = self.chunks.next() {
+                            if chunk_item.entity_keys.is_empty() {
+                                continue;
                             }
-                        )+
-                        self.chunk = Some((archetype, chunk_item, query_types, 0));
+
+                            let mut query_types = [QueryType::NotMatched; $count];
+                            $(
+                                {
+                                    let query_type = $q::query(archetype);
+                                    match &query_type {
+                                        QueryType::NotMatched => continue 'chunks,
+                                        QueryType::With(_)
+                                        | QueryType::WithShared(_)
+                                        | QueryType::Added(_)
+                                        | QueryType::Changed(_) => {
+                                            query_types[$i] = query_type;
+                                        }
+                                        _ => unreachable!(),
+                                    };
+                                }
+                            )+
+                            self.chunk = Some((archetype, chunk_item, query_types, 0));
+                            break;
+                        }
+                    }
+
+                    let (_, chunk_item, query_types, index) = self.chunk.as_mut()?;
+                    let last_change_tick = self.last_change_tick;
+                    if !query_types
+                        .iter()
+                        .all(|query_type| query_type.filter(chunk_item, *index, last_change_tick))
+                    {
+                        continue;
                     }
-                }
 
-                let (archetype, chunk_item, query_types, index) = self.chunk.as_mut()?;
-
-                unsafe {
-                    Some((
-                        $(
-                            {
-                                let component: *mut Box<dyn Any> = match &query_types[$i] {
-                                    QueryType::With((_, position)) => {
-                                        &mut chunk_item.components[*index * archetype.components_len() + *position]
-                                            .component
-                                    }
-                                    QueryType::WithShared(key) => {
-                                        &mut self.shared_components.get_mut(key).unwrap().component
-                                    }
-                                    _ => unreachable!(),
-                                };
-
-                                (*component).downcast_mut::<$c>().unwrap()
-                            },
-                        )+
-                    ))
+                    let chunk_item: *mut ChunkItem = &mut **chunk_item;
+                    let shared_components: *mut _ = &mut *self.shared_components;
+                    unsafe {
+                        return Some((
+                            $(
+                                fetch_simple::<$c>(
+                                    &query_types[$i],
+                                    chunk_item,
+                                    shared_components,
+                                    *index,
+                                    self.change_tick,
+                                ),
+                            )+
+                        ));
+                    }
                 }
             }
         }
@@ -580,35 +712,32 @@ query_simple! {
 
 /// Queried components returns by [`QueryComplex`].
 pub struct Queried<'a> {
-    components: HashMap<ComponentKey, &'a mut Box<dyn Any>>,
+    components: HashMap<ComponentKey, (&'a mut (dyn Any + 'static), &'a mut u64)>,
     shared_components: HashMap<SharedComponentKey, &'a mut Box<dyn Any>>,
+    change_tick: u64,
 }
 
 impl<'a> Queried<'a> {
     /// Returns a component with a specific component type.
-    pub fn component<C>(&'a mut self) -> Option<&'a mut C>
+    pub fn component<C>(&'a mut self) -> Option<Mut<'a, C>>
     where
         C: Component + 'static,
     {
-        Some(
-            self.components
-                .get_mut(&ComponentKey::new::<C>())?
-                .downcast_mut::<C>()
-                .unwrap(),
-        )
+        let (component, changed_tick) = self.components.get_mut(&ComponentKey::new::<C>())?;
+        Some(Mut::new(
+            component.downcast_mut::<C>().unwrap(),
+            Some(changed_tick),
+            self.change_tick,
+        ))
     }
 
     /// Returns a component with a specific component type.
     /// Panic if no such component.
-    pub fn component_unchecked<C>(&'a mut self) -> &'a mut C
+    pub fn component_unchecked<C>(&'a mut self) -> Mut<'a, C>
     where
         C: Component + 'static,
     {
-        self.components
-            .get_mut(&ComponentKey::new::<C>())
-            .unwrap()
-            .downcast_mut::<C>()
-            .unwrap()
+        self.component::<C>().unwrap()
     }
 
     /// Returns a shared component with a specific component type.
@@ -644,6 +773,8 @@ pub struct QueryComplexIter<'a, S> {
     shared_components: &'a mut HashMap<SharedComponentKey, SharedComponentItem>,
     chunks: hashbrown::hash_map::IterMut<'a, Archetype, ChunkItem>,
     chunk: Option<(&'a Archetype, &'a mut ChunkItem, QueryType, usize)>,
+    change_tick: u64,
+    last_change_tick: u64,
     _k: PhantomData<S>,
 }
 
@@ -652,73 +783,89 @@ where
     S: QueryOp + 'static,
 {
     fn new(manager: &'a mut EntityManager) -> Self {
+        let change_tick = manager.change_tick();
+        let last_change_tick = manager.last_change_tick();
         Self {
             shared_components: &mut manager.shared_components,
             chunks: manager.chunks.iter_mut(),
             chunk: None,
+            change_tick,
+            last_change_tick,
             _k: PhantomData,
         }
     }
 }
 
 impl<'a, S> Iterator for QueryComplexIter<'a, S>
-    where
+where
     S: QueryOp + 'static,
 {
     type Item = Queried<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some((_, chunk_item, _, index)) = self.chunk.as_mut() {
-            *index += 1;
+        loop {
+            if let Some((_, chunk_item, _, index)) = self.chunk.as_mut() {
+                *index += 1;
 
-            if *index >= chunk_item.entity_keys.len() {
-                self.chunk = None;
+                if *index >= chunk_item.entity_keys.len() {
+                    self.chunk = None;
+                }
             }
-        }
 
-        if self.chunk.is_none() {
-            // finds next chunk that matches the query
-            while let Some((archetype, chunk_item)) = self.chunks.next() {
-                let query_type = S::query(archetype);
-                match &query_type {
-                    QueryType::NotMatched => continue,
-                    _ => {
-                        self.chunk = Some((archetype, chunk_item, query_type, 0));
+            if self.chunk.is_none() {
+                // finds next non-empty chunk that matches the query
+                while let Some((archetype, chunk_item)) = self.chunks.next() {
+                    if chunk_item.entity_keys.is_empty() {
+                        continue;
                     }
-                }
-            }
-        }
 
-        let (archetype, chunk_item, query_type, index) = self.chunk.as_mut()?;
-
-        let mut components = HashMap::with_capacity(1);
-        let mut shared_components = HashMap::with_capacity(1);
-        match query_type {
-            QueryType::With((key, position)) => {
-                let component: *mut Box<dyn Any> = &mut chunk_item.components
-                    [*index * archetype.components_len() + *position]
-                    .component;
-                unsafe {
-                    components.insert_unique_unchecked(*key, &mut *component);
-                }
-            }
-            QueryType::WithShared(key) => {
-                let component: *mut Box<dyn Any> =
-                    &mut self.shared_components.get_mut(key).unwrap().component;
-                unsafe {
-                    shared_components.insert_unique_unchecked(*key, &mut *component);
+                    let query_type = S::query(archetype);
+                    match &query_type {
+                        QueryType::NotMatched => continue,
+                        _ => {
+                            self.chunk = Some((archetype, chunk_item, query_type, 0));
+                            break;
+                        }
+                    }
                 }
             }
-            QueryType::Without => {
-                // does nothing
+
+            let (_, chunk_item, query_type, index) = self.chunk.as_mut()?;
+            if !query_type.filter(chunk_item, *index, self.last_change_tick) {
+                continue;
             }
-            QueryType::NotMatched => unreachable!(),
-        };
 
-        Some(Queried {
-            components,
-            shared_components,
-        })
+            let mut components = HashMap::with_capacity(1);
+            let mut shared_components = HashMap::with_capacity(1);
+            match query_type {
+                QueryType::With((key, position))
+                | QueryType::Added((key, position))
+                | QueryType::Changed((key, position)) => {
+                    let column: *mut Column = &mut chunk_item.columns[*position];
+                    unsafe {
+                        let component = (*column).get_any_with_tick_mut(*index).unwrap();
+                        components.insert_unique_unchecked(*key, component);
+                    }
+                }
+                QueryType::WithShared(key) => {
+                    let component: *mut Box<dyn Any> =
+                        &mut self.shared_components.get_mut(key).unwrap().component;
+                    unsafe {
+                        shared_components.insert_unique_unchecked(*key, &mut *component);
+                    }
+                }
+                QueryType::Without => {
+                    // does nothing
+                }
+                QueryType::NotMatched => unreachable!(),
+            };
+
+            return Some(Queried {
+                components,
+                shared_components,
+                change_tick: self.change_tick,
+            });
+        }
     }
 }
 
@@ -776,15 +923,21 @@ macro_rules! query_complex {
             shared_components: &'a mut HashMap<SharedComponentKey, SharedComponentItem>,
             chunks: hashbrown::hash_map::IterMut<'a, Archetype, ChunkItem>,
             chunk: Option<(&'a Archetype, &'a mut ChunkItem, [QueryType; $count], usize)>,
+            change_tick: u64,
+            last_change_tick: u64,
             _k: PhantomData<($($q,)+)>,
         }
 
         impl<'a, $($q,)+> $iter<'a, $($q,)+> {
             fn new(manager: &'a mut EntityManager) -> Self {
+                let change_tick = manager.change_tick();
+                let last_change_tick = manager.last_change_tick();
                 Self {
                     shared_components: &mut manager.shared_components,
                     chunks: manager.chunks.iter_mut(),
                     chunk: None,
+                    change_tick,
+                    last_change_tick,
                     _k: PhantomData,
                 }
             }
@@ -799,58 +952,80 @@ macro_rules! query_complex {
             type Item = Queried<'a>;
 
             fn next(&mut self) -> Option<Self::Item> {
-                if let Some((_, chunk_item, _, index)) = self.chunk.as_mut() {
-                    *index += 1;
+                loop {
+                    if let Some((_, chunk_item, _, index)) = self.chunk.as_mut() {
+                        *index += 1;
 
-                    if *index >= chunk_item.entity_keys.len() {
-                        self.chunk = None;
+                        if *index >= chunk_item.entity_keys.len() {
+                            self.chunk = None;
+                        }
                     }
-                }
 
-                if self.chunk.is_none() {
-                    while let Some((archetype, chunk_item)) = self.chunks.next() {
-                        let mut query_types = [QueryType::NotMatched; $count];
-                        $(
-                            {
-                                let query_type = $q::query(archetype);
-                                match &query_type {
-                                    QueryType::NotMatched => continue,
-                                    _ => {
-                                        query_types[$i] = query_type;
-                                    }
-                                };
+                    if self.chunk.is_none() {
+                        // finds next non-empty chunk that matches the query
+                        'chunks: while let Some((archetype, chunk_item)) = self.chunks.next() {
+                            if chunk_item.entity_keys.is_empty() {
+                                continue;
                             }
-                        )+
-                        self.chunk = Some((archetype, chunk_item, query_types, 0));
+
+                            let mut query_types = [QueryType::NotMatched; $count];
+                            $(
+                                {
+                                    let query_type = $q::query(archetype);
+                                    match &query_type {
+                                        QueryType::NotMatched => continue 'chunks,
+                                        _ => {
+                                            query_types[$i] = query_type;
+                                        }
+                                    };
+                                }
+                            )+
+                            self.chunk = Some((archetype, chunk_item, query_types, 0));
+                            break;
+                        }
                     }
-                }
 
-                let (archetype, chunk_item, query_types, index) = self.chunk.as_mut()?;
+                    let (_, chunk_item, query_types, index) = self.chunk.as_mut()?;
+                    let last_change_tick = self.last_change_tick;
+                    if !query_types
+                        .iter()
+                        .all(|query_type| query_type.filter(chunk_item, *index, last_change_tick))
+                    {
+                        continue;
+                    }
 
-                let mut components = HashMap::with_capacity(1);
-                let mut shared_components = HashMap::with_capacity(1);
-                $(
-                    match &query_types[$i] {
-                        QueryType::With((key, position)) => {
-                            let component: *mut Box<dyn Any> = &mut chunk_item.components[*index * archetype.components_len() + *position].component;
-                            unsafe {
-                                components.insert_unique_unchecked(*key, &mut *component);
+                    let mut components = HashMap::with_capacity($count);
+                    let mut shared_components = HashMap::with_capacity($count);
+                    $(
+                        match &query_types[$i] {
+                            QueryType::With((key, position))
+                            | QueryType::Added((key, position))
+                            | QueryType::Changed((key, position)) => {
+                                let column: *mut Column = &mut chunk_item.columns[*position];
+                                unsafe {
+                                    let component = (*column).get_any_with_tick_mut(*index).unwrap();
+                                    components.insert_unique_unchecked(*key, component);
+                                }
                             }
-                        }
-                        QueryType::WithShared(key) => {
-                            let component: *mut Box<dyn Any> = &mut self.shared_components.get_mut(key).unwrap().component;
-                            unsafe {
-                                shared_components.insert_unique_unchecked(*key, &mut *component);
+                            QueryType::WithShared(key) => {
+                                let component: *mut Box<dyn Any> = &mut self.shared_components.get_mut(key).unwrap().component;
+                                unsafe {
+                                    shared_components.insert_unique_unchecked(*key, &mut *component);
+                                }
                             }
-                        }
-                        QueryType::Without => {
-                            // does nothing
-                        }
-                        QueryType::NotMatched => unreachable!(),
-                    };
-                )+
-
-                Some(Queried { components, shared_components, })
+                            QueryType::Without => {
+                                // does nothing
+                            }
+                            QueryType::NotMatched => unreachable!(),
+                        };
+                    )+
+
+                    return Some(Queried {
+                        components,
+                        shared_components,
+                        change_tick: self.change_tick,
+                    });
+                }
             }
         }
     };
@@ -0,0 +1,804 @@
+use std::{
+    any::Any,
+    fmt::{Debug, Display},
+};
+
+use hashbrown::{HashMap, HashSet};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use super::{
+    component::{Component, ComponentKey, ComponentSet, SharedComponentKey},
+    entity::EntityKey,
+    error::Error as EntityError,
+    manager::EntityManager,
+    prefab::{PrefabInstance, PREFAB_INSTANCE_NAME},
+};
+
+/// A component referring to other entities by [`EntityKey`].
+///
+/// Entity keys are different after a scene is loaded,
+/// components registered by [`ComponentRegistry::register_with_entities`]
+/// are remapped to the loaded entities.
+pub trait MapEntities {
+    fn map_entities(&mut self, mapper: &mut dyn FnMut(EntityKey) -> EntityKey);
+}
+
+type MapEntitiesFn = fn(&mut EntityManager, &EntityKey, &mut dyn FnMut(EntityKey) -> EntityKey);
+
+struct Registration {
+    name: String,
+    serialize: fn(&dyn Any) -> Result<Value, serde_json::Error>,
+    deserialize: fn(Value, &mut ComponentSet) -> Result<(), Error>,
+    replace: fn(&mut EntityManager, &EntityKey, Value) -> Result<(), Error>,
+    map_entities: Option<MapEntitiesFn>,
+}
+
+struct SharedRegistration {
+    name: String,
+    serialize: fn(&dyn Any) -> Result<Value, serde_json::Error>,
+    deserialize: fn(Value) -> Result<Box<dyn Any>, Error>,
+    insert: fn(&mut EntityManager, Box<dyn Any>) -> Result<(), Error>,
+}
+
+fn serialize<C>(component: &dyn Any) -> Result<Value, serde_json::Error>
+where
+    C: Serialize + 'static,
+{
+    serde_json::to_value(component.downcast_ref::<C>().unwrap())
+}
+
+/// A registry of components opting in serialization, by a stable type name.
+///
+/// Components not registered are skipped when serializing,
+/// as well as entities having no registered non-shared component.
+/// Hierarchy components are always serialized as hierarchy links.
+pub struct ComponentRegistry {
+    components: HashMap<ComponentKey, Registration>,
+    component_names: HashMap<String, ComponentKey>,
+    shared_components: HashMap<SharedComponentKey, SharedRegistration>,
+    shared_component_names: HashMap<String, SharedComponentKey>,
+}
+
+impl ComponentRegistry {
+    /// Constructs a new component registry, with [`PrefabInstance`] registered as `prefab_instance`.
+    pub fn new() -> Self {
+        let mut registry = Self {
+            components: HashMap::new(),
+            component_names: HashMap::new(),
+            shared_components: HashMap::new(),
+            shared_component_names: HashMap::new(),
+        };
+        registry
+            .register::<PrefabInstance>(PREFAB_INSTANCE_NAME)
+            .unwrap();
+        registry
+    }
+
+    /// Returns the registered name of a component.
+    pub fn component_name(&self, key: &ComponentKey) -> Option<&str> {
+        self.components
+            .get(key)
+            .map(|registration| registration.name.as_str())
+    }
+
+    /// Returns the registered name of a shared component.
+    pub fn shared_component_name(&self, key: &SharedComponentKey) -> Option<&str> {
+        self.shared_components
+            .get(key)
+            .map(|registration| registration.name.as_str())
+    }
+
+    /// Returns the shared component key by a registered name.
+    pub(super) fn shared_component_key(&self, name: &str) -> Option<SharedComponentKey> {
+        self.shared_component_names.get(name).copied()
+    }
+
+    /// Serializes registered components by their names, skipping components not registered.
+    pub(super) fn serialize_components<'a, I>(
+        &self,
+        components: I,
+    ) -> Result<Map<String, Value>, Error>
+    where
+        I: IntoIterator<Item = (ComponentKey, &'a (dyn Any + 'static))>,
+    {
+        let mut serialized = Map::new();
+        for (key, component) in components {
+            let Some(registration) = self.components.get(&key) else {
+                continue;
+            };
+            serialized.insert(
+                registration.name.clone(),
+                (registration.serialize)(component)?,
+            );
+        }
+        Ok(serialized)
+    }
+
+    /// Deserializes components and shared component references into a component set.
+    /// Existence of shared components is not checked.
+    pub(super) fn deserialize_components(
+        &self,
+        components: &Map<String, Value>,
+        shared_components: &[String],
+    ) -> Result<ComponentSet, Error> {
+        let mut set = ComponentSet::new();
+        for (name, value) in components.iter() {
+            let Some(registration) = self
+                .component_names
+                .get(name)
+                .and_then(|key| self.components.get(key))
+            else {
+                return Err(Error::UnregisteredComponent(name.clone()));
+            };
+            (registration.deserialize)(value.clone(), &mut set)?;
+        }
+        for name in shared_components.iter() {
+            let Some(key) = self.shared_component_key(name) else {
+                return Err(Error::UnregisteredComponent(name.clone()));
+            };
+            set.1.push(key);
+        }
+        set.1.sort();
+        Ok(set)
+    }
+
+    /// Deserializes a component and puts it into an entity, replacing the existing one if any.
+    pub(super) fn replace_component(
+        &self,
+        manager: &mut EntityManager,
+        entity_key: &EntityKey,
+        name: &str,
+        value: Value,
+    ) -> Result<(), Error> {
+        let Some(registration) = self
+            .component_names
+            .get(name)
+            .and_then(|key| self.components.get(key))
+        else {
+            return Err(Error::UnregisteredComponent(name.to_string()));
+        };
+        (registration.replace)(manager, entity_key, value)
+    }
+
+    /// Remaps entity keys in a component of an entity, if the component refers to other entities.
+    pub(super) fn map_entities(
+        &self,
+        manager: &mut EntityManager,
+        entity_key: &EntityKey,
+        name: &str,
+        mapper: &mut dyn FnMut(EntityKey) -> EntityKey,
+    ) {
+        let map_entities = self
+            .component_names
+            .get(name)
+            .and_then(|key| self.components.get(key))
+            .and_then(|registration| registration.map_entities);
+        if let Some(map_entities) = map_entities {
+            map_entities(manager, entity_key, mapper);
+        }
+    }
+
+    /// Registers a component by a stable type name.
+    pub fn register<C>(&mut self, name: &str) -> Result<(), Error>
+    where
+        C: Component + Serialize + DeserializeOwned + 'static,
+    {
+        self.register_component::<C>(name, None)
+    }
+
+    /// Registers a component referring to other entities by a stable type name.
+    /// Entity keys in the component are remapped when loaded.
+    pub fn register_with_entities<C>(&mut self, name: &str) -> Result<(), Error>
+    where
+        C: Component + MapEntities + Serialize + DeserializeOwned + 'static,
+    {
+        fn map_entities<C>(
+            manager: &mut EntityManager,
+            entity_key: &EntityKey,
+            mapper: &mut dyn FnMut(EntityKey) -> EntityKey,
+        ) where
+            C: Component + MapEntities + 'static,
+        {
+            if let Some(mut component) = manager.get_mut::<C>(entity_key) {
+                component.map_entities(mapper);
+            }
+        }
+
+        self.register_component::<C>(name, Some(map_entities::<C>))
+    }
+
+    fn register_component<C>(
+        &mut self,
+        name: &str,
+        map_entities: Option<MapEntitiesFn>,
+    ) -> Result<(), Error>
+    where
+        C: Component + Serialize + DeserializeOwned + 'static,
+    {
+        fn deserialize<C>(value: Value, components: &mut ComponentSet) -> Result<(), Error>
+        where
+            C: Component + DeserializeOwned + 'static,
+        {
+            let component = serde_json::from_value::<C>(value)?;
+            components.add(component)?;
+            Ok(())
+        }
+
+        fn replace<C>(
+            manager: &mut EntityManager,
+            entity_key: &EntityKey,
+            value: Value,
+        ) -> Result<(), Error>
+        where
+            C: Component + DeserializeOwned + 'static,
+        {
+            let component = serde_json::from_value::<C>(value)?;
+            match manager.get_mut::<C>(entity_key) {
+                Some(mut existing) => *existing = component,
+                None => manager.add_component(entity_key, component)?,
+            }
+            Ok(())
+        }
+
+        let key = ComponentKey::new::<C>();
+        if self.components.contains_key(&key) || self.component_names.contains_key(name) {
+            return Err(Error::DuplicateRegistration(name.to_string()));
+        }
+
+        self.components.insert(
+            key,
+            Registration {
+                name: name.to_string(),
+                serialize: serialize::<C>,
+                deserialize: deserialize::<C>,
+                replace: replace::<C>,
+                map_entities,
+            },
+        );
+        self.component_names.insert(name.to_string(), key);
+        Ok(())
+    }
+
+    /// Registers a shared component by a stable type name.
+    pub fn register_shared<C, T>(&mut self, name: &str) -> Result<(), Error>
+    where
+        C: Component + Serialize + DeserializeOwned + 'static,
+        T: 'static,
+    {
+        fn deserialize<C>(value: Value) -> Result<Box<dyn Any>, Error>
+        where
+            C: DeserializeOwned + 'static,
+        {
+            Ok(Box::new(serde_json::from_value::<C>(value)?))
+        }
+
+        fn insert<C, T>(manager: &mut EntityManager, component: Box<dyn Any>) -> Result<(), Error>
+        where
+            C: Component + 'static,
+            T: 'static,
+        {
+            let component = *component.downcast::<C>().unwrap();
+            manager.add_shared_component::<C, T>(component)?;
+            Ok(())
+        }
+
+        let key = SharedComponentKey::new::<C, T>();
+        if self.shared_components.contains_key(&key)
+            || self.shared_component_names.contains_key(name)
+        {
+            return Err(Error::DuplicateRegistration(name.to_string()));
+        }
+
+        self.shared_components.insert(
+            key,
+            SharedRegistration {
+                name: name.to_string(),
+                serialize: serialize::<C>,
+                deserialize: deserialize::<C>,
+                insert: insert::<C, T>,
+            },
+        );
+        self.shared_component_names.insert(name.to_string(), key);
+        Ok(())
+    }
+}
+
+impl Default for ComponentRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct SceneData {
+    pub(super) shared_components: Map<String, Value>,
+    pub(super) entities: Vec<EntityData>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct EntityData {
+    pub(super) key: EntityKey,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) uuid: Option<String>,
+    pub(super) components: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(super) shared_components: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(super) children: Vec<EntityKey>,
+}
+
+/// Serializes all entities of an entity manager into JSON,
+/// including registered components, shared components, UUIDs and hierarchy links.
+pub fn serialize_world(
+    manager: &EntityManager,
+    registry: &ComponentRegistry,
+) -> Result<String, Error> {
+    let mut shared_components = Map::new();
+    for (key, item) in manager.shared_components.iter() {
+        let Some(registration) = registry.shared_components.get(key) else {
+            continue;
+        };
+        shared_components.insert(
+            registration.name.clone(),
+            (registration.serialize)(item.component.as_ref())?,
+        );
+    }
+
+    let mut entities = Vec::new();
+    for key in manager.entity_keys() {
+        let components =
+            registry.serialize_components(manager.entity_components_any(&key).unwrap())?;
+        // entity could not be created without any non-shared component
+        if components.is_empty() {
+            continue;
+        }
+
+        let shared_components = manager
+            .entity_archetype(&key)
+            .unwrap()
+            .shared_component_keys()
+            .iter()
+            .filter_map(|shared| registry.shared_component_name(shared))
+            .map(|name| name.to_string())
+            .collect();
+
+        entities.push(EntityData {
+            key,
+            uuid: manager.entity_uuid(&key).map(|uuid| uuid.to_string()),
+            components,
+            shared_components,
+            children: manager.children(&key).to_vec(),
+        });
+    }
+
+    // drops hierarchy links to entities not serialized
+    let keys = entities
+        .iter()
+        .map(|entity| entity.key)
+        .collect::<HashSet<_>>();
+    for entity in entities.iter_mut() {
+        entity.children.retain(|child| keys.contains(child));
+    }
+
+    let scene = SceneData {
+        shared_components,
+        entities,
+    };
+    Ok(serde_json::to_string(&scene)?)
+}
+
+/// Deserializes entities from JSON produced by [`serialize_world`] into an entity manager,
+/// in addition to existing entities.
+/// Returns keys of created entities, in the same order as they are serialized.
+///
+/// Entity keys are remapped to created entities, for hierarchy links
+/// and components registered by [`ComponentRegistry::register_with_entities`].
+/// Entity keys referring to entities not in the scene are left untouched.
+///
+/// The scene is validated before anything is created, including shared component values
+/// and hierarchy links, which must give each entity at most one parent without cycles.
+/// So the entity manager is unchanged if the scene is invalid.
+/// Shared components are restored by [`EntityManager::add_shared_component`],
+/// so they must not exist in the entity manager already.
+pub fn deserialize_world(
+    manager: &mut EntityManager,
+    registry: &ComponentRegistry,
+    json: &str,
+) -> Result<Vec<EntityKey>, Error> {
+    let SceneData {
+        shared_components,
+        entities,
+    } = serde_json::from_str::<SceneData>(json)?;
+
+    // validates before changing anything
+    let mut shared = Vec::with_capacity(shared_components.len());
+    for (name, value) in shared_components.iter() {
+        let Some(key) = registry.shared_component_names.get(name) else {
+            return Err(Error::UnregisteredComponent(name.clone()));
+        };
+        if manager.shared_components.contains_key(key) {
+            return Err(Error::Entity(EntityError::DuplicateSharedComponent(*key)));
+        }
+        let component = (registry.shared_components[key].deserialize)(value.clone())?;
+        shared.push((*key, component));
+    }
+    let mut keys = HashSet::new();
+    let mut uuids = HashSet::new();
+    let mut components = Vec::with_capacity(entities.len());
+    for entity in entities.iter() {
+        if !keys.insert(entity.key) {
+            return Err(Error::DuplicateEntity(entity.key));
+        }
+        if let Some(uuid) = entity.uuid.as_ref() {
+            let uuid = Uuid::parse_str(uuid).map_err(|_| Error::InvalidUuid(uuid.to_string()))?;
+            if !uuids.insert(uuid) || manager.entity_by_uuid(&uuid).is_some() {
+                return Err(Error::Entity(EntityError::DuplicateEntityUuid(uuid)));
+            }
+        }
+
+        let set = registry.deserialize_components(&entity.components, &entity.shared_components)?;
+        for name in entity.shared_components.iter() {
+            let key = registry.shared_component_key(name).unwrap();
+            if !shared_components.contains_key(name)
+                && !manager.shared_components.contains_key(&key)
+            {
+                return Err(Error::Entity(EntityError::NoSuchSharedComponent(key)));
+            }
+        }
+        if set.0.is_empty() {
+            return Err(Error::Entity(EntityError::EmptyComponents));
+        }
+        components.push(set);
+    }
+    let mut parents = HashMap::with_capacity(entities.len());
+    for entity in entities.iter() {
+        for child in entity.children.iter() {
+            if !keys.contains(child) {
+                return Err(Error::NoSuchEntity(*child));
+            }
+            if *child == entity.key {
+                return Err(Error::Entity(EntityError::CyclicHierarchy(
+                    *child, entity.key,
+                )));
+            }
+            if parents.insert(*child, entity.key).is_some() {
+                return Err(Error::MultipleParents(*child));
+            }
+        }
+    }
+    // each entity has at most one parent, so a cycle is found by walking up from any entity of it
+    for (child, parent) in parents.iter() {
+        let mut ancestor = *parent;
+        for _ in 0..parents.len() {
+            if ancestor == *child {
+                return Err(Error::Entity(EntityError::CyclicHierarchy(*child, *parent)));
+            }
+            match parents.get(&ancestor) {
+                Some(next) => ancestor = *next,
+                None => break,
+            }
+        }
+    }
+
+    for (key, component) in shared {
+        (registry.shared_components[&key].insert)(manager, component)?;
+    }
+
+    let mut mapping = HashMap::with_capacity(entities.len());
+    let mut created = Vec::with_capacity(entities.len());
+    for (entity, set) in entities.iter().zip(components) {
+        let key = match entity.uuid.as_ref() {
+            Some(uuid) => manager.create_entity_with_uuid(set, Uuid::parse_str(uuid).unwrap())?,
+            None => manager.create_entity(set)?,
+        };
+        mapping.insert(entity.key, key);
+        created.push(key);
+    }
+
+    // restores hierarchy links, keeping order of children
+    for entity in entities.iter() {
+        let parent = mapping[&entity.key];
+        for child in entity.children.iter() {
+            manager.set_parent(&mapping[child], &parent)?;
+        }
+    }
+
+    // remaps entity keys in components
+    let mut mapper = |key: EntityKey| mapping.get(&key).copied().unwrap_or(key);
+    for (entity, key) in entities.iter().zip(created.iter()) {
+        for name in entity.components.keys() {
+            registry.map_entities(manager, key, name, &mut mapper);
+        }
+    }
+
+    Ok(created)
+}
+
+pub enum Error {
+    DuplicateRegistration(String),
+    UnregisteredComponent(String),
+    DuplicateEntity(EntityKey),
+    NoSuchEntity(EntityKey),
+    InvalidUuid(String),
+    Json(serde_json::Error),
+    InvalidPrefab(String),
+    /// Entity is listed as a child of more than one entity in scene.
+    MultipleParents(EntityKey),
+    Entity(EntityError),
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl From<EntityError> for Error {
+    fn from(err: EntityError) -> Self {
+        Self::Entity(err)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::DuplicateRegistration(name) => {
+                write!(f, "component {name} registered already")
+            }
+            Error::UnregisteredComponent(name) => write!(f, "component {name} not registered"),
+            Error::DuplicateEntity(key) => write!(f, "duplicate entity {:?} in scene", key),
+            Error::NoSuchEntity(key) => write!(f, "entity {:?} not in scene", key),
+            Error::InvalidUuid(uuid) => write!(f, "invalid uuid {uuid}"),
+            Error::Json(err) => write!(f, "invalid scene json: {err}"),
+            Error::InvalidPrefab(msg) => write!(f, "invalid prefab: {msg}"),
+            Error::MultipleParents(key) => {
+                write!(f, "entity {:?} has multiple parents in scene", key)
+            }
+            Error::Entity(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Debug for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Position(f64, f64);
+
+    impl Component for Position {}
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Name(String);
+
+    impl Component for Name {}
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Target(EntityKey);
+
+    impl Component for Target {}
+
+    impl MapEntities for Target {
+        fn map_entities(&mut self, mapper: &mut dyn FnMut(EntityKey) -> EntityKey) {
+            self.0 = mapper(self.0);
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Gravity(f64);
+
+    impl Component for Gravity {}
+
+    struct World;
+
+    /// A component never serialized.
+    struct Cache;
+
+    impl Component for Cache {}
+
+    fn registry() -> ComponentRegistry {
+        let mut registry = ComponentRegistry::new();
+        registry.register::<Position>("position").unwrap();
+        registry.register::<Name>("name").unwrap();
+        registry.register_with_entities::<Target>("target").unwrap();
+        registry
+            .register_shared::<Gravity, World>("gravity")
+            .unwrap();
+        registry
+    }
+
+    fn spawn(manager: &mut EntityManager, name: &str) -> EntityKey {
+        let mut components = ComponentSet::with_component(Name(name.to_string()));
+        components.add(Position(0.0, 1.0)).unwrap();
+        manager.create_entity(components).unwrap()
+    }
+
+    fn find(manager: &EntityManager, name: &str) -> EntityKey {
+        manager
+            .entity_keys()
+            .into_iter()
+            .find(|key| manager.get::<Name>(key).map(|n| n.0.as_str()) == Some(name))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let registry = registry();
+        let mut manager = EntityManager::new();
+        manager
+            .add_shared_component::<Gravity, World>(Gravity(9.8))
+            .unwrap();
+        let root = spawn(&mut manager, "root");
+        let a = spawn(&mut manager, "a");
+        let b = spawn(&mut manager, "b");
+        let removed = spawn(&mut manager, "removed");
+        let c = spawn(&mut manager, "c");
+        manager.remove_entity(&removed).unwrap();
+        manager.set_parent(&b, &root).unwrap();
+        manager.set_parent(&a, &root).unwrap();
+        manager.set_parent(&c, &a).unwrap();
+        manager.add_component(&c, Target(b)).unwrap();
+        manager.add_component(&b, Cache).unwrap();
+        // neither serialized nor linked to
+        let cache = manager
+            .create_entity(ComponentSet::with_component(Cache))
+            .unwrap();
+        manager.set_parent(&cache, &b).unwrap();
+        let uuid = Uuid::new_v4();
+        manager.set_entity_uuid(&a, Some(uuid)).unwrap();
+        let mut components = ComponentSet::with_component(Name("shared".to_string()));
+        components
+            .1
+            .push(SharedComponentKey::new::<Gravity, World>());
+        manager.create_entity(components).unwrap();
+
+        let json = serialize_world(&manager, &registry).unwrap();
+
+        // loads into a manager having other entities, so that entity keys change
+        let mut loaded = EntityManager::new();
+        for _ in 0..3 {
+            spawn(&mut loaded, "existing");
+        }
+        let created = deserialize_world(&mut loaded, &registry, &json).unwrap();
+        assert_eq!(created.len(), 5);
+        assert_eq!(loaded.entity_keys().len(), 8);
+
+        let root = find(&loaded, "root");
+        let a = find(&loaded, "a");
+        let b = find(&loaded, "b");
+        let c = find(&loaded, "c");
+        assert_eq!(loaded.children(&root), &[b, a]);
+        assert_eq!(loaded.children(&a), &[c]);
+        assert_eq!(loaded.parent(&c), Some(a));
+        assert_eq!(loaded.get::<Target>(&c), Some(&Target(b)));
+        assert_eq!(loaded.get::<Position>(&c), Some(&Position(0.0, 1.0)));
+        assert!(!loaded.has_component::<Cache>(&b));
+        assert_eq!(loaded.entity_by_uuid(&uuid), Some(a));
+        let shared = find(&loaded, "shared");
+        assert!(loaded
+            .entity_archetype(&shared)
+            .unwrap()
+            .has_shared_component::<Gravity, World>());
+        assert!(loaded.has_shared_component::<Gravity, World>());
+
+        // serialization is stable across round trips
+        let mut first = EntityManager::new();
+        deserialize_world(&mut first, &registry, &json).unwrap();
+        let first_json = serialize_world(&first, &registry).unwrap();
+        let mut second = EntityManager::new();
+        deserialize_world(&mut second, &registry, &first_json).unwrap();
+        assert_eq!(serialize_world(&second, &registry).unwrap(), first_json);
+    }
+
+    #[test]
+    fn test_invalid_scenes() {
+        let registry = registry();
+        let mut manager = EntityManager::new();
+        let a = spawn(&mut manager, "a");
+        let uuid = Uuid::new_v4();
+        manager.set_entity_uuid(&a, Some(uuid)).unwrap();
+        let json = serialize_world(&manager, &registry).unwrap();
+
+        // uuid conflicts with the existing entity
+        assert!(matches!(
+            deserialize_world(&mut manager, &registry, &json),
+            Err(Error::Entity(EntityError::DuplicateEntityUuid(duplicate))) if duplicate == uuid
+        ));
+        assert_eq!(manager.entity_keys(), vec![a]);
+
+        let mut partial = ComponentRegistry::new();
+        partial.register::<Name>("name").unwrap();
+        let mut empty = EntityManager::new();
+        assert!(matches!(
+            deserialize_world(&mut empty, &partial, &json),
+            Err(Error::UnregisteredComponent(name)) if name == "position"
+        ));
+        assert!(empty.entity_keys().is_empty());
+
+        assert!(matches!(
+            deserialize_world(&mut empty, &registry, "{}"),
+            Err(Error::Json(_))
+        ));
+        assert!(matches!(
+            partial.register::<Name>("another"),
+            Err(Error::DuplicateRegistration(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_scene_leaves_manager_unchanged() {
+        let mut registry = registry();
+        registry.register_shared::<Name, World>("title").unwrap();
+        let mut source = EntityManager::new();
+        let a = spawn(&mut source, "a");
+        let b = spawn(&mut source, "b");
+        let c = spawn(&mut source, "c");
+        let json = serialize_world(&source, &registry).unwrap();
+
+        let mut manager = EntityManager::new();
+        let existing = spawn(&mut manager, "existing");
+        let mut load = |edit: &dyn Fn(&mut SceneData)| {
+            let mut scene = serde_json::from_str::<SceneData>(&json).unwrap();
+            scene
+                .shared_components
+                .insert("gravity".to_string(), serde_json::json!(9.8));
+            edit(&mut scene);
+            let result = deserialize_world(
+                &mut manager,
+                &registry,
+                &serde_json::to_string(&scene).unwrap(),
+            );
+            assert_eq!(manager.entity_keys(), vec![existing]);
+            assert!(!manager.has_shared_component::<Gravity, World>());
+            result
+        };
+        let link = |scene: &mut SceneData, parent: EntityKey, child: EntityKey| {
+            scene
+                .entities
+                .iter_mut()
+                .find(|entity| entity.key == parent)
+                .unwrap()
+                .children
+                .push(child);
+        };
+
+        // second shared component is malformed
+        assert!(matches!(
+            load(&|scene| {
+                scene
+                    .shared_components
+                    .insert("title".to_string(), serde_json::json!(42));
+            }),
+            Err(Error::Json(_))
+        ));
+        assert!(matches!(
+            load(&|scene| link(scene, a, a)),
+            Err(Error::Entity(EntityError::CyclicHierarchy(entity, parent))) if entity == a && parent == a
+        ));
+        assert!(matches!(
+            load(&|scene| {
+                link(scene, a, b);
+                link(scene, b, c);
+                link(scene, c, a);
+            }),
+            Err(Error::Entity(EntityError::CyclicHierarchy(_, _)))
+        ));
+        assert!(matches!(
+            load(&|scene| {
+                link(scene, a, c);
+                link(scene, b, c);
+            }),
+            Err(Error::MultipleParents(child)) if child == c
+        ));
+    }
+}
@@ -0,0 +1,232 @@
+use std::marker::PhantomData;
+
+use super::resource::Resource;
+
+/// A double-buffered event channel of event type `E`, stored as a [`Resource`].
+///
+/// Events survive for two [`Events::update`] calls,
+/// so a reader running once per frame never misses any event
+/// no matter it runs before or after the sender in a frame.
+/// [`App`](super::app::App) updates every event channel added by [`App::add_event`](super::app::App::add_event)
+/// at the end of each tick.
+pub struct Events<E> {
+    previous: Vec<(u64, E)>,
+    current: Vec<(u64, E)>,
+    next_id: u64,
+}
+
+impl<E> Events<E> {
+    /// Constructs a new empty event channel.
+    pub fn new() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Sends an event.
+    pub fn send(&mut self, event: E) {
+        self.current.push((self.next_id, event));
+        self.next_id += 1;
+    }
+
+    /// Swaps buffers, dropping events sent before last update.
+    pub fn update(&mut self) {
+        std::mem::swap(&mut self.previous, &mut self.current);
+        self.current.clear();
+    }
+
+    /// Returns the number of events retained.
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    /// Returns `true` if no event is retained.
+    pub fn is_empty(&self) -> bool {
+        self.previous.is_empty() && self.current.is_empty()
+    }
+
+    /// Returns an iterator over all retained events, ignoring any reader.
+    pub fn iter(&self) -> impl Iterator<Item = &E> {
+        self.previous
+            .iter()
+            .chain(self.current.iter())
+            .map(|(_, event)| event)
+    }
+
+    /// Drops all retained events.
+    pub fn clear(&mut self) {
+        self.previous.clear();
+        self.current.clear();
+    }
+}
+
+impl<E> Default for Events<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> Resource for Events<E> {}
+
+/// A reader of [`Events`] tracking its own cursor.
+///
+/// Each reader reads every event only once, so every system should keep its own reader.
+pub struct EventReader<E> {
+    cursor: u64,
+    _event: PhantomData<E>,
+}
+
+impl<E> EventReader<E> {
+    /// Constructs a new reader reading events sent from now on.
+    pub fn new(events: &Events<E>) -> Self {
+        Self {
+            cursor: events.next_id,
+            _event: PhantomData,
+        }
+    }
+
+    /// Reads events sent since last read, oldest first.
+    pub fn read<'a>(&mut self, events: &'a Events<E>) -> impl Iterator<Item = &'a E> + 'a {
+        let cursor = self.cursor;
+        self.cursor = events.next_id;
+        events
+            .previous
+            .iter()
+            .chain(events.current.iter())
+            .filter(move |(id, _)| *id >= cursor)
+            .map(|(_, event)| event)
+    }
+
+    /// Returns the number of unread events.
+    pub fn len(&self, events: &Events<E>) -> usize {
+        events
+            .previous
+            .iter()
+            .chain(events.current.iter())
+            .filter(|(id, _)| *id >= self.cursor)
+            .count()
+    }
+
+    /// Returns `true` if there is no unread event.
+    pub fn is_empty(&self, events: &Events<E>) -> bool {
+        self.len(events) == 0
+    }
+}
+
+impl<E> Default for EventReader<E> {
+    /// Constructs a new reader reading all events retained in the channel.
+    fn default() -> Self {
+        Self {
+            cursor: 0,
+            _event: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::anewthing::{
+        app::App,
+        ecs::command::Commands,
+        renderer::Renderer,
+        resource::FrameTime,
+        system::{Stage, SystemDescriptor},
+    };
+
+    struct NoopRenderer;
+
+    impl Renderer for NoopRenderer {
+        fn render(&mut self, _: &App, _: f64) {}
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Clicked(f64);
+
+    #[test]
+    fn test_events() {
+        let mut events = Events::new();
+        events.send(0);
+        let mut early = EventReader::default();
+        let mut reader = EventReader::new(&events);
+
+        events.send(1);
+        events.send(2);
+        assert_eq!(reader.len(&events), 2);
+        assert_eq!(
+            reader.read(&events).copied().collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert!(reader.is_empty(&events));
+
+        // events survive one update
+        events.update();
+        events.send(3);
+        assert_eq!(reader.read(&events).copied().collect::<Vec<_>>(), vec![3]);
+        assert_eq!(
+            early.read(&events).copied().collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+
+        // unread events are dropped after two updates
+        events.send(4);
+        let mut late = EventReader::default();
+        events.update();
+        events.update();
+        assert_eq!(late.read(&events).count(), 0);
+        assert_eq!(reader.read(&events).count(), 0);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_events_in_app() {
+        let mut app = App::new(NoopRenderer);
+        app.add_event::<Clicked>();
+        // sent before any system runs
+        app.send_event(Clicked(-1.0));
+
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let received_cloned = Rc::clone(&received);
+        let mut reader = EventReader::default();
+        // reader runs before sender, receiving events of the previous frame
+        app.add_system(
+            Stage::PreUpdate,
+            SystemDescriptor::new("reader", move |app: &mut App, _: &mut Commands, _: f64| {
+                let events = app.events::<Clicked>().unwrap();
+                received_cloned
+                    .borrow_mut()
+                    .extend(reader.read(events).copied());
+            }),
+        )
+        .unwrap();
+        app.add_system(
+            Stage::Update,
+            SystemDescriptor::new("sender", |app: &mut App, _: &mut Commands, _: f64| {
+                let timestamp = app.resource::<FrameTime>().unwrap().timestamp();
+                app.send_event(Clicked(timestamp));
+            }),
+        )
+        .unwrap();
+
+        app.tick(0.0);
+        app.tick(16.0);
+        app.tick(32.0);
+
+        assert_eq!(
+            *received.borrow(),
+            vec![Clicked(-1.0), Clicked(0.0), Clicked(16.0)]
+        );
+        let frame_time = app.resource::<FrameTime>().unwrap();
+        assert_eq!(frame_time.frame(), 3);
+        assert_eq!(frame_time.delta(), 16.0);
+        // events of the last frame are kept for readers running before sender in the next frame
+        assert_eq!(
+            app.events::<Clicked>().unwrap().iter().collect::<Vec<_>>(),
+            vec![&Clicked(32.0)]
+        );
+    }
+}
@@ -2,8 +2,12 @@ pub mod app;
 pub mod buffering;
 pub mod clock;
 pub mod ecs;
+pub mod event;
 pub mod plugin;
 pub mod renderer;
+pub mod resource;
+pub mod system;
 pub mod texturing;
+pub mod transform;
 #[cfg(feature = "web")]
 pub mod web;
@@ -0,0 +1,128 @@
+use std::any::{Any, TypeId};
+
+use hashbrown::HashMap;
+
+/// A global singleton stored in [`App`](super::app::App), keyed by its type.
+pub trait Resource {}
+
+/// A type-keyed storage of [`Resource`]s.
+pub struct Resources(HashMap<TypeId, Box<dyn Any>>);
+
+impl Resources {
+    /// Constructs a new empty resource storage.
+    pub fn new() -> Self {
+        Self(HashMap::new())
+    }
+
+    /// Returns `true` if a resource of type `R` exists.
+    pub fn contains<R>(&self) -> bool
+    where
+        R: Resource + 'static,
+    {
+        self.0.contains_key(&TypeId::of::<R>())
+    }
+
+    /// Returns a resource of type `R`.
+    pub fn get<R>(&self) -> Option<&R>
+    where
+        R: Resource + 'static,
+    {
+        self.0
+            .get(&TypeId::of::<R>())
+            .map(|r| r.downcast_ref::<R>().unwrap())
+    }
+
+    /// Returns a resource of type `R` mutably.
+    pub fn get_mut<R>(&mut self) -> Option<&mut R>
+    where
+        R: Resource + 'static,
+    {
+        self.0
+            .get_mut(&TypeId::of::<R>())
+            .map(|r| r.downcast_mut::<R>().unwrap())
+    }
+
+    /// Inserts a resource, returning the previous one of the same type if any.
+    pub fn insert<R>(&mut self, resource: R) -> Option<R>
+    where
+        R: Resource + 'static,
+    {
+        self.0
+            .insert(TypeId::of::<R>(), Box::new(resource))
+            .map(|r| *r.downcast::<R>().unwrap())
+    }
+
+    /// Returns a resource of type `R` mutably, inserting one by `f` if not exists.
+    pub fn get_or_insert_with<R, F>(&mut self, f: F) -> &mut R
+    where
+        R: Resource + 'static,
+        F: FnOnce() -> R,
+    {
+        self.0
+            .entry(TypeId::of::<R>())
+            .or_insert_with(|| Box::new(f()))
+            .downcast_mut::<R>()
+            .unwrap()
+    }
+
+    /// Removes a resource of type `R`.
+    pub fn remove<R>(&mut self) -> Option<R>
+    where
+        R: Resource + 'static,
+    {
+        self.0
+            .remove(&TypeId::of::<R>())
+            .map(|r| *r.downcast::<R>().unwrap())
+    }
+}
+
+impl Default for Resources {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Time of current frame, updated by [`App`](super::app::App) at the beginning of each tick.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameTime {
+    timestamp: f64,
+    delta: f64,
+    frame: u64,
+}
+
+impl FrameTime {
+    pub(super) fn new() -> Self {
+        Self {
+            timestamp: 0.0,
+            delta: 0.0,
+            frame: 0,
+        }
+    }
+
+    pub(super) fn advance(&mut self, timestamp: f64) {
+        self.delta = if self.frame == 0 {
+            0.0
+        } else {
+            timestamp - self.timestamp
+        };
+        self.timestamp = timestamp;
+        self.frame += 1;
+    }
+
+    /// Returns timestamp of current frame.
+    pub fn timestamp(&self) -> f64 {
+        self.timestamp
+    }
+
+    /// Returns elapsed time since previous frame. Always `0.0` in the first frame.
+    pub fn delta(&self) -> f64 {
+        self.delta
+    }
+
+    /// Returns the number of frames ticked, including current one.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+}
+
+impl Resource for FrameTime {}
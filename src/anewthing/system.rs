@@ -0,0 +1,568 @@
+use std::fmt::{Debug, Display};
+
+use hashbrown::HashSet;
+use log::warn;
+
+use super::{app::App, ecs::command::Commands};
+
+/// Stages a [`System`] could be scheduled in.
+///
+/// [`Stage::Startup`] runs only once, right before the first tick,
+/// startup systems added after that never run.
+/// The other stages run in declaration order on every tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    Startup,
+    PreUpdate,
+    Update,
+    PostUpdate,
+    Render,
+}
+
+impl Stage {
+    /// All stages in running order.
+    pub const ALL: [Stage; 5] = [
+        Stage::Startup,
+        Stage::PreUpdate,
+        Stage::Update,
+        Stage::PostUpdate,
+        Stage::Render,
+    ];
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// A system running game logic against an [`App`].
+///
+/// Structural changes recorded into [`Commands`] are applied when the stage finishes,
+/// so they could be recorded while iterating queries of the entity manager.
+pub trait System {
+    fn run(&mut self, app: &mut App, commands: &mut Commands, timestamp: f64);
+}
+
+impl<F> System for F
+where
+    F: FnMut(&mut App, &mut Commands, f64),
+{
+    fn run(&mut self, app: &mut App, commands: &mut Commands, timestamp: f64) {
+        self(app, commands, timestamp)
+    }
+}
+
+/// A system with a unique label, ordering constraints and run conditions.
+pub struct SystemDescriptor {
+    label: String,
+    system: Box<dyn System>,
+    before: Vec<String>,
+    after: Vec<String>,
+    conditions: Vec<Box<dyn FnMut(&App) -> bool>>,
+    last_run: u64,
+}
+
+impl SystemDescriptor {
+    /// Constructs a new system descriptor with a unique label.
+    pub fn new<L, S>(label: L, system: S) -> Self
+    where
+        L: Into<String>,
+        S: System + 'static,
+    {
+        Self {
+            label: label.into(),
+            system: Box::new(system),
+            before: Vec::new(),
+            after: Vec::new(),
+            conditions: Vec::new(),
+            last_run: 0,
+        }
+    }
+
+    /// Runs this system before system with `label`.
+    /// Constraint is ignored if no such system in the same stage.
+    pub fn before<L>(mut self, label: L) -> Self
+    where
+        L: Into<String>,
+    {
+        self.before.push(label.into());
+        self
+    }
+
+    /// Runs this system after system with `label`.
+    /// Constraint is ignored if no such system in the same stage.
+    pub fn after<L>(mut self, label: L) -> Self
+    where
+        L: Into<String>,
+    {
+        self.after.push(label.into());
+        self
+    }
+
+    /// Runs this system only if `condition` returns `true`.
+    /// Multiple conditions are combined and all of them have to be satisfied.
+    pub fn run_if<F>(mut self, condition: F) -> Self
+    where
+        F: FnMut(&App) -> bool + 'static,
+    {
+        self.conditions.push(Box::new(condition));
+        self
+    }
+
+    /// Returns label of this system.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Returns the system.
+    pub fn system(&self) -> &dyn System {
+        self.system.as_ref()
+    }
+
+    /// Returns the system mutably.
+    pub fn system_mut(&mut self) -> &mut dyn System {
+        self.system.as_mut()
+    }
+
+    fn should_run(&mut self, app: &App) -> bool {
+        self.conditions.iter_mut().all(|condition| condition(app))
+    }
+}
+
+struct StageItem {
+    systems: Vec<SystemDescriptor>,
+    order: Vec<usize>,
+}
+
+impl StageItem {
+    fn new() -> Self {
+        Self {
+            systems: Vec::new(),
+            order: Vec::new(),
+        }
+    }
+}
+
+/// System scheduler running systems stage by stage.
+///
+/// Systems in the same stage are sorted by their ordering constraints.
+/// Systems without constraints between each other run in insertion order.
+pub struct SystemScheduler {
+    stages: [StageItem; 5],
+    started: bool,
+}
+
+impl SystemScheduler {
+    /// Constructs a new system scheduler.
+    pub fn new() -> Self {
+        Self {
+            stages: [
+                StageItem::new(),
+                StageItem::new(),
+                StageItem::new(),
+                StageItem::new(),
+                StageItem::new(),
+            ],
+            started: false,
+        }
+    }
+
+    /// Returns `true` if startup stage has already run.
+    pub fn started(&self) -> bool {
+        self.started
+    }
+
+    /// Returns `true` if a system with `label` is scheduled.
+    pub fn has_system(&self, label: &str) -> bool {
+        self.stages
+            .iter()
+            .any(|stage| stage.systems.iter().any(|system| system.label == label))
+    }
+
+    /// Returns labels of systems in a stage, in running order.
+    pub fn labels(&self, stage: Stage) -> Vec<&str> {
+        let stage = &self.stages[stage.index()];
+        stage
+            .order
+            .iter()
+            .map(|index| stage.systems[*index].label.as_str())
+            .collect()
+    }
+
+    /// Adds a system to a stage.
+    ///
+    /// Returns an error and leaves scheduler unchanged
+    /// if label is duplicated or ordering constraints are cyclic.
+    pub fn add_system(&mut self, stage: Stage, system: SystemDescriptor) -> Result<(), Error> {
+        if self.has_system(&system.label) {
+            return Err(Error::DuplicateSystem(system.label));
+        }
+
+        let item = &mut self.stages[stage.index()];
+        item.systems.push(system);
+        match sort_systems(&item.systems) {
+            Ok(order) => {
+                item.order = order;
+                Ok(())
+            }
+            Err(labels) => {
+                item.systems.pop();
+                Err(Error::CyclicOrdering(stage, labels))
+            }
+        }
+    }
+
+    /// Removes a system by label.
+    pub fn remove_system(&mut self, label: &str) -> Option<SystemDescriptor> {
+        for item in self.stages.iter_mut() {
+            let Some(index) = item.systems.iter().position(|system| system.label == label) else {
+                continue;
+            };
+
+            let removed = item.systems.remove(index);
+            // removing a system never introduces a cycle
+            item.order = sort_systems(&item.systems).unwrap();
+            return Some(removed);
+        }
+
+        None
+    }
+
+    /// Runs startup stage if not yet run, and then all the other stages in order.
+    pub fn run(&mut self, app: &mut App, timestamp: f64) {
+        if !self.started {
+            self.run_stage(Stage::Startup, app, timestamp);
+            self.started = true;
+        }
+
+        for stage in [
+            Stage::PreUpdate,
+            Stage::Update,
+            Stage::PostUpdate,
+            Stage::Render,
+        ] {
+            self.run_stage(stage, app, timestamp);
+        }
+    }
+
+    /// Runs a single stage.
+    ///
+    /// Change tick of the entity manager is maintained for each system,
+    /// so change detection queries in a system see changes since the system last ran.
+    ///
+    /// Systems in the stage share a command buffer, which is applied after all of them run.
+    pub fn run_stage(&mut self, stage: Stage, app: &mut App, timestamp: f64) {
+        let mut commands = Commands::new(app.entity_manager());
+        let item = &mut self.stages[stage.index()];
+        for index in item.order.iter() {
+            let system = &mut item.systems[*index];
+            if system.should_run(app) {
+                app.entity_manager_mut()
+                    .set_last_change_tick(system.last_run);
+                system.system.run(app, &mut commands, timestamp);
+                system.last_run = app.entity_manager_mut().increment_change_tick();
+            }
+        }
+
+        for err in commands.apply(app.entity_manager_mut()) {
+            warn!(target: "SystemScheduler", "failed to apply command in stage {:?}: {err}", stage);
+        }
+    }
+
+    /// Moves all systems from another scheduler into this one.
+    /// Returns errors of systems that could not be added.
+    pub(super) fn merge(&mut self, other: SystemScheduler) -> Vec<Error> {
+        let mut errors = Vec::new();
+        for (stage, item) in Stage::ALL.iter().copied().zip(other.stages) {
+            for system in item.systems {
+                if let Err(err) = self.add_system(stage, system) {
+                    errors.push(err);
+                }
+            }
+        }
+        errors
+    }
+}
+
+/// Sorts systems topologically by their ordering constraints.
+/// Ties are broken by insertion order, so sorting is stable.
+///
+/// Returns labels of systems involved in a cycle if constraints are cyclic.
+fn sort_systems(systems: &[SystemDescriptor]) -> Result<Vec<usize>, Vec<String>> {
+    let index_of = |label: &str| systems.iter().position(|system| system.label == label);
+
+    let mut edges: HashSet<(usize, usize)> = HashSet::new();
+    for (index, system) in systems.iter().enumerate() {
+        for label in &system.before {
+            if let Some(other) = index_of(label) {
+                edges.insert((index, other));
+            }
+        }
+        for label in &system.after {
+            if let Some(other) = index_of(label) {
+                edges.insert((other, index));
+            }
+        }
+    }
+
+    let mut in_degrees = vec![0usize; systems.len()];
+    for (_, to) in edges.iter() {
+        in_degrees[*to] += 1;
+    }
+
+    let mut sorted = Vec::with_capacity(systems.len());
+    let mut visited = vec![false; systems.len()];
+    while sorted.len() < systems.len() {
+        let Some(next) = (0..systems.len()).find(|i| !visited[*i] && in_degrees[*i] == 0) else {
+            let labels = (0..systems.len())
+                .filter(|i| !visited[*i])
+                .map(|i| systems[i].label.clone())
+                .collect();
+            return Err(labels);
+        };
+
+        visited[next] = true;
+        sorted.push(next);
+        for (_, to) in edges.iter().filter(|(from, _)| *from == next) {
+            in_degrees[*to] -= 1;
+        }
+    }
+
+    Ok(sorted)
+}
+
+pub enum Error {
+    DuplicateSystem(String),
+    CyclicOrdering(Stage, Vec<String>),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::DuplicateSystem(label) => write!(f, "duplicate system {label}"),
+            Error::CyclicOrdering(stage, labels) => write!(
+                f,
+                "cyclic ordering in stage {:?} among systems {}",
+                stage,
+                labels.join(", ")
+            ),
+        }
+    }
+}
+
+impl Debug for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::anewthing::{
+        ecs::{
+            component::{Component, ComponentSet},
+            query::{QuerySimple, With},
+        },
+        renderer::Renderer,
+    };
+
+    struct RecordingRenderer(Rc<RefCell<Vec<String>>>);
+
+    impl Renderer for RecordingRenderer {
+        fn render(&mut self, _: &App, _: f64) {
+            self.0.borrow_mut().push("render".to_string());
+        }
+    }
+
+    fn record(label: &'static str, records: &Rc<RefCell<Vec<String>>>) -> SystemDescriptor {
+        let records = Rc::clone(records);
+        SystemDescriptor::new(label, move |_: &mut App, _: &mut Commands, _: f64| {
+            records.borrow_mut().push(label.to_string())
+        })
+    }
+
+    #[test]
+    fn test_stages() {
+        let records = Rc::new(RefCell::new(Vec::new()));
+        let mut app = App::new(RecordingRenderer(Rc::clone(&records)));
+        app.add_system(Stage::Render, record("render_system", &records))
+            .unwrap();
+        app.add_system(Stage::PostUpdate, record("post_update", &records))
+            .unwrap();
+        app.add_system(Stage::Update, record("update", &records))
+            .unwrap();
+        app.add_system(Stage::PreUpdate, record("pre_update", &records))
+            .unwrap();
+        app.add_system(Stage::Startup, record("startup", &records))
+            .unwrap();
+
+        app.tick(0.0);
+        app.tick(16.0);
+
+        assert_eq!(
+            *records.borrow(),
+            vec![
+                "startup",
+                "pre_update",
+                "update",
+                "post_update",
+                "render_system",
+                "render",
+                "pre_update",
+                "update",
+                "post_update",
+                "render_system",
+                "render",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ordering() {
+        let records = Rc::new(RefCell::new(Vec::new()));
+        let mut app = App::new(RecordingRenderer(Rc::clone(&records)));
+        app.add_system(Stage::Update, record("c", &records).after("b"))
+            .unwrap();
+        app.add_system(Stage::Update, record("a", &records))
+            .unwrap();
+        app.add_system(Stage::Update, record("b", &records).before("a"))
+            .unwrap();
+        app.add_system(Stage::Update, record("d", &records).after("missing"))
+            .unwrap();
+
+        assert_eq!(
+            app.scheduler().labels(Stage::Update),
+            vec!["b", "c", "a", "d"]
+        );
+
+        app.tick(0.0);
+        assert_eq!(*records.borrow(), vec!["b", "c", "a", "d", "render"]);
+    }
+
+    #[test]
+    fn test_invalid_systems() {
+        let records = Rc::new(RefCell::new(Vec::new()));
+        let mut app = App::new(RecordingRenderer(Rc::clone(&records)));
+        app.add_system(Stage::Update, record("a", &records).before("b"))
+            .unwrap();
+        app.add_system(Stage::Update, record("b", &records).before("c"))
+            .unwrap();
+
+        assert!(matches!(
+            app.add_system(Stage::Update, record("c", &records).before("a")),
+            Err(Error::CyclicOrdering(Stage::Update, _))
+        ));
+        assert!(matches!(
+            app.add_system(Stage::PostUpdate, record("a", &records)),
+            Err(Error::DuplicateSystem(_))
+        ));
+        // constraints across stages do not count
+        app.add_system(Stage::PostUpdate, record("c", &records).before("a"))
+            .unwrap();
+
+        assert_eq!(app.scheduler().labels(Stage::Update), vec!["a", "b"]);
+        assert!(app.remove_system("a").is_some());
+        assert!(app.remove_system("a").is_none());
+        assert_eq!(app.scheduler().labels(Stage::Update), vec!["b"]);
+    }
+
+    #[test]
+    fn test_run_conditions() {
+        let records = Rc::new(RefCell::new(Vec::new()));
+        let mut app = App::new(RecordingRenderer(Rc::clone(&records)));
+        let enabled = Rc::new(RefCell::new(false));
+        let enabled_cloned = Rc::clone(&enabled);
+        app.add_system(
+            Stage::Update,
+            record("conditional", &records).run_if(move |_| *enabled_cloned.borrow()),
+        )
+        .unwrap();
+        app.add_system(
+            Stage::Update,
+            record("never", &records).run_if(|_| true).run_if(|_| false),
+        )
+        .unwrap();
+
+        app.tick(0.0);
+        *enabled.borrow_mut() = true;
+        app.tick(16.0);
+
+        assert_eq!(*records.borrow(), vec!["render", "conditional", "render"]);
+    }
+
+    #[test]
+    fn test_add_system_while_ticking() {
+        let records = Rc::new(RefCell::new(Vec::new()));
+        let mut app = App::new(RecordingRenderer(Rc::clone(&records)));
+        let inner = record("inner", &records);
+        let mut inner = Some(inner);
+        app.add_system(
+            Stage::Startup,
+            SystemDescriptor::new("spawner", move |app: &mut App, _: &mut Commands, _: f64| {
+                app.add_system(Stage::Update, inner.take().unwrap())
+                    .unwrap();
+            }),
+        )
+        .unwrap();
+
+        app.tick(0.0);
+        app.tick(16.0);
+
+        assert_eq!(*records.borrow(), vec!["render", "inner", "render"]);
+    }
+
+    #[test]
+    fn test_commands() {
+        #[derive(Debug, PartialEq)]
+        struct Counter(u32);
+
+        impl Component for Counter {}
+
+        let records = Rc::new(RefCell::new(Vec::new()));
+        let mut app = App::new(RecordingRenderer(Rc::clone(&records)));
+        let spawned = Rc::new(RefCell::new(Vec::new()));
+        let spawned_cloned = Rc::clone(&spawned);
+        app.add_system(
+            Stage::Update,
+            SystemDescriptor::new(
+                "spawner",
+                move |app: &mut App, commands: &mut Commands, _: f64| {
+                    for counter in <With<Counter>>::query_simple(app.entity_manager_mut()) {
+                        let key =
+                            commands.spawn(ComponentSet::with_component(Counter(counter.0 + 1)));
+                        spawned_cloned.borrow_mut().push(key);
+                    }
+                },
+            ),
+        )
+        .unwrap();
+        app.add_system(
+            Stage::Update,
+            SystemDescriptor::new(
+                "checker",
+                |app: &mut App, commands: &mut Commands, _: f64| {
+                    // commands are not applied until stage finishes
+                    assert_eq!(commands.len(), app.entity_manager().entity_keys().len());
+                },
+            )
+            .after("spawner"),
+        )
+        .unwrap();
+        app.entity_manager_mut()
+            .create_entity(ComponentSet::with_component(Counter(0)))
+            .unwrap();
+
+        app.tick(0.0);
+        app.tick(16.0);
+
+        let manager = app.entity_manager();
+        assert_eq!(manager.entity_keys().len(), 4);
+        let spawned = spawned.borrow();
+        assert_eq!(spawned.len(), 3);
+        assert_eq!(manager.get::<Counter>(&spawned[0]), Some(&Counter(1)));
+    }
+}
@@ -96,6 +96,43 @@ impl Texturing {
         self.push_with_params(data, level, None, None, None, None, None, None, None)
     }
 
+    /// Pushes texture data into a face of a cube map texture.
+    pub fn push_cube_map_face<T>(&self, data: T, cube_map_face: TextureCubeMapFace, level: usize)
+    where
+        T: TextureData + 'static,
+    {
+        self.push_with_params(
+            data,
+            level,
+            Some(cube_map_face),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    /// Pushes texture data into a 3d texture or a 2d array texture,
+    /// with depth or array length of the data.
+    pub fn push_volume<T>(&self, data: T, level: usize, depth_or_len: usize)
+    where
+        T: TextureData + 'static,
+    {
+        self.push_with_params(
+            data,
+            level,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(depth_or_len),
+        )
+    }
+
     /// Pushes texture data into the texture with byte offset indicating where to start replacing data.
     fn push_with_params<T>(
         &self,
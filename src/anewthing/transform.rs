@@ -0,0 +1,300 @@
+use log::warn;
+use nalgebra::{Matrix4, UnitQuaternion, Vector3};
+
+use super::{
+    app::App,
+    ecs::{command::Commands, component::Component, entity::EntityKey, manager::EntityManager},
+    plugin::Plugin,
+    system::{Stage, SystemDescriptor},
+};
+
+/// Local transformation of an entity, relative to its parent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    translation: Vector3<f64>,
+    rotation: UnitQuaternion<f64>,
+    scale: Vector3<f64>,
+}
+
+impl Transform {
+    /// Constructs a new identity transformation.
+    pub fn new() -> Self {
+        Self {
+            translation: Vector3::zeros(),
+            rotation: UnitQuaternion::identity(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    /// Constructs a new transformation with translation only.
+    pub fn with_translation(translation: Vector3<f64>) -> Self {
+        Self {
+            translation,
+            ..Self::new()
+        }
+    }
+
+    /// Constructs a new transformation with translation, rotation and scale.
+    pub fn with_translation_rotation_scale(
+        translation: Vector3<f64>,
+        rotation: UnitQuaternion<f64>,
+        scale: Vector3<f64>,
+    ) -> Self {
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn translation(&self) -> &Vector3<f64> {
+        &self.translation
+    }
+
+    pub fn rotation(&self) -> &UnitQuaternion<f64> {
+        &self.rotation
+    }
+
+    pub fn scale(&self) -> &Vector3<f64> {
+        &self.scale
+    }
+
+    pub fn set_translation(&mut self, translation: Vector3<f64>) {
+        self.translation = translation;
+    }
+
+    pub fn set_rotation(&mut self, rotation: UnitQuaternion<f64>) {
+        self.rotation = rotation;
+    }
+
+    pub fn set_scale(&mut self, scale: Vector3<f64>) {
+        self.scale = scale;
+    }
+
+    /// Composes the local matrix, scaling first, then rotating and translating at last.
+    pub fn matrix(&self) -> Matrix4<f64> {
+        Matrix4::new_translation(&self.translation)
+            * self.rotation.to_homogeneous()
+            * Matrix4::new_nonuniform_scaling(&self.scale)
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Component for Transform {}
+
+/// World transformation of an entity, computed by [`propagate_transforms`].
+///
+/// Entities with [`Transform`] receive this component on the first propagation,
+/// spawning entities with it beforehand saves an archetype move.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlobalTransform(Matrix4<f64>);
+
+impl GlobalTransform {
+    /// Returns the world matrix.
+    pub fn matrix(&self) -> &Matrix4<f64> {
+        &self.0
+    }
+
+    /// Returns the world translation.
+    pub fn translation(&self) -> Vector3<f64> {
+        self.0.column(3).xyz()
+    }
+}
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        Self(Matrix4::identity())
+    }
+}
+
+impl Component for GlobalTransform {}
+
+/// Propagates transformations through the hierarchy depth-first,
+/// computing [`GlobalTransform`] of every entity having a [`Transform`].
+///
+/// An entity without [`Transform`] passes the world matrix of its parent to its children as it is.
+/// [`GlobalTransform`] is marked as changed only when the world matrix actually changes.
+pub fn propagate_transforms(manager: &mut EntityManager) {
+    let mut stack = manager
+        .entity_keys()
+        .into_iter()
+        .filter(|key| manager.parent(key).is_none())
+        .map(|key| (key, Matrix4::identity()))
+        .collect::<Vec<(EntityKey, Matrix4<f64>)>>();
+
+    while let Some((key, parent_matrix)) = stack.pop() {
+        let matrix = match manager.get::<Transform>(&key) {
+            Some(transform) => {
+                let matrix = parent_matrix * transform.matrix();
+                match manager.get_mut::<GlobalTransform>(&key) {
+                    Some(mut global) => {
+                        if global.0 != matrix {
+                            global.0 = matrix;
+                        }
+                    }
+                    None => {
+                        let _ = manager.add_component(&key, GlobalTransform(matrix));
+                    }
+                }
+                matrix
+            }
+            None => parent_matrix,
+        };
+
+        stack.extend(
+            manager
+                .children(&key)
+                .iter()
+                .rev()
+                .map(|child| (*child, matrix)),
+        );
+    }
+}
+
+/// A plugin propagating transformations in [`Stage::PostUpdate`],
+/// after all transformations are updated in [`Stage::Update`].
+pub struct TransformPlugin;
+
+impl TransformPlugin {
+    /// Label of the transformation propagation system.
+    pub const SYSTEM_LABEL: &'static str = "propagate_transforms";
+}
+
+impl Plugin for TransformPlugin {
+    fn plugin(&mut self, app: &mut App) {
+        let system = SystemDescriptor::new(
+            Self::SYSTEM_LABEL,
+            |app: &mut App, _: &mut Commands, _: f64| {
+                propagate_transforms(app.entity_manager_mut())
+            },
+        );
+        if let Err(err) = app.add_system(Stage::PostUpdate, system) {
+            warn!(target: "TransformPlugin", "failed to add system: {err}");
+        }
+    }
+
+    fn plugout(&mut self, app: &mut App) {
+        app.remove_system(Self::SYSTEM_LABEL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anewthing::ecs::{
+        component::ComponentSet,
+        query::{Changed, QuerySimple},
+    };
+
+    fn spawn(manager: &mut EntityManager, x: f64) -> EntityKey {
+        let mut components =
+            ComponentSet::with_component(Transform::with_translation(Vector3::new(x, 0.0, 0.0)));
+        components.add(GlobalTransform::default()).unwrap();
+        manager.create_entity(components).unwrap()
+    }
+
+    fn world_x(manager: &EntityManager, key: &EntityKey) -> f64 {
+        manager.get::<GlobalTransform>(key).unwrap().translation().x
+    }
+
+    #[test]
+    fn test_propagate_transforms() {
+        let mut manager = EntityManager::new();
+        let root = spawn(&mut manager, 1.0);
+        let a = spawn(&mut manager, 2.0);
+        let b = spawn(&mut manager, 4.0);
+        // an entity without any transformation
+        let group = manager
+            .create_entity(ComponentSet::with_component(GlobalTransform::default()))
+            .unwrap();
+        let c = manager
+            .create_entity(ComponentSet::with_component(Transform::with_translation(
+                Vector3::new(8.0, 0.0, 0.0),
+            )))
+            .unwrap();
+        manager.set_parent(&a, &root).unwrap();
+        manager.set_parent(&b, &a).unwrap();
+        manager.set_parent(&group, &b).unwrap();
+        manager.set_parent(&c, &group).unwrap();
+
+        propagate_transforms(&mut manager);
+        assert_eq!(world_x(&manager, &root), 1.0);
+        assert_eq!(world_x(&manager, &a), 3.0);
+        assert_eq!(world_x(&manager, &b), 7.0);
+        // global transform is added on demand, passing through entity without transformation
+        assert_eq!(world_x(&manager, &c), 15.0);
+        assert_eq!(world_x(&manager, &group), 0.0);
+
+        // rotation and scale of parent apply to children
+        manager
+            .get_mut::<Transform>(&root)
+            .unwrap()
+            .set_scale(Vector3::new(2.0, 2.0, 2.0));
+        manager
+            .get_mut::<Transform>(&a)
+            .unwrap()
+            .set_rotation(UnitQuaternion::from_axis_angle(
+                &Vector3::z_axis(),
+                std::f64::consts::FRAC_PI_2,
+            ));
+        propagate_transforms(&mut manager);
+        let world = manager.get::<GlobalTransform>(&b).unwrap().translation();
+        assert!((world - Vector3::new(5.0, 8.0, 0.0)).norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_reparent_and_remove() {
+        let mut manager = EntityManager::new();
+        let a = spawn(&mut manager, 1.0);
+        let b = spawn(&mut manager, 10.0);
+        let c = spawn(&mut manager, 100.0);
+        let d = spawn(&mut manager, 1000.0);
+        manager.set_parent(&c, &a).unwrap();
+        manager.set_parent(&d, &c).unwrap();
+        propagate_transforms(&mut manager);
+        assert_eq!(world_x(&manager, &d), 1101.0);
+
+        manager.set_parent(&c, &b).unwrap();
+        propagate_transforms(&mut manager);
+        assert_eq!(world_x(&manager, &c), 110.0);
+        assert_eq!(world_x(&manager, &d), 1110.0);
+
+        manager.remove_parent(&c).unwrap();
+        propagate_transforms(&mut manager);
+        assert_eq!(world_x(&manager, &d), 1100.0);
+
+        manager.set_parent(&c, &a).unwrap();
+        manager.remove_entity_recursive(&a).unwrap();
+        assert_eq!(manager.entity_keys(), vec![b]);
+        propagate_transforms(&mut manager);
+        assert_eq!(world_x(&manager, &b), 10.0);
+    }
+
+    #[test]
+    fn test_unchanged_global_transforms() {
+        let mut manager = EntityManager::new();
+        let a = spawn(&mut manager, 1.0);
+        let b = spawn(&mut manager, 2.0);
+        let c = spawn(&mut manager, 3.0);
+        manager.set_parent(&b, &a).unwrap();
+        propagate_transforms(&mut manager);
+        let last_run = manager.increment_change_tick();
+
+        manager
+            .get_mut::<Transform>(&b)
+            .unwrap()
+            .set_translation(Vector3::new(5.0, 0.0, 0.0));
+        propagate_transforms(&mut manager);
+        manager.set_last_change_tick(last_run);
+        let changed = <Changed<GlobalTransform>>::query_simple(&mut manager)
+            .map(|global| global.translation().x)
+            .collect::<Vec<_>>();
+        assert_eq!(changed, vec![6.0]);
+        assert_eq!(world_x(&manager, &c), 3.0);
+    }
+}
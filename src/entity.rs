@@ -1,23 +1,31 @@
 use std::{any::Any, cell::RefCell, collections::VecDeque, rc::Rc};
 
-use gl_matrix4rust::mat4::Mat4;
+use gl_matrix4rust::{mat4::Mat4, vec4::Vec4};
 use indexmap::IndexMap;
 use uuid::Uuid;
 use web_sys::WebGlVertexArrayObject;
 
 use crate::{
-    bounding::{merge_bounding_volumes, CullingBoundingVolume},
+    bounding::{merge_bounding_volumes, Culling, CullingBoundingVolume},
     clock::Tick,
+    frustum::ViewFrustum,
     geometry::{Geometry, GeometryMessage},
     material::webgl::{MaterialMessage, StandardMaterial},
     message::{channel, Aborter, Executor, Receiver, Sender},
     renderer::webgl::{
         attribute::AttributeValue,
+        buffer::{self, Buffer, BufferComponentSize, BufferDataType, BufferUsage},
+        matrix::GlF32,
         uniform::{UniformBlockValue, UniformValue},
     },
     value::Readonly,
 };
 
+/// Attribute name of per-instance model matrices of an [`InstancedEntity`].
+pub const INSTANCE_MODEL_MATRIX_ATTRIBUTE_NAME: &'static str = "a_Entity_InstanceModelMatrix";
+/// Attribute name of per-instance colors of an [`InstancedEntity`].
+pub const INSTANCE_COLOR_ATTRIBUTE_NAME: &'static str = "a_Entity_InstanceColor";
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum EntityMessage {
     Changed,
@@ -36,6 +44,12 @@ pub trait Entity {
 
     fn bounding_volume(&self) -> Option<Readonly<'_, CullingBoundingVolume>>;
 
+    /// Returns static batch id of this entity.
+    ///
+    /// Entities sharing the same static batch id are treated as static entities with identical materials,
+    /// which could be merged into a single draw call by static batching.
+    fn static_batch(&self) -> Option<&Uuid>;
+
     fn geometry(&self) -> Option<&dyn Geometry>;
 
     fn geometry_mut(&mut self) -> Option<&mut dyn Geometry>;
@@ -64,6 +78,10 @@ pub trait Entity {
 
     fn as_vertex_array_object_entity_mut(&mut self) -> Option<&mut dyn VertexArrayObjectEntity>;
 
+    fn as_instanced_entity(&self) -> Option<&dyn InstancedEntity>;
+
+    fn as_instanced_entity_mut(&mut self) -> Option<&mut dyn InstancedEntity>;
+
     fn as_any(&self) -> &dyn Any;
 
     fn as_any_mut(&mut self) -> &mut dyn Any;
@@ -75,6 +93,30 @@ pub trait VertexArrayObjectEntity {
     fn store_vertex_array_object(&mut self, vao: WebGlVertexArrayObject);
 }
 
+/// Entity drawing its geometry multiple times in a single draw call.
+///
+/// Per-instance model matrices and colors are provided by [`Entity::attribute_value`]
+/// under [`INSTANCE_MODEL_MATRIX_ATTRIBUTE_NAME`] and [`INSTANCE_COLOR_ATTRIBUTE_NAME`].
+/// Per-instance model matrices are applied before model matrix of the entity.
+pub trait InstancedEntity {
+    /// Returns number of instances to draw.
+    fn instance_count(&self) -> usize;
+
+    /// Culls instances outside a view frustum and compacts remaining instances,
+    /// then only remaining instances are drawn.
+    /// All instances are restored if `view_frustum` is `None`.
+    ///
+    /// Returns number of instances to draw.
+    fn cull_instances(&mut self, view_frustum: Option<&ViewFrustum>) -> usize;
+
+    /// Draws all instances regardless of culling if `enable` is `true`,
+    /// for passes not limited by the view frustum, such as shadow mapping.
+    /// Instances left by the last culling are drawn again if `enable` is `false`.
+    ///
+    /// Returns number of instances to draw.
+    fn draw_all_instances(&mut self, enable: bool) -> usize;
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum GroupMessage {
     Changed,
@@ -206,6 +248,8 @@ pub struct SimpleEntity {
     enable_bounding: bool,
     bounding_volume: Option<CullingBoundingVolume>,
 
+    static_batch: Option<Uuid>,
+
     vao: Rc<RefCell<Option<WebGlVertexArrayObject>>>,
 
     channel: (Sender<EntityMessage>, Receiver<EntityMessage>),
@@ -231,6 +275,8 @@ impl SimpleEntity {
             enable_bounding: true,
             bounding_volume: None,
 
+            static_batch: None,
+
             vao: Rc::new(RefCell::new(None)),
 
             channel: channel(),
@@ -377,6 +423,14 @@ impl SimpleEntity {
         *self.should_recalculate_bounding.borrow_mut() = true;
     }
 
+    /// Sets static batch id of this entity.
+    /// Entity should never move and should share an identical material with other entities in the same batch.
+    pub fn set_static_batch(&mut self, static_batch: Option<Uuid>) {
+        self.static_batch = static_batch;
+        *self.should_update.borrow_mut() = true;
+        self.channel.0.send(EntityMessage::Changed);
+    }
+
     fn update_matrices(&mut self, group: &dyn Group) {
         self.parent_compose_model_matrix = *group.compose_model_matrix();
         self.compose_model_matrix = self.parent_compose_model_matrix * self.model_matrix;
@@ -418,6 +472,10 @@ impl Entity for SimpleEntity {
             .map(|volume| Readonly::Borrowed(volume))
     }
 
+    fn static_batch(&self) -> Option<&Uuid> {
+        self.static_batch.as_ref()
+    }
+
     fn geometry(&self) -> Option<&dyn Geometry> {
         self.geometry
             .as_ref()
@@ -518,6 +576,14 @@ impl Entity for SimpleEntity {
         Some(self)
     }
 
+    fn as_instanced_entity(&self) -> Option<&dyn InstancedEntity> {
+        None
+    }
+
+    fn as_instanced_entity_mut(&mut self) -> Option<&mut dyn InstancedEntity> {
+        None
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -537,6 +603,396 @@ impl VertexArrayObjectEntity for SimpleEntity {
     }
 }
 
+/// An instance of a [`SimpleInstancedEntity`].
+#[derive(Clone, Copy)]
+pub struct Instance {
+    model_matrix: Mat4<f64>,
+    color: Vec4<f32>,
+}
+
+impl Instance {
+    /// Constructs a new instance with a model matrix and a color.
+    /// Color of an instance multiplies with color of the material.
+    pub fn new(model_matrix: Mat4<f64>, color: Vec4<f32>) -> Self {
+        Self {
+            model_matrix,
+            color,
+        }
+    }
+
+    /// Returns model matrix of this instance.
+    pub fn model_matrix(&self) -> &Mat4<f64> {
+        &self.model_matrix
+    }
+
+    /// Returns color of this instance.
+    pub fn color(&self) -> &Vec4<f32> {
+        &self.color
+    }
+}
+
+/// A [`SimpleEntity`] drawing its geometry once for each [`Instance`] in a single draw call.
+///
+/// Vertex array object is never stored for instanced entity,
+/// attributes are rebound for each draw to upload compacted instances.
+///
+/// Visible instances and all instances are uploaded into different buffers,
+/// switching between them by [`InstancedEntity::draw_all_instances`] uploads nothing.
+pub struct SimpleInstancedEntity {
+    entity: SimpleEntity,
+
+    instances: Vec<Instance>,
+    instance_bounding_volumes: Option<Vec<CullingBoundingVolume>>,
+    bounding_volume: Option<CullingBoundingVolume>,
+
+    visible_instances: Option<Vec<usize>>,
+    model_matrices: Buffer,
+    colors: Buffer,
+
+    all_instances_uploaded: bool,
+    all_model_matrices: Buffer,
+    all_colors: Buffer,
+    draw_all_instances: bool,
+
+    should_recalculate_instances: bool,
+}
+
+impl SimpleInstancedEntity {
+    pub fn new() -> Self {
+        Self {
+            entity: SimpleEntity::new(),
+
+            instances: Vec::new(),
+            instance_bounding_volumes: None,
+            bounding_volume: None,
+
+            visible_instances: None,
+            model_matrices: buffer::Builder::new(BufferUsage::DYNAMIC_DRAW).build(),
+            colors: buffer::Builder::new(BufferUsage::DYNAMIC_DRAW).build(),
+
+            all_instances_uploaded: false,
+            all_model_matrices: buffer::Builder::new(BufferUsage::DYNAMIC_DRAW).build(),
+            all_colors: buffer::Builder::new(BufferUsage::DYNAMIC_DRAW).build(),
+            draw_all_instances: false,
+
+            should_recalculate_instances: true,
+        }
+    }
+
+    /// Returns the inner [`SimpleEntity`] providing model matrix, geometry and material.
+    pub fn entity(&self) -> &SimpleEntity {
+        &self.entity
+    }
+
+    /// Returns the inner mutable [`SimpleEntity`] providing model matrix, geometry and material.
+    pub fn entity_mut(&mut self) -> &mut SimpleEntity {
+        &mut self.entity
+    }
+
+    /// Returns all instances.
+    pub fn instances(&self) -> &[Instance] {
+        &self.instances
+    }
+
+    /// Adds a new instance.
+    pub fn add_instance(&mut self, instance: Instance) {
+        self.instances.push(instance);
+        self.mark_instances_changed();
+    }
+
+    /// Replaces the instance at `index`, returns the old one.
+    /// Returns `None` and does nothing if `index` out of range.
+    pub fn set_instance(&mut self, index: usize, instance: Instance) -> Option<Instance> {
+        let old_instance = std::mem::replace(self.instances.get_mut(index)?, instance);
+        self.mark_instances_changed();
+        Some(old_instance)
+    }
+
+    /// Removes the instance at `index`, returns the removed one.
+    /// Returns `None` and does nothing if `index` out of range.
+    pub fn remove_instance(&mut self, index: usize) -> Option<Instance> {
+        if index >= self.instances.len() {
+            return None;
+        }
+
+        let instance = self.instances.remove(index);
+        self.mark_instances_changed();
+        Some(instance)
+    }
+
+    /// Removes all instances.
+    pub fn clear_instances(&mut self) {
+        self.instances.clear();
+        self.mark_instances_changed();
+    }
+
+    fn mark_instances_changed(&mut self) {
+        self.should_recalculate_instances = true;
+        self.visible_instances = None;
+        self.all_instances_uploaded = false;
+        *self.entity.should_update.borrow_mut() = true;
+        self.entity
+            .channel
+            .0
+            .send(EntityMessage::BoundingVolumeChanged);
+        self.entity.channel.0.send(EntityMessage::Changed);
+    }
+
+    fn update_instance_bounding_volumes(&mut self) {
+        let compose_model_matrix = *self.entity.compose_model_matrix();
+        let bounding_volume = if self.entity.bounding_enabled() {
+            self.entity
+                .geometry()
+                .and_then(|geom| geom.bounding_volume())
+                .map(|bounding| *bounding.as_ref())
+        } else {
+            None
+        };
+
+        self.instance_bounding_volumes = bounding_volume.map(|bounding| {
+            self.instances
+                .iter()
+                .map(|instance| {
+                    CullingBoundingVolume::new(
+                        bounding.transform(compose_model_matrix * instance.model_matrix),
+                    )
+                })
+                .collect()
+        });
+        self.bounding_volume = self
+            .instance_bounding_volumes
+            .as_ref()
+            .and_then(|boundings| {
+                merge_bounding_volumes(boundings.iter().map(|bounding| bounding.bounding_volume()))
+            })
+            .map(|bounding| CullingBoundingVolume::new(bounding));
+    }
+
+    fn upload_instances(&mut self, visible_instances: &[usize]) {
+        let (model_matrices, colors) = compact_instances(&self.instances, visible_instances);
+        self.model_matrices.buffer_data(model_matrices);
+        self.colors.buffer_data(colors);
+    }
+
+    fn upload_all_instances(&mut self) {
+        let indices = (0..self.instances.len()).collect::<Vec<_>>();
+        let (model_matrices, colors) = compact_instances(&self.instances, &indices);
+        self.all_model_matrices.buffer_data(model_matrices);
+        self.all_colors.buffer_data(colors);
+        self.all_instances_uploaded = true;
+    }
+}
+
+/// Returns indices of instances not outside the view frustum.
+/// All instances are visible if view frustum or bounding volumes of instances are unavailable.
+fn visible_instances(
+    instance_count: usize,
+    bounding_volumes: Option<&[CullingBoundingVolume]>,
+    view_frustum: Option<&ViewFrustum>,
+) -> Vec<usize> {
+    match (view_frustum, bounding_volumes) {
+        (Some(view_frustum), Some(boundings)) => boundings
+            .iter()
+            .enumerate()
+            .filter(|(_, bounding)| !matches!(bounding.cull(view_frustum), Culling::Outside))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>(),
+        _ => (0..instance_count).collect::<Vec<_>>(),
+    }
+}
+
+/// Compacts model matrices and colors of instances at `indices`,
+/// in native endian float bytes.
+fn compact_instances(instances: &[Instance], indices: &[usize]) -> (Vec<u8>, Vec<u8>) {
+    let mut model_matrices = Vec::with_capacity(indices.len() * 64);
+    let mut colors = Vec::with_capacity(indices.len() * 16);
+    for index in indices {
+        let instance = &instances[*index];
+        model_matrices.extend(
+            instance
+                .model_matrix
+                .to_f32_array()
+                .iter()
+                .flat_map(|value| value.to_ne_bytes()),
+        );
+        colors.extend(
+            instance
+                .color
+                .to_f32_array()
+                .iter()
+                .flat_map(|value| value.to_ne_bytes()),
+        );
+    }
+    (model_matrices, colors)
+}
+
+impl Entity for SimpleInstancedEntity {
+    fn id(&self) -> &Uuid {
+        self.entity.id()
+    }
+
+    fn compose_model_matrix(&self) -> Readonly<'_, Mat4<f64>> {
+        self.entity.compose_model_matrix()
+    }
+
+    fn compose_normal_matrix(&self) -> Readonly<'_, Mat4<f64>> {
+        self.entity.compose_normal_matrix()
+    }
+
+    fn bounding_volume(&self) -> Option<Readonly<'_, CullingBoundingVolume>> {
+        self.bounding_volume
+            .as_ref()
+            .map(|volume| Readonly::Borrowed(volume))
+    }
+
+    fn static_batch(&self) -> Option<&Uuid> {
+        // instanced entity draws all instances in a single draw call already
+        None
+    }
+
+    fn geometry(&self) -> Option<&dyn Geometry> {
+        self.entity.geometry()
+    }
+
+    fn geometry_mut(&mut self) -> Option<&mut dyn Geometry> {
+        self.entity.geometry_mut()
+    }
+
+    fn material(&self) -> Option<&dyn StandardMaterial> {
+        self.entity.material()
+    }
+
+    fn material_mut(&mut self) -> Option<&mut dyn StandardMaterial> {
+        self.entity.material_mut()
+    }
+
+    fn attribute_value(&self, name: &str) -> Option<AttributeValue<'_>> {
+        match name {
+            INSTANCE_MODEL_MATRIX_ATTRIBUTE_NAME => Some(AttributeValue::InstancedBuffer {
+                buffer: Readonly::Borrowed(if self.draw_all_instances {
+                    &self.all_model_matrices
+                } else {
+                    &self.model_matrices
+                }),
+                component_size: BufferComponentSize::Four,
+                data_type: BufferDataType::FLOAT,
+                normalized: false,
+                component_count_per_instance: 4,
+                divisor: 1,
+            }),
+            INSTANCE_COLOR_ATTRIBUTE_NAME => Some(AttributeValue::InstancedBuffer {
+                buffer: Readonly::Borrowed(if self.draw_all_instances {
+                    &self.all_colors
+                } else {
+                    &self.colors
+                }),
+                component_size: BufferComponentSize::Four,
+                data_type: BufferDataType::FLOAT,
+                normalized: false,
+                component_count_per_instance: 1,
+                divisor: 1,
+            }),
+            _ => self.entity.attribute_value(name),
+        }
+    }
+
+    fn uniform_value(&self, name: &str) -> Option<UniformValue<'_>> {
+        self.entity.uniform_value(name)
+    }
+
+    fn uniform_block_value(&self, name: &str) -> Option<UniformBlockValue<'_>> {
+        self.entity.uniform_block_value(name)
+    }
+
+    fn tick(&mut self, tick: &Tick) {
+        self.entity.tick(tick);
+    }
+
+    fn changed(&self) -> Receiver<EntityMessage> {
+        self.entity.changed()
+    }
+
+    fn should_update(&self) -> bool {
+        self.entity.should_update() || self.should_recalculate_instances
+    }
+
+    fn update(&mut self, group: &dyn Group) {
+        // model matrix, geometry or bounding of the inner entity changes bounding volumes of instances
+        let should_recalculate_instances =
+            self.entity.should_update() || self.should_recalculate_instances;
+
+        self.entity.update(group);
+
+        if should_recalculate_instances {
+            self.update_instance_bounding_volumes();
+            self.should_recalculate_instances = false;
+        }
+    }
+
+    fn as_vertex_array_object_entity(&self) -> Option<&dyn VertexArrayObjectEntity> {
+        None
+    }
+
+    fn as_vertex_array_object_entity_mut(&mut self) -> Option<&mut dyn VertexArrayObjectEntity> {
+        None
+    }
+
+    fn as_instanced_entity(&self) -> Option<&dyn InstancedEntity> {
+        Some(self)
+    }
+
+    fn as_instanced_entity_mut(&mut self) -> Option<&mut dyn InstancedEntity> {
+        Some(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl InstancedEntity for SimpleInstancedEntity {
+    fn instance_count(&self) -> usize {
+        if self.draw_all_instances {
+            return self.instances.len();
+        }
+
+        self.visible_instances
+            .as_ref()
+            .map(|visible_instances| visible_instances.len())
+            .unwrap_or(0)
+    }
+
+    fn cull_instances(&mut self, view_frustum: Option<&ViewFrustum>) -> usize {
+        let visible_instances = visible_instances(
+            self.instances.len(),
+            self.instance_bounding_volumes.as_deref(),
+            view_frustum,
+        );
+
+        // uploads only if visible instances changed
+        if self.visible_instances.as_ref() != Some(&visible_instances) {
+            self.upload_instances(&visible_instances);
+            self.visible_instances = Some(visible_instances);
+        }
+        self.draw_all_instances = false;
+
+        self.instance_count()
+    }
+
+    fn draw_all_instances(&mut self, enable: bool) -> usize {
+        if enable && !self.all_instances_uploaded {
+            self.upload_all_instances();
+        }
+        self.draw_all_instances = enable;
+
+        self.instance_count()
+    }
+}
+
 pub struct SimpleGroup {
     id: Uuid,
 
@@ -849,3 +1305,119 @@ impl Group for SimpleGroup {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use gl_matrix4rust::{mat4::Mat4, vec3::Vec3, vec4::Vec4};
+
+    use crate::{
+        bounding::{BoundingVolume, CullingBoundingVolume},
+        frustum::ViewFrustum,
+        plane::Plane,
+    };
+
+    use super::{
+        compact_instances, visible_instances, Instance, InstancedEntity, SimpleInstancedEntity,
+    };
+
+    /// A box shaped frustum from `-1.0` to `1.0` on each axis, with outward normals.
+    fn box_frustum() -> ViewFrustum {
+        let plane = |x: f64, y: f64, z: f64| Plane::new(Vec3::new(x, y, z), Vec3::new(x, y, z));
+        ViewFrustum::new(
+            plane(-1.0, 0.0, 0.0),
+            plane(1.0, 0.0, 0.0),
+            plane(0.0, 1.0, 0.0),
+            plane(0.0, -1.0, 0.0),
+            plane(0.0, 0.0, 1.0),
+            Some(plane(0.0, 0.0, -1.0)),
+        )
+    }
+
+    fn sphere(x: f64, radius: f64) -> CullingBoundingVolume {
+        CullingBoundingVolume::new(BoundingVolume::BoundingSphere {
+            center: Vec3::new(x, 0.0, 0.0),
+            radius,
+        })
+    }
+
+    fn instance(x: f64) -> Instance {
+        Instance::new(
+            Mat4::<f64>::from_translation(&Vec3::new(x, 0.0, 0.0)),
+            Vec4::new(1.0, 0.5, 0.25, 1.0),
+        )
+    }
+
+    #[test]
+    fn test_visible_instances() {
+        let frustum = box_frustum();
+        let boundings = [
+            // inside
+            sphere(0.0, 0.5),
+            // outside
+            sphere(5.0, 0.5),
+            // intersects
+            sphere(1.2, 0.5),
+            // outside
+            sphere(-3.0, 1.0),
+        ];
+
+        assert_eq!(
+            visible_instances(4, Some(&boundings), Some(&frustum)),
+            vec![0, 2]
+        );
+        assert_eq!(
+            visible_instances(4, Some(&boundings), None),
+            vec![0, 1, 2, 3]
+        );
+        assert_eq!(visible_instances(3, None, Some(&frustum)), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_compact_instances() {
+        let instances = [instance(1.0), instance(2.0), instance(3.0)];
+        let (model_matrices, colors) = compact_instances(&instances, &[2, 0]);
+        assert_eq!(model_matrices.len(), 2 * 64);
+        assert_eq!(colors.len(), 2 * 16);
+
+        let read = |bytes: &[u8], index: usize| {
+            f32::from_ne_bytes([
+                bytes[index * 4],
+                bytes[index * 4 + 1],
+                bytes[index * 4 + 2],
+                bytes[index * 4 + 3],
+            ])
+        };
+        // translation x of the first compacted instance is the one of instance 2
+        assert_eq!(read(&model_matrices, 12), 3.0);
+        assert_eq!(read(&model_matrices, 16 + 12), 1.0);
+        assert_eq!(read(&colors, 1), 0.5);
+        assert_eq!(read(&colors, 4 + 2), 0.25);
+
+        let (model_matrices, colors) = compact_instances(&instances, &[]);
+        assert!(model_matrices.is_empty() && colors.is_empty());
+    }
+
+    #[test]
+    fn test_draw_all_instances() {
+        let mut entity = SimpleInstancedEntity::new();
+        for x in [0.0, 1.0, 2.0] {
+            entity.add_instance(instance(x));
+        }
+
+        // nothing is drawn before culling
+        assert_eq!(entity.instance_count(), 0);
+        assert_eq!(entity.cull_instances(Some(&box_frustum())), 3);
+        assert_eq!(entity.draw_all_instances(true), 3);
+
+        entity.remove_instance(0);
+        assert_eq!(entity.instance_count(), 2);
+        // visible instances are invalidated until culled again
+        assert_eq!(entity.draw_all_instances(false), 0);
+        assert_eq!(entity.cull_instances(None), 2);
+
+        entity.draw_all_instances(true);
+        // culling always switches back to visible instances
+        assert_eq!(entity.cull_instances(None), 2);
+        assert!(!entity.draw_all_instances);
+    }
+}
@@ -11,6 +11,10 @@ pub enum Error {
     NoSuchEntity,
     NoSuchGroup,
     InvalidDirectDrawSurface,
+    InvalidGltf(String),
+    InvalidWavefrontObj(String),
+    InvalidKtx2(String),
+    InvalidRadianceHdr(String),
     WebGLRenderError(crate::renderer::webgl::error::Error),
     JsError(js_sys::Error),
     CommonError(Option<String>)
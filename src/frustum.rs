@@ -1,3 +1,5 @@
+use gl_matrix4rust::{mat4::Mat4, vec3::Vec3};
+
 use crate::plane::Plane;
 
 /// View frustum, for a view frustum, far plane is optional.
@@ -61,3 +63,195 @@ impl ViewFrustum {
         &self.bottom
     }
 }
+
+/// Corners of a slice of a view frustum in world space.
+/// Four corners on the near side come first, in order of left bottom, right bottom, right top and left top,
+/// followed by four corners on the far side in the same order.
+pub type FrustumCorners = [Vec3<f64>; 8];
+
+/// Coordinates of the four edges of a view frustum in NDC,
+/// in order of left bottom, right bottom, right top and left top.
+const NDC_EDGES: [(f64, f64); 4] = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+
+/// Edges of a view frustum in world space.
+struct FrustumEdges {
+    /// Distance from camera to near plane along view direction.
+    near: f64,
+    /// Intersections of edges and near plane.
+    origins: [Vec3<f64>; 4],
+    /// Directions of edges, scaled to advance `1.0` along view direction.
+    directions: [Vec3<f64>; 4],
+}
+
+/// Unprojects edges of a view frustum from a view projection matrix.
+/// Far plane is never touched, infinite perspective projections are supported as well.
+fn frustum_edges(view_proj_matrix: &Mat4<f64>) -> Option<FrustumEdges> {
+    let inverse = view_proj_matrix.invert().ok()?;
+    let unproject = |x: f64, y: f64, z: f64| inverse * Vec3::<f64>::new(x, y, z);
+
+    let near_center = unproject(0.0, 0.0, -1.0);
+    let forward = (unproject(0.0, 0.0, 0.0) - near_center).normalize();
+
+    let mut near = 0.0;
+    let mut origins = [near_center; 4];
+    let mut directions = [forward; 4];
+    for (i, (x, y)) in NDC_EDGES.iter().enumerate() {
+        let origin = unproject(*x, *y, -1.0);
+        let direction = unproject(*x, *y, 0.0) - origin;
+        let direction = direction / direction.dot(&forward);
+
+        // edges of a perspective projection converge at camera position,
+        // walking back from near plane until lateral offset to view axis vanishes gets near distance.
+        // edges of an orthogonal projection never converge, leaving near distance `0.0`.
+        let spread = (direction - forward).length();
+        if spread > f64::EPSILON {
+            near = (origin - near_center).length() / spread;
+        }
+
+        origins[i] = origin;
+        directions[i] = direction;
+    }
+
+    Some(FrustumEdges {
+        near,
+        origins,
+        directions,
+    })
+}
+
+/// Returns distance from camera to near plane along view direction of a view projection matrix.
+/// Returns `0.0` for orthogonal projections and `None` if matrix is not invertible.
+pub fn frustum_near_distance(view_proj_matrix: &Mat4<f64>) -> Option<f64> {
+    frustum_edges(view_proj_matrix).map(|edges| edges.near)
+}
+
+/// Returns corners of a slice of the view frustum of a view projection matrix,
+/// bounded by `near` and `far` distances from camera along view direction.
+/// Distances are not required to be inside the view frustum.
+///
+/// Returns `None` if matrix is not invertible.
+pub fn frustum_slice_corners(
+    view_proj_matrix: &Mat4<f64>,
+    near: f64,
+    far: f64,
+) -> Option<FrustumCorners> {
+    let edges = frustum_edges(view_proj_matrix)?;
+
+    let mut corners = [edges.origins[0]; 8];
+    for (i, (origin, direction)) in edges.origins.iter().zip(edges.directions).enumerate() {
+        corners[i] = *origin + direction * (near - edges.near);
+        corners[i + 4] = *origin + direction * (far - edges.near);
+    }
+    Some(corners)
+}
+
+/// Splits view distances from `near` to `far` into `count` cascades using practical split scheme,
+/// which blends logarithmic splits and uniform splits by `lambda`.
+/// `lambda` of `1.0` results in logarithmic splits and `0.0` results in uniform splits.
+/// Logarithmic splits require a positive `near`, uniform splits are always used otherwise.
+///
+/// Returns `count + 1` distances, starting with `near` and ending with `far`.
+pub fn cascade_splits(near: f64, far: f64, count: usize, lambda: f64) -> Vec<f64> {
+    let count = count.max(1);
+    let lambda = if near > 0.0 {
+        lambda.clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    (0..=count)
+        .map(|i| {
+            if i == 0 {
+                near
+            } else if i == count {
+                far
+            } else {
+                let logarithmic = if lambda > 0.0 {
+                    near * (far / near).powf(i as f64 / count as f64)
+                } else {
+                    0.0
+                };
+                let uniform = near + (far - near) * i as f64 / count as f64;
+                lambda * logarithmic + (1.0 - lambda) * uniform
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use gl_matrix4rust::{mat4::Mat4, vec3::Vec3};
+
+    use super::{cascade_splits, frustum_near_distance, frustum_slice_corners};
+
+    fn assert_vec3(vec: &Vec3<f64>, [x, y, z]: [f64; 3]) {
+        assert!(
+            (vec.x() - x).abs() < 1e-6 && (vec.y() - y).abs() < 1e-6 && (vec.z() - z).abs() < 1e-6,
+            "{:?} != {:?}",
+            [*vec.x(), *vec.y(), *vec.z()],
+            [x, y, z]
+        );
+    }
+
+    /// Camera at `(0, 0, 5)` looking at `-Z`, with 90 degrees field of view and aspect of `1.0`.
+    fn view_proj_matrix(far: Option<f64>) -> Mat4<f64> {
+        let view = Mat4::<f64>::from_look_at(
+            &Vec3::new(0.0, 0.0, 5.0),
+            &Vec3::new(0.0, 0.0, 0.0),
+            &Vec3::new(0.0, 1.0, 0.0),
+        );
+        let proj = Mat4::<f64>::from_perspective(std::f64::consts::FRAC_PI_2, 1.0, 0.5, far);
+        proj * view
+    }
+
+    #[test]
+    fn test_cascade_splits() {
+        let splits = cascade_splits(1.0, 100.0, 2, 0.0);
+        assert_eq!(splits, vec![1.0, 50.5, 100.0]);
+
+        let splits = cascade_splits(1.0, 100.0, 2, 1.0);
+        assert!((splits[1] - 10.0).abs() < 1e-9);
+
+        let splits = cascade_splits(0.5, 50.0, 4, 0.75);
+        assert_eq!(splits.len(), 5);
+        assert_eq!(splits[0], 0.5);
+        assert_eq!(splits[4], 50.0);
+        assert!(splits.windows(2).all(|pair| pair[0] < pair[1]));
+
+        // logarithmic splits are impossible without a positive near distance
+        let splits = cascade_splits(0.0, 30.0, 3, 1.0);
+        assert_eq!(splits, vec![0.0, 10.0, 20.0, 30.0]);
+
+        assert_eq!(cascade_splits(1.0, 10.0, 0, 0.5), vec![1.0, 10.0]);
+    }
+
+    #[test]
+    fn test_frustum_near_distance() {
+        let near = frustum_near_distance(&view_proj_matrix(Some(100.0))).unwrap();
+        assert!((near - 0.5).abs() < 1e-6);
+
+        let near = frustum_near_distance(&view_proj_matrix(None)).unwrap();
+        assert!((near - 0.5).abs() < 1e-6);
+
+        let ortho = Mat4::<f64>::from_ortho(-1.0, 1.0, -1.0, 1.0, 0.0, 10.0);
+        assert!(frustum_near_distance(&ortho).unwrap().abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_frustum_slice_corners() {
+        for far in [Some(100.0), None] {
+            let corners = frustum_slice_corners(&view_proj_matrix(far), 10.0, 20.0).unwrap();
+            assert_vec3(&corners[0], [-10.0, -10.0, -5.0]);
+            assert_vec3(&corners[1], [10.0, -10.0, -5.0]);
+            assert_vec3(&corners[2], [10.0, 10.0, -5.0]);
+            assert_vec3(&corners[3], [-10.0, 10.0, -5.0]);
+            assert_vec3(&corners[4], [-20.0, -20.0, -15.0]);
+            assert_vec3(&corners[6], [20.0, 20.0, -15.0]);
+        }
+
+        let ortho = Mat4::<f64>::from_ortho(-2.0, 2.0, -1.0, 1.0, 0.0, 10.0);
+        let corners = frustum_slice_corners(&ortho, 1.0, 3.0).unwrap();
+        assert_vec3(&corners[0], [-2.0, -1.0, -1.0]);
+        assert_vec3(&corners[6], [2.0, 1.0, -3.0]);
+    }
+}
@@ -1,5 +1,10 @@
 use gl_matrix4rust::vec3::Vec3;
 
+use super::{
+    DEFAULT_SHADOW_CASCADES, DEFAULT_SHADOW_CASCADE_LAMBDA, DEFAULT_SHADOW_DEPTH_BIAS,
+    DEFAULT_SHADOW_DISTANCE, DEFAULT_SHADOW_SLOPE_BIAS, MAX_SHADOW_CASCADES,
+};
+
 /// Directional light.
 /// Direction of a directional light should points from light to outside
 /// and should be normalized.
@@ -10,6 +15,12 @@ pub struct DirectionalLight {
     ambient: Vec3<f32>,
     diffuse: Vec3<f32>,
     specular: Vec3<f32>,
+    shadow: bool,
+    shadow_depth_bias: f32,
+    shadow_slope_bias: f32,
+    shadow_distance: f32,
+    shadow_cascades: usize,
+    shadow_cascade_lambda: f32,
 }
 impl DirectionalLight {
     /// Constructs a new directional light.
@@ -26,6 +37,12 @@ impl DirectionalLight {
             ambient,
             diffuse,
             specular,
+            shadow: false,
+            shadow_depth_bias: DEFAULT_SHADOW_DEPTH_BIAS,
+            shadow_slope_bias: DEFAULT_SHADOW_SLOPE_BIAS,
+            shadow_distance: DEFAULT_SHADOW_DISTANCE,
+            shadow_cascades: DEFAULT_SHADOW_CASCADES,
+            shadow_cascade_lambda: DEFAULT_SHADOW_CASCADE_LAMBDA,
         }
     }
 
@@ -83,4 +100,72 @@ impl DirectionalLight {
     pub fn set_specular(&mut self, specular: Vec3<f32>) {
         self.specular = specular;
     }
+
+    /// Returns `true` if this directional light casts shadows.
+    pub fn shadow_enabled(&self) -> bool {
+        self.shadow
+    }
+
+    /// Enables shadow casting of this directional light.
+    pub fn enable_shadow(&mut self) {
+        self.shadow = true;
+    }
+
+    /// Disables shadow casting of this directional light.
+    pub fn disable_shadow(&mut self) {
+        self.shadow = false;
+    }
+
+    /// Returns constant depth bias of shadow mapping.
+    pub fn shadow_depth_bias(&self) -> f32 {
+        self.shadow_depth_bias
+    }
+
+    /// Sets constant depth bias of shadow mapping.
+    pub fn set_shadow_depth_bias(&mut self, bias: f32) {
+        self.shadow_depth_bias = bias;
+    }
+
+    /// Returns slope scaled depth bias of shadow mapping.
+    /// Final bias is `max(slope_bias * (1.0 - dot(normal, to_light)), depth_bias)`.
+    pub fn shadow_slope_bias(&self) -> f32 {
+        self.shadow_slope_bias
+    }
+
+    /// Sets slope scaled depth bias of shadow mapping.
+    pub fn set_shadow_slope_bias(&mut self, bias: f32) {
+        self.shadow_slope_bias = bias;
+    }
+
+    /// Returns maximum view distance from camera receiving shadows.
+    /// Shadow cascades split view frustum of camera from near plane to this distance.
+    pub fn shadow_distance(&self) -> f32 {
+        self.shadow_distance
+    }
+
+    /// Sets maximum view distance from camera receiving shadows.
+    pub fn set_shadow_distance(&mut self, distance: f32) {
+        self.shadow_distance = distance;
+    }
+
+    /// Returns number of shadow cascades.
+    pub fn shadow_cascades(&self) -> usize {
+        self.shadow_cascades
+    }
+
+    /// Sets number of shadow cascades, clamped to range from `1` to [`MAX_SHADOW_CASCADES`].
+    pub fn set_shadow_cascades(&mut self, cascades: usize) {
+        self.shadow_cascades = cascades.clamp(1, MAX_SHADOW_CASCADES);
+    }
+
+    /// Returns weight of logarithmic splits against uniform splits when splitting shadow cascades.
+    pub fn shadow_cascade_lambda(&self) -> f32 {
+        self.shadow_cascade_lambda
+    }
+
+    /// Sets weight of logarithmic splits against uniform splits when splitting shadow cascades,
+    /// clamped to range from `0.0` to `1.0`.
+    pub fn set_shadow_cascade_lambda(&mut self, lambda: f32) {
+        self.shadow_cascade_lambda = lambda.clamp(0.0, 1.0);
+    }
 }
@@ -4,3 +4,19 @@ pub mod directional_light;
 pub mod point_light;
 pub mod spot_light;
 pub mod attenuation;
+
+/// Default constant depth bias for shadow mapping.
+pub const DEFAULT_SHADOW_DEPTH_BIAS: f32 = 0.0005;
+/// Default slope scaled depth bias for shadow mapping.
+pub const DEFAULT_SHADOW_SLOPE_BIAS: f32 = 0.005;
+/// Default far distance of shadow casting for spot lights and point lights.
+pub const DEFAULT_SHADOW_FAR: f32 = 100.0;
+/// Default maximum view distance of shadows of directional lights.
+pub const DEFAULT_SHADOW_DISTANCE: f32 = 50.0;
+/// Maximum shadow cascades of a directional light,
+/// same as `ATOY_MAX_SHADOW_CASCADES` in lighting shader snippet.
+pub const MAX_SHADOW_CASCADES: usize = 4;
+/// Default shadow cascades of directional lights.
+pub const DEFAULT_SHADOW_CASCADES: usize = 4;
+/// Default weight of logarithmic splits against uniform splits when splitting shadow cascades.
+pub const DEFAULT_SHADOW_CASCADE_LAMBDA: f32 = 0.75;
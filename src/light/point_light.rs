@@ -1,5 +1,7 @@
 use gl_matrix4rust::vec3::Vec3;
 
+use super::{DEFAULT_SHADOW_DEPTH_BIAS, DEFAULT_SHADOW_FAR, DEFAULT_SHADOW_SLOPE_BIAS};
+
 /// Point light. Position of a point light should be in world space.
 #[derive(Clone, Copy, PartialEq)]
 pub struct PointLight {
@@ -8,6 +10,10 @@ pub struct PointLight {
     ambient: Vec3<f32>,
     diffuse: Vec3<f32>,
     specular: Vec3<f32>,
+    shadow: bool,
+    shadow_depth_bias: f32,
+    shadow_slope_bias: f32,
+    shadow_far: f32,
 }
 
 impl PointLight {
@@ -24,6 +30,10 @@ impl PointLight {
             ambient,
             diffuse,
             specular,
+            shadow: false,
+            shadow_depth_bias: DEFAULT_SHADOW_DEPTH_BIAS,
+            shadow_slope_bias: DEFAULT_SHADOW_SLOPE_BIAS,
+            shadow_far: DEFAULT_SHADOW_FAR,
         }
     }
 
@@ -81,4 +91,51 @@ impl PointLight {
     pub fn set_specular(&mut self, specular: Vec3<f32>) {
         self.specular = specular;
     }
+
+    /// Returns `true` if this point light casts shadows.
+    pub fn shadow_enabled(&self) -> bool {
+        self.shadow
+    }
+
+    /// Enables shadow casting of this point light.
+    pub fn enable_shadow(&mut self) {
+        self.shadow = true;
+    }
+
+    /// Disables shadow casting of this point light.
+    pub fn disable_shadow(&mut self) {
+        self.shadow = false;
+    }
+
+    /// Returns constant depth bias of shadow mapping.
+    pub fn shadow_depth_bias(&self) -> f32 {
+        self.shadow_depth_bias
+    }
+
+    /// Sets constant depth bias of shadow mapping.
+    pub fn set_shadow_depth_bias(&mut self, bias: f32) {
+        self.shadow_depth_bias = bias;
+    }
+
+    /// Returns slope scaled depth bias of shadow mapping.
+    /// Final bias is `max(slope_bias * (1.0 - dot(normal, to_light)), depth_bias)`.
+    pub fn shadow_slope_bias(&self) -> f32 {
+        self.shadow_slope_bias
+    }
+
+    /// Sets slope scaled depth bias of shadow mapping.
+    pub fn set_shadow_slope_bias(&mut self, bias: f32) {
+        self.shadow_slope_bias = bias;
+    }
+
+    /// Returns far distance of shadow casting.
+    /// Surfaces farther than this distance from the point light are never shadowed.
+    pub fn shadow_far(&self) -> f32 {
+        self.shadow_far
+    }
+
+    /// Sets far distance of shadow casting.
+    pub fn set_shadow_far(&mut self, far: f32) {
+        self.shadow_far = far;
+    }
 }
@@ -1,5 +1,7 @@
 use gl_matrix4rust::vec3::Vec3;
 
+use super::{DEFAULT_SHADOW_DEPTH_BIAS, DEFAULT_SHADOW_FAR, DEFAULT_SHADOW_SLOPE_BIAS};
+
 /// Spot light. Position and direction of a spot light should be in world space.
 #[derive(Clone, Copy, PartialEq)]
 pub struct SpotLight {
@@ -11,6 +13,10 @@ pub struct SpotLight {
     specular: Vec3<f32>,
     inner_cutoff: f32,
     outer_cutoff: f32,
+    shadow: bool,
+    shadow_depth_bias: f32,
+    shadow_slope_bias: f32,
+    shadow_far: f32,
 }
 
 impl SpotLight {
@@ -36,6 +42,10 @@ impl SpotLight {
             specular,
             inner_cutoff: inner_cutoff,
             outer_cutoff: inner_cutoff.max(outer_cutoff),
+            shadow: false,
+            shadow_depth_bias: DEFAULT_SHADOW_DEPTH_BIAS,
+            shadow_slope_bias: DEFAULT_SHADOW_SLOPE_BIAS,
+            shadow_far: DEFAULT_SHADOW_FAR,
         }
     }
 
@@ -123,4 +133,51 @@ impl SpotLight {
     pub fn set_outer_cutoff(&mut self, outer_cutoff: f32) {
         self.outer_cutoff = outer_cutoff.max(self.inner_cutoff);
     }
+
+    /// Returns `true` if this spot light casts shadows.
+    pub fn shadow_enabled(&self) -> bool {
+        self.shadow
+    }
+
+    /// Enables shadow casting of this spot light.
+    pub fn enable_shadow(&mut self) {
+        self.shadow = true;
+    }
+
+    /// Disables shadow casting of this spot light.
+    pub fn disable_shadow(&mut self) {
+        self.shadow = false;
+    }
+
+    /// Returns constant depth bias of shadow mapping.
+    pub fn shadow_depth_bias(&self) -> f32 {
+        self.shadow_depth_bias
+    }
+
+    /// Sets constant depth bias of shadow mapping.
+    pub fn set_shadow_depth_bias(&mut self, bias: f32) {
+        self.shadow_depth_bias = bias;
+    }
+
+    /// Returns slope scaled depth bias of shadow mapping.
+    /// Final bias is `max(slope_bias * (1.0 - dot(normal, to_light)), depth_bias)`.
+    pub fn shadow_slope_bias(&self) -> f32 {
+        self.shadow_slope_bias
+    }
+
+    /// Sets slope scaled depth bias of shadow mapping.
+    pub fn set_shadow_slope_bias(&mut self, bias: f32) {
+        self.shadow_slope_bias = bias;
+    }
+
+    /// Returns far distance of shadow casting.
+    /// Surfaces farther than this distance from the spot light are never shadowed.
+    pub fn shadow_far(&self) -> f32 {
+        self.shadow_far
+    }
+
+    /// Sets far distance of shadow casting.
+    pub fn set_shadow_far(&mut self, far: f32) {
+        self.shadow_far = far;
+    }
 }
@@ -10,9 +10,9 @@ use crate::{
     error::Error,
     message::{channel, Receiver, Sender},
     renderer::webgl::texture::{
-        Builder, SamplerParameter, Texture, Texture2D, TextureCompressedData,
-        TextureCompressedFormat, TextureData, TextureInternalFormat, TextureParameter,
-        TextureSource,
+        Builder, SamplerParameter, Texture, Texture2D, Texture2DArray, Texture3D,
+        TextureCompressedData, TextureCompressedFormat, TextureCubeMap, TextureCubeMapFace,
+        TextureData, TextureInternalFormat, TextureParameter, TextureSource,
     },
     window,
 };
@@ -24,6 +24,12 @@ pub const DDS_DXT1: u32 = 0x31545844;
 pub const DDS_DXT3: u32 = 0x33545844;
 pub const DDS_DXT5: u32 = 0x35545844;
 pub const DDS_DXT10: u32 = 0x30315844;
+pub const DDS_ATI1: u32 = 0x31495441;
+pub const DDS_BC4U: u32 = 0x55344342;
+pub const DDS_BC4S: u32 = 0x53344342;
+pub const DDS_ATI2: u32 = 0x32495441;
+pub const DDS_BC5U: u32 = 0x55354342;
+pub const DDS_BC5S: u32 = 0x53354342;
 pub const DDS_HEADER_SIZE: u32 = 124;
 pub const DDS_PIXELFORMAT_SIZE: u32 = 32;
 pub const DDS_HEADER_FLAG_DDSD_CAPS: u32 = 0x1;
@@ -40,6 +46,38 @@ pub const DDS_PIXELFORMAT_FLAG_FOUR_CC: u32 = 0x4;
 pub const DDS_PIXELFORMAT_FLAG_RGB: u32 = 0x40;
 pub const DDS_PIXELFORMAT_FLAG_YUV: u32 = 0x200;
 pub const DDS_PIXELFORMAT_FLAG_LUMINANCE: u32 = 0x20000;
+pub const DDS_CAPS2_CUBEMAP: u32 = 0x200;
+pub const DDS_CAPS2_CUBEMAP_POSITIVEX: u32 = 0x400;
+pub const DDS_CAPS2_CUBEMAP_NEGATIVEX: u32 = 0x800;
+pub const DDS_CAPS2_CUBEMAP_POSITIVEY: u32 = 0x1000;
+pub const DDS_CAPS2_CUBEMAP_NEGATIVEY: u32 = 0x2000;
+pub const DDS_CAPS2_CUBEMAP_POSITIVEZ: u32 = 0x4000;
+pub const DDS_CAPS2_CUBEMAP_NEGATIVEZ: u32 = 0x8000;
+pub const DDS_CAPS2_VOLUME: u32 = 0x200000;
+pub const DDS_DXGI_FORMAT_BC1_TYPELESS: u32 = 70;
+pub const DDS_DXGI_FORMAT_BC1_UNORM: u32 = 71;
+pub const DDS_DXGI_FORMAT_BC1_UNORM_SRGB: u32 = 72;
+pub const DDS_DXGI_FORMAT_BC2_TYPELESS: u32 = 73;
+pub const DDS_DXGI_FORMAT_BC2_UNORM: u32 = 74;
+pub const DDS_DXGI_FORMAT_BC2_UNORM_SRGB: u32 = 75;
+pub const DDS_DXGI_FORMAT_BC3_TYPELESS: u32 = 76;
+pub const DDS_DXGI_FORMAT_BC3_UNORM: u32 = 77;
+pub const DDS_DXGI_FORMAT_BC3_UNORM_SRGB: u32 = 78;
+pub const DDS_DXGI_FORMAT_BC4_TYPELESS: u32 = 79;
+pub const DDS_DXGI_FORMAT_BC4_UNORM: u32 = 80;
+pub const DDS_DXGI_FORMAT_BC4_SNORM: u32 = 81;
+pub const DDS_DXGI_FORMAT_BC5_TYPELESS: u32 = 82;
+pub const DDS_DXGI_FORMAT_BC5_UNORM: u32 = 83;
+pub const DDS_DXGI_FORMAT_BC5_SNORM: u32 = 84;
+pub const DDS_DXGI_FORMAT_BC6H_TYPELESS: u32 = 94;
+pub const DDS_DXGI_FORMAT_BC6H_UF16: u32 = 95;
+pub const DDS_DXGI_FORMAT_BC6H_SF16: u32 = 96;
+pub const DDS_DXGI_FORMAT_BC7_TYPELESS: u32 = 97;
+pub const DDS_DXGI_FORMAT_BC7_UNORM: u32 = 98;
+pub const DDS_DXGI_FORMAT_BC7_UNORM_SRGB: u32 = 99;
+pub const DDS_DIMENSION_TEXTURE2D: u32 = 3;
+pub const DDS_DIMENSION_TEXTURE3D: u32 = 4;
+pub const DDS_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
 
 pub struct Header {
     pub size: u32,
@@ -98,6 +136,47 @@ impl Header {
     pub fn ddsd_depth(&self) -> bool {
         self.flags & DDS_HEADER_FLAG_DDSD_DEPTH != 0
     }
+
+    /// Returns `true` if `DDSCAPS2_CUBEMAP` flag is available.
+    pub fn ddscaps2_cubemap(&self) -> bool {
+        self.caps2 & DDS_CAPS2_CUBEMAP != 0
+    }
+
+    /// Returns `true` if `DDSCAPS2_VOLUME` flag is available.
+    pub fn ddscaps2_volume(&self) -> bool {
+        self.caps2 & DDS_CAPS2_VOLUME != 0
+    }
+
+    /// Returns cube map faces stored in the surface, in storing order.
+    pub fn cube_map_faces(&self) -> Vec<TextureCubeMapFace> {
+        [
+            (DDS_CAPS2_CUBEMAP_POSITIVEX, TextureCubeMapFace::PositiveX),
+            (DDS_CAPS2_CUBEMAP_NEGATIVEX, TextureCubeMapFace::NegativeX),
+            (DDS_CAPS2_CUBEMAP_POSITIVEY, TextureCubeMapFace::PositiveY),
+            (DDS_CAPS2_CUBEMAP_NEGATIVEY, TextureCubeMapFace::NegativeY),
+            (DDS_CAPS2_CUBEMAP_POSITIVEZ, TextureCubeMapFace::PositiveZ),
+            (DDS_CAPS2_CUBEMAP_NEGATIVEZ, TextureCubeMapFace::NegativeZ),
+        ]
+        .iter()
+        .filter(|(flag, _)| self.caps2 & *flag != 0)
+        .map(|(_, face)| *face)
+        .collect()
+    }
+
+    /// Returns mipmap count, at least 1.
+    ///
+    /// Mipmap count is clamped to `floor(log2(max(width, height, depth))) + 1`,
+    /// levels beyond that are never read.
+    pub fn levels(&self) -> usize {
+        if self.ddsd_mipmap_count() {
+            let depth = if self.ddsd_depth() { self.depth } else { 1 };
+            let size = self.width.max(self.height).max(depth);
+            let max_levels = (u32::BITS - size.leading_zeros()) as usize;
+            (self.mipmap_count as usize).min(max_levels).max(1)
+        } else {
+            1
+        }
+    }
 }
 
 pub struct HeaderDxt10 {
@@ -108,6 +187,217 @@ pub struct HeaderDxt10 {
     pub misc_flags2: u32,
 }
 
+impl HeaderDxt10 {
+    /// Returns `true` if `DDS_RESOURCE_MISC_TEXTURECUBE` flag is available.
+    pub fn texture_cube(&self) -> bool {
+        self.misc_flag & DDS_RESOURCE_MISC_TEXTURECUBE != 0
+    }
+}
+
+/// Texture layouts a DirectDraw Surface could be created as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DirectDrawSurfaceLayout {
+    Texture2D,
+    TextureCubeMap,
+    Texture2DArray { len: usize },
+    Texture3D { depth: usize },
+}
+
+/// Location of an image stored in a DirectDraw Surface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DirectDrawSurfaceImage {
+    /// Array index, or cube map face index of [`Header::cube_map_faces`].
+    pub index: usize,
+    pub level: usize,
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    /// Byte offset from the start of the file.
+    pub byte_offset: usize,
+    pub byte_length: usize,
+}
+
+/// Returns data offset of a DirectDraw Surface.
+pub fn data_offset(header_dxt10: Option<&HeaderDxt10>) -> usize {
+    match header_dxt10 {
+        Some(_) => 148,
+        None => 128,
+    }
+}
+
+/// Returns texture layout of a DirectDraw Surface.
+pub fn surface_layout(
+    header: &Header,
+    header_dxt10: Option<&HeaderDxt10>,
+) -> DirectDrawSurfaceLayout {
+    match header_dxt10 {
+        Some(dxt10) if dxt10.resource_dimension == DDS_DIMENSION_TEXTURE3D => {
+            DirectDrawSurfaceLayout::Texture3D {
+                depth: (header.depth as usize).max(1),
+            }
+        }
+        Some(dxt10) if dxt10.texture_cube() => DirectDrawSurfaceLayout::TextureCubeMap,
+        Some(dxt10) if dxt10.array_size > 1 => DirectDrawSurfaceLayout::Texture2DArray {
+            len: dxt10.array_size as usize,
+        },
+        _ => {
+            if header.ddscaps2_cubemap() {
+                DirectDrawSurfaceLayout::TextureCubeMap
+            } else if header.ddscaps2_volume() && header.ddsd_depth() {
+                DirectDrawSurfaceLayout::Texture3D {
+                    depth: (header.depth as usize).max(1),
+                }
+            } else {
+                DirectDrawSurfaceLayout::Texture2D
+            }
+        }
+    }
+}
+
+/// Returns compressed format of a DirectDraw Surface.
+/// Returns `None` if format is not supported.
+///
+/// `dxt1_use_alpha` only works for DXT1 and `use_srgb` only works for formats having a sRGB variant.
+/// sRGB variant is always used if DXGI format of DX10 header says so.
+pub fn surface_compressed_format(
+    header: &Header,
+    header_dxt10: Option<&HeaderDxt10>,
+    dxt1_use_alpha: bool,
+    use_srgb: bool,
+) -> Option<TextureCompressedFormat> {
+    let bc1 = |use_srgb: bool| match (dxt1_use_alpha, use_srgb) {
+        (false, false) => TextureCompressedFormat::RGB_S3TC_DXT1,
+        (true, false) => TextureCompressedFormat::RGBA_S3TC_DXT1,
+        (false, true) => TextureCompressedFormat::SRGB_S3TC_DXT1,
+        (true, true) => TextureCompressedFormat::SRGB_ALPHA_S3TC_DXT1,
+    };
+    let bc2 = |use_srgb: bool| match use_srgb {
+        false => TextureCompressedFormat::RGBA_S3TC_DXT3,
+        true => TextureCompressedFormat::SRGB_ALPHA_S3TC_DXT3,
+    };
+    let bc3 = |use_srgb: bool| match use_srgb {
+        false => TextureCompressedFormat::RGBA_S3TC_DXT5,
+        true => TextureCompressedFormat::SRGB_ALPHA_S3TC_DXT5,
+    };
+    let bc7 = |use_srgb: bool| match use_srgb {
+        false => TextureCompressedFormat::RGBA_BPTC_UNORM,
+        true => TextureCompressedFormat::SRGB_ALPHA_BPTC_UNORM,
+    };
+
+    match header_dxt10 {
+        Some(dxt10) => match dxt10.dxgi_format {
+            DDS_DXGI_FORMAT_BC1_TYPELESS | DDS_DXGI_FORMAT_BC1_UNORM => Some(bc1(use_srgb)),
+            DDS_DXGI_FORMAT_BC1_UNORM_SRGB => Some(bc1(true)),
+            DDS_DXGI_FORMAT_BC2_TYPELESS | DDS_DXGI_FORMAT_BC2_UNORM => Some(bc2(use_srgb)),
+            DDS_DXGI_FORMAT_BC2_UNORM_SRGB => Some(bc2(true)),
+            DDS_DXGI_FORMAT_BC3_TYPELESS | DDS_DXGI_FORMAT_BC3_UNORM => Some(bc3(use_srgb)),
+            DDS_DXGI_FORMAT_BC3_UNORM_SRGB => Some(bc3(true)),
+            DDS_DXGI_FORMAT_BC4_TYPELESS | DDS_DXGI_FORMAT_BC4_UNORM => {
+                Some(TextureCompressedFormat::RED_RGTC1)
+            }
+            DDS_DXGI_FORMAT_BC4_SNORM => Some(TextureCompressedFormat::SIGNED_RED_RGTC1),
+            DDS_DXGI_FORMAT_BC5_TYPELESS | DDS_DXGI_FORMAT_BC5_UNORM => {
+                Some(TextureCompressedFormat::RED_GREEN_RGTC2)
+            }
+            DDS_DXGI_FORMAT_BC5_SNORM => Some(TextureCompressedFormat::SIGNED_RED_GREEN_RGTC2),
+            DDS_DXGI_FORMAT_BC6H_TYPELESS | DDS_DXGI_FORMAT_BC6H_UF16 => {
+                Some(TextureCompressedFormat::RGB_BPTC_UNSIGNED_FLOAT)
+            }
+            DDS_DXGI_FORMAT_BC6H_SF16 => Some(TextureCompressedFormat::RGB_BPTC_SIGNED_FLOAT),
+            DDS_DXGI_FORMAT_BC7_TYPELESS | DDS_DXGI_FORMAT_BC7_UNORM => Some(bc7(use_srgb)),
+            DDS_DXGI_FORMAT_BC7_UNORM_SRGB => Some(bc7(true)),
+            _ => None,
+        },
+        None => match header.pixel_format.four_cc {
+            DDS_DXT1 => Some(bc1(use_srgb)),
+            DDS_DXT3 => Some(bc2(use_srgb)),
+            DDS_DXT5 => Some(bc3(use_srgb)),
+            DDS_ATI1 | DDS_BC4U => Some(TextureCompressedFormat::RED_RGTC1),
+            DDS_BC4S => Some(TextureCompressedFormat::SIGNED_RED_RGTC1),
+            DDS_ATI2 | DDS_BC5U => Some(TextureCompressedFormat::RED_GREEN_RGTC2),
+            DDS_BC5S => Some(TextureCompressedFormat::SIGNED_RED_GREEN_RGTC2),
+            _ => None,
+        },
+    }
+}
+
+/// Returns locations of all images stored in a DirectDraw Surface.
+///
+/// Images of 2d textures, array textures and cube maps are stored index by index,
+/// all mipmaps of an index are stored continuously.
+/// For volume textures, each mipmap stores all depth slices of the level.
+/// Only level 0 is returned if `read_mipmaps` is `false`.
+///
+/// Errors if any image lies outside `file_length` bytes,
+/// or if the DX10 header describes a cube map array, which is not supported by WebGL 2.0.
+pub fn surface_images(
+    header: &Header,
+    header_dxt10: Option<&HeaderDxt10>,
+    pixel_format: TextureCompressedFormat,
+    read_mipmaps: bool,
+    file_length: usize,
+) -> Result<Vec<DirectDrawSurfaceImage>, Error> {
+    let width = header.width as usize;
+    let height = header.height as usize;
+    let levels = header.levels();
+    let layout = surface_layout(header, header_dxt10);
+    let count = match layout {
+        DirectDrawSurfaceLayout::Texture2D | DirectDrawSurfaceLayout::Texture3D { .. } => 1,
+        DirectDrawSurfaceLayout::TextureCubeMap => match header_dxt10 {
+            Some(dxt10) if dxt10.array_size > 1 => {
+                return Err(Error::InvalidDirectDrawSurface);
+            }
+            // DX10 cube maps always store all 6 faces
+            Some(_) => 6,
+            None => header.cube_map_faces().len(),
+        },
+        DirectDrawSurfaceLayout::Texture2DArray { len } => len,
+    };
+
+    // ensures byte length of level 0 never overflows, a block of DDS formats takes at most 16 bytes
+    width
+        .checked_add(3)
+        .zip(height.checked_add(3))
+        .and_then(|(width, height)| (width / 4).checked_mul(height / 4))
+        .and_then(|blocks| blocks.checked_mul(16))
+        .ok_or(Error::InvalidDirectDrawSurface)?;
+
+    let mut byte_offset = data_offset(header_dxt10);
+    let mut images = Vec::new();
+    for index in 0..count {
+        for level in 0..levels {
+            let width = (width >> level).max(1);
+            let height = (height >> level).max(1);
+            let depth = match layout {
+                DirectDrawSurfaceLayout::Texture3D { depth } => (depth >> level).max(1),
+                _ => 1,
+            };
+            let byte_length = pixel_format
+                .byte_length(width, height)
+                .checked_mul(depth)
+                .ok_or(Error::InvalidDirectDrawSurface)?;
+            let end = byte_offset
+                .checked_add(byte_length)
+                .filter(|end| *end <= file_length)
+                .ok_or(Error::InvalidDirectDrawSurface)?;
+            if level == 0 || read_mipmaps {
+                images.push(DirectDrawSurfaceImage {
+                    index,
+                    level,
+                    width,
+                    height,
+                    depth,
+                    byte_offset,
+                    byte_length,
+                });
+            }
+            byte_offset = end;
+        }
+    }
+
+    Ok(images)
+}
+
 pub struct PixelFormat {
     pub size: u32,
     pub flags: u32,
@@ -151,6 +441,51 @@ impl PixelFormat {
     }
 }
 
+struct CompressedTextureSource {
+    raw: ArrayBuffer,
+    byte_offset: usize,
+    byte_length: usize,
+    pixel_format: TextureCompressedFormat,
+    width: usize,
+    height: usize,
+}
+
+impl CompressedTextureSource {
+    fn new(
+        raw: &ArrayBuffer,
+        pixel_format: TextureCompressedFormat,
+        image: &DirectDrawSurfaceImage,
+    ) -> Self {
+        Self {
+            raw: raw.clone(),
+            byte_offset: image.byte_offset,
+            byte_length: image.byte_length,
+            pixel_format,
+            width: image.width,
+            height: image.height,
+        }
+    }
+}
+
+impl TextureSource for CompressedTextureSource {
+    fn data(&self) -> TextureData {
+        TextureData::Compressed {
+            pixel_format: self.pixel_format,
+            data: TextureCompressedData::Uint8Array {
+                width: self.width,
+                height: self.height,
+                data: Uint8Array::new_with_byte_offset_and_length(
+                    &self.raw,
+                    self.byte_offset as u32,
+                    self.byte_length as u32,
+                ),
+                src_element_offset: None,
+                src_element_length_override: None,
+            },
+        }
+    }
+}
+
 /// DirectDraw Surface.
 pub struct DirectDrawSurface {
     pub magic_number: u32,
@@ -191,14 +526,17 @@ impl DirectDrawSurface {
         }
 
         // parses header dxt10
-        let (header_dxt10, data) = if header.pixel_format.four_cc == DDS_DXT10 {
-            (
-                Some(Self::parse_header_dxt10(&data_view)),
-                Uint8Array::new_with_byte_offset(&raw, 148),
-            )
+        let header_dxt10 = if header.pixel_format.four_cc == DDS_DXT10 {
+            // a dds file with dxt10 header has at least 148 bytes
+            if raw.byte_length() < 148 {
+                return Err(Error::InvalidDirectDrawSurface);
+            }
+            Some(Self::parse_header_dxt10(&data_view))
         } else {
-            (None, Uint8Array::new_with_byte_offset(&raw, 128))
+            None
         };
+        let data =
+            Uint8Array::new_with_byte_offset(&raw, data_offset(header_dxt10.as_ref()) as u32);
 
         Ok(Self {
             magic_number,
@@ -209,6 +547,49 @@ impl DirectDrawSurface {
         })
     }
 
+    /// Returns texture layout of this DirectDraw Surface.
+    pub fn layout(&self) -> DirectDrawSurfaceLayout {
+        surface_layout(&self.header, self.header_dxt10.as_ref())
+    }
+
+    /// Returns compressed format of this DirectDraw Surface.
+    /// Returns `None` if format is not supported.
+    pub fn compressed_format(
+        &self,
+        dxt1_use_alpha: bool,
+        use_srgb: bool,
+    ) -> Option<TextureCompressedFormat> {
+        surface_compressed_format(
+            &self.header,
+            self.header_dxt10.as_ref(),
+            dxt1_use_alpha,
+            use_srgb,
+        )
+    }
+
+    fn levels(&self, read_mipmaps: bool) -> usize {
+        if read_mipmaps {
+            self.header.levels()
+        } else {
+            1
+        }
+    }
+
+    fn images(
+        &self,
+        pixel_format: TextureCompressedFormat,
+        read_mipmaps: bool,
+    ) -> Option<Vec<DirectDrawSurfaceImage>> {
+        surface_images(
+            &self.header,
+            self.header_dxt10.as_ref(),
+            pixel_format,
+            read_mipmaps,
+            self.raw.byte_length() as usize,
+        )
+        .ok()
+    }
+
     /// Tries to create a [`Texture2D`] from this DirectDraw Surface.
     /// Returns `None` if unable to create a valid descriptor.
     pub fn texture_2d<SI, TI>(
@@ -223,121 +604,175 @@ impl DirectDrawSurface {
         SI: IntoIterator<Item = SamplerParameter>,
         TI: IntoIterator<Item = TextureParameter>,
     {
-        struct CompressedTextureSource {
-            raw: ArrayBuffer,
-            byte_offset: usize,
-            byte_length: usize,
-            pixel_format: TextureCompressedFormat,
-            width: usize,
-            height: usize,
-        }
-
-        impl CompressedTextureSource {
-            fn new(
-                raw: &ArrayBuffer,
-                byte_offset: usize,
-                byte_length: usize,
-                pixel_format: TextureCompressedFormat,
-                width: usize,
-                height: usize,
-            ) -> Self {
-                Self {
-                    raw: raw.clone(),
-                    byte_offset,
-                    byte_length,
-                    pixel_format,
-                    width,
-                    height,
-                }
-            }
+        if self.layout() != DirectDrawSurfaceLayout::Texture2D {
+            return None;
+        }
+        let pixel_format = self.compressed_format(dxt1_use_alpha, use_srgb)?;
+        let images = self.images(pixel_format, read_mipmaps)?;
+
+        let mut builder = Builder::<Texture2D>::new(
+            TextureInternalFormat::Compressed(pixel_format),
+            self.levels(read_mipmaps),
+            self.header.width as usize,
+            self.header.height as usize,
+        );
+        builder.set_texture_parameters(texture_params);
+        builder.set_sampler_parameters(sampler_params);
+        for image in images {
+            builder.tex_image(
+                CompressedTextureSource::new(&self.raw, pixel_format, &image),
+                image.level,
+                false,
+            );
         }
 
-        impl TextureSource for CompressedTextureSource {
-            fn data(&self) -> TextureData {
-                TextureData::Compressed {
-                    pixel_format: self.pixel_format,
-                    data: TextureCompressedData::Uint8Array {
-                        width: self.width,
-                        height: self.height,
-                        data: Uint8Array::new_with_byte_offset_and_length(
-                            &self.raw,
-                            self.byte_offset as u32,
-                            self.byte_length as u32,
-                        ),
-                        src_element_offset: None,
-                        src_element_length_override: None,
-                    },
-                }
-            }
+        Some(builder.build())
+    }
+
+    /// Tries to create a [`TextureCubeMap`] from this DirectDraw Surface.
+    /// Returns `None` if unable to create a valid descriptor.
+    ///
+    /// Faces absent from the DirectDraw Surface are left uninitialized.
+    /// Cube map arrays are not supported by WebGL 2.0 and `None` is returned.
+    pub fn texture_cube_map<SI, TI>(
+        &self,
+        dxt1_use_alpha: bool,
+        use_srgb: bool,
+        read_mipmaps: bool,
+        sampler_params: SI,
+        texture_params: TI,
+    ) -> Option<Texture<TextureCubeMap>>
+    where
+        SI: IntoIterator<Item = SamplerParameter>,
+        TI: IntoIterator<Item = TextureParameter>,
+    {
+        if self.layout() != DirectDrawSurfaceLayout::TextureCubeMap {
+            return None;
         }
+        let pixel_format = self.compressed_format(dxt1_use_alpha, use_srgb)?;
+        let images = self.images(pixel_format, read_mipmaps)?;
+        let faces = match &self.header_dxt10 {
+            Some(_) => vec![
+                TextureCubeMapFace::PositiveX,
+                TextureCubeMapFace::NegativeX,
+                TextureCubeMapFace::PositiveY,
+                TextureCubeMapFace::NegativeY,
+                TextureCubeMapFace::PositiveZ,
+                TextureCubeMapFace::NegativeZ,
+            ],
+            None => self.header.cube_map_faces(),
+        };
+
+        let mut builder = Builder::<TextureCubeMap>::new(
+            TextureInternalFormat::Compressed(pixel_format),
+            self.levels(read_mipmaps),
+            self.header.width as usize,
+            self.header.height as usize,
+        );
+        builder.set_texture_parameters(texture_params);
+        builder.set_sampler_parameters(sampler_params);
+        for image in images {
+            builder.tex_image(
+                CompressedTextureSource::new(&self.raw, pixel_format, &image),
+                faces[image.index],
+                image.level,
+                false,
+            );
+        }
+
+        Some(builder.build())
+    }
 
-        let pixel_format = match (self.header.pixel_format.four_cc, dxt1_use_alpha, use_srgb) {
-            (DDS_DXT1, false, false) => Some(TextureCompressedFormat::RGB_S3TC_DXT1),
-            (DDS_DXT1, true, false) => Some(TextureCompressedFormat::RGBA_S3TC_DXT1),
-            (DDS_DXT1, false, true) => Some(TextureCompressedFormat::SRGB_S3TC_DXT1),
-            (DDS_DXT1, true, true) => Some(TextureCompressedFormat::SRGB_ALPHA_S3TC_DXT1),
-            (DDS_DXT3, _, false) => Some(TextureCompressedFormat::RGBA_S3TC_DXT3),
-            (DDS_DXT3, _, true) => Some(TextureCompressedFormat::SRGB_ALPHA_S3TC_DXT3),
-            (DDS_DXT5, _, false) => Some(TextureCompressedFormat::RGBA_S3TC_DXT5),
-            (DDS_DXT5, _, true) => Some(TextureCompressedFormat::SRGB_ALPHA_S3TC_DXT5),
-            (_, _, _) => None,
+    /// Tries to create a [`Texture2DArray`] from this DirectDraw Surface.
+    /// Returns `None` if unable to create a valid descriptor.
+    pub fn texture_2d_array<SI, TI>(
+        &self,
+        dxt1_use_alpha: bool,
+        use_srgb: bool,
+        read_mipmaps: bool,
+        sampler_params: SI,
+        texture_params: TI,
+    ) -> Option<Texture<Texture2DArray>>
+    where
+        SI: IntoIterator<Item = SamplerParameter>,
+        TI: IntoIterator<Item = TextureParameter>,
+    {
+        let DirectDrawSurfaceLayout::Texture2DArray { len } = self.layout() else {
+            return None;
         };
+        let pixel_format = self.compressed_format(dxt1_use_alpha, use_srgb)?;
+        let images = self.images(pixel_format, read_mipmaps)?;
+
+        let mut builder = Builder::<Texture2DArray>::new(
+            TextureInternalFormat::Compressed(pixel_format),
+            self.levels(read_mipmaps),
+            self.header.width as usize,
+            self.header.height as usize,
+            len,
+        );
+        builder.set_texture_parameters(texture_params);
+        builder.set_sampler_parameters(sampler_params);
+        for image in images {
+            builder.tex_sub_image(
+                CompressedTextureSource::new(&self.raw, pixel_format, &image),
+                image.level,
+                0,
+                0,
+                image.index,
+                image.width,
+                image.height,
+                1,
+                false,
+            );
+        }
 
-        match pixel_format {
-            Some(pixel_format) => {
-                let width = self.header.width as usize;
-                let height = self.header.height as usize;
-                let levels = self.header.mipmap_count as usize;
+        Some(builder.build())
+    }
 
-                let mut builder = Builder::<Texture2D>::new(
-                    TextureInternalFormat::Compressed(pixel_format),
-                    levels,
-                    width,
-                    height,
-                );
-                builder.set_texture_parameters(texture_params);
-                builder.set_sampler_parameters(sampler_params);
-
-                if read_mipmaps && self.header.ddsd_mipmap_count() {
-                    // reads mipmaps
-                    let mut byte_offset = 128usize;
-                    for level in 0..levels {
-                        let width = (width >> level).max(1);
-                        let height = (height >> level).max(1);
-                        let byte_length = pixel_format.byte_length(width, height);
-                        builder.tex_image(
-                            CompressedTextureSource::new(
-                                &self.raw,
-                                byte_offset,
-                                byte_length,
-                                pixel_format,
-                                width,
-                                height,
-                            ),
-                            level,
-                            false,
-                        );
-                        byte_offset += byte_length;
-                    }
-                } else {
-                    builder.tex_image(
-                        CompressedTextureSource::new(
-                            &self.raw,
-                            128,
-                            pixel_format.byte_length(width, height),
-                            pixel_format,
-                            width,
-                            height,
-                        ),
-                        0,
-                        false,
-                    );
-                };
-
-                Some(builder.build())
-            }
-            None => None,
+    /// Tries to create a [`Texture3D`] from this DirectDraw Surface.
+    /// Returns `None` if unable to create a valid descriptor.
+    pub fn texture_3d<SI, TI>(
+        &self,
+        dxt1_use_alpha: bool,
+        use_srgb: bool,
+        read_mipmaps: bool,
+        sampler_params: SI,
+        texture_params: TI,
+    ) -> Option<Texture<Texture3D>>
+    where
+        SI: IntoIterator<Item = SamplerParameter>,
+        TI: IntoIterator<Item = TextureParameter>,
+    {
+        let DirectDrawSurfaceLayout::Texture3D { depth } = self.layout() else {
+            return None;
+        };
+        let pixel_format = self.compressed_format(dxt1_use_alpha, use_srgb)?;
+        let images = self.images(pixel_format, read_mipmaps)?;
+
+        let mut builder = Builder::<Texture3D>::new(
+            TextureInternalFormat::Compressed(pixel_format),
+            self.levels(read_mipmaps),
+            self.header.width as usize,
+            self.header.height as usize,
+            depth,
+        );
+        builder.set_texture_parameters(texture_params);
+        builder.set_sampler_parameters(sampler_params);
+        for image in images {
+            builder.tex_sub_image(
+                CompressedTextureSource::new(&self.raw, pixel_format, &image),
+                image.level,
+                0,
+                0,
+                0,
+                image.width,
+                image.height,
+                image.depth,
+                false,
+            );
         }
+
+        Some(builder.build())
     }
 
     fn parse_magic_number(data_view: &DataView) -> u32 {
@@ -642,17 +1077,116 @@ impl Loader<Texture<Texture2D>> for DirectDrawSurfaceLoader {
             }
 
             let dds = (*self.dds).as_ref().unwrap();
-            let texture = dds
-                .texture_2d(
-                    self.dxt1_use_alpha,
-                    self.use_srgb,
-                    self.read_mipmaps,
-                    self.sampler_params.clone(),
-                    self.texture_params.clone(),
-                )
-                .unwrap();
+            dds.texture_2d(
+                self.dxt1_use_alpha,
+                self.use_srgb,
+                self.read_mipmaps,
+                self.sampler_params.clone(),
+                self.texture_params.clone(),
+            )
+            .ok_or(Error::InvalidDirectDrawSurface)
+        }
+    }
+
+    fn success(&self) -> Receiver<LoaderStatus> {
+        self.channel.1.clone()
+    }
+}
+
+impl Loader<Texture<TextureCubeMap>> for DirectDrawSurfaceLoader {
+    type Failure = Error;
+
+    fn status(&self) -> LoaderStatus {
+        unsafe { *self.status }
+    }
+
+    fn load(&mut self) {
+        Self::load(&self);
+    }
+
+    fn loaded(&self) -> Result<Texture<TextureCubeMap>, Error> {
+        unsafe {
+            if let Some(err) = &*self.error {
+                return Err(err.clone());
+            }
+
+            let dds = (*self.dds).as_ref().unwrap();
+            dds.texture_cube_map(
+                self.dxt1_use_alpha,
+                self.use_srgb,
+                self.read_mipmaps,
+                self.sampler_params.clone(),
+                self.texture_params.clone(),
+            )
+            .ok_or(Error::InvalidDirectDrawSurface)
+        }
+    }
+
+    fn success(&self) -> Receiver<LoaderStatus> {
+        self.channel.1.clone()
+    }
+}
+
+impl Loader<Texture<Texture2DArray>> for DirectDrawSurfaceLoader {
+    type Failure = Error;
+
+    fn status(&self) -> LoaderStatus {
+        unsafe { *self.status }
+    }
+
+    fn load(&mut self) {
+        Self::load(&self);
+    }
+
+    fn loaded(&self) -> Result<Texture<Texture2DArray>, Error> {
+        unsafe {
+            if let Some(err) = &*self.error {
+                return Err(err.clone());
+            }
+
+            let dds = (*self.dds).as_ref().unwrap();
+            dds.texture_2d_array(
+                self.dxt1_use_alpha,
+                self.use_srgb,
+                self.read_mipmaps,
+                self.sampler_params.clone(),
+                self.texture_params.clone(),
+            )
+            .ok_or(Error::InvalidDirectDrawSurface)
+        }
+    }
+
+    fn success(&self) -> Receiver<LoaderStatus> {
+        self.channel.1.clone()
+    }
+}
 
-            Ok(texture)
+impl Loader<Texture<Texture3D>> for DirectDrawSurfaceLoader {
+    type Failure = Error;
+
+    fn status(&self) -> LoaderStatus {
+        unsafe { *self.status }
+    }
+
+    fn load(&mut self) {
+        Self::load(&self);
+    }
+
+    fn loaded(&self) -> Result<Texture<Texture3D>, Error> {
+        unsafe {
+            if let Some(err) = &*self.error {
+                return Err(err.clone());
+            }
+
+            let dds = (*self.dds).as_ref().unwrap();
+            dds.texture_3d(
+                self.dxt1_use_alpha,
+                self.use_srgb,
+                self.read_mipmaps,
+                self.sampler_params.clone(),
+                self.texture_params.clone(),
+            )
+            .ok_or(Error::InvalidDirectDrawSurface)
         }
     }
 
@@ -660,3 +1194,359 @@ impl Loader<Texture<Texture2D>> for DirectDrawSurfaceLoader {
         self.channel.1.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(
+        width: u32,
+        height: u32,
+        depth: u32,
+        mipmap_count: u32,
+        four_cc: u32,
+        caps2: u32,
+    ) -> Header {
+        let mut flags = DDS_HEADER_FLAG_DDSD_CAPS
+            | DDS_HEADER_FLAG_DDSD_HEIGHT
+            | DDS_HEADER_FLAG_DDSD_WIDTH
+            | DDS_HEADER_FLAG_DDSD_PIXELFORMAT;
+        if mipmap_count > 0 {
+            flags |= DDS_HEADER_FLAG_DDSD_MIPMAPCOUNT;
+        }
+        if depth > 0 {
+            flags |= DDS_HEADER_FLAG_DDSD_DEPTH;
+        }
+
+        Header {
+            size: DDS_HEADER_SIZE,
+            flags,
+            height,
+            width,
+            pitch_or_linear_size: 0,
+            depth,
+            mipmap_count,
+            reserved1: [0; 11],
+            pixel_format: PixelFormat {
+                size: DDS_PIXELFORMAT_SIZE,
+                flags: DDS_PIXELFORMAT_FLAG_FOUR_CC,
+                four_cc,
+                rgb_bit_count: 0,
+                r_bit_mask: 0,
+                g_bit_mask: 0,
+                b_bit_mask: 0,
+                a_bit_mask: 0,
+            },
+            caps: 0,
+            caps2,
+            caps3: 0,
+            caps4: 0,
+            reserved2: 0,
+        }
+    }
+
+    fn header_dxt10(
+        dxgi_format: u32,
+        resource_dimension: u32,
+        misc_flag: u32,
+        array_size: u32,
+    ) -> HeaderDxt10 {
+        HeaderDxt10 {
+            dxgi_format,
+            resource_dimension,
+            misc_flag,
+            array_size,
+            misc_flags2: 0,
+        }
+    }
+
+    #[test]
+    fn test_compressed_format() {
+        let h = header(4, 4, 0, 0, DDS_BC4S, 0);
+        assert_eq!(
+            surface_compressed_format(&h, None, false, false),
+            Some(TextureCompressedFormat::SIGNED_RED_RGTC1)
+        );
+        let h = header(4, 4, 0, 0, DDS_ATI2, 0);
+        assert_eq!(
+            surface_compressed_format(&h, None, false, false),
+            Some(TextureCompressedFormat::RED_GREEN_RGTC2)
+        );
+
+        let h = header(4, 4, 0, 0, DDS_DXT10, 0);
+        let cases = [
+            (
+                DDS_DXGI_FORMAT_BC1_UNORM_SRGB,
+                false,
+                TextureCompressedFormat::SRGB_ALPHA_S3TC_DXT1,
+            ),
+            (
+                DDS_DXGI_FORMAT_BC4_UNORM,
+                false,
+                TextureCompressedFormat::RED_RGTC1,
+            ),
+            (
+                DDS_DXGI_FORMAT_BC5_SNORM,
+                false,
+                TextureCompressedFormat::SIGNED_RED_GREEN_RGTC2,
+            ),
+            (
+                DDS_DXGI_FORMAT_BC6H_UF16,
+                false,
+                TextureCompressedFormat::RGB_BPTC_UNSIGNED_FLOAT,
+            ),
+            (
+                DDS_DXGI_FORMAT_BC6H_SF16,
+                true,
+                TextureCompressedFormat::RGB_BPTC_SIGNED_FLOAT,
+            ),
+            (
+                DDS_DXGI_FORMAT_BC7_UNORM,
+                false,
+                TextureCompressedFormat::RGBA_BPTC_UNORM,
+            ),
+            (
+                DDS_DXGI_FORMAT_BC7_UNORM,
+                true,
+                TextureCompressedFormat::SRGB_ALPHA_BPTC_UNORM,
+            ),
+            (
+                DDS_DXGI_FORMAT_BC7_UNORM_SRGB,
+                false,
+                TextureCompressedFormat::SRGB_ALPHA_BPTC_UNORM,
+            ),
+        ];
+        for (dxgi_format, use_srgb, expected) in cases {
+            let dxt10 = header_dxt10(dxgi_format, DDS_DIMENSION_TEXTURE2D, 0, 1);
+            assert_eq!(
+                surface_compressed_format(&h, Some(&dxt10), true, use_srgb),
+                Some(expected)
+            );
+        }
+
+        // uncompressed dxgi format is not supported
+        let dxt10 = header_dxt10(28, DDS_DIMENSION_TEXTURE2D, 0, 1);
+        assert_eq!(
+            surface_compressed_format(&h, Some(&dxt10), true, true),
+            None
+        );
+    }
+
+    #[test]
+    fn test_layout() {
+        let h = header(
+            4,
+            4,
+            0,
+            0,
+            DDS_DXT1,
+            DDS_CAPS2_CUBEMAP | DDS_CAPS2_CUBEMAP_POSITIVEX | DDS_CAPS2_CUBEMAP_NEGATIVEZ,
+        );
+        assert_eq!(
+            surface_layout(&h, None),
+            DirectDrawSurfaceLayout::TextureCubeMap
+        );
+        assert_eq!(
+            h.cube_map_faces(),
+            vec![TextureCubeMapFace::PositiveX, TextureCubeMapFace::NegativeZ]
+        );
+
+        let h = header(4, 4, 8, 0, DDS_DXT1, DDS_CAPS2_VOLUME);
+        assert_eq!(
+            surface_layout(&h, None),
+            DirectDrawSurfaceLayout::Texture3D { depth: 8 }
+        );
+
+        let h = header(4, 4, 0, 0, DDS_DXT10, 0);
+        let dxt10 = header_dxt10(DDS_DXGI_FORMAT_BC7_UNORM, DDS_DIMENSION_TEXTURE2D, 0, 5);
+        assert_eq!(
+            surface_layout(&h, Some(&dxt10)),
+            DirectDrawSurfaceLayout::Texture2DArray { len: 5 }
+        );
+        let dxt10 = header_dxt10(
+            DDS_DXGI_FORMAT_BC7_UNORM,
+            DDS_DIMENSION_TEXTURE2D,
+            DDS_RESOURCE_MISC_TEXTURECUBE,
+            1,
+        );
+        assert_eq!(
+            surface_layout(&h, Some(&dxt10)),
+            DirectDrawSurfaceLayout::TextureCubeMap
+        );
+        let dxt10 = header_dxt10(DDS_DXGI_FORMAT_BC7_UNORM, DDS_DIMENSION_TEXTURE2D, 0, 1);
+        assert_eq!(
+            surface_layout(&h, Some(&dxt10)),
+            DirectDrawSurfaceLayout::Texture2D
+        );
+    }
+
+    #[test]
+    fn test_images_2d_mipmaps() {
+        // DXT1 stores 8 bytes per 4x4 block
+        let h = header(8, 8, 0, 3, DDS_DXT1, 0);
+        let images =
+            surface_images(&h, None, TextureCompressedFormat::RGB_S3TC_DXT1, true, 176).unwrap();
+        let offsets = images
+            .iter()
+            .map(|image| {
+                (
+                    image.level,
+                    image.width,
+                    image.byte_offset,
+                    image.byte_length,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            offsets,
+            vec![(0, 8, 128, 32), (1, 4, 160, 8), (2, 2, 168, 8)]
+        );
+
+        let images =
+            surface_images(&h, None, TextureCompressedFormat::RGB_S3TC_DXT1, false, 176).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].byte_offset, 128);
+    }
+
+    #[test]
+    fn test_images_array_after_dxt10_header() {
+        // BC7 stores 16 bytes per 4x4 block
+        let h = header(4, 4, 0, 2, DDS_DXT10, 0);
+        let dxt10 = header_dxt10(DDS_DXGI_FORMAT_BC7_UNORM, DDS_DIMENSION_TEXTURE2D, 0, 2);
+        let images = surface_images(
+            &h,
+            Some(&dxt10),
+            TextureCompressedFormat::RGBA_BPTC_UNORM,
+            true,
+            212,
+        )
+        .unwrap();
+        let offsets = images
+            .iter()
+            .map(|image| (image.index, image.level, image.byte_offset))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            offsets,
+            vec![(0, 0, 148), (0, 1, 164), (1, 0, 180), (1, 1, 196)]
+        );
+
+        // only level 0 of each layer, but offsets still skip mipmaps
+        let images = surface_images(
+            &h,
+            Some(&dxt10),
+            TextureCompressedFormat::RGBA_BPTC_UNORM,
+            false,
+            212,
+        )
+        .unwrap();
+        let offsets = images
+            .iter()
+            .map(|image| (image.index, image.level, image.byte_offset))
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec![(0, 0, 148), (1, 0, 180)]);
+    }
+
+    #[test]
+    fn test_images_cube_map_and_volume() {
+        let h = header(4, 4, 0, 0, DDS_DXT5, DDS_CAPS2_CUBEMAP | 0xfc00);
+        let images =
+            surface_images(&h, None, TextureCompressedFormat::RGBA_S3TC_DXT5, true, 224).unwrap();
+        assert_eq!(images.len(), 6);
+        assert_eq!(images[5].index, 5);
+        assert_eq!(images[5].byte_offset, 128 + 5 * 16);
+
+        let h = header(8, 8, 4, 2, DDS_DXT1, DDS_CAPS2_VOLUME);
+        let images =
+            surface_images(&h, None, TextureCompressedFormat::RGB_S3TC_DXT1, true, 272).unwrap();
+        let offsets = images
+            .iter()
+            .map(|image| {
+                (
+                    image.level,
+                    image.depth,
+                    image.byte_offset,
+                    image.byte_length,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec![(0, 4, 128, 128), (1, 2, 256, 16)]);
+    }
+
+    #[test]
+    fn test_images_bounded_by_file() {
+        // mipmap count is clamped to 4 levels of a 8x8 texture
+        let h = header(8, 8, 0, 100, DDS_DXT1, 0);
+        assert_eq!(h.levels(), 4);
+        let images =
+            surface_images(&h, None, TextureCompressedFormat::RGB_S3TC_DXT1, true, 184).unwrap();
+        assert_eq!(images.len(), 4);
+        assert_eq!(images[3].byte_offset, 176);
+
+        // last mipmap lies outside the file
+        assert!(
+            surface_images(&h, None, TextureCompressedFormat::RGB_S3TC_DXT1, true, 183).is_err()
+        );
+        // mipmaps are skipped but still have to be stored
+        assert!(
+            surface_images(&h, None, TextureCompressedFormat::RGB_S3TC_DXT1, false, 183).is_err()
+        );
+
+        let h = header(u32::MAX, u32::MAX, 0, u32::MAX, DDS_DXT1, 0);
+        assert_eq!(h.levels(), 32);
+        assert!(
+            surface_images(&h, None, TextureCompressedFormat::RGB_S3TC_DXT1, true, 1024).is_err()
+        );
+
+        let h = header(4, 4, 0, 0, DDS_DXT10, 0);
+        let dxt10 = header_dxt10(
+            DDS_DXGI_FORMAT_BC7_UNORM,
+            DDS_DIMENSION_TEXTURE2D,
+            0,
+            u32::MAX,
+        );
+        assert!(surface_images(
+            &h,
+            Some(&dxt10),
+            TextureCompressedFormat::RGBA_BPTC_UNORM,
+            true,
+            1024
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_images_reject_cube_map_array() {
+        let h = header(4, 4, 0, 0, DDS_DXT10, 0);
+        let dxt10 = header_dxt10(
+            DDS_DXGI_FORMAT_BC7_UNORM,
+            DDS_DIMENSION_TEXTURE2D,
+            DDS_RESOURCE_MISC_TEXTURECUBE,
+            2,
+        );
+        let file_length = 148 + 12 * 16;
+        assert!(surface_images(
+            &h,
+            Some(&dxt10),
+            TextureCompressedFormat::RGBA_BPTC_UNORM,
+            true,
+            file_length
+        )
+        .is_err());
+
+        let dxt10 = header_dxt10(
+            DDS_DXGI_FORMAT_BC7_UNORM,
+            DDS_DIMENSION_TEXTURE2D,
+            DDS_RESOURCE_MISC_TEXTURECUBE,
+            1,
+        );
+        let images = surface_images(
+            &h,
+            Some(&dxt10),
+            TextureCompressedFormat::RGBA_BPTC_UNORM,
+            true,
+            file_length,
+        )
+        .unwrap();
+        assert_eq!(images.len(), 6);
+    }
+}
@@ -0,0 +1,1779 @@
+use std::{any::Any, convert::TryFrom, ops::Range};
+
+use gl_matrix4rust::{mat4::Mat4, quat::Quat, vec3::Vec3};
+use hashbrown::HashMap;
+use js_sys::Promise;
+use serde::Deserialize;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    js_sys::{Array, ArrayBuffer, Uint8Array},
+    Blob, Request, RequestInit, Response, Url,
+};
+
+use crate::{
+    bounding::BoundingVolume,
+    clock::Tick,
+    document,
+    entity::{SimpleEntity, SimpleGroup},
+    error::{AsJsError, Error},
+    geometry::{Geometry, GeometryMessage, IndexedGeometry},
+    material::{
+        webgl::{solid_color::SolidColorMaterial, texture},
+        Transparency,
+    },
+    message::{channel, Receiver, Sender},
+    renderer::webgl::{
+        attribute::AttributeValue,
+        buffer::{self, BufferComponentSize, BufferDataType, BufferUsage},
+        draw::{CullFace, DrawMode, ElementIndicesDataType},
+        texture::{
+            SamplerParameter, TextureMagnificationFilter, TextureMinificationFilter,
+            TextureWrapMethod,
+        },
+        uniform::{UniformBlockValue, UniformValue},
+    },
+    value::Readonly,
+    window,
+};
+
+use super::{texture::TextureLoader, Loader, LoaderStatus};
+
+pub const GLB_MAGIC_NUMBER: u32 = 0x46546C67;
+pub const GLB_VERSION: u32 = 2;
+pub const GLB_HEADER_SIZE: usize = 12;
+pub const GLB_CHUNK_HEADER_SIZE: usize = 8;
+pub const GLB_CHUNK_TYPE_JSON: u32 = 0x4E4F534A;
+pub const GLB_CHUNK_TYPE_BIN: u32 = 0x004E4942;
+
+/// Available accessor component types mapped from glTF specification.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "u32")]
+pub enum ComponentType {
+    BYTE,
+    UNSIGNED_BYTE,
+    SHORT,
+    UNSIGNED_SHORT,
+    UNSIGNED_INT,
+    FLOAT,
+}
+
+impl TryFrom<u32> for ComponentType {
+    type Error = String;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            5120 => Ok(Self::BYTE),
+            5121 => Ok(Self::UNSIGNED_BYTE),
+            5122 => Ok(Self::SHORT),
+            5123 => Ok(Self::UNSIGNED_SHORT),
+            5125 => Ok(Self::UNSIGNED_INT),
+            5126 => Ok(Self::FLOAT),
+            _ => Err(format!("unknown accessor component type {}", value)),
+        }
+    }
+}
+
+impl ComponentType {
+    /// Returns bytes length of a component.
+    pub fn byte_length(&self) -> usize {
+        match self {
+            ComponentType::BYTE => 1,
+            ComponentType::UNSIGNED_BYTE => 1,
+            ComponentType::SHORT => 2,
+            ComponentType::UNSIGNED_SHORT => 2,
+            ComponentType::UNSIGNED_INT => 4,
+            ComponentType::FLOAT => 4,
+        }
+    }
+
+    /// Returns associated [`BufferDataType`].
+    pub fn buffer_data_type(&self) -> BufferDataType {
+        match self {
+            ComponentType::BYTE => BufferDataType::BYTE,
+            ComponentType::UNSIGNED_BYTE => BufferDataType::UNSIGNED_BYTE,
+            ComponentType::SHORT => BufferDataType::SHORT,
+            ComponentType::UNSIGNED_SHORT => BufferDataType::UNSIGNED_SHORT,
+            ComponentType::UNSIGNED_INT => BufferDataType::UNSIGNED_INT,
+            ComponentType::FLOAT => BufferDataType::FLOAT,
+        }
+    }
+
+    /// Returns associated [`ElementIndicesDataType`].
+    /// Returns `None` if component type is not allowed to be used as indices.
+    pub fn element_indices_data_type(&self) -> Option<ElementIndicesDataType> {
+        match self {
+            ComponentType::UNSIGNED_BYTE => Some(ElementIndicesDataType::UNSIGNED_BYTE),
+            ComponentType::UNSIGNED_SHORT => Some(ElementIndicesDataType::UNSIGNED_SHORT),
+            ComponentType::UNSIGNED_INT => Some(ElementIndicesDataType::UNSIGNED_INT),
+            _ => None,
+        }
+    }
+}
+
+/// Available accessor types mapped from glTF specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum AccessorType {
+    SCALAR,
+    VEC2,
+    VEC3,
+    VEC4,
+    MAT2,
+    MAT3,
+    MAT4,
+}
+
+impl AccessorType {
+    /// Returns the number of components of an element.
+    pub fn component_count(&self) -> usize {
+        match self {
+            AccessorType::SCALAR => 1,
+            AccessorType::VEC2 => 2,
+            AccessorType::VEC3 => 3,
+            AccessorType::VEC4 => 4,
+            AccessorType::MAT2 => 4,
+            AccessorType::MAT3 => 9,
+            AccessorType::MAT4 => 16,
+        }
+    }
+
+    /// Returns associated [`BufferComponentSize`].
+    /// Returns `None` if accessor type is not allowed to be used as vertex attribute.
+    pub fn buffer_component_size(&self) -> Option<BufferComponentSize> {
+        match self {
+            AccessorType::SCALAR => Some(BufferComponentSize::One),
+            AccessorType::VEC2 => Some(BufferComponentSize::Two),
+            AccessorType::VEC3 => Some(BufferComponentSize::Three),
+            AccessorType::VEC4 => Some(BufferComponentSize::Four),
+            _ => None,
+        }
+    }
+}
+
+/// Material alpha mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+pub enum AlphaMode {
+    #[default]
+    OPAQUE,
+    MASK,
+    BLEND,
+}
+
+fn default_scale() -> [f64; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_one() -> f64 {
+    1.0
+}
+
+fn default_base_color_factor() -> [f64; 4] {
+    [1.0, 1.0, 1.0, 1.0]
+}
+
+fn default_alpha_cutoff() -> f64 {
+    0.5
+}
+
+fn default_wrap() -> u32 {
+    10497
+}
+
+fn default_primitive_mode() -> u32 {
+    4
+}
+
+/// glTF asset metadata.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Asset {
+    pub version: String,
+    pub min_version: Option<String>,
+    pub generator: Option<String>,
+    pub copyright: Option<String>,
+}
+
+/// glTF scene.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scene {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub nodes: Vec<usize>,
+}
+
+/// glTF node.
+///
+/// A node holds either a column-major `matrix` or a TRS transformation, never both.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Node {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub children: Vec<usize>,
+    pub mesh: Option<usize>,
+    pub matrix: Option<[f64; 16]>,
+    pub translation: Option<[f64; 3]>,
+    pub rotation: Option<[f64; 4]>,
+    pub scale: Option<[f64; 3]>,
+}
+
+impl Node {
+    /// Returns local transformation matrix of this node.
+    pub fn model_matrix(&self) -> Mat4<f64> {
+        if let Some(matrix) = self.matrix {
+            return Mat4::<f64>::from_slice(matrix);
+        }
+
+        let [tx, ty, tz] = self.translation.unwrap_or([0.0, 0.0, 0.0]);
+        let [rx, ry, rz, rw] = self.rotation.unwrap_or([0.0, 0.0, 0.0, 1.0]);
+        let [sx, sy, sz] = self.scale.unwrap_or(default_scale());
+        Mat4::<f64>::from_rotation_translation_scale(
+            &Quat::<f64>::new(rx, ry, rz, rw),
+            &Vec3::<f64>::new(tx, ty, tz),
+            &Vec3::<f64>::new(sx, sy, sz),
+        )
+    }
+}
+
+/// glTF mesh.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Mesh {
+    pub name: Option<String>,
+    pub primitives: Vec<Primitive>,
+}
+
+/// glTF mesh primitive.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Primitive {
+    pub attributes: HashMap<String, usize>,
+    pub indices: Option<usize>,
+    pub material: Option<usize>,
+    #[serde(default = "default_primitive_mode")]
+    pub mode: u32,
+}
+
+impl Primitive {
+    /// Returns [`DrawMode`] of this primitive.
+    pub fn draw_mode(&self) -> Result<DrawMode, Error> {
+        match self.mode {
+            0 => Ok(DrawMode::POINTS),
+            1 => Ok(DrawMode::LINES),
+            2 => Ok(DrawMode::LINE_LOOP),
+            3 => Ok(DrawMode::LINE_STRIP),
+            4 => Ok(DrawMode::TRIANGLES),
+            5 => Ok(DrawMode::TRIANGLE_STRIP),
+            6 => Ok(DrawMode::TRIANGLE_FAN),
+            _ => Err(Error::InvalidGltf(format!(
+                "unknown primitive mode {}",
+                self.mode
+            ))),
+        }
+    }
+}
+
+/// glTF accessor.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Accessor {
+    pub buffer_view: Option<usize>,
+    #[serde(default)]
+    pub byte_offset: usize,
+    pub component_type: ComponentType,
+    #[serde(default)]
+    pub normalized: bool,
+    pub count: usize,
+    #[serde(rename = "type")]
+    pub accessor_type: AccessorType,
+    pub min: Option<Vec<f64>>,
+    pub max: Option<Vec<f64>>,
+    pub sparse: Option<AccessorSparse>,
+}
+
+impl Accessor {
+    /// Returns bytes length of an element.
+    pub fn element_byte_length(&self) -> usize {
+        self.component_type.byte_length() * self.accessor_type.component_count()
+    }
+}
+
+/// glTF sparse accessor storage.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessorSparse {
+    pub count: usize,
+    pub indices: AccessorSparseIndices,
+    pub values: AccessorSparseValues,
+}
+
+/// Indices of a glTF sparse accessor.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessorSparseIndices {
+    pub buffer_view: usize,
+    #[serde(default)]
+    pub byte_offset: usize,
+    pub component_type: ComponentType,
+}
+
+/// Values of a glTF sparse accessor.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessorSparseValues {
+    pub buffer_view: usize,
+    #[serde(default)]
+    pub byte_offset: usize,
+}
+
+/// glTF buffer view.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BufferView {
+    pub buffer: usize,
+    #[serde(default)]
+    pub byte_offset: usize,
+    pub byte_length: usize,
+    pub byte_stride: Option<usize>,
+    pub target: Option<u32>,
+}
+
+/// glTF buffer.
+/// `uri` is `None` if buffer refers to the binary chunk of a GLB container.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Buffer {
+    pub uri: Option<String>,
+    pub byte_length: usize,
+}
+
+/// glTF material.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Material {
+    pub name: Option<String>,
+    pub pbr_metallic_roughness: Option<PbrMetallicRoughness>,
+    pub normal_texture: Option<TextureInfo>,
+    pub occlusion_texture: Option<TextureInfo>,
+    pub emissive_texture: Option<TextureInfo>,
+    #[serde(default)]
+    pub emissive_factor: [f64; 3],
+    #[serde(default)]
+    pub alpha_mode: AlphaMode,
+    #[serde(default = "default_alpha_cutoff")]
+    pub alpha_cutoff: f64,
+    #[serde(default)]
+    pub double_sided: bool,
+}
+
+/// glTF metallic-roughness material model.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PbrMetallicRoughness {
+    #[serde(default = "default_base_color_factor")]
+    pub base_color_factor: [f64; 4],
+    pub base_color_texture: Option<TextureInfo>,
+    #[serde(default = "default_one")]
+    pub metallic_factor: f64,
+    #[serde(default = "default_one")]
+    pub roughness_factor: f64,
+    pub metallic_roughness_texture: Option<TextureInfo>,
+}
+
+/// Reference to a glTF texture.
+/// `scale` is only available for normal textures and `strength` for occlusion textures.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextureInfo {
+    pub index: usize,
+    #[serde(default)]
+    pub tex_coord: usize,
+    pub scale: Option<f64>,
+    pub strength: Option<f64>,
+}
+
+/// glTF texture.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Texture {
+    pub sampler: Option<usize>,
+    pub source: Option<usize>,
+}
+
+/// glTF image, either from an uri or from a buffer view.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Image {
+    pub name: Option<String>,
+    pub uri: Option<String>,
+    pub mime_type: Option<String>,
+    pub buffer_view: Option<usize>,
+}
+
+/// glTF texture sampler.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sampler {
+    pub mag_filter: Option<u32>,
+    pub min_filter: Option<u32>,
+    #[serde(default = "default_wrap")]
+    pub wrap_s: u32,
+    #[serde(default = "default_wrap")]
+    pub wrap_t: u32,
+}
+
+impl Sampler {
+    /// Returns associated [`SamplerParameter`]s.
+    pub fn sampler_parameters(&self) -> Vec<SamplerParameter> {
+        fn wrap(value: u32) -> TextureWrapMethod {
+            match value {
+                33071 => TextureWrapMethod::CLAMP_TO_EDGE,
+                33648 => TextureWrapMethod::MIRRORED_REPEAT,
+                _ => TextureWrapMethod::REPEAT,
+            }
+        }
+
+        let mut params = vec![
+            SamplerParameter::WRAP_S(wrap(self.wrap_s)),
+            SamplerParameter::WRAP_T(wrap(self.wrap_t)),
+        ];
+        match self.mag_filter {
+            Some(9728) => params.push(SamplerParameter::MAG_FILTER(
+                TextureMagnificationFilter::NEAREST,
+            )),
+            Some(9729) => params.push(SamplerParameter::MAG_FILTER(
+                TextureMagnificationFilter::LINEAR,
+            )),
+            _ => {}
+        };
+        let min_filter = match self.min_filter {
+            Some(9728) => Some(TextureMinificationFilter::NEAREST),
+            Some(9729) => Some(TextureMinificationFilter::LINEAR),
+            Some(9984) => Some(TextureMinificationFilter::NEAREST_MIPMAP_NEAREST),
+            Some(9985) => Some(TextureMinificationFilter::LINEAR_MIPMAP_NEAREST),
+            Some(9986) => Some(TextureMinificationFilter::NEAREST_MIPMAP_LINEAR),
+            Some(9987) => Some(TextureMinificationFilter::LINEAR_MIPMAP_LINEAR),
+            _ => None,
+        };
+        if let Some(min_filter) = min_filter {
+            params.push(SamplerParameter::MIN_FILTER(min_filter));
+        }
+
+        params
+    }
+}
+
+/// glTF JSON document.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Document {
+    pub asset: Asset,
+    pub scene: Option<usize>,
+    #[serde(default)]
+    pub scenes: Vec<Scene>,
+    #[serde(default)]
+    pub nodes: Vec<Node>,
+    #[serde(default)]
+    pub meshes: Vec<Mesh>,
+    #[serde(default)]
+    pub accessors: Vec<Accessor>,
+    #[serde(default)]
+    pub buffer_views: Vec<BufferView>,
+    #[serde(default)]
+    pub buffers: Vec<Buffer>,
+    #[serde(default)]
+    pub materials: Vec<Material>,
+    #[serde(default)]
+    pub textures: Vec<Texture>,
+    #[serde(default)]
+    pub images: Vec<Image>,
+    #[serde(default)]
+    pub samplers: Vec<Sampler>,
+}
+
+impl Document {
+    /// Parses a glTF JSON document or a binary GLB container from raw bytes.
+    /// Returns the document and the binary chunk if it is a GLB container.
+    pub fn from_slice(bytes: &[u8]) -> Result<(Self, Option<Vec<u8>>), Error> {
+        let is_glb = bytes.len() >= 4
+            && u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) == GLB_MAGIC_NUMBER;
+        if is_glb {
+            let (json, binary) = parse_glb(bytes)?;
+            Ok((Self::from_json(json)?, binary.map(|binary| binary.to_vec())))
+        } else {
+            Ok((Self::from_json(bytes)?, None))
+        }
+    }
+
+    /// Parses a glTF JSON document.
+    pub fn from_json(json: &[u8]) -> Result<Self, Error> {
+        let document = serde_json::from_slice::<Self>(json)
+            .map_err(|err| Error::InvalidGltf(err.to_string()))?;
+        if !document.asset.version.starts_with("2.") {
+            return Err(Error::InvalidGltf(format!(
+                "unsupported glTF version {}",
+                document.asset.version
+            )));
+        }
+
+        Ok(document)
+    }
+
+    /// Returns buffers that should be fetched from external resources,
+    /// buffers stored in GLB binary chunk or data uri are excluded.
+    pub fn external_buffers(&self) -> Vec<(usize, &str)> {
+        self.buffers
+            .iter()
+            .enumerate()
+            .filter_map(|(index, buffer)| match buffer.uri.as_deref() {
+                Some(uri) if !uri.starts_with("data:") => Some((index, uri)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns root nodes of a scene.
+    /// If document has no scene, nodes that are not a child of any other node are returned.
+    pub fn root_nodes(&self, scene: Option<usize>) -> Result<Vec<usize>, Error> {
+        match scene.or(self.scene) {
+            Some(index) => self
+                .scenes
+                .get(index)
+                .map(|scene| scene.nodes.clone())
+                .ok_or(Error::InvalidGltf(format!("no such scene {}", index))),
+            None => match self.scenes.first() {
+                Some(scene) => Ok(scene.nodes.clone()),
+                None => {
+                    let mut is_child = vec![false; self.nodes.len()];
+                    for node in &self.nodes {
+                        for child in &node.children {
+                            if let Some(is_child) = is_child.get_mut(*child) {
+                                *is_child = true;
+                            }
+                        }
+                    }
+                    Ok((0..self.nodes.len())
+                        .filter(|index| !is_child[*index])
+                        .collect())
+                }
+            },
+        }
+    }
+}
+
+/// Splits a GLB container into JSON chunk and optional binary chunk.
+pub fn parse_glb(bytes: &[u8]) -> Result<(&[u8], Option<&[u8]>), Error> {
+    fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, Error> {
+        bytes
+            .get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or(Error::InvalidGltf(
+                "unexpected end of GLB container".to_string(),
+            ))
+    }
+
+    if read_u32(bytes, 0)? != GLB_MAGIC_NUMBER {
+        return Err(Error::InvalidGltf("invalid GLB magic number".to_string()));
+    }
+    let version = read_u32(bytes, 4)?;
+    if version != GLB_VERSION {
+        return Err(Error::InvalidGltf(format!(
+            "unsupported GLB version {}",
+            version
+        )));
+    }
+    let length = (read_u32(bytes, 8)? as usize).min(bytes.len());
+
+    let mut json = None;
+    let mut binary = None;
+    let mut offset = GLB_HEADER_SIZE;
+    while offset + GLB_CHUNK_HEADER_SIZE <= length {
+        let chunk_length = read_u32(bytes, offset)? as usize;
+        let chunk_type = read_u32(bytes, offset + 4)?;
+        let start = offset + GLB_CHUNK_HEADER_SIZE;
+        let Some(end) = start.checked_add(chunk_length) else {
+            return Err(Error::InvalidGltf(
+                "GLB chunk out of container range".to_string(),
+            ));
+        };
+        let Some(chunk) = bytes.get(start..end) else {
+            return Err(Error::InvalidGltf(
+                "GLB chunk out of container range".to_string(),
+            ));
+        };
+
+        match chunk_type {
+            GLB_CHUNK_TYPE_JSON if json.is_none() => json = Some(chunk),
+            GLB_CHUNK_TYPE_BIN if binary.is_none() => binary = Some(chunk),
+            // unknown chunks must be ignored
+            _ => {}
+        };
+
+        // chunks are 4-byte aligned
+        offset = (end + 3) & !3;
+    }
+
+    match json {
+        Some(json) => Ok((json, binary)),
+        None => Err(Error::InvalidGltf("GLB JSON chunk not found".to_string())),
+    }
+}
+
+/// Returns byte range of the `index`-th element of `length` bytes,
+/// elements start from `offset` and stride `stride` bytes.
+/// Returns `None` if the range overflows.
+fn element_range(
+    offset: usize,
+    index: usize,
+    stride: usize,
+    length: usize,
+) -> Option<Range<usize>> {
+    let start = index.checked_mul(stride)?.checked_add(offset)?;
+    let end = start.checked_add(length)?;
+    Some(start..end)
+}
+
+/// Decodes data of a base64 data uri.
+/// Returns `None` if uri is not a data uri.
+pub fn decode_data_uri(uri: &str) -> Option<Result<Vec<u8>, Error>> {
+    let content = uri.strip_prefix("data:")?;
+    let Some((mime, data)) = content.split_once(',') else {
+        return Some(Err(Error::InvalidGltf("malformed data uri".to_string())));
+    };
+    if !mime.ends_with(";base64") {
+        return Some(Err(Error::InvalidGltf(
+            "only base64 data uri is supported".to_string(),
+        )));
+    }
+
+    let mut decoded = Vec::with_capacity(data.len() / 4 * 3);
+    let mut acc = 0u32;
+    let mut bits = 0u32;
+    for c in data.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            b' ' | b'\t' | b'\r' | b'\n' => continue,
+            _ => {
+                return Some(Err(Error::InvalidGltf(
+                    "invalid base64 character in data uri".to_string(),
+                )))
+            }
+        };
+        acc = (acc << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+
+    Some(Ok(decoded))
+}
+
+/// Resolves an uri referenced by a glTF document against a base url.
+/// Data uri and absolute url are returned as it is.
+pub fn resolve_uri(base_url: Option<&str>, uri: &str) -> String {
+    if uri.starts_with("data:") {
+        return uri.to_string();
+    }
+
+    match base_url.and_then(|base| url::Url::parse(base).ok()) {
+        Some(base) => base
+            .join(uri)
+            .map(|url| url.to_string())
+            .unwrap_or_else(|_| uri.to_string()),
+        None => uri.to_string(),
+    }
+}
+
+/// Tightly packed data read from a glTF accessor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessorData {
+    pub component_type: ComponentType,
+    pub accessor_type: AccessorType,
+    pub normalized: bool,
+    pub count: usize,
+    pub data: Vec<u8>,
+}
+
+impl AccessorData {
+    /// Returns bytes length of an element.
+    pub fn element_byte_length(&self) -> usize {
+        self.component_type.byte_length() * self.accessor_type.component_count()
+    }
+
+    fn component(&self, index: usize) -> f64 {
+        let size = self.component_type.byte_length();
+        let b = &self.data[index * size..(index + 1) * size];
+        match self.component_type {
+            ComponentType::BYTE => {
+                let v = b[0] as i8 as f64;
+                if self.normalized {
+                    (v / 127.0).max(-1.0)
+                } else {
+                    v
+                }
+            }
+            ComponentType::UNSIGNED_BYTE => {
+                let v = b[0] as f64;
+                if self.normalized {
+                    v / 255.0
+                } else {
+                    v
+                }
+            }
+            ComponentType::SHORT => {
+                let v = i16::from_le_bytes([b[0], b[1]]) as f64;
+                if self.normalized {
+                    (v / 32767.0).max(-1.0)
+                } else {
+                    v
+                }
+            }
+            ComponentType::UNSIGNED_SHORT => {
+                let v = u16::from_le_bytes([b[0], b[1]]) as f64;
+                if self.normalized {
+                    v / 65535.0
+                } else {
+                    v
+                }
+            }
+            ComponentType::UNSIGNED_INT => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            ComponentType::FLOAT => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+        }
+    }
+
+    /// Returns all components as float values, normalized if required.
+    pub fn to_f32_vec(&self) -> Vec<f32> {
+        let len = self.count * self.accessor_type.component_count();
+        (0..len).map(|i| self.component(i) as f32).collect()
+    }
+
+    /// Returns all components as unsigned integer values.
+    pub fn to_u32_vec(&self) -> Vec<u32> {
+        let len = self.count * self.accessor_type.component_count();
+        (0..len).map(|i| self.component(i) as u32).collect()
+    }
+}
+
+/// A glTF document with all buffers resolved.
+pub struct Gltf {
+    document: Document,
+    buffers: Vec<Vec<u8>>,
+    base_url: Option<String>,
+}
+
+impl Gltf {
+    /// Constructs a glTF by a parsed [`Document`], an optional GLB binary chunk
+    /// and buffers fetched from external resources keyed by buffer index.
+    pub fn new(
+        document: Document,
+        binary: Option<Vec<u8>>,
+        mut external_buffers: HashMap<usize, Vec<u8>>,
+    ) -> Result<Self, Error> {
+        let mut binary = binary;
+        let mut buffers = Vec::with_capacity(document.buffers.len());
+        for (index, buffer) in document.buffers.iter().enumerate() {
+            let data = match buffer.uri.as_deref() {
+                None => binary.take().ok_or(Error::InvalidGltf(format!(
+                    "buffer {} refers to a missing GLB binary chunk",
+                    index
+                )))?,
+                Some(uri) => match decode_data_uri(uri) {
+                    Some(data) => data?,
+                    None => external_buffers
+                        .remove(&index)
+                        .ok_or(Error::InvalidGltf(format!(
+                            "external buffer {} is not provided",
+                            index
+                        )))?,
+                },
+            };
+
+            if data.len() < buffer.byte_length {
+                return Err(Error::InvalidGltf(format!(
+                    "buffer {} is shorter than declared byte length",
+                    index
+                )));
+            }
+            buffers.push(data);
+        }
+
+        Ok(Self {
+            document,
+            buffers,
+            base_url: None,
+        })
+    }
+
+    /// Parses a self-contained glTF from raw bytes,
+    /// all buffers should be stored either in GLB binary chunk or in data uri.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, Error> {
+        let (document, binary) = Document::from_slice(bytes)?;
+        Self::new(document, binary, HashMap::new())
+    }
+
+    /// Returns glTF [`Document`].
+    pub fn document(&self) -> &Document {
+        &self.document
+    }
+
+    /// Returns buffer data by buffer index.
+    pub fn buffer(&self, index: usize) -> Option<&[u8]> {
+        self.buffers.get(index).map(|buffer| buffer.as_slice())
+    }
+
+    /// Returns base url for resolving relative uris.
+    pub fn base_url(&self) -> Option<&str> {
+        self.base_url.as_deref()
+    }
+
+    /// Sets base url for resolving relative uris.
+    pub fn set_base_url(&mut self, base_url: Option<String>) {
+        self.base_url = base_url;
+    }
+
+    /// Returns data of a buffer view.
+    pub fn buffer_view(&self, index: usize) -> Result<&[u8], Error> {
+        let view = self
+            .document
+            .buffer_views
+            .get(index)
+            .ok_or(Error::InvalidGltf(format!("no such buffer view {}", index)))?;
+        self.buffers
+            .get(view.buffer)
+            .and_then(|buffer| {
+                let end = view.byte_offset.checked_add(view.byte_length)?;
+                buffer.get(view.byte_offset..end)
+            })
+            .ok_or(Error::InvalidGltf(format!(
+                "buffer view {} out of buffer range",
+                index
+            )))
+    }
+
+    /// Reads an accessor into a tightly packed [`AccessorData`],
+    /// byte stride and sparse storage are resolved.
+    pub fn read_accessor(&self, index: usize) -> Result<AccessorData, Error> {
+        let accessor = self
+            .document
+            .accessors
+            .get(index)
+            .ok_or(Error::InvalidGltf(format!("no such accessor {}", index)))?;
+        let element_byte_length = accessor.element_byte_length();
+        let data_byte_length = accessor
+            .count
+            .checked_mul(element_byte_length)
+            .ok_or(Error::InvalidGltf(format!("accessor {} too large", index)))?;
+
+        let mut data = match accessor.buffer_view {
+            Some(view_index) => {
+                let view = self.buffer_view(view_index)?;
+                let stride = self.document.buffer_views[view_index]
+                    .byte_stride
+                    .unwrap_or(element_byte_length);
+                // ensures the last element is inside buffer view before allocating
+                if accessor.count > 0
+                    && element_range(
+                        accessor.byte_offset,
+                        accessor.count - 1,
+                        stride,
+                        element_byte_length,
+                    )
+                    .map_or(true, |range| range.end > view.len())
+                {
+                    return Err(Error::InvalidGltf(format!(
+                        "accessor {} out of buffer view range",
+                        index
+                    )));
+                }
+
+                let mut data = Vec::with_capacity(data_byte_length);
+                for i in 0..accessor.count {
+                    let Some(element) =
+                        element_range(accessor.byte_offset, i, stride, element_byte_length)
+                            .and_then(|range| view.get(range))
+                    else {
+                        return Err(Error::InvalidGltf(format!(
+                            "accessor {} out of buffer view range",
+                            index
+                        )));
+                    };
+                    data.extend_from_slice(element);
+                }
+                data
+            }
+            // accessor without buffer view must be initialized with zeros
+            None => vec![0u8; data_byte_length],
+        };
+
+        if let Some(sparse) = &accessor.sparse {
+            let indices = self.buffer_view(sparse.indices.buffer_view)?;
+            let values = self.buffer_view(sparse.values.buffer_view)?;
+            let index_byte_length = sparse.indices.component_type.byte_length();
+            for i in 0..sparse.count {
+                let Some(b) = element_range(
+                    sparse.indices.byte_offset,
+                    i,
+                    index_byte_length,
+                    index_byte_length,
+                )
+                .and_then(|range| indices.get(range)) else {
+                    return Err(Error::InvalidGltf(format!(
+                        "sparse indices of accessor {} out of range",
+                        index
+                    )));
+                };
+                let target = match sparse.indices.component_type {
+                    ComponentType::UNSIGNED_BYTE => b[0] as usize,
+                    ComponentType::UNSIGNED_SHORT => u16::from_le_bytes([b[0], b[1]]) as usize,
+                    ComponentType::UNSIGNED_INT => {
+                        u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize
+                    }
+                    _ => {
+                        return Err(Error::InvalidGltf(format!(
+                            "invalid sparse indices component type of accessor {}",
+                            index
+                        )))
+                    }
+                };
+
+                let (Some(value), Some(dst)) = (
+                    element_range(
+                        sparse.values.byte_offset,
+                        i,
+                        element_byte_length,
+                        element_byte_length,
+                    )
+                    .and_then(|range| values.get(range)),
+                    element_range(0, target, element_byte_length, element_byte_length)
+                        .and_then(|range| data.get_mut(range)),
+                ) else {
+                    return Err(Error::InvalidGltf(format!(
+                        "sparse values of accessor {} out of range",
+                        index
+                    )));
+                };
+                dst.copy_from_slice(value);
+            }
+        }
+
+        Ok(AccessorData {
+            component_type: accessor.component_type,
+            accessor_type: accessor.accessor_type,
+            normalized: accessor.normalized,
+            count: accessor.count,
+            data,
+        })
+    }
+
+    /// Returns an url of an image that could be used as the source of a [`TextureLoader`].
+    /// Images stored in buffer view are wrapped into a [`Blob`] object url.
+    pub fn image_url(&self, index: usize) -> Result<String, Error> {
+        let image = self
+            .document
+            .images
+            .get(index)
+            .ok_or(Error::InvalidGltf(format!("no such image {}", index)))?;
+        match (&image.uri, image.buffer_view) {
+            (Some(uri), _) => Ok(resolve_uri(self.base_url(), uri)),
+            (None, Some(view)) => {
+                let data = self.buffer_view(view)?;
+                let parts = Array::of1(&Uint8Array::from(data));
+                let blob = Blob::new_with_u8_array_sequence(&parts).map_err(js_error)?;
+                Url::create_object_url_with_blob(&blob).map_err(js_error)
+            }
+            (None, None) => Err(Error::InvalidGltf(format!(
+                "image {} has neither uri nor buffer view",
+                index
+            ))),
+        }
+    }
+
+    /// Builds a [`SimpleGroup`] hierarchy from a scene.
+    /// Uses default scene if `scene` is `None`.
+    ///
+    /// Each node is built into a [`SimpleGroup`] with node transformation as its model matrix,
+    /// and each primitive of the node mesh is built into a [`SimpleEntity`] under the group.
+    pub fn build_scene(&self, scene: Option<usize>) -> Result<SimpleGroup, Error> {
+        let mut builder = SceneBuilder {
+            gltf: self,
+            buffers: HashMap::new(),
+        };
+
+        let mut root = SimpleGroup::new();
+        for node in self.document.root_nodes(scene)? {
+            let group = builder.build_node(node, 0)?;
+            root.add_sub_group(group);
+        }
+
+        Ok(root)
+    }
+}
+
+fn js_error(err: JsValue) -> Error {
+    Error::CommonError(err.as_error().and_then(|err| err.message().as_string()))
+}
+
+/// Maximum depth of node hierarchy, preventing from cyclic node references.
+const MAX_NODE_DEPTH: usize = 256;
+
+struct SceneBuilder<'a> {
+    gltf: &'a Gltf,
+    // buffers are cached by accessor index and shared by primitives
+    buffers: HashMap<usize, (buffer::Buffer, AccessorData)>,
+}
+
+impl<'a> SceneBuilder<'a> {
+    fn build_node(&mut self, index: usize, depth: usize) -> Result<SimpleGroup, Error> {
+        if depth > MAX_NODE_DEPTH {
+            return Err(Error::InvalidGltf(
+                "node hierarchy too deep or cyclic".to_string(),
+            ));
+        }
+
+        let node = self
+            .gltf
+            .document
+            .nodes
+            .get(index)
+            .ok_or(Error::InvalidGltf(format!("no such node {}", index)))?;
+
+        let mut group = SimpleGroup::new();
+        group.set_model_matrix(node.model_matrix());
+
+        if let Some(mesh) = node.mesh {
+            let mesh = self
+                .gltf
+                .document
+                .meshes
+                .get(mesh)
+                .ok_or(Error::InvalidGltf(format!("no such mesh {}", mesh)))?;
+            for primitive in &mesh.primitives {
+                group.add_entity(self.build_primitive(primitive)?);
+            }
+        }
+
+        for child in &node.children {
+            group.add_sub_group(self.build_node(*child, depth + 1)?);
+        }
+
+        Ok(group)
+    }
+
+    fn accessor_buffer(
+        &mut self,
+        accessor: usize,
+    ) -> Result<&(buffer::Buffer, AccessorData), Error> {
+        if !self.buffers.contains_key(&accessor) {
+            let data = self.gltf.read_accessor(accessor)?;
+            let buffer = buffer::Builder::new(BufferUsage::STATIC_DRAW)
+                .buffer_data(data.data.clone())
+                .build();
+            self.buffers.insert(accessor, (buffer, data));
+        }
+
+        Ok(self.buffers.get(&accessor).unwrap())
+    }
+
+    fn build_primitive(&mut self, primitive: &Primitive) -> Result<SimpleEntity, Error> {
+        let material = primitive
+            .material
+            .and_then(|material| self.gltf.document.materials.get(material));
+        let double_sided = material.map(|m| m.double_sided).unwrap_or(false);
+
+        let mut geometry = GltfGeometry {
+            draw_mode: primitive.draw_mode()?,
+            count: 0,
+            double_sided,
+            attributes: HashMap::new(),
+            indices: None,
+            bounding_volume: None,
+            channel: channel(),
+        };
+
+        for (semantic, accessor) in &primitive.attributes {
+            let (buffer, data) = self.accessor_buffer(*accessor)?;
+            let Some(component_size) = data.accessor_type.buffer_component_size() else {
+                continue;
+            };
+
+            if semantic == "POSITION" {
+                geometry.count = data.count;
+                geometry.bounding_volume = Some(build_bounding_volume(
+                    &self.gltf.document.accessors[*accessor],
+                    data,
+                ));
+            }
+
+            geometry.attributes.insert(
+                semantic.clone(),
+                GltfAttribute {
+                    buffer: buffer.clone(),
+                    component_size,
+                    data_type: data.component_type.buffer_data_type(),
+                    normalized: data.normalized,
+                },
+            );
+        }
+
+        if let Some(indices) = primitive.indices {
+            let (buffer, data) = self.accessor_buffer(indices)?;
+            let Some(data_type) = data.component_type.element_indices_data_type() else {
+                return Err(Error::InvalidGltf(format!(
+                    "invalid indices component type of accessor {}",
+                    indices
+                )));
+            };
+            geometry.count = data.count;
+            geometry.indices = Some((buffer.clone(), data_type));
+        }
+
+        let mut entity = SimpleEntity::new();
+        entity.set_geometry(Some(geometry));
+        match material {
+            Some(material) => self.apply_material(&mut entity, material)?,
+            None => {
+                entity.set_material(Some(SolidColorMaterial::with_color(
+                    Vec3::<f32>::new(1.0, 1.0, 1.0),
+                    128.0,
+                    Transparency::Opaque,
+                )));
+            }
+        };
+
+        Ok(entity)
+    }
+
+    fn texture_loader(&self, info: &TextureInfo, is_srgb: bool) -> Result<TextureLoader, Error> {
+        let texture = self
+            .gltf
+            .document
+            .textures
+            .get(info.index)
+            .ok_or(Error::InvalidGltf(format!(
+                "no such texture {}",
+                info.index
+            )))?;
+        let source = texture.source.ok_or(Error::InvalidGltf(format!(
+            "texture {} has no image source",
+            info.index
+        )))?;
+        let sampler_params = texture
+            .sampler
+            .and_then(|sampler| self.gltf.document.samplers.get(sampler))
+            .map(|sampler| sampler.sampler_parameters())
+            .unwrap_or_default();
+
+        Ok(TextureLoader::with_params(
+            self.gltf.image_url(source)?,
+            [],
+            sampler_params,
+            [],
+            true,
+            is_srgb,
+        ))
+    }
+
+    fn apply_material(&self, entity: &mut SimpleEntity, material: &Material) -> Result<(), Error> {
+        let (base_color_factor, base_color_texture) = match &material.pbr_metallic_roughness {
+            Some(pbr) => (pbr.base_color_factor, pbr.base_color_texture.as_ref()),
+            None => (default_base_color_factor(), None),
+        };
+        let transparency = match material.alpha_mode {
+            AlphaMode::OPAQUE | AlphaMode::MASK => Transparency::Opaque,
+            AlphaMode::BLEND => {
+                let alpha = base_color_factor[3] as f32;
+                if alpha >= 1.0 {
+                    Transparency::Opaque
+                } else if alpha <= 0.0 {
+                    Transparency::Transparent
+                } else {
+                    Transparency::Translucent(alpha)
+                }
+            }
+        };
+
+        match base_color_texture {
+            Some(info) => {
+                let mut builder = texture::Builder::new(self.texture_loader(info, true)?)
+                    .set_transparency(transparency);
+                if let Some(normal) = &material.normal_texture {
+                    builder = builder.set_normal_map(self.texture_loader(normal, false)?);
+                }
+                entity.set_material(Some(builder.build()));
+            }
+            None => {
+                entity.set_material(Some(SolidColorMaterial::with_color(
+                    Vec3::<f32>::new(
+                        base_color_factor[0] as f32,
+                        base_color_factor[1] as f32,
+                        base_color_factor[2] as f32,
+                    ),
+                    128.0,
+                    transparency,
+                )));
+            }
+        };
+
+        Ok(())
+    }
+}
+
+fn build_bounding_volume(accessor: &Accessor, data: &AccessorData) -> BoundingVolume {
+    let (min, max) = match (accessor.min.as_deref(), accessor.max.as_deref()) {
+        (Some([min_x, min_y, min_z, ..]), Some([max_x, max_y, max_z, ..])) => {
+            ([*min_x, *min_y, *min_z], [*max_x, *max_y, *max_z])
+        }
+        _ => {
+            let mut min = [f64::MAX; 3];
+            let mut max = [f64::MIN; 3];
+            for position in data.to_f32_vec().chunks_exact(3) {
+                for i in 0..3 {
+                    min[i] = min[i].min(position[i] as f64);
+                    max[i] = max[i].max(position[i] as f64);
+                }
+            }
+            if data.count == 0 {
+                ([0.0; 3], [0.0; 3])
+            } else {
+                (min, max)
+            }
+        }
+    };
+
+    BoundingVolume::AxisAlignedBoundingBox {
+        min_x: min[0],
+        max_x: max[0],
+        min_y: min[1],
+        max_y: max[1],
+        min_z: min[2],
+        max_z: max[2],
+    }
+}
+
+struct GltfAttribute {
+    buffer: buffer::Buffer,
+    component_size: BufferComponentSize,
+    data_type: BufferDataType,
+    normalized: bool,
+}
+
+/// A geometry built from a glTF mesh primitive.
+///
+/// Vertex attributes are keyed by glTF attribute semantic, such as `POSITION` or `TEXCOORD_0`,
+/// and attributes without a standard binding could be accessed by semantic name
+/// through [`Geometry::attribute_value`].
+pub struct GltfGeometry {
+    draw_mode: DrawMode,
+    count: usize,
+    double_sided: bool,
+    attributes: HashMap<String, GltfAttribute>,
+    indices: Option<(buffer::Buffer, ElementIndicesDataType)>,
+    bounding_volume: Option<BoundingVolume>,
+    channel: (Sender<GeometryMessage>, Receiver<GeometryMessage>),
+}
+
+impl GltfGeometry {
+    fn attribute(&self, semantic: &str) -> Option<AttributeValue<'_>> {
+        self.attributes
+            .get(semantic)
+            .map(|attribute| AttributeValue::ArrayBuffer {
+                buffer: Readonly::Borrowed(&attribute.buffer),
+                component_size: attribute.component_size,
+                data_type: attribute.data_type,
+                normalized: attribute.normalized,
+                bytes_stride: 0,
+                byte_offset: 0,
+            })
+    }
+
+    /// Returns `true` if this geometry is drawn with indices.
+    pub fn is_indexed(&self) -> bool {
+        self.indices.is_some()
+    }
+}
+
+impl Geometry for GltfGeometry {
+    fn draw_mode(&self) -> DrawMode {
+        self.draw_mode
+    }
+
+    fn draw_range(&self) -> Range<usize> {
+        0..self.count
+    }
+
+    fn cull_face(&self) -> Option<CullFace> {
+        if self.double_sided {
+            None
+        } else {
+            Some(CullFace::BACK)
+        }
+    }
+
+    fn bounding_volume(&self) -> Option<Readonly<'_, BoundingVolume>> {
+        self.bounding_volume
+            .as_ref()
+            .map(|bounding| Readonly::Borrowed(bounding))
+    }
+
+    fn positions(&self) -> Option<AttributeValue<'_>> {
+        self.attribute("POSITION")
+    }
+
+    fn normals(&self) -> Option<AttributeValue<'_>> {
+        self.attribute("NORMAL")
+    }
+
+    fn tangents(&self) -> Option<AttributeValue<'_>> {
+        self.attribute("TANGENT")
+    }
+
+    fn bitangents(&self) -> Option<AttributeValue<'_>> {
+        None
+    }
+
+    fn texture_coordinates(&self) -> Option<AttributeValue<'_>> {
+        self.attribute("TEXCOORD_0")
+    }
+
+    fn attribute_value(&self, name: &str) -> Option<AttributeValue<'_>> {
+        self.attribute(name)
+    }
+
+    fn uniform_value(&self, _: &str) -> Option<UniformValue<'_>> {
+        None
+    }
+
+    fn uniform_block_value(&self, _: &str) -> Option<UniformBlockValue<'_>> {
+        None
+    }
+
+    fn tick(&mut self, _: &Tick) {}
+
+    fn changed(&self) -> Receiver<GeometryMessage> {
+        self.channel.1.clone()
+    }
+
+    fn as_indexed_geometry(&self) -> Option<&dyn IndexedGeometry> {
+        if self.indices.is_some() {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl IndexedGeometry for GltfGeometry {
+    fn indices(&self) -> Readonly<'_, buffer::Buffer> {
+        Readonly::Borrowed(&self.indices.as_ref().unwrap().0)
+    }
+
+    fn indices_data_type(&self) -> ElementIndicesDataType {
+        self.indices.as_ref().unwrap().1
+    }
+
+    fn indices_range(&self) -> Option<Range<usize>> {
+        None
+    }
+}
+
+/// A loader loads glTF 2.0 JSON or GLB container from url
+/// and builds a [`SimpleGroup`] hierarchy from the default scene.
+pub struct GltfLoader {
+    url: String,
+    status: *mut LoaderStatus,
+    channel: (Sender<LoaderStatus>, Receiver<LoaderStatus>),
+    gltf: *mut Option<Gltf>,
+    error: *mut Option<Error>,
+
+    scene: Option<usize>,
+
+    promise: *mut Option<Promise>,
+}
+
+impl Drop for GltfLoader {
+    fn drop(&mut self) {
+        unsafe {
+            drop(Box::from_raw(self.status));
+            drop(Box::from_raw(self.gltf));
+            drop(Box::from_raw(self.error));
+            drop(Box::from_raw(self.promise));
+        }
+    }
+}
+
+impl GltfLoader {
+    /// Constructs a new glTF loader.
+    pub fn new<S>(url: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::with_scene(url, None)
+    }
+
+    /// Constructs a new glTF loader building a specified scene.
+    pub fn with_scene<S>(url: S, scene: Option<usize>) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            url: url.into(),
+            status: Box::leak(Box::new(LoaderStatus::Unload)),
+            channel: channel(),
+            gltf: Box::leak(Box::new(None)),
+            error: Box::leak(Box::new(None)),
+
+            scene,
+
+            promise: Box::leak(Box::new(None)),
+        }
+    }
+
+    async fn fetch_array_buffer(url: &str) -> Result<Vec<u8>, Error> {
+        let mut opts = RequestInit::new();
+        opts.method("GET");
+
+        let request = Request::new_with_str_and_init(url, &opts).map_err(js_error)?;
+        let response = JsFuture::from(window().fetch_with_request(&request))
+            .await
+            .map_err(js_error)?
+            .dyn_into::<Response>()
+            .unwrap();
+        if !response.ok() {
+            return Err(Error::CommonError(Some(format!(
+                "failed to fetch {}, status {}",
+                url,
+                response.status()
+            ))));
+        }
+
+        let array_buffer = JsFuture::from(response.array_buffer().map_err(js_error)?)
+            .await
+            .map_err(js_error)?
+            .dyn_into::<ArrayBuffer>()
+            .unwrap();
+
+        Ok(Uint8Array::new(&array_buffer).to_vec())
+    }
+
+    async fn fetch_gltf(url: String) -> Result<Gltf, Error> {
+        let base_url = document()
+            .url()
+            .ok()
+            .map(|document_url| resolve_uri(Some(&document_url), &url));
+
+        let bytes = Self::fetch_array_buffer(&url).await?;
+        let (document, binary) = Document::from_slice(&bytes)?;
+
+        let mut external_buffers = HashMap::new();
+        for (index, uri) in document.external_buffers() {
+            let uri = resolve_uri(base_url.as_deref(), uri);
+            external_buffers.insert(index, Self::fetch_array_buffer(&uri).await?);
+        }
+
+        let mut gltf = Gltf::new(document, binary, external_buffers)?;
+        gltf.set_base_url(base_url);
+        Ok(gltf)
+    }
+
+    /// Starts loading glTF.
+    /// This method does nothing if glTF is not in [`LoaderStatus::Unload`] status.
+    pub fn load(&self) {
+        unsafe {
+            if LoaderStatus::Unload != *self.status {
+                return;
+            }
+
+            let status = self.status;
+            let gltf = self.gltf;
+            let error = self.error;
+            let sender = self.channel.0.clone();
+            let url = self.url.clone();
+
+            (*self.status) = LoaderStatus::Loading;
+            self.channel.0.send(*self.status);
+
+            let promise = wasm_bindgen_futures::future_to_promise(async move {
+                match Self::fetch_gltf(url).await {
+                    Ok(parsed) => {
+                        (*status) = LoaderStatus::Loaded;
+                        (*gltf) = Some(parsed);
+                        sender.send(*status);
+                        Ok(JsValue::undefined())
+                    }
+                    Err(err) => {
+                        (*status) = LoaderStatus::Errored;
+                        (*error) = Some(err);
+                        sender.send(*status);
+                        Err(JsValue::undefined())
+                    }
+                }
+            });
+            (*self.promise) = Some(promise);
+        }
+    }
+
+    /// Starts loading glTF and puts it into a [`Promise`].
+    /// This method does nothing if glTF is not in [`LoaderStatus::Unload`] status.
+    pub fn load_promise(&self) -> Promise {
+        unsafe {
+            self.load();
+            (*self.promise).clone().unwrap()
+        }
+    }
+
+    /// Starts loading glTF and asynchronous awaiting.
+    /// This method does nothing if glTF is not in [`LoaderStatus::Unload`] status.
+    pub async fn load_async(&self) -> Result<&Gltf, Error> {
+        unsafe {
+            self.load();
+            match JsFuture::from((*self.promise).clone().unwrap()).await {
+                Ok(_) => Ok((*self.gltf).as_ref().unwrap()),
+                Err(_) => Err((*self.error).clone().unwrap()),
+            }
+        }
+    }
+
+    /// Returns [`Gltf`] regardless whether successfully loaded or not.
+    pub fn gltf(&self) -> Option<&Gltf> {
+        unsafe { (*self.gltf).as_ref() }
+    }
+
+    /// Returns [`Gltf`] if successfully loaded.
+    pub fn loaded_gltf(&self) -> Option<&Gltf> {
+        unsafe {
+            match &*self.status {
+                LoaderStatus::Unload | LoaderStatus::Loading | LoaderStatus::Errored => None,
+                LoaderStatus::Loaded => (*self.gltf).as_ref(),
+            }
+        }
+    }
+
+    /// Returns glTF source url.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl Loader<SimpleGroup> for GltfLoader {
+    type Failure = Error;
+
+    fn status(&self) -> LoaderStatus {
+        unsafe { *self.status }
+    }
+
+    fn load(&mut self) {
+        Self::load(&self);
+    }
+
+    fn loaded(&self) -> Result<SimpleGroup, Error> {
+        unsafe {
+            if let Some(err) = &*self.error {
+                return Err(err.clone());
+            }
+
+            (*self.gltf).as_ref().unwrap().build_scene(self.scene)
+        }
+    }
+
+    fn success(&self) -> Receiver<LoaderStatus> {
+        self.channel.1.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hashbrown::HashMap;
+
+    use super::{
+        decode_data_uri, parse_glb, AccessorType, ComponentType, Document, Gltf,
+        GLB_CHUNK_TYPE_BIN, GLB_CHUNK_TYPE_JSON, GLB_MAGIC_NUMBER,
+    };
+
+    const TRIANGLE: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "mesh": 0, "translation": [1.0, 2.0, 3.0], "children": [1] }, { "mesh": 0 }],
+        "meshes": [{ "primitives": [{ "attributes": { "POSITION": 1 }, "indices": 0 }] }],
+        "buffers": [{ "byteLength": 44 }],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 6 },
+            { "buffer": 0, "byteOffset": 8, "byteLength": 36 }
+        ],
+        "accessors": [
+            { "bufferView": 0, "componentType": 5123, "count": 3, "type": "SCALAR" },
+            { "bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0] }
+        ]
+    }"#;
+
+    fn triangle_binary() -> Vec<u8> {
+        let mut binary = Vec::new();
+        for index in [0u16, 1, 2] {
+            binary.extend_from_slice(&index.to_le_bytes());
+        }
+        binary.extend_from_slice(&[0, 0]);
+        for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            binary.extend_from_slice(&value.to_le_bytes());
+        }
+        binary
+    }
+
+    fn build_glb(json: &str, binary: &[u8]) -> Vec<u8> {
+        let mut json = json.as_bytes().to_vec();
+        while json.len() % 4 != 0 {
+            json.push(b' ');
+        }
+
+        let length = 12 + 8 + json.len() + 8 + binary.len();
+        let mut glb = Vec::with_capacity(length);
+        glb.extend_from_slice(&GLB_MAGIC_NUMBER.to_le_bytes());
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&(length as u32).to_le_bytes());
+        glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&GLB_CHUNK_TYPE_JSON.to_le_bytes());
+        glb.extend_from_slice(&json);
+        glb.extend_from_slice(&(binary.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&GLB_CHUNK_TYPE_BIN.to_le_bytes());
+        glb.extend_from_slice(binary);
+        glb
+    }
+
+    #[test]
+    fn test_parse_glb() {
+        let binary = triangle_binary();
+        let glb = build_glb(TRIANGLE, &binary);
+
+        let (json, bin) = parse_glb(&glb).unwrap();
+        assert!(json.starts_with(b"{"));
+        assert_eq!(Some(binary.as_slice()), bin);
+
+        let gltf = Gltf::from_slice(&glb).unwrap();
+        assert_eq!(2, gltf.document().nodes.len());
+        assert_eq!(vec![0], gltf.document().root_nodes(None).unwrap());
+        assert_eq!(Some(binary.as_slice()), gltf.buffer(0));
+    }
+
+    #[test]
+    fn test_parse_invalid_glb() {
+        let glb = build_glb(TRIANGLE, &triangle_binary());
+        assert!(parse_glb(&glb[..16]).is_err());
+        assert!(parse_glb(&[0u8; 12]).is_err());
+
+        // chunk length overflows the container
+        let mut overflowed = glb.clone();
+        overflowed[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(parse_glb(&overflowed).is_err());
+        assert!(Document::from_slice(br#"{ "asset": { "version": "1.0" } }"#).is_err());
+    }
+
+    #[test]
+    fn test_read_accessor() {
+        let glb = build_glb(TRIANGLE, &triangle_binary());
+        let gltf = Gltf::from_slice(&glb).unwrap();
+
+        let indices = gltf.read_accessor(0).unwrap();
+        assert_eq!(ComponentType::UNSIGNED_SHORT, indices.component_type);
+        assert_eq!(vec![0, 1, 2], indices.to_u32_vec());
+
+        let positions = gltf.read_accessor(1).unwrap();
+        assert_eq!(AccessorType::VEC3, positions.accessor_type);
+        assert_eq!(
+            vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            positions.to_f32_vec()
+        );
+    }
+
+    #[test]
+    fn test_read_strided_and_sparse_accessor() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [{ "uri": "data:application/octet-stream;base64,AQIDBAUGBwgBCQo=", "byteLength": 11 }],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 8, "byteStride": 4 },
+                { "buffer": 0, "byteOffset": 8, "byteLength": 1 },
+                { "buffer": 0, "byteOffset": 9, "byteLength": 2 }
+            ],
+            "accessors": [
+                { "bufferView": 0, "componentType": 5121, "count": 2, "type": "VEC2" },
+                { "bufferView": 0, "componentType": 5121, "count": 2, "type": "VEC2",
+                  "sparse": { "count": 1,
+                              "indices": { "bufferView": 1, "componentType": 5121 },
+                              "values": { "bufferView": 2 } } }
+            ]
+        }"#;
+        let gltf = Gltf::from_slice(json.as_bytes()).unwrap();
+
+        assert_eq!(vec![1, 2, 5, 6], gltf.read_accessor(0).unwrap().data);
+        assert_eq!(vec![1, 2, 9, 10], gltf.read_accessor(1).unwrap().data);
+    }
+
+    #[test]
+    fn test_read_overflowing_accessor() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [{ "uri": "data:application/octet-stream;base64,AQIDBAUGBwgBCQo=", "byteLength": 11 }],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 8, "byteStride": 4 },
+                { "buffer": 0, "byteOffset": 18446744073709551615, "byteLength": 1 }
+            ],
+            "accessors": [
+                { "bufferView": 0, "componentType": 5121, "count": 18446744073709551615, "type": "VEC2" },
+                { "bufferView": 0, "componentType": 5121, "count": 1000000000, "type": "VEC2" },
+                { "bufferView": 0, "byteOffset": 18446744073709551615, "componentType": 5121, "count": 1, "type": "VEC2" },
+                { "bufferView": 1, "componentType": 5121, "count": 1, "type": "SCALAR" }
+            ]
+        }"#;
+        let gltf = Gltf::from_slice(json.as_bytes()).unwrap();
+
+        assert!(gltf.buffer_view(1).is_err());
+        for index in 0..4 {
+            assert!(gltf.read_accessor(index).is_err());
+        }
+    }
+
+    #[test]
+    fn test_external_buffers() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [{ "uri": "triangle.bin", "byteLength": 4 }]
+        }"#;
+        let (document, binary) = Document::from_slice(json.as_bytes()).unwrap();
+        assert_eq!(vec![(0, "triangle.bin")], document.external_buffers());
+        assert!(Gltf::new(document.clone(), binary.clone(), HashMap::new()).is_err());
+
+        let mut external_buffers = HashMap::new();
+        external_buffers.insert(0, vec![1, 2, 3, 4]);
+        let gltf = Gltf::new(document, binary, external_buffers).unwrap();
+        assert_eq!(Some([1u8, 2, 3, 4].as_slice()), gltf.buffer(0));
+    }
+
+    #[test]
+    fn test_decode_data_uri() {
+        assert_eq!(
+            b"hello".to_vec(),
+            decode_data_uri("data:application/octet-stream;base64,aGVsbG8=")
+                .unwrap()
+                .unwrap()
+        );
+        assert!(decode_data_uri("triangle.bin").is_none());
+        assert!(decode_data_uri("data:text/plain,hello").unwrap().is_err());
+    }
+}
@@ -0,0 +1,778 @@
+use js_sys::Promise;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    js_sys::{ArrayBuffer, Float32Array, Uint16Array, Uint8Array},
+    Request, RequestInit, Response,
+};
+
+use crate::{
+    error::Error,
+    message::{channel, Receiver, Sender},
+    renderer::webgl::texture::{
+        Builder, SamplerParameter, Texture, Texture2D, TextureData, TextureInternalFormat,
+        TextureParameter, TexturePixelStorage, TextureSource, TextureUncompressedData,
+        TextureUncompressedInternalFormat, TextureUncompressedPixelDataType,
+        TextureUncompressedPixelFormat,
+    },
+    window,
+};
+
+use super::{Loader, LoaderStatus};
+
+/// Supported pixel format of Radiance HDR file.
+pub const RADIANCE_HDR_FORMAT_RGBE: &'static str = "32-bit_rle_rgbe";
+
+/// Decodes a RGBE pixel into linear RGB values.
+pub fn decode_rgbe(rgbe: [u8; 4]) -> [f32; 3] {
+    let [r, g, b, e] = rgbe;
+    if e == 0 {
+        return [0.0, 0.0, 0.0];
+    }
+
+    // 2^(e - 128) / 256
+    let f = 2.0f32.powi(e as i32 - 136);
+    [r as f32 * f, g as f32 * f, b as f32 * f]
+}
+
+/// Converts a single precision float into half precision float bits.
+/// Values out of range are clamped to infinity and NaN is preserved.
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7fffff;
+
+    // infinity and NaN
+    if exponent == 0xff {
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        // overflows to infinity
+        sign | 0x7c00
+    } else if exponent <= 0 {
+        // underflows to zero or subnormal
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x800000;
+        let shift = (14 - exponent) as u32;
+        let half = mantissa >> shift;
+        let round = (mantissa >> (shift - 1)) & 1;
+        sign | (half + round) as u16
+    } else {
+        // rounding carry may overflow into exponent, which is still correct
+        let half = ((exponent as u32) << 10) | (mantissa >> 13);
+        let round = (mantissa >> 12) & 1;
+        sign | (half + round) as u16
+    }
+}
+
+/// Decodes a RGBE scanline starting from `offset` into `out`,
+/// supporting flat, old style run length encoded and new style run length encoded scanlines.
+///
+/// Returns byte offset of next scanline.
+pub fn decode_scanline(bytes: &[u8], offset: usize, out: &mut [[u8; 4]]) -> Result<usize, Error> {
+    let width = out.len();
+    let eof = || Error::InvalidRadianceHdr("unexpected end of scanline".to_string());
+
+    let header = bytes.get(offset..offset + 4).ok_or_else(eof)?;
+    let is_new_rle =
+        width >= 8 && width <= 0x7fff && header[0] == 2 && header[1] == 2 && header[2] & 0x80 == 0;
+
+    if !is_new_rle {
+        let mut cursor = offset;
+        let mut shift = 0;
+        let mut x = 0;
+        while x < width {
+            let pixel = bytes.get(cursor..cursor + 4).ok_or_else(eof)?;
+            cursor += 4;
+
+            if pixel[0] == 1 && pixel[1] == 1 && pixel[2] == 1 {
+                // old style run length, repeats previous pixel
+                if x == 0 {
+                    return Err(Error::InvalidRadianceHdr(
+                        "run length at start of scanline".to_string(),
+                    ));
+                }
+                // consecutive run lengths accumulate into higher bytes
+                if shift >= usize::BITS - 8 {
+                    return Err(Error::InvalidRadianceHdr(
+                        "too many consecutive run lengths".to_string(),
+                    ));
+                }
+                let count = (pixel[3] as usize) << shift;
+                if x + count > width {
+                    return Err(Error::InvalidRadianceHdr(
+                        "run length overflows scanline".to_string(),
+                    ));
+                }
+                let previous = out[x - 1];
+                out[x..x + count].fill(previous);
+                x += count;
+                shift += 8;
+            } else {
+                out[x] = [pixel[0], pixel[1], pixel[2], pixel[3]];
+                x += 1;
+                shift = 0;
+            }
+        }
+        return Ok(cursor);
+    }
+
+    let encoded_width = ((header[2] as usize) << 8) | header[3] as usize;
+    if encoded_width != width {
+        return Err(Error::InvalidRadianceHdr(format!(
+            "scanline width {} mismatches image width {}",
+            encoded_width, width
+        )));
+    }
+
+    // new style run length, each channel encoded separately
+    let mut cursor = offset + 4;
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = *bytes.get(cursor).ok_or_else(eof)? as usize;
+            cursor += 1;
+
+            if count > 128 {
+                let count = count - 128;
+                if x + count > width {
+                    return Err(Error::InvalidRadianceHdr(
+                        "run length overflows scanline".to_string(),
+                    ));
+                }
+                let value = *bytes.get(cursor).ok_or_else(eof)?;
+                cursor += 1;
+                for pixel in &mut out[x..x + count] {
+                    pixel[channel] = value;
+                }
+                x += count;
+            } else {
+                if count == 0 || x + count > width {
+                    return Err(Error::InvalidRadianceHdr(
+                        "invalid literal run in scanline".to_string(),
+                    ));
+                }
+                let values = bytes.get(cursor..cursor + count).ok_or_else(eof)?;
+                cursor += count;
+                for (pixel, value) in out[x..x + count].iter_mut().zip(values) {
+                    pixel[channel] = *value;
+                }
+                x += count;
+            }
+        }
+    }
+
+    Ok(cursor)
+}
+
+/// Reads a header line starting from `cursor` and moves `cursor` to the next line.
+fn read_line<'a>(bytes: &'a [u8], cursor: &mut usize) -> Result<&'a str, Error> {
+    let rest = &bytes[*cursor..];
+    let len = rest
+        .iter()
+        .position(|b| *b == b'\n')
+        .ok_or_else(|| Error::InvalidRadianceHdr("unexpected end of header".to_string()))?;
+    *cursor += len + 1;
+    std::str::from_utf8(&rest[..len])
+        .map(|line| line.trim_end_matches('\r'))
+        .map_err(|_| Error::InvalidRadianceHdr("header is not utf-8".to_string()))
+}
+
+/// Radiance HDR image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadianceHdr {
+    pub width: usize,
+    pub height: usize,
+    /// Accumulated `EXPOSURE` values of header, already applied to pixels.
+    pub exposure: f32,
+    /// Linear RGB pixels in row major,
+    /// rows are stored from bottom to top which is the order of WebGL texture.
+    pub data: Vec<f32>,
+}
+
+impl RadianceHdr {
+    /// Parses a Radiance HDR file.
+    ///
+    /// Only `32-bit_rle_rgbe` format and `-Y h +X w` or `+Y h +X w` resolution are supported.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut cursor = 0;
+        let mut next_line = || read_line(bytes, &mut cursor);
+
+        // parses header
+        if !next_line()?.starts_with("#?") {
+            return Err(Error::InvalidRadianceHdr("missing magic token".to_string()));
+        }
+        let mut exposure = 1.0f32;
+        loop {
+            let line = next_line()?;
+            if line.is_empty() {
+                break;
+            }
+
+            if let Some(format) = line.strip_prefix("FORMAT=") {
+                if format.trim() != RADIANCE_HDR_FORMAT_RGBE {
+                    return Err(Error::InvalidRadianceHdr(format!(
+                        "unsupported format {}",
+                        format
+                    )));
+                }
+            } else if let Some(value) = line.strip_prefix("EXPOSURE=") {
+                let value = value
+                    .trim()
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|value| value.is_finite() && *value > 0.0)
+                    .ok_or_else(|| {
+                        Error::InvalidRadianceHdr(format!("invalid exposure {}", value))
+                    })?;
+                exposure *= value;
+                if !exposure.is_finite() || exposure <= 0.0 {
+                    return Err(Error::InvalidRadianceHdr(format!(
+                        "invalid accumulated exposure {}",
+                        exposure
+                    )));
+                }
+            }
+        }
+
+        // parses resolution
+        let resolution = next_line()?.split_whitespace().collect::<Vec<_>>();
+        let (flip, height, width) = match resolution.as_slice() {
+            [y, height, "+X", width] if *y == "-Y" || *y == "+Y" => {
+                let parse = |value: &str| {
+                    value.parse::<usize>().map_err(|_| {
+                        Error::InvalidRadianceHdr(format!("invalid resolution {}", value))
+                    })
+                };
+                (*y == "-Y", parse(height)?, parse(width)?)
+            }
+            _ => {
+                return Err(Error::InvalidRadianceHdr(format!(
+                    "unsupported resolution {}",
+                    resolution.join(" ")
+                )))
+            }
+        };
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidRadianceHdr("empty image".to_string()));
+        }
+
+        // a scanline takes at least 4 bytes and a 4 bytes run length repeats at most 255 pixels,
+        // rejects resolutions the rest of the file could never fill before allocating
+        let remaining = bytes.len() - cursor;
+        let pixels = width
+            .checked_mul(height)
+            .filter(|pixels| height <= remaining / 4 && *pixels / 64 <= remaining)
+            .ok_or_else(|| {
+                Error::InvalidRadianceHdr(format!(
+                    "resolution {}x{} exceeds file length",
+                    width, height
+                ))
+            })?;
+        let len = pixels
+            .checked_mul(3)
+            .ok_or_else(|| Error::InvalidRadianceHdr("image too large".to_string()))?;
+
+        // decodes scanlines
+        let mut scanline = vec![[0u8; 4]; width];
+        let mut data = vec![0.0f32; len];
+        for y in 0..height {
+            cursor = decode_scanline(bytes, cursor, &mut scanline)?;
+
+            // -Y stores rows from top to bottom
+            let row = if flip { height - 1 - y } else { y };
+            let row = &mut data[row * width * 3..(row + 1) * width * 3];
+            for (pixel, rgbe) in row.chunks_exact_mut(3).zip(scanline.iter()) {
+                let [r, g, b] = decode_rgbe(*rgbe);
+                pixel[0] = r / exposure;
+                pixel[1] = g / exposure;
+                pixel[2] = b / exposure;
+            }
+        }
+
+        Ok(Self {
+            width,
+            height,
+            exposure,
+            data,
+        })
+    }
+
+    /// Parses a Radiance HDR file from raw data stored in [`ArrayBuffer`].
+    pub fn from_array_buffer(raw: ArrayBuffer) -> Result<Self, Error> {
+        Self::from_bytes(&Uint8Array::new(&raw).to_vec())
+    }
+
+    /// Returns pixels in half precision float bits.
+    pub fn to_half_float(&self) -> Vec<u16> {
+        self.data.iter().map(|value| f32_to_f16(*value)).collect()
+    }
+
+    /// Creates a [`Texture2D`] from this Radiance HDR image.
+    ///
+    /// Texture is created in [`TextureUncompressedInternalFormat::RGB16F`] if `use_half_float` is `true`,
+    /// which is filterable on all WebGL 2.0 implementations.
+    /// Otherwise, [`TextureUncompressedInternalFormat::RGB32F`] is used and
+    /// linear filtering requires extension `OES_texture_float_linear`.
+    pub fn texture_2d<SI, TI>(
+        &self,
+        use_half_float: bool,
+        sampler_params: SI,
+        texture_params: TI,
+    ) -> Texture<Texture2D>
+    where
+        SI: IntoIterator<Item = SamplerParameter>,
+        TI: IntoIterator<Item = TextureParameter>,
+    {
+        let (internal_format, source) = if use_half_float {
+            (
+                TextureUncompressedInternalFormat::RGB16F,
+                RadianceHdrTextureSource::HalfFloat {
+                    width: self.width,
+                    height: self.height,
+                    data: Uint16Array::from(self.to_half_float().as_slice()),
+                },
+            )
+        } else {
+            (
+                TextureUncompressedInternalFormat::RGB32F,
+                RadianceHdrTextureSource::Float {
+                    width: self.width,
+                    height: self.height,
+                    data: Float32Array::from(self.data.as_slice()),
+                },
+            )
+        };
+
+        let mut builder = Builder::<Texture2D>::new(
+            TextureInternalFormat::Uncompressed(internal_format),
+            1,
+            self.width,
+            self.height,
+        );
+        builder.set_texture_parameters(texture_params);
+        builder.set_sampler_parameters(sampler_params);
+        builder.tex_image(source, 0, false);
+        builder.build()
+    }
+}
+
+enum RadianceHdrTextureSource {
+    Float {
+        width: usize,
+        height: usize,
+        data: Float32Array,
+    },
+    HalfFloat {
+        width: usize,
+        height: usize,
+        data: Uint16Array,
+    },
+}
+
+impl TextureSource for RadianceHdrTextureSource {
+    fn data(&self) -> TextureData {
+        // rows of RGB half float are not always aligned to 4 bytes
+        let pixel_storages = vec![TexturePixelStorage::UNPACK_ALIGNMENT(1)];
+        match self {
+            RadianceHdrTextureSource::Float {
+                width,
+                height,
+                data,
+            } => TextureData::Uncompressed {
+                pixel_format: TextureUncompressedPixelFormat::RGB,
+                pixel_data_type: TextureUncompressedPixelDataType::FLOAT,
+                pixel_storages,
+                data: TextureUncompressedData::Float32Array {
+                    width: *width,
+                    height: *height,
+                    data: data.clone(),
+                    src_element_offset: None,
+                },
+            },
+            RadianceHdrTextureSource::HalfFloat {
+                width,
+                height,
+                data,
+            } => TextureData::Uncompressed {
+                pixel_format: TextureUncompressedPixelFormat::RGB,
+                pixel_data_type: TextureUncompressedPixelDataType::HALF_FLOAT,
+                pixel_storages,
+                data: TextureUncompressedData::Uint16Array {
+                    width: *width,
+                    height: *height,
+                    data: data.clone(),
+                    src_element_offset: None,
+                },
+            },
+        }
+    }
+}
+
+/// An texture loader loads texture from Radiance HDR file.
+pub struct RadianceHdrLoader {
+    url: String,
+    status: *mut LoaderStatus,
+    channel: (Sender<LoaderStatus>, Receiver<LoaderStatus>),
+    hdr: *mut Option<RadianceHdr>,
+    error: *mut Option<Error>,
+
+    use_half_float: bool,
+    sampler_params: Vec<SamplerParameter>,
+    texture_params: Vec<TextureParameter>,
+
+    promise: *mut Option<Promise>,
+    promise_resolve: *mut Option<Closure<dyn FnMut(JsValue)>>,
+    promise_reject: *mut Option<Closure<dyn FnMut(JsValue)>>,
+}
+
+impl Drop for RadianceHdrLoader {
+    fn drop(&mut self) {
+        unsafe {
+            drop(Box::from_raw(self.status));
+            drop(Box::from_raw(self.hdr));
+            drop(Box::from_raw(self.error));
+            drop(Box::from_raw(self.promise));
+            drop(Box::from_raw(self.promise_resolve));
+            drop(Box::from_raw(self.promise_reject));
+        }
+    }
+}
+
+impl RadianceHdrLoader {
+    /// Constructs a new hdr loader.
+    pub fn new<S>(url: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::with_params(url, true, Vec::new(), Vec::new())
+    }
+
+    /// Constructs a new hdr loader with parameters.
+    pub fn with_params<S, SI, TI>(
+        url: S,
+        use_half_float: bool,
+        sampler_params: SI,
+        texture_params: TI,
+    ) -> Self
+    where
+        S: Into<String>,
+        SI: IntoIterator<Item = SamplerParameter>,
+        TI: IntoIterator<Item = TextureParameter>,
+    {
+        Self {
+            url: url.into(),
+            status: Box::leak(Box::new(LoaderStatus::Unload)),
+            channel: channel(),
+            hdr: Box::leak(Box::new(None)),
+            error: Box::leak(Box::new(None)),
+
+            use_half_float,
+            sampler_params: sampler_params.into_iter().collect(),
+            texture_params: texture_params.into_iter().collect(),
+
+            promise: Box::leak(Box::new(None)),
+            promise_resolve: Box::leak(Box::new(None)),
+            promise_reject: Box::leak(Box::new(None)),
+        }
+    }
+
+    async fn fetch_buffer(url: String) -> Result<JsValue, JsValue> {
+        let mut opts = RequestInit::new();
+        opts.method("GET");
+
+        let request = Request::new_with_str_and_init(&url, &opts)?;
+        let response = JsFuture::from(window().fetch_with_request(&request))
+            .await?
+            .dyn_into::<Response>()
+            .unwrap();
+        if !response.ok() {
+            return Err(js_sys::Error::new(&format!(
+                "failed to fetch {}, status {}",
+                url,
+                response.status()
+            ))
+            .into());
+        }
+
+        let array_buffer = JsFuture::from(response.array_buffer()?).await?;
+
+        Ok(array_buffer)
+    }
+
+    /// Starts loading image.
+    /// This method does nothing if image is not in [`LoaderStatus::Unload`] status.
+    pub fn load(&self) {
+        unsafe {
+            if LoaderStatus::Unload != *self.status {
+                return;
+            }
+
+            let status = self.status;
+            let hdr = self.hdr;
+            let error = self.error;
+
+            let sender = self.channel.0.clone();
+            *self.promise_resolve =
+                Some(Closure::new(
+                    move |array_buffer: JsValue| match RadianceHdr::from_array_buffer(
+                        array_buffer.dyn_into::<ArrayBuffer>().unwrap(),
+                    ) {
+                        Ok(parsed) => {
+                            (*status) = LoaderStatus::Loaded;
+                            (*hdr) = Some(parsed);
+                            sender.send(*status);
+                        }
+                        Err(err) => {
+                            (*status) = LoaderStatus::Errored;
+                            (*error) = Some(err);
+                            sender.send(*status);
+                        }
+                    },
+                ));
+
+            let sender = self.channel.0.clone();
+            *self.promise_reject = Some(Closure::new(move |err: JsValue| {
+                (*status) = LoaderStatus::Errored;
+                (*error) = Some(Error::JsError(err.dyn_into::<js_sys::Error>().unwrap()));
+                sender.send(*status);
+            }));
+
+            (*self.status) = LoaderStatus::Loading;
+
+            let promise =
+                wasm_bindgen_futures::future_to_promise(Self::fetch_buffer(self.url.to_string()));
+            let promise = promise
+                .then(&(*self.promise_resolve).as_ref().unwrap())
+                .catch(&(*self.promise_reject).as_ref().unwrap());
+            (*self.promise) = Some(promise);
+        }
+    }
+
+    /// Starts loading image and puts it into a [`Promise`].
+    /// This method does nothing if image is not in [`LoaderStatus::Unload`] status.
+    pub fn load_promise(&self) -> Promise {
+        unsafe {
+            self.load();
+            (*self.promise).clone().unwrap()
+        }
+    }
+
+    /// Starts loading image and asynchronous awaiting.
+    /// This method does nothing if image is not in [`LoaderStatus::Unload`] status.
+    pub async fn load_async(&self) -> Result<&RadianceHdr, Error> {
+        unsafe {
+            self.load();
+            match wasm_bindgen_futures::JsFuture::from((*self.promise).clone().unwrap()).await {
+                Ok(_) => Ok((*self.hdr).as_ref().unwrap()),
+                Err(_) => Err((*self.error).clone().unwrap()),
+            }
+        }
+    }
+
+    /// Returns [`RadianceHdr`] regardless whether successfully loaded or not.
+    pub fn hdr(&self) -> Option<&RadianceHdr> {
+        unsafe { (*self.hdr).as_ref() }
+    }
+
+    /// Returns [`RadianceHdr`] if successfully loaded.
+    pub fn loaded_hdr(&self) -> Option<&RadianceHdr> {
+        unsafe {
+            match &*self.status {
+                LoaderStatus::Unload | LoaderStatus::Loading | LoaderStatus::Errored => None,
+                LoaderStatus::Loaded => (*self.hdr).as_ref(),
+            }
+        }
+    }
+
+    /// Returns image source url.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl Loader<Texture<Texture2D>> for RadianceHdrLoader {
+    type Failure = Error;
+
+    fn status(&self) -> LoaderStatus {
+        unsafe { *self.status }
+    }
+
+    fn load(&mut self) {
+        Self::load(&self);
+    }
+
+    fn loaded(&self) -> Result<Texture<Texture2D>, Error> {
+        unsafe {
+            if let Some(err) = &*self.error {
+                return Err(err.clone());
+            }
+
+            let hdr = (*self.hdr).as_ref().unwrap();
+            Ok(hdr.texture_2d(
+                self.use_half_float,
+                self.sampler_params.clone(),
+                self.texture_params.clone(),
+            ))
+        }
+    }
+
+    fn success(&self) -> Receiver<LoaderStatus> {
+        self.channel.1.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(resolution: &str, scanlines: &[u8]) -> Vec<u8> {
+        let mut bytes = format!(
+            "#?RADIANCE\n# made by hand\nFORMAT=32-bit_rle_rgbe\nEXPOSURE=2.0\n\n{}\n",
+            resolution
+        )
+        .into_bytes();
+        bytes.extend_from_slice(scanlines);
+        bytes
+    }
+
+    #[test]
+    fn test_decode_rgbe() {
+        assert_eq!(decode_rgbe([0, 0, 0, 0]), [0.0, 0.0, 0.0]);
+        assert_eq!(decode_rgbe([255, 255, 255, 0]), [0.0, 0.0, 0.0]);
+        assert_eq!(decode_rgbe([128, 64, 32, 129]), [1.0, 0.5, 0.25]);
+        assert_eq!(decode_rgbe([128, 128, 128, 136]), [128.0, 128.0, 128.0]);
+    }
+
+    #[test]
+    fn test_f32_to_f16() {
+        assert_eq!(f32_to_f16(0.0), 0x0000);
+        assert_eq!(f32_to_f16(-0.0), 0x8000);
+        assert_eq!(f32_to_f16(1.0), 0x3c00);
+        assert_eq!(f32_to_f16(0.5), 0x3800);
+        assert_eq!(f32_to_f16(-2.0), 0xc000);
+        assert_eq!(f32_to_f16(65504.0), 0x7bff);
+        assert_eq!(f32_to_f16(1.0e6), 0x7c00);
+        assert_eq!(f32_to_f16(f32::INFINITY), 0x7c00);
+        assert_eq!(f32_to_f16(2.0f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_f16(2.0f32.powi(-14)), 0x0400);
+        assert_eq!(f32_to_f16(1.0e-10), 0x0000);
+        assert!(f32_to_f16(f32::NAN) & 0x3ff != 0);
+    }
+
+    #[test]
+    fn test_decode_flat_and_old_rle_scanline() {
+        let bytes = [
+            128, 0, 0, 129, // red
+            1, 1, 1, 2, // repeats 2 times
+            0, 128, 0, 129, // green
+        ];
+        let mut out = [[0u8; 4]; 4];
+        let next = decode_scanline(&bytes, 0, &mut out).unwrap();
+        assert_eq!(next, 12);
+        assert_eq!(
+            out,
+            [
+                [128, 0, 0, 129],
+                [128, 0, 0, 129],
+                [128, 0, 0, 129],
+                [0, 128, 0, 129]
+            ]
+        );
+
+        let mut out = [[0u8; 4]; 4];
+        assert!(decode_scanline(&[1, 1, 1, 2], 0, &mut out).is_err());
+    }
+
+    #[test]
+    fn test_decode_new_rle_scanline() {
+        let mut bytes = vec![2, 2, 0, 8];
+        // red, a run of 8
+        bytes.extend_from_slice(&[128 + 8, 64]);
+        // green, 4 literals then a run of 4
+        bytes.extend_from_slice(&[4, 1, 2, 3, 4, 128 + 4, 9]);
+        // blue, a run of 8
+        bytes.extend_from_slice(&[128 + 8, 0]);
+        // exponent, a run of 8
+        bytes.extend_from_slice(&[128 + 8, 130]);
+
+        let mut out = [[0u8; 4]; 8];
+        let next = decode_scanline(&bytes, 0, &mut out).unwrap();
+        assert_eq!(next, bytes.len());
+        assert_eq!(out[0], [64, 1, 0, 130]);
+        assert_eq!(out[3], [64, 4, 0, 130]);
+        assert_eq!(out[7], [64, 9, 0, 130]);
+
+        // run overflows scanline
+        let mut bytes = vec![2, 2, 0, 8, 128 + 9, 64];
+        bytes.extend_from_slice(&[0; 16]);
+        assert!(decode_scanline(&bytes, 0, &mut out).is_err());
+
+        // truncated
+        assert!(decode_scanline(&[2, 2, 0, 8, 4, 1], 0, &mut out).is_err());
+    }
+
+    #[test]
+    fn test_parse_flips_rows() {
+        // top row is red, bottom row is blue
+        let bytes = file(
+            "-Y 2 +X 1",
+            &[
+                128, 0, 0, 129, // top
+                0, 0, 128, 129, // bottom
+            ],
+        );
+        let hdr = RadianceHdr::from_bytes(&bytes).unwrap();
+        assert_eq!(hdr.width, 1);
+        assert_eq!(hdr.height, 2);
+        assert_eq!(hdr.exposure, 2.0);
+        // stored from bottom to top, exposure divided
+        assert_eq!(hdr.data, vec![0.0, 0.0, 0.5, 0.5, 0.0, 0.0]);
+
+        let bytes = file("+Y 2 +X 1", &[128, 0, 0, 129, 0, 0, 128, 129]);
+        let hdr = RadianceHdr::from_bytes(&bytes).unwrap();
+        assert_eq!(hdr.data, vec![0.5, 0.0, 0.0, 0.0, 0.0, 0.5]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(RadianceHdr::from_bytes(b"P6\n").is_err());
+        assert!(RadianceHdr::from_bytes(
+            b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n\0\0\0\0"
+        )
+        .is_err());
+        assert!(RadianceHdr::from_bytes(&file("+X 1 -Y 1", &[0, 0, 0, 0])).is_err());
+        assert!(RadianceHdr::from_bytes(&file("-Y 2 +X 1", &[0, 0, 0, 0])).is_err());
+
+        // exposure must be positive and finite
+        for exposure in ["0", "-1", "inf", "NaN"] {
+            let bytes = format!(
+                "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\nEXPOSURE={}\n\n-Y 1 +X 1\n\0\0\0\0",
+                exposure
+            );
+            assert!(RadianceHdr::from_bytes(bytes.as_bytes()).is_err());
+        }
+        let bytes = "#?RADIANCE\nEXPOSURE=1e30\nEXPOSURE=1e30\n\n-Y 1 +X 1\n\0\0\0\0";
+        assert!(RadianceHdr::from_bytes(bytes.as_bytes()).is_err());
+
+        // resolution larger than the file could hold
+        let huge = format!("-Y {} +X {}", usize::MAX, usize::MAX);
+        assert!(RadianceHdr::from_bytes(&file(&huge, &[0, 0, 0, 0])).is_err());
+        assert!(RadianceHdr::from_bytes(&file("-Y 1 +X 1000000", &[0, 0, 0, 0])).is_err());
+    }
+
+    #[test]
+    fn test_decode_too_many_run_lengths() {
+        let mut bytes = vec![128, 0, 0, 129];
+        for _ in 0..usize::BITS / 8 {
+            bytes.extend_from_slice(&[1, 1, 1, 0]);
+        }
+        let mut out = vec![[0u8; 4]; 16];
+        assert!(decode_scanline(&bytes, 0, &mut out).is_err());
+    }
+}
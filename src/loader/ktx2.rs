@@ -0,0 +1,1242 @@
+use std::{borrow::Cow, convert::TryFrom, io::Read, rc::Rc};
+
+use indexmap::IndexMap;
+use js_sys::Promise;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    js_sys::{ArrayBuffer, Uint8Array},
+    Request, RequestInit, Response,
+};
+
+use crate::{
+    anewthing::{
+        texturing::{TextureCubeMapFace, TextureData, Texturing},
+        web::webgl::{
+            capabilities::WebGlCapabilities,
+            texture::{
+                WebGlCompressedTextureData, WebGlTextureCompressedFormat, WebGlTextureData,
+                WebGlTextureInternalFormat, WebGlTextureLayoutWithSize, WebGlTextureOptions,
+            },
+        },
+    },
+    error::{AsJsError, Error},
+    message::{channel, Receiver, Sender},
+    window,
+};
+
+use super::{Loader, LoaderStatus};
+
+pub const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+pub const KTX2_HEADER_SIZE: usize = 80;
+pub const KTX2_LEVEL_INDEX_SIZE: usize = 24;
+pub const KTX2_SUPERCOMPRESSION_NONE: u32 = 0;
+pub const KTX2_SUPERCOMPRESSION_BASIS_LZ: u32 = 1;
+pub const KTX2_SUPERCOMPRESSION_ZSTD: u32 = 2;
+pub const KTX2_SUPERCOMPRESSION_ZLIB: u32 = 3;
+pub const KTX2_DFD_COLOR_MODEL_ETC1S: u8 = 163;
+pub const KTX2_DFD_COLOR_MODEL_UASTC: u8 = 166;
+pub const KTX2_DFD_TRANSFER_SRGB: u8 = 2;
+pub const KTX2_DFD_CHANNEL_UASTC_RGBA: u8 = 3;
+pub const KTX2_DFD_CHANNEL_ETC1S_AAA: u8 = 15;
+
+/// KTX2 file header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub vk_format: u32,
+    pub type_size: u32,
+    pub pixel_width: u32,
+    pub pixel_height: u32,
+    pub pixel_depth: u32,
+    pub layer_count: u32,
+    pub face_count: u32,
+    pub level_count: u32,
+    pub supercompression_scheme: u32,
+}
+
+/// KTX2 index locating data format descriptor, key/value data and supercompression global data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Index {
+    pub dfd_byte_offset: u32,
+    pub dfd_byte_length: u32,
+    pub kvd_byte_offset: u32,
+    pub kvd_byte_length: u32,
+    pub sgd_byte_offset: u64,
+    pub sgd_byte_length: u64,
+}
+
+/// KTX2 level index of a mipmap level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelIndex {
+    pub byte_offset: u64,
+    pub byte_length: u64,
+    pub uncompressed_byte_length: u64,
+}
+
+/// Basic block of a KTX2 data format descriptor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataFormatDescriptor {
+    pub color_model: u8,
+    pub color_primaries: u8,
+    pub transfer_function: u8,
+    pub flags: u8,
+    /// Channel type of the first sample, used to identify alpha channel of Basis Universal data.
+    pub first_channel_type: Option<u8>,
+    pub sample_count: usize,
+}
+
+/// Source format of KTX2 image data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Ktx2SourceFormat {
+    /// A block compressed format could be uploaded directly.
+    Compressed(WebGlTextureCompressedFormat),
+    /// Basis Universal ETC1S, supercompressed by BasisLZ.
+    Etc1s,
+    /// Basis Universal UASTC.
+    Uastc,
+}
+
+/// Compressed formats support flags, collected from [`WebGlCapabilities`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Ktx2FormatSupport {
+    pub astc: bool,
+    pub bptc: bool,
+    pub s3tc: bool,
+    pub s3tc_srgb: bool,
+    pub etc: bool,
+    pub etc1: bool,
+    pub pvrtc: bool,
+    pub rgtc: bool,
+}
+
+impl Ktx2FormatSupport {
+    /// Collects supported compressed formats from [`WebGlCapabilities`].
+    pub fn from_capabilities(capabilities: &WebGlCapabilities) -> Self {
+        Self {
+            astc: capabilities.compressed_astc_supported(),
+            bptc: capabilities.compressed_bptc_supported(),
+            s3tc: capabilities.compressed_s3tc_supported(),
+            s3tc_srgb: capabilities.compressed_s3tc_srgb_supported(),
+            etc: capabilities.compressed_etc_supported(),
+            etc1: capabilities.compressed_etc1_supported(),
+            pvrtc: capabilities.compressed_pvrtc_supported(),
+            rgtc: capabilities.compressed_rgtc_supported(),
+        }
+    }
+
+    /// Returns `true` if a compressed format is supported.
+    pub fn supports(&self, format: WebGlTextureCompressedFormat) -> bool {
+        use WebGlTextureCompressedFormat::*;
+
+        match format {
+            COMPRESSED_RGB_S3TC_DXT1
+            | COMPRESSED_RGBA_S3TC_DXT1
+            | COMPRESSED_RGBA_S3TC_DXT3
+            | COMPRESSED_RGBA_S3TC_DXT5 => self.s3tc,
+            COMPRESSED_SRGB_S3TC_DXT1
+            | COMPRESSED_SRGB_ALPHA_S3TC_DXT1
+            | COMPRESSED_SRGB_ALPHA_S3TC_DXT3
+            | COMPRESSED_SRGB_ALPHA_S3TC_DXT5 => self.s3tc_srgb,
+            COMPRESSED_R11_EAC
+            | COMPRESSED_SIGNED_R11_EAC
+            | COMPRESSED_RG11_EAC
+            | COMPRESSED_SIGNED_RG11_EAC
+            | COMPRESSED_RGB8_ETC2
+            | COMPRESSED_RGBA8_ETC2_EAC
+            | COMPRESSED_SRGB8_ETC2
+            | COMPRESSED_SRGB8_ALPHA8_ETC2_EAC
+            | COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2
+            | COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2 => self.etc,
+            COMPRESSED_RGB_PVRTC_2BPPV1_IMG
+            | COMPRESSED_RGBA_PVRTC_2BPPV1_IMG
+            | COMPRESSED_RGB_PVRTC_4BPPV1_IMG
+            | COMPRESSED_RGBA_PVRTC_4BPPV1_IMG => self.pvrtc,
+            COMPRESSED_RGB_ETC1_WEBGL => self.etc1,
+            COMPRESSED_RGBA_BPTC_UNORM
+            | COMPRESSED_SRGB_ALPHA_BPTC_UNORM
+            | COMPRESSED_RGB_BPTC_SIGNED_FLOAT
+            | COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT => self.bptc,
+            COMPRESSED_RED_RGTC1
+            | COMPRESSED_SIGNED_RED_RGTC1
+            | COMPRESSED_RED_GREEN_RGTC2
+            | COMPRESSED_SIGNED_RED_GREEN_RGTC2 => self.rgtc,
+            _ => self.astc,
+        }
+    }
+
+    /// Picks the best transcoding target for Basis Universal data.
+    ///
+    /// Formats are preferred in order of ASTC 4x4, BPTC, S3TC, ETC2, ETC1 and PVRTC.
+    /// ETC1 and PVRTC are only picked for images without alpha channel.
+    pub fn transcode_target(
+        &self,
+        has_alpha: bool,
+        is_srgb: bool,
+    ) -> Option<WebGlTextureCompressedFormat> {
+        use WebGlTextureCompressedFormat::*;
+
+        if self.astc {
+            return Some(if is_srgb {
+                COMPRESSED_SRGB8_ALPHA8_ASTC_4x4
+            } else {
+                COMPRESSED_RGBA_ASTC_4x4
+            });
+        }
+        if self.bptc {
+            return Some(if is_srgb {
+                COMPRESSED_SRGB_ALPHA_BPTC_UNORM
+            } else {
+                COMPRESSED_RGBA_BPTC_UNORM
+            });
+        }
+        if self.s3tc && (!is_srgb || !self.s3tc_srgb) {
+            return Some(if has_alpha {
+                COMPRESSED_RGBA_S3TC_DXT5
+            } else {
+                COMPRESSED_RGB_S3TC_DXT1
+            });
+        }
+        if self.s3tc_srgb {
+            return Some(if has_alpha {
+                COMPRESSED_SRGB_ALPHA_S3TC_DXT5
+            } else {
+                COMPRESSED_SRGB_S3TC_DXT1
+            });
+        }
+        if self.etc {
+            return Some(match (has_alpha, is_srgb) {
+                (true, true) => COMPRESSED_SRGB8_ALPHA8_ETC2_EAC,
+                (true, false) => COMPRESSED_RGBA8_ETC2_EAC,
+                (false, true) => COMPRESSED_SRGB8_ETC2,
+                (false, false) => COMPRESSED_RGB8_ETC2,
+            });
+        }
+        if self.etc1 && !has_alpha {
+            return Some(COMPRESSED_RGB_ETC1_WEBGL);
+        }
+        if self.pvrtc && !has_alpha {
+            return Some(COMPRESSED_RGB_PVRTC_4BPPV1_IMG);
+        }
+
+        None
+    }
+}
+
+/// Maps a Vulkan format of KTX2 to a [`WebGlTextureCompressedFormat`].
+/// Returns `None` if format is not a block compressed format available in WebGL.
+pub fn vk_format_to_compressed_format(vk_format: u32) -> Option<WebGlTextureCompressedFormat> {
+    use WebGlTextureCompressedFormat::*;
+
+    let format = match vk_format {
+        131 => COMPRESSED_RGB_S3TC_DXT1,
+        132 => COMPRESSED_SRGB_S3TC_DXT1,
+        133 => COMPRESSED_RGBA_S3TC_DXT1,
+        134 => COMPRESSED_SRGB_ALPHA_S3TC_DXT1,
+        135 => COMPRESSED_RGBA_S3TC_DXT3,
+        136 => COMPRESSED_SRGB_ALPHA_S3TC_DXT3,
+        137 => COMPRESSED_RGBA_S3TC_DXT5,
+        138 => COMPRESSED_SRGB_ALPHA_S3TC_DXT5,
+        139 => COMPRESSED_RED_RGTC1,
+        140 => COMPRESSED_SIGNED_RED_RGTC1,
+        141 => COMPRESSED_RED_GREEN_RGTC2,
+        142 => COMPRESSED_SIGNED_RED_GREEN_RGTC2,
+        143 => COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT,
+        144 => COMPRESSED_RGB_BPTC_SIGNED_FLOAT,
+        145 => COMPRESSED_RGBA_BPTC_UNORM,
+        146 => COMPRESSED_SRGB_ALPHA_BPTC_UNORM,
+        147 => COMPRESSED_RGB8_ETC2,
+        148 => COMPRESSED_SRGB8_ETC2,
+        149 => COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2,
+        150 => COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2,
+        151 => COMPRESSED_RGBA8_ETC2_EAC,
+        152 => COMPRESSED_SRGB8_ALPHA8_ETC2_EAC,
+        153 => COMPRESSED_R11_EAC,
+        154 => COMPRESSED_SIGNED_R11_EAC,
+        155 => COMPRESSED_RG11_EAC,
+        156 => COMPRESSED_SIGNED_RG11_EAC,
+        157 => COMPRESSED_RGBA_ASTC_4x4,
+        158 => COMPRESSED_SRGB8_ALPHA8_ASTC_4x4,
+        159 => COMPRESSED_RGBA_ASTC_5x4,
+        160 => COMPRESSED_SRGB8_ALPHA8_ASTC_5x4,
+        161 => COMPRESSED_RGBA_ASTC_5x5,
+        162 => COMPRESSED_SRGB8_ALPHA8_ASTC_5x5,
+        163 => COMPRESSED_RGBA_ASTC_6x5,
+        164 => COMPRESSED_SRGB8_ALPHA8_ASTC_6x5,
+        165 => COMPRESSED_RGBA_ASTC_6x6,
+        166 => COMPRESSED_SRGB8_ALPHA8_ASTC_6x6,
+        167 => COMPRESSED_RGBA_ASTC_8x5,
+        168 => COMPRESSED_SRGB8_ALPHA8_ASTC_8x5,
+        169 => COMPRESSED_RGBA_ASTC_8x6,
+        170 => COMPRESSED_SRGB8_ALPHA8_ASTC_8x6,
+        171 => COMPRESSED_RGBA_ASTC_8x8,
+        172 => COMPRESSED_SRGB8_ALPHA8_ASTC_8x8,
+        173 => COMPRESSED_RGBA_ASTC_10x5,
+        174 => COMPRESSED_SRGB8_ALPHA8_ASTC_10x5,
+        175 => COMPRESSED_RGBA_ASTC_10x6,
+        176 => COMPRESSED_SRGB8_ALPHA8_ASTC_10x6,
+        // ASTC 10x8 is not available in WebGL
+        179 => COMPRESSED_RGBA_ASTC_10x10,
+        180 => COMPRESSED_SRGB8_ALPHA8_ASTC_10x10,
+        181 => COMPRESSED_RGBA_ASTC_12x10,
+        182 => COMPRESSED_SRGB8_ALPHA8_ASTC_12x10,
+        183 => COMPRESSED_RGBA_ASTC_12x12,
+        184 => COMPRESSED_SRGB8_ALPHA8_ASTC_12x12,
+        1000054000 => COMPRESSED_RGBA_PVRTC_2BPPV1_IMG,
+        1000054001 => COMPRESSED_RGBA_PVRTC_4BPPV1_IMG,
+        _ => return None,
+    };
+    Some(format)
+}
+
+/// An image of Basis Universal data to be transcoded.
+pub struct Ktx2TranscodeImage<'a> {
+    pub source_format: Ktx2SourceFormat,
+    pub level: usize,
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    pub layer_count: usize,
+    pub face_count: usize,
+    pub has_alpha: bool,
+    /// Level data, already inflated if zstd supercompression applied.
+    pub data: &'a [u8],
+    /// Supercompression global data, required by BasisLZ.
+    pub global_data: &'a [u8],
+}
+
+/// A transcoder transcodes Basis Universal data into a GPU compressed format.
+///
+/// Basis Universal transcoding is not done in this crate,
+/// developer should provide a transcoder, usually backed by the Basis Universal transcoder module.
+pub trait Ktx2Transcoder {
+    /// Transcodes all images of a level into target format.
+    /// Images should be concatenated in the same order as KTX2 level data.
+    fn transcode(
+        &self,
+        image: &Ktx2TranscodeImage<'_>,
+        target: WebGlTextureCompressedFormat,
+    ) -> Result<Vec<u8>, Error>;
+}
+
+/// A parsed KTX2 container.
+pub struct Ktx2 {
+    header: Header,
+    index: Index,
+    levels: Vec<LevelIndex>,
+    dfd: DataFormatDescriptor,
+    key_values: IndexMap<String, Vec<u8>>,
+    raw: Vec<u8>,
+}
+
+fn read_u8(bytes: &[u8], offset: usize) -> Result<u8, Error> {
+    bytes
+        .get(offset)
+        .copied()
+        .ok_or(Error::InvalidKtx2("unexpected end of file".to_string()))
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, Error> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or(Error::InvalidKtx2("unexpected end of file".to_string()))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, Error> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or(Error::InvalidKtx2("unexpected end of file".to_string()))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64, Error> {
+    bytes
+        .get(offset..offset + 8)
+        .map(|b| u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+        .ok_or(Error::InvalidKtx2("unexpected end of file".to_string()))
+}
+
+fn slice(bytes: &[u8], offset: u64, length: u64) -> Result<&[u8], Error> {
+    let start = usize::try_from(offset)
+        .map_err(|_| Error::InvalidKtx2("byte offset overflow".to_string()))?;
+    let length = usize::try_from(length)
+        .map_err(|_| Error::InvalidKtx2("byte length overflow".to_string()))?;
+    bytes
+        .get(start..start.saturating_add(length))
+        .ok_or(Error::InvalidKtx2("data out of file range".to_string()))
+}
+
+impl Ktx2 {
+    /// Parses a KTX2 container from raw bytes.
+    pub fn from_bytes(raw: Vec<u8>) -> Result<Self, Error> {
+        let bytes = raw.as_slice();
+        if bytes.len() < KTX2_HEADER_SIZE || bytes[..12] != KTX2_IDENTIFIER {
+            return Err(Error::InvalidKtx2("invalid KTX2 identifier".to_string()));
+        }
+
+        let header = Self::parse_header(bytes)?;
+        if header.pixel_width == 0 {
+            return Err(Error::InvalidKtx2(
+                "pixel width must not be zero".to_string(),
+            ));
+        }
+        if header.face_count != 1 && header.face_count != 6 {
+            return Err(Error::InvalidKtx2(format!(
+                "invalid face count {}",
+                header.face_count
+            )));
+        }
+
+        // a full mipmap chain ends at 1x1x1
+        let max_size = header
+            .pixel_width
+            .max(header.pixel_height)
+            .max(header.pixel_depth);
+        let max_levels = u32::BITS - max_size.leading_zeros();
+        if header.level_count > max_levels {
+            return Err(Error::InvalidKtx2(format!(
+                "level count {} exceeds maximum {}",
+                header.level_count, max_levels
+            )));
+        }
+
+        let index = Self::parse_index(bytes)?;
+        let levels = Self::parse_level_indices(bytes, header.level_count.max(1) as usize)?;
+        let dfd = Self::parse_dfd(slice(
+            bytes,
+            index.dfd_byte_offset as u64,
+            index.dfd_byte_length as u64,
+        )?)?;
+        let key_values = Self::parse_key_values(slice(
+            bytes,
+            index.kvd_byte_offset as u64,
+            index.kvd_byte_length as u64,
+        )?)?;
+        slice(bytes, index.sgd_byte_offset, index.sgd_byte_length)?;
+
+        Ok(Self {
+            header,
+            index,
+            levels,
+            dfd,
+            key_values,
+            raw,
+        })
+    }
+
+    fn parse_header(bytes: &[u8]) -> Result<Header, Error> {
+        Ok(Header {
+            vk_format: read_u32(bytes, 12)?,
+            type_size: read_u32(bytes, 16)?,
+            pixel_width: read_u32(bytes, 20)?,
+            pixel_height: read_u32(bytes, 24)?,
+            pixel_depth: read_u32(bytes, 28)?,
+            layer_count: read_u32(bytes, 32)?,
+            face_count: read_u32(bytes, 36)?,
+            level_count: read_u32(bytes, 40)?,
+            supercompression_scheme: read_u32(bytes, 44)?,
+        })
+    }
+
+    fn parse_index(bytes: &[u8]) -> Result<Index, Error> {
+        Ok(Index {
+            dfd_byte_offset: read_u32(bytes, 48)?,
+            dfd_byte_length: read_u32(bytes, 52)?,
+            kvd_byte_offset: read_u32(bytes, 56)?,
+            kvd_byte_length: read_u32(bytes, 60)?,
+            sgd_byte_offset: read_u64(bytes, 64)?,
+            sgd_byte_length: read_u64(bytes, 72)?,
+        })
+    }
+
+    fn parse_level_indices(bytes: &[u8], level_count: usize) -> Result<Vec<LevelIndex>, Error> {
+        let fits = level_count
+            .checked_mul(KTX2_LEVEL_INDEX_SIZE)
+            .and_then(|length| length.checked_add(KTX2_HEADER_SIZE))
+            .map(|end| end <= bytes.len())
+            .unwrap_or(false);
+        if !fits {
+            return Err(Error::InvalidKtx2(
+                "level indices out of file range".to_string(),
+            ));
+        }
+
+        let mut levels = Vec::with_capacity(level_count);
+        for level in 0..level_count {
+            let offset = KTX2_HEADER_SIZE + level * KTX2_LEVEL_INDEX_SIZE;
+            let index = LevelIndex {
+                byte_offset: read_u64(bytes, offset)?,
+                byte_length: read_u64(bytes, offset + 8)?,
+                uncompressed_byte_length: read_u64(bytes, offset + 16)?,
+            };
+            slice(bytes, index.byte_offset, index.byte_length)?;
+            levels.push(index);
+        }
+        Ok(levels)
+    }
+
+    fn parse_dfd(dfd: &[u8]) -> Result<DataFormatDescriptor, Error> {
+        // dfd starts with a total size, follows by the basic descriptor block
+        let block_size = read_u16(dfd, 10)? as usize;
+        // each sample takes 16 bytes after 24 bytes of block header
+        let sample_count = block_size.saturating_sub(24) / 16;
+        let first_channel_type = if sample_count > 0 {
+            // channel type takes low 4 bits of the 4th byte of a sample
+            Some(read_u8(dfd, 4 + 24 + 3)? & 0xF)
+        } else {
+            None
+        };
+
+        Ok(DataFormatDescriptor {
+            color_model: read_u8(dfd, 12)?,
+            color_primaries: read_u8(dfd, 13)?,
+            transfer_function: read_u8(dfd, 14)?,
+            flags: read_u8(dfd, 15)?,
+            first_channel_type,
+            sample_count,
+        })
+    }
+
+    fn parse_key_values(kvd: &[u8]) -> Result<IndexMap<String, Vec<u8>>, Error> {
+        let mut key_values = IndexMap::new();
+        let mut offset = 0;
+        while offset + 4 <= kvd.len() {
+            let length = read_u32(kvd, offset)? as usize;
+            let Some(key_and_value) = kvd.get(offset + 4..offset + 4 + length) else {
+                return Err(Error::InvalidKtx2(
+                    "key/value data out of range".to_string(),
+                ));
+            };
+            let Some(nul) = key_and_value.iter().position(|b| *b == 0) else {
+                return Err(Error::InvalidKtx2(
+                    "key of key/value data is not NUL terminated".to_string(),
+                ));
+            };
+            let key = String::from_utf8(key_and_value[..nul].to_vec())
+                .map_err(|_| Error::InvalidKtx2("key is not a valid UTF-8 string".to_string()))?;
+            key_values.insert(key, key_and_value[nul + 1..].to_vec());
+
+            // each key/value pair is 4-byte aligned
+            offset = (offset + 4 + length + 3) & !3;
+        }
+        Ok(key_values)
+    }
+
+    /// Returns KTX2 header.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Returns KTX2 index.
+    pub fn index(&self) -> &Index {
+        &self.index
+    }
+
+    /// Returns level indices, level 0 is the base level.
+    pub fn levels(&self) -> &[LevelIndex] {
+        &self.levels
+    }
+
+    /// Returns basic block of data format descriptor.
+    pub fn data_format_descriptor(&self) -> &DataFormatDescriptor {
+        &self.dfd
+    }
+
+    /// Returns key/value data in file order.
+    pub fn key_values(&self) -> &IndexMap<String, Vec<u8>> {
+        &self.key_values
+    }
+
+    /// Returns supercompression global data.
+    pub fn supercompression_global_data(&self) -> &[u8] {
+        slice(
+            &self.raw,
+            self.index.sgd_byte_offset,
+            self.index.sgd_byte_length,
+        )
+        .unwrap()
+    }
+
+    /// Returns `true` if image data is encoded in sRGB transfer function.
+    pub fn is_srgb(&self) -> bool {
+        self.dfd.transfer_function == KTX2_DFD_TRANSFER_SRGB
+    }
+
+    /// Returns `true` if image data is a cube map.
+    pub fn is_cube_map(&self) -> bool {
+        self.header.face_count == 6
+    }
+
+    /// Returns `true` if Basis Universal data has alpha channel.
+    pub fn has_alpha(&self) -> bool {
+        match self.dfd.color_model {
+            KTX2_DFD_COLOR_MODEL_UASTC => {
+                self.dfd.first_channel_type == Some(KTX2_DFD_CHANNEL_UASTC_RGBA)
+            }
+            KTX2_DFD_COLOR_MODEL_ETC1S => self.dfd.sample_count == 2,
+            _ => false,
+        }
+    }
+
+    /// Returns source format of image data.
+    pub fn source_format(&self) -> Result<Ktx2SourceFormat, Error> {
+        if self.header.vk_format != 0 {
+            return vk_format_to_compressed_format(self.header.vk_format)
+                .map(Ktx2SourceFormat::Compressed)
+                .ok_or(Error::InvalidKtx2(format!(
+                    "unsupported vk format {}",
+                    self.header.vk_format
+                )));
+        }
+
+        match self.dfd.color_model {
+            KTX2_DFD_COLOR_MODEL_ETC1S => Ok(Ktx2SourceFormat::Etc1s),
+            KTX2_DFD_COLOR_MODEL_UASTC => Ok(Ktx2SourceFormat::Uastc),
+            color_model => Err(Error::InvalidKtx2(format!(
+                "unsupported color model {}",
+                color_model
+            ))),
+        }
+    }
+
+    /// Returns size of a level in `(width, height, depth)`.
+    pub fn level_size(&self, level: usize) -> (usize, usize, usize) {
+        let size = |s: u32| {
+            u32::try_from(level)
+                .ok()
+                .and_then(|level| (s as usize).checked_shr(level))
+                .unwrap_or(0)
+                .max(1)
+        };
+        (
+            size(self.header.pixel_width),
+            size(self.header.pixel_height),
+            size(self.header.pixel_depth),
+        )
+    }
+
+    /// Returns data of a level, inflated if zstd supercompression applied.
+    /// Data supercompressed by BasisLZ is returned as it is.
+    pub fn level_data(&self, level: usize) -> Result<Cow<'_, [u8]>, Error> {
+        let index = self
+            .levels
+            .get(level)
+            .ok_or(Error::InvalidKtx2(format!("no such level {}", level)))?;
+        let data = slice(&self.raw, index.byte_offset, index.byte_length)?;
+
+        match self.header.supercompression_scheme {
+            KTX2_SUPERCOMPRESSION_NONE | KTX2_SUPERCOMPRESSION_BASIS_LZ => Ok(Cow::Borrowed(data)),
+            KTX2_SUPERCOMPRESSION_ZSTD => {
+                let mut source = data;
+                let mut decoder = ruzstd::StreamingDecoder::new(&mut source)
+                    .map_err(|err| Error::InvalidKtx2(err.to_string()))?;
+                // uncompressed length is untrusted, lets the vector grow with inflated data
+                // and stops right after exceeding the declared length
+                let mut inflated = Vec::new();
+                (&mut decoder)
+                    .take(index.uncompressed_byte_length.saturating_add(1))
+                    .read_to_end(&mut inflated)
+                    .map_err(|err| Error::InvalidKtx2(err.to_string()))?;
+                if inflated.len() as u64 != index.uncompressed_byte_length {
+                    return Err(Error::InvalidKtx2(format!(
+                        "inflated length of level {} mismatched",
+                        level
+                    )));
+                }
+                Ok(Cow::Owned(inflated))
+            }
+            scheme => Err(Error::InvalidKtx2(format!(
+                "unsupported supercompression scheme {}",
+                scheme
+            ))),
+        }
+    }
+
+    /// Returns [`WebGlTextureLayoutWithSize`] of this texture.
+    /// Cube map arrays are not supported by WebGL 2.0 and errors are returned.
+    pub fn layout(&self) -> Result<WebGlTextureLayoutWithSize, Error> {
+        let levels = Some(self.levels.len());
+        let width = self.header.pixel_width as usize;
+        let height = (self.header.pixel_height as usize).max(1);
+        if self.header.pixel_depth > 0 {
+            Ok(WebGlTextureLayoutWithSize::Texture3D {
+                levels,
+                width,
+                height,
+                depth: self.header.pixel_depth as usize,
+            })
+        } else if self.is_cube_map() {
+            if self.header.layer_count > 0 {
+                return Err(Error::InvalidKtx2(
+                    "cube map array is not supported".to_string(),
+                ));
+            }
+            Ok(WebGlTextureLayoutWithSize::TextureCubeMap {
+                levels,
+                width,
+                height,
+            })
+        } else if self.header.layer_count > 0 {
+            Ok(WebGlTextureLayoutWithSize::Texture2DArray {
+                levels,
+                width,
+                height,
+                len: self.header.layer_count as usize,
+            })
+        } else {
+            Ok(WebGlTextureLayoutWithSize::Texture2D {
+                levels,
+                width,
+                height,
+            })
+        }
+    }
+
+    /// Creates a [`Texturing`] with all levels pushed in,
+    /// and [`WebGlTextureOptions`] for creating the texture.
+    ///
+    /// Block compressed data is uploaded as it is if supported by [`WebGlCapabilities`].
+    /// Basis Universal data is transcoded by `transcoder` into the best format picked
+    /// by [`Ktx2FormatSupport::transcode_target`].
+    pub fn texturing(
+        &self,
+        capabilities: &WebGlCapabilities,
+        transcoder: Option<&dyn Ktx2Transcoder>,
+    ) -> Result<(Texturing, WebGlTextureOptions), Error> {
+        let support = Ktx2FormatSupport::from_capabilities(capabilities);
+        let (format, transcode_source) = match self.source_format()? {
+            Ktx2SourceFormat::Compressed(format) => {
+                if !support.supports(format) {
+                    return Err(Error::InvalidKtx2(format!(
+                        "compressed format {:?} is not supported",
+                        format
+                    )));
+                }
+                (format, None)
+            }
+            source_format => {
+                let target = support
+                    .transcode_target(self.has_alpha(), self.is_srgb())
+                    .ok_or(Error::InvalidKtx2(
+                        "no compressed format available for transcoding".to_string(),
+                    ))?;
+                (target, Some(source_format))
+            }
+        };
+
+        let layout = self.layout()?;
+        let texturing = Texturing::new();
+        for level in 0..self.levels.len() {
+            let (width, height, depth) = self.level_size(level);
+            let data = self.level_data(level)?;
+            let data = match transcode_source {
+                None => data,
+                Some(source_format) => {
+                    let transcoder = transcoder.ok_or(Error::InvalidKtx2(
+                        "transcoder is required for Basis Universal data".to_string(),
+                    ))?;
+                    let image = Ktx2TranscodeImage {
+                        source_format,
+                        level,
+                        width,
+                        height,
+                        depth,
+                        layer_count: self.header.layer_count.max(1) as usize,
+                        face_count: self.header.face_count as usize,
+                        has_alpha: self.has_alpha(),
+                        data: &data,
+                        global_data: self.supercompression_global_data(),
+                    };
+                    Cow::Owned(transcoder.transcode(&image, format)?)
+                }
+            };
+
+            match layout {
+                WebGlTextureLayoutWithSize::TextureCubeMap { .. } => {
+                    // faces are stored in order of +X, -X, +Y, -Y, +Z, -Z
+                    const FACES: [TextureCubeMapFace; 6] = [
+                        TextureCubeMapFace::PositiveX,
+                        TextureCubeMapFace::NegativeX,
+                        TextureCubeMapFace::PositiveY,
+                        TextureCubeMapFace::NegativeY,
+                        TextureCubeMapFace::PositiveZ,
+                        TextureCubeMapFace::NegativeZ,
+                    ];
+                    let face_length = data.len() / 6;
+                    for (i, face) in FACES.iter().enumerate() {
+                        texturing.push_cube_map_face(
+                            Ktx2TextureData::new(
+                                width,
+                                height,
+                                &data[i * face_length..(i + 1) * face_length],
+                            ),
+                            *face,
+                            level,
+                        );
+                    }
+                }
+                WebGlTextureLayoutWithSize::Texture2DArray { len, .. } => {
+                    texturing.push_volume(Ktx2TextureData::new(width, height, &data), level, len);
+                }
+                WebGlTextureLayoutWithSize::Texture3D { .. } => {
+                    texturing.push_volume(Ktx2TextureData::new(width, height, &data), level, depth);
+                }
+                WebGlTextureLayoutWithSize::Texture2D { .. } => {
+                    texturing.push(Ktx2TextureData::new(width, height, &data), level);
+                }
+            };
+        }
+
+        Ok((
+            texturing,
+            WebGlTextureOptions {
+                layout,
+                internal_format: WebGlTextureInternalFormat::Compressed(format),
+            },
+        ))
+    }
+}
+
+/// Compressed image data of a KTX2 level.
+pub struct Ktx2TextureData {
+    width: usize,
+    height: usize,
+    data: Uint8Array,
+}
+
+impl Ktx2TextureData {
+    fn new(width: usize, height: usize, data: &[u8]) -> Self {
+        Self {
+            width,
+            height,
+            data: Uint8Array::from(data),
+        }
+    }
+}
+
+impl TextureData for Ktx2TextureData {
+    fn as_webgl_texture_data(&self) -> Option<WebGlTextureData> {
+        Some(WebGlTextureData::Compressed {
+            data: WebGlCompressedTextureData::Uint8Array {
+                width: self.width,
+                height: self.height,
+                data: self.data.clone(),
+                element_offset: None,
+                element_length_override: None,
+            },
+        })
+    }
+}
+
+/// A loader loads KTX2 container from url.
+pub struct Ktx2Loader {
+    url: String,
+    status: *mut LoaderStatus,
+    channel: (Sender<LoaderStatus>, Receiver<LoaderStatus>),
+    ktx2: *mut Option<Ktx2>,
+    error: *mut Option<Error>,
+    capabilities: WebGlCapabilities,
+    transcoder: Option<Rc<dyn Ktx2Transcoder>>,
+
+    promise: *mut Option<Promise>,
+}
+
+impl Drop for Ktx2Loader {
+    fn drop(&mut self) {
+        unsafe {
+            drop(Box::from_raw(self.status));
+            drop(Box::from_raw(self.ktx2));
+            drop(Box::from_raw(self.error));
+            drop(Box::from_raw(self.promise));
+        }
+    }
+}
+
+impl Ktx2Loader {
+    /// Constructs a new KTX2 loader.
+    /// Only KTX2 containing block compressed data supported by `capabilities` is loadable.
+    pub fn new<S>(url: S, capabilities: WebGlCapabilities) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            url: url.into(),
+            status: Box::leak(Box::new(LoaderStatus::Unload)),
+            channel: channel(),
+            ktx2: Box::leak(Box::new(None)),
+            error: Box::leak(Box::new(None)),
+            capabilities,
+            transcoder: None,
+
+            promise: Box::leak(Box::new(None)),
+        }
+    }
+
+    /// Constructs a new KTX2 loader with a [`Ktx2Transcoder`] transcoding Basis Universal data.
+    pub fn with_transcoder<S>(
+        url: S,
+        capabilities: WebGlCapabilities,
+        transcoder: Rc<dyn Ktx2Transcoder>,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        let mut loader = Self::new(url, capabilities);
+        loader.transcoder = Some(transcoder);
+        loader
+    }
+
+    async fn fetch_ktx2(url: String) -> Result<Ktx2, Error> {
+        fn js_error(err: JsValue) -> Error {
+            Error::CommonError(err.as_error().and_then(|err| err.message().as_string()))
+        }
+
+        let mut opts = RequestInit::new();
+        opts.method("GET");
+
+        let request = Request::new_with_str_and_init(&url, &opts).map_err(js_error)?;
+        let response = JsFuture::from(window().fetch_with_request(&request))
+            .await
+            .map_err(js_error)?
+            .dyn_into::<Response>()
+            .unwrap();
+        if !response.ok() {
+            return Err(Error::CommonError(Some(format!(
+                "failed to fetch {}, status {}",
+                url,
+                response.status()
+            ))));
+        }
+
+        let array_buffer = JsFuture::from(response.array_buffer().map_err(js_error)?)
+            .await
+            .map_err(js_error)?
+            .dyn_into::<ArrayBuffer>()
+            .unwrap();
+
+        Ktx2::from_bytes(Uint8Array::new(&array_buffer).to_vec())
+    }
+
+    /// Starts loading KTX2.
+    /// This method does nothing if KTX2 is not in [`LoaderStatus::Unload`] status.
+    pub fn load(&self) {
+        unsafe {
+            if LoaderStatus::Unload != *self.status {
+                return;
+            }
+
+            let status = self.status;
+            let ktx2 = self.ktx2;
+            let error = self.error;
+            let sender = self.channel.0.clone();
+            let url = self.url.clone();
+
+            (*self.status) = LoaderStatus::Loading;
+            self.channel.0.send(*self.status);
+
+            let promise = wasm_bindgen_futures::future_to_promise(async move {
+                match Self::fetch_ktx2(url).await {
+                    Ok(parsed) => {
+                        (*status) = LoaderStatus::Loaded;
+                        (*ktx2) = Some(parsed);
+                        sender.send(*status);
+                        Ok(JsValue::undefined())
+                    }
+                    Err(err) => {
+                        (*status) = LoaderStatus::Errored;
+                        (*error) = Some(err);
+                        sender.send(*status);
+                        Err(JsValue::undefined())
+                    }
+                }
+            });
+            (*self.promise) = Some(promise);
+        }
+    }
+
+    /// Starts loading KTX2 and puts it into a [`Promise`].
+    /// This method does nothing if KTX2 is not in [`LoaderStatus::Unload`] status.
+    pub fn load_promise(&self) -> Promise {
+        unsafe {
+            self.load();
+            (*self.promise).clone().unwrap()
+        }
+    }
+
+    /// Starts loading KTX2 and asynchronous awaiting.
+    /// This method does nothing if KTX2 is not in [`LoaderStatus::Unload`] status.
+    pub async fn load_async(&self) -> Result<&Ktx2, Error> {
+        unsafe {
+            self.load();
+            match JsFuture::from((*self.promise).clone().unwrap()).await {
+                Ok(_) => Ok((*self.ktx2).as_ref().unwrap()),
+                Err(_) => Err((*self.error).clone().unwrap()),
+            }
+        }
+    }
+
+    /// Returns [`Ktx2`] if successfully loaded.
+    pub fn loaded_ktx2(&self) -> Option<&Ktx2> {
+        unsafe {
+            match &*self.status {
+                LoaderStatus::Unload | LoaderStatus::Loading | LoaderStatus::Errored => None,
+                LoaderStatus::Loaded => (*self.ktx2).as_ref(),
+            }
+        }
+    }
+
+    /// Returns KTX2 source url.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl Loader<(Texturing, WebGlTextureOptions)> for Ktx2Loader {
+    type Failure = Error;
+
+    fn status(&self) -> LoaderStatus {
+        unsafe { *self.status }
+    }
+
+    fn load(&mut self) {
+        Self::load(&self);
+    }
+
+    fn loaded(&self) -> Result<(Texturing, WebGlTextureOptions), Error> {
+        unsafe {
+            if let Some(err) = &*self.error {
+                return Err(err.clone());
+            }
+
+            let ktx2 = (*self.ktx2).as_ref().unwrap();
+            ktx2.texturing(&self.capabilities, self.transcoder.as_deref())
+        }
+    }
+
+    fn success(&self) -> Receiver<LoaderStatus> {
+        self.channel.1.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::anewthing::web::webgl::texture::{
+        WebGlTextureCompressedFormat, WebGlTextureLayoutWithSize,
+    };
+
+    use super::{
+        Ktx2, Ktx2FormatSupport, Ktx2SourceFormat, KTX2_DFD_COLOR_MODEL_UASTC, KTX2_IDENTIFIER,
+        KTX2_SUPERCOMPRESSION_NONE,
+    };
+
+    /// Builds a KTX2 fixture with a 8x8 BC1 sRGB texture and 2 levels.
+    fn bc1_fixture() -> Vec<u8> {
+        build_fixture(132, 8, 8, 0, 1, &[vec![1u8; 32], vec![2u8; 8]], 1, 2)
+    }
+
+    fn build_fixture(
+        vk_format: u32,
+        width: u32,
+        height: u32,
+        layers: u32,
+        faces: u32,
+        levels: &[Vec<u8>],
+        color_model: u8,
+        transfer_function: u8,
+    ) -> Vec<u8> {
+        let mut dfd = Vec::new();
+        dfd.extend_from_slice(&44u32.to_le_bytes());
+        dfd.extend_from_slice(&0u32.to_le_bytes());
+        dfd.extend_from_slice(&2u16.to_le_bytes());
+        dfd.extend_from_slice(&40u16.to_le_bytes());
+        dfd.extend_from_slice(&[color_model, 1, transfer_function, 0]);
+        dfd.extend_from_slice(&[0u8; 12]);
+        // one sample
+        dfd.extend_from_slice(&[0, 0, 63, 0]);
+        dfd.extend_from_slice(&[0u8; 12]);
+
+        let mut kvd = Vec::new();
+        let key_value = b"KTXwriter\0atoy";
+        kvd.extend_from_slice(&(key_value.len() as u32).to_le_bytes());
+        kvd.extend_from_slice(key_value);
+        while kvd.len() % 4 != 0 {
+            kvd.push(0);
+        }
+
+        let level_index_end = 80 + 24 * levels.len();
+        let dfd_offset = level_index_end;
+        let kvd_offset = dfd_offset + dfd.len();
+        let mut data_offset = kvd_offset + kvd.len();
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&KTX2_IDENTIFIER);
+        for value in [
+            vk_format,
+            1,
+            width,
+            height,
+            0,
+            layers,
+            faces,
+            levels.len() as u32,
+            KTX2_SUPERCOMPRESSION_NONE,
+            dfd_offset as u32,
+            dfd.len() as u32,
+            kvd_offset as u32,
+            kvd.len() as u32,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+
+        let mut data = Vec::new();
+        for level in levels {
+            bytes.extend_from_slice(&(data_offset as u64).to_le_bytes());
+            bytes.extend_from_slice(&(level.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(&(level.len() as u64).to_le_bytes());
+            data_offset += level.len();
+            data.extend_from_slice(level);
+        }
+        bytes.extend_from_slice(&dfd);
+        bytes.extend_from_slice(&kvd);
+        bytes.extend_from_slice(&data);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_header_and_levels() {
+        let ktx2 = Ktx2::from_bytes(bc1_fixture()).unwrap();
+
+        assert_eq!(132, ktx2.header().vk_format);
+        assert_eq!(8, ktx2.header().pixel_width);
+        assert_eq!(2, ktx2.levels().len());
+        assert_eq!(32, ktx2.levels()[0].byte_length);
+        assert_eq!((4, 4, 1), ktx2.level_size(1));
+        assert_eq!((1, 1, 1), ktx2.level_size(100));
+        assert_eq!(vec![2u8; 8], ktx2.level_data(1).unwrap().to_vec());
+        assert!(ktx2.is_srgb());
+        assert!(!ktx2.is_cube_map());
+        assert_eq!(
+            Ktx2SourceFormat::Compressed(WebGlTextureCompressedFormat::COMPRESSED_SRGB_S3TC_DXT1),
+            ktx2.source_format().unwrap()
+        );
+        assert_eq!(
+            WebGlTextureLayoutWithSize::Texture2D {
+                levels: Some(2),
+                width: 8,
+                height: 8
+            },
+            ktx2.layout().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_key_values() {
+        let ktx2 = Ktx2::from_bytes(bc1_fixture()).unwrap();
+        assert_eq!(Some(&b"atoy".to_vec()), ktx2.key_values().get("KTXwriter"));
+    }
+
+    #[test]
+    fn test_parse_cube_map_and_uastc() {
+        let ktx2 = Ktx2::from_bytes(build_fixture(
+            0,
+            4,
+            4,
+            0,
+            6,
+            &[vec![0u8; 96]],
+            KTX2_DFD_COLOR_MODEL_UASTC,
+            1,
+        ))
+        .unwrap();
+
+        assert!(ktx2.is_cube_map());
+        assert!(!ktx2.is_srgb());
+        assert_eq!(Ktx2SourceFormat::Uastc, ktx2.source_format().unwrap());
+        assert_eq!(
+            WebGlTextureLayoutWithSize::TextureCubeMap {
+                levels: Some(1),
+                width: 4,
+                height: 4
+            },
+            ktx2.layout().unwrap()
+        );
+
+        // cube map array
+        let ktx2 = Ktx2::from_bytes(build_fixture(
+            0,
+            4,
+            4,
+            2,
+            6,
+            &[vec![0u8; 192]],
+            KTX2_DFD_COLOR_MODEL_UASTC,
+            1,
+        ))
+        .unwrap();
+        assert!(ktx2.layout().is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid_ktx2() {
+        let mut bytes = bc1_fixture();
+        assert!(Ktx2::from_bytes(bytes[..60].to_vec()).is_err());
+
+        // level data out of range
+        let len = bytes.len();
+        bytes.truncate(len - 1);
+        assert!(Ktx2::from_bytes(bytes).is_err());
+
+        let mut bytes = bc1_fixture();
+        bytes[0] = 0;
+        assert!(Ktx2::from_bytes(bytes).is_err());
+
+        // invalid face count
+        let bytes = build_fixture(132, 8, 8, 0, 2, &[vec![0u8; 32]], 1, 2);
+        assert!(Ktx2::from_bytes(bytes).is_err());
+
+        // level count beyond a full mipmap chain of 8x8, which has 4 levels
+        let mut bytes = bc1_fixture();
+        bytes[40..44].copy_from_slice(&5u32.to_le_bytes());
+        assert!(Ktx2::from_bytes(bytes).is_err());
+
+        // level indices of a large texture out of file range, checked before allocating
+        let mut bytes = bc1_fixture();
+        bytes[20..24].copy_from_slice(&u32::MAX.to_le_bytes());
+        bytes[40..44].copy_from_slice(&32u32.to_le_bytes());
+        assert!(Ktx2::from_bytes(bytes).is_err());
+    }
+
+    #[test]
+    fn test_transcode_target() {
+        let mut support = Ktx2FormatSupport::default();
+        assert_eq!(None, support.transcode_target(true, false));
+
+        support.pvrtc = true;
+        assert_eq!(None, support.transcode_target(true, false));
+        assert_eq!(
+            Some(WebGlTextureCompressedFormat::COMPRESSED_RGB_PVRTC_4BPPV1_IMG),
+            support.transcode_target(false, false)
+        );
+
+        support.s3tc = true;
+        support.s3tc_srgb = true;
+        assert_eq!(
+            Some(WebGlTextureCompressedFormat::COMPRESSED_SRGB_ALPHA_S3TC_DXT5),
+            support.transcode_target(true, true)
+        );
+        assert_eq!(
+            Some(WebGlTextureCompressedFormat::COMPRESSED_RGB_S3TC_DXT1),
+            support.transcode_target(false, false)
+        );
+
+        support.astc = true;
+        assert_eq!(
+            Some(WebGlTextureCompressedFormat::COMPRESSED_SRGB8_ALPHA8_ASTC_4x4),
+            support.transcode_target(false, true)
+        );
+    }
+}
@@ -1,6 +1,11 @@
 use crate::message::Receiver;
 
 pub mod dds;
+pub mod gltf;
+pub mod hdr;
+#[cfg(feature = "webgl")]
+pub mod ktx2;
+pub mod obj;
 pub mod texture;
 
 /// Loader status.
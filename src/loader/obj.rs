@@ -0,0 +1,984 @@
+use std::{any::Any, ops::Range};
+
+use gl_matrix4rust::vec3::Vec3;
+use hashbrown::HashMap;
+use js_sys::Promise;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    js_sys::{Float32Array, Uint32Array},
+    Request, RequestInit, Response,
+};
+
+use crate::{
+    bounding::BoundingVolume,
+    clock::Tick,
+    document,
+    entity::{SimpleEntity, SimpleGroup},
+    error::{AsJsError, Error},
+    geometry::{Geometry, GeometryMessage, IndexedGeometry},
+    material::{
+        webgl::{solid_color::SolidColorMaterial, texture},
+        Transparency,
+    },
+    message::{channel, Receiver, Sender},
+    renderer::webgl::{
+        attribute::AttributeValue,
+        buffer::{self, Buffer, BufferComponentSize, BufferDataType, BufferUsage},
+        draw::{CullFace, DrawMode, ElementIndicesDataType},
+        uniform::{UniformBlockValue, UniformValue},
+    },
+    value::Readonly,
+    window,
+};
+
+use super::{gltf::resolve_uri, texture::TextureLoader, Loader, LoaderStatus};
+
+/// A mesh parsed from a Wavefront OBJ file.
+///
+/// A new mesh is started whenever an `o`, `g` or `usemtl` statement appears.
+/// Vertices are deduplicated by position, texture coordinate and normal index triple,
+/// and polygons are triangulated as triangle fans.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ObjMesh {
+    pub name: Option<String>,
+    pub material: Option<String>,
+    /// Positions, 3 components per vertex.
+    pub positions: Vec<f32>,
+    /// Normals, 3 components per vertex.
+    /// Normals are generated by averaging face normals if OBJ provides none of them.
+    pub normals: Vec<f32>,
+    /// Texture coordinates, 2 components per vertex. Empty if OBJ provides none of them.
+    pub texture_coordinates: Vec<f32>,
+    pub indices: Vec<u32>,
+}
+
+impl ObjMesh {
+    /// Returns the number of vertices.
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len() / 3
+    }
+
+    /// Returns axis aligned bounding box of this mesh.
+    pub fn bounding_volume(&self) -> BoundingVolume {
+        let mut min = [f64::MAX; 3];
+        let mut max = [f64::MIN; 3];
+        for position in self.positions.chunks_exact(3) {
+            for i in 0..3 {
+                min[i] = min[i].min(position[i] as f64);
+                max[i] = max[i].max(position[i] as f64);
+            }
+        }
+        if self.positions.is_empty() {
+            min = [0.0; 3];
+            max = [0.0; 3];
+        }
+
+        BoundingVolume::AxisAlignedBoundingBox {
+            min_x: min[0],
+            max_x: max[0],
+            min_y: min[1],
+            max_y: max[1],
+            min_z: min[2],
+            max_z: max[2],
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+/// A parsed Wavefront OBJ file.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Obj {
+    /// Material libraries referenced by `mtllib` statements.
+    pub material_libraries: Vec<String>,
+    pub meshes: Vec<ObjMesh>,
+}
+
+/// A material parsed from a Wavefront MTL file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MtlMaterial {
+    pub name: String,
+    pub ambient_color: [f32; 3],
+    pub diffuse_color: [f32; 3],
+    /// Specular shininess, only used by untextured materials.
+    pub specular_shininess: f32,
+    /// Dissolve factor, `1.0` is fully opaque.
+    pub dissolve: f32,
+    pub diffuse_map: Option<String>,
+    pub normal_map: Option<String>,
+}
+
+impl MtlMaterial {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            ambient_color: [0.0, 0.0, 0.0],
+            diffuse_color: [1.0, 1.0, 1.0],
+            specular_shininess: 128.0,
+            dissolve: 1.0,
+            diffuse_map: None,
+            normal_map: None,
+        }
+    }
+
+    /// Returns [`Transparency`] from dissolve factor.
+    pub fn transparency(&self) -> Transparency {
+        if self.dissolve >= 1.0 {
+            Transparency::Opaque
+        } else if self.dissolve <= 0.0 {
+            Transparency::Transparent
+        } else {
+            Transparency::Translucent(self.dissolve)
+        }
+    }
+}
+
+fn parse_f32(line: usize, value: Option<&str>) -> Result<f32, Error> {
+    value
+        .and_then(|value| value.parse::<f32>().ok())
+        .ok_or(Error::InvalidWavefrontObj(format!(
+            "invalid number at line {}",
+            line
+        )))
+}
+
+fn parse_vec3(line: usize, values: &mut std::str::SplitWhitespace) -> Result<[f32; 3], Error> {
+    Ok([
+        parse_f32(line, values.next())?,
+        parse_f32(line, values.next())?,
+        parse_f32(line, values.next())?,
+    ])
+}
+
+/// Resolves an OBJ index, which is 1-based and could be negative relative to the end.
+fn resolve_index(line: usize, value: &str, len: usize) -> Result<usize, Error> {
+    let index = value
+        .parse::<isize>()
+        .map_err(|_| Error::InvalidWavefrontObj(format!("invalid face index at line {}", line)))?;
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        len as isize + index
+    };
+
+    if index == 0 || resolved < 0 || resolved as usize >= len {
+        Err(Error::InvalidWavefrontObj(format!(
+            "face index {} out of range at line {}",
+            index, line
+        )))
+    } else {
+        Ok(resolved as usize)
+    }
+}
+
+/// Removes trailing comment and surrounding whitespaces of a line.
+fn strip_line(line: &str) -> &str {
+    match line.find('#') {
+        Some(index) => line[..index].trim(),
+        None => line.trim(),
+    }
+}
+
+struct MeshBuilder {
+    mesh: ObjMesh,
+    has_normals: bool,
+    vertices: HashMap<(usize, Option<usize>, Option<usize>), u32>,
+}
+
+impl MeshBuilder {
+    fn new(name: Option<String>, material: Option<String>) -> Self {
+        Self {
+            mesh: ObjMesh {
+                name,
+                material,
+                ..Default::default()
+            },
+            has_normals: false,
+            vertices: HashMap::new(),
+        }
+    }
+
+    fn finish(mut self, with_texture_coordinates: bool) -> ObjMesh {
+        if !self.has_normals {
+            self.mesh.normals = generate_normals(&self.mesh.positions, &self.mesh.indices);
+        }
+        if !with_texture_coordinates {
+            self.mesh.texture_coordinates.clear();
+        }
+        self.mesh
+    }
+}
+
+/// Parses a Wavefront OBJ text.
+///
+/// Supports `v`, `vt`, `vn`, `f`, `o`, `g`, `usemtl` and `mtllib` statements,
+/// other statements are ignored.
+pub fn parse_obj(text: &str) -> Result<Obj, Error> {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut texture_coordinates: Vec<[f32; 2]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+
+    let mut material_libraries = Vec::new();
+    let mut meshes = Vec::new();
+    let mut current = MeshBuilder::new(None, None);
+
+    for (line_index, line) in text.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = strip_line(line);
+        let mut values = line.split_whitespace();
+        let Some(keyword) = values.next() else {
+            continue;
+        };
+
+        match keyword {
+            "v" => positions.push(parse_vec3(line_number, &mut values)?),
+            "vn" => normals.push(parse_vec3(line_number, &mut values)?),
+            "vt" => {
+                let u = parse_f32(line_number, values.next())?;
+                let v = values
+                    .next()
+                    .map(|v| parse_f32(line_number, Some(v)))
+                    .unwrap_or(Ok(0.0))?;
+                texture_coordinates.push([u, v]);
+            }
+            "o" | "g" | "usemtl" => {
+                let name = values.collect::<Vec<_>>().join(" ");
+                let name = if name.is_empty() { None } else { Some(name) };
+                let (mesh_name, material) = if keyword == "usemtl" {
+                    (current.mesh.name.clone(), name)
+                } else {
+                    (name, current.mesh.material.clone())
+                };
+
+                let previous =
+                    std::mem::replace(&mut current, MeshBuilder::new(mesh_name, material));
+                if !previous.mesh.is_empty() {
+                    meshes.push(previous.finish(!texture_coordinates.is_empty()));
+                }
+            }
+            "mtllib" => {
+                let library = values.collect::<Vec<_>>().join(" ");
+                if !library.is_empty() {
+                    material_libraries.push(library);
+                }
+            }
+            "f" => {
+                let mut face = Vec::new();
+                for vertex in values {
+                    let mut parts = vertex.split('/');
+                    let position = resolve_index(
+                        line_number,
+                        parts.next().unwrap_or_default(),
+                        positions.len(),
+                    )?;
+                    let texture_coordinate = match parts.next() {
+                        Some(value) if !value.is_empty() => Some(resolve_index(
+                            line_number,
+                            value,
+                            texture_coordinates.len(),
+                        )?),
+                        _ => None,
+                    };
+                    let normal = match parts.next() {
+                        Some(value) if !value.is_empty() => {
+                            Some(resolve_index(line_number, value, normals.len())?)
+                        }
+                        _ => None,
+                    };
+
+                    let key = (position, texture_coordinate, normal);
+                    let index = match current.vertices.get(&key) {
+                        Some(index) => *index,
+                        None => {
+                            let mesh = &mut current.mesh;
+                            let index = mesh.vertex_count() as u32;
+                            mesh.positions.extend_from_slice(&positions[position]);
+                            mesh.texture_coordinates.extend_from_slice(
+                                &texture_coordinate
+                                    .map(|index| texture_coordinates[index])
+                                    .unwrap_or([0.0, 0.0]),
+                            );
+                            mesh.normals.extend_from_slice(
+                                &normal
+                                    .map(|index| normals[index])
+                                    .unwrap_or([0.0, 0.0, 0.0]),
+                            );
+                            current.has_normals |= normal.is_some();
+                            current.vertices.insert(key, index);
+                            index
+                        }
+                    };
+                    face.push(index);
+                }
+
+                if face.len() < 3 {
+                    return Err(Error::InvalidWavefrontObj(format!(
+                        "face with less than 3 vertices at line {}",
+                        line_number
+                    )));
+                }
+
+                // triangulates polygon as a triangle fan
+                for i in 1..face.len() - 1 {
+                    current
+                        .mesh
+                        .indices
+                        .extend_from_slice(&[face[0], face[i], face[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !current.mesh.is_empty() {
+        meshes.push(current.finish(!texture_coordinates.is_empty()));
+    }
+
+    Ok(Obj {
+        material_libraries,
+        meshes,
+    })
+}
+
+/// Parses a Wavefront MTL text.
+///
+/// Supports `newmtl`, `Ka`, `Kd`, `Ns`, `d`, `Tr`, `map_Kd` and `map_Bump`/`bump`/`norm`
+/// statements, other statements are ignored.
+/// `Ks` is ignored as well, since none of the built materials has a specular color.
+pub fn parse_mtl(text: &str) -> Result<HashMap<String, MtlMaterial>, Error> {
+    let mut materials = HashMap::new();
+    let mut current: Option<MtlMaterial> = None;
+
+    for (line_index, line) in text.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = strip_line(line);
+        let mut values = line.split_whitespace();
+        let Some(keyword) = values.next() else {
+            continue;
+        };
+
+        if keyword == "newmtl" {
+            if let Some(material) = current.take() {
+                materials.insert(material.name.clone(), material);
+            }
+            let name = values.collect::<Vec<_>>().join(" ");
+            current = Some(MtlMaterial::new(name));
+            continue;
+        }
+
+        let Some(material) = current.as_mut() else {
+            continue;
+        };
+        match keyword {
+            "Ka" => material.ambient_color = parse_vec3(line_number, &mut values)?,
+            "Kd" => material.diffuse_color = parse_vec3(line_number, &mut values)?,
+            "Ns" => material.specular_shininess = parse_f32(line_number, values.next())?,
+            "d" => material.dissolve = parse_f32(line_number, values.next())?,
+            "Tr" => material.dissolve = 1.0 - parse_f32(line_number, values.next())?,
+            // texture options are skipped, file name is always the last value
+            "map_Kd" => material.diffuse_map = values.last().map(|map| map.to_string()),
+            "map_Bump" | "map_bump" | "bump" | "norm" => {
+                material.normal_map = values.last().map(|map| map.to_string())
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(material) = current.take() {
+        materials.insert(material.name.clone(), material);
+    }
+
+    Ok(materials)
+}
+
+/// Generates smooth vertex normals by averaging normals of faces sharing a vertex.
+pub fn generate_normals(positions: &[f32], indices: &[u32]) -> Vec<f32> {
+    let mut normals = vec![0.0f32; positions.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [
+            triangle[0] as usize * 3,
+            triangle[1] as usize * 3,
+            triangle[2] as usize * 3,
+        ];
+        let e1 = [
+            positions[b] - positions[a],
+            positions[b + 1] - positions[a + 1],
+            positions[b + 2] - positions[a + 2],
+        ];
+        let e2 = [
+            positions[c] - positions[a],
+            positions[c + 1] - positions[a + 1],
+            positions[c + 2] - positions[a + 2],
+        ];
+        // unnormalized cross product weights face normal by face area
+        let n = [
+            e1[1] * e2[2] - e1[2] * e2[1],
+            e1[2] * e2[0] - e1[0] * e2[2],
+            e1[0] * e2[1] - e1[1] * e2[0],
+        ];
+        for vertex in [a, b, c] {
+            normals[vertex] += n[0];
+            normals[vertex + 1] += n[1];
+            normals[vertex + 2] += n[2];
+        }
+    }
+
+    for normal in normals.chunks_exact_mut(3) {
+        let length = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+        if length > 0.0 {
+            normal[0] /= length;
+            normal[1] /= length;
+            normal[2] /= length;
+        }
+    }
+
+    normals
+}
+
+/// An indexed geometry built from an [`ObjMesh`].
+pub struct ObjGeometry {
+    num_indices: usize,
+    positions: Buffer,
+    normals: Buffer,
+    texture_coordinates: Option<Buffer>,
+    indices: Buffer,
+    bounding_volume: BoundingVolume,
+    channel: (Sender<GeometryMessage>, Receiver<GeometryMessage>),
+}
+
+impl ObjGeometry {
+    /// Constructs a new OBJ geometry from a parsed mesh.
+    pub fn new(mesh: &ObjMesh) -> Self {
+        fn float_buffer(data: &[f32]) -> Buffer {
+            let array = Float32Array::new_with_length(data.len() as u32);
+            array.copy_from(data);
+            buffer::Builder::new(BufferUsage::STATIC_DRAW)
+                .buffer_data(array)
+                .build()
+        }
+
+        let indices = Uint32Array::new_with_length(mesh.indices.len() as u32);
+        indices.copy_from(&mesh.indices);
+
+        Self {
+            num_indices: mesh.indices.len(),
+            positions: float_buffer(&mesh.positions),
+            normals: float_buffer(&mesh.normals),
+            texture_coordinates: if mesh.texture_coordinates.is_empty() {
+                None
+            } else {
+                Some(float_buffer(&mesh.texture_coordinates))
+            },
+            indices: buffer::Builder::new(BufferUsage::STATIC_DRAW)
+                .buffer_data(indices)
+                .build(),
+            bounding_volume: mesh.bounding_volume(),
+            channel: channel(),
+        }
+    }
+}
+
+impl Geometry for ObjGeometry {
+    fn draw_mode(&self) -> DrawMode {
+        DrawMode::TRIANGLES
+    }
+
+    fn draw_range(&self) -> Range<usize> {
+        0..self.num_indices
+    }
+
+    fn cull_face(&self) -> Option<CullFace> {
+        Some(CullFace::BACK)
+    }
+
+    fn bounding_volume(&self) -> Option<Readonly<'_, BoundingVolume>> {
+        Some(Readonly::Borrowed(&self.bounding_volume))
+    }
+
+    fn positions(&self) -> Option<AttributeValue<'_>> {
+        Some(AttributeValue::ArrayBuffer {
+            buffer: Readonly::Borrowed(&self.positions),
+            component_size: BufferComponentSize::Three,
+            data_type: BufferDataType::FLOAT,
+            normalized: false,
+            bytes_stride: 0,
+            byte_offset: 0,
+        })
+    }
+
+    fn normals(&self) -> Option<AttributeValue<'_>> {
+        Some(AttributeValue::ArrayBuffer {
+            buffer: Readonly::Borrowed(&self.normals),
+            component_size: BufferComponentSize::Three,
+            data_type: BufferDataType::FLOAT,
+            normalized: false,
+            bytes_stride: 0,
+            byte_offset: 0,
+        })
+    }
+
+    fn tangents(&self) -> Option<AttributeValue<'_>> {
+        None
+    }
+
+    fn bitangents(&self) -> Option<AttributeValue<'_>> {
+        None
+    }
+
+    fn texture_coordinates(&self) -> Option<AttributeValue<'_>> {
+        self.texture_coordinates
+            .as_ref()
+            .map(|buffer| AttributeValue::ArrayBuffer {
+                buffer: Readonly::Borrowed(buffer),
+                component_size: BufferComponentSize::Two,
+                data_type: BufferDataType::FLOAT,
+                normalized: false,
+                bytes_stride: 0,
+                byte_offset: 0,
+            })
+    }
+
+    fn attribute_value(&self, _: &str) -> Option<AttributeValue<'_>> {
+        None
+    }
+
+    fn uniform_value(&self, _: &str) -> Option<UniformValue<'_>> {
+        None
+    }
+
+    fn uniform_block_value(&self, _: &str) -> Option<UniformBlockValue<'_>> {
+        None
+    }
+
+    fn tick(&mut self, _: &Tick) {}
+
+    fn changed(&self) -> Receiver<GeometryMessage> {
+        self.channel.1.clone()
+    }
+
+    fn as_indexed_geometry(&self) -> Option<&dyn IndexedGeometry> {
+        Some(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl IndexedGeometry for ObjGeometry {
+    fn indices(&self) -> Readonly<'_, Buffer> {
+        Readonly::Borrowed(&self.indices)
+    }
+
+    fn indices_data_type(&self) -> ElementIndicesDataType {
+        ElementIndicesDataType::UNSIGNED_INT
+    }
+
+    fn indices_range(&self) -> Option<Range<usize>> {
+        None
+    }
+}
+
+/// Builds a [`SimpleGroup`] from a parsed OBJ, each mesh is built into a [`SimpleEntity`].
+///
+/// Meshes using a material not found in `materials` fallback to a white [`SolidColorMaterial`].
+/// Texture maps are resolved against `base_url`.
+///
+/// Materials having a diffuse map are built into a textured material, which has no specular shininess,
+/// so `Ns` only applies to materials built into a [`SolidColorMaterial`].
+pub fn build_group(
+    obj: &Obj,
+    materials: &HashMap<String, MtlMaterial>,
+    base_url: Option<&str>,
+) -> SimpleGroup {
+    let mut group = SimpleGroup::new();
+    for mesh in &obj.meshes {
+        let mut entity = SimpleEntity::new();
+        entity.set_geometry(Some(ObjGeometry::new(mesh)));
+
+        let material = mesh
+            .material
+            .as_ref()
+            .and_then(|material| materials.get(material));
+        match material {
+            Some(material)
+                if material.diffuse_map.is_some() && !mesh.texture_coordinates.is_empty() =>
+            {
+                let texture_loader = |url: &str, is_srgb: bool| {
+                    TextureLoader::with_params(
+                        resolve_uri(base_url, url),
+                        [],
+                        [],
+                        [],
+                        true,
+                        is_srgb,
+                    )
+                };
+
+                let diffuse_map = material.diffuse_map.as_deref().unwrap();
+                let mut builder = texture::Builder::new(texture_loader(diffuse_map, true))
+                    .set_transparency(material.transparency());
+                if let Some(normal_map) = material.normal_map.as_deref() {
+                    builder = builder.set_normal_map(texture_loader(normal_map, false));
+                }
+                entity.set_material(Some(builder.build()));
+            }
+            Some(material) => {
+                let [r, g, b] = material.diffuse_color;
+                entity.set_material(Some(SolidColorMaterial::with_color(
+                    Vec3::<f32>::new(r, g, b),
+                    material.specular_shininess,
+                    material.transparency(),
+                )));
+            }
+            None => {
+                entity.set_material(Some(SolidColorMaterial::with_color(
+                    Vec3::<f32>::new(1.0, 1.0, 1.0),
+                    128.0,
+                    Transparency::Opaque,
+                )));
+            }
+        };
+
+        group.add_entity(entity);
+    }
+
+    group
+}
+
+/// A loader loads Wavefront OBJ file and its MTL material libraries from url,
+/// and builds them into a [`SimpleGroup`].
+pub struct ObjLoader {
+    url: String,
+    status: *mut LoaderStatus,
+    channel: (Sender<LoaderStatus>, Receiver<LoaderStatus>),
+    obj: *mut Option<(Obj, HashMap<String, MtlMaterial>, Option<String>)>,
+    error: *mut Option<Error>,
+
+    promise: *mut Option<Promise>,
+}
+
+impl Drop for ObjLoader {
+    fn drop(&mut self) {
+        unsafe {
+            drop(Box::from_raw(self.status));
+            drop(Box::from_raw(self.obj));
+            drop(Box::from_raw(self.error));
+            drop(Box::from_raw(self.promise));
+        }
+    }
+}
+
+impl ObjLoader {
+    /// Constructs a new OBJ loader.
+    pub fn new<S>(url: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            url: url.into(),
+            status: Box::leak(Box::new(LoaderStatus::Unload)),
+            channel: channel(),
+            obj: Box::leak(Box::new(None)),
+            error: Box::leak(Box::new(None)),
+
+            promise: Box::leak(Box::new(None)),
+        }
+    }
+
+    async fn fetch_text(url: &str) -> Result<String, Error> {
+        fn js_error(err: JsValue) -> Error {
+            Error::CommonError(err.as_error().and_then(|err| err.message().as_string()))
+        }
+
+        let mut opts = RequestInit::new();
+        opts.method("GET");
+
+        let request = Request::new_with_str_and_init(url, &opts).map_err(js_error)?;
+        let response = JsFuture::from(window().fetch_with_request(&request))
+            .await
+            .map_err(js_error)?
+            .dyn_into::<Response>()
+            .unwrap();
+        if !response.ok() {
+            return Err(Error::CommonError(Some(format!(
+                "failed to fetch {}, status {}",
+                url,
+                response.status()
+            ))));
+        }
+
+        JsFuture::from(response.text().map_err(js_error)?)
+            .await
+            .map_err(js_error)?
+            .as_string()
+            .ok_or(Error::CommonError(Some(format!(
+                "failed to read text from {}",
+                url
+            ))))
+    }
+
+    async fn fetch_obj(
+        url: String,
+    ) -> Result<(Obj, HashMap<String, MtlMaterial>, Option<String>), Error> {
+        let base_url = document()
+            .url()
+            .ok()
+            .map(|document_url| resolve_uri(Some(&document_url), &url));
+
+        let obj = parse_obj(&Self::fetch_text(&url).await?)?;
+        let mut materials = HashMap::new();
+        for library in &obj.material_libraries {
+            let library_url = resolve_uri(base_url.as_deref(), library);
+            materials.extend(parse_mtl(&Self::fetch_text(&library_url).await?)?);
+        }
+
+        Ok((obj, materials, base_url))
+    }
+
+    /// Starts loading OBJ.
+    /// This method does nothing if OBJ is not in [`LoaderStatus::Unload`] status.
+    pub fn load(&self) {
+        unsafe {
+            if LoaderStatus::Unload != *self.status {
+                return;
+            }
+
+            let status = self.status;
+            let obj = self.obj;
+            let error = self.error;
+            let sender = self.channel.0.clone();
+            let url = self.url.clone();
+
+            (*self.status) = LoaderStatus::Loading;
+            self.channel.0.send(*self.status);
+
+            let promise = wasm_bindgen_futures::future_to_promise(async move {
+                match Self::fetch_obj(url).await {
+                    Ok(parsed) => {
+                        (*status) = LoaderStatus::Loaded;
+                        (*obj) = Some(parsed);
+                        sender.send(*status);
+                        Ok(JsValue::undefined())
+                    }
+                    Err(err) => {
+                        (*status) = LoaderStatus::Errored;
+                        (*error) = Some(err);
+                        sender.send(*status);
+                        Err(JsValue::undefined())
+                    }
+                }
+            });
+            (*self.promise) = Some(promise);
+        }
+    }
+
+    /// Starts loading OBJ and puts it into a [`Promise`].
+    /// This method does nothing if OBJ is not in [`LoaderStatus::Unload`] status.
+    pub fn load_promise(&self) -> Promise {
+        unsafe {
+            self.load();
+            (*self.promise).clone().unwrap()
+        }
+    }
+
+    /// Starts loading OBJ and asynchronous awaiting.
+    /// This method does nothing if OBJ is not in [`LoaderStatus::Unload`] status.
+    pub async fn load_async(&self) -> Result<&Obj, Error> {
+        unsafe {
+            self.load();
+            match JsFuture::from((*self.promise).clone().unwrap()).await {
+                Ok(_) => Ok(&(*self.obj).as_ref().unwrap().0),
+                Err(_) => Err((*self.error).clone().unwrap()),
+            }
+        }
+    }
+
+    /// Returns [`Obj`] if successfully loaded.
+    pub fn loaded_obj(&self) -> Option<&Obj> {
+        unsafe {
+            match &*self.status {
+                LoaderStatus::Unload | LoaderStatus::Loading | LoaderStatus::Errored => None,
+                LoaderStatus::Loaded => (*self.obj).as_ref().map(|(obj, _, _)| obj),
+            }
+        }
+    }
+
+    /// Returns MTL materials if successfully loaded.
+    pub fn loaded_materials(&self) -> Option<&HashMap<String, MtlMaterial>> {
+        unsafe {
+            match &*self.status {
+                LoaderStatus::Unload | LoaderStatus::Loading | LoaderStatus::Errored => None,
+                LoaderStatus::Loaded => (*self.obj).as_ref().map(|(_, materials, _)| materials),
+            }
+        }
+    }
+
+    /// Returns OBJ source url.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl Loader<SimpleGroup> for ObjLoader {
+    type Failure = Error;
+
+    fn status(&self) -> LoaderStatus {
+        unsafe { *self.status }
+    }
+
+    fn load(&mut self) {
+        Self::load(&self);
+    }
+
+    fn loaded(&self) -> Result<SimpleGroup, Error> {
+        unsafe {
+            if let Some(err) = &*self.error {
+                return Err(err.clone());
+            }
+
+            let (obj, materials, base_url) = (*self.obj).as_ref().unwrap();
+            Ok(build_group(obj, materials, base_url.as_deref()))
+        }
+    }
+
+    fn success(&self) -> Receiver<LoaderStatus> {
+        self.channel.1.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_normals, parse_mtl, parse_obj, Transparency};
+
+    #[test]
+    fn test_parse_triangle() {
+        let obj = parse_obj(
+            "# triangle\n\
+             v 0 0 0\n\
+             v 1 0 0\n\
+             v 0 1 0\n\
+             vn 0 0 1\n\
+             f 1//1 2//1 3//1\n",
+        )
+        .unwrap();
+
+        assert_eq!(1, obj.meshes.len());
+        let mesh = &obj.meshes[0];
+        assert_eq!(
+            vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            mesh.positions
+        );
+        assert_eq!(
+            vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            mesh.normals
+        );
+        assert!(mesh.texture_coordinates.is_empty());
+        assert_eq!(vec![0, 1, 2], mesh.indices);
+    }
+
+    #[test]
+    fn test_parse_polygon_and_negative_indices() {
+        let obj = parse_obj(
+            "v 0 0 0\n\
+             v 1 0 0\n\
+             v 1 1 0\n\
+             v 0 1 0\n\
+             vt 0 0\n\
+             vt 1 0\n\
+             vt 1 1\n\
+             vt 0 1\n\
+             f -4/-4 -3/-3 -2/-2 -1/-1\n",
+        )
+        .unwrap();
+
+        let mesh = &obj.meshes[0];
+        assert_eq!(4, mesh.vertex_count());
+        assert_eq!(vec![0, 1, 2, 0, 2, 3], mesh.indices);
+        assert_eq!(
+            vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0],
+            mesh.texture_coordinates
+        );
+        // normals are generated
+        assert_eq!(vec![0.0, 0.0, 1.0], mesh.normals[..3].to_vec());
+    }
+
+    #[test]
+    fn test_parse_groups_and_materials() {
+        let obj = parse_obj(
+            "mtllib scene.mtl\n\
+             v 0 0 0\n\
+             v 1 0 0\n\
+             v 0 1 0\n\
+             o first\n\
+             usemtl red\n\
+             f 1 2 3\n\
+             usemtl blue\n\
+             f 3 2 1\n\
+             g second\n\
+             f 1 2 3\n",
+        )
+        .unwrap();
+
+        assert_eq!(vec!["scene.mtl".to_string()], obj.material_libraries);
+        let names = obj
+            .meshes
+            .iter()
+            .map(|mesh| (mesh.name.as_deref(), mesh.material.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (Some("first"), Some("red")),
+                (Some("first"), Some("blue")),
+                (Some("second"), Some("blue"))
+            ],
+            names
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_obj() {
+        assert!(parse_obj("v 0 0 0\nf 1 2 3\n").is_err());
+        assert!(parse_obj("v 0 0 0\nv 1 0 0\nf 1 2\n").is_err());
+        assert!(parse_obj("v 0 0 a\n").is_err());
+        assert!(parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 0 1 2\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_mtl() {
+        let materials = parse_mtl(
+            "newmtl red\n\
+             Kd 1 0 0\n\
+             Ks 0.5 0.5 0.5\n\
+             Ns 32\n\
+             d 0.5\n\
+             newmtl textured\n\
+             map_Kd -s 1 1 1 albedo.png\n\
+             bump normal.png\n",
+        )
+        .unwrap();
+
+        let red = materials.get("red").unwrap();
+        assert_eq!([1.0, 0.0, 0.0], red.diffuse_color);
+        assert_eq!(32.0, red.specular_shininess);
+        assert!(Transparency::Translucent(0.5) == red.transparency());
+
+        let textured = materials.get("textured").unwrap();
+        assert_eq!(Some("albedo.png"), textured.diffuse_map.as_deref());
+        assert_eq!(Some("normal.png"), textured.normal_map.as_deref());
+        assert!(Transparency::Opaque == textured.transparency());
+    }
+
+    #[test]
+    fn test_generate_normals() {
+        let normals = generate_normals(&[0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0], &[0, 1, 2]);
+        assert_eq!(vec![0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0], normals);
+    }
+}
@@ -1,8 +1,11 @@
+pub mod pbr;
 pub mod solid_color;
 pub mod texture;
 
 use std::{any::Any, borrow::Cow};
 
+use uuid::Uuid;
+
 use crate::{
     clock::Tick,
     message::Receiver,
@@ -43,6 +46,14 @@ pub trait StandardMaterial {
     /// Returns a custom uniform block buffer binding value by an uniform block name.
     fn uniform_block_value(&self, name: &str) -> Option<UniformBlockValue<'_>>;
 
+    /// Returns ids of textures currently used by this material.
+    /// Drawer sorts entities sharing the same program by textures to reduce texture binding changes.
+    ///
+    /// Returns nothing as default.
+    fn textures(&self) -> Vec<Uuid> {
+        Vec::new()
+    }
+
     /// Returns GLSL code snippet with processing function for fragment shader.
     fn fragment_process(&self) -> Cow<'_, str>;
 
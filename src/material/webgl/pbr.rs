@@ -0,0 +1,617 @@
+use std::{any::Any, borrow::Cow, cell::RefCell, rc::Rc};
+
+use gl_matrix4rust::vec3::Vec3;
+use log::warn;
+use uuid::Uuid;
+
+use crate::{
+    clock::Tick,
+    error::Error,
+    loader::{Loader, LoaderStatus},
+    message::{channel, Receiver, Sender},
+    pipeline::webgl::brdf_lut::{StandardBrdfLut, DEFAULT_BRDF_LUT_SIZE},
+    renderer::webgl::{
+        attribute::AttributeValue,
+        matrix::GlF32,
+        program::Define,
+        state::FrameState,
+        texture::{Texture, Texture2D, TextureCubeMap, TextureUnit},
+        uniform::{UniformBlockValue, UniformValue},
+    },
+    value::Readonly,
+};
+
+use super::{texture::WaitLoader, MaterialMessage, StandardMaterial, Transparency};
+
+/// Image based lighting environment for [`PbrMaterial`].
+///
+/// Irradiance and prefiltered specular cube maps are provided by developer,
+/// while the BRDF lookup table is generated at runtime the first time it is prepared.
+/// An image based lighting environment is supposed to be shared by materials
+/// using [`Rc<RefCell<ImageBasedLighting>>`], so the lookup table is generated only once.
+pub struct ImageBasedLighting {
+    irradiance: Texture<TextureCubeMap>,
+    prefiltered: Texture<TextureCubeMap>,
+    prefiltered_max_lod: f32,
+    brdf_lut_size: usize,
+    brdf_lut: Option<Texture<Texture2D>>,
+}
+
+impl ImageBasedLighting {
+    /// Constructs a new image based lighting environment.
+    ///
+    /// Roughness of prefiltered cube map should map linearly to mipmap levels,
+    /// from `0.0` on level 0 to `1.0` on level `prefiltered_max_lod`.
+    pub fn new(
+        irradiance: Texture<TextureCubeMap>,
+        prefiltered: Texture<TextureCubeMap>,
+        prefiltered_max_lod: f32,
+    ) -> Self {
+        Self::with_brdf_lut_size(
+            irradiance,
+            prefiltered,
+            prefiltered_max_lod,
+            DEFAULT_BRDF_LUT_SIZE,
+        )
+    }
+
+    /// Constructs a new image based lighting environment with specified BRDF lookup table size.
+    pub fn with_brdf_lut_size(
+        irradiance: Texture<TextureCubeMap>,
+        prefiltered: Texture<TextureCubeMap>,
+        prefiltered_max_lod: f32,
+        brdf_lut_size: usize,
+    ) -> Self {
+        Self {
+            irradiance,
+            prefiltered,
+            prefiltered_max_lod,
+            brdf_lut_size,
+            brdf_lut: None,
+        }
+    }
+
+    /// Returns diffuse irradiance cube map.
+    pub fn irradiance(&self) -> &Texture<TextureCubeMap> {
+        &self.irradiance
+    }
+
+    /// Returns specular prefiltered cube map.
+    pub fn prefiltered(&self) -> &Texture<TextureCubeMap> {
+        &self.prefiltered
+    }
+
+    /// Returns maximum mipmap level of prefiltered cube map.
+    pub fn prefiltered_max_lod(&self) -> f32 {
+        self.prefiltered_max_lod
+    }
+
+    /// Returns BRDF lookup table. Returns `None` if it is not generated yet.
+    pub fn brdf_lut(&self) -> Option<&Texture<Texture2D>> {
+        self.brdf_lut.as_ref()
+    }
+
+    /// Returns `true` if BRDF lookup table is generated.
+    pub fn ready(&self) -> bool {
+        self.brdf_lut.is_some()
+    }
+
+    /// Generates BRDF lookup table if not generated yet.
+    pub fn prepare(&mut self, state: &mut FrameState) -> Result<(), Error> {
+        if self.brdf_lut.is_some() {
+            return Ok(());
+        }
+
+        let lut = StandardBrdfLut::new().generate(state, self.brdf_lut_size)?;
+        self.brdf_lut = Some(lut);
+        Ok(())
+    }
+}
+
+/// Texture map slot of [`PbrMaterial`].
+struct TextureMap {
+    unit: TextureUnit,
+    loader: Option<Rc<RefCell<dyn Loader<Texture<Texture2D>, Failure = Error>>>>,
+    texture: Rc<RefCell<Option<(Texture<Texture2D>, TextureUnit)>>>,
+}
+
+impl TextureMap {
+    fn new(
+        unit: TextureUnit,
+        loader: Option<Rc<RefCell<dyn Loader<Texture<Texture2D>, Failure = Error>>>>,
+    ) -> Self {
+        Self {
+            unit,
+            loader,
+            texture: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.loader.is_some()
+    }
+
+    fn ready(&self) -> bool {
+        !self.enabled() || self.texture.borrow().is_some()
+    }
+
+    fn prepare(&self, sender: &Sender<MaterialMessage>) {
+        let Some(texture_loader) = self.loader.as_ref() else {
+            return;
+        };
+
+        let mut loader = texture_loader.borrow_mut();
+        if LoaderStatus::Unload == loader.status() {
+            loader.load();
+            loader.success().on(WaitLoader {
+                unit: self.unit,
+                loader: Rc::downgrade(texture_loader),
+                target: Rc::downgrade(&self.texture),
+                sender: sender.clone(),
+            });
+        }
+    }
+
+    fn uniform_value(&self) -> Option<UniformValue<'_>> {
+        let texture = self.texture.borrow();
+        match &*texture {
+            Some((texture, unit)) => Some(UniformValue::Texture2D {
+                texture: Readonly::Owned(texture.clone()),
+                unit: *unit,
+            }),
+            None => None,
+        }
+    }
+}
+
+/// A physically based material in metallic-roughness workflow,
+/// lighting by Cook-Torrance BRDF and optional image based lighting.
+///
+/// Every map is multiplied by its corresponding factor.
+/// Metallic and roughness are sampled from blue and green channel of their maps,
+/// as glTF packs them, so a packed metallic-roughness texture could be used for both.
+/// Base color and emissive maps are expected to be in linear space,
+/// loads them with an sRGB internal format if they are stored in sRGB.
+pub struct PbrMaterial {
+    transparency: Transparency,
+
+    base_color: Vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+    emissive: Vec3<f32>,
+    occlusion_strength: f32,
+
+    base_color_map: TextureMap,
+    metallic_map: TextureMap,
+    roughness_map: TextureMap,
+    normal_map: TextureMap,
+    occlusion_map: TextureMap,
+    emissive_map: TextureMap,
+
+    ibl: Option<Rc<RefCell<ImageBasedLighting>>>,
+
+    channel: (Sender<MaterialMessage>, Receiver<MaterialMessage>),
+}
+
+impl PbrMaterial {
+    /// Constructs a new physically based material without any map.
+    pub fn new(base_color: Vec3<f32>, metallic: f32, roughness: f32) -> Self {
+        Builder::new()
+            .set_base_color(base_color)
+            .set_metallic(metallic)
+            .set_roughness(roughness)
+            .build()
+    }
+
+    /// Returns base color factor.
+    pub fn base_color(&self) -> Vec3<f32> {
+        self.base_color
+    }
+
+    /// Sets base color factor.
+    pub fn set_base_color(&mut self, base_color: Vec3<f32>) {
+        self.base_color = base_color;
+        self.channel.0.send(MaterialMessage::Changed);
+    }
+
+    /// Returns metallic factor.
+    pub fn metallic(&self) -> f32 {
+        self.metallic
+    }
+
+    /// Sets metallic factor.
+    pub fn set_metallic(&mut self, metallic: f32) {
+        self.metallic = metallic;
+        self.channel.0.send(MaterialMessage::Changed);
+    }
+
+    /// Returns roughness factor.
+    pub fn roughness(&self) -> f32 {
+        self.roughness
+    }
+
+    /// Sets roughness factor.
+    pub fn set_roughness(&mut self, roughness: f32) {
+        self.roughness = roughness;
+        self.channel.0.send(MaterialMessage::Changed);
+    }
+
+    /// Returns emissive factor.
+    pub fn emissive(&self) -> Vec3<f32> {
+        self.emissive
+    }
+
+    /// Sets emissive factor.
+    pub fn set_emissive(&mut self, emissive: Vec3<f32>) {
+        self.emissive = emissive;
+        self.channel.0.send(MaterialMessage::Changed);
+    }
+
+    /// Returns image based lighting environment.
+    pub fn image_based_lighting(&self) -> Option<&Rc<RefCell<ImageBasedLighting>>> {
+        self.ibl.as_ref()
+    }
+
+    /// Sets image based lighting environment.
+    pub fn set_image_based_lighting(&mut self, ibl: Option<Rc<RefCell<ImageBasedLighting>>>) {
+        self.ibl = ibl;
+        self.channel.0.send(MaterialMessage::Changed);
+    }
+
+    fn maps(&self) -> [&TextureMap; 6] {
+        [
+            &self.base_color_map,
+            &self.metallic_map,
+            &self.roughness_map,
+            &self.normal_map,
+            &self.occlusion_map,
+            &self.emissive_map,
+        ]
+    }
+}
+
+impl StandardMaterial for PbrMaterial {
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed("PbrMaterial")
+    }
+
+    fn ready(&self) -> bool {
+        let ibl_ready = match self.ibl.as_ref() {
+            Some(ibl) => ibl.borrow().ready(),
+            None => true,
+        };
+        ibl_ready && self.maps().iter().all(|map| map.ready())
+    }
+
+    fn prepare(&mut self, state: &mut FrameState) {
+        for map in self.maps() {
+            map.prepare(&self.channel.0);
+        }
+
+        if let Some(ibl) = self.ibl.as_ref() {
+            if let Err(err) = ibl.borrow_mut().prepare(state) {
+                warn!("Failed to generate BRDF lookup table. {}", err);
+            }
+        }
+    }
+
+    fn tick(&mut self, _: &Tick) {}
+
+    fn changed(&self) -> Receiver<MaterialMessage> {
+        self.channel.1.clone()
+    }
+
+    fn transparency(&self) -> Transparency {
+        self.transparency
+    }
+
+    fn attribute_value(&self, _: &str) -> Option<AttributeValue<'_>> {
+        None
+    }
+
+    fn uniform_value(&self, name: &str) -> Option<UniformValue<'_>> {
+        match name {
+            "u_Material_BaseColor" => {
+                Some(UniformValue::FloatVector3(self.base_color.to_f32_array()))
+            }
+            "u_Material_Metallic" => Some(UniformValue::Float1(self.metallic)),
+            "u_Material_Roughness" => Some(UniformValue::Float1(self.roughness)),
+            "u_Material_Emissive" => Some(UniformValue::FloatVector3(self.emissive.to_f32_array())),
+            "u_Material_OcclusionStrength" => Some(UniformValue::Float1(self.occlusion_strength)),
+            "u_Material_Transparency" => Some(UniformValue::Float1(self.transparency.alpha())),
+            "u_Material_BaseColorMap" => self.base_color_map.uniform_value(),
+            "u_Material_MetallicMap" => self.metallic_map.uniform_value(),
+            "u_Material_RoughnessMap" => self.roughness_map.uniform_value(),
+            "u_Material_NormalMap" => self.normal_map.uniform_value(),
+            "u_Material_OcclusionMap" => self.occlusion_map.uniform_value(),
+            "u_Material_EmissiveMap" => self.emissive_map.uniform_value(),
+            "u_Material_IrradianceMap" => {
+                self.ibl.as_ref().map(|ibl| UniformValue::TextureCubeMap {
+                    texture: Readonly::Owned(ibl.borrow().irradiance().clone()),
+                    unit: TextureUnit::TEXTURE6,
+                })
+            }
+            "u_Material_PrefilteredMap" => {
+                self.ibl.as_ref().map(|ibl| UniformValue::TextureCubeMap {
+                    texture: Readonly::Owned(ibl.borrow().prefiltered().clone()),
+                    unit: TextureUnit::TEXTURE7,
+                })
+            }
+            "u_Material_PrefilteredMaxLod" => self
+                .ibl
+                .as_ref()
+                .map(|ibl| UniformValue::Float1(ibl.borrow().prefiltered_max_lod())),
+            "u_Material_BrdfLut" => self.ibl.as_ref().and_then(|ibl| {
+                ibl.borrow().brdf_lut().map(|lut| UniformValue::Texture2D {
+                    texture: Readonly::Owned(lut.clone()),
+                    unit: TextureUnit::TEXTURE8,
+                })
+            }),
+            _ => None,
+        }
+    }
+
+    fn uniform_block_value(&self, _: &str) -> Option<UniformBlockValue<'_>> {
+        None
+    }
+
+    fn textures(&self) -> Vec<Uuid> {
+        let mut textures = self
+            .maps()
+            .iter()
+            .filter_map(|map| map.texture.borrow().as_ref().map(|(texture, _)| texture.id()))
+            .collect::<Vec<_>>();
+        if let Some(ibl) = self.ibl.as_ref() {
+            let ibl = ibl.borrow();
+            textures.push(ibl.irradiance().id());
+            textures.push(ibl.prefiltered().id());
+        }
+        textures
+    }
+
+    fn fragment_process(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("./shaders/pbr_fragment_process.glsl"))
+    }
+
+    fn vertex_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn fragment_defines(&self) -> Cow<'_, [Define<'_>]> {
+        let mut defines = vec![Define::WithoutValue(Cow::Borrowed("USE_PBR"))];
+        let maps = [
+            (&self.base_color_map, "USE_BASE_COLOR_MAP"),
+            (&self.metallic_map, "USE_METALLIC_MAP"),
+            (&self.roughness_map, "USE_ROUGHNESS_MAP"),
+            (&self.normal_map, "USE_NORMAL_MAP"),
+            (&self.occlusion_map, "USE_OCCLUSION_MAP"),
+            (&self.emissive_map, "USE_EMISSIVE_MAP"),
+        ];
+        for (map, define) in maps {
+            if map.enabled() {
+                defines.push(Define::WithoutValue(Cow::Borrowed(define)));
+            }
+        }
+        if self.ibl.is_some() {
+            defines.push(Define::WithoutValue(Cow::Borrowed("USE_IBL")));
+        }
+        Cow::Owned(defines)
+    }
+
+    fn snippet(&self, _: &str) -> Option<Cow<'_, str>> {
+        None
+    }
+
+    fn use_position_eye_space(&self) -> bool {
+        false
+    }
+
+    fn use_normal(&self) -> bool {
+        true
+    }
+
+    fn use_texture_coordinate(&self) -> bool {
+        self.maps().iter().any(|map| map.enabled())
+    }
+
+    fn use_tbn(&self) -> bool {
+        self.normal_map.enabled()
+    }
+
+    fn use_calculated_bitangent(&self) -> bool {
+        true
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+pub struct Builder {
+    transparency: Transparency,
+    base_color: Vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+    emissive: Vec3<f32>,
+    occlusion_strength: f32,
+    base_color_loader: Option<Rc<RefCell<dyn Loader<Texture<Texture2D>, Failure = Error>>>>,
+    metallic_loader: Option<Rc<RefCell<dyn Loader<Texture<Texture2D>, Failure = Error>>>>,
+    roughness_loader: Option<Rc<RefCell<dyn Loader<Texture<Texture2D>, Failure = Error>>>>,
+    normal_loader: Option<Rc<RefCell<dyn Loader<Texture<Texture2D>, Failure = Error>>>>,
+    occlusion_loader: Option<Rc<RefCell<dyn Loader<Texture<Texture2D>, Failure = Error>>>>,
+    emissive_loader: Option<Rc<RefCell<dyn Loader<Texture<Texture2D>, Failure = Error>>>>,
+    metallic_roughness_packed: bool,
+    ibl: Option<Rc<RefCell<ImageBasedLighting>>>,
+}
+
+impl Builder {
+    /// Constructs a new physically based material builder.
+    /// By default, the transparency is set to [`Transparency::Opaque`],
+    /// base color is white, the material is fully metallic and fully rough, and emits nothing.
+    pub fn new() -> Self {
+        Self {
+            transparency: Transparency::Opaque,
+            base_color: Vec3::<f32>::new(1.0, 1.0, 1.0),
+            metallic: 1.0,
+            roughness: 1.0,
+            emissive: Vec3::<f32>::new(0.0, 0.0, 0.0),
+            occlusion_strength: 1.0,
+            base_color_loader: None,
+            metallic_loader: None,
+            roughness_loader: None,
+            normal_loader: None,
+            occlusion_loader: None,
+            emissive_loader: None,
+            metallic_roughness_packed: false,
+            ibl: None,
+        }
+    }
+
+    /// Sets transparency for the material.
+    pub fn set_transparency(mut self, transparency: Transparency) -> Self {
+        self.transparency = transparency;
+        self
+    }
+
+    /// Sets base color factor for the material.
+    pub fn set_base_color(mut self, base_color: Vec3<f32>) -> Self {
+        self.base_color = base_color;
+        self
+    }
+
+    /// Sets metallic factor for the material.
+    pub fn set_metallic(mut self, metallic: f32) -> Self {
+        self.metallic = metallic;
+        self
+    }
+
+    /// Sets roughness factor for the material.
+    pub fn set_roughness(mut self, roughness: f32) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    /// Sets emissive factor for the material.
+    pub fn set_emissive(mut self, emissive: Vec3<f32>) -> Self {
+        self.emissive = emissive;
+        self
+    }
+
+    /// Sets occlusion strength for the material, only works when occlusion map is set.
+    pub fn set_occlusion_strength(mut self, occlusion_strength: f32) -> Self {
+        self.occlusion_strength = occlusion_strength;
+        self
+    }
+
+    /// Sets base color map for the material.
+    pub fn set_base_color_map<L>(mut self, loader: L) -> Self
+    where
+        L: Loader<Texture<Texture2D>, Failure = Error> + 'static,
+    {
+        self.base_color_loader = Some(Rc::new(RefCell::new(loader)));
+        self
+    }
+
+    /// Sets metallic map for the material, metallic is sampled from blue channel.
+    pub fn set_metallic_map<L>(mut self, loader: L) -> Self
+    where
+        L: Loader<Texture<Texture2D>, Failure = Error> + 'static,
+    {
+        self.metallic_loader = Some(Rc::new(RefCell::new(loader)));
+        self.metallic_roughness_packed = false;
+        self
+    }
+
+    /// Sets roughness map for the material, roughness is sampled from green channel.
+    pub fn set_roughness_map<L>(mut self, loader: L) -> Self
+    where
+        L: Loader<Texture<Texture2D>, Failure = Error> + 'static,
+    {
+        self.roughness_loader = Some(Rc::new(RefCell::new(loader)));
+        self.metallic_roughness_packed = false;
+        self
+    }
+
+    /// Sets a packed metallic-roughness map for the material,
+    /// metallic in blue channel and roughness in green channel, as glTF does.
+    /// The texture is loaded only once and shared by metallic and roughness.
+    pub fn set_metallic_roughness_map<L>(mut self, loader: L) -> Self
+    where
+        L: Loader<Texture<Texture2D>, Failure = Error> + 'static,
+    {
+        let loader: Rc<RefCell<dyn Loader<Texture<Texture2D>, Failure = Error>>> =
+            Rc::new(RefCell::new(loader));
+        self.metallic_loader = Some(Rc::clone(&loader));
+        self.roughness_loader = Some(loader);
+        self.metallic_roughness_packed = true;
+        self
+    }
+
+    /// Sets normal map for the material.
+    pub fn set_normal_map<L>(mut self, loader: L) -> Self
+    where
+        L: Loader<Texture<Texture2D>, Failure = Error> + 'static,
+    {
+        self.normal_loader = Some(Rc::new(RefCell::new(loader)));
+        self
+    }
+
+    /// Sets occlusion map for the material, occlusion is sampled from red channel.
+    pub fn set_occlusion_map<L>(mut self, loader: L) -> Self
+    where
+        L: Loader<Texture<Texture2D>, Failure = Error> + 'static,
+    {
+        self.occlusion_loader = Some(Rc::new(RefCell::new(loader)));
+        self
+    }
+
+    /// Sets emissive map for the material.
+    pub fn set_emissive_map<L>(mut self, loader: L) -> Self
+    where
+        L: Loader<Texture<Texture2D>, Failure = Error> + 'static,
+    {
+        self.emissive_loader = Some(Rc::new(RefCell::new(loader)));
+        self
+    }
+
+    /// Sets image based lighting environment for the material.
+    pub fn set_image_based_lighting(mut self, ibl: Rc<RefCell<ImageBasedLighting>>) -> Self {
+        self.ibl = Some(ibl);
+        self
+    }
+
+    pub fn build(self) -> PbrMaterial {
+        let metallic_map = TextureMap::new(TextureUnit::TEXTURE1, self.metallic_loader);
+        let roughness_map = if self.metallic_roughness_packed {
+            // shares loaded texture with metallic map, only metallic map waits for the loader
+            TextureMap {
+                unit: metallic_map.unit,
+                loader: metallic_map.loader.clone(),
+                texture: Rc::clone(&metallic_map.texture),
+            }
+        } else {
+            TextureMap::new(TextureUnit::TEXTURE2, self.roughness_loader)
+        };
+
+        PbrMaterial {
+            transparency: self.transparency,
+            base_color: self.base_color,
+            metallic: self.metallic,
+            roughness: self.roughness,
+            emissive: self.emissive,
+            occlusion_strength: self.occlusion_strength,
+            base_color_map: TextureMap::new(TextureUnit::TEXTURE0, self.base_color_loader),
+            metallic_map,
+            roughness_map,
+            normal_map: TextureMap::new(TextureUnit::TEXTURE3, self.normal_loader),
+            occlusion_map: TextureMap::new(TextureUnit::TEXTURE4, self.occlusion_loader),
+            emissive_map: TextureMap::new(TextureUnit::TEXTURE5, self.emissive_loader),
+            ibl: self.ibl,
+            channel: channel(),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+/**
+ * Physically Based Frgament Process Snippet.
+ */
+
+uniform vec3 u_Material_BaseColor;
+uniform float u_Material_Metallic;
+uniform float u_Material_Roughness;
+uniform vec3 u_Material_Emissive;
+uniform float u_Material_Transparency;
+
+#ifdef USE_BASE_COLOR_MAP
+uniform sampler2D u_Material_BaseColorMap;
+#endif
+
+#ifdef USE_METALLIC_MAP
+uniform sampler2D u_Material_MetallicMap;
+#endif
+
+#ifdef USE_ROUGHNESS_MAP
+uniform sampler2D u_Material_RoughnessMap;
+#endif
+
+#ifdef USE_NORMAL_MAP
+uniform sampler2D u_Material_NormalMap;
+#endif
+
+#ifdef USE_OCCLUSION_MAP
+uniform sampler2D u_Material_OcclusionMap;
+uniform float u_Material_OcclusionStrength;
+#endif
+
+#ifdef USE_EMISSIVE_MAP
+uniform sampler2D u_Material_EmissiveMap;
+#endif
+
+#ifdef USE_IBL
+uniform samplerCube u_Material_IrradianceMap;
+uniform samplerCube u_Material_PrefilteredMap;
+uniform float u_Material_PrefilteredMaxLod;
+uniform sampler2D u_Material_BrdfLut;
+#endif
+
+atoy_PbrFragment fragment_process() {
+    vec3 base_color = u_Material_BaseColor;
+    float transparency = u_Material_Transparency;
+    #ifdef USE_BASE_COLOR_MAP
+    vec4 base_color_texel = texture(u_Material_BaseColorMap, v_TexCoord);
+    base_color *= base_color_texel.rgb;
+    transparency *= base_color_texel.a;
+    #endif
+
+    // metallic and roughness are sampled from blue and green channel, same as glTF packs them
+    float metallic = u_Material_Metallic;
+    #ifdef USE_METALLIC_MAP
+    metallic *= texture(u_Material_MetallicMap, v_TexCoord).b;
+    #endif
+    metallic = clamp(metallic, 0.0f, 1.0f);
+
+    float roughness = u_Material_Roughness;
+    #ifdef USE_ROUGHNESS_MAP
+    roughness *= texture(u_Material_RoughnessMap, v_TexCoord).g;
+    #endif
+    // prevents specular highlight from collapsing into a single point
+    roughness = clamp(roughness, 0.04f, 1.0f);
+
+    vec3 normal;
+    #ifdef USE_NORMAL_MAP
+    normal = texture(u_Material_NormalMap, v_TexCoord).xyz * 2.0f - 1.0f;
+    normal = normalize(v_TBN * normal);
+    #else
+    normal = normalize(v_Normal);
+    #endif
+
+    float occlusion = 1.0f;
+    #ifdef USE_OCCLUSION_MAP
+    occlusion += u_Material_OcclusionStrength * (texture(u_Material_OcclusionMap, v_TexCoord).r - 1.0f);
+    #endif
+
+    vec3 emission = u_Material_Emissive;
+    #ifdef USE_EMISSIVE_MAP
+    emission *= texture(u_Material_EmissiveMap, v_TexCoord).rgb;
+    #endif
+
+    #if defined(USE_LIGHTING) && defined(USE_IBL)
+    vec3 to_camera = normalize(u_CameraPosition - v_Position);
+    emission += atoy_image_based_lighting(u_Material_IrradianceMap, u_Material_PrefilteredMap, u_Material_PrefilteredMaxLod, u_Material_BrdfLut, base_color, metallic, roughness, normal, to_camera) * occlusion;
+    #endif
+
+    return atoy_PbrFragment(v_Position, normal, base_color, metallic, roughness, occlusion, emission, transparency);
+}
@@ -6,6 +6,7 @@ use std::{
 };
 
 use log::warn;
+use uuid::Uuid;
 
 use crate::{
     clock::Tick,
@@ -201,6 +202,13 @@ impl StandardMaterial for TextureMaterial {
         None
     }
 
+    fn textures(&self) -> Vec<Uuid> {
+        [&self.albedo, &self.normal, &self.parallax]
+            .iter()
+            .filter_map(|texture| texture.borrow().as_ref().map(|(texture, _)| texture.id()))
+            .collect()
+    }
+
     fn fragment_process(&self) -> Cow<'_, str> {
         Cow::Borrowed(include_str!("./shaders/texture_fragment_process.glsl"))
     }
@@ -255,11 +263,12 @@ impl StandardMaterial for TextureMaterial {
     }
 }
 
-struct WaitLoader {
-    unit: TextureUnit,
-    loader: Weak<RefCell<dyn Loader<Texture<Texture2D>, Failure = Error>>>,
-    target: Weak<RefCell<Option<(Texture<Texture2D>, TextureUnit)>>>,
-    sender: Sender<MaterialMessage>,
+/// Executor storing a loaded texture into material and notifying material changed.
+pub(super) struct WaitLoader {
+    pub(super) unit: TextureUnit,
+    pub(super) loader: Weak<RefCell<dyn Loader<Texture<Texture2D>, Failure = Error>>>,
+    pub(super) target: Weak<RefCell<Option<(Texture<Texture2D>, TextureUnit)>>>,
+    pub(super) sender: Sender<MaterialMessage>,
 }
 
 impl Executor for WaitLoader {
@@ -285,7 +294,7 @@ impl Executor for WaitLoader {
                 };
 
                 let err = loader.borrow().loaded().err().unwrap();
-                warn!("Failed to load albedo texture. {}", err)
+                warn!("Failed to load texture. {}", err)
             }
         }
     }
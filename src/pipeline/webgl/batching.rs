@@ -0,0 +1,670 @@
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    ops::Range,
+    rc::{Rc, Weak},
+};
+
+use uuid::Uuid;
+use web_sys::js_sys::Uint8Array;
+
+use crate::{
+    bounding::{merge_bounding_volumes, BoundingVolume, CullingBoundingVolume},
+    clock::Tick,
+    entity::Entity,
+    geometry::{Geometry, GeometryMessage, IndexedGeometry},
+    message::{channel, Receiver, Sender},
+    renderer::webgl::{
+        attribute::AttributeValue,
+        buffer::{self, Buffer, BufferComponentSize, BufferDataType, BufferUsage},
+        draw::{CullFace, DrawMode, ElementIndicesDataType},
+        error::Error,
+        matrix::GlF32,
+        program::Define,
+        state::FrameState,
+        uniform::{UniformBlockValue, UniformValue},
+    },
+    value::Readonly,
+};
+
+/// Maximum vertices of an entity could be merged into a static batch.
+/// Entities with more vertices are drawn individually.
+pub const STATIC_BATCHING_MAX_VERTICES: usize = 4096;
+
+/// Drawing counters of a frame.
+///
+/// Counters are totals of all entity shading passes of a frame,
+/// such as gbuffer, forward and translucent passes.
+/// Shadow mapping and picking are not counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct DrawStatistics {
+    draw_calls: usize,
+    program_switches: usize,
+    program_switches_saved: usize,
+    static_batches: usize,
+    static_batched_entities: usize,
+}
+
+impl DrawStatistics {
+    /// Returns number of draw calls issued for entities.
+    pub fn draw_calls(&self) -> usize {
+        self.draw_calls
+    }
+
+    /// Returns number of times a different program is used.
+    pub fn program_switches(&self) -> usize {
+        self.program_switches
+    }
+
+    /// Returns number of program switches saved by state sorting,
+    /// compared with drawing opaque entities in distance order.
+    /// Always `0` if state sorting is disabled.
+    pub fn program_switches_saved(&self) -> usize {
+        self.program_switches_saved
+    }
+
+    /// Returns number of static batches drawn.
+    pub fn static_batches(&self) -> usize {
+        self.static_batches
+    }
+
+    /// Returns number of entities merged into static batches drawn.
+    pub fn static_batched_entities(&self) -> usize {
+        self.static_batched_entities
+    }
+
+    pub(super) fn add_draw_call(&mut self) {
+        self.draw_calls += 1;
+    }
+
+    pub(super) fn add_program_switch(&mut self) {
+        self.program_switches += 1;
+    }
+
+    pub(super) fn set_program_switches_saved(&mut self, program_switches_saved: usize) {
+        self.program_switches_saved = program_switches_saved;
+    }
+
+    pub(super) fn add_static_batch(&mut self, entities: usize) {
+        self.static_batches += 1;
+        self.static_batched_entities += entities;
+    }
+}
+
+/// Sorting key grouping entities by rendering states,
+/// ordered by program first, then textures and then vertex buffers.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(super) struct StateSortKey {
+    program: u64,
+    textures: Vec<Uuid>,
+    vertex_buffer: Option<Uuid>,
+}
+
+impl StateSortKey {
+    /// Computes sorting key of an entity.
+    /// Returns `None` if entity has no geometry or material.
+    pub(super) fn new(entity: &dyn Entity) -> Option<Self> {
+        let geometry = entity.geometry()?;
+        let material = entity.material()?;
+
+        // program name is decided by material name and defines
+        let mut hasher = DefaultHasher::new();
+        material.name().hash(&mut hasher);
+        let hash_defines = |defines: &[Define<'_>], hasher: &mut DefaultHasher| {
+            for define in defines {
+                define.name().hash(hasher);
+                define.value().hash(hasher);
+            }
+        };
+        hash_defines(&material.vertex_defines(), &mut hasher);
+        hash_defines(&material.fragment_defines(), &mut hasher);
+        material.use_position_eye_space().hash(&mut hasher);
+        material.use_normal().hash(&mut hasher);
+        material.use_texture_coordinate().hash(&mut hasher);
+        material.use_tbn().hash(&mut hasher);
+        material.use_tbn_invert().hash(&mut hasher);
+        material.use_calculated_bitangent().hash(&mut hasher);
+        entity.as_instanced_entity().is_some().hash(&mut hasher);
+
+        // entities sharing vertex buffers are likely sharing the same vertex array layout
+        let vertex_buffer = match geometry.positions() {
+            Some(AttributeValue::ArrayBuffer { buffer, .. }) => Some(buffer.id()),
+            _ => None,
+        };
+
+        Some(Self {
+            program: hasher.finish(),
+            textures: material.textures(),
+            vertex_buffer,
+        })
+    }
+}
+
+/// Returns number of program switches drawing entities in order of their sort keys.
+/// Entities without sort key are not drawn by material programs and are skipped.
+pub(super) fn program_switches<'a, I>(keys: I) -> usize
+where
+    I: IntoIterator<Item = Option<&'a StateSortKey>>,
+{
+    let mut switches = 0;
+    let mut last_program = None;
+    for key in keys.into_iter().flatten() {
+        if last_program != Some(key.program) {
+            switches += 1;
+            last_program = Some(key.program);
+        }
+    }
+    switches
+}
+
+/// Static entities sharing the same static batch id merged into a single geometry.
+///
+/// Vertices of all members are transformed into model space of the first member,
+/// which is the representative providing model matrix and material for drawing.
+pub struct StaticBatch {
+    id: Uuid,
+    representative: Weak<RefCell<dyn Entity>>,
+    members: Vec<(Weak<RefCell<dyn Entity>>, [f32; 16])>,
+    geometry: StaticBatchGeometry,
+    bounding_volume: Option<CullingBoundingVolume>,
+}
+
+impl StaticBatch {
+    /// Merges static entities into a static batch.
+    /// Returns `None` if less than 2 entities or any entity is not mergeable.
+    ///
+    /// Entities are mergeable only if they draw triangles with the same vertex attributes in float.
+    pub(super) fn build(
+        state: &FrameState,
+        id: Uuid,
+        entities: &[Rc<RefCell<dyn Entity>>],
+    ) -> Result<Option<Self>, Error> {
+        if entities.len() < 2 {
+            return Ok(None);
+        }
+
+        let representative = entities[0].borrow();
+        let Some(representative_geometry) = representative.geometry() else {
+            return Ok(None);
+        };
+        let cull_face = representative_geometry.cull_face();
+        let Ok(inverse_representative) = representative.compose_model_matrix().invert() else {
+            return Ok(None);
+        };
+
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut tangents = Vec::new();
+        let mut bitangents = Vec::new();
+        let mut texture_coordinates = Vec::new();
+        let mut layout = None;
+        let mut boundings = Vec::with_capacity(entities.len());
+        let mut world_boundings = Vec::with_capacity(entities.len());
+        let mut members = Vec::with_capacity(entities.len());
+        for entity in entities {
+            let entity_ref = entity.borrow();
+            let Some(geometry) = entity_ref.geometry() else {
+                return Ok(None);
+            };
+            if geometry.draw_mode() != DrawMode::TRIANGLES {
+                return Ok(None);
+            }
+
+            let Some(vertices) = read_vertex_indices(state, geometry)? else {
+                return Ok(None);
+            };
+            if vertices.len() > STATIC_BATCHING_MAX_VERTICES {
+                return Ok(None);
+            }
+
+            let compose_model_matrix = *entity_ref.compose_model_matrix();
+            let model_matrix = inverse_representative * compose_model_matrix;
+            let Ok(normal_matrix) = model_matrix.invert() else {
+                return Ok(None);
+            };
+            let model_matrix_f32 = model_matrix.to_f32_array();
+            let normal_matrix_f32 = normal_matrix.transpose().to_f32_array();
+
+            let attributes = vec![
+                geometry.positions(),
+                geometry.normals(),
+                geometry.tangents(),
+                geometry.bitangents(),
+                geometry.texture_coordinates(),
+            ];
+            let mut entity_layout = [None; 5];
+            let mut entity_data = Vec::with_capacity(5);
+            for (index, attribute) in attributes.into_iter().enumerate() {
+                let Some(attribute) = attribute else {
+                    entity_data.push(Vec::new());
+                    continue;
+                };
+                let Some((data, components)) = read_float_attribute(state, attribute, &vertices)?
+                else {
+                    return Ok(None);
+                };
+                entity_layout[index] = Some(components);
+                entity_data.push(data);
+            }
+            match layout {
+                Some(layout) if layout != entity_layout => return Ok(None),
+                Some(_) => {}
+                None => layout = Some(entity_layout),
+            }
+            // positions are required
+            if entity_layout[0].is_none() {
+                return Ok(None);
+            }
+
+            let outputs = vec![
+                &mut positions,
+                &mut normals,
+                &mut tangents,
+                &mut bitangents,
+                &mut texture_coordinates,
+            ];
+            for (index, (output, data)) in outputs.into_iter().zip(entity_data).enumerate() {
+                let Some(components) = entity_layout[index] else {
+                    continue;
+                };
+                let appended = output_extend(output, &data);
+                match index {
+                    // positions
+                    0 => transform_vectors(appended, components, |v| {
+                        transform_point(&model_matrix_f32, v)
+                    }),
+                    // normals
+                    1 => transform_vectors(appended, components, |v| {
+                        transform_direction(&normal_matrix_f32, v)
+                    }),
+                    // tangents and bitangents
+                    2 | 3 => transform_vectors(appended, components, |v| {
+                        transform_direction(&model_matrix_f32, v)
+                    }),
+                    // texture coordinates
+                    _ => {}
+                }
+            }
+
+            if let Some(bounding) = geometry.bounding_volume() {
+                boundings.push(bounding.transform(model_matrix));
+            }
+            if let Some(bounding) = entity_ref.bounding_volume() {
+                world_boundings.push(bounding.bounding_volume());
+            }
+            members.push((Rc::downgrade(entity), compose_model_matrix.to_f32_array()));
+        }
+
+        let layout = layout.unwrap();
+        let vertex_count = positions.len() / layout[0].unwrap();
+        let bounding_volume = if world_boundings.len() == entities.len() {
+            merge_bounding_volumes(world_boundings).map(CullingBoundingVolume::new)
+        } else {
+            None
+        };
+        let geometry = StaticBatchGeometry {
+            vertex_count,
+            cull_face,
+            bounding_volume: if boundings.len() == entities.len() {
+                merge_bounding_volumes(boundings)
+            } else {
+                None
+            },
+            positions: float_buffer(positions, layout[0]),
+            normals: float_buffer(normals, layout[1]),
+            tangents: float_buffer(tangents, layout[2]),
+            bitangents: float_buffer(bitangents, layout[3]),
+            texture_coordinates: float_buffer(texture_coordinates, layout[4]),
+            channel: channel(),
+        };
+        drop(representative);
+
+        Ok(Some(Self {
+            id,
+            representative: Rc::downgrade(&entities[0]),
+            members,
+            geometry,
+            bounding_volume,
+        }))
+    }
+
+    /// Returns static batch id.
+    pub fn id(&self) -> &Uuid {
+        &self.id
+    }
+
+    /// Returns the representative entity providing model matrix and material.
+    pub fn representative(&self) -> Option<Rc<RefCell<dyn Entity>>> {
+        self.representative.upgrade()
+    }
+
+    /// Returns number of entities merged into this batch.
+    pub fn members_count(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns merged geometry.
+    pub fn geometry(&self) -> &dyn Geometry {
+        &self.geometry
+    }
+
+    /// Returns bounding volume of all members in world space.
+    pub fn bounding_volume(&self) -> Option<&CullingBoundingVolume> {
+        self.bounding_volume.as_ref()
+    }
+
+    /// Returns `true` if this batch is built from the same entities at the same place.
+    pub(super) fn matches(&self, entities: &[Rc<RefCell<dyn Entity>>]) -> bool {
+        self.members.len() == entities.len()
+            && self
+                .members
+                .iter()
+                .zip(entities)
+                .all(|((member, model_matrix), entity)| {
+                    member
+                        .upgrade()
+                        .map(|member| Rc::ptr_eq(&member, entity))
+                        .unwrap_or(false)
+                        && &entity.borrow().compose_model_matrix().to_f32_array() == model_matrix
+                })
+    }
+}
+
+/// Merged geometry of a [`StaticBatch`].
+struct StaticBatchGeometry {
+    vertex_count: usize,
+    cull_face: Option<CullFace>,
+    bounding_volume: Option<BoundingVolume>,
+    positions: Option<(Buffer, BufferComponentSize)>,
+    normals: Option<(Buffer, BufferComponentSize)>,
+    tangents: Option<(Buffer, BufferComponentSize)>,
+    bitangents: Option<(Buffer, BufferComponentSize)>,
+    texture_coordinates: Option<(Buffer, BufferComponentSize)>,
+    channel: (Sender<GeometryMessage>, Receiver<GeometryMessage>),
+}
+
+impl Geometry for StaticBatchGeometry {
+    fn draw_mode(&self) -> DrawMode {
+        DrawMode::TRIANGLES
+    }
+
+    fn draw_range(&self) -> Range<usize> {
+        0..self.vertex_count
+    }
+
+    fn cull_face(&self) -> Option<CullFace> {
+        self.cull_face
+    }
+
+    fn bounding_volume(&self) -> Option<Readonly<'_, BoundingVolume>> {
+        self.bounding_volume.as_ref().map(Readonly::Borrowed)
+    }
+
+    fn positions(&self) -> Option<AttributeValue<'_>> {
+        float_attribute(&self.positions)
+    }
+
+    fn normals(&self) -> Option<AttributeValue<'_>> {
+        float_attribute(&self.normals)
+    }
+
+    fn tangents(&self) -> Option<AttributeValue<'_>> {
+        float_attribute(&self.tangents)
+    }
+
+    fn bitangents(&self) -> Option<AttributeValue<'_>> {
+        float_attribute(&self.bitangents)
+    }
+
+    fn texture_coordinates(&self) -> Option<AttributeValue<'_>> {
+        float_attribute(&self.texture_coordinates)
+    }
+
+    fn attribute_value(&self, _: &str) -> Option<AttributeValue<'_>> {
+        None
+    }
+
+    fn uniform_value(&self, _: &str) -> Option<UniformValue<'_>> {
+        None
+    }
+
+    fn uniform_block_value(&self, _: &str) -> Option<UniformBlockValue<'_>> {
+        None
+    }
+
+    fn tick(&mut self, _: &Tick) {}
+
+    fn changed(&self) -> Receiver<GeometryMessage> {
+        self.channel.1.clone()
+    }
+
+    fn as_indexed_geometry(&self) -> Option<&dyn IndexedGeometry> {
+        None
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+fn float_attribute(buffer: &Option<(Buffer, BufferComponentSize)>) -> Option<AttributeValue<'_>> {
+    buffer
+        .as_ref()
+        .map(|(buffer, component_size)| AttributeValue::ArrayBuffer {
+            buffer: Readonly::Borrowed(buffer),
+            component_size: *component_size,
+            data_type: BufferDataType::FLOAT,
+            normalized: false,
+            bytes_stride: 0,
+            byte_offset: 0,
+        })
+}
+
+fn float_buffer(
+    data: Vec<f32>,
+    components: Option<usize>,
+) -> Option<(Buffer, BufferComponentSize)> {
+    let component_size = match components? {
+        1 => BufferComponentSize::One,
+        2 => BufferComponentSize::Two,
+        3 => BufferComponentSize::Three,
+        _ => BufferComponentSize::Four,
+    };
+    let buffer = buffer::Builder::new(BufferUsage::STATIC_DRAW)
+        .buffer_data(
+            data.into_iter()
+                .flat_map(|value| value.to_ne_bytes())
+                .collect::<Vec<_>>(),
+        )
+        .build();
+    Some((buffer, component_size))
+}
+
+/// Reads buffer data back from WebGL runtime.
+fn read_buffer(state: &FrameState, buffer: &Buffer) -> Result<Option<Vec<u8>>, Error> {
+    state.buffer_store().register(buffer)?;
+    buffer.upload()?;
+    Ok(buffer
+        .read_back()?
+        .map(|data| Uint8Array::new(&data).to_vec()))
+}
+
+/// Returns indices of vertices in drawing order, element indices are expanded for indexed geometry.
+/// Returns `None` if indices could not be read back.
+fn read_vertex_indices(
+    state: &FrameState,
+    geometry: &dyn Geometry,
+) -> Result<Option<Vec<usize>>, Error> {
+    let range = geometry.draw_range();
+    let Some(indexed) = geometry.as_indexed_geometry() else {
+        return Ok(Some(range.collect()));
+    };
+
+    let Some(data) = read_buffer(state, &indexed.indices())? else {
+        return Ok(None);
+    };
+    let byte_length = match indexed.indices_data_type() {
+        ElementIndicesDataType::UNSIGNED_BYTE => 1,
+        ElementIndicesDataType::UNSIGNED_SHORT => 2,
+        ElementIndicesDataType::UNSIGNED_INT => 4,
+    };
+    // range start is the byte offset of the first index for indexed geometry
+    let start = range.start;
+    let mut vertices = Vec::with_capacity(range.len());
+    for i in 0..range.len() {
+        let offset = start + i * byte_length;
+        let Some(bytes) = data.get(offset..offset + byte_length) else {
+            return Ok(None);
+        };
+        let index = match byte_length {
+            1 => bytes[0] as usize,
+            2 => u16::from_ne_bytes([bytes[0], bytes[1]]) as usize,
+            _ => u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize,
+        };
+        vertices.push(index);
+    }
+    Ok(Some(vertices))
+}
+
+/// Reads float attribute data of `vertices` back from WebGL runtime.
+/// Returns flatten data and component count,
+/// or `None` if attribute is not a float array buffer.
+fn read_float_attribute(
+    state: &FrameState,
+    attribute: AttributeValue<'_>,
+    vertices: &[usize],
+) -> Result<Option<(Vec<f32>, usize)>, Error> {
+    let AttributeValue::ArrayBuffer {
+        buffer,
+        component_size,
+        data_type: BufferDataType::FLOAT,
+        normalized: false,
+        bytes_stride,
+        byte_offset,
+    } = attribute
+    else {
+        return Ok(None);
+    };
+    let Some(bytes) = read_buffer(state, &buffer)? else {
+        return Ok(None);
+    };
+
+    let components = component_size as usize;
+    let stride = if bytes_stride == 0 {
+        components * 4
+    } else {
+        bytes_stride
+    };
+    let mut data = Vec::with_capacity(vertices.len() * components);
+    for vertex in vertices {
+        for component in 0..components {
+            let offset = byte_offset + vertex * stride + component * 4;
+            let Some(value) = bytes.get(offset..offset + 4) else {
+                return Ok(None);
+            };
+            data.push(f32::from_ne_bytes([value[0], value[1], value[2], value[3]]));
+        }
+    }
+    Ok(Some((data, components)))
+}
+
+/// Appends `data` to `output` and returns the appended part.
+fn output_extend<'a>(output: &'a mut Vec<f32>, data: &[f32]) -> &'a mut [f32] {
+    let start = output.len();
+    output.extend_from_slice(data);
+    &mut output[start..]
+}
+
+/// Transforms the first three components of each vector in place.
+fn transform_vectors<F>(data: &mut [f32], components: usize, transform: F)
+where
+    F: Fn([f32; 3]) -> [f32; 3],
+{
+    if components < 3 {
+        return;
+    }
+
+    for vector in data.chunks_exact_mut(components) {
+        let [x, y, z] = transform([vector[0], vector[1], vector[2]]);
+        vector[0] = x;
+        vector[1] = y;
+        vector[2] = z;
+    }
+}
+
+/// Transforms a point by a column major affine matrix.
+fn transform_point(m: &[f32; 16], [x, y, z]: [f32; 3]) -> [f32; 3] {
+    [
+        m[0] * x + m[4] * y + m[8] * z + m[12],
+        m[1] * x + m[5] * y + m[9] * z + m[13],
+        m[2] * x + m[6] * y + m[10] * z + m[14],
+    ]
+}
+
+/// Transforms a direction by a column major matrix, ignoring translation.
+fn transform_direction(m: &[f32; 16], [x, y, z]: [f32; 3]) -> [f32; 3] {
+    [
+        m[0] * x + m[4] * y + m[8] * z,
+        m[1] * x + m[5] * y + m[9] * z,
+        m[2] * x + m[6] * y + m[10] * z,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        program_switches, transform_direction, transform_point, transform_vectors, StateSortKey,
+    };
+
+    fn key(program: u64) -> StateSortKey {
+        StateSortKey {
+            program,
+            textures: Vec::new(),
+            vertex_buffer: None,
+        }
+    }
+
+    #[test]
+    fn test_program_switches() {
+        let mut keys = vec![Some(key(1)), Some(key(2)), None, Some(key(1)), Some(key(2))];
+        assert_eq!(program_switches(keys.iter().map(|key| key.as_ref())), 4);
+
+        keys.sort();
+        assert_eq!(program_switches(keys.iter().map(|key| key.as_ref())), 2);
+
+        assert_eq!(program_switches(Vec::<Option<&StateSortKey>>::new()), 0);
+        assert_eq!(program_switches(vec![None, None]), 0);
+    }
+
+    #[test]
+    fn test_transform_point() {
+        #[rustfmt::skip]
+        let m = [
+            0.0, 1.0, 0.0, 0.0,
+            -1.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 2.0, 0.0,
+            1.0, 2.0, 3.0, 1.0,
+        ];
+
+        assert_eq!(transform_point(&m, [1.0, 0.0, 1.0]), [1.0, 3.0, 5.0]);
+        assert_eq!(transform_direction(&m, [1.0, 0.0, 1.0]), [0.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_transform_vectors() {
+        let mut data = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+        transform_vectors(&mut data, 4, |[x, y, z]| [x * 2.0, y * 2.0, z * 2.0]);
+        assert_eq!(data, [2.0, 4.0, 6.0, 4.0, 10.0, 12.0, 14.0, 8.0]);
+
+        // vectors less than 3 components are left untouched
+        let mut data = [1.0, 2.0];
+        transform_vectors(&mut data, 2, |[x, y, z]| [x * 2.0, y * 2.0, z * 2.0]);
+        assert_eq!(data, [1.0, 2.0]);
+    }
+}
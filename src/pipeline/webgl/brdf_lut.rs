@@ -0,0 +1,125 @@
+use std::borrow::Cow;
+
+use web_sys::WebGl2RenderingContext;
+
+use crate::renderer::webgl::{
+    error::Error,
+    program::{Define, ProgramSource},
+    state::FrameState,
+    texture::{
+        Builder, SamplerParameter, Texture, Texture2D, TextureInternalFormat,
+        TextureMagnificationFilter, TextureMinificationFilter, TextureUncompressedInternalFormat,
+        TextureWrapMethod,
+    },
+};
+
+/// Default width and height of a BRDF lookup table.
+pub const DEFAULT_BRDF_LUT_SIZE: usize = 512;
+
+/// Standard BRDF lookup table generator.
+/// Integrates the split sum approximation of a GGX specular BRDF into a 2d texture,
+/// sampled by `(n_dot_v, roughness)` with scale to F0 in red channel and bias in green channel.
+///
+/// Lookup table is stored in [`TextureUncompressedInternalFormat::RG16F`] if
+/// extension `EXT_color_buffer_float` supported,
+/// or [`TextureUncompressedInternalFormat::RGBA8`] otherwise.
+pub struct StandardBrdfLut;
+
+impl StandardBrdfLut {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Generates a new BRDF lookup table with `size` for both width and height.
+    pub fn generate(
+        &mut self,
+        state: &mut FrameState,
+        size: usize,
+    ) -> Result<Texture<Texture2D>, Error> {
+        let internal_format = if state.capabilities().color_buffer_float_supported() {
+            TextureUncompressedInternalFormat::RG16F
+        } else {
+            TextureUncompressedInternalFormat::RGBA8
+        };
+
+        let mut builder = Builder::<Texture2D>::new(
+            TextureInternalFormat::Uncompressed(internal_format),
+            1,
+            size,
+            size,
+        );
+        builder.set_sampler_parameters([
+            SamplerParameter::MAG_FILTER(TextureMagnificationFilter::LINEAR),
+            SamplerParameter::MIN_FILTER(TextureMinificationFilter::LINEAR),
+            SamplerParameter::WRAP_S(TextureWrapMethod::CLAMP_TO_EDGE),
+            SamplerParameter::WRAP_T(TextureWrapMethod::CLAMP_TO_EDGE),
+        ]);
+        let lut = builder.build();
+        lut.init(state.gl())?;
+        let native = lut.native()?;
+
+        let program = state
+            .program_store_mut()
+            .get_or_compile_program(&BrdfIntegration)?;
+        program.use_program()?;
+
+        let gl = state.gl();
+        let framebuffer = gl
+            .create_framebuffer()
+            .ok_or(Error::CreateFramebufferFailure)?;
+        gl.bind_framebuffer(WebGl2RenderingContext::DRAW_FRAMEBUFFER, Some(&framebuffer));
+        gl.framebuffer_texture_2d(
+            WebGl2RenderingContext::DRAW_FRAMEBUFFER,
+            WebGl2RenderingContext::COLOR_ATTACHMENT0,
+            WebGl2RenderingContext::TEXTURE_2D,
+            Some(&native),
+            0,
+        );
+        gl.viewport(0, 0, size as i32, size as i32);
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLE_FAN, 0, 4);
+        gl.bind_framebuffer(WebGl2RenderingContext::DRAW_FRAMEBUFFER, None);
+        gl.delete_framebuffer(Some(&framebuffer));
+        gl.viewport(
+            0,
+            0,
+            state.canvas().width() as i32,
+            state.canvas().height() as i32,
+        );
+
+        program.unuse_program()?;
+
+        Ok(lut)
+    }
+}
+
+struct BrdfIntegration;
+
+impl ProgramSource for BrdfIntegration {
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed("BrdfIntegration")
+    }
+
+    fn vertex_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("./shaders/computation.vert"))
+    }
+
+    fn fragment_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("./shaders/brdf_lut.frag"))
+    }
+
+    fn universal_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn vertex_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn fragment_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn snippet(&self, _: &str) -> Option<Cow<'_, str>> {
+        None
+    }
+}
@@ -0,0 +1,801 @@
+use std::borrow::Cow;
+
+use gl_matrix4rust::{mat4::Mat4, vec3::Vec3};
+use log::warn;
+use web_sys::js_sys::{Float32Array, Uint32Array};
+
+use crate::{
+    frustum::{cascade_splits, frustum_near_distance, frustum_slice_corners},
+    light::attenuation::Attenuation,
+    renderer::webgl::{
+        buffer::Buffer,
+        error::Error,
+        matrix::GlF32,
+        program::Program,
+        state::FrameState,
+        texture::{
+            Builder, SamplerParameter, Texture, Texture2D, TextureData, TextureInternalFormat,
+            TextureMagnificationFilter, TextureMinificationFilter, TextureSource,
+            TextureUncompressedData, TextureUncompressedInternalFormat,
+            TextureUncompressedPixelDataType, TextureUncompressedPixelFormat, TextureUnit,
+            TextureWrapMethod,
+        },
+        uniform::{UniformBinding, UniformValue},
+    },
+    scene::Scene,
+};
+
+use super::{
+    UBO_CLUSTERS_BLOCK_BINDING, UBO_CLUSTERS_BYTE_LENGTH, UBO_CLUSTERS_DEPTH_RANGE_BYTE_OFFSET,
+    UBO_CLUSTERS_DIMENSIONS_BYTE_OFFSET, UBO_CLUSTERS_UNIFORM_BLOCK_MOUNT_POINT,
+    UBO_LIGHTS_POINT_LIGHT_BYTE_LENGTH, UBO_LIGHTS_SPOT_LIGHT_BYTE_LENGTH,
+};
+
+/// Default cluster grid, 16 by 9 tiles in screen space and 24 slices along view direction.
+pub const DEFAULT_CLUSTER_GRID: ClusterGrid = ClusterGrid { x: 16, y: 9, z: 24 };
+/// Default view distance where the last slice of clusters ends.
+pub const DEFAULT_CLUSTER_FAR: f64 = 500.0;
+/// Maximum lights assigned to a single cluster.
+/// Lights beyond this count are dropped from the cluster in order of their indices.
+pub const MAX_LIGHTS_PER_CLUSTER: usize = 128;
+/// Maximum point lights and spot lights in total applied by clustered lighting.
+pub const MAX_CLUSTERED_LIGHTS: usize = 2048;
+/// Light contribution below which a light is considered out of range,
+/// relative to the brightest color component of the light.
+pub const LIGHT_RANGE_CUTOFF: f32 = 1.0 / 256.0;
+
+/// Texture unit the light data texture bound to.
+pub const CLUSTER_LIGHTS_TEXTURE_UNIT: TextureUnit = TextureUnit::TEXTURE15;
+/// Texture unit the light indices texture bound to.
+pub const CLUSTER_INDICES_TEXTURE_UNIT: TextureUnit = TextureUnit::TEXTURE16;
+
+/// Texels of a light in light data texture, enough for the largest light, a spot light.
+const LIGHT_TEXELS: usize = UBO_LIGHTS_SPOT_LIGHT_BYTE_LENGTH / 16;
+/// Width of light indices texture.
+const INDICES_TEXTURE_WIDTH: usize = 2048;
+
+const CLUSTER_LIGHTS_TEXTURE_UNIFORM_NAME: &'static str = "u_ClusterLightsTexture";
+const CLUSTER_LIGHTS_TEXTURE_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(CLUSTER_LIGHTS_TEXTURE_UNIFORM_NAME));
+
+const CLUSTER_INDICES_TEXTURE_UNIFORM_NAME: &'static str = "u_ClusterIndicesTexture";
+const CLUSTER_INDICES_TEXTURE_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(CLUSTER_INDICES_TEXTURE_UNIFORM_NAME));
+
+/// Dimensions of a cluster grid.
+/// View frustum is split into `x` by `y` tiles in screen space
+/// and `z` slices along view direction, each cluster is a froxel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClusterGrid {
+    x: usize,
+    y: usize,
+    z: usize,
+}
+
+impl ClusterGrid {
+    /// Constructs a new cluster grid. Each dimension is at least `1`.
+    pub fn new(x: usize, y: usize, z: usize) -> Self {
+        Self {
+            x: x.max(1),
+            y: y.max(1),
+            z: z.max(1),
+        }
+    }
+
+    /// Returns tiles in screen space horizontally.
+    pub fn x(&self) -> usize {
+        self.x
+    }
+
+    /// Returns tiles in screen space vertically.
+    pub fn y(&self) -> usize {
+        self.y
+    }
+
+    /// Returns slices along view direction.
+    pub fn z(&self) -> usize {
+        self.z
+    }
+
+    /// Returns total count of clusters.
+    pub fn count(&self) -> usize {
+        self.x * self.y * self.z
+    }
+
+    /// Returns index of a cluster, tiles in a slice are stored row by row and slices follow one another.
+    pub fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        x + y * self.x + z * self.x * self.y
+    }
+}
+
+/// Bounding sphere of the range of a light in view space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightVolume {
+    pub center: Vec3<f64>,
+    pub radius: f64,
+}
+
+/// Lights assigned to clusters of a cluster grid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterAssignment {
+    grid: ClusterGrid,
+    near: f64,
+    far: f64,
+    ranges: Vec<(u32, u32)>,
+    indices: Vec<u32>,
+}
+
+impl ClusterAssignment {
+    /// Returns cluster grid.
+    pub fn grid(&self) -> ClusterGrid {
+        self.grid
+    }
+
+    /// Returns view distance where the first slice starts.
+    pub fn near(&self) -> f64 {
+        self.near
+    }
+
+    /// Returns view distance where the last slice ends.
+    pub fn far(&self) -> f64 {
+        self.far
+    }
+
+    /// Returns offset into [`ClusterAssignment::indices`] and count of lights of each cluster,
+    /// indexed by [`ClusterGrid::index`].
+    pub fn ranges(&self) -> &[(u32, u32)] {
+        &self.ranges
+    }
+
+    /// Returns light indices of all clusters.
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Returns light indices of a cluster.
+    pub fn lights(&self, x: usize, y: usize, z: usize) -> &[u32] {
+        let (offset, count) = self.ranges[self.grid.index(x, y, z)];
+        &self.indices[offset as usize..(offset + count) as usize]
+    }
+}
+
+/// Returns distance where lighting of a light attenuates below [`LIGHT_RANGE_CUTOFF`] of `intensity`,
+/// the brightest color component of the light.
+/// Returns [`f64::INFINITY`] if lighting never attenuates below the cutoff.
+pub fn light_range(attenuation: &Attenuation, intensity: f32) -> f64 {
+    if intensity <= 0.0 {
+        return 0.0;
+    }
+
+    let a = attenuation.a() as f64;
+    let b = attenuation.b() as f64;
+    let c = attenuation.c() as f64;
+    // solves a + b * d + c * d^2 = intensity / cutoff
+    let k = (intensity / LIGHT_RANGE_CUTOFF) as f64;
+    if k <= a {
+        0.0
+    } else if c > 0.0 {
+        (-b + (b * b + 4.0 * c * (k - a)).sqrt()) / (2.0 * c)
+    } else if b > 0.0 {
+        (k - a) / b
+    } else {
+        f64::INFINITY
+    }
+}
+
+/// Assigns lights to clusters of the view frustum of a projection matrix.
+///
+/// Slices split view distances from near plane to `far` logarithmically,
+/// or uniformly for orthogonal projections. Tiles split each slice uniformly in NDC.
+/// A light is assigned to a cluster if its volume intersects bounding box of the cluster,
+/// up to [`MAX_LIGHTS_PER_CLUSTER`] lights per cluster.
+/// Index of a light in `lights` is stored, `None` stands for a light never assigned, such as a disabled light.
+///
+/// Returns `None` if projection matrix is not invertible or `far` is not beyond near plane.
+pub fn assign_lights(
+    proj_matrix: &Mat4<f64>,
+    grid: ClusterGrid,
+    far: f64,
+    lights: &[Option<LightVolume>],
+) -> Option<ClusterAssignment> {
+    let near = frustum_near_distance(proj_matrix)?;
+    if far <= near {
+        return None;
+    }
+    let splits = cascade_splits(near, far, grid.z, 1.0);
+
+    // bounding boxes of clusters in view space, ordered by cluster index
+    let mut bounds = Vec::with_capacity(grid.count());
+    for z in 0..grid.z {
+        let corners = frustum_slice_corners(proj_matrix, splits[z], splits[z + 1])?;
+        let corners = corners.map(|corner| [*corner.x(), *corner.y(), *corner.z()]);
+        for y in 0..grid.y {
+            let v0 = y as f64 / grid.y as f64;
+            let v1 = (y + 1) as f64 / grid.y as f64;
+            for x in 0..grid.x {
+                let u0 = x as f64 / grid.x as f64;
+                let u1 = (x + 1) as f64 / grid.x as f64;
+
+                let mut min = [f64::INFINITY; 3];
+                let mut max = [f64::NEG_INFINITY; 3];
+                for side in [0, 4] {
+                    for (u, v) in [(u0, v0), (u1, v0), (u1, v1), (u0, v1)] {
+                        let bottom = lerp(&corners[side], &corners[side + 1], u);
+                        let top = lerp(&corners[side + 3], &corners[side + 2], u);
+                        let point = lerp(&bottom, &top, v);
+                        for axis in 0..3 {
+                            min[axis] = min[axis].min(point[axis]);
+                            max[axis] = max[axis].max(point[axis]);
+                        }
+                    }
+                }
+                bounds.push((min, max));
+            }
+        }
+    }
+
+    let slice_len = grid.x * grid.y;
+    let mut clusters = vec![Vec::new(); grid.count()];
+    for (index, light) in lights.iter().enumerate() {
+        let Some(light) = light else {
+            continue;
+        };
+        let center = [*light.center.x(), *light.center.y(), *light.center.z()];
+        let depth = -center[2];
+
+        for z in 0..grid.z {
+            // skips slices never overlapped by view distances the light covers
+            if depth + light.radius < splits[z] || depth - light.radius > splits[z + 1] {
+                continue;
+            }
+
+            for cluster in z * slice_len..(z + 1) * slice_len {
+                let lights = &mut clusters[cluster];
+                if lights.len() >= MAX_LIGHTS_PER_CLUSTER {
+                    continue;
+                }
+
+                let (min, max) = &bounds[cluster];
+                let distance_squared = (0..3)
+                    .map(|axis| {
+                        let offset = center[axis] - center[axis].clamp(min[axis], max[axis]);
+                        offset * offset
+                    })
+                    .sum::<f64>();
+                if distance_squared <= light.radius * light.radius {
+                    lights.push(index as u32);
+                }
+            }
+        }
+    }
+
+    let mut ranges = Vec::with_capacity(clusters.len());
+    let mut indices = Vec::new();
+    for lights in clusters {
+        ranges.push((indices.len() as u32, lights.len() as u32));
+        indices.extend(lights);
+    }
+
+    Some(ClusterAssignment {
+        grid,
+        near,
+        far,
+        ranges,
+        indices,
+    })
+}
+
+fn lerp(from: &[f64; 3], to: &[f64; 3], t: f64) -> [f64; 3] {
+    [
+        from[0] + (to[0] - from[0]) * t,
+        from[1] + (to[1] - from[1]) * t,
+        from[2] + (to[2] - from[2]) * t,
+    ]
+}
+
+/// Packs offset and count of each cluster and light indices of all clusters into light indices texture data,
+/// wrapped into rows of [`INDICES_TEXTURE_WIDTH`] texels.
+///
+/// Offset and count of cluster `i` are stored at texels `2 * i` and `2 * i + 1`,
+/// light indices follow and offsets point into the whole texture.
+/// Clusters absent from `ranges` are empty.
+fn pack_indices(clusters: usize, ranges: &[(u32, u32)], indices: &[u32]) -> Vec<u32> {
+    let base = clusters * 2;
+    let len = base + indices.len();
+    let rows = (len + INDICES_TEXTURE_WIDTH - 1) / INDICES_TEXTURE_WIDTH;
+    let mut data = vec![0u32; rows * INDICES_TEXTURE_WIDTH];
+    for (index, (offset, count)) in ranges.iter().enumerate().take(clusters) {
+        data[index * 2] = base as u32 + *offset;
+        data[index * 2 + 1] = *count;
+    }
+    data[base..len].copy_from_slice(indices);
+    data
+}
+
+/// Raw data uploading to a cluster texture.
+enum ClusterTextureSource {
+    Float {
+        width: usize,
+        height: usize,
+        data: Float32Array,
+    },
+    UnsignedInteger {
+        pixel_format: TextureUncompressedPixelFormat,
+        width: usize,
+        height: usize,
+        data: Uint32Array,
+    },
+}
+
+impl TextureSource for ClusterTextureSource {
+    fn data(&self) -> TextureData {
+        match self {
+            ClusterTextureSource::Float {
+                width,
+                height,
+                data,
+            } => TextureData::Uncompressed {
+                pixel_format: TextureUncompressedPixelFormat::RGBA,
+                pixel_data_type: TextureUncompressedPixelDataType::FLOAT,
+                pixel_storages: Vec::new(),
+                data: TextureUncompressedData::Float32Array {
+                    width: *width,
+                    height: *height,
+                    data: data.clone(),
+                    src_element_offset: None,
+                },
+            },
+            ClusterTextureSource::UnsignedInteger {
+                pixel_format,
+                width,
+                height,
+                data,
+            } => TextureData::Uncompressed {
+                pixel_format: *pixel_format,
+                pixel_data_type: TextureUncompressedPixelDataType::UNSIGNED_INT,
+                pixel_storages: Vec::new(),
+                data: TextureUncompressedData::Uint32Array {
+                    width: *width,
+                    height: *height,
+                    data: data.clone(),
+                    src_element_offset: None,
+                },
+            },
+        }
+    }
+}
+
+/// Standard clustered lighting.
+///
+/// Lights are assigned to clusters of the camera view frustum on CPU every frame,
+/// shaders look up the cluster of a fragment and loop over lights assigned to it only.
+/// Point lights and spot lights are packed into a light data texture,
+/// point lights come first and spot lights follow, up to [`MAX_CLUSTERED_LIGHTS`] lights.
+/// Offset and count of each cluster and light indices of all clusters are packed into a light indices texture,
+/// so that clustered lighting occupies two samplers only, keeping lit programs
+/// with shadows, physically based materials and image based lighting within
+/// the guaranteed 16 fragment texture image units.
+///
+/// Directional lights and area lights are still applied from Uniform Buffer Object `atoy_Lights`.
+/// Shadows are available for lights packed into `atoy_Lights` only.
+pub struct StandardClusteredLighting {
+    grid: ClusterGrid,
+    far: f64,
+
+    lights_texture: Option<Texture<Texture2D>>,
+    indices_texture: Option<Texture<Texture2D>>,
+}
+
+impl StandardClusteredLighting {
+    pub fn new() -> Self {
+        Self {
+            grid: DEFAULT_CLUSTER_GRID,
+            far: DEFAULT_CLUSTER_FAR,
+
+            lights_texture: None,
+            indices_texture: None,
+        }
+    }
+
+    /// Returns cluster grid.
+    pub fn grid(&self) -> ClusterGrid {
+        self.grid
+    }
+
+    /// Sets cluster grid.
+    pub fn set_grid(&mut self, grid: ClusterGrid) {
+        self.grid = grid;
+    }
+
+    /// Returns view distance where the last slice of clusters ends.
+    /// Fragments beyond are lit by lights of the last slice.
+    pub fn far(&self) -> f64 {
+        self.far
+    }
+
+    /// Sets view distance where the last slice of clusters ends.
+    pub fn set_far(&mut self, far: f64) {
+        self.far = far;
+    }
+
+    /// Returns a texture of at least `height` rows, creates a new one if `texture` is too small.
+    fn reserve_texture<'a>(
+        state: &FrameState,
+        texture: &'a mut Option<Texture<Texture2D>>,
+        internal_format: TextureUncompressedInternalFormat,
+        width: usize,
+        height: usize,
+    ) -> Result<&'a Texture<Texture2D>, Error> {
+        let height = height.max(1);
+        let reusable = texture
+            .as_ref()
+            .map(|texture| texture.width() == width && texture.height() >= height)
+            .unwrap_or(false);
+
+        if !reusable {
+            let mut builder = Builder::<Texture2D>::new(
+                TextureInternalFormat::Uncompressed(internal_format),
+                1,
+                width,
+                height.next_power_of_two(),
+            );
+            builder.set_sampler_parameters([
+                SamplerParameter::MAG_FILTER(TextureMagnificationFilter::NEAREST),
+                SamplerParameter::MIN_FILTER(TextureMinificationFilter::NEAREST),
+                SamplerParameter::WRAP_S(TextureWrapMethod::CLAMP_TO_EDGE),
+                SamplerParameter::WRAP_T(TextureWrapMethod::CLAMP_TO_EDGE),
+            ]);
+            let created = builder.build();
+            created.init(state.gl())?;
+            *texture = Some(created);
+        }
+
+        Ok(texture.as_ref().unwrap())
+    }
+
+    /// Assigns lights in the scene to clusters, uploads cluster textures
+    /// and updates Uniform Buffer Object `atoy_Clusters`.
+    ///
+    /// Cluster textures are not bound after updating,
+    /// calls [`StandardClusteredLighting::bind_textures`] before drawing lit entities.
+    pub fn update(
+        &mut self,
+        state: &mut FrameState,
+        scene: &Scene,
+        clusters_ubo: &mut Buffer,
+    ) -> Result<(), Error> {
+        let point_lights = scene.point_lights();
+        let spot_lights = scene.spot_lights();
+        if point_lights.len() + spot_lights.len() > MAX_CLUSTERED_LIGHTS {
+            warn!(
+                "only {} point lights and spot lights are available in clustered lighting, ignored",
+                MAX_CLUSTERED_LIGHTS
+            );
+        }
+        let point_lights = &point_lights[..point_lights.len().min(MAX_CLUSTERED_LIGHTS)];
+        let spot_lights = &spot_lights[..spot_lights
+            .len()
+            .min(MAX_CLUSTERED_LIGHTS - point_lights.len())];
+
+        let view_matrix = state.camera().view_matrix();
+        let attenuation = scene.light_attenuation();
+        let mut data = vec![0.0f32; (point_lights.len() + spot_lights.len()) * LIGHT_TEXELS * 4];
+        let mut volumes = Vec::with_capacity(point_lights.len() + spot_lights.len());
+        for (index, light) in point_lights.iter().enumerate() {
+            let offset = index * LIGHT_TEXELS * 4;
+            copy_ubo(
+                &mut data[offset..offset + UBO_LIGHTS_POINT_LIGHT_BYTE_LENGTH / 4],
+                &light.ubo(),
+            );
+            volumes.push(light.enabled().then(|| LightVolume {
+                center: view_matrix * light.position(),
+                radius: light_range(
+                    attenuation,
+                    intensity(&[light.ambient(), light.diffuse(), light.specular()]),
+                ),
+            }));
+        }
+        // spot lights are bounded by spheres as well, conservative but cheap
+        for (index, light) in spot_lights.iter().enumerate() {
+            let offset = (point_lights.len() + index) * LIGHT_TEXELS * 4;
+            copy_ubo(
+                &mut data[offset..offset + UBO_LIGHTS_SPOT_LIGHT_BYTE_LENGTH / 4],
+                &light.ubo(),
+            );
+            volumes.push(light.enabled().then(|| LightVolume {
+                center: view_matrix * light.position(),
+                radius: light_range(
+                    attenuation,
+                    intensity(&[light.ambient(), light.diffuse(), light.specular()]),
+                ),
+            }));
+        }
+
+        let grid = self.grid;
+        let assignment = assign_lights(&state.camera().proj_matrix(), grid, self.far, &volumes);
+        let (near, far, ranges, indices) = match assignment.as_ref() {
+            Some(assignment) => (
+                assignment.near(),
+                assignment.far(),
+                assignment.ranges(),
+                assignment.indices(),
+            ),
+            // clusters are left empty if view frustum is unavailable
+            None => (0.0, self.far, &[][..], &[][..]),
+        };
+
+        // light data
+        let rows = volumes.len();
+        let texture = Self::reserve_texture(
+            state,
+            &mut self.lights_texture,
+            TextureUncompressedInternalFormat::RGBA32F,
+            LIGHT_TEXELS,
+            rows,
+        )?;
+        if rows != 0 {
+            texture.tex_sub_image(
+                ClusterTextureSource::Float {
+                    width: LIGHT_TEXELS,
+                    height: rows,
+                    data: Float32Array::from(data.as_slice()),
+                },
+                0,
+                0,
+                0,
+                LIGHT_TEXELS,
+                rows,
+                false,
+            );
+        }
+
+        // offset and count of each cluster followed by light indices, wrapped into rows
+        let indices_data = pack_indices(grid.count(), ranges, indices);
+        let rows = indices_data.len() / INDICES_TEXTURE_WIDTH;
+        let texture = Self::reserve_texture(
+            state,
+            &mut self.indices_texture,
+            TextureUncompressedInternalFormat::R32UI,
+            INDICES_TEXTURE_WIDTH,
+            rows,
+        )?;
+        texture.tex_sub_image(
+            ClusterTextureSource::UnsignedInteger {
+                pixel_format: TextureUncompressedPixelFormat::RED_INTEGER,
+                width: INDICES_TEXTURE_WIDTH,
+                height: rows,
+                data: Uint32Array::from(indices_data.as_slice()),
+            },
+            0,
+            0,
+            0,
+            INDICES_TEXTURE_WIDTH,
+            rows,
+            false,
+        );
+
+        // u_ClusterDimensions and u_ClusterDepthRange
+        let mut ubo = [0u8; UBO_CLUSTERS_BYTE_LENGTH];
+        for (i, value) in [grid.x, grid.y, grid.z, point_lights.len()]
+            .iter()
+            .enumerate()
+        {
+            let offset = UBO_CLUSTERS_DIMENSIONS_BYTE_OFFSET + i * 4;
+            ubo[offset..offset + 4].copy_from_slice(&(*value as u32).to_ne_bytes());
+        }
+        for (i, value) in [near as f32, far as f32].iter().enumerate() {
+            let offset = UBO_CLUSTERS_DEPTH_RANGE_BYTE_OFFSET + i * 4;
+            ubo[offset..offset + 4].copy_from_slice(&value.to_ne_bytes());
+        }
+        state.buffer_store().register(clusters_ubo)?;
+        clusters_ubo.buffer_sub_data(ubo, 0);
+        clusters_ubo.bind_ubo(UBO_CLUSTERS_UNIFORM_BLOCK_MOUNT_POINT)?;
+
+        Ok(())
+    }
+
+    /// Binds cluster textures to their texture units.
+    pub fn bind_textures(&self) -> Result<(), Error> {
+        for (texture, unit) in self.textures() {
+            if let Some(texture) = texture {
+                texture.bind(unit)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Unbinds cluster textures from their texture units.
+    pub fn unbind_textures(&self) -> Result<(), Error> {
+        for (texture, unit) in self.textures() {
+            if let Some(texture) = texture {
+                texture.unbind(unit)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn textures(&self) -> [(Option<&Texture<Texture2D>>, TextureUnit); 2] {
+        [
+            (self.lights_texture.as_ref(), CLUSTER_LIGHTS_TEXTURE_UNIT),
+            (self.indices_texture.as_ref(), CLUSTER_INDICES_TEXTURE_UNIT),
+        ]
+    }
+}
+
+/// Copies a light packed for Uniform Buffer Object into light data.
+fn copy_ubo(data: &mut [f32], ubo: &[u8]) {
+    for (value, bytes) in data.iter_mut().zip(ubo.chunks_exact(4)) {
+        *value = f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+}
+
+/// Returns the brightest color component of all colors of a light.
+fn intensity(colors: &[Vec3<f32>]) -> f32 {
+    colors
+        .iter()
+        .flat_map(|color| color.to_f32_array())
+        .fold(0.0, f32::max)
+}
+
+/// Mounts Uniform Buffer Object `atoy_Clusters` and binds cluster samplers to a lit program.
+pub(super) fn mount_clusters(program: &Program) -> Result<(), Error> {
+    program.mount_uniform_block_by_binding(
+        &UBO_CLUSTERS_BLOCK_BINDING,
+        UBO_CLUSTERS_UNIFORM_BLOCK_MOUNT_POINT,
+    )?;
+    for (binding, unit) in [
+        (
+            &CLUSTER_LIGHTS_TEXTURE_UNIFORM_BINDING,
+            CLUSTER_LIGHTS_TEXTURE_UNIT,
+        ),
+        (
+            &CLUSTER_INDICES_TEXTURE_UNIFORM_BINDING,
+            CLUSTER_INDICES_TEXTURE_UNIT,
+        ),
+    ] {
+        program.bind_uniform_value_by_binding(
+            binding,
+            &UniformValue::Integer1(unit.unit_index() as i32),
+            None,
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use gl_matrix4rust::{mat4::Mat4, vec3::Vec3};
+
+    use crate::light::attenuation::Attenuation;
+
+    use super::{
+        assign_lights, light_range, pack_indices, ClusterGrid, LightVolume, INDICES_TEXTURE_WIDTH,
+        LIGHT_RANGE_CUTOFF, MAX_LIGHTS_PER_CLUSTER,
+    };
+
+    /// 90 degrees field of view and aspect of `1.0`, near plane at `1.0`.
+    fn proj_matrix() -> Mat4<f64> {
+        Mat4::<f64>::from_perspective(std::f64::consts::FRAC_PI_2, 1.0, 1.0, None)
+    }
+
+    fn light(x: f64, y: f64, z: f64, radius: f64) -> Option<LightVolume> {
+        Some(LightVolume {
+            center: Vec3::new(x, y, z),
+            radius,
+        })
+    }
+
+    #[test]
+    fn test_light_range() {
+        let range = light_range(&Attenuation::new(1.0, 0.0, 1.0), 1.0);
+        assert!((1.0 + range * range - 1.0 / LIGHT_RANGE_CUTOFF as f64).abs() < 1e-6);
+
+        let range = light_range(&Attenuation::new(0.0, 2.0, 0.0), 1.0);
+        assert!((range - 128.0).abs() < 1e-6);
+
+        assert_eq!(
+            light_range(&Attenuation::new(1.0, 0.0, 0.0), 1.0),
+            f64::INFINITY
+        );
+        assert_eq!(light_range(&Attenuation::new(1.0, 0.0, 1.0), 0.0), 0.0);
+        assert_eq!(light_range(&Attenuation::new(1000.0, 0.0, 1.0), 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_assign_lights() {
+        let grid = ClusterGrid::new(2, 2, 4);
+        let lights = [
+            // small light in the right top tile, near the view axis at view distance 3.0
+            light(0.5, 0.5, -3.0, 0.1),
+            // disabled light
+            None,
+            // behind camera
+            light(0.0, 0.0, 10.0, 1.0),
+            // covers everything
+            light(0.0, 0.0, -10.0, f64::INFINITY),
+        ];
+        let assignment = assign_lights(&proj_matrix(), grid, 81.0, &lights).unwrap();
+        assert!((assignment.near() - 1.0).abs() < 1e-6);
+        assert_eq!(assignment.ranges().len(), grid.count());
+
+        // logarithmic slices of 1.0, 3.0, 9.0, 27.0 and 81.0
+        for z in 0..4 {
+            for y in 0..2 {
+                for x in 0..2 {
+                    let expected: &[u32] = if (x, y, z) == (1, 1, 0) || (x, y, z) == (1, 1, 1) {
+                        &[0, 3]
+                    } else {
+                        &[3]
+                    };
+                    assert_eq!(assignment.lights(x, y, z), expected, "{x} {y} {z}");
+                }
+            }
+        }
+
+        // light crossing tiles and slices
+        let assignment =
+            assign_lights(&proj_matrix(), grid, 81.0, &[light(0.0, 2.0, -9.0, 1.0)]).unwrap();
+        for z in 0..4 {
+            for y in 0..2 {
+                for x in 0..2 {
+                    let expected: &[u32] = if y == 1 && (z == 1 || z == 2) {
+                        &[0]
+                    } else {
+                        &[]
+                    };
+                    assert_eq!(assignment.lights(x, y, z), expected, "{x} {y} {z}");
+                }
+            }
+        }
+
+        assert!(assign_lights(&proj_matrix(), grid, 0.5, &lights).is_none());
+    }
+
+    #[test]
+    fn test_assign_lights_limit() {
+        let grid = ClusterGrid::new(1, 1, 1);
+        let lights = vec![light(0.0, 0.0, -5.0, 1.0); MAX_LIGHTS_PER_CLUSTER + 10];
+        let assignment = assign_lights(&proj_matrix(), grid, 10.0, &lights).unwrap();
+        let expected = (0..MAX_LIGHTS_PER_CLUSTER as u32).collect::<Vec<_>>();
+        assert_eq!(assignment.lights(0, 0, 0), expected.as_slice());
+    }
+
+    #[test]
+    fn test_assign_lights_orthogonal() {
+        // uniform slices of 0.0, 5.0 and 10.0
+        let ortho = Mat4::<f64>::from_ortho(-1.0, 1.0, -1.0, 1.0, 0.0, 10.0);
+        let grid = ClusterGrid::new(2, 1, 2);
+        let assignment = assign_lights(&ortho, grid, 10.0, &[light(-0.6, 0.0, -7.0, 0.3)]).unwrap();
+        assert_eq!(assignment.near(), 0.0);
+        assert_eq!(assignment.lights(0, 0, 1), &[0]);
+        assert!(assignment.lights(1, 0, 1).is_empty());
+        assert!(assignment.lights(0, 0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_cluster_grid() {
+        let grid = ClusterGrid::new(0, 3, 0);
+        assert_eq!((grid.x(), grid.y(), grid.z()), (1, 3, 1));
+        assert_eq!(grid.count(), 3);
+        assert_eq!(grid.index(0, 2, 0), 2);
+    }
+
+    #[test]
+    fn test_pack_indices() {
+        let data = pack_indices(3, &[(0, 2), (2, 0), (2, 1)], &[4, 7, 9]);
+        assert_eq!(data.len(), INDICES_TEXTURE_WIDTH);
+        assert_eq!(&data[..9], &[6, 2, 8, 0, 8, 1, 4, 7, 9]);
+        assert!(data[9..].iter().all(|value| *value == 0));
+
+        // clusters are empty without assignment
+        let data = pack_indices(2, &[], &[]);
+        assert_eq!(&data[..4], &[0, 0, 0, 0]);
+
+        // wraps into rows
+        let indices = vec![1; INDICES_TEXTURE_WIDTH];
+        let data = pack_indices(1, &[(0, INDICES_TEXTURE_WIDTH as u32)], &indices);
+        assert_eq!(data.len(), INDICES_TEXTURE_WIDTH * 2);
+        assert_eq!(&data[..2], &[2, INDICES_TEXTURE_WIDTH as u32]);
+        assert_eq!(data[INDICES_TEXTURE_WIDTH + 1], 1);
+    }
+}
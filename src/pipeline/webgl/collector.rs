@@ -1,8 +1,10 @@
 use std::{
-    cell::RefCell,
+    cell::{RefCell, RefMut},
     rc::{Rc, Weak},
 };
 
+use hashbrown::{HashMap, HashSet};
+use log::warn;
 use uuid::Uuid;
 
 use crate::{
@@ -14,11 +16,15 @@ use crate::{
     scene::Scene,
 };
 
+use super::batching::{program_switches, DrawStatistics, StateSortKey, StaticBatch};
+
 pub struct CollectedEntities<'a> {
     entities: &'a [Weak<RefCell<dyn Entity>>],
     opaque_entities: &'a [Weak<RefCell<dyn Entity>>],
     transparent_entities: &'a [Weak<RefCell<dyn Entity>>],
     translucent_entities: &'a [Weak<RefCell<dyn Entity>>],
+    static_batches: &'a [Rc<StaticBatch>],
+    statistics: &'a RefCell<DrawStatistics>,
 }
 
 impl<'a> CollectedEntities<'a> {
@@ -37,18 +43,35 @@ impl<'a> CollectedEntities<'a> {
     pub fn translucent_entities(&self) -> &[Weak<RefCell<dyn Entity>>] {
         self.translucent_entities
     }
+
+    /// Returns visible static batches.
+    /// Entities merged into static batches are excluded from [`CollectedEntities::opaque_entities`].
+    pub fn static_batches(&self) -> &[Rc<StaticBatch>] {
+        self.static_batches
+    }
+
+    pub(super) fn statistics_mut(&self) -> RefMut<'_, DrawStatistics> {
+        self.statistics.borrow_mut()
+    }
 }
 
 pub struct StandardEntitiesCollector {
     enable_culling: bool,
     enable_distance_sorting: bool,
+    enable_state_sorting: bool,
+    enable_static_batching: bool,
 
+    static_batches: HashMap<Uuid, (Vec<Uuid>, Option<Rc<StaticBatch>>)>,
+    statistics: RefCell<DrawStatistics>,
+
+    last_program_switches_saved: usize,
     last_view_frustum: Option<ViewFrustum>,
     last_entities_group_id: Option<Uuid>,
     last_entities: Vec<Weak<RefCell<dyn Entity>>>,
     last_opaque_entities: Vec<Weak<RefCell<dyn Entity>>>,
     last_transparent_entities: Vec<Weak<RefCell<dyn Entity>>>,
     last_translucent_entities: Vec<Weak<RefCell<dyn Entity>>>,
+    last_static_batches: Vec<Rc<StaticBatch>>,
 }
 
 impl StandardEntitiesCollector {
@@ -57,24 +80,33 @@ impl StandardEntitiesCollector {
         Self {
             enable_culling: true,
             enable_distance_sorting: true,
+            enable_state_sorting: true,
+            enable_static_batching: false,
+
+            static_batches: HashMap::new(),
+            statistics: RefCell::new(DrawStatistics::default()),
 
+            last_program_switches_saved: 0,
             last_view_frustum: None,
             last_entities_group_id: None,
             last_entities: Vec::new(),
             last_opaque_entities: Vec::new(),
             last_transparent_entities: Vec::new(),
             last_translucent_entities: Vec::new(),
+            last_static_batches: Vec::new(),
         }
     }
 
     /// Clears previous collected result.
     pub fn clear(&mut self) {
+        self.last_program_switches_saved = 0;
         self.last_view_frustum = None;
         self.last_entities_group_id = None;
         self.last_entities.clear();
         self.last_opaque_entities.clear();
         self.last_transparent_entities.clear();
         self.last_translucent_entities.clear();
+        self.last_static_batches.clear();
     }
 
     /// Returns `true` if entity culling enabled.
@@ -119,6 +151,58 @@ impl StandardEntitiesCollector {
         }
     }
 
+    /// Returns `true` if state sorting enabled.
+    pub fn state_sorting_enabled(&self) -> bool {
+        self.enable_state_sorting
+    }
+
+    /// Enables state sorting, opaque entities are sorted by program, textures and vertex buffers
+    /// to reduce state changes. Distance order is kept among entities with the same states.
+    pub fn enable_state_sorting(&mut self) {
+        if self.enable_state_sorting != true {
+            self.enable_state_sorting = true;
+            self.clear();
+        }
+    }
+
+    /// Disables state sorting.
+    pub fn disable_state_sorting(&mut self) {
+        if self.enable_state_sorting != false {
+            self.enable_state_sorting = false;
+            self.clear();
+        }
+    }
+
+    /// Returns `true` if static batching enabled.
+    pub fn static_batching_enabled(&self) -> bool {
+        self.enable_static_batching
+    }
+
+    /// Enables static batching, opaque entities sharing the same [`Entity::static_batch`]
+    /// are merged into a [`StaticBatch`] and drawn in a single draw call.
+    pub fn enable_static_batching(&mut self) {
+        if self.enable_static_batching != true {
+            self.enable_static_batching = true;
+            self.clear();
+        }
+    }
+
+    /// Disables static batching.
+    pub fn disable_static_batching(&mut self) {
+        if self.enable_static_batching != false {
+            self.enable_static_batching = false;
+            self.static_batches.clear();
+            self.clear();
+        }
+    }
+
+    /// Returns drawing counters of last frame.
+    pub fn statistics(&self) -> DrawStatistics {
+        let mut statistics = *self.statistics.borrow();
+        statistics.set_program_switches_saved(self.last_program_switches_saved);
+        statistics
+    }
+
     /// Returns last collected entities.
     pub fn last_collected_entities(&self) -> CollectedEntities {
         CollectedEntities {
@@ -126,6 +210,8 @@ impl StandardEntitiesCollector {
             opaque_entities: &self.last_opaque_entities,
             transparent_entities: &self.last_transparent_entities,
             translucent_entities: &self.last_translucent_entities,
+            static_batches: &self.last_static_batches,
+            statistics: &self.statistics,
         }
     }
 
@@ -141,6 +227,8 @@ impl StandardEntitiesCollector {
             distance: f64,
         }
 
+        *self.statistics.borrow_mut() = DrawStatistics::default();
+
         let mut group = scene.entities().borrow_mut();
         let view_frustum = state.camera().view_frustum();
 
@@ -156,12 +244,7 @@ impl StandardEntitiesCollector {
                 .map(|last_view_frustum| last_view_frustum != &view_frustum)
                 .unwrap_or(true);
         if !should_recollect {
-            return CollectedEntities {
-                entities: &self.last_entities,
-                opaque_entities: &self.last_opaque_entities,
-                transparent_entities: &self.last_transparent_entities,
-                translucent_entities: &self.last_translucent_entities,
-            };
+            return self.last_collected_entities();
         }
 
         self.clear();
@@ -170,6 +253,12 @@ impl StandardEntitiesCollector {
         let view_position = state.camera().position();
         let culling = self.culling_enabled();
         let distance_sorting = self.distance_sorting_enabled();
+        let state_sorting = self.state_sorting_enabled();
+        let batched_entities = if self.static_batching_enabled() {
+            self.update_static_batches(state, &*group, culling.then(|| &view_frustum))
+        } else {
+            HashSet::new()
+        };
         let mut entities = Vec::new();
 
         if culling {
@@ -181,6 +270,9 @@ impl StandardEntitiesCollector {
                     },
                     None => f64::INFINITY,
                 };
+                if !cull_instances(&entity, Some(&view_frustum)) {
+                    continue;
+                }
 
                 let transparency = entity
                     .borrow()
@@ -211,6 +303,9 @@ impl StandardEntitiesCollector {
                         },
                         None => f64::INFINITY,
                     };
+                    if !cull_instances(&entity, Some(&view_frustum)) {
+                        continue;
+                    }
 
                     let transparency = entity
                         .borrow()
@@ -227,6 +322,10 @@ impl StandardEntitiesCollector {
             }
         } else {
             for entity in group.entities_hierarchy() {
+                if !cull_instances(&entity, None) {
+                    continue;
+                }
+
                 let transparency = entity
                     .borrow()
                     .material()
@@ -253,6 +352,7 @@ impl StandardEntitiesCollector {
             entities.sort_by(|a, b| a.distance.total_cmp(&b.distance));
         }
 
+        let mut opaque_entities = Vec::new();
         for CollectedEntity {
             entity,
             transparency,
@@ -271,10 +371,19 @@ impl StandardEntitiesCollector {
                 }
             }
 
+            let batched = batched_entities.contains(entity.borrow().id());
+            let sort_key = if state_sorting && !batched {
+                StateSortKey::new(&*entity.borrow())
+            } else {
+                None
+            };
+
             let entity = Rc::downgrade(&entity);
             self.last_entities.push(Weak::clone(&entity));
             match transparency {
-                Transparency::Opaque => self.last_opaque_entities.push(Weak::clone(&entity)),
+                // entities merged into static batches are drawn by batches
+                Transparency::Opaque if batched => {}
+                Transparency::Opaque => opaque_entities.push((sort_key, entity)),
                 Transparency::Transparent => {
                     self.last_transparent_entities.push(Weak::clone(&entity))
                 }
@@ -284,14 +393,103 @@ impl StandardEntitiesCollector {
             }
         }
 
+        // stable sorting keeps distance order among entities with the same states
+        if state_sorting {
+            let unsorted_switches =
+                program_switches(opaque_entities.iter().map(|(key, _)| key.as_ref()));
+            opaque_entities.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let sorted_switches =
+                program_switches(opaque_entities.iter().map(|(key, _)| key.as_ref()));
+            self.last_program_switches_saved = unsorted_switches.saturating_sub(sorted_switches);
+        }
+        self.last_opaque_entities
+            .extend(opaque_entities.into_iter().map(|(_, entity)| entity));
+
         self.last_entities_group_id = Some(*group.id());
         self.last_view_frustum = Some(view_frustum);
 
-        CollectedEntities {
-            entities: &self.last_entities,
-            opaque_entities: &self.last_opaque_entities,
-            transparent_entities: &self.last_transparent_entities,
-            translucent_entities: &self.last_translucent_entities,
+        self.last_collected_entities()
+    }
+
+    /// Merges opaque entities sharing the same static batch id into static batches,
+    /// batches are rebuilt only when their members changed or moved.
+    /// Visible batches are collected into last static batches.
+    ///
+    /// Returns ids of all entities merged into batches, including entities of invisible batches.
+    fn update_static_batches(
+        &mut self,
+        state: &FrameState,
+        group: &dyn Group,
+        view_frustum: Option<&ViewFrustum>,
+    ) -> HashSet<Uuid> {
+        let mut batches: HashMap<Uuid, Vec<Rc<RefCell<dyn Entity>>>> = HashMap::new();
+        for entity in group.entities_hierarchy() {
+            let static_batch = {
+                let entity = entity.borrow();
+                let Some(static_batch) = entity.static_batch() else {
+                    continue;
+                };
+                if entity.geometry().is_none() {
+                    continue;
+                }
+                match entity.material() {
+                    Some(material)
+                        if material.ready() && material.transparency() == Transparency::Opaque => {}
+                    _ => continue,
+                }
+                *static_batch
+            };
+            batches.entry(static_batch).or_default().push(entity);
+        }
+        self.static_batches.retain(|id, _| batches.contains_key(id));
+
+        let mut batched_entities = HashSet::new();
+        for (id, entities) in batches {
+            let entity_ids = entities
+                .iter()
+                .map(|entity| *entity.borrow().id())
+                .collect::<Vec<_>>();
+            let up_to_date = match self.static_batches.get(&id) {
+                Some((_, Some(batch))) => batch.matches(&entities),
+                Some((last_entity_ids, None)) => last_entity_ids == &entity_ids,
+                None => false,
+            };
+            if !up_to_date {
+                let batch = match StaticBatch::build(state, id, &entities) {
+                    Ok(batch) => batch.map(Rc::new),
+                    Err(err) => {
+                        warn!(
+                            target: "StaticBatching",
+                            "failed to build static batch {}: {}", id, err
+                        );
+                        None
+                    }
+                };
+                self.static_batches.insert(id, (entity_ids.clone(), batch));
+            }
+
+            let Some((_, Some(batch))) = self.static_batches.get(&id) else {
+                continue;
+            };
+            batched_entities.extend(entity_ids);
+
+            if let (Some(view_frustum), Some(bounding)) = (view_frustum, batch.bounding_volume()) {
+                if let Culling::Outside = bounding.cull(view_frustum) {
+                    continue;
+                }
+            }
+            self.last_static_batches.push(Rc::clone(batch));
         }
+
+        batched_entities
+    }
+}
+
+/// Culls instances of an instanced entity and compacts remaining instances.
+/// Returns `false` if the entity is an instanced entity and no instance remains.
+fn cull_instances(entity: &Rc<RefCell<dyn Entity>>, view_frustum: Option<&ViewFrustum>) -> bool {
+    match entity.borrow_mut().as_instanced_entity_mut() {
+        Some(instanced) => instanced.cull_instances(view_frustum) != 0,
+        None => true,
     }
 }
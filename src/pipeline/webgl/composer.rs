@@ -6,12 +6,12 @@ use web_sys::{WebGl2RenderingContext, WebGlTexture};
 use crate::renderer::webgl::{
     error::Error,
     framebuffer::{
-        AttachmentSource, Framebuffer, FramebufferAttachmentTarget, FramebufferBuilder,
-        FramebufferTarget,
+        AttachmentSource, ClearPolicy, Framebuffer, FramebufferAttachmentTarget,
+        FramebufferBuilder, FramebufferTarget,
     },
     program::{Define, ProgramSource},
     state::FrameState,
-    texture::{TextureUncompressedInternalFormat, TextureUnit},
+    texture::TextureUnit,
     uniform::{UniformBinding, UniformValue},
 };
 
@@ -28,7 +28,7 @@ pub const DEFAULT_ENABLE_GAMMA_CORRECTION: bool = true;
 pub const DEFAULT_ENABLE_GAMMA: f32 = 2.2;
 
 /// Standard texture composer.
-/// Composes all textures into canvas framebuffer.
+/// Composes all textures into a composed texture and then prints it into canvas framebuffer.
 pub struct StandardComposer {
     shader_provider: ComposerShaderProvider,
     composed_framebuffer: Framebuffer,
@@ -43,11 +43,7 @@ impl StandardComposer {
         Self {
             shader_provider: ComposerShaderProvider::new(false),
 
-            composed_framebuffer: FramebufferBuilder::new()
-                .set_color_attachment0(AttachmentSource::new_texture(
-                    TextureUncompressedInternalFormat::RGBA8,
-                ))
-                .build(),
+            composed_framebuffer: FramebufferBuilder::new().build(),
             clear_color: DEFAULT_CLEAR_COLOR,
 
             enable_gamma_correction: DEFAULT_ENABLE_GAMMA_CORRECTION,
@@ -85,19 +81,24 @@ impl StandardComposer {
 }
 
 impl StandardComposer {
-    pub fn draw<'a, I>(&mut self, state: &mut FrameState, textures: I) -> Result<(), Error>
-    where
-        I: IntoIterator<Item = &'a WebGlTexture>,
-    {
-        self.compose(state, textures)?;
-        self.print(state)?;
-        Ok(())
-    }
-
-    fn compose<'a, I>(&mut self, state: &mut FrameState, textures: I) -> Result<(), Error>
+    /// Composes textures into the composed texture, which is usually allocated by render graph.
+    pub fn compose<'a, I>(
+        &mut self,
+        state: &mut FrameState,
+        composed: &WebGlTexture,
+        textures: I,
+    ) -> Result<(), Error>
     where
         I: IntoIterator<Item = &'a WebGlTexture>,
     {
+        self.composed_framebuffer.set_attachment(
+            FramebufferAttachmentTarget::COLOR_ATTACHMENT0,
+            Some(AttachmentSource::from_texture(
+                composed.clone(),
+                0,
+                ClearPolicy::ColorFloat([0.0; 4]),
+            )),
+        )?;
         self.composed_framebuffer.init(state.gl())?;
         self.composed_framebuffer
             .bind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
@@ -139,7 +140,8 @@ impl StandardComposer {
         Ok(())
     }
 
-    fn print(&mut self, state: &mut FrameState) -> Result<(), Error> {
+    /// Prints the composed texture into canvas framebuffer.
+    pub fn print(&mut self, state: &mut FrameState, composed: &WebGlTexture) -> Result<(), Error> {
         state.gl().clear_color(
             *self.clear_color.x() as f32,
             *self.clear_color.y() as f32,
@@ -162,12 +164,7 @@ impl StandardComposer {
             )?;
         }
 
-        state.do_computation([(
-            self.composed_framebuffer
-                .texture(FramebufferAttachmentTarget::COLOR_ATTACHMENT0)?
-                .unwrap(),
-            TextureUnit::TEXTURE0,
-        )])?;
+        state.do_computation([(composed, TextureUnit::TEXTURE0)])?;
 
         program.unuse_program()?;
 
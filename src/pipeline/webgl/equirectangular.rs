@@ -0,0 +1,184 @@
+use std::borrow::Cow;
+
+use web_sys::WebGl2RenderingContext;
+
+use crate::renderer::webgl::{
+    capabilities::EXTENSION_EXT_COLOR_BUFFER_FLOAT,
+    error::Error,
+    program::{Define, ProgramSource},
+    state::FrameState,
+    texture::{
+        Builder, SamplerParameter, Texture, Texture2D, TextureCubeMap, TextureInternalFormat,
+        TextureMagnificationFilter, TextureMinificationFilter, TextureUncompressedInternalFormat,
+        TextureUnit, TextureWrapMethod,
+    },
+    uniform::{UniformBinding, UniformValue},
+};
+
+const EQUIRECTANGULAR_TEXTURE_UNIFORM_NAME: &'static str = "u_EquirectangularTexture";
+const EQUIRECTANGULAR_TEXTURE_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(EQUIRECTANGULAR_TEXTURE_UNIFORM_NAME));
+
+const CUBE_MAP_FACE_UNIFORM_NAME: &'static str = "u_CubeMapFace";
+const CUBE_MAP_FACE_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(CUBE_MAP_FACE_UNIFORM_NAME));
+
+/// Cube map face targets, index of a face is the value of `u_CubeMapFace`.
+const CUBE_MAP_FACE_TARGETS: [u32; 6] = [
+    WebGl2RenderingContext::TEXTURE_CUBE_MAP_POSITIVE_X,
+    WebGl2RenderingContext::TEXTURE_CUBE_MAP_NEGATIVE_X,
+    WebGl2RenderingContext::TEXTURE_CUBE_MAP_POSITIVE_Y,
+    WebGl2RenderingContext::TEXTURE_CUBE_MAP_NEGATIVE_Y,
+    WebGl2RenderingContext::TEXTURE_CUBE_MAP_POSITIVE_Z,
+    WebGl2RenderingContext::TEXTURE_CUBE_MAP_NEGATIVE_Z,
+];
+
+/// Standard equirectangular to cube map converter.
+/// Renders an equirectangular environment image into each face of a [`TextureCubeMap`],
+/// the result could be used as a skybox or as a source of environment lighting.
+///
+/// Equirectangular image is expected to store rows from bottom to top,
+/// as [`RadianceHdr`](crate::loader::hdr::RadianceHdr) does.
+pub struct StandardEquirectangularToCubeMap;
+
+impl StandardEquirectangularToCubeMap {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Converts an equirectangular texture into a new cube map with `size` for each face.
+    ///
+    /// Extension `EXT_color_buffer_float` is required if `internal_format` is a float format.
+    /// All mipmaps are generated after converting if `generate_mipmaps` is `true`.
+    pub fn convert(
+        &mut self,
+        state: &mut FrameState,
+        equirectangular: &Texture<Texture2D>,
+        size: usize,
+        internal_format: TextureUncompressedInternalFormat,
+        generate_mipmaps: bool,
+    ) -> Result<Texture<TextureCubeMap>, Error> {
+        let is_float = match internal_format {
+            TextureUncompressedInternalFormat::RGBA32F
+            | TextureUncompressedInternalFormat::RGBA16F
+            | TextureUncompressedInternalFormat::R11F_G11F_B10F
+            | TextureUncompressedInternalFormat::RG32F
+            | TextureUncompressedInternalFormat::RG16F
+            | TextureUncompressedInternalFormat::R32F
+            | TextureUncompressedInternalFormat::R16F => true,
+            _ => false,
+        };
+        if is_float && !state.capabilities().color_buffer_float_supported() {
+            return Err(Error::ExtensionUnsupported(
+                EXTENSION_EXT_COLOR_BUFFER_FLOAT,
+            ));
+        }
+
+        let internal_format = TextureInternalFormat::Uncompressed(internal_format);
+        let mut builder = if generate_mipmaps {
+            Builder::<TextureCubeMap>::with_auto_levels(internal_format, size, size)
+        } else {
+            Builder::<TextureCubeMap>::new(internal_format, 1, size, size)
+        };
+        builder.set_sampler_parameters([
+            SamplerParameter::MAG_FILTER(TextureMagnificationFilter::LINEAR),
+            SamplerParameter::MIN_FILTER(if generate_mipmaps {
+                TextureMinificationFilter::LINEAR_MIPMAP_LINEAR
+            } else {
+                TextureMinificationFilter::LINEAR
+            }),
+            SamplerParameter::WRAP_S(TextureWrapMethod::CLAMP_TO_EDGE),
+            SamplerParameter::WRAP_T(TextureWrapMethod::CLAMP_TO_EDGE),
+            SamplerParameter::WRAP_R(TextureWrapMethod::CLAMP_TO_EDGE),
+        ]);
+        let cube_map = builder.build();
+        cube_map.init(state.gl())?;
+        let native = cube_map.native()?;
+
+        equirectangular.init(state.gl())?;
+
+        let program = state
+            .program_store_mut()
+            .get_or_compile_program(&EquirectangularToCubeMapping)?;
+        program.use_program()?;
+        program.bind_uniform_value_by_binding(
+            &EQUIRECTANGULAR_TEXTURE_UNIFORM_BINDING,
+            &UniformValue::Integer1(0),
+            None,
+        )?;
+        let unbinder = equirectangular.bind(TextureUnit::TEXTURE0)?;
+
+        let gl = state.gl();
+        let framebuffer = gl
+            .create_framebuffer()
+            .ok_or(Error::CreateFramebufferFailure)?;
+        gl.bind_framebuffer(WebGl2RenderingContext::DRAW_FRAMEBUFFER, Some(&framebuffer));
+        gl.viewport(0, 0, size as i32, size as i32);
+        for (index, target) in CUBE_MAP_FACE_TARGETS.iter().enumerate() {
+            program.bind_uniform_value_by_binding(
+                &CUBE_MAP_FACE_UNIFORM_BINDING,
+                &UniformValue::Integer1(index as i32),
+                None,
+            )?;
+            gl.framebuffer_texture_2d(
+                WebGl2RenderingContext::DRAW_FRAMEBUFFER,
+                WebGl2RenderingContext::COLOR_ATTACHMENT0,
+                *target,
+                Some(&native),
+                0,
+            );
+            gl.draw_arrays(WebGl2RenderingContext::TRIANGLE_FAN, 0, 4);
+        }
+        gl.bind_framebuffer(WebGl2RenderingContext::DRAW_FRAMEBUFFER, None);
+        gl.delete_framebuffer(Some(&framebuffer));
+        gl.viewport(
+            0,
+            0,
+            state.canvas().width() as i32,
+            state.canvas().height() as i32,
+        );
+
+        unbinder.unbind();
+        program.unuse_program()?;
+
+        if generate_mipmaps {
+            gl.bind_texture(WebGl2RenderingContext::TEXTURE_CUBE_MAP, Some(&native));
+            gl.generate_mipmap(WebGl2RenderingContext::TEXTURE_CUBE_MAP);
+            gl.bind_texture(WebGl2RenderingContext::TEXTURE_CUBE_MAP, None);
+        }
+
+        Ok(cube_map)
+    }
+}
+
+struct EquirectangularToCubeMapping;
+
+impl ProgramSource for EquirectangularToCubeMapping {
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed("EquirectangularToCubeMapping")
+    }
+
+    fn vertex_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("./shaders/computation.vert"))
+    }
+
+    fn fragment_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("./shaders/equirectangular_to_cube_map.frag"))
+    }
+
+    fn universal_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn vertex_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn fragment_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn snippet(&self, _: &str) -> Option<Cow<'_, str>> {
+        None
+    }
+}
@@ -0,0 +1,873 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use hashbrown::HashSet;
+use web_sys::{WebGl2RenderingContext, WebGlTexture};
+
+use crate::{
+    renderer::webgl::{
+        conversion::ToGlEnum, error::Error, framebuffer::SizePolicy, params::GetWebGlParameters,
+        state::FrameState, texture::TextureUncompressedInternalFormat,
+    },
+    scene::Scene,
+};
+
+/// Descriptor of a transient texture allocated by [`RenderGraph`].
+/// Transient textures having equal descriptors may share the same texture if their lifetimes never overlap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextureDescriptor {
+    pub internal_format: TextureUncompressedInternalFormat,
+    pub size_policy: SizePolicy,
+}
+
+impl TextureDescriptor {
+    /// Constructs a new texture descriptor following size of drawing buffer.
+    pub fn new(internal_format: TextureUncompressedInternalFormat) -> Self {
+        Self {
+            internal_format,
+            size_policy: SizePolicy::FollowDrawingBuffer,
+        }
+    }
+
+    /// Constructs a new texture descriptor with a size policy.
+    pub fn with_size_policy(
+        internal_format: TextureUncompressedInternalFormat,
+        size_policy: SizePolicy,
+    ) -> Self {
+        Self {
+            internal_format,
+            size_policy,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ResourceKind {
+    /// Texture allocated and aliased by render graph.
+    Transient(TextureDescriptor),
+    /// Texture owned outside render graph, published by the pass writing it.
+    Imported,
+    /// Final output, such as the canvas framebuffer.
+    /// Passes writing outputs are never culled.
+    Output,
+}
+
+struct ResourceItem {
+    name: String,
+    kind: ResourceKind,
+}
+
+/// Resources declared by a [`RenderPass`] in [`RenderPass::setup`].
+///
+/// A pass reading and writing the same resource modifies it in place.
+/// All passes writing a resource run before passes only reading it,
+/// passes writing it without reading run before passes modifying it,
+/// and passes modifying the same resource run in the order they are added.
+#[derive(Debug, Default)]
+pub struct PassBuilder {
+    reads: Vec<String>,
+    writes: Vec<String>,
+    side_effect: bool,
+}
+
+impl PassBuilder {
+    /// Constructs a new empty pass builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares reading a resource.
+    pub fn read<N>(&mut self, name: N) -> &mut Self
+    where
+        N: Into<String>,
+    {
+        self.reads.push(name.into());
+        self
+    }
+
+    /// Declares writing a resource.
+    pub fn write<N>(&mut self, name: N) -> &mut Self
+    where
+        N: Into<String>,
+    {
+        self.writes.push(name.into());
+        self
+    }
+
+    /// Declares the pass has side effects outside render graph, which is never culled then.
+    pub fn side_effect(&mut self) -> &mut Self {
+        self.side_effect = true;
+        self
+    }
+}
+
+/// A render pass in [`RenderGraph`], executing with a context `C` shared by all passes.
+pub trait RenderPass<C> {
+    /// Declares resources the pass reads and writes under current context.
+    /// A pass declaring neither writes nor side effects is always culled.
+    ///
+    /// Setup is only called when render graph compiles,
+    /// call [`RenderGraph::set_dirty`] if declarations changed.
+    fn setup(&self, context: &C, builder: &mut PassBuilder);
+
+    /// Executes the pass.
+    /// Textures of declared resources are available from [`GraphResources`].
+    fn execute(
+        &mut self,
+        state: &mut FrameState,
+        scene: &mut Scene,
+        context: &mut C,
+        resources: &mut GraphResources<'_>,
+    ) -> Result<(), Error>;
+}
+
+struct PassItem<C> {
+    label: String,
+    pass: Box<dyn RenderPass<C>>,
+}
+
+/// Result of render graph compilation.
+struct CompiledGraph {
+    /// Indices of passes in execution order.
+    order: Vec<usize>,
+    /// Indices of culled passes.
+    culled: Vec<usize>,
+    /// Descriptors of allocated textures.
+    slots: Vec<TextureDescriptor>,
+    /// Allocated texture slot of each resource.
+    aliases: Vec<Option<usize>>,
+    /// First and last position in execution order using each resource.
+    lifetimes: Vec<Option<(usize, usize)>>,
+}
+
+/// Textures of resources available to passes during execution.
+pub struct GraphResources<'a> {
+    resources: &'a [ResourceItem],
+    textures: Vec<Option<WebGlTexture>>,
+}
+
+impl<'a> GraphResources<'a> {
+    fn index(&self, name: &str) -> Result<usize, Error> {
+        self.resources
+            .iter()
+            .position(|resource| resource.name == name)
+            .ok_or_else(|| Error::RenderGraphNoSuchResource(name.to_string()))
+    }
+
+    /// Returns texture of a resource.
+    /// Transient textures are always available,
+    /// while imported textures are available only after being published by their writers.
+    pub fn texture(&self, name: &str) -> Result<&WebGlTexture, Error> {
+        let index = self.index(name)?;
+        self.textures[index]
+            .as_ref()
+            .ok_or_else(|| Error::RenderGraphTextureUnavailable(name.to_string()))
+    }
+
+    /// Publishes texture of an imported resource written by current pass.
+    pub fn publish(&mut self, name: &str, texture: WebGlTexture) -> Result<(), Error> {
+        let index = self.index(name)?;
+        if self.resources[index].kind != ResourceKind::Imported {
+            return Err(Error::RenderGraphTextureUnavailable(name.to_string()));
+        }
+        self.textures[index] = Some(texture);
+        Ok(())
+    }
+}
+
+/// Textures allocated for transient resources, one for each slot.
+struct TransientTextures {
+    gl: Option<WebGl2RenderingContext>,
+    textures: Vec<(WebGlTexture, TextureDescriptor, usize, usize)>,
+}
+
+impl TransientTextures {
+    fn new() -> Self {
+        Self {
+            gl: None,
+            textures: Vec::new(),
+        }
+    }
+
+    /// Allocates textures for slots, reusing existing ones having the same descriptor and size.
+    fn allocate(
+        &mut self,
+        gl: &WebGl2RenderingContext,
+        slots: &[TextureDescriptor],
+    ) -> Result<(), Error> {
+        if self
+            .gl
+            .as_ref()
+            .map(|current| current != gl)
+            .unwrap_or(false)
+        {
+            self.delete();
+        }
+        self.gl = Some(gl.clone());
+
+        for (index, descriptor) in slots.iter().enumerate() {
+            let (width, height) = descriptor.size_policy.size(gl);
+            if let Some((_, current, w, h)) = self.textures.get(index) {
+                if current == descriptor && *w == width && *h == height {
+                    continue;
+                }
+            }
+
+            let binding = gl.texture_binding_2d();
+            let texture = gl.create_texture().ok_or(Error::CreateTextureFailure)?;
+            gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&texture));
+            gl.tex_storage_2d(
+                WebGl2RenderingContext::TEXTURE_2D,
+                1,
+                descriptor.internal_format.gl_enum(),
+                width as i32,
+                height as i32,
+            );
+            gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, binding.as_ref());
+
+            let item = (texture, *descriptor, width, height);
+            if index < self.textures.len() {
+                let (previous, ..) = std::mem::replace(&mut self.textures[index], item);
+                gl.delete_texture(Some(&previous));
+            } else {
+                self.textures.push(item);
+            }
+        }
+        for (texture, ..) in self.textures.drain(slots.len()..) {
+            gl.delete_texture(Some(&texture));
+        }
+
+        Ok(())
+    }
+
+    fn delete(&mut self) {
+        if let Some(gl) = self.gl.as_ref() {
+            for (texture, ..) in self.textures.drain(..) {
+                gl.delete_texture(Some(&texture));
+            }
+        }
+    }
+}
+
+impl Drop for TransientTextures {
+    fn drop(&mut self) {
+        self.delete();
+    }
+}
+
+/// A render graph ordering passes by resources they read and write.
+///
+/// On compilation, render graph orders passes, culls passes contributing nothing to outputs
+/// and assigns transient textures to as few textures as possible by their lifetimes.
+/// Compilation is cached until passes, resources or [`RenderGraph::set_dirty`] changes it.
+pub struct RenderGraph<C> {
+    passes: Vec<PassItem<C>>,
+    resources: Vec<ResourceItem>,
+    compiled: Option<CompiledGraph>,
+    transient_textures: TransientTextures,
+}
+
+impl<C> RenderGraph<C> {
+    /// Constructs a new empty render graph.
+    pub fn new() -> Self {
+        Self {
+            passes: Vec::new(),
+            resources: Vec::new(),
+            compiled: None,
+            transient_textures: TransientTextures::new(),
+        }
+    }
+
+    /// Marks render graph to compile again before next execution.
+    pub fn set_dirty(&mut self) {
+        self.compiled = None;
+    }
+
+    fn add_resource<N>(&mut self, name: N, kind: ResourceKind) -> Result<(), Error>
+    where
+        N: Into<String>,
+    {
+        let name = name.into();
+        if self.resources.iter().any(|resource| resource.name == name) {
+            return Err(Error::RenderGraphDuplicateResource(name));
+        }
+        self.resources.push(ResourceItem { name, kind });
+        self.set_dirty();
+        Ok(())
+    }
+
+    /// Declares a transient texture allocated by render graph.
+    pub fn create_texture<N>(&mut self, name: N, descriptor: TextureDescriptor) -> Result<(), Error>
+    where
+        N: Into<String>,
+    {
+        self.add_resource(name, ResourceKind::Transient(descriptor))
+    }
+
+    /// Declares a texture owned outside render graph,
+    /// which is published by the pass writing it using [`GraphResources::publish`].
+    pub fn import_texture<N>(&mut self, name: N) -> Result<(), Error>
+    where
+        N: Into<String>,
+    {
+        self.add_resource(name, ResourceKind::Imported)
+    }
+
+    /// Declares an output, such as the canvas framebuffer.
+    pub fn create_output<N>(&mut self, name: N) -> Result<(), Error>
+    where
+        N: Into<String>,
+    {
+        self.add_resource(name, ResourceKind::Output)
+    }
+
+    /// Returns `true` if a resource is declared.
+    pub fn has_resource(&self, name: &str) -> bool {
+        self.resources.iter().any(|resource| resource.name == name)
+    }
+
+    fn pass_index(&self, label: &str) -> Option<usize> {
+        self.passes.iter().position(|item| item.label == label)
+    }
+
+    fn insert_pass<L, P>(&mut self, index: usize, label: L, pass: P) -> Result<(), Error>
+    where
+        L: Into<String>,
+        P: RenderPass<C> + 'static,
+    {
+        let label = label.into();
+        if self.pass_index(&label).is_some() {
+            return Err(Error::RenderGraphDuplicatePass(label));
+        }
+        self.passes.insert(
+            index,
+            PassItem {
+                label,
+                pass: Box::new(pass),
+            },
+        );
+        self.set_dirty();
+        Ok(())
+    }
+
+    /// Adds a pass at the end.
+    pub fn add_pass<L, P>(&mut self, label: L, pass: P) -> Result<(), Error>
+    where
+        L: Into<String>,
+        P: RenderPass<C> + 'static,
+    {
+        self.insert_pass(self.passes.len(), label, pass)
+    }
+
+    /// Adds a pass right before another one.
+    /// Position only matters to passes modifying the same resource.
+    pub fn insert_pass_before<L, P>(&mut self, before: &str, label: L, pass: P) -> Result<(), Error>
+    where
+        L: Into<String>,
+        P: RenderPass<C> + 'static,
+    {
+        let index = self
+            .pass_index(before)
+            .ok_or_else(|| Error::RenderGraphNoSuchPass(before.to_string()))?;
+        self.insert_pass(index, label, pass)
+    }
+
+    /// Adds a pass right after another one.
+    /// Position only matters to passes modifying the same resource.
+    pub fn insert_pass_after<L, P>(&mut self, after: &str, label: L, pass: P) -> Result<(), Error>
+    where
+        L: Into<String>,
+        P: RenderPass<C> + 'static,
+    {
+        let index = self
+            .pass_index(after)
+            .ok_or_else(|| Error::RenderGraphNoSuchPass(after.to_string()))?;
+        self.insert_pass(index + 1, label, pass)
+    }
+
+    /// Removes a pass.
+    pub fn remove_pass(&mut self, label: &str) -> Option<Box<dyn RenderPass<C>>> {
+        let index = self.pass_index(label)?;
+        self.set_dirty();
+        Some(self.passes.remove(index).pass)
+    }
+
+    /// Returns labels of all passes in the order they are added.
+    pub fn pass_labels(&self) -> Vec<&str> {
+        self.passes.iter().map(|item| item.label.as_str()).collect()
+    }
+
+    /// Compiles render graph if it is not compiled yet or marked as dirty.
+    pub fn compile(&mut self, context: &C) -> Result<(), Error> {
+        if self.compiled.is_some() {
+            return Ok(());
+        }
+
+        let declarations = self
+            .passes
+            .iter()
+            .map(|item| {
+                let mut builder = PassBuilder::new();
+                item.pass.setup(context, &mut builder);
+                builder
+            })
+            .collect::<Vec<_>>();
+        self.compiled = Some(self.compile_declarations(declarations)?);
+        Ok(())
+    }
+
+    fn compile_declarations(&self, declarations: Vec<PassBuilder>) -> Result<CompiledGraph, Error> {
+        let resource_index = |name: &String| {
+            self.resources
+                .iter()
+                .position(|resource| &resource.name == name)
+                .ok_or_else(|| Error::RenderGraphNoSuchResource(name.clone()))
+        };
+        let mut reads = Vec::with_capacity(declarations.len());
+        let mut writes = Vec::with_capacity(declarations.len());
+        for declaration in declarations.iter() {
+            reads.push(
+                declaration
+                    .reads
+                    .iter()
+                    .map(resource_index)
+                    .collect::<Result<HashSet<_>, _>>()?,
+            );
+            writes.push(
+                declaration
+                    .writes
+                    .iter()
+                    .map(resource_index)
+                    .collect::<Result<HashSet<_>, _>>()?,
+            );
+        }
+
+        // builds dependencies between passes, resource by resource
+        let passes_len = declarations.len();
+        let mut dependencies = vec![HashSet::new(); passes_len];
+        let mut unwritten_reads = Vec::new();
+        for resource in 0..self.resources.len() {
+            let writers = (0..passes_len)
+                .filter(|pass| writes[*pass].contains(&resource))
+                .collect::<Vec<_>>();
+            let (modifiers, mut chain): (Vec<usize>, Vec<usize>) = writers
+                .into_iter()
+                .partition(|pass| reads[*pass].contains(&resource));
+            chain.extend(modifiers);
+
+            for pair in chain.windows(2) {
+                dependencies[pair[1]].insert(pair[0]);
+            }
+            match chain.last() {
+                Some(last) => {
+                    for pass in (0..passes_len)
+                        .filter(|pass| reads[*pass].contains(&resource) && !chain.contains(pass))
+                    {
+                        dependencies[pass].insert(*last);
+                    }
+                }
+                None => unwritten_reads.extend(
+                    (0..passes_len)
+                        .filter(|pass| reads[*pass].contains(&resource))
+                        .map(|pass| (pass, resource)),
+                ),
+            }
+            // a modifier reads nothing if no pass writes the resource before it
+            if let Some(first) = chain.first() {
+                if reads[*first].contains(&resource) {
+                    unwritten_reads.push((*first, resource));
+                }
+            }
+        }
+
+        // culls passes contributing nothing to outputs or side effects
+        let mut alive = vec![false; passes_len];
+        let mut stack = (0..passes_len)
+            .filter(|pass| {
+                declarations[*pass].side_effect
+                    || writes[*pass]
+                        .iter()
+                        .any(|resource| self.resources[*resource].kind == ResourceKind::Output)
+            })
+            .collect::<Vec<_>>();
+        while let Some(pass) = stack.pop() {
+            if alive[pass] {
+                continue;
+            }
+            alive[pass] = true;
+            stack.extend(dependencies[pass].iter().copied());
+        }
+
+        for (pass, resource) in unwritten_reads {
+            if alive[pass] && self.resources[resource].kind != ResourceKind::Output {
+                return Err(Error::RenderGraphReadBeforeWrite {
+                    pass: self.passes[pass].label.clone(),
+                    resource: self.resources[resource].name.clone(),
+                });
+            }
+        }
+
+        // orders alive passes topologically, preferring the order they are added
+        let mut remaining = (0..passes_len)
+            .map(|pass| dependencies[pass].len())
+            .collect::<Vec<_>>();
+        let mut dependents = vec![Vec::new(); passes_len];
+        for (pass, dependencies) in dependencies.iter().enumerate() {
+            for dependency in dependencies {
+                dependents[*dependency].push(pass);
+            }
+        }
+        let mut ready = (0..passes_len)
+            .filter(|pass| alive[*pass] && remaining[*pass] == 0)
+            .map(Reverse)
+            .collect::<BinaryHeap<_>>();
+        let mut order = Vec::new();
+        while let Some(Reverse(pass)) = ready.pop() {
+            order.push(pass);
+            for dependent in dependents[pass].iter() {
+                remaining[*dependent] -= 1;
+                if alive[*dependent] && remaining[*dependent] == 0 {
+                    ready.push(Reverse(*dependent));
+                }
+            }
+        }
+        let alive_len = alive.iter().filter(|alive| **alive).count();
+        if order.len() != alive_len {
+            let cyclic = (0..passes_len)
+                .filter(|pass| alive[*pass] && !order.contains(pass))
+                .map(|pass| self.passes[pass].label.clone())
+                .collect();
+            return Err(Error::RenderGraphCyclicPasses(cyclic));
+        }
+        let culled = (0..passes_len).filter(|pass| !alive[*pass]).collect();
+
+        // computes lifetimes of resources in execution order
+        let mut lifetimes: Vec<Option<(usize, usize)>> = vec![None; self.resources.len()];
+        for (position, pass) in order.iter().enumerate() {
+            for resource in reads[*pass].iter().chain(writes[*pass].iter()) {
+                let lifetime = lifetimes[*resource].get_or_insert((position, position));
+                lifetime.1 = position;
+            }
+        }
+
+        // assigns transient textures to slots, sharing a slot if lifetimes never overlap
+        let mut transients = (0..self.resources.len())
+            .filter_map(
+                |resource| match (self.resources[resource].kind, lifetimes[resource]) {
+                    (ResourceKind::Transient(descriptor), Some(lifetime)) => {
+                        Some((resource, descriptor, lifetime))
+                    }
+                    _ => None,
+                },
+            )
+            .collect::<Vec<_>>();
+        transients.sort_by_key(|(_, _, (first, _))| *first);
+        let mut slots: Vec<(TextureDescriptor, usize)> = Vec::new();
+        let mut aliases = vec![None; self.resources.len()];
+        for (resource, descriptor, (first, last)) in transients {
+            let slot = slots
+                .iter()
+                .position(|(slot, slot_last)| slot == &descriptor && *slot_last < first);
+            let slot = match slot {
+                Some(slot) => {
+                    slots[slot].1 = last;
+                    slot
+                }
+                None => {
+                    slots.push((descriptor, last));
+                    slots.len() - 1
+                }
+            };
+            aliases[resource] = Some(slot);
+        }
+
+        Ok(CompiledGraph {
+            order,
+            culled,
+            slots: slots
+                .into_iter()
+                .map(|(descriptor, _)| descriptor)
+                .collect(),
+            aliases,
+            lifetimes,
+        })
+    }
+
+    fn resource_index(&self, name: &str) -> Option<usize> {
+        self.resources
+            .iter()
+            .position(|resource| resource.name == name)
+    }
+
+    /// Returns labels of passes in execution order.
+    /// Returns an empty list if render graph is not compiled.
+    pub fn execution_order(&self) -> Vec<&str> {
+        let Some(compiled) = self.compiled.as_ref() else {
+            return Vec::new();
+        };
+        compiled
+            .order
+            .iter()
+            .map(|pass| self.passes[*pass].label.as_str())
+            .collect()
+    }
+
+    /// Returns labels of culled passes.
+    /// Returns an empty list if render graph is not compiled.
+    pub fn culled_passes(&self) -> Vec<&str> {
+        let Some(compiled) = self.compiled.as_ref() else {
+            return Vec::new();
+        };
+        compiled
+            .culled
+            .iter()
+            .map(|pass| self.passes[*pass].label.as_str())
+            .collect()
+    }
+
+    /// Returns descriptors of textures allocated for transient resources.
+    pub fn texture_slots(&self) -> &[TextureDescriptor] {
+        self.compiled
+            .as_ref()
+            .map(|compiled| compiled.slots.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Returns index of the texture slot assigned to a transient resource.
+    /// Transient resources sharing the same slot are aliased.
+    pub fn texture_slot(&self, name: &str) -> Option<usize> {
+        self.compiled.as_ref()?.aliases[self.resource_index(name)?]
+    }
+
+    /// Returns positions of the first and the last pass using a resource in execution order.
+    pub fn resource_lifetime(&self, name: &str) -> Option<(usize, usize)> {
+        self.compiled.as_ref()?.lifetimes[self.resource_index(name)?]
+    }
+
+    /// Compiles render graph if necessary, allocates transient textures and executes passes in order.
+    pub fn execute(
+        &mut self,
+        state: &mut FrameState,
+        scene: &mut Scene,
+        context: &mut C,
+    ) -> Result<(), Error> {
+        self.compile(context)?;
+
+        let compiled = self.compiled.as_ref().unwrap();
+        self.transient_textures
+            .allocate(state.gl(), &compiled.slots)?;
+
+        let mut resources = GraphResources {
+            resources: &self.resources,
+            textures: compiled
+                .aliases
+                .iter()
+                .map(|slot| slot.map(|slot| self.transient_textures.textures[slot].0.clone()))
+                .collect(),
+        };
+        for pass in compiled.order.iter() {
+            self.passes[*pass]
+                .pass
+                .execute(state, scene, context, &mut resources)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<C> Default for RenderGraph<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A pass declaring resources only, never executed in tests.
+    #[derive(Default)]
+    struct Declared {
+        reads: Vec<&'static str>,
+        writes: Vec<&'static str>,
+        side_effect: bool,
+    }
+
+    impl Declared {
+        fn new(reads: &[&'static str], writes: &[&'static str]) -> Self {
+            Self {
+                reads: reads.to_vec(),
+                writes: writes.to_vec(),
+                side_effect: false,
+            }
+        }
+    }
+
+    impl RenderPass<()> for Declared {
+        fn setup(&self, _: &(), builder: &mut PassBuilder) {
+            for name in self.reads.iter() {
+                builder.read(*name);
+            }
+            for name in self.writes.iter() {
+                builder.write(*name);
+            }
+            if self.side_effect {
+                builder.side_effect();
+            }
+        }
+
+        fn execute(
+            &mut self,
+            _: &mut FrameState,
+            _: &mut Scene,
+            _: &mut (),
+            _: &mut GraphResources<'_>,
+        ) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    fn rgba8() -> TextureDescriptor {
+        TextureDescriptor::new(TextureUncompressedInternalFormat::RGBA8)
+    }
+
+    #[test]
+    fn test_order_and_culling() {
+        let mut graph = RenderGraph::new();
+        graph.create_texture("color", rgba8()).unwrap();
+        graph.create_texture("composed", rgba8()).unwrap();
+        graph.create_texture("debug", rgba8()).unwrap();
+        graph.create_output("canvas").unwrap();
+
+        // added in reverse order on purpose
+        graph
+            .add_pass("present", Declared::new(&["composed"], &["canvas"]))
+            .unwrap();
+        graph
+            .add_pass("compose", Declared::new(&["color"], &["composed"]))
+            .unwrap();
+        graph
+            .add_pass("post", Declared::new(&["color"], &["color"]))
+            .unwrap();
+        graph
+            .add_pass("debug", Declared::new(&["color"], &["debug"]))
+            .unwrap();
+        graph
+            .add_pass("shading", Declared::new(&[], &["color"]))
+            .unwrap();
+        graph
+            .add_pass(
+                "picking",
+                Declared {
+                    side_effect: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        graph.compile(&()).unwrap();
+        assert_eq!(
+            graph.execution_order(),
+            vec!["shading", "post", "compose", "present", "picking"]
+        );
+        assert_eq!(graph.culled_passes(), vec!["debug"]);
+        assert_eq!(graph.resource_lifetime("color"), Some((0, 2)));
+        assert_eq!(graph.resource_lifetime("debug"), None);
+        assert_eq!(graph.texture_slot("debug"), None);
+
+        // custom passes modifying the same resource run in the order they are inserted
+        graph
+            .insert_pass_before("post", "outline", Declared::new(&["color"], &["color"]))
+            .unwrap();
+        graph
+            .insert_pass_after("post", "fxaa", Declared::new(&["color"], &["color"]))
+            .unwrap();
+        graph.compile(&()).unwrap();
+        assert_eq!(
+            graph.execution_order(),
+            vec!["shading", "outline", "post", "fxaa", "compose", "present", "picking"]
+        );
+
+        graph.remove_pass("present").unwrap();
+        graph.compile(&()).unwrap();
+        assert_eq!(graph.execution_order(), vec!["picking"]);
+    }
+
+    #[test]
+    fn test_texture_aliasing() {
+        let mut graph = RenderGraph::new();
+        graph.create_texture("a", rgba8()).unwrap();
+        graph.create_texture("b", rgba8()).unwrap();
+        graph.create_texture("c", rgba8()).unwrap();
+        graph
+            .create_texture(
+                "d",
+                TextureDescriptor::new(TextureUncompressedInternalFormat::RGBA16F),
+            )
+            .unwrap();
+        graph.create_output("canvas").unwrap();
+        graph.add_pass("1", Declared::new(&[], &["a"])).unwrap();
+        graph.add_pass("2", Declared::new(&["a"], &["b"])).unwrap();
+        graph
+            .add_pass("3", Declared::new(&["b"], &["c", "d"]))
+            .unwrap();
+        graph
+            .add_pass("4", Declared::new(&["c", "d"], &["canvas"]))
+            .unwrap();
+
+        graph.compile(&()).unwrap();
+        assert_eq!(graph.resource_lifetime("a"), Some((0, 1)));
+        assert_eq!(graph.resource_lifetime("c"), Some((2, 3)));
+        // a and c never live at the same time, while b overlaps both
+        assert_eq!(graph.texture_slot("a"), Some(0));
+        assert_eq!(graph.texture_slot("b"), Some(1));
+        assert_eq!(graph.texture_slot("c"), Some(0));
+        // textures with different descriptors never alias
+        assert_eq!(graph.texture_slot("d"), Some(2));
+        assert_eq!(graph.texture_slots().len(), 3);
+    }
+
+    #[test]
+    fn test_invalid_graphs() {
+        let mut graph = RenderGraph::new();
+        graph.create_texture("a", rgba8()).unwrap();
+        graph.create_texture("b", rgba8()).unwrap();
+        graph.create_output("canvas").unwrap();
+        assert!(matches!(
+            graph.create_output("a"),
+            Err(Error::RenderGraphDuplicateResource(_))
+        ));
+
+        graph.add_pass("1", Declared::new(&["b"], &["a"])).unwrap();
+        graph
+            .add_pass("2", Declared::new(&["a"], &["b", "canvas"]))
+            .unwrap();
+        assert!(matches!(
+            graph.add_pass("2", Declared::default()),
+            Err(Error::RenderGraphDuplicatePass(label)) if label == "2"
+        ));
+        assert!(matches!(
+            graph.insert_pass_after("3", "4", Declared::default()),
+            Err(Error::RenderGraphNoSuchPass(label)) if label == "3"
+        ));
+        match graph.compile(&()) {
+            Err(Error::RenderGraphCyclicPasses(labels)) => assert_eq!(labels, vec!["1", "2"]),
+            _ => panic!("cyclic passes compiled"),
+        }
+        assert_eq!(
+            graph.compile(&()).unwrap_err().to_string(),
+            "render passes 1, 2 are cyclic"
+        );
+
+        graph.remove_pass("1").unwrap();
+        assert!(matches!(
+            graph.compile(&()),
+            Err(Error::RenderGraphReadBeforeWrite { pass, resource }) if pass == "2" && resource == "a"
+        ));
+
+        graph.add_pass("3", Declared::new(&["x"], &[])).unwrap();
+        assert!(matches!(
+            graph.compile(&()),
+            Err(Error::RenderGraphNoSuchResource(name)) if name == "x"
+        ));
+    }
+}
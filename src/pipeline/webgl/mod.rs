@@ -1,8 +1,16 @@
+pub mod batching;
+pub mod brdf_lut;
 pub mod cleanup;
+pub mod cluster;
 pub mod collector;
 pub mod composer;
+pub mod equirectangular;
+pub mod graph;
+pub mod passes;
+pub mod postprocess;
 pub mod preparation;
 pub mod shading;
+pub mod shadow;
 
 use std::{borrow::Cow, cell::RefCell, rc::Rc};
 
@@ -12,21 +20,30 @@ use uuid::Uuid;
 
 use crate::{
     entity::Entity,
+    light::MAX_SHADOW_CASCADES,
     renderer::webgl::{
         buffer::{
             self, Buffer, BufferData, BufferSource, BufferUsage, MemoryPolicy, Preallocation,
         },
         error::Error,
         state::FrameState,
+        texture::{Texture, Texture3D},
         uniform::UniformBlockBinding,
     },
     scene::{Scene, MAX_AREA_LIGHTS, MAX_DIRECTIONAL_LIGHTS, MAX_POINT_LIGHTS, MAX_SPOT_LIGHTS},
 };
 
 use self::{
+    batching::DrawStatistics,
     cleanup::StandardCleanup,
-    collector::StandardEntitiesCollector,
+    cluster::{ClusterGrid, StandardClusteredLighting},
+    collector::{CollectedEntities, StandardEntitiesCollector},
     composer::StandardComposer,
+    graph::RenderGraph,
+    postprocess::{
+        color_grading::StandardColorGrading, depth_of_field::StandardDepthOfField,
+        fxaa::StandardFxaa, ssao::StandardSsao, PostProcessEffect, PostProcessStack,
+    },
     preparation::StandardPreparation,
     shading::{
         deferred::{
@@ -37,8 +54,10 @@ use self::{
             hdr::StandardHdrShading, hdr_multisamples::StandardMultisamplesHdrShading,
             simple::StandardSimpleShading, simple_multisamples::StandardMultisamplesSimpleShading,
         },
-        picking::StandardPicking,
+        oit::StandardWeightedBlendedOit,
+        picking::{PickedElement, PickingRegion, StandardPicking},
     },
+    shadow::StandardShadowMapping,
 };
 
 use super::Pipeline;
@@ -61,12 +80,28 @@ pub const UBO_GAUSSIAN_KERNEL_BLOCK_NAME: &'static str = "atoy_GaussianKernel";
 pub const UBO_GAUSSIAN_KERNEL_BLOCK_BINDING: UniformBlockBinding =
     UniformBlockBinding::Custom(Cow::Borrowed(UBO_GAUSSIAN_KERNEL_BLOCK_NAME));
 
+/// Uniform Buffer Object `atoy_Shadows`.
+pub const UBO_SHADOWS_BLOCK_NAME: &'static str = "atoy_Shadows";
+/// [`UniformBlockBinding`] Uniform Buffer Object `atoy_Shadows`.
+pub const UBO_SHADOWS_BLOCK_BINDING: UniformBlockBinding =
+    UniformBlockBinding::Custom(Cow::Borrowed(UBO_SHADOWS_BLOCK_NAME));
+
+/// Uniform Buffer Object `atoy_Clusters`.
+pub const UBO_CLUSTERS_BLOCK_NAME: &'static str = "atoy_Clusters";
+/// [`UniformBlockBinding`] Uniform Buffer Object `atoy_Clusters`.
+pub const UBO_CLUSTERS_BLOCK_BINDING: UniformBlockBinding =
+    UniformBlockBinding::Custom(Cow::Borrowed(UBO_CLUSTERS_BLOCK_NAME));
+
 /// Uniform Buffer Object mount point for `atoy_UniversalVert` and `atoy_UniversalFrag`.
 pub const UBO_UNIVERSAL_UNIFORM_BLOCK_MOUNT_POINT: u32 = 0;
 /// Uniform Buffer Object mount point for `atoy_Lights`.
 pub const UBO_LIGHTS_UNIFORM_BLOCK_MOUNT_POINT: u32 = 1;
 /// Uniform Buffer Object mount point for gaussian blur.
 pub const UBO_GAUSSIAN_BLUR_UNIFORM_BLOCK_MOUNT_POINT: u32 = 2;
+/// Uniform Buffer Object mount point for `atoy_Shadows`.
+pub const UBO_SHADOWS_UNIFORM_BLOCK_MOUNT_POINT: u32 = 3;
+/// Uniform Buffer Object mount point for `atoy_Clusters`.
+pub const UBO_CLUSTERS_UNIFORM_BLOCK_MOUNT_POINT: u32 = 4;
 
 /// Uniform Buffer Object bytes length for `u_RenderTime`.
 pub const UBO_UNIVERSAL_UNIFORMS_RENDER_TIME_BYTE_LENGTH: usize = 16;
@@ -134,6 +169,71 @@ pub const UBO_LIGHTS_SPOT_LIGHTS_BYTE_OFFSET: usize =
 pub const UBO_LIGHTS_AREA_LIGHTS_BYTE_OFFSET: usize =
     UBO_LIGHTS_SPOT_LIGHTS_BYTE_OFFSET + MAX_SPOT_LIGHTS * UBO_LIGHTS_SPOT_LIGHT_BYTE_LENGTH;
 
+/// Uniform Buffer Object bytes length for a `u_DirectionalShadowMatrices` item.
+pub const UBO_SHADOWS_DIRECTIONAL_SHADOW_MATRIX_BYTE_LENGTH: usize = 64;
+/// Uniform Buffer Object bytes length for a `u_DirectionalShadowRegions` item.
+pub const UBO_SHADOWS_DIRECTIONAL_SHADOW_REGION_BYTE_LENGTH: usize = 16;
+/// Uniform Buffer Object bytes length for a `u_DirectionalShadowCascades` item.
+pub const UBO_SHADOWS_DIRECTIONAL_SHADOW_CASCADE_BYTE_LENGTH: usize = 16;
+/// Uniform Buffer Object bytes length for a `u_SpotShadowMatrices` item.
+pub const UBO_SHADOWS_SPOT_SHADOW_MATRIX_BYTE_LENGTH: usize = 64;
+/// Uniform Buffer Object bytes length for a `u_SpotShadowRegions` item.
+pub const UBO_SHADOWS_SPOT_SHADOW_REGION_BYTE_LENGTH: usize = 16;
+/// Uniform Buffer Object bytes length for a `u_PointShadows` item.
+pub const UBO_SHADOWS_POINT_SHADOW_BYTE_LENGTH: usize = 16;
+
+/// Uniform Buffer Object bytes length for `atoy_Shadows`.
+pub const UBO_SHADOWS_BYTE_LENGTH: usize = UBO_SHADOWS_DIRECTIONAL_SHADOW_MATRIX_BYTE_LENGTH
+    * MAX_DIRECTIONAL_LIGHTS
+    * MAX_SHADOW_CASCADES
+    + UBO_SHADOWS_DIRECTIONAL_SHADOW_REGION_BYTE_LENGTH
+        * MAX_DIRECTIONAL_LIGHTS
+        * MAX_SHADOW_CASCADES
+    + UBO_SHADOWS_DIRECTIONAL_SHADOW_CASCADE_BYTE_LENGTH * MAX_DIRECTIONAL_LIGHTS
+    + UBO_SHADOWS_SPOT_SHADOW_MATRIX_BYTE_LENGTH * MAX_SPOT_LIGHTS
+    + UBO_SHADOWS_SPOT_SHADOW_REGION_BYTE_LENGTH * MAX_SPOT_LIGHTS
+    + UBO_SHADOWS_POINT_SHADOW_BYTE_LENGTH * MAX_POINT_LIGHTS;
+
+/// Uniform Buffer Object bytes offset for `u_DirectionalShadowMatrices`.
+pub const UBO_SHADOWS_DIRECTIONAL_SHADOW_MATRICES_BYTE_OFFSET: usize = 0;
+/// Uniform Buffer Object bytes offset for `u_DirectionalShadowRegions`.
+pub const UBO_SHADOWS_DIRECTIONAL_SHADOW_REGIONS_BYTE_OFFSET: usize =
+    UBO_SHADOWS_DIRECTIONAL_SHADOW_MATRICES_BYTE_OFFSET
+        + MAX_DIRECTIONAL_LIGHTS
+            * MAX_SHADOW_CASCADES
+            * UBO_SHADOWS_DIRECTIONAL_SHADOW_MATRIX_BYTE_LENGTH;
+/// Uniform Buffer Object bytes offset for `u_DirectionalShadowCascades`.
+pub const UBO_SHADOWS_DIRECTIONAL_SHADOW_CASCADES_BYTE_OFFSET: usize =
+    UBO_SHADOWS_DIRECTIONAL_SHADOW_REGIONS_BYTE_OFFSET
+        + MAX_DIRECTIONAL_LIGHTS
+            * MAX_SHADOW_CASCADES
+            * UBO_SHADOWS_DIRECTIONAL_SHADOW_REGION_BYTE_LENGTH;
+/// Uniform Buffer Object bytes offset for `u_SpotShadowMatrices`.
+pub const UBO_SHADOWS_SPOT_SHADOW_MATRICES_BYTE_OFFSET: usize =
+    UBO_SHADOWS_DIRECTIONAL_SHADOW_CASCADES_BYTE_OFFSET
+        + MAX_DIRECTIONAL_LIGHTS * UBO_SHADOWS_DIRECTIONAL_SHADOW_CASCADE_BYTE_LENGTH;
+/// Uniform Buffer Object bytes offset for `u_SpotShadowRegions`.
+pub const UBO_SHADOWS_SPOT_SHADOW_REGIONS_BYTE_OFFSET: usize =
+    UBO_SHADOWS_SPOT_SHADOW_MATRICES_BYTE_OFFSET
+        + MAX_SPOT_LIGHTS * UBO_SHADOWS_SPOT_SHADOW_MATRIX_BYTE_LENGTH;
+/// Uniform Buffer Object bytes offset for `u_PointShadows`.
+pub const UBO_SHADOWS_POINT_SHADOWS_BYTE_OFFSET: usize = UBO_SHADOWS_SPOT_SHADOW_REGIONS_BYTE_OFFSET
+    + MAX_SPOT_LIGHTS * UBO_SHADOWS_SPOT_SHADOW_REGION_BYTE_LENGTH;
+
+/// Uniform Buffer Object bytes length for `u_ClusterDimensions`.
+pub const UBO_CLUSTERS_DIMENSIONS_BYTE_LENGTH: usize = 16;
+/// Uniform Buffer Object bytes length for `u_ClusterDepthRange`.
+pub const UBO_CLUSTERS_DEPTH_RANGE_BYTE_LENGTH: usize = 16;
+
+/// Uniform Buffer Object bytes length for `atoy_Clusters`.
+pub const UBO_CLUSTERS_BYTE_LENGTH: usize =
+    UBO_CLUSTERS_DIMENSIONS_BYTE_LENGTH + UBO_CLUSTERS_DEPTH_RANGE_BYTE_LENGTH;
+
+/// Uniform Buffer Object bytes offset for `u_ClusterDimensions`.
+pub const UBO_CLUSTERS_DIMENSIONS_BYTE_OFFSET: usize = 0;
+/// Uniform Buffer Object bytes offset for `u_ClusterDepthRange`.
+pub const UBO_CLUSTERS_DEPTH_RANGE_BYTE_OFFSET: usize = 16;
+
 /// Uniform Buffer Object data in f32 for `atoy_GaussianKernel`.
 #[rustfmt::skip]
 pub const UBO_GAUSSIAN_KERNEL: [f32; 324] = [
@@ -238,6 +338,19 @@ pub enum StandardPipelineShading {
     Picking,
 }
 
+/// Transparency strategies for translucent entities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
+pub enum StandardTransparency {
+    /// Draws translucent entities from farthest to nearest,
+    /// relying on distance sorting of entities collector.
+    Sorted,
+    /// Weighted blended order-independent transparency,
+    /// drawing translucent entities in any order and compositing them by weights.
+    /// Falls back to [`StandardTransparency::Sorted`] if `EXT_color_buffer_float` is not supported.
+    WeightedBlended,
+}
+
 pub const DEFAULT_SHADING: StandardPipelineShading = StandardPipelineShading::DeferredShading;
 pub const DEFAULT_LIGHTING_ENABLED: bool = true;
 pub const DEFAULT_MULTISAMPLES_ENABLED: bool = true;
@@ -246,8 +359,21 @@ pub const DEFAULT_HDR_ENABLED: bool = true;
 pub const DEFAULT_HDR_TONE_MAPPING_TYPE: HdrToneMappingType = HdrToneMappingType::Reinhard;
 pub const DEFAULT_BLOOM_ENABLED: bool = false;
 pub const DEFAULT_BLOOM_BLUR_EPOCH: usize = 5;
-
+pub const DEFAULT_SHADOW_ENABLED: bool = false;
+pub const DEFAULT_CLUSTERED_LIGHTING_ENABLED: bool = false;
+pub const DEFAULT_TRANSPARENCY: StandardTransparency = StandardTransparency::Sorted;
+
+/// Standard pipeline, executing built-in shading paths through a [`RenderGraph`].
+///
+/// Custom passes could be inserted between built-in passes using [`StandardPipeline::render_graph_mut`],
+/// labels of built-in passes and names of built-in resources are listed in [`passes`].
 pub struct StandardPipeline {
+    graph: RenderGraph<StandardPipelineContext>,
+    context: StandardPipelineContext,
+}
+
+/// Shared context of passes in [`StandardPipeline`].
+pub struct StandardPipelineContext {
     pipeline_shading: StandardPipelineShading,
 
     preparation: StandardPreparation,
@@ -257,8 +383,12 @@ pub struct StandardPipeline {
     hdr_shading: StandardHdrShading,
     multisamples_hdr_shading: StandardMultisamplesHdrShading,
     composer: StandardComposer,
+    post_process: PostProcessStack,
     cleanup: StandardCleanup,
     picking: StandardPicking,
+    shadow_mapping: StandardShadowMapping,
+    clustered_lighting: StandardClusteredLighting,
+    oit: StandardWeightedBlendedOit,
 
     gbuffer: StandardGBufferCollector,
     deferred_shading: StandardDeferredShading,
@@ -267,6 +397,8 @@ pub struct StandardPipeline {
     universal_ubo: Buffer,
     lights_ubo: Buffer,
     gaussian_kernel_ubo: Buffer,
+    shadows_ubo: Buffer,
+    clusters_ubo: Buffer,
 
     lighting: bool,
     multisamples: bool,
@@ -275,6 +407,9 @@ pub struct StandardPipeline {
     hdr_tone_mapping_type: HdrToneMappingType,
     bloom: bool,
     bloom_blur_epoch: usize,
+    shadow: bool,
+    clustered: bool,
+    transparency: StandardTransparency,
 }
 
 #[derive(Debug)]
@@ -292,7 +427,7 @@ impl BufferSource for GaussianKernelBufferSource {
 
 impl StandardPipeline {
     pub fn new() -> Self {
-        Self {
+        let context = StandardPipelineContext {
             pipeline_shading: DEFAULT_SHADING,
 
             preparation: StandardPreparation::new(),
@@ -302,8 +437,12 @@ impl StandardPipeline {
             multisamples_hdr_shading: StandardMultisamplesHdrShading::new(),
             hdr_shading: StandardHdrShading::new(),
             composer: StandardComposer::new(),
+            post_process: PostProcessStack::new(),
             cleanup: StandardCleanup::new(),
             picking: StandardPicking::new(),
+            shadow_mapping: StandardShadowMapping::new(),
+            clustered_lighting: StandardClusteredLighting::new(),
+            oit: StandardWeightedBlendedOit::new(),
 
             gbuffer: StandardGBufferCollector::new(),
             deferred_shading: StandardDeferredShading::new(),
@@ -321,6 +460,14 @@ impl StandardPipeline {
                 .buffer_data(UBO_GAUSSIAN_KERNEL_BYTES)
                 .set_memory_policy(MemoryPolicy::restorable(GaussianKernelBufferSource))
                 .build(),
+            shadows_ubo: buffer::Builder::new(BufferUsage::DYNAMIC_DRAW)
+                .buffer_data(Preallocation::new(UBO_SHADOWS_BYTE_LENGTH))
+                .set_memory_policy(MemoryPolicy::Unfree)
+                .build(),
+            clusters_ubo: buffer::Builder::new(BufferUsage::DYNAMIC_DRAW)
+                .buffer_data(Preallocation::new(UBO_CLUSTERS_BYTE_LENGTH))
+                .set_memory_policy(MemoryPolicy::Unfree)
+                .build(),
 
             lighting: DEFAULT_LIGHTING_ENABLED,
             multisamples: DEFAULT_MULTISAMPLES_ENABLED,
@@ -329,176 +476,426 @@ impl StandardPipeline {
             hdr_tone_mapping_type: DEFAULT_HDR_TONE_MAPPING_TYPE,
             bloom: DEFAULT_BLOOM_ENABLED,
             bloom_blur_epoch: DEFAULT_BLOOM_BLUR_EPOCH,
+            shadow: DEFAULT_SHADOW_ENABLED,
+            clustered: DEFAULT_CLUSTERED_LIGHTING_ENABLED,
+            transparency: DEFAULT_TRANSPARENCY,
+        };
+
+        Self {
+            graph: passes::standard_render_graph(),
+            context,
         }
     }
 
-    pub fn set_dirty(&mut self) {}
+    /// Marks render graph to compile again, since passes may declare different resources now.
+    pub fn set_dirty(&mut self) {
+        self.graph.set_dirty();
+    }
+
+    /// Returns render graph executing passes of the pipeline.
+    pub fn render_graph(&self) -> &RenderGraph<StandardPipelineContext> {
+        &self.graph
+    }
+
+    /// Returns mutable render graph executing passes of the pipeline,
+    /// custom passes could be added or inserted between built-in passes.
+    pub fn render_graph_mut(&mut self) -> &mut RenderGraph<StandardPipelineContext> {
+        &mut self.graph
+    }
 
     pub fn pipeline_shading(&self) -> StandardPipelineShading {
-        self.pipeline_shading
+        self.context.pipeline_shading
     }
 
     pub fn set_pipeline_shading(&mut self, pipeline_shading: StandardPipelineShading) {
-        self.pipeline_shading = pipeline_shading;
+        self.context.pipeline_shading = pipeline_shading;
+        self.set_dirty();
     }
 
     pub fn clear_color(&self) -> &Vec4<f32> {
-        self.composer.clear_color()
+        self.context.composer.clear_color()
     }
 
     pub fn set_clear_color(&mut self, clear_color: Vec4<f32>) {
-        self.composer.set_clear_color(clear_color);
+        self.context.composer.set_clear_color(clear_color);
         self.set_dirty();
     }
 
     pub fn gamma_correction_enabled(&self) -> bool {
-        self.composer.gamma_correction_enabled()
+        self.context.composer.gamma_correction_enabled()
     }
 
     pub fn enable_gamma_correction(&mut self) {
-        self.composer.enable_gamma_correction();
+        self.context.composer.enable_gamma_correction();
     }
 
     pub fn disable_gamma_correction(&mut self) {
-        self.composer.disable_gamma_correction();
+        self.context.composer.disable_gamma_correction();
     }
 
     pub fn gamma(&self) -> f32 {
-        self.composer.gamma()
+        self.context.composer.gamma()
     }
 
     pub fn set_gamma(&mut self, gamma: f32) {
-        self.composer.set_gamma(gamma);
+        self.context.composer.set_gamma(gamma);
     }
 
     /// Returns `true` if entity culling enabled.
 
     pub fn culling_enabled(&self) -> bool {
-        self.entities_collector.culling_enabled()
+        self.context.entities_collector.culling_enabled()
     }
 
     /// Enables culling by bounding volumes.
 
     pub fn enable_culling(&mut self) {
-        self.entities_collector.enable_culling();
+        self.context.entities_collector.enable_culling();
         self.set_dirty();
     }
 
     /// Disables culling by bounding volumes.
 
     pub fn disable_culling(&mut self) {
-        self.entities_collector.disable_culling();
+        self.context.entities_collector.disable_culling();
         self.set_dirty();
     }
 
     /// Returns `true` if entity distance sorting enabled.
 
     pub fn distance_sorting_enabled(&self) -> bool {
-        self.entities_collector.distance_sorting_enabled()
+        self.context.entities_collector.distance_sorting_enabled()
     }
 
     /// Enables distance sorting by bounding volumes.
 
     pub fn enable_distance_sorting(&mut self) {
-        self.entities_collector.enable_distance_sorting();
+        self.context.entities_collector.enable_distance_sorting();
         self.set_dirty();
     }
 
     /// Disables distance sorting by bounding volumes.
 
     pub fn disable_distance_sorting(&mut self) {
-        self.entities_collector.disable_distance_sorting();
+        self.context.entities_collector.disable_distance_sorting();
         self.set_dirty();
     }
 
+    /// Returns `true` if opaque entities state sorting enabled.
+    pub fn state_sorting_enabled(&self) -> bool {
+        self.context.entities_collector.state_sorting_enabled()
+    }
+
+    /// Enables sorting opaque entities by program, textures and vertex buffers.
+    pub fn enable_state_sorting(&mut self) {
+        self.context.entities_collector.enable_state_sorting();
+        self.set_dirty();
+    }
+
+    /// Disables sorting opaque entities by program, textures and vertex buffers.
+    pub fn disable_state_sorting(&mut self) {
+        self.context.entities_collector.disable_state_sorting();
+        self.set_dirty();
+    }
+
+    /// Returns `true` if static batching enabled.
+    pub fn static_batching_enabled(&self) -> bool {
+        self.context.entities_collector.static_batching_enabled()
+    }
+
+    /// Enables merging static entities sharing the same static batch id into single draw calls.
+    pub fn enable_static_batching(&mut self) {
+        self.context.entities_collector.enable_static_batching();
+        self.set_dirty();
+    }
+
+    /// Disables static batching.
+    pub fn disable_static_batching(&mut self) {
+        self.context.entities_collector.disable_static_batching();
+        self.set_dirty();
+    }
+
+    /// Returns drawing counters of last frame,
+    /// including number of program switches saved by state sorting.
+    pub fn draw_statistics(&self) -> DrawStatistics {
+        self.context.entities_collector.statistics()
+    }
+
     /// Returns `true` if enable lighting.
     /// Diffuse color of material used directly if lighting is disabled.
 
     pub fn lighting_enabled(&self) -> bool {
-        self.lighting
+        self.context.lighting
     }
 
     /// Enables lighting.
 
     pub fn enable_lighting(&mut self) {
-        self.lighting = true;
+        self.context.lighting = true;
         self.set_dirty();
     }
 
     /// Disables lighting.
 
     pub fn disable_lighting(&mut self) {
-        self.lighting = false;
+        self.context.lighting = false;
         self.set_dirty();
     }
 
     pub fn hdr_enabled(&self) -> bool {
-        self.hdr
+        self.context.hdr
     }
 
     pub fn enable_hdr(&mut self) {
-        self.hdr = true;
+        self.context.hdr = true;
         self.set_dirty();
     }
 
     pub fn disable_hdr(&mut self) {
-        self.hdr = false;
+        self.context.hdr = false;
         self.set_dirty();
     }
 
     pub fn hdr_tone_mapping_type(&self) -> HdrToneMappingType {
-        self.hdr_tone_mapping_type
+        self.context.hdr_tone_mapping_type
     }
 
     pub fn set_hdr_tone_mapping_type(&mut self, tone_mapping_type: HdrToneMappingType) {
-        self.hdr_tone_mapping_type = tone_mapping_type;
+        self.context.hdr_tone_mapping_type = tone_mapping_type;
         self.set_dirty();
     }
 
     pub fn bloom_enabled(&self) -> bool {
-        self.bloom
+        self.context.bloom
     }
 
     pub fn enable_bloom(&mut self) {
-        self.bloom = true;
+        self.context.bloom = true;
         self.set_dirty();
     }
 
     pub fn disable_bloom(&mut self) {
-        self.bloom = false;
+        self.context.bloom = false;
         self.set_dirty();
     }
 
     pub fn bloom_blur_epoch(&self) -> usize {
-        self.bloom_blur_epoch
+        self.context.bloom_blur_epoch
     }
 
     pub fn set_bloom_blur_epoch(&mut self, epoch: usize) {
-        self.bloom_blur_epoch = epoch;
+        self.context.bloom_blur_epoch = epoch;
+        self.set_dirty();
+    }
+
+    /// Returns `true` if shadow mapping enabled.
+    /// Shadows are only rendered when lighting is enabled as well.
+    pub fn shadow_enabled(&self) -> bool {
+        self.context.shadow
+    }
+
+    /// Enables shadow mapping.
+    pub fn enable_shadow(&mut self) {
+        self.context.shadow = true;
+        self.set_dirty();
+    }
+
+    /// Disables shadow mapping.
+    pub fn disable_shadow(&mut self) {
+        self.context.shadow = false;
+        self.set_dirty();
+    }
+
+    /// Returns width and height of the shadow atlas
+    /// shared by directional lights and spot lights.
+    pub fn shadow_atlas_size(&self) -> usize {
+        self.context.shadow_mapping.atlas_size()
+    }
+
+    /// Sets width and height of the shadow atlas.
+    pub fn set_shadow_atlas_size(&mut self, size: usize) {
+        self.context.shadow_mapping.set_atlas_size(size);
+        self.set_dirty();
+    }
+
+    /// Returns `true` if clustered lighting enabled.
+    /// Clustered lighting lifts limits of point lights and spot lights,
+    /// each fragment loops over lights around it only.
+    pub fn clustered_lighting_enabled(&self) -> bool {
+        self.context.clustered
+    }
+
+    /// Enables clustered lighting.
+    pub fn enable_clustered_lighting(&mut self) {
+        self.context.clustered = true;
+        self.set_dirty();
+    }
+
+    /// Disables clustered lighting.
+    pub fn disable_clustered_lighting(&mut self) {
+        self.context.clustered = false;
+        self.set_dirty();
+    }
+
+    /// Returns cluster grid of clustered lighting.
+    pub fn cluster_grid(&self) -> ClusterGrid {
+        self.context.clustered_lighting.grid()
+    }
+
+    /// Sets cluster grid of clustered lighting.
+    pub fn set_cluster_grid(&mut self, grid: ClusterGrid) {
+        self.context.clustered_lighting.set_grid(grid);
+    }
+
+    /// Returns view distance where the last slice of clusters ends.
+    pub fn cluster_far(&self) -> f64 {
+        self.context.clustered_lighting.far()
+    }
+
+    /// Sets view distance where the last slice of clusters ends.
+    pub fn set_cluster_far(&mut self, far: f64) {
+        self.context.clustered_lighting.set_far(far);
+    }
+
+    /// Returns transparency strategy for translucent entities.
+    pub fn transparency(&self) -> StandardTransparency {
+        self.context.transparency
+    }
+
+    /// Sets transparency strategy for translucent entities.
+    /// Distance sorting could be disabled under [`StandardTransparency::WeightedBlended`]
+    /// if only translucent entities rely on it.
+    pub fn set_transparency(&mut self, transparency: StandardTransparency) {
+        self.context.transparency = transparency;
         self.set_dirty();
     }
 
     pub fn multisamples_enabled(&self) -> bool {
-        self.multisamples
+        self.context.multisamples
     }
 
     pub fn enable_multisamples(&mut self) {
-        self.multisamples = true;
+        self.context.multisamples = true;
         self.set_dirty();
     }
 
     pub fn disable_multisamples(&mut self) {
-        self.multisamples = false;
+        self.context.multisamples = false;
         self.set_dirty();
     }
 
     pub fn multisamples_count(&self) -> usize {
-        self.multisamples_count
+        self.context.multisamples_count
     }
 
     pub fn set_multisamples_count(&mut self, count: usize) {
-        self.multisamples_count = count;
+        self.context.multisamples_count = count;
+        self.set_dirty();
+    }
+
+    /// Returns post processing effects applied after tone mapping and bloom.
+    pub fn post_process(&self) -> &PostProcessStack {
+        &self.context.post_process
+    }
+
+    /// Returns mutable post processing effects applied after tone mapping and bloom.
+    pub fn post_process_mut(&mut self) -> &mut PostProcessStack {
+        // enabled effects may require gbuffer
         self.set_dirty();
+        &mut self.context.post_process
+    }
+
+    fn post_process_effect_enabled<E>(&self) -> bool
+    where
+        E: PostProcessEffect + 'static,
+    {
+        self.context
+            .post_process
+            .effect_by_type::<E>()
+            .map(|effect| effect.enabled())
+            .unwrap_or(false)
+    }
+
+    fn set_post_process_effect_enabled<E>(&mut self, enabled: bool)
+    where
+        E: PostProcessEffect + 'static,
+    {
+        if let Some(effect) = self.post_process_mut().effect_by_type_mut::<E>() {
+            effect.set_enabled(enabled);
+        }
+    }
+
+    /// Returns `true` if FXAA enabled.
+    pub fn fxaa_enabled(&self) -> bool {
+        self.post_process_effect_enabled::<StandardFxaa>()
+    }
+
+    /// Enables FXAA.
+    pub fn enable_fxaa(&mut self) {
+        self.set_post_process_effect_enabled::<StandardFxaa>(true);
+    }
+
+    /// Disables FXAA.
+    pub fn disable_fxaa(&mut self) {
+        self.set_post_process_effect_enabled::<StandardFxaa>(false);
+    }
+
+    /// Returns `true` if SSAO enabled.
+    /// SSAO only applies under deferred shading.
+    pub fn ssao_enabled(&self) -> bool {
+        self.post_process_effect_enabled::<StandardSsao>()
+    }
+
+    /// Enables SSAO.
+    pub fn enable_ssao(&mut self) {
+        self.set_post_process_effect_enabled::<StandardSsao>(true);
+    }
+
+    /// Disables SSAO.
+    pub fn disable_ssao(&mut self) {
+        self.set_post_process_effect_enabled::<StandardSsao>(false);
+    }
+
+    /// Returns `true` if depth of field enabled.
+    /// Depth of field only applies under deferred shading.
+    pub fn depth_of_field_enabled(&self) -> bool {
+        self.post_process_effect_enabled::<StandardDepthOfField>()
+    }
+
+    /// Enables depth of field.
+    pub fn enable_depth_of_field(&mut self) {
+        self.set_post_process_effect_enabled::<StandardDepthOfField>(true);
+    }
+
+    /// Disables depth of field.
+    pub fn disable_depth_of_field(&mut self) {
+        self.set_post_process_effect_enabled::<StandardDepthOfField>(false);
+    }
+
+    /// Returns `true` if color grading enabled.
+    pub fn color_grading_enabled(&self) -> bool {
+        self.post_process_effect_enabled::<StandardColorGrading>()
+    }
+
+    /// Enables color grading.
+    pub fn enable_color_grading(&mut self) {
+        self.set_post_process_effect_enabled::<StandardColorGrading>(true);
+    }
+
+    /// Disables color grading.
+    pub fn disable_color_grading(&mut self) {
+        self.set_post_process_effect_enabled::<StandardColorGrading>(false);
+    }
+
+    /// Sets 3d lookup table for color grading.
+    pub fn set_color_grading_lut(&mut self, lut: Option<Texture<Texture3D>>) {
+        if let Some(color_grading) = self
+            .context
+            .post_process
+            .effect_by_type_mut::<StandardColorGrading>()
+        {
+            color_grading.set_lut(lut);
+        }
     }
 
     /// Returns picked entity index.
@@ -508,11 +905,13 @@ impl StandardPipeline {
         window_position_x: i32,
         window_position_y: i32,
     ) -> Result<Option<Uuid>, Error> {
-        self.picking
+        self.context
+            .picking
             .pick_entity_async(
                 window_position_x,
                 window_position_y,
-                self.entities_collector
+                self.context
+                    .entities_collector
                     .last_collected_entities()
                     .entities()
                     .to_vec(),
@@ -527,10 +926,59 @@ impl StandardPipeline {
         window_position_x: i32,
         window_position_y: i32,
     ) -> Result<Option<Rc<RefCell<dyn Entity>>>, Error> {
-        self.picking.pick_entity(
+        self.context.picking.pick_entity(
             window_position_x,
             window_position_y,
-            &self.entities_collector.last_collected_entities(),
+            &self.context.entities_collector.last_collected_entities(),
+        )
+    }
+
+    /// Returns ids of entities visible inside a region.
+    /// Executes [`StandardPipeline::picking`] before calling this method, or the result maybe incorrect.
+    pub async fn pick_entities_async(
+        &mut self,
+        region: PickingRegion,
+        fully_inside: bool,
+    ) -> Result<Vec<Uuid>, Error> {
+        self.context
+            .picking
+            .pick_entities_async(
+                region,
+                fully_inside,
+                self.context
+                    .entities_collector
+                    .last_collected_entities()
+                    .entities()
+                    .to_vec(),
+            )
+            .await
+    }
+
+    /// Returns entities visible inside a region.
+    /// Executes [`StandardPipeline::picking`] before calling this method, or the result maybe incorrect.
+    pub fn pick_entities(
+        &mut self,
+        region: &PickingRegion,
+        fully_inside: bool,
+    ) -> Result<Vec<Rc<RefCell<dyn Entity>>>, Error> {
+        self.context.picking.pick_entities(
+            region,
+            fully_inside,
+            &self.context.entities_collector.last_collected_entities(),
+        )
+    }
+
+    /// Returns picked entity with its picked vertex and primitive.
+    /// Executes [`StandardPipeline::picking`] before calling this method, or the result maybe incorrect.
+    pub fn pick_element(
+        &mut self,
+        window_position_x: i32,
+        window_position_y: i32,
+    ) -> Result<Option<PickedElement>, Error> {
+        self.context.picking.pick_element(
+            window_position_x,
+            window_position_y,
+            &self.context.entities_collector.last_collected_entities(),
         )
     }
 
@@ -541,120 +989,59 @@ impl StandardPipeline {
         window_position_x: i32,
         window_position_y: i32,
     ) -> Result<Option<Vec3<f64>>, Error> {
-        self.picking.pick_position(
+        self.context.picking.pick_position(
             window_position_x,
             window_position_y,
-            &self.entities_collector.last_collected_entities(),
+            &self.context.entities_collector.last_collected_entities(),
         )
     }
 }
 
-impl StandardPipeline {
-    fn forward_shading(&mut self, state: &mut FrameState, scene: &mut Scene) -> Result<(), Error> {
-        let lighting = self.lighting_enabled();
-        let hdr_supported = state.capabilities().color_buffer_float_supported();
-        let hdr = hdr_supported && self.hdr_enabled();
-        let bloom = self.bloom_enabled();
-        let bloom_blur_epoch = self.bloom_blur_epoch();
-        let multisamples = self.multisamples_enabled() && self.multisamples_count() != 0;
-
-        unsafe {
-            let collected_entities = self.entities_collector.collect_entities(state, scene);
-            let compose_textures = match (hdr, multisamples) {
-                (true, false) => {
-                    self.hdr_shading.draw(
-                        state,
-                        bloom,
-                        bloom_blur_epoch,
-                        self.hdr_tone_mapping_type,
-                        &collected_entities,
-                        lighting,
-                        &self.gaussian_kernel_ubo,
-                    )?;
-                    self.hdr_shading.draw_texture()?.unwrap()
-                }
-                (true, true) => {
-                    self.multisamples_hdr_shading.draw(
-                        state,
-                        self.multisamples_count,
-                        bloom,
-                        bloom_blur_epoch,
-                        self.hdr_tone_mapping_type,
-                        &collected_entities,
-                        lighting,
-                        &self.gaussian_kernel_ubo,
-                    )?;
-                    self.multisamples_hdr_shading.draw_texture()?.unwrap()
-                }
-                (false, false) => {
-                    self.simple_shading
-                        .draw(state, &collected_entities, lighting)?;
-                    self.simple_shading.draw_texture()?.unwrap()
-                }
-                (false, true) => {
-                    self.multisamples_simple_shading.draw(
-                        state,
-                        self.multisamples_count,
-                        &collected_entities,
-                        lighting,
-                    )?;
-                    self.multisamples_simple_shading.draw_texture()?.unwrap()
-                }
-            };
-            self.composer.draw(state, [compose_textures])?;
-        };
+impl StandardPipelineContext {
+    /// Returns current pipeline shading.
+    pub fn pipeline_shading(&self) -> StandardPipelineShading {
+        self.pipeline_shading
+    }
 
-        Ok(())
+    /// Returns `true` if lighting enabled.
+    pub fn lighting_enabled(&self) -> bool {
+        self.lighting
     }
 
-    fn deferred_shading(&mut self, state: &mut FrameState, scene: &mut Scene) -> Result<(), Error> {
-        let lighting = self.lighting_enabled();
-        let multisamples = if self.multisamples_enabled() {
-            Some(self.multisamples_count())
-        } else {
-            None
-        };
+    /// Returns `true` if shadow maps are rendered under current pipeline shading.
+    pub fn shadow_active(&self) -> bool {
+        self.lighting && self.shadow && self.pipeline_shading != StandardPipelineShading::Picking
+    }
 
-        let collected_entities = self.entities_collector.collect_entities(state, scene);
-
-        // deferred shading on opaque entities
-        let (
-            positions_and_specular_shininess_texture,
-            normals_texture,
-            albedo_texture,
-            depth_stencil,
-        ) = self
-            .gbuffer
-            .collect(state, &collected_entities, multisamples)?;
-        self.deferred_shading.draw(
-            state,
-            positions_and_specular_shininess_texture,
-            normals_texture,
-            albedo_texture,
-            lighting,
-        )?;
+    /// Returns `true` if translucent entities are drawn by weighted blended order-independent transparency.
+    pub fn oit_active(&self, state: &FrameState) -> bool {
+        self.transparency == StandardTransparency::WeightedBlended
+            && state.capabilities().color_buffer_float_supported()
+    }
 
-        // then forward shading on translucent entities
-        self.deferred_translucent_shading.draw(
-            state,
-            &depth_stencil,
-            &collected_entities,
-            lighting,
-        )?;
+    /// Returns `true` if lights are assigned to clusters under current pipeline shading.
+    pub fn clustered_active(&self) -> bool {
+        self.lighting && self.clustered && self.pipeline_shading != StandardPipelineShading::Picking
+    }
 
-        let opaque_textures = self.deferred_shading.draw_texture()?.unwrap();
-        let translucent_texture = self.deferred_translucent_shading.draw_texture()?.unwrap();
-        self.composer
-            .draw(state, [opaque_textures, translucent_texture])?;
+    /// Returns entities collected in current frame.
+    pub fn collected_entities(&self) -> CollectedEntities {
+        self.entities_collector.last_collected_entities()
+    }
 
-        Ok(())
+    /// Returns Uniform Buffer Object `atoy_Universal`.
+    pub fn universal_ubo(&self) -> &Buffer {
+        &self.universal_ubo
     }
 
-    fn picking(&mut self, state: &mut FrameState, scene: &mut Scene) -> Result<(), Error> {
-        let collected_entities = self.entities_collector.collect_entities(state, scene);
-        self.picking.draw(state, &collected_entities)?;
+    /// Returns Uniform Buffer Object `atoy_Lights`.
+    pub fn lights_ubo(&self) -> &Buffer {
+        &self.lights_ubo
+    }
 
-        Ok(())
+    /// Returns Uniform Buffer Object `atoy_GaussianKernel`.
+    pub fn gaussian_kernel_ubo(&self) -> &Buffer {
+        &self.gaussian_kernel_ubo
     }
 }
 
@@ -664,30 +1051,44 @@ impl Pipeline for StandardPipeline {
     type Error = Error;
 
     fn execute(&mut self, state: &mut Self::State, scene: &mut Scene) -> Result<(), Self::Error> {
-        self.preparation
-            .prepare(state, scene, &mut self.universal_ubo, &mut self.lights_ubo)?;
-
-        match self.pipeline_shading {
-            StandardPipelineShading::Picking => {
-                self.picking(state, scene)?;
-            }
-            StandardPipelineShading::ForwardShading => {
-                self.forward_shading(state, scene)?;
-                self.cleanup
-                    .cleanup(&self.universal_ubo, &self.lights_ubo)?;
-            }
-            StandardPipelineShading::DeferredShading => {
-                if state.capabilities().color_buffer_float_supported() {
-                    self.deferred_shading(state, scene)?;
-                } else {
-                    // fallback to forward shading if color buffer float not supported
-                    self.forward_shading(state, scene)?;
-                    self.pipeline_shading = StandardPipelineShading::ForwardShading;
-                }
-                self.cleanup
-                    .cleanup(&self.universal_ubo, &self.lights_ubo)?;
-            }
-        };
+        let context = &mut self.context;
+        context.preparation.prepare(
+            state,
+            scene,
+            &mut context.universal_ubo,
+            &mut context.lights_ubo,
+        )?;
+
+        // fallback to forward shading if color buffer float not supported
+        if context.pipeline_shading == StandardPipelineShading::DeferredShading
+            && !state.capabilities().color_buffer_float_supported()
+        {
+            context.pipeline_shading = StandardPipelineShading::ForwardShading;
+            self.graph.set_dirty();
+        }
+
+        // entities are collected once and shared by all passes
+        context.entities_collector.collect_entities(state, scene);
+
+        self.graph.execute(state, scene, context)?;
+
+        if context.shadow_active() {
+            context.shadow_mapping.unbind_textures()?;
+            context
+                .shadows_ubo
+                .unbind_ubo(UBO_SHADOWS_UNIFORM_BLOCK_MOUNT_POINT)?;
+        }
+        if context.clustered_active() {
+            context.clustered_lighting.unbind_textures()?;
+            context
+                .clusters_ubo
+                .unbind_ubo(UBO_CLUSTERS_UNIFORM_BLOCK_MOUNT_POINT)?;
+        }
+        if context.pipeline_shading != StandardPipelineShading::Picking {
+            context
+                .cleanup
+                .cleanup(&context.universal_ubo, &context.lights_ubo)?;
+        }
 
         // flushes all commands
         state.gl().flush();
@@ -0,0 +1,545 @@
+use crate::{
+    renderer::webgl::{
+        error::Error, state::FrameState, texture::TextureUncompressedInternalFormat,
+    },
+    scene::Scene,
+};
+
+use super::{
+    graph::{GraphResources, PassBuilder, RenderGraph, RenderPass, TextureDescriptor},
+    postprocess::PostProcessGBuffer,
+    StandardPipelineContext, StandardPipelineShading,
+};
+
+/// Label of the pass rendering shadow maps.
+pub const SHADOW_PASS: &'static str = "shadow";
+/// Label of the pass assigning point lights and spot lights to clusters.
+pub const LIGHT_CLUSTERS_PASS: &'static str = "light_clusters";
+/// Label of the pass drawing entities by forward shading.
+pub const FORWARD_SHADING_PASS: &'static str = "forward_shading";
+/// Label of the pass collecting opaque entities into gbuffer.
+pub const GBUFFER_PASS: &'static str = "gbuffer";
+/// Label of the pass lighting gbuffer by deferred shading.
+pub const DEFERRED_SHADING_PASS: &'static str = "deferred_shading";
+/// Label of the pass drawing translucent entities by forward shading after deferred shading.
+pub const DEFERRED_TRANSLUCENT_SHADING_PASS: &'static str = "deferred_translucent_shading";
+/// Label of the pass composing shaded textures.
+pub const COMPOSE_PASS: &'static str = "compose";
+/// Label of the pass applying post processing effects on composed texture.
+pub const POST_PROCESS_PASS: &'static str = "post_process";
+/// Label of the pass printing post processed texture into canvas.
+pub const PRESENT_PASS: &'static str = "present";
+/// Label of the pass drawing entities for picking.
+pub const PICKING_PASS: &'static str = "picking";
+
+/// Shadow atlas and point light shadow maps, bound to their texture units after rendering.
+pub const SHADOW_MAPS: &'static str = "shadow_maps";
+/// Light data and light indices textures, bound to their texture units after assignment.
+pub const LIGHT_CLUSTERS: &'static str = "light_clusters";
+/// Shaded color of opaque entities, or of all entities under forward shading.
+pub const SCENE_COLOR: &'static str = "scene_color";
+/// Shaded color of translucent entities under deferred shading.
+pub const TRANSLUCENT_COLOR: &'static str = "translucent_color";
+/// Gbuffer texture of positions and specular shininess.
+pub const GBUFFER_POSITIONS_AND_SPECULAR_SHININESS: &'static str =
+    "gbuffer_positions_and_specular_shininess";
+/// Gbuffer texture of normals.
+pub const GBUFFER_NORMALS: &'static str = "gbuffer_normals";
+/// Gbuffer texture of albedo.
+pub const GBUFFER_ALBEDO: &'static str = "gbuffer_albedo";
+/// Gbuffer texture of metallic, roughness and ambient occlusion.
+pub const GBUFFER_METALLIC_ROUGHNESS_OCCLUSION: &'static str =
+    "gbuffer_metallic_roughness_occlusion";
+/// Gbuffer texture of emission.
+pub const GBUFFER_EMISSION: &'static str = "gbuffer_emission";
+/// Gbuffer depth stencil texture.
+pub const GBUFFER_DEPTH_STENCIL: &'static str = "gbuffer_depth_stencil";
+/// Transient texture composing all shaded textures.
+pub const COMPOSED: &'static str = "composed";
+/// Composed texture after post processing effects.
+pub const POST_PROCESSED: &'static str = "post_processed";
+/// Canvas framebuffer.
+pub const CANVAS: &'static str = "canvas";
+
+/// Creates render graph with resources and passes of [`StandardPipeline`](super::StandardPipeline).
+pub(super) fn standard_render_graph() -> RenderGraph<StandardPipelineContext> {
+    fn build() -> Result<RenderGraph<StandardPipelineContext>, Error> {
+        let mut graph = RenderGraph::new();
+
+        for name in [
+            SHADOW_MAPS,
+            LIGHT_CLUSTERS,
+            SCENE_COLOR,
+            TRANSLUCENT_COLOR,
+            GBUFFER_POSITIONS_AND_SPECULAR_SHININESS,
+            GBUFFER_NORMALS,
+            GBUFFER_ALBEDO,
+            GBUFFER_METALLIC_ROUGHNESS_OCCLUSION,
+            GBUFFER_EMISSION,
+            GBUFFER_DEPTH_STENCIL,
+            POST_PROCESSED,
+        ] {
+            graph.import_texture(name)?;
+        }
+        graph.create_texture(
+            COMPOSED,
+            TextureDescriptor::new(TextureUncompressedInternalFormat::RGBA8),
+        )?;
+        graph.create_output(CANVAS)?;
+
+        graph.add_pass(SHADOW_PASS, ShadowPass)?;
+        graph.add_pass(LIGHT_CLUSTERS_PASS, LightClustersPass)?;
+        graph.add_pass(FORWARD_SHADING_PASS, ForwardShadingPass)?;
+        graph.add_pass(GBUFFER_PASS, GBufferPass)?;
+        graph.add_pass(DEFERRED_SHADING_PASS, DeferredShadingPass)?;
+        graph.add_pass(
+            DEFERRED_TRANSLUCENT_SHADING_PASS,
+            DeferredTranslucentShadingPass,
+        )?;
+        graph.add_pass(COMPOSE_PASS, ComposePass)?;
+        graph.add_pass(POST_PROCESS_PASS, PostProcessPass)?;
+        graph.add_pass(PRESENT_PASS, PresentPass)?;
+        graph.add_pass(PICKING_PASS, PickingPass)?;
+
+        Ok(graph)
+    }
+
+    build().expect("built-in render graph should be valid")
+}
+
+struct ShadowPass;
+
+impl RenderPass<StandardPipelineContext> for ShadowPass {
+    fn setup(&self, context: &StandardPipelineContext, builder: &mut PassBuilder) {
+        if context.shadow_active() {
+            builder.write(SHADOW_MAPS);
+        }
+    }
+
+    fn execute(
+        &mut self,
+        state: &mut FrameState,
+        scene: &mut Scene,
+        context: &mut StandardPipelineContext,
+        _: &mut GraphResources<'_>,
+    ) -> Result<(), Error> {
+        context
+            .shadow_mapping
+            .render(state, scene, &mut context.shadows_ubo)?;
+        context.shadow_mapping.bind_textures()?;
+        Ok(())
+    }
+}
+
+struct LightClustersPass;
+
+impl RenderPass<StandardPipelineContext> for LightClustersPass {
+    fn setup(&self, context: &StandardPipelineContext, builder: &mut PassBuilder) {
+        if context.clustered_active() {
+            builder.write(LIGHT_CLUSTERS);
+        }
+    }
+
+    fn execute(
+        &mut self,
+        state: &mut FrameState,
+        scene: &mut Scene,
+        context: &mut StandardPipelineContext,
+        _: &mut GraphResources<'_>,
+    ) -> Result<(), Error> {
+        context
+            .clustered_lighting
+            .update(state, scene, &mut context.clusters_ubo)?;
+        context.clustered_lighting.bind_textures()?;
+        Ok(())
+    }
+}
+
+struct ForwardShadingPass;
+
+impl RenderPass<StandardPipelineContext> for ForwardShadingPass {
+    fn setup(&self, context: &StandardPipelineContext, builder: &mut PassBuilder) {
+        if context.pipeline_shading != StandardPipelineShading::ForwardShading {
+            return;
+        }
+        if context.shadow_active() {
+            builder.read(SHADOW_MAPS);
+        }
+        if context.clustered_active() {
+            builder.read(LIGHT_CLUSTERS);
+        }
+        builder.write(SCENE_COLOR);
+    }
+
+    fn execute(
+        &mut self,
+        state: &mut FrameState,
+        _: &mut Scene,
+        context: &mut StandardPipelineContext,
+        resources: &mut GraphResources<'_>,
+    ) -> Result<(), Error> {
+        let lighting = context.lighting;
+        let hdr = state.capabilities().color_buffer_float_supported() && context.hdr;
+        let bloom = context.bloom;
+        let bloom_blur_epoch = context.bloom_blur_epoch;
+        let multisamples = context.multisamples && context.multisamples_count != 0;
+        let shadow = context.shadow_active();
+        let clustered = context.clustered_active();
+        let oit = if context.oit_active(state) {
+            Some(&mut context.oit)
+        } else {
+            None
+        };
+
+        let collected_entities = context.entities_collector.last_collected_entities();
+        let texture = match (hdr, multisamples) {
+            (true, false) => {
+                context.hdr_shading.draw(
+                    state,
+                    bloom,
+                    bloom_blur_epoch,
+                    context.hdr_tone_mapping_type,
+                    &collected_entities,
+                    lighting,
+                    shadow,
+                    clustered,
+                    oit,
+                    &context.gaussian_kernel_ubo,
+                )?;
+                context.hdr_shading.draw_texture()?.unwrap()
+            }
+            (true, true) => {
+                context.multisamples_hdr_shading.draw(
+                    state,
+                    context.multisamples_count,
+                    bloom,
+                    bloom_blur_epoch,
+                    context.hdr_tone_mapping_type,
+                    &collected_entities,
+                    lighting,
+                    shadow,
+                    clustered,
+                    oit,
+                    &context.gaussian_kernel_ubo,
+                )?;
+                context.multisamples_hdr_shading.draw_texture()?.unwrap()
+            }
+            (false, false) => {
+                unsafe {
+                    context.simple_shading.draw(
+                        state,
+                        &collected_entities,
+                        lighting,
+                        shadow,
+                        clustered,
+                        oit,
+                    )?;
+                }
+                context.simple_shading.draw_texture()?.unwrap()
+            }
+            (false, true) => {
+                context.multisamples_simple_shading.draw(
+                    state,
+                    context.multisamples_count,
+                    &collected_entities,
+                    lighting,
+                    shadow,
+                    clustered,
+                    oit,
+                )?;
+                context.multisamples_simple_shading.draw_texture()?.unwrap()
+            }
+        };
+        resources.publish(SCENE_COLOR, texture.clone())?;
+
+        Ok(())
+    }
+}
+
+struct GBufferPass;
+
+impl RenderPass<StandardPipelineContext> for GBufferPass {
+    fn setup(&self, context: &StandardPipelineContext, builder: &mut PassBuilder) {
+        if context.pipeline_shading != StandardPipelineShading::DeferredShading {
+            return;
+        }
+        builder
+            .write(GBUFFER_POSITIONS_AND_SPECULAR_SHININESS)
+            .write(GBUFFER_NORMALS)
+            .write(GBUFFER_ALBEDO)
+            .write(GBUFFER_METALLIC_ROUGHNESS_OCCLUSION)
+            .write(GBUFFER_EMISSION)
+            .write(GBUFFER_DEPTH_STENCIL);
+    }
+
+    fn execute(
+        &mut self,
+        state: &mut FrameState,
+        _: &mut Scene,
+        context: &mut StandardPipelineContext,
+        resources: &mut GraphResources<'_>,
+    ) -> Result<(), Error> {
+        let multisamples = if context.multisamples {
+            Some(context.multisamples_count)
+        } else {
+            None
+        };
+
+        let collected_entities = context.entities_collector.last_collected_entities();
+        let (
+            positions_and_specular_shininess_texture,
+            normals_texture,
+            albedo_texture,
+            metallic_roughness_occlusion_texture,
+            emission_texture,
+            depth_stencil,
+        ) = context
+            .gbuffer
+            .collect(state, &collected_entities, multisamples, context.lighting)?;
+        resources.publish(
+            GBUFFER_POSITIONS_AND_SPECULAR_SHININESS,
+            positions_and_specular_shininess_texture.clone(),
+        )?;
+        resources.publish(GBUFFER_NORMALS, normals_texture.clone())?;
+        resources.publish(GBUFFER_ALBEDO, albedo_texture.clone())?;
+        resources.publish(
+            GBUFFER_METALLIC_ROUGHNESS_OCCLUSION,
+            metallic_roughness_occlusion_texture.clone(),
+        )?;
+        resources.publish(GBUFFER_EMISSION, emission_texture.clone())?;
+        resources.publish(GBUFFER_DEPTH_STENCIL, depth_stencil.clone())?;
+
+        Ok(())
+    }
+}
+
+struct DeferredShadingPass;
+
+impl RenderPass<StandardPipelineContext> for DeferredShadingPass {
+    fn setup(&self, context: &StandardPipelineContext, builder: &mut PassBuilder) {
+        if context.pipeline_shading != StandardPipelineShading::DeferredShading {
+            return;
+        }
+        if context.shadow_active() {
+            builder.read(SHADOW_MAPS);
+        }
+        if context.clustered_active() {
+            builder.read(LIGHT_CLUSTERS);
+        }
+        builder
+            .read(GBUFFER_POSITIONS_AND_SPECULAR_SHININESS)
+            .read(GBUFFER_NORMALS)
+            .read(GBUFFER_ALBEDO)
+            .read(GBUFFER_METALLIC_ROUGHNESS_OCCLUSION)
+            .read(GBUFFER_EMISSION)
+            .write(SCENE_COLOR);
+    }
+
+    fn execute(
+        &mut self,
+        state: &mut FrameState,
+        _: &mut Scene,
+        context: &mut StandardPipelineContext,
+        resources: &mut GraphResources<'_>,
+    ) -> Result<(), Error> {
+        let shadow = context.shadow_active();
+        let clustered = context.clustered_active();
+        context.deferred_shading.draw(
+            state,
+            resources.texture(GBUFFER_POSITIONS_AND_SPECULAR_SHININESS)?,
+            resources.texture(GBUFFER_NORMALS)?,
+            resources.texture(GBUFFER_ALBEDO)?,
+            resources.texture(GBUFFER_METALLIC_ROUGHNESS_OCCLUSION)?,
+            resources.texture(GBUFFER_EMISSION)?,
+            context.lighting,
+            shadow,
+            clustered,
+        )?;
+        let texture = context.deferred_shading.draw_texture()?.unwrap().clone();
+        resources.publish(SCENE_COLOR, texture)?;
+
+        Ok(())
+    }
+}
+
+struct DeferredTranslucentShadingPass;
+
+impl RenderPass<StandardPipelineContext> for DeferredTranslucentShadingPass {
+    fn setup(&self, context: &StandardPipelineContext, builder: &mut PassBuilder) {
+        if context.pipeline_shading != StandardPipelineShading::DeferredShading {
+            return;
+        }
+        if context.shadow_active() {
+            builder.read(SHADOW_MAPS);
+        }
+        if context.clustered_active() {
+            builder.read(LIGHT_CLUSTERS);
+        }
+        builder.read(GBUFFER_DEPTH_STENCIL).write(TRANSLUCENT_COLOR);
+    }
+
+    fn execute(
+        &mut self,
+        state: &mut FrameState,
+        _: &mut Scene,
+        context: &mut StandardPipelineContext,
+        resources: &mut GraphResources<'_>,
+    ) -> Result<(), Error> {
+        let shadow = context.shadow_active();
+        let clustered = context.clustered_active();
+        let oit = if context.oit_active(state) {
+            Some(&mut context.oit)
+        } else {
+            None
+        };
+        let collected_entities = context.entities_collector.last_collected_entities();
+        context.deferred_translucent_shading.draw(
+            state,
+            resources.texture(GBUFFER_DEPTH_STENCIL)?,
+            &collected_entities,
+            context.lighting,
+            shadow,
+            clustered,
+            oit,
+        )?;
+        let texture = context
+            .deferred_translucent_shading
+            .draw_texture()?
+            .unwrap()
+            .clone();
+        resources.publish(TRANSLUCENT_COLOR, texture)?;
+
+        Ok(())
+    }
+}
+
+struct ComposePass;
+
+impl RenderPass<StandardPipelineContext> for ComposePass {
+    fn setup(&self, context: &StandardPipelineContext, builder: &mut PassBuilder) {
+        match context.pipeline_shading {
+            StandardPipelineShading::ForwardShading => {
+                builder.read(SCENE_COLOR).write(COMPOSED);
+            }
+            StandardPipelineShading::DeferredShading => {
+                builder
+                    .read(SCENE_COLOR)
+                    .read(TRANSLUCENT_COLOR)
+                    .write(COMPOSED);
+            }
+            StandardPipelineShading::Picking => {}
+        }
+    }
+
+    fn execute(
+        &mut self,
+        state: &mut FrameState,
+        _: &mut Scene,
+        context: &mut StandardPipelineContext,
+        resources: &mut GraphResources<'_>,
+    ) -> Result<(), Error> {
+        let mut textures = vec![resources.texture(SCENE_COLOR)?];
+        if context.pipeline_shading == StandardPipelineShading::DeferredShading {
+            textures.push(resources.texture(TRANSLUCENT_COLOR)?);
+        }
+        context
+            .composer
+            .compose(state, resources.texture(COMPOSED)?, textures)?;
+
+        Ok(())
+    }
+}
+
+struct PostProcessPass;
+
+impl RenderPass<StandardPipelineContext> for PostProcessPass {
+    fn setup(&self, context: &StandardPipelineContext, builder: &mut PassBuilder) {
+        if context.pipeline_shading == StandardPipelineShading::Picking {
+            return;
+        }
+        builder.read(COMPOSED);
+        if context.pipeline_shading == StandardPipelineShading::DeferredShading
+            && context.post_process.requires_gbuffer()
+        {
+            builder
+                .read(GBUFFER_POSITIONS_AND_SPECULAR_SHININESS)
+                .read(GBUFFER_NORMALS);
+        }
+        builder.write(POST_PROCESSED);
+    }
+
+    fn execute(
+        &mut self,
+        state: &mut FrameState,
+        _: &mut Scene,
+        context: &mut StandardPipelineContext,
+        resources: &mut GraphResources<'_>,
+    ) -> Result<(), Error> {
+        // gbuffer is unavailable under forward shading, effects requiring it skip then
+        let gbuffer = if context.pipeline_shading == StandardPipelineShading::DeferredShading
+            && context.post_process.requires_gbuffer()
+        {
+            Some(PostProcessGBuffer {
+                positions_and_specular_shininess: resources
+                    .texture(GBUFFER_POSITIONS_AND_SPECULAR_SHININESS)?,
+                normals: resources.texture(GBUFFER_NORMALS)?,
+            })
+        } else {
+            None
+        };
+        let texture =
+            context
+                .post_process
+                .apply(state, resources.texture(COMPOSED)?, gbuffer.as_ref())?;
+        resources.publish(POST_PROCESSED, texture)?;
+
+        Ok(())
+    }
+}
+
+struct PresentPass;
+
+impl RenderPass<StandardPipelineContext> for PresentPass {
+    fn setup(&self, context: &StandardPipelineContext, builder: &mut PassBuilder) {
+        if context.pipeline_shading != StandardPipelineShading::Picking {
+            builder.read(POST_PROCESSED).write(CANVAS);
+        }
+    }
+
+    fn execute(
+        &mut self,
+        state: &mut FrameState,
+        _: &mut Scene,
+        context: &mut StandardPipelineContext,
+        resources: &mut GraphResources<'_>,
+    ) -> Result<(), Error> {
+        context
+            .composer
+            .print(state, resources.texture(POST_PROCESSED)?)?;
+
+        Ok(())
+    }
+}
+
+struct PickingPass;
+
+impl RenderPass<StandardPipelineContext> for PickingPass {
+    fn setup(&self, context: &StandardPipelineContext, builder: &mut PassBuilder) {
+        if context.pipeline_shading == StandardPipelineShading::Picking {
+            builder.side_effect();
+        }
+    }
+
+    fn execute(
+        &mut self,
+        state: &mut FrameState,
+        _: &mut Scene,
+        context: &mut StandardPipelineContext,
+        _: &mut GraphResources<'_>,
+    ) -> Result<(), Error> {
+        let collected_entities = context.entities_collector.last_collected_entities();
+        context.picking.draw(state, &collected_entities)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,193 @@
+use std::{any::Any, borrow::Cow};
+
+use web_sys::WebGlTexture;
+
+use crate::renderer::webgl::{
+    error::Error,
+    framebuffer::{Framebuffer, FramebufferAttachmentTarget, FramebufferTarget},
+    program::{Define, ProgramSource},
+    state::FrameState,
+    texture::{Texture, Texture3D, TextureUncompressedInternalFormat, TextureUnit},
+    uniform::{UniformBinding, UniformValue},
+};
+
+use super::{
+    color_framebuffer, PostProcessEffect, PostProcessGBuffer, INTENSITY_UNIFORM_BINDING,
+    TEXTURE_UNIFORM_BINDING,
+};
+
+const LUT_TEXTURE_UNIFORM_NAME: &'static str = "u_LutTexture";
+const LUT_TEXTURE_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(LUT_TEXTURE_UNIFORM_NAME));
+
+const GAMMA_UNIFORM_NAME: &'static str = "u_Gamma";
+const GAMMA_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(GAMMA_UNIFORM_NAME));
+
+pub const COLOR_GRADING_EFFECT_NAME: &'static str = "color_grading";
+pub const DEFAULT_COLOR_GRADING_ENABLED: bool = false;
+pub const DEFAULT_COLOR_GRADING_INTENSITY: f32 = 1.0;
+pub const DEFAULT_COLOR_GRADING_GAMMA: f32 = 2.2;
+
+/// Color grading by a 3d lookup table.
+///
+/// Lookup table is indexed by gamma encoded color and stores gamma encoded graded color,
+/// the same as lookup tables authored in image editors, and should be sampled with linear filtering.
+/// Color is encoded by gamma before looking up and decoded back to linear after.
+/// Skips if no lookup table is set.
+pub struct StandardColorGrading {
+    enabled: bool,
+    lut: Option<Texture<Texture3D>>,
+    intensity: f32,
+    gamma: f32,
+    framebuffer: Framebuffer,
+}
+
+impl StandardColorGrading {
+    pub fn new() -> Self {
+        Self {
+            enabled: DEFAULT_COLOR_GRADING_ENABLED,
+            lut: None,
+            intensity: DEFAULT_COLOR_GRADING_INTENSITY,
+            gamma: DEFAULT_COLOR_GRADING_GAMMA,
+            framebuffer: color_framebuffer(TextureUncompressedInternalFormat::RGBA8),
+        }
+    }
+
+    /// Returns lookup table.
+    pub fn lut(&self) -> Option<&Texture<Texture3D>> {
+        self.lut.as_ref()
+    }
+
+    /// Sets lookup table.
+    pub fn set_lut(&mut self, lut: Option<Texture<Texture3D>>) {
+        self.lut = lut;
+    }
+
+    /// Returns intensity in range `[0.0, 1.0]` mixing original color and graded color.
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    /// Sets intensity in range `[0.0, 1.0]` mixing original color and graded color.
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity.clamp(0.0, 1.0);
+    }
+
+    /// Returns gamma encoding color for looking up,
+    /// which should be the same as gamma of the lookup table.
+    pub fn gamma(&self) -> f32 {
+        self.gamma
+    }
+
+    /// Sets gamma encoding color for looking up,
+    /// which should be the same as gamma of the lookup table.
+    pub fn set_gamma(&mut self, gamma: f32) {
+        self.gamma = gamma;
+    }
+}
+
+impl PostProcessEffect for StandardColorGrading {
+    fn name(&self) -> &str {
+        COLOR_GRADING_EFFECT_NAME
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn apply(
+        &mut self,
+        state: &mut FrameState,
+        input: &WebGlTexture,
+        _: Option<&PostProcessGBuffer<'_>>,
+    ) -> Result<Option<&WebGlTexture>, Error> {
+        let Some(lut) = self.lut.as_ref() else {
+            return Ok(None);
+        };
+
+        let program = state
+            .program_store_mut()
+            .get_or_compile_program(&ColorGrading)?;
+        program.use_program()?;
+        program.bind_uniform_value_by_binding(
+            &TEXTURE_UNIFORM_BINDING,
+            &UniformValue::Integer1(0),
+            None,
+        )?;
+        program.bind_uniform_value_by_binding(
+            &LUT_TEXTURE_UNIFORM_BINDING,
+            &UniformValue::Integer1(1),
+            None,
+        )?;
+        program.bind_uniform_value_by_binding(
+            &INTENSITY_UNIFORM_BINDING,
+            &UniformValue::Float1(self.intensity),
+            None,
+        )?;
+        program.bind_uniform_value_by_binding(
+            &GAMMA_UNIFORM_BINDING,
+            &UniformValue::Float1(self.gamma),
+            None,
+        )?;
+
+        lut.init(state.gl())?;
+        lut.bind(TextureUnit::TEXTURE1)?;
+
+        self.framebuffer.init(state.gl())?;
+        self.framebuffer.bind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
+        state.do_computation([(input, TextureUnit::TEXTURE0)])?;
+        self.framebuffer
+            .unbind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
+
+        lut.unbind(TextureUnit::TEXTURE1)?;
+        program.unuse_program()?;
+
+        self.framebuffer
+            .texture(FramebufferAttachmentTarget::COLOR_ATTACHMENT0)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+struct ColorGrading;
+
+impl ProgramSource for ColorGrading {
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed("ColorGrading")
+    }
+
+    fn vertex_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("../shaders/computation.vert"))
+    }
+
+    fn fragment_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("../shaders/color_grading.frag"))
+    }
+
+    fn universal_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn vertex_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn fragment_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn snippet(&self, _: &str) -> Option<Cow<'_, str>> {
+        None
+    }
+}
@@ -0,0 +1,218 @@
+use std::{any::Any, borrow::Cow};
+
+use web_sys::WebGlTexture;
+
+use crate::{
+    pipeline::webgl::{
+        UBO_UNIVERSAL_UNIFORMS_BLOCK_BINDING, UBO_UNIVERSAL_UNIFORM_BLOCK_MOUNT_POINT,
+    },
+    renderer::webgl::{
+        error::Error,
+        framebuffer::{Framebuffer, FramebufferAttachmentTarget, FramebufferTarget},
+        program::{Define, ProgramSource},
+        state::FrameState,
+        texture::{TextureUncompressedInternalFormat, TextureUnit},
+        uniform::{UniformBinding, UniformValue},
+    },
+};
+
+use super::{
+    color_framebuffer, PostProcessEffect, PostProcessGBuffer, NORMALS_TEXTURE_UNIFORM_BINDING,
+    POSITIONS_TEXTURE_UNIFORM_BINDING, TEXTURE_UNIFORM_BINDING,
+};
+
+const FOCUS_DISTANCE_UNIFORM_NAME: &'static str = "u_FocusDistance";
+const FOCUS_DISTANCE_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(FOCUS_DISTANCE_UNIFORM_NAME));
+
+const FOCUS_RANGE_UNIFORM_NAME: &'static str = "u_FocusRange";
+const FOCUS_RANGE_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(FOCUS_RANGE_UNIFORM_NAME));
+
+const MAX_BLUR_RADIUS_UNIFORM_NAME: &'static str = "u_MaxBlurRadius";
+const MAX_BLUR_RADIUS_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(MAX_BLUR_RADIUS_UNIFORM_NAME));
+
+pub const DEPTH_OF_FIELD_EFFECT_NAME: &'static str = "depth_of_field";
+pub const DEFAULT_DEPTH_OF_FIELD_ENABLED: bool = false;
+pub const DEFAULT_DEPTH_OF_FIELD_FOCUS_DISTANCE: f32 = 10.0;
+pub const DEFAULT_DEPTH_OF_FIELD_FOCUS_RANGE: f32 = 10.0;
+pub const DEFAULT_DEPTH_OF_FIELD_MAX_BLUR_RADIUS: f32 = 8.0;
+
+/// Depth of field, blurring entities by their distances to the focus plane.
+/// Only applies under deferred shading.
+pub struct StandardDepthOfField {
+    enabled: bool,
+    focus_distance: f32,
+    focus_range: f32,
+    max_blur_radius: f32,
+    framebuffer: Framebuffer,
+}
+
+impl StandardDepthOfField {
+    pub fn new() -> Self {
+        Self {
+            enabled: DEFAULT_DEPTH_OF_FIELD_ENABLED,
+            focus_distance: DEFAULT_DEPTH_OF_FIELD_FOCUS_DISTANCE,
+            focus_range: DEFAULT_DEPTH_OF_FIELD_FOCUS_RANGE,
+            max_blur_radius: DEFAULT_DEPTH_OF_FIELD_MAX_BLUR_RADIUS,
+            framebuffer: color_framebuffer(TextureUncompressedInternalFormat::RGBA8),
+        }
+    }
+
+    /// Returns distance from camera to the focus plane.
+    pub fn focus_distance(&self) -> f32 {
+        self.focus_distance
+    }
+
+    /// Sets distance from camera to the focus plane.
+    pub fn set_focus_distance(&mut self, focus_distance: f32) {
+        self.focus_distance = focus_distance;
+    }
+
+    /// Returns distance from the focus plane where blur reaches its maximum.
+    pub fn focus_range(&self) -> f32 {
+        self.focus_range
+    }
+
+    /// Sets distance from the focus plane where blur reaches its maximum.
+    pub fn set_focus_range(&mut self, focus_range: f32) {
+        self.focus_range = focus_range;
+    }
+
+    /// Returns maximum blur radius in pixels.
+    pub fn max_blur_radius(&self) -> f32 {
+        self.max_blur_radius
+    }
+
+    /// Sets maximum blur radius in pixels.
+    pub fn set_max_blur_radius(&mut self, max_blur_radius: f32) {
+        self.max_blur_radius = max_blur_radius;
+    }
+}
+
+impl PostProcessEffect for StandardDepthOfField {
+    fn name(&self) -> &str {
+        DEPTH_OF_FIELD_EFFECT_NAME
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn requires_gbuffer(&self) -> bool {
+        true
+    }
+
+    fn apply(
+        &mut self,
+        state: &mut FrameState,
+        input: &WebGlTexture,
+        gbuffer: Option<&PostProcessGBuffer<'_>>,
+    ) -> Result<Option<&WebGlTexture>, Error> {
+        let Some(gbuffer) = gbuffer else {
+            return Ok(None);
+        };
+
+        let program = state
+            .program_store_mut()
+            .get_or_compile_program(&DepthOfField)?;
+        program.use_program()?;
+        program.mount_uniform_block_by_binding(
+            &UBO_UNIVERSAL_UNIFORMS_BLOCK_BINDING,
+            UBO_UNIVERSAL_UNIFORM_BLOCK_MOUNT_POINT,
+        )?;
+        program.bind_uniform_value_by_binding(
+            &TEXTURE_UNIFORM_BINDING,
+            &UniformValue::Integer1(0),
+            None,
+        )?;
+        program.bind_uniform_value_by_binding(
+            &POSITIONS_TEXTURE_UNIFORM_BINDING,
+            &UniformValue::Integer1(1),
+            None,
+        )?;
+        program.bind_uniform_value_by_binding(
+            &NORMALS_TEXTURE_UNIFORM_BINDING,
+            &UniformValue::Integer1(2),
+            None,
+        )?;
+        program.bind_uniform_value_by_binding(
+            &FOCUS_DISTANCE_UNIFORM_BINDING,
+            &UniformValue::Float1(self.focus_distance),
+            None,
+        )?;
+        program.bind_uniform_value_by_binding(
+            &FOCUS_RANGE_UNIFORM_BINDING,
+            &UniformValue::Float1(self.focus_range.max(f32::EPSILON)),
+            None,
+        )?;
+        program.bind_uniform_value_by_binding(
+            &MAX_BLUR_RADIUS_UNIFORM_BINDING,
+            &UniformValue::Float1(self.max_blur_radius),
+            None,
+        )?;
+
+        self.framebuffer.init(state.gl())?;
+        self.framebuffer.bind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
+        state.do_computation([
+            (input, TextureUnit::TEXTURE0),
+            (
+                gbuffer.positions_and_specular_shininess,
+                TextureUnit::TEXTURE1,
+            ),
+            (gbuffer.normals, TextureUnit::TEXTURE2),
+        ])?;
+        self.framebuffer
+            .unbind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
+
+        program.unuse_program()?;
+
+        self.framebuffer
+            .texture(FramebufferAttachmentTarget::COLOR_ATTACHMENT0)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+struct DepthOfField;
+
+impl ProgramSource for DepthOfField {
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed("DepthOfField")
+    }
+
+    fn vertex_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("../shaders/computation.vert"))
+    }
+
+    fn fragment_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("../shaders/depth_of_field.frag"))
+    }
+
+    fn universal_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn vertex_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn fragment_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn snippet(&self, _: &str) -> Option<Cow<'_, str>> {
+        None
+    }
+}
@@ -0,0 +1,112 @@
+use std::{any::Any, borrow::Cow};
+
+use web_sys::WebGlTexture;
+
+use crate::renderer::webgl::{
+    error::Error,
+    framebuffer::{Framebuffer, FramebufferAttachmentTarget, FramebufferTarget},
+    program::{Define, ProgramSource},
+    state::FrameState,
+    texture::{TextureUncompressedInternalFormat, TextureUnit},
+    uniform::UniformValue,
+};
+
+use super::{color_framebuffer, PostProcessEffect, PostProcessGBuffer, TEXTURE_UNIFORM_BINDING};
+
+pub const FXAA_EFFECT_NAME: &'static str = "fxaa";
+pub const DEFAULT_FXAA_ENABLED: bool = false;
+
+/// Fast approximate anti-aliasing, smoothing edges by luma without multisampling.
+pub struct StandardFxaa {
+    enabled: bool,
+    framebuffer: Framebuffer,
+}
+
+impl StandardFxaa {
+    pub fn new() -> Self {
+        Self {
+            enabled: DEFAULT_FXAA_ENABLED,
+            framebuffer: color_framebuffer(TextureUncompressedInternalFormat::RGBA8),
+        }
+    }
+}
+
+impl PostProcessEffect for StandardFxaa {
+    fn name(&self) -> &str {
+        FXAA_EFFECT_NAME
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn apply(
+        &mut self,
+        state: &mut FrameState,
+        input: &WebGlTexture,
+        _: Option<&PostProcessGBuffer<'_>>,
+    ) -> Result<Option<&WebGlTexture>, Error> {
+        let program = state.program_store_mut().get_or_compile_program(&Fxaa)?;
+        program.use_program()?;
+        program.bind_uniform_value_by_binding(
+            &TEXTURE_UNIFORM_BINDING,
+            &UniformValue::Integer1(0),
+            None,
+        )?;
+
+        self.framebuffer.init(state.gl())?;
+        self.framebuffer.bind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
+        state.do_computation([(input, TextureUnit::TEXTURE0)])?;
+        self.framebuffer
+            .unbind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
+
+        program.unuse_program()?;
+
+        self.framebuffer
+            .texture(FramebufferAttachmentTarget::COLOR_ATTACHMENT0)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+struct Fxaa;
+
+impl ProgramSource for Fxaa {
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed("Fxaa")
+    }
+
+    fn vertex_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("../shaders/computation.vert"))
+    }
+
+    fn fragment_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("../shaders/fxaa.frag"))
+    }
+
+    fn universal_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn vertex_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn fragment_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn snippet(&self, _: &str) -> Option<Cow<'_, str>> {
+        None
+    }
+}
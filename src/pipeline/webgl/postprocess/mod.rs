@@ -0,0 +1,319 @@
+pub mod color_grading;
+pub mod depth_of_field;
+pub mod fxaa;
+pub mod ssao;
+
+use std::{any::Any, borrow::Cow};
+
+use web_sys::WebGlTexture;
+
+use crate::renderer::webgl::{
+    error::Error,
+    framebuffer::{AttachmentSource, Framebuffer, FramebufferBuilder},
+    state::FrameState,
+    texture::TextureUncompressedInternalFormat,
+    uniform::UniformBinding,
+};
+
+use self::{
+    color_grading::StandardColorGrading, depth_of_field::StandardDepthOfField, fxaa::StandardFxaa,
+    ssao::StandardSsao,
+};
+
+const TEXTURE_UNIFORM_NAME: &'static str = "u_Texture";
+const TEXTURE_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(TEXTURE_UNIFORM_NAME));
+
+const POSITIONS_TEXTURE_UNIFORM_NAME: &'static str = "u_PositionsTexture";
+const POSITIONS_TEXTURE_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(POSITIONS_TEXTURE_UNIFORM_NAME));
+
+const NORMALS_TEXTURE_UNIFORM_NAME: &'static str = "u_NormalsTexture";
+const NORMALS_TEXTURE_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(NORMALS_TEXTURE_UNIFORM_NAME));
+
+const INTENSITY_UNIFORM_NAME: &'static str = "u_Intensity";
+const INTENSITY_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(INTENSITY_UNIFORM_NAME));
+
+/// Creates a framebuffer with a single color attachment following size of drawing buffer.
+fn color_framebuffer(internal_format: TextureUncompressedInternalFormat) -> Framebuffer {
+    FramebufferBuilder::new()
+        .set_color_attachment0(AttachmentSource::new_texture(internal_format))
+        .build()
+}
+
+/// Gbuffer textures available to post processing effects under deferred shading.
+pub struct PostProcessGBuffer<'a> {
+    /// Positions in world space and specular shininess.
+    pub positions_and_specular_shininess: &'a WebGlTexture,
+    /// Normals in world space, alpha component is `0.0` if no entity drawn.
+    pub normals: &'a WebGlTexture,
+}
+
+/// A post processing effect applied to the composed texture after tone mapping and bloom.
+pub trait PostProcessEffect {
+    /// Returns name of the effect, which should be unique in a [`PostProcessStack`].
+    fn name(&self) -> &str;
+
+    /// Returns `true` if the effect is enabled.
+    fn enabled(&self) -> bool;
+
+    /// Enables or disables the effect.
+    fn set_enabled(&mut self, enabled: bool);
+
+    /// Returns `true` if the effect samples gbuffer.
+    /// Gbuffer is only available under deferred shading.
+    fn requires_gbuffer(&self) -> bool {
+        false
+    }
+
+    /// Applies the effect on input texture and returns the output texture owned by the effect.
+    /// Returns `None` if the effect skips and input texture passes through,
+    /// such as an effect requiring gbuffer when gbuffer is unavailable.
+    fn apply(
+        &mut self,
+        state: &mut FrameState,
+        input: &WebGlTexture,
+        gbuffer: Option<&PostProcessGBuffer<'_>>,
+    ) -> Result<Option<&WebGlTexture>, Error>;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// A stack of [`PostProcessEffect`]s applied in order.
+///
+/// By default, the stack contains built-in effects in the following order, all disabled:
+/// [`StandardSsao`], [`StandardDepthOfField`], [`StandardColorGrading`] and [`StandardFxaa`].
+pub struct PostProcessStack {
+    effects: Vec<Box<dyn PostProcessEffect>>,
+}
+
+impl PostProcessStack {
+    /// Constructs a new post processing stack with built-in effects.
+    pub fn new() -> Self {
+        Self {
+            effects: vec![
+                Box::new(StandardSsao::new()),
+                Box::new(StandardDepthOfField::new()),
+                Box::new(StandardColorGrading::new()),
+                Box::new(StandardFxaa::new()),
+            ],
+        }
+    }
+
+    /// Returns effects in order.
+    pub fn effects(&self) -> &[Box<dyn PostProcessEffect>] {
+        &self.effects
+    }
+
+    /// Adds an effect at the end of the stack.
+    pub fn add_effect<E>(&mut self, effect: E)
+    where
+        E: PostProcessEffect + 'static,
+    {
+        self.effects.push(Box::new(effect));
+    }
+
+    /// Inserts an effect at specified index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert_effect<E>(&mut self, index: usize, effect: E)
+    where
+        E: PostProcessEffect + 'static,
+    {
+        self.effects.insert(index, Box::new(effect));
+    }
+
+    /// Removes an effect by name.
+    pub fn remove_effect(&mut self, name: &str) -> Option<Box<dyn PostProcessEffect>> {
+        let index = self
+            .effects
+            .iter()
+            .position(|effect| effect.name() == name)?;
+        Some(self.effects.remove(index))
+    }
+
+    /// Returns an effect by name.
+    pub fn effect(&self, name: &str) -> Option<&dyn PostProcessEffect> {
+        self.effects
+            .iter()
+            .find(|effect| effect.name() == name)
+            .map(|effect| effect.as_ref())
+    }
+
+    /// Returns a mutable effect by name.
+    pub fn effect_mut(&mut self, name: &str) -> Option<&mut (dyn PostProcessEffect + 'static)> {
+        self.effects
+            .iter_mut()
+            .find(|effect| effect.name() == name)
+            .map(|effect| effect.as_mut())
+    }
+
+    /// Returns the first effect of type `E`.
+    pub fn effect_by_type<E>(&self) -> Option<&E>
+    where
+        E: PostProcessEffect + 'static,
+    {
+        self.effects
+            .iter()
+            .find_map(|effect| effect.as_any().downcast_ref::<E>())
+    }
+
+    /// Returns the first mutable effect of type `E`.
+    pub fn effect_by_type_mut<E>(&mut self) -> Option<&mut E>
+    where
+        E: PostProcessEffect + 'static,
+    {
+        self.effects
+            .iter_mut()
+            .find_map(|effect| effect.as_any_mut().downcast_mut::<E>())
+    }
+
+    /// Returns `true` if any enabled effect samples gbuffer.
+    pub fn requires_gbuffer(&self) -> bool {
+        self.effects
+            .iter()
+            .any(|effect| effect.enabled() && effect.requires_gbuffer())
+    }
+
+    /// Applies enabled effects in order and returns the final texture.
+    /// Returns input texture if no effect applied.
+    pub fn apply(
+        &mut self,
+        state: &mut FrameState,
+        input: &WebGlTexture,
+        gbuffer: Option<&PostProcessGBuffer<'_>>,
+    ) -> Result<WebGlTexture, Error> {
+        let mut current = input.clone();
+        for effect in self.effects.iter_mut().filter(|effect| effect.enabled()) {
+            if let Some(output) = effect.apply(state, &current, gbuffer)? {
+                current = output.clone();
+            }
+        }
+        Ok(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        color_grading::{StandardColorGrading, COLOR_GRADING_EFFECT_NAME},
+        depth_of_field::DEPTH_OF_FIELD_EFFECT_NAME,
+        fxaa::{StandardFxaa, FXAA_EFFECT_NAME},
+        ssao::{StandardSsao, SSAO_EFFECT_NAME},
+        PostProcessStack,
+    };
+
+    fn names(stack: &PostProcessStack) -> Vec<&str> {
+        stack.effects().iter().map(|effect| effect.name()).collect()
+    }
+
+    #[test]
+    fn test_stack_order() {
+        let mut stack = PostProcessStack::new();
+        assert_eq!(
+            names(&stack),
+            vec![
+                SSAO_EFFECT_NAME,
+                DEPTH_OF_FIELD_EFFECT_NAME,
+                COLOR_GRADING_EFFECT_NAME,
+                FXAA_EFFECT_NAME
+            ]
+        );
+        assert!(stack.effects().iter().all(|effect| !effect.enabled()));
+
+        let fxaa = stack.remove_effect(FXAA_EFFECT_NAME).unwrap();
+        assert_eq!(fxaa.name(), FXAA_EFFECT_NAME);
+        assert!(stack.remove_effect(FXAA_EFFECT_NAME).is_none());
+        assert!(stack.effect(FXAA_EFFECT_NAME).is_none());
+        assert!(stack.effect_by_type::<StandardFxaa>().is_none());
+
+        stack.insert_effect(0, StandardFxaa::new());
+        stack.add_effect(StandardSsao::new());
+        assert_eq!(
+            names(&stack),
+            vec![
+                FXAA_EFFECT_NAME,
+                SSAO_EFFECT_NAME,
+                DEPTH_OF_FIELD_EFFECT_NAME,
+                COLOR_GRADING_EFFECT_NAME,
+                SSAO_EFFECT_NAME
+            ]
+        );
+    }
+
+    #[test]
+    fn test_effect_by_type() {
+        let mut stack = PostProcessStack::new();
+        stack
+            .effect_by_type_mut::<StandardColorGrading>()
+            .unwrap()
+            .set_intensity(0.5);
+        assert_eq!(
+            stack
+                .effect_by_type::<StandardColorGrading>()
+                .unwrap()
+                .intensity(),
+            0.5
+        );
+
+        stack
+            .effect_mut(SSAO_EFFECT_NAME)
+            .unwrap()
+            .set_enabled(true);
+        assert!(stack.effect_by_type::<StandardSsao>().unwrap().enabled());
+    }
+
+    #[test]
+    fn test_requires_gbuffer() {
+        let mut stack = PostProcessStack::new();
+        assert!(!stack.requires_gbuffer());
+
+        // effects not sampling gbuffer
+        stack
+            .effect_mut(FXAA_EFFECT_NAME)
+            .unwrap()
+            .set_enabled(true);
+        stack
+            .effect_mut(COLOR_GRADING_EFFECT_NAME)
+            .unwrap()
+            .set_enabled(true);
+        assert!(!stack.requires_gbuffer());
+
+        stack
+            .effect_mut(DEPTH_OF_FIELD_EFFECT_NAME)
+            .unwrap()
+            .set_enabled(true);
+        assert!(stack.requires_gbuffer());
+
+        stack.remove_effect(DEPTH_OF_FIELD_EFFECT_NAME).unwrap();
+        assert!(!stack.requires_gbuffer());
+        stack
+            .effect_mut(SSAO_EFFECT_NAME)
+            .unwrap()
+            .set_enabled(true);
+        assert!(stack.requires_gbuffer());
+    }
+
+    #[test]
+    fn test_intensity_clamped() {
+        let mut color_grading = StandardColorGrading::new();
+        color_grading.set_intensity(1.5);
+        assert_eq!(color_grading.intensity(), 1.0);
+        color_grading.set_intensity(-0.5);
+        assert_eq!(color_grading.intensity(), 0.0);
+        color_grading.set_intensity(0.25);
+        assert_eq!(color_grading.intensity(), 0.25);
+
+        let mut ssao = StandardSsao::new();
+        ssao.set_intensity(2.0);
+        assert_eq!(ssao.intensity(), 1.0);
+        ssao.set_intensity(-1.0);
+        assert_eq!(ssao.intensity(), 0.0);
+    }
+}
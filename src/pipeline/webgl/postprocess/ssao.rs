@@ -0,0 +1,297 @@
+use std::{any::Any, borrow::Cow};
+
+use web_sys::WebGlTexture;
+
+use crate::{
+    pipeline::webgl::{
+        UBO_UNIVERSAL_UNIFORMS_BLOCK_BINDING, UBO_UNIVERSAL_UNIFORM_BLOCK_MOUNT_POINT,
+    },
+    renderer::webgl::{
+        error::Error,
+        framebuffer::{Framebuffer, FramebufferAttachmentTarget, FramebufferTarget},
+        program::{Define, ProgramSource},
+        state::FrameState,
+        texture::{TextureUncompressedInternalFormat, TextureUnit},
+        uniform::{UniformBinding, UniformValue},
+    },
+};
+
+use super::{
+    color_framebuffer, PostProcessEffect, PostProcessGBuffer, INTENSITY_UNIFORM_BINDING,
+    NORMALS_TEXTURE_UNIFORM_BINDING, POSITIONS_TEXTURE_UNIFORM_BINDING, TEXTURE_UNIFORM_BINDING,
+};
+
+const RADIUS_UNIFORM_NAME: &'static str = "u_Radius";
+const RADIUS_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(RADIUS_UNIFORM_NAME));
+
+const BIAS_UNIFORM_NAME: &'static str = "u_Bias";
+const BIAS_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(BIAS_UNIFORM_NAME));
+
+const OCCLUSION_TEXTURE_UNIFORM_NAME: &'static str = "u_OcclusionTexture";
+const OCCLUSION_TEXTURE_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(OCCLUSION_TEXTURE_UNIFORM_NAME));
+
+pub const SSAO_EFFECT_NAME: &'static str = "ssao";
+pub const DEFAULT_SSAO_ENABLED: bool = false;
+pub const DEFAULT_SSAO_RADIUS: f32 = 0.5;
+pub const DEFAULT_SSAO_BIAS: f32 = 0.025;
+pub const DEFAULT_SSAO_INTENSITY: f32 = 1.0;
+
+/// Screen space ambient occlusion, darkening creases and corners sampled from gbuffer.
+/// Only applies under deferred shading.
+pub struct StandardSsao {
+    enabled: bool,
+    radius: f32,
+    bias: f32,
+    intensity: f32,
+    occlusion_framebuffer: Framebuffer,
+    framebuffer: Framebuffer,
+}
+
+impl StandardSsao {
+    pub fn new() -> Self {
+        Self {
+            enabled: DEFAULT_SSAO_ENABLED,
+            radius: DEFAULT_SSAO_RADIUS,
+            bias: DEFAULT_SSAO_BIAS,
+            intensity: DEFAULT_SSAO_INTENSITY,
+            occlusion_framebuffer: color_framebuffer(TextureUncompressedInternalFormat::R8),
+            framebuffer: color_framebuffer(TextureUncompressedInternalFormat::RGBA8),
+        }
+    }
+
+    /// Returns sampling radius in world space.
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    /// Sets sampling radius in world space.
+    pub fn set_radius(&mut self, radius: f32) {
+        self.radius = radius;
+    }
+
+    /// Returns depth bias preventing self occlusion.
+    pub fn bias(&self) -> f32 {
+        self.bias
+    }
+
+    /// Sets depth bias preventing self occlusion.
+    pub fn set_bias(&mut self, bias: f32) {
+        self.bias = bias;
+    }
+
+    /// Returns intensity of occlusion in range `[0.0, 1.0]`.
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    /// Sets intensity of occlusion in range `[0.0, 1.0]`.
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity.clamp(0.0, 1.0);
+    }
+
+    fn draw_occlusion(
+        &mut self,
+        state: &mut FrameState,
+        gbuffer: &PostProcessGBuffer<'_>,
+    ) -> Result<(), Error> {
+        let program = state
+            .program_store_mut()
+            .get_or_compile_program(&SsaoOcclusion)?;
+        program.use_program()?;
+        program.mount_uniform_block_by_binding(
+            &UBO_UNIVERSAL_UNIFORMS_BLOCK_BINDING,
+            UBO_UNIVERSAL_UNIFORM_BLOCK_MOUNT_POINT,
+        )?;
+        program.bind_uniform_value_by_binding(
+            &POSITIONS_TEXTURE_UNIFORM_BINDING,
+            &UniformValue::Integer1(0),
+            None,
+        )?;
+        program.bind_uniform_value_by_binding(
+            &NORMALS_TEXTURE_UNIFORM_BINDING,
+            &UniformValue::Integer1(1),
+            None,
+        )?;
+        program.bind_uniform_value_by_binding(
+            &RADIUS_UNIFORM_BINDING,
+            &UniformValue::Float1(self.radius),
+            None,
+        )?;
+        program.bind_uniform_value_by_binding(
+            &BIAS_UNIFORM_BINDING,
+            &UniformValue::Float1(self.bias),
+            None,
+        )?;
+
+        self.occlusion_framebuffer.init(state.gl())?;
+        self.occlusion_framebuffer
+            .bind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
+        state.do_computation([
+            (
+                gbuffer.positions_and_specular_shininess,
+                TextureUnit::TEXTURE0,
+            ),
+            (gbuffer.normals, TextureUnit::TEXTURE1),
+        ])?;
+        self.occlusion_framebuffer
+            .unbind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
+
+        program.unuse_program()?;
+
+        Ok(())
+    }
+
+    fn blend_occlusion(
+        &mut self,
+        state: &mut FrameState,
+        input: &WebGlTexture,
+    ) -> Result<(), Error> {
+        let occlusion_texture = self
+            .occlusion_framebuffer
+            .texture(FramebufferAttachmentTarget::COLOR_ATTACHMENT0)?
+            .unwrap();
+
+        let program = state
+            .program_store_mut()
+            .get_or_compile_program(&SsaoBlend)?;
+        program.use_program()?;
+        program.bind_uniform_value_by_binding(
+            &TEXTURE_UNIFORM_BINDING,
+            &UniformValue::Integer1(0),
+            None,
+        )?;
+        program.bind_uniform_value_by_binding(
+            &OCCLUSION_TEXTURE_UNIFORM_BINDING,
+            &UniformValue::Integer1(1),
+            None,
+        )?;
+        program.bind_uniform_value_by_binding(
+            &INTENSITY_UNIFORM_BINDING,
+            &UniformValue::Float1(self.intensity),
+            None,
+        )?;
+
+        self.framebuffer.init(state.gl())?;
+        self.framebuffer.bind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
+        state.do_computation([
+            (input, TextureUnit::TEXTURE0),
+            (occlusion_texture, TextureUnit::TEXTURE1),
+        ])?;
+        self.framebuffer
+            .unbind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
+
+        program.unuse_program()?;
+
+        Ok(())
+    }
+}
+
+impl PostProcessEffect for StandardSsao {
+    fn name(&self) -> &str {
+        SSAO_EFFECT_NAME
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn requires_gbuffer(&self) -> bool {
+        true
+    }
+
+    fn apply(
+        &mut self,
+        state: &mut FrameState,
+        input: &WebGlTexture,
+        gbuffer: Option<&PostProcessGBuffer<'_>>,
+    ) -> Result<Option<&WebGlTexture>, Error> {
+        let Some(gbuffer) = gbuffer else {
+            return Ok(None);
+        };
+
+        self.draw_occlusion(state, gbuffer)?;
+        self.blend_occlusion(state, input)?;
+
+        self.framebuffer
+            .texture(FramebufferAttachmentTarget::COLOR_ATTACHMENT0)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+struct SsaoOcclusion;
+
+impl ProgramSource for SsaoOcclusion {
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed("SsaoOcclusion")
+    }
+
+    fn vertex_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("../shaders/computation.vert"))
+    }
+
+    fn fragment_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("../shaders/ssao.frag"))
+    }
+
+    fn universal_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn vertex_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn fragment_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn snippet(&self, _: &str) -> Option<Cow<'_, str>> {
+        None
+    }
+}
+
+struct SsaoBlend;
+
+impl ProgramSource for SsaoBlend {
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed("SsaoBlend")
+    }
+
+    fn vertex_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("../shaders/computation.vert"))
+    }
+
+    fn fragment_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("../shaders/ssao_blend.frag"))
+    }
+
+    fn universal_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn vertex_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn fragment_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn snippet(&self, _: &str) -> Option<Cow<'_, str>> {
+        None
+    }
+}
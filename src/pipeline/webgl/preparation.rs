@@ -145,13 +145,15 @@ impl StandardPreparation {
             self.last_ambient_light = scene.ambient_light().clone();
         }
 
-        // uses for sending empty data
+        // uses for sending empty data.
+        // lights beyond maximum count are left out, they are applied by clustered lighting only
         macro_rules! update_lights {
             ($(($last:ident, $lights:ident, $count:tt, $len:tt, $offset:tt))+) => {
                 $(
                     match &mut self.$last {
                         Some(last_lights) => {
                             let lights = scene.$lights();
+                            let lights = &lights[..lights.len().min($count)];
 
                             for (index, light) in lights.into_iter().enumerate() {
                                 let last = last_lights.get(index);
@@ -179,6 +181,7 @@ impl StandardPreparation {
                         }
                         None => {
                             let lights = scene.$lights();
+                            let lights = &lights[..lights.len().min($count)];
                             let mut last_lights = Vec::with_capacity(lights.len());
 
                             // clears first
@@ -256,7 +259,9 @@ impl DirectionalLight {
         ubo[0..3].copy_from_slice(&self.direction().to_f32_array());
         ubo[3] = if self.enabled() { 1.0 } else { 0.0 };
         ubo[4..7].copy_from_slice(&self.ambient().to_f32_array());
+        ubo[7] = self.shadow_depth_bias();
         ubo[8..11].copy_from_slice(&self.diffuse().to_f32_array());
+        ubo[11] = self.shadow_slope_bias();
         ubo[12..15].copy_from_slice(&self.specular().to_f32_array());
 
         unsafe {
@@ -269,12 +274,14 @@ impl DirectionalLight {
 }
 
 impl PointLight {
-    fn ubo(&self) -> [u8; UBO_LIGHTS_POINT_LIGHT_BYTE_LENGTH] {
+    pub(super) fn ubo(&self) -> [u8; UBO_LIGHTS_POINT_LIGHT_BYTE_LENGTH] {
         let mut ubo = [0.0f32; UBO_LIGHTS_POINT_LIGHT_BYTE_LENGTH / 4];
         ubo[0..3].copy_from_slice(&self.position().to_f32_array());
         ubo[3] = if self.enabled() { 1.0 } else { 0.0 };
         ubo[4..7].copy_from_slice(&self.ambient().to_f32_array());
+        ubo[7] = self.shadow_depth_bias();
         ubo[8..11].copy_from_slice(&self.diffuse().to_f32_array());
+        ubo[11] = self.shadow_slope_bias();
         ubo[12..15].copy_from_slice(&self.specular().to_f32_array());
 
         unsafe {
@@ -313,22 +320,23 @@ impl AreaLight {
 }
 
 impl SpotLight {
-    fn ubo(&self) -> [u8; UBO_LIGHTS_AREA_LIGHT_BYTE_LENGTH] {
-        let mut ubo = [0.0f32; UBO_LIGHTS_AREA_LIGHT_BYTE_LENGTH / 4];
+    pub(super) fn ubo(&self) -> [u8; UBO_LIGHTS_SPOT_LIGHT_BYTE_LENGTH] {
+        let mut ubo = [0.0f32; UBO_LIGHTS_SPOT_LIGHT_BYTE_LENGTH / 4];
         ubo[0..3].copy_from_slice(&self.direction().to_f32_array());
         ubo[3] = if self.enabled() { 1.0 } else { 0.0 };
         ubo[4..7].copy_from_slice(&self.position().to_f32_array());
-        ubo[7] = 0.0;
+        ubo[7] = self.shadow_depth_bias();
         ubo[8..11].copy_from_slice(&self.ambient().to_f32_array());
         ubo[11] = self.inner_cutoff().cos();
         ubo[12..15].copy_from_slice(&self.diffuse().to_f32_array());
         ubo[15] = self.outer_cutoff().cos();
         ubo[16..19].copy_from_slice(&self.specular().to_f32_array());
+        ubo[19] = self.shadow_slope_bias();
 
         unsafe {
             std::mem::transmute::<
-                [f32; UBO_LIGHTS_AREA_LIGHT_BYTE_LENGTH / 4],
-                [u8; UBO_LIGHTS_AREA_LIGHT_BYTE_LENGTH],
+                [f32; UBO_LIGHTS_SPOT_LIGHT_BYTE_LENGTH / 4],
+                [u8; UBO_LIGHTS_SPOT_LIGHT_BYTE_LENGTH],
             >(ubo)
         }
     }
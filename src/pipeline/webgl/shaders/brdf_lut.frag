@@ -0,0 +1,91 @@
+#version 300 es
+
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+precision highp int;
+#else
+precision mediump float;
+precision mediump int;
+#endif
+
+#define PI 3.1415926535897932384626433832795f
+#define SAMPLE_COUNT 1024u
+
+in vec2 v_TexCoord;
+
+out vec4 o_Color;
+
+/**
+ * Returns the `i`th point of `n` points in Hammersley sequence.
+ */
+vec2 hammersley(uint i, uint n) {
+    uint bits = i;
+    bits = (bits << 16u) | (bits >> 16u);
+    bits = ((bits & 0x55555555u) << 1u) | ((bits & 0xAAAAAAAAu) >> 1u);
+    bits = ((bits & 0x33333333u) << 2u) | ((bits & 0xCCCCCCCCu) >> 2u);
+    bits = ((bits & 0x0F0F0F0Fu) << 4u) | ((bits & 0xF0F0F0F0u) >> 4u);
+    bits = ((bits & 0x00FF00FFu) << 8u) | ((bits & 0xFF00FF00u) >> 8u);
+    float radical_inverse = float(bits) * 2.3283064365386963e-10f;
+    return vec2(float(i) / float(n), radical_inverse);
+}
+
+/**
+ * Importance samples a halfway vector around `normal` using GGX distribution.
+ */
+vec3 importance_sample_ggx(vec2 xi, vec3 normal, float roughness) {
+    float a = roughness * roughness;
+
+    float phi = 2.0f * PI * xi.x;
+    float cos_theta = sqrt((1.0f - xi.y) / (1.0f + (a * a - 1.0f) * xi.y));
+    float sin_theta = sqrt(1.0f - cos_theta * cos_theta);
+    vec3 h = vec3(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+
+    vec3 up = abs(normal.z) < 0.999f ? vec3(0.0f, 0.0f, 1.0f) : vec3(1.0f, 0.0f, 0.0f);
+    vec3 tangent = normalize(cross(up, normal));
+    vec3 bitangent = cross(normal, tangent);
+    return normalize(tangent * h.x + bitangent * h.y + normal * h.z);
+}
+
+/**
+ * Schlick-GGX geometry function for image based lighting.
+ */
+float geometry_schlick_ggx(float n_dot_v, float roughness) {
+    float k = (roughness * roughness) / 2.0f;
+    return n_dot_v / (n_dot_v * (1.0f - k) + k);
+}
+
+/**
+ * Integrates BRDF scale and bias to F0 by `n_dot_v` and `roughness`.
+ */
+vec2 integrate_brdf(float n_dot_v, float roughness) {
+    vec3 v = vec3(sqrt(1.0f - n_dot_v * n_dot_v), 0.0f, n_dot_v);
+    vec3 n = vec3(0.0f, 0.0f, 1.0f);
+
+    float scale = 0.0f;
+    float bias = 0.0f;
+    for(uint i = 0u; i < SAMPLE_COUNT; i++) {
+        vec2 xi = hammersley(i, SAMPLE_COUNT);
+        vec3 h = importance_sample_ggx(xi, n, roughness);
+        vec3 l = normalize(2.0f * dot(v, h) * h - v);
+
+        float n_dot_l = max(l.z, 0.0f);
+        float n_dot_h = max(h.z, 0.0f);
+        float v_dot_h = max(dot(v, h), 0.0f);
+        if(n_dot_l > 0.0f) {
+            float g = geometry_schlick_ggx(n_dot_v, roughness) * geometry_schlick_ggx(n_dot_l, roughness);
+            float g_vis = (g * v_dot_h) / (n_dot_h * n_dot_v);
+            float fc = pow(1.0f - v_dot_h, 5.0f);
+
+            scale += (1.0f - fc) * g_vis;
+            bias += fc * g_vis;
+        }
+    }
+
+    return vec2(scale, bias) / float(SAMPLE_COUNT);
+}
+
+void main() {
+    // avoids dividing by zero when n_dot_v is 0.0
+    float n_dot_v = max(v_TexCoord.x, 0.001f);
+    o_Color = vec4(integrate_brdf(n_dot_v, v_TexCoord.y), 0.0f, 1.0f);
+}
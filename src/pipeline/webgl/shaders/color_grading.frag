@@ -0,0 +1,38 @@
+#version 300 es
+
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+precision highp sampler2D;
+precision highp sampler3D;
+#else
+precision mediump float;
+precision mediump sampler2D;
+precision mediump sampler3D;
+#endif
+
+uniform sampler2D u_Texture;
+uniform sampler3D u_LutTexture;
+uniform float u_Intensity;
+uniform float u_Gamma;
+
+out vec4 o_Color;
+
+void main() {
+    vec4 color = texelFetch(u_Texture, ivec2(gl_FragCoord.xy), 0);
+    if(color.a == 0.0f) {
+        o_Color = color;
+        return;
+    }
+
+    // composed color is premultiplied by alpha
+    vec3 rgb = clamp(color.rgb / color.a, 0.0f, 1.0f);
+    // lut is indexed by gamma encoded color
+    vec3 encoded = pow(rgb, vec3(1.0f / u_Gamma));
+    // scales and offsets coordinate to sample between texel centers of the lut
+    float size = float(textureSize(u_LutTexture, 0).x);
+    vec3 coordinate = encoded * ((size - 1.0f) / size) + 0.5f / size;
+    // decodes graded color back to linear
+    vec3 graded = pow(texture(u_LutTexture, coordinate).rgb, vec3(u_Gamma));
+
+    o_Color = vec4(mix(rgb, graded, u_Intensity) * color.a, color.a);
+}
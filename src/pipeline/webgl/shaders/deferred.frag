@@ -12,6 +12,7 @@ precision mediump sampler2D;
 
 #include Defines
 #include UniversalUniforms
+#include Pbr
 
 #ifdef USE_LIGHTING
 #include Lighting
@@ -22,6 +23,8 @@ in vec2 v_TexCoord;
 uniform sampler2D u_PositionsAndSpecularShininessTexture;
 uniform sampler2D u_NormalsTexture;
 uniform sampler2D u_AlbedoTexture;
+uniform sampler2D u_MetallicRoughnessOcclusionTexture;
+uniform sampler2D u_EmissionTexture;
 
 out vec4 o_Color;
 
@@ -37,12 +40,24 @@ void main() {
     }
     vec3 albedo = albedo_and_existence.xyz;
 
-    #ifdef USE_LIGHTING
-    atoy_LightingMaterial lighting_material = atoy_LightingMaterial(position, normal, albedo, specular_shininess);
-    vec3 color = atoy_lighting(u_CameraPosition, lighting_material);
-    #else
-    vec3 color = albedo;
-    #endif
+    vec3 color;
+    vec4 metallic_roughness_occlusion_and_pbr = texture(u_MetallicRoughnessOcclusionTexture, v_TexCoord);
+    if(metallic_roughness_occlusion_and_pbr.a == 1.0f) {
+        vec3 emission = texture(u_EmissionTexture, v_TexCoord).rgb;
+        #ifdef USE_LIGHTING
+        atoy_PbrFragment fragment = atoy_PbrFragment(position, normal, albedo, metallic_roughness_occlusion_and_pbr.r, metallic_roughness_occlusion_and_pbr.g, metallic_roughness_occlusion_and_pbr.b, emission, 1.0f);
+        color = atoy_pbr_lighting(u_CameraPosition, fragment);
+        #else
+        color = albedo + emission;
+        #endif
+    } else {
+        #ifdef USE_LIGHTING
+        atoy_LightingMaterial lighting_material = atoy_LightingMaterial(position, normal, albedo, specular_shininess);
+        color = atoy_lighting(u_CameraPosition, lighting_material);
+        #else
+        color = albedo;
+        #endif
+    }
 
     o_Color = vec4(color, 1.0f);
 }
\ No newline at end of file
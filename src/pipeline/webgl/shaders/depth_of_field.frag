@@ -0,0 +1,66 @@
+#version 300 es
+
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+precision highp int;
+precision highp sampler2D;
+#else
+precision mediump float;
+precision mediump int;
+precision mediump sampler2D;
+#endif
+
+#include UniversalUniforms
+
+#define SAMPLES 32
+#define GOLDEN_ANGLE 2.39996323f
+
+uniform sampler2D u_Texture;
+uniform sampler2D u_PositionsTexture;
+uniform sampler2D u_NormalsTexture;
+uniform float u_FocusDistance;
+uniform float u_FocusRange;
+uniform float u_MaxBlurRadius;
+
+out vec4 o_Color;
+
+// returns circle of confusion in range [0.0, 1.0], background is always out of focus
+float circle_of_confusion(ivec2 pixel) {
+    if(texelFetch(u_NormalsTexture, pixel, 0).a == 0.0f) {
+        return 1.0f;
+    }
+    vec3 position = texelFetch(u_PositionsTexture, pixel, 0).xyz;
+    float distance = -(u_ViewMatrix * vec4(position, 1.0f)).z;
+    return clamp(abs(distance - u_FocusDistance) / u_FocusRange, 0.0f, 1.0f);
+}
+
+void main() {
+    ivec2 pixel = ivec2(gl_FragCoord.xy);
+    ivec2 max_pixel = textureSize(u_Texture, 0) - 1;
+
+    vec4 center = texelFetch(u_Texture, pixel, 0);
+    float radius = circle_of_confusion(pixel) * u_MaxBlurRadius;
+    if(radius < 0.5f) {
+        o_Color = center;
+        return;
+    }
+
+    // gathers samples in a disc by golden angle spiral
+    vec4 color = center;
+    float weight = 1.0f;
+    for(int i = 1; i < SAMPLES; i++) {
+        float fi = float(i);
+        float r = radius * sqrt(fi / float(SAMPLES));
+        float theta = fi * GOLDEN_ANGLE;
+        ivec2 sample_pixel = clamp(pixel + ivec2(round(vec2(cos(theta), sin(theta)) * r)), ivec2(0), max_pixel);
+
+        // a sample contributes only if its own blur reaches current pixel,
+        // preventing sharp entities in focus from bleeding
+        float sample_radius = circle_of_confusion(sample_pixel) * u_MaxBlurRadius;
+        float w = clamp(sample_radius / max(r, 1.0f), 0.0f, 1.0f);
+        color += texelFetch(u_Texture, sample_pixel, 0) * w;
+        weight += w;
+    }
+
+    o_Color = color / weight;
+}
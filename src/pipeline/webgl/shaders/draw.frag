@@ -5,17 +5,23 @@ precision highp float;
 precision highp int;
 precision highp sampler2D;
 precision highp sampler2DArray;
+precision highp samplerCube;
 #else
 precision mediump float;
 precision mediump int;
 precision mediump sampler2D;
 precision mediump sampler2DArray;
+precision mediump samplerCube;
 #endif
 
 #include Defines
 #include UniversalUniforms
 #include FragmentConstants
 
+#ifdef USE_PBR
+#include Pbr
+#endif
+
 layout(location = 0) out vec4 o_Color;
 
 #ifdef USE_BLOOM
@@ -23,6 +29,16 @@ uniform vec3 u_BloomThreshold;
 layout(location = 1) out vec4 o_BloomColor;
 #endif
 
+#ifdef USE_OIT
+// weighted blended order-independent transparency,
+// `o_Color` accumulates weighted colors in `rgb` and revealage in `a`, `o_Weight` accumulates weighted transparencies in `r`
+layout(location = 1) out vec4 o_Weight;
+#endif
+
+#ifdef USE_INSTANCING
+in vec4 v_InstanceColor;
+#endif
+
 #include FragmentProcess
 
 #ifdef USE_LIGHTING
@@ -30,16 +46,42 @@ layout(location = 1) out vec4 o_BloomColor;
 #endif
 
 void main() {
-    atoy_Fragment fragment = fragment_process();
-
     vec3 color;
-    #ifdef USE_LIGHTING
+    float transparency;
+    #ifdef USE_PBR
+    atoy_PbrFragment fragment = fragment_process();
+        #ifdef USE_INSTANCING
+    fragment.base_color *= v_InstanceColor.rgb;
+    fragment.transparency *= v_InstanceColor.a;
+        #endif
+        #ifdef USE_LIGHTING
+    color = atoy_pbr_lighting(u_CameraPosition, fragment);
+        #else
+    color = fragment.base_color + fragment.emission;
+        #endif
+    transparency = fragment.transparency;
+    #else
+    atoy_Fragment fragment = fragment_process();
+        #ifdef USE_INSTANCING
+    fragment.albedo *= v_InstanceColor.rgb;
+    fragment.transparency *= v_InstanceColor.a;
+        #endif
+        #ifdef USE_LIGHTING
     atoy_LightingMaterial lighting_material = atoy_LightingMaterial(fragment.position, fragment.normal, fragment.albedo, fragment.shininess);
     color = atoy_lighting(u_CameraPosition, lighting_material);
-    #else
+        #else
     color = fragment.albedo;
+        #endif
+    transparency = fragment.transparency;
+    #endif
+    #ifdef USE_OIT
+    // weight function from McGuire and Bavoil, Weighted Blended Order-Independent Transparency
+    float weight = clamp(pow(min(1.0f, transparency * 10.0f) + 0.01f, 3.0f) * 1e8f * pow(1.0f - gl_FragCoord.z * 0.9f, 3.0f), 1e-2f, 3e3f);
+    o_Color = vec4(color * transparency * weight, transparency);
+    o_Weight = vec4(transparency * weight, 0.0f, 0.0f, 0.0f);
+    #else
+    o_Color = vec4(color, transparency);
     #endif
-    o_Color = vec4(color, fragment.transparency);
 
     #ifdef USE_BLOOM
     if(dot(color, u_BloomThreshold) > 1.0f) {
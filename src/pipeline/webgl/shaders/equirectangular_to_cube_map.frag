@@ -0,0 +1,46 @@
+#version 300 es
+
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+precision highp sampler2D;
+#else
+precision mediump float;
+precision mediump sampler2D;
+#endif
+
+#define PI 3.1415926535897932384626433832795f
+
+in vec2 v_TexCoord;
+
+uniform sampler2D u_EquirectangularTexture;
+uniform int u_CubeMapFace;
+
+out vec4 o_Color;
+
+/**
+ * Returns sampling direction of a cube map face from face texture coordinate.
+ * Follows the cube map face selection table of OpenGL ES 3.0.
+ */
+vec3 cube_map_direction(int face, vec2 tex_coord) {
+    vec2 st = tex_coord * 2.0f - 1.0f;
+    switch(face) {
+        case 0:
+            return vec3(1.0f, -st.y, -st.x);
+        case 1:
+            return vec3(-1.0f, -st.y, st.x);
+        case 2:
+            return vec3(st.x, 1.0f, st.y);
+        case 3:
+            return vec3(st.x, -1.0f, -st.y);
+        case 4:
+            return vec3(st.x, -st.y, 1.0f);
+        default:
+            return vec3(-st.x, -st.y, -1.0f);
+    }
+}
+
+void main() {
+    vec3 direction = normalize(cube_map_direction(u_CubeMapFace, v_TexCoord));
+    vec2 uv = vec2(atan(direction.z, direction.x) / (2.0f * PI) + 0.5f, asin(direction.y) / PI + 0.5f);
+    o_Color = vec4(texture(u_EquirectangularTexture, uv).rgb, 1.0f);
+}
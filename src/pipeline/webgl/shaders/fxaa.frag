@@ -0,0 +1,178 @@
+#version 300 es
+
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+precision highp int;
+precision highp sampler2D;
+#else
+precision mediump float;
+precision mediump int;
+precision mediump sampler2D;
+#endif
+
+#define EDGE_THRESHOLD_MIN 0.0312f
+#define EDGE_THRESHOLD_MAX 0.125f
+#define SUBPIXEL_QUALITY 0.75f
+#define ITERATIONS 12
+
+in vec2 v_TexCoord;
+
+uniform sampler2D u_Texture;
+
+out vec4 o_Color;
+
+// computation sampler filters by nearest, filters bilinearly by hand
+vec4 sample_bilinear(vec2 uv) {
+    vec2 size = vec2(textureSize(u_Texture, 0));
+    ivec2 max_pixel = ivec2(size) - 1;
+    vec2 position = uv * size - 0.5f;
+    ivec2 pixel = ivec2(floor(position));
+    vec2 t = fract(position);
+
+    vec4 c00 = texelFetch(u_Texture, clamp(pixel, ivec2(0), max_pixel), 0);
+    vec4 c10 = texelFetch(u_Texture, clamp(pixel + ivec2(1, 0), ivec2(0), max_pixel), 0);
+    vec4 c01 = texelFetch(u_Texture, clamp(pixel + ivec2(0, 1), ivec2(0), max_pixel), 0);
+    vec4 c11 = texelFetch(u_Texture, clamp(pixel + ivec2(1, 1), ivec2(0), max_pixel), 0);
+    return mix(mix(c00, c10, t.x), mix(c01, c11, t.x), t.y);
+}
+
+float luma(vec2 uv) {
+    return sqrt(dot(sample_bilinear(uv).rgb, vec3(0.299f, 0.587f, 0.114f)));
+}
+
+float step_quality(int i) {
+    if(i < 5) {
+        return 1.0f;
+    } else if(i < 7) {
+        return 1.5f;
+    } else if(i < 10) {
+        return 2.0f;
+    } else {
+        return 4.0f;
+    }
+}
+
+void main() {
+    vec2 texel = 1.0f / vec2(textureSize(u_Texture, 0));
+    vec4 center = sample_bilinear(v_TexCoord);
+
+    float luma_center = luma(v_TexCoord);
+    float luma_down = luma(v_TexCoord + vec2(0.0f, -texel.y));
+    float luma_up = luma(v_TexCoord + vec2(0.0f, texel.y));
+    float luma_left = luma(v_TexCoord + vec2(-texel.x, 0.0f));
+    float luma_right = luma(v_TexCoord + vec2(texel.x, 0.0f));
+
+    float luma_min = min(luma_center, min(min(luma_down, luma_up), min(luma_left, luma_right)));
+    float luma_max = max(luma_center, max(max(luma_down, luma_up), max(luma_left, luma_right)));
+    float luma_range = luma_max - luma_min;
+
+    // skips if not on an edge
+    if(luma_range < max(EDGE_THRESHOLD_MIN, luma_max * EDGE_THRESHOLD_MAX)) {
+        o_Color = center;
+        return;
+    }
+
+    float luma_down_left = luma(v_TexCoord + vec2(-texel.x, -texel.y));
+    float luma_up_right = luma(v_TexCoord + vec2(texel.x, texel.y));
+    float luma_up_left = luma(v_TexCoord + vec2(-texel.x, texel.y));
+    float luma_down_right = luma(v_TexCoord + vec2(texel.x, -texel.y));
+
+    float luma_down_up = luma_down + luma_up;
+    float luma_left_right = luma_left + luma_right;
+    float luma_left_corners = luma_down_left + luma_up_left;
+    float luma_down_corners = luma_down_left + luma_down_right;
+    float luma_right_corners = luma_down_right + luma_up_right;
+    float luma_up_corners = luma_up_right + luma_up_left;
+
+    // estimates direction of the edge
+    float edge_horizontal = abs(-2.0f * luma_left + luma_left_corners) + abs(-2.0f * luma_center + luma_down_up) * 2.0f + abs(-2.0f * luma_right + luma_right_corners);
+    float edge_vertical = abs(-2.0f * luma_up + luma_up_corners) + abs(-2.0f * luma_center + luma_left_right) * 2.0f + abs(-2.0f * luma_down + luma_down_corners);
+    bool is_horizontal = edge_horizontal >= edge_vertical;
+
+    // chooses the side of the edge with the steepest gradient
+    float luma1 = is_horizontal ? luma_down : luma_left;
+    float luma2 = is_horizontal ? luma_up : luma_right;
+    float gradient1 = luma1 - luma_center;
+    float gradient2 = luma2 - luma_center;
+    bool is_1_steepest = abs(gradient1) >= abs(gradient2);
+    float gradient_scaled = 0.25f * max(abs(gradient1), abs(gradient2));
+
+    float step_length = is_horizontal ? texel.y : texel.x;
+    float luma_local_average;
+    if(is_1_steepest) {
+        step_length = -step_length;
+        luma_local_average = 0.5f * (luma1 + luma_center);
+    } else {
+        luma_local_average = 0.5f * (luma2 + luma_center);
+    }
+
+    vec2 current_uv = v_TexCoord;
+    if(is_horizontal) {
+        current_uv.y += step_length * 0.5f;
+    } else {
+        current_uv.x += step_length * 0.5f;
+    }
+
+    // explores both ends of the edge
+    vec2 offset = is_horizontal ? vec2(texel.x, 0.0f) : vec2(0.0f, texel.y);
+    vec2 uv1 = current_uv - offset;
+    vec2 uv2 = current_uv + offset;
+    float luma_end1 = luma(uv1) - luma_local_average;
+    float luma_end2 = luma(uv2) - luma_local_average;
+    bool reached1 = abs(luma_end1) >= gradient_scaled;
+    bool reached2 = abs(luma_end2) >= gradient_scaled;
+    if(!reached1) {
+        uv1 -= offset;
+    }
+    if(!reached2) {
+        uv2 += offset;
+    }
+
+    for(int i = 2; i < ITERATIONS; i++) {
+        if(reached1 && reached2) {
+            break;
+        }
+        if(!reached1) {
+            luma_end1 = luma(uv1) - luma_local_average;
+        }
+        if(!reached2) {
+            luma_end2 = luma(uv2) - luma_local_average;
+        }
+        reached1 = abs(luma_end1) >= gradient_scaled;
+        reached2 = abs(luma_end2) >= gradient_scaled;
+        if(!reached1) {
+            uv1 -= offset * step_quality(i);
+        }
+        if(!reached2) {
+            uv2 += offset * step_quality(i);
+        }
+    }
+
+    float distance1 = is_horizontal ? (v_TexCoord.x - uv1.x) : (v_TexCoord.y - uv1.y);
+    float distance2 = is_horizontal ? (uv2.x - v_TexCoord.x) : (uv2.y - v_TexCoord.y);
+    bool is_direction1 = distance1 < distance2;
+    float distance_final = min(distance1, distance2);
+    float edge_thickness = distance1 + distance2;
+    float pixel_offset = -distance_final / edge_thickness + 0.5f;
+
+    // only offsets if luma variation at the closer end is coherent with the center
+    bool is_luma_center_smaller = luma_center < luma_local_average;
+    bool correct_variation = ((is_direction1 ? luma_end1 : luma_end2) < 0.0f) != is_luma_center_smaller;
+    float final_offset = correct_variation ? pixel_offset : 0.0f;
+
+    // subpixel antialiasing
+    float luma_average = (1.0f / 12.0f) * (2.0f * (luma_down_up + luma_left_right) + luma_left_corners + luma_right_corners);
+    float subpixel_offset1 = clamp(abs(luma_average - luma_center) / luma_range, 0.0f, 1.0f);
+    float subpixel_offset2 = (-2.0f * subpixel_offset1 + 3.0f) * subpixel_offset1 * subpixel_offset1;
+    float subpixel_offset = subpixel_offset2 * subpixel_offset2 * SUBPIXEL_QUALITY;
+    final_offset = max(final_offset, subpixel_offset);
+
+    vec2 final_uv = v_TexCoord;
+    if(is_horizontal) {
+        final_uv.y += final_offset * step_length;
+    } else {
+        final_uv.x += final_offset * step_length;
+    }
+
+    o_Color = sample_bilinear(final_uv);
+}
@@ -5,26 +5,56 @@ precision highp float;
 precision highp int;
 precision highp sampler2D;
 precision highp sampler2DArray;
+precision highp samplerCube;
 #else
 precision mediump float;
 precision mediump int;
 precision mediump sampler2D;
 precision mediump sampler2DArray;
+precision mediump samplerCube;
 #endif
 
 #include Defines
 #include UniversalUniforms
 #include FragmentConstants
 
+#ifdef USE_PBR
+#include Pbr
+#endif
+
 layout(location = 0) out vec4 o_PositionAndSpecularShininess;
 layout(location = 1) out vec4 o_Normal;
 layout(location = 2) out vec4 o_Albedo;
+layout(location = 3) out vec4 o_MetallicRoughnessOcclusion;
+layout(location = 4) out vec4 o_Emission;
+
+#ifdef USE_INSTANCING
+in vec4 v_InstanceColor;
+#endif
 
 #include FragmentProcess
 
 void main() {
+    #ifdef USE_PBR
+    atoy_PbrFragment fragment = fragment_process();
+        #ifdef USE_INSTANCING
+    fragment.base_color *= v_InstanceColor.rgb;
+        #endif
+    o_PositionAndSpecularShininess = vec4(fragment.position, 0.0f);
+    o_Normal = vec4(fragment.normal, 1.0f);
+    o_Albedo = vec4(fragment.base_color, 1.0f);
+    // alpha component marks the fragment as physically based
+    o_MetallicRoughnessOcclusion = vec4(fragment.metallic, fragment.roughness, fragment.occlusion, 1.0f);
+    o_Emission = vec4(fragment.emission, 1.0f);
+    #else
     atoy_Fragment fragment = fragment_process();
+        #ifdef USE_INSTANCING
+    fragment.albedo *= v_InstanceColor.rgb;
+        #endif
     o_PositionAndSpecularShininess = vec4(fragment.position, fragment.shininess);
     o_Normal = vec4(fragment.normal, 1.0f);
     o_Albedo = vec4(fragment.albedo, 1.0f);
+    o_MetallicRoughnessOcclusion = vec4(0.0f);
+    o_Emission = vec4(0.0f);
+    #endif
 }
\ No newline at end of file
@@ -0,0 +1,51 @@
+#version 300 es
+
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+precision highp sampler2D;
+#else
+precision mediump float;
+precision mediump sampler2D;
+#endif
+
+#include Defines
+
+in vec2 v_TexCoord;
+
+/**
+ * Accumulated weighted colors in `rgb` and revealage in `a`.
+ */
+uniform sampler2D u_AccumulationTexture;
+/**
+ * Accumulated weighted transparencies in `r`.
+ */
+uniform sampler2D u_WeightTexture;
+
+layout(location = 0) out vec4 o_Color;
+
+#ifdef USE_BLOOM
+uniform vec3 u_BloomThreshold;
+layout(location = 1) out vec4 o_BloomColor;
+#endif
+
+void main() {
+    vec4 accumulation = texture(u_AccumulationTexture, v_TexCoord);
+    float revealage = accumulation.a;
+    // no translucent entity covers this fragment
+    if(revealage >= 1.0f) {
+        discard;
+    }
+
+    float weight = texture(u_WeightTexture, v_TexCoord).r;
+    vec3 color = accumulation.rgb / max(weight, 1e-5f);
+    float alpha = 1.0f - revealage;
+    o_Color = vec4(color, alpha);
+
+    #ifdef USE_BLOOM
+    if(dot(color, u_BloomThreshold) > 1.0f) {
+        o_BloomColor = vec4(color, alpha);
+    } else {
+        o_BloomColor = vec4(0.0f, 0.0f, 0.0f, alpha);
+    }
+    #endif
+}
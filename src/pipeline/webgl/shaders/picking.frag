@@ -7,13 +7,15 @@ precision mediump float;
 #endif
 
 layout(location = 0) out uint out_Index;
-layout(location = 1) out uvec3 out_Position;
+layout(location = 1) out uvec4 out_Position;
 
 in vec3 v_Position;
+flat in uint v_VertexId;
 
 uniform uint u_Index;
 
 void main() {
     out_Index = u_Index;
-    out_Position = uvec3(floatBitsToUint(v_Position.x), floatBitsToUint(v_Position.y), floatBitsToUint(v_Position.z));
+    // vertex id of provoking vertex plus 1 in w, 0 means nothing rendered
+    out_Position = uvec4(floatBitsToUint(v_Position.x), floatBitsToUint(v_Position.y), floatBitsToUint(v_Position.z), v_VertexId + 1u);
 }
@@ -1,13 +1,25 @@
 #version 300 es 
 
+#include Defines
+
 in vec4 a_Position;
 out vec3 v_Position;
+flat out uint v_VertexId;
 uniform mat4 u_ModelMatrix;
 
+#ifdef USE_INSTANCING
+in mat4 a_Entity_InstanceModelMatrix;
+#endif
+
 #include UniversalUniforms
 
 void main() {
+    #ifdef USE_INSTANCING
+    vec4 position = u_ModelMatrix * a_Entity_InstanceModelMatrix * a_Position;
+    #else
     vec4 position = u_ModelMatrix * a_Position;
+    #endif
     v_Position = vec3(position);
+    v_VertexId = uint(gl_VertexID);
     gl_Position = u_ViewProjMatrix * position;
 }
@@ -0,0 +1,22 @@
+#version 300 es
+
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+#else
+precision mediump float;
+#endif
+
+#include Defines
+
+#ifdef USE_LINEAR_DEPTH
+in vec3 v_Position;
+uniform vec3 u_LightPosition;
+uniform float u_ShadowFar;
+#endif
+
+void main() {
+    #ifdef USE_LINEAR_DEPTH
+    // stores linear distance to light, shared by all faces of a cube shadow map
+    gl_FragDepth = clamp(distance(v_Position, u_LightPosition) / u_ShadowFar, 0.0f, 1.0f);
+    #endif
+}
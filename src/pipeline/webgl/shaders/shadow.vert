@@ -0,0 +1,27 @@
+#version 300 es
+
+#include Defines
+
+in vec4 a_Position;
+uniform mat4 u_ModelMatrix;
+uniform mat4 u_ShadowMatrix;
+
+#ifdef USE_INSTANCING
+in mat4 a_Entity_InstanceModelMatrix;
+#endif
+
+#ifdef USE_LINEAR_DEPTH
+out vec3 v_Position;
+#endif
+
+void main() {
+    #ifdef USE_INSTANCING
+    vec4 position = u_ModelMatrix * a_Entity_InstanceModelMatrix * a_Position;
+    #else
+    vec4 position = u_ModelMatrix * a_Position;
+    #endif
+    #ifdef USE_LINEAR_DEPTH
+    v_Position = vec3(position);
+    #endif
+    gl_Position = u_ShadowMatrix * position;
+}
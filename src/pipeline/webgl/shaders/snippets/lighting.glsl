@@ -123,14 +123,18 @@ struct atoy_AmbientLight {
  * - `position`: Light position.
  * - `enabled`: Is light enabled.
  * - `ambient`: Light ambient color.
+ * - `shadow_depth_bias`: Constant depth bias of shadow mapping.
  * - `diffuse`: Light diffuse color.
+ * - `shadow_slope_bias`: Slope scaled depth bias of shadow mapping.
  * - `specular`: Light specular color.
  */
 struct atoy_PointLight {
     vec3 position;
     bool enabled;
     vec3 ambient;
+    float shadow_depth_bias;
     vec3 diffuse;
+    float shadow_slope_bias;
     vec3 specular;
 };
 
@@ -140,14 +144,18 @@ struct atoy_PointLight {
  * - `direction`: Light direction.
  * - `enabled`: Is light enabled.
  * - `ambient`: Light ambient color.
+ * - `shadow_depth_bias`: Constant depth bias of shadow mapping.
  * - `diffuse`: Light diffuse color.
+ * - `shadow_slope_bias`: Slope scaled depth bias of shadow mapping.
  * - `specular`: Light specular color.
  */
 struct atoy_DirectionalLight {
     vec3 direction;
     bool enabled;
     vec3 ambient;
+    float shadow_depth_bias;
     vec3 diffuse;
+    float shadow_slope_bias;
     vec3 specular;
 };
 
@@ -157,21 +165,25 @@ struct atoy_DirectionalLight {
  * - `direction`: Light direction.
  * - `enabled`: Is light enabled.
  * - `position`: Light position.
+ * - `shadow_depth_bias`: Constant depth bias of shadow mapping.
  * - `ambient`: Light ambient color.
  * - `inner_cutoff`: Inner cutoff in cosine value for smooth lighting.
  * - `diffuse`: Light diffuse color.
  * - `outer_cutoff`: Outer cutoff in cosine value for smooth lighting.
  * - `specular`: Light specular color.
+ * - `shadow_slope_bias`: Slope scaled depth bias of shadow mapping.
  */
 struct atoy_SpotLight {
     vec3 direction;
     bool enabled;
     vec3 position;
+    float shadow_depth_bias;
     vec3 ambient;
     float inner_cutoff;
     vec3 diffuse;
     float outer_cutoff;
     vec3 specular;
+    float shadow_slope_bias;
 };
 
 /**
@@ -219,6 +231,311 @@ layout(std140) uniform atoy_Lights {
     atoy_AreaLight u_AreaLights[AREA_LIGHTS_COUNT];
 };
 
+#ifdef USE_SHADOW
+/**
+ * Uniform block providing shadow mapping information of lights.
+ *
+ * - `u_DirectionalShadowMatrices`: Light space projection matrices of each shadow cascade of directional lights.
+ * - `u_DirectionalShadowRegions`: Regions of each shadow cascade of directional lights in shadow atlas in `(x, y, width, height)`, zero width for no shadow.
+ * - `u_DirectionalShadowCascades`: Far view distances of shadow cascades of directional lights, zero for no cascade.
+ * - `u_SpotShadowMatrices`: Light space projection matrices of spot lights.
+ * - `u_SpotShadowRegions`: Regions of spot lights in shadow atlas in `(x, y, width, height)`, zero width for no shadow.
+ * - `u_PointShadows`: Cube shadow map slot of point lights in `x`, negative for no shadow, and shadow far distance in `y`.
+ */
+#define ATOY_MAX_SHADOW_CASCADES 4
+#define ATOY_SHADOW_CASCADE_BLEND 0.1f
+
+layout(std140) uniform atoy_Shadows {
+    mat4 u_DirectionalShadowMatrices[DIRECTIONAL_LIGHTS_COUNT * ATOY_MAX_SHADOW_CASCADES];
+    vec4 u_DirectionalShadowRegions[DIRECTIONAL_LIGHTS_COUNT * ATOY_MAX_SHADOW_CASCADES];
+    vec4 u_DirectionalShadowCascades[DIRECTIONAL_LIGHTS_COUNT];
+    mat4 u_SpotShadowMatrices[SPOT_LIGHTS_COUNT];
+    vec4 u_SpotShadowRegions[SPOT_LIGHTS_COUNT];
+    vec4 u_PointShadows[POINT_LIGHTS_COUNT];
+};
+
+uniform highp sampler2DShadow u_ShadowAtlas;
+uniform highp samplerCubeShadow u_PointShadowMap0;
+uniform highp samplerCubeShadow u_PointShadowMap1;
+uniform highp samplerCubeShadow u_PointShadowMap2;
+uniform highp samplerCubeShadow u_PointShadowMap3;
+
+/**
+ * Sampling directions of point light shadow percentage closer filtering.
+ */
+const vec3 ATOY_POINT_SHADOW_OFFSETS[20] = vec3[](
+    vec3(1.0f, 1.0f, 1.0f), vec3(1.0f, -1.0f, 1.0f), vec3(-1.0f, -1.0f, 1.0f), vec3(-1.0f, 1.0f, 1.0f),
+    vec3(1.0f, 1.0f, -1.0f), vec3(1.0f, -1.0f, -1.0f), vec3(-1.0f, -1.0f, -1.0f), vec3(-1.0f, 1.0f, -1.0f),
+    vec3(1.0f, 1.0f, 0.0f), vec3(1.0f, -1.0f, 0.0f), vec3(-1.0f, -1.0f, 0.0f), vec3(-1.0f, 1.0f, 0.0f),
+    vec3(1.0f, 0.0f, 1.0f), vec3(-1.0f, 0.0f, 1.0f), vec3(1.0f, 0.0f, -1.0f), vec3(-1.0f, 0.0f, -1.0f),
+    vec3(0.0f, 1.0f, 1.0f), vec3(0.0f, -1.0f, 1.0f), vec3(0.0f, -1.0f, -1.0f), vec3(0.0f, 1.0f, -1.0f)
+);
+
+/**
+ * Calculates slope scaled depth bias.
+ *
+ * `normal` and `to_light` should be normalized.
+ */
+float atoy_shadow_bias(float depth_bias, float slope_bias, vec3 normal, vec3 to_light) {
+    return max(slope_bias * (1.0f - max(dot(normal, to_light), 0.0f)), depth_bias);
+}
+
+/**
+ * Samples shadow atlas using 3x3 percentage closer filtering.
+ * Returns `1.0` for fully lit and `0.0` for fully shadowed.
+ */
+float atoy_atlas_shadow(mat4 shadow_matrix, vec4 region, vec3 position, float bias) {
+    if(region.z == 0.0f) {
+        return 1.0f;
+    }
+
+    vec4 clip = shadow_matrix * vec4(position, 1.0f);
+    vec3 coord = (clip.xyz / clip.w) * 0.5f + 0.5f;
+    // positions outside light frustum are never shadowed
+    if(coord.x < 0.0f || coord.x > 1.0f || coord.y < 0.0f || coord.y > 1.0f || coord.z > 1.0f) {
+        return 1.0f;
+    }
+
+    // samples never leave the region of this light
+    vec2 texel = 1.0f / vec2(textureSize(u_ShadowAtlas, 0));
+    vec2 min_uv = region.xy + texel * 0.5f;
+    vec2 max_uv = region.xy + region.zw - texel * 0.5f;
+    vec2 uv = region.xy + coord.xy * region.zw;
+    float reference = coord.z - bias;
+
+    float visibility = 0.0f;
+    for(int x = -1; x <= 1; x++) {
+        for(int y = -1; y <= 1; y++) {
+            vec2 sample_uv = clamp(uv + vec2(float(x), float(y)) * texel, min_uv, max_uv);
+            visibility += texture(u_ShadowAtlas, vec3(sample_uv, reference));
+        }
+    }
+    return visibility / 9.0f;
+}
+
+/**
+ * Samples a cube shadow map by slot.
+ * Sampler arrays could only be indexed by constant expressions, so slots are selected one by one.
+ */
+float atoy_point_shadow_sample(int slot, vec3 direction, float reference) {
+    if(slot == 0) {
+        return texture(u_PointShadowMap0, vec4(direction, reference));
+    } else if(slot == 1) {
+        return texture(u_PointShadowMap1, vec4(direction, reference));
+    } else if(slot == 2) {
+        return texture(u_PointShadowMap2, vec4(direction, reference));
+    } else if(slot == 3) {
+        return texture(u_PointShadowMap3, vec4(direction, reference));
+    } else {
+        return 1.0f;
+    }
+}
+
+/**
+ * Calculates shadow of a directional light at index `index`.
+ * Shadow cascade is selected by view distance of `position`.
+ * Returns `1.0` for fully lit and `0.0` for fully shadowed.
+ */
+float atoy_directional_shadow(int index, vec3 position, vec3 normal) {
+    vec4 cascades = u_DirectionalShadowCascades[index];
+    if(cascades.x == 0.0f) {
+        return 1.0f;
+    }
+
+    atoy_DirectionalLight light = u_DirectionalLights[index];
+    float bias = atoy_shadow_bias(light.shadow_depth_bias, light.shadow_slope_bias, normal, -light.direction);
+    float depth = -(u_ViewMatrix * vec4(position, 1.0f)).z;
+
+    float near = 0.0f;
+    for(int cascade = 0; cascade < ATOY_MAX_SHADOW_CASCADES; cascade++) {
+        float far = cascades[cascade];
+        if(far == 0.0f) {
+            break;
+        }
+
+        if(depth <= far) {
+            int slot = index * ATOY_MAX_SHADOW_CASCADES + cascade;
+            float shadow = atoy_atlas_shadow(u_DirectionalShadowMatrices[slot], u_DirectionalShadowRegions[slot], position, bias);
+
+            // blends into next cascade near far boundary, the last cascade fades out to fully lit
+            float band = (far - near) * ATOY_SHADOW_CASCADE_BLEND;
+            if(depth > far - band) {
+                float next = 1.0f;
+                if(cascade + 1 < ATOY_MAX_SHADOW_CASCADES && cascades[cascade + 1] != 0.0f) {
+                    next = atoy_atlas_shadow(u_DirectionalShadowMatrices[slot + 1], u_DirectionalShadowRegions[slot + 1], position, bias);
+                }
+                shadow = mix(shadow, next, (depth - (far - band)) / band);
+            }
+            return shadow;
+        }
+        near = far;
+    }
+    return 1.0f;
+}
+
+/**
+ * Calculates shadow of a spot light at index `index`.
+ * Returns `1.0` for fully lit and `0.0` for fully shadowed.
+ */
+float atoy_spot_shadow(int index, vec3 position, vec3 normal) {
+    atoy_SpotLight light = u_SpotLights[index];
+    float bias = atoy_shadow_bias(light.shadow_depth_bias, light.shadow_slope_bias, normal, normalize(light.position - position));
+    return atoy_atlas_shadow(u_SpotShadowMatrices[index], u_SpotShadowRegions[index], position, bias);
+}
+
+/**
+ * Calculates shadow of a point light at index `index` using 20 samples percentage closer filtering.
+ * Returns `1.0` for fully lit and `0.0` for fully shadowed.
+ */
+float atoy_point_shadow(int index, vec3 position, vec3 normal) {
+    vec4 shadow = u_PointShadows[index];
+    int slot = int(shadow.x);
+    if(slot < 0) {
+        return 1.0f;
+    }
+
+    atoy_PointLight light = u_PointLights[index];
+    vec3 from_light = position - light.position;
+    float light_distance = length(from_light);
+    float reference = light_distance / shadow.y;
+    if(reference > 1.0f) {
+        return 1.0f;
+    }
+
+    float bias = atoy_shadow_bias(light.shadow_depth_bias, light.shadow_slope_bias, normal, -from_light / light_distance);
+    reference -= bias;
+
+    // filtering radius grows with distance, softening far shadows
+    float radius = 0.02f * light_distance;
+    float visibility = 0.0f;
+    for(int i = 0; i < 20; i++) {
+        visibility += atoy_point_shadow_sample(slot, from_light + ATOY_POINT_SHADOW_OFFSETS[i] * radius, reference);
+    }
+    return visibility / 20.0f;
+}
+#else
+float atoy_directional_shadow(int index, vec3 position, vec3 normal) {
+    return 1.0f;
+}
+
+float atoy_spot_shadow(int index, vec3 position, vec3 normal) {
+    return 1.0f;
+}
+
+float atoy_point_shadow(int index, vec3 position, vec3 normal) {
+    return 1.0f;
+}
+#endif
+
+#ifdef USE_CLUSTERED_LIGHTING
+/**
+ * Uniform block providing cluster grid of clustered lighting.
+ *
+ * - `u_ClusterDimensions`: Tiles in screen space in `x` and `y`, slices along view direction in `z`, and point lights count in light data texture in `w`.
+ * - `u_ClusterDepthRange`: View distance where the first slice starts in `x` and the last slice ends in `y`. Slices are logarithmic if `x` is positive, or uniform otherwise.
+ */
+layout(std140) uniform atoy_Clusters {
+    uvec4 u_ClusterDimensions;
+    vec4 u_ClusterDepthRange;
+};
+
+/**
+ * Light data texture, each row stores a light packed same as `atoy_Lights`. Point lights come first and spot lights follow.
+ */
+uniform highp sampler2D u_ClusterLightsTexture;
+/**
+ * Light indices texture, wrapped into rows. Offset and lights count of cluster `i` are stored at texels `2 * i` and `2 * i + 1`,
+ * and light indices of all clusters follow. Offsets point into the whole texture.
+ */
+uniform highp usampler2D u_ClusterIndicesTexture;
+
+/**
+ * Returns the value at `offset` in light indices texture.
+ */
+uint atoy_cluster_texel(uint offset) {
+    int width = textureSize(u_ClusterIndicesTexture, 0).x;
+    int i = int(offset);
+    return texelFetch(u_ClusterIndicesTexture, ivec2(i % width, i / width), 0).r;
+}
+
+/**
+ * Returns offset into light indices texture and lights count of the cluster containing `position` in WORLD space.
+ */
+uvec2 atoy_cluster(vec3 position) {
+    vec4 clip = u_ViewProjMatrix * vec4(position, 1.0f);
+    vec2 uv = (clip.xy / clip.w) * 0.5f + 0.5f;
+
+    float depth = -(u_ViewMatrix * vec4(position, 1.0f)).z;
+    float near = u_ClusterDepthRange.x;
+    float far = u_ClusterDepthRange.y;
+    float slice;
+    if(near > 0.0f) {
+        slice = log(max(depth, near) / near) / log(far / near);
+    } else {
+        slice = (depth - near) / (far - near);
+    }
+
+    ivec3 dimensions = ivec3(u_ClusterDimensions.xyz);
+    ivec3 cluster = clamp(ivec3(floor(vec3(uv, slice) * vec3(dimensions))), ivec3(0), dimensions - 1);
+    uint index = uint(cluster.x + cluster.y * dimensions.x + cluster.z * dimensions.x * dimensions.y);
+    return uvec2(atoy_cluster_texel(index * 2u), atoy_cluster_texel(index * 2u + 1u));
+}
+
+/**
+ * Returns the light index at `offset` in light indices texture.
+ */
+int atoy_cluster_light_index(uint offset) {
+    return int(atoy_cluster_texel(offset));
+}
+
+/**
+ * Returns `true` if light at `index` in light data texture is a point light.
+ */
+bool atoy_cluster_is_point_light(int index) {
+    return index < int(u_ClusterDimensions.w);
+}
+
+/**
+ * Unpacks the point light at `index` in light data texture.
+ */
+atoy_PointLight atoy_cluster_point_light(int index) {
+    vec4 t0 = texelFetch(u_ClusterLightsTexture, ivec2(0, index), 0);
+    vec4 t1 = texelFetch(u_ClusterLightsTexture, ivec2(1, index), 0);
+    vec4 t2 = texelFetch(u_ClusterLightsTexture, ivec2(2, index), 0);
+    vec4 t3 = texelFetch(u_ClusterLightsTexture, ivec2(3, index), 0);
+    return atoy_PointLight(t0.xyz, t0.w != 0.0f, t1.xyz, t1.w, t2.xyz, t2.w, t3.xyz);
+}
+
+/**
+ * Unpacks the spot light at `index` in light data texture.
+ */
+atoy_SpotLight atoy_cluster_spot_light(int index) {
+    vec4 t0 = texelFetch(u_ClusterLightsTexture, ivec2(0, index), 0);
+    vec4 t1 = texelFetch(u_ClusterLightsTexture, ivec2(1, index), 0);
+    vec4 t2 = texelFetch(u_ClusterLightsTexture, ivec2(2, index), 0);
+    vec4 t3 = texelFetch(u_ClusterLightsTexture, ivec2(3, index), 0);
+    vec4 t4 = texelFetch(u_ClusterLightsTexture, ivec2(4, index), 0);
+    return atoy_SpotLight(t0.xyz, t0.w != 0.0f, t1.xyz, t1.w, t2.xyz, t2.w, t3.xyz, t3.w, t4.xyz, t4.w);
+}
+
+/**
+ * Calculates shadow of a clustered point light at `index` in light data texture.
+ * Only point lights packed into `atoy_Lights` as well have shadows.
+ */
+float atoy_cluster_point_shadow(int index, vec3 position, vec3 normal) {
+    return index < POINT_LIGHTS_COUNT ? atoy_point_shadow(index, position, normal) : 1.0f;
+}
+
+/**
+ * Calculates shadow of a clustered spot light at `index` in light data texture.
+ * Only spot lights packed into `atoy_Lights` as well have shadows.
+ */
+float atoy_cluster_spot_shadow(int index, vec3 position, vec3 normal) {
+    int spot_index = index - int(u_ClusterDimensions.w);
+    return spot_index < SPOT_LIGHTS_COUNT ? atoy_spot_shadow(spot_index, position, normal) : 1.0f;
+}
+#endif
+
 /**
  * Applies `atoy_AmbientLight` to lighting.
  */
@@ -232,27 +549,30 @@ void atoy_ambient_lighting(atoy_AmbientLight light, atoy_LightingMaterial materi
 
 /**
  * Applies `atoy_DirectionalLight` to lighting.
+ * Diffuse and specular lighting are scaled by `shadow`.
  */
-void atoy_directional_lighting(atoy_DirectionalLight light, atoy_LightingMaterial material, vec3 to_camera, inout vec3 lighting) {
+void atoy_directional_lighting(atoy_DirectionalLight light, atoy_LightingMaterial material, vec3 to_camera, float shadow, inout vec3 lighting) {
     if(!light.enabled) {
         return;
     }
 
     vec3 color = vec3(0.0f);
-    color += atoy_ambient(light.ambient, material.albedo);
     color += atoy_diffuse(light.diffuse, material.albedo, material.normal, to_camera);
     // for directional light, skip specular lighting if incident of lighting is perpendicular with surface normal
     if(max(dot(-light.direction, material.normal), 0.0f) != 0.0f) {
         color += atoy_specular_phong(light.specular, material.albedo, material.shininess, material.normal, -light.direction, to_camera);
     }
+    color *= shadow;
+    color += atoy_ambient(light.ambient, material.albedo);
 
     lighting += color;
 }
 
 /**
  * Applies `atoy_PointLight` to lighting.
+ * Diffuse and specular lighting are scaled by `shadow`.
  */
-void atoy_point_lighting(atoy_PointLight light, atoy_LightingMaterial material, vec3 to_camera, inout vec3 lighting) {
+void atoy_point_lighting(atoy_PointLight light, atoy_LightingMaterial material, vec3 to_camera, float shadow, inout vec3 lighting) {
     if(!light.enabled) {
         return;
     }
@@ -262,9 +582,10 @@ void atoy_point_lighting(atoy_PointLight light, atoy_LightingMaterial material,
     to_light = normalize(to_light);
 
     vec3 color = vec3(0.0f);
-    color += atoy_ambient(light.ambient, material.albedo);
     color += atoy_diffuse(light.diffuse, material.albedo, material.normal, to_camera);
     color += atoy_specular_phong(light.specular, material.albedo, material.shininess, material.normal, to_light, to_camera);
+    color *= shadow;
+    color += atoy_ambient(light.ambient, material.albedo);
 
     float attenuation = atoy_attenuation_power(u_Attenuations.x, u_Attenuations.y, u_Attenuations.z, light_distance);
     color *= attenuation;
@@ -274,8 +595,9 @@ void atoy_point_lighting(atoy_PointLight light, atoy_LightingMaterial material,
 
 /**
  * Applies `atoy_SpotLight` to a lighting result.
+ * Diffuse and specular lighting are scaled by `shadow`.
  */
-void atoy_spot_lighting(atoy_SpotLight light, atoy_LightingMaterial material, vec3 to_camera, inout vec3 lighting) {
+void atoy_spot_lighting(atoy_SpotLight light, atoy_LightingMaterial material, vec3 to_camera, float shadow, inout vec3 lighting) {
     if(!light.enabled) {
         return;
     }
@@ -291,9 +613,10 @@ void atoy_spot_lighting(atoy_SpotLight light, atoy_LightingMaterial material, ve
     }
 
     vec3 color = vec3(0.0f);
-    color += atoy_ambient(light.ambient, material.albedo);
     color += atoy_diffuse(light.diffuse, material.albedo, material.normal, to_camera);
     color += atoy_specular_phong(light.specular, material.albedo, material.shininess, material.normal, to_light, to_camera);
+    color *= shadow;
+    color += atoy_ambient(light.ambient, material.albedo);
 
     float attenuation = atoy_attenuation_power(u_Attenuations.x, u_Attenuations.y, u_Attenuations.z, light_distance);
     color *= attenuation;
@@ -377,18 +700,36 @@ vec3 atoy_lighting(vec3 camera_position, atoy_LightingMaterial material) {
 
     // directional lights
     for(int i = 0; i < DIRECTIONAL_LIGHTS_COUNT; i++) {
-        atoy_directional_lighting(u_DirectionalLights[i], material, to_camera, lighting);
+        float shadow = atoy_directional_shadow(i, material.position, material.normal);
+        atoy_directional_lighting(u_DirectionalLights[i], material, to_camera, shadow, lighting);
     }
 
+    #ifdef USE_CLUSTERED_LIGHTING
+    // point lights and spot lights in the cluster
+    uvec2 cluster = atoy_cluster(material.position);
+    for(uint i = 0u; i < cluster.y; i++) {
+        int index = atoy_cluster_light_index(cluster.x + i);
+        if(atoy_cluster_is_point_light(index)) {
+            float shadow = atoy_cluster_point_shadow(index, material.position, material.normal);
+            atoy_point_lighting(atoy_cluster_point_light(index), material, to_camera, shadow, lighting);
+        } else {
+            float shadow = atoy_cluster_spot_shadow(index, material.position, material.normal);
+            atoy_spot_lighting(atoy_cluster_spot_light(index), material, to_camera, shadow, lighting);
+        }
+    }
+    #else
     // point lights
     for(int i = 0; i < POINT_LIGHTS_COUNT; i++) {
-        atoy_point_lighting(u_PointLights[i], material, to_camera, lighting);
+        float shadow = atoy_point_shadow(i, material.position, material.normal);
+        atoy_point_lighting(u_PointLights[i], material, to_camera, shadow, lighting);
     }
 
     // spot lights 
     for(int i = 0; i < SPOT_LIGHTS_COUNT; i++) {
-        atoy_spot_lighting(u_SpotLights[i], material, to_camera, lighting);
+        float shadow = atoy_spot_shadow(i, material.position, material.normal);
+        atoy_spot_lighting(u_SpotLights[i], material, to_camera, shadow, lighting);
     }
+    #endif
 
     // area lights 
     for(int i = 0; i < AREA_LIGHTS_COUNT; i++) {
@@ -396,4 +737,200 @@ vec3 atoy_lighting(vec3 camera_position, atoy_LightingMaterial material) {
     }
 
     return lighting;
-}
\ No newline at end of file
+}
+
+#ifdef USE_PBR
+/**
+ * Applies `atoy_AmbientLight` to a physically based lighting result.
+ */
+void atoy_pbr_ambient_lighting(atoy_AmbientLight light, atoy_PbrFragment fragment, inout vec3 lighting) {
+    if(!light.enabled) {
+        return;
+    }
+
+    lighting += atoy_ambient(light.color, fragment.base_color) * fragment.occlusion;
+}
+
+/**
+ * Applies `atoy_DirectionalLight` to a physically based lighting result.
+ * Direct lighting is scaled by `shadow`.
+ */
+void atoy_pbr_directional_lighting(atoy_DirectionalLight light, atoy_PbrFragment fragment, vec3 to_camera, float shadow, inout vec3 lighting) {
+    if(!light.enabled) {
+        return;
+    }
+
+    vec3 color = vec3(0.0f);
+    color += atoy_ambient(light.ambient, fragment.base_color) * fragment.occlusion;
+    color += atoy_cook_torrance(light.diffuse, light.specular, fragment.base_color, fragment.metallic, fragment.roughness, fragment.normal, -light.direction, to_camera) * shadow;
+
+    lighting += color;
+}
+
+/**
+ * Applies `atoy_PointLight` to a physically based lighting result.
+ * Direct lighting is scaled by `shadow`.
+ */
+void atoy_pbr_point_lighting(atoy_PointLight light, atoy_PbrFragment fragment, vec3 to_camera, float shadow, inout vec3 lighting) {
+    if(!light.enabled) {
+        return;
+    }
+
+    vec3 to_light = light.position - fragment.position;
+    float light_distance = length(to_light);
+    to_light = normalize(to_light);
+
+    vec3 color = vec3(0.0f);
+    color += atoy_ambient(light.ambient, fragment.base_color) * fragment.occlusion;
+    color += atoy_cook_torrance(light.diffuse, light.specular, fragment.base_color, fragment.metallic, fragment.roughness, fragment.normal, to_light, to_camera) * shadow;
+
+    float attenuation = atoy_attenuation_power(u_Attenuations.x, u_Attenuations.y, u_Attenuations.z, light_distance);
+    color *= attenuation;
+
+    lighting += color;
+}
+
+/**
+ * Applies `atoy_SpotLight` to a physically based lighting result.
+ * Direct lighting is scaled by `shadow`.
+ */
+void atoy_pbr_spot_lighting(atoy_SpotLight light, atoy_PbrFragment fragment, vec3 to_camera, float shadow, inout vec3 lighting) {
+    if(!light.enabled) {
+        return;
+    }
+
+    vec3 to_light = light.position - fragment.position;
+    float light_distance = length(to_light);
+    to_light = normalize(to_light);
+
+    // skips out of outer cutoff
+    float cos_theta = dot(-to_light, light.direction);
+    if(cos_theta < light.outer_cutoff) {
+        return;
+    }
+
+    vec3 color = vec3(0.0f);
+    color += atoy_ambient(light.ambient, fragment.base_color) * fragment.occlusion;
+    color += atoy_cook_torrance(light.diffuse, light.specular, fragment.base_color, fragment.metallic, fragment.roughness, fragment.normal, to_light, to_camera) * shadow;
+
+    float attenuation = atoy_attenuation_power(u_Attenuations.x, u_Attenuations.y, u_Attenuations.z, light_distance);
+    color *= attenuation;
+
+    // applies smooth lighting
+    if(cos_theta < light.inner_cutoff) {
+        float ratio = (light.inner_cutoff - cos_theta) / (light.inner_cutoff - light.outer_cutoff);
+        float intensity = smoothstep(1.0f, 0.0f, ratio);
+        color *= intensity;
+    }
+
+    lighting += color;
+}
+
+/**
+ * Applies `atoy_AreaLight` to a physically based lighting result.
+ */
+void atoy_pbr_area_lighting(atoy_AreaLight light, atoy_PbrFragment fragment, vec3 to_camera, inout vec3 lighting) {
+    if(!light.enabled) {
+        return;
+    }
+
+    vec3 to_light = light.position - fragment.position;
+    float light_distance = length(to_light);
+    to_light = normalize(to_light);
+    vec3 from_light = -to_light;
+
+    float cos_theta = dot(light.direction, from_light);
+    if(cos_theta < 0.0f) {
+        return;
+    }
+
+    vec3 pop = light.position + light.direction * light.offset;
+    float how = light.outer_width / 2.0f;
+    float hoh = light.outer_height / 2.0f;
+
+    float h = light.offset / cos_theta;
+    float d = light_distance - h;
+    vec3 p = fragment.position + d * to_light;
+
+    vec3 v = p - pop;
+    float x = abs(dot(v, light.right));
+    float y = abs(dot(v, light.up));
+    if(x > how || y > hoh) {
+        return;
+    }
+
+    vec3 color = vec3(0.0f);
+    color += atoy_ambient(light.ambient, fragment.base_color) * fragment.occlusion;
+    color += atoy_cook_torrance(light.diffuse, light.specular, fragment.base_color, fragment.metallic, fragment.roughness, fragment.normal, to_light, to_camera);
+
+    float attenuation = atoy_attenuation_power(u_Attenuations.x, u_Attenuations.y, u_Attenuations.z, light_distance);
+    color *= attenuation;
+
+    float intensity = 1.0f;
+    float hiw = light.inner_width / 2.0f;
+    float hih = light.inner_height / 2.0f;
+    if(x > hiw) {
+        float ix = clamp((how - x) / (how - hiw), 0.0f, 1.0f);
+        intensity = min(ix, intensity);
+    }
+    if(y > hih) {
+        float iy = clamp((hoh - y) / (hoh - hih), 0.0f, 1.0f);
+        intensity = min(iy, intensity);
+    }
+    color *= intensity;
+
+    lighting += color;
+}
+
+/**
+ * Calculates scene mixed lighting for a physically based fragment.
+ * `emission` of the fragment is added as it is.
+ */
+vec3 atoy_pbr_lighting(vec3 camera_position, atoy_PbrFragment fragment) {
+    vec3 to_camera = normalize(camera_position - fragment.position);
+    vec3 lighting = fragment.emission;
+
+    // ambient light
+    atoy_pbr_ambient_lighting(u_AmbientLight, fragment, lighting);
+
+    // directional lights
+    for(int i = 0; i < DIRECTIONAL_LIGHTS_COUNT; i++) {
+        float shadow = atoy_directional_shadow(i, fragment.position, fragment.normal);
+        atoy_pbr_directional_lighting(u_DirectionalLights[i], fragment, to_camera, shadow, lighting);
+    }
+
+    #ifdef USE_CLUSTERED_LIGHTING
+    // point lights and spot lights in the cluster
+    uvec2 cluster = atoy_cluster(fragment.position);
+    for(uint i = 0u; i < cluster.y; i++) {
+        int index = atoy_cluster_light_index(cluster.x + i);
+        if(atoy_cluster_is_point_light(index)) {
+            float shadow = atoy_cluster_point_shadow(index, fragment.position, fragment.normal);
+            atoy_pbr_point_lighting(atoy_cluster_point_light(index), fragment, to_camera, shadow, lighting);
+        } else {
+            float shadow = atoy_cluster_spot_shadow(index, fragment.position, fragment.normal);
+            atoy_pbr_spot_lighting(atoy_cluster_spot_light(index), fragment, to_camera, shadow, lighting);
+        }
+    }
+    #else
+    // point lights
+    for(int i = 0; i < POINT_LIGHTS_COUNT; i++) {
+        float shadow = atoy_point_shadow(i, fragment.position, fragment.normal);
+        atoy_pbr_point_lighting(u_PointLights[i], fragment, to_camera, shadow, lighting);
+    }
+
+    // spot lights 
+    for(int i = 0; i < SPOT_LIGHTS_COUNT; i++) {
+        float shadow = atoy_spot_shadow(i, fragment.position, fragment.normal);
+        atoy_pbr_spot_lighting(u_SpotLights[i], fragment, to_camera, shadow, lighting);
+    }
+    #endif
+
+    // area lights 
+    for(int i = 0; i < AREA_LIGHTS_COUNT; i++) {
+        atoy_pbr_area_lighting(u_AreaLights[i], fragment, to_camera, lighting);
+    }
+
+    return lighting;
+}
+#endif
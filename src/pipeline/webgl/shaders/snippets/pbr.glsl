@@ -0,0 +1,154 @@
+/**
+ * Physically Based Rendering Code Snippet.
+ */
+
+#define ATOY_PI 3.1415926535897932384626433832795f
+
+/**
+ * Physically based fragment difinition for fragment, in metallic-roughness workflow.
+ *
+ * - `position`: Position in WORLD space of this fragment.
+ * - `normal`: Normal of this position in WORLD space of this fragment.
+ * - `base_color`: Base color of this fragment.
+ * - `metallic`: Metallic of this fragment.
+ * - `roughness`: Perceptual roughness of this fragment.
+ * - `occlusion`: Ambient occlusion of this fragment, `1.0` for no occlusion.
+ * - `emission`: Color emitted by this fragment without lighting, image based lighting is included.
+ * - `transparency`: Transparency of this fragment.
+ */
+struct atoy_PbrFragment {
+    vec3 position;
+    vec3 normal;
+    vec3 base_color;
+    float metallic;
+    float roughness;
+    float occlusion;
+    vec3 emission;
+    float transparency;
+};
+
+/**
+ * Returns specular reflectance at normal incidence.
+ * Dielectric surfaces use a constant `0.04`.
+ */
+vec3 atoy_f0(vec3 base_color, float metallic) {
+    return mix(vec3(0.04f), base_color, metallic);
+}
+
+/**
+ * Trowbridge-Reitz GGX normal distribution function.
+ *
+ * `normal` and `halfway` should be normalized.
+ */
+float atoy_distribution_ggx(vec3 normal, vec3 halfway, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float n_dot_h = max(dot(normal, halfway), 0.0f);
+    float denominator = n_dot_h * n_dot_h * (a2 - 1.0f) + 1.0f;
+    return a2 / max(ATOY_PI * denominator * denominator, 0.0000001f);
+}
+
+/**
+ * Schlick-GGX geometry function for direct lighting.
+ */
+float atoy_geometry_schlick_ggx(float n_dot_v, float roughness) {
+    float r = roughness + 1.0f;
+    float k = (r * r) / 8.0f;
+    return n_dot_v / (n_dot_v * (1.0f - k) + k);
+}
+
+/**
+ * Smith geometry function combining view and light obstruction.
+ *
+ * `normal`, `to_light` and `to_camera` should be normalized.
+ */
+float atoy_geometry_smith(vec3 normal, vec3 to_light, vec3 to_camera, float roughness) {
+    float n_dot_v = max(dot(normal, to_camera), 0.0f);
+    float n_dot_l = max(dot(normal, to_light), 0.0f);
+    return atoy_geometry_schlick_ggx(n_dot_v, roughness) * atoy_geometry_schlick_ggx(n_dot_l, roughness);
+}
+
+/**
+ * Fresnel-Schlick approximation.
+ */
+vec3 atoy_fresnel_schlick(float cos_theta, vec3 f0) {
+    return f0 + (1.0f - f0) * pow(clamp(1.0f - cos_theta, 0.0f, 1.0f), 5.0f);
+}
+
+/**
+ * Fresnel-Schlick approximation with roughness injected, for ambient lighting.
+ */
+vec3 atoy_fresnel_schlick_roughness(float cos_theta, vec3 f0, float roughness) {
+    return f0 + (max(vec3(1.0f - roughness), f0) - f0) * pow(clamp(1.0f - cos_theta, 0.0f, 1.0f), 5.0f);
+}
+
+/**
+ * Calculates Cook-Torrance BRDF reflected radiance of a light.
+ * Diffuse part is lit by `diffuse` color and specular part is lit by `specular` color.
+ *
+ * `normal`, `to_light` and `to_camera` should be normalized.
+ */
+vec3 atoy_cook_torrance(
+    vec3 diffuse,
+    vec3 specular,
+    vec3 base_color,
+    float metallic,
+    float roughness,
+    vec3 normal,
+    vec3 to_light,
+    vec3 to_camera
+) {
+    float n_dot_l = max(dot(normal, to_light), 0.0f);
+    if(n_dot_l == 0.0f) {
+        return vec3(0.0f);
+    }
+
+    vec3 h = normalize(to_light + to_camera); // halfway vector
+    float n_dot_v = max(dot(normal, to_camera), 0.0f);
+
+    vec3 f = atoy_fresnel_schlick(max(dot(h, to_camera), 0.0f), atoy_f0(base_color, metallic));
+    float d = atoy_distribution_ggx(normal, h, roughness);
+    float g = atoy_geometry_smith(normal, to_light, to_camera, roughness);
+    vec3 specular_brdf = (d * g * f) / max(4.0f * n_dot_v * n_dot_l, 0.0001f);
+
+    vec3 kd = (vec3(1.0f) - f) * (1.0f - metallic);
+    vec3 diffuse_brdf = kd * base_color / ATOY_PI;
+
+    return (diffuse_brdf * diffuse + specular_brdf * specular) * n_dot_l;
+}
+
+/**
+ * Calculates image based ambient lighting using split sum approximation.
+ *
+ * - `irradiance_map`: Diffuse irradiance cube map.
+ * - `prefiltered_map`: Specular prefiltered cube map, roughness mapped linearly from level 0 to `prefiltered_max_lod`.
+ * - `brdf_lut`: BRDF integration map, `x` for scale and `y` for bias, sampled by `(n_dot_v, roughness)`.
+ *
+ * `normal` and `to_camera` should be normalized.
+ */
+vec3 atoy_image_based_lighting(
+    samplerCube irradiance_map,
+    samplerCube prefiltered_map,
+    float prefiltered_max_lod,
+    sampler2D brdf_lut,
+    vec3 base_color,
+    float metallic,
+    float roughness,
+    vec3 normal,
+    vec3 to_camera
+) {
+    float n_dot_v = max(dot(normal, to_camera), 0.0f);
+    vec3 f0 = atoy_f0(base_color, metallic);
+    vec3 f = atoy_fresnel_schlick_roughness(n_dot_v, f0, roughness);
+
+    vec3 kd = (vec3(1.0f) - f) * (1.0f - metallic);
+    vec3 irradiance = texture(irradiance_map, normal).rgb;
+    vec3 diffuse = kd * irradiance * base_color;
+
+    vec3 r = reflect(-to_camera, normal);
+    vec3 prefiltered = textureLod(prefiltered_map, r, roughness * prefiltered_max_lod).rgb;
+    vec2 brdf = texture(brdf_lut, vec2(n_dot_v, roughness)).rg;
+    vec3 specular = prefiltered * (f * brdf.x + brdf.y);
+
+    return diffuse + specular;
+}
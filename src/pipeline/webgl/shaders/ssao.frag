@@ -0,0 +1,76 @@
+#version 300 es
+
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+precision highp int;
+precision highp sampler2D;
+#else
+precision mediump float;
+precision mediump int;
+precision mediump sampler2D;
+#endif
+
+#include UniversalUniforms
+
+#define SAMPLES 16
+
+uniform sampler2D u_PositionsTexture;
+uniform sampler2D u_NormalsTexture;
+uniform float u_Radius;
+uniform float u_Bias;
+
+out vec4 o_Color;
+
+float random(vec2 seed) {
+    return fract(sin(dot(seed, vec2(12.9898f, 78.233f))) * 43758.5453f);
+}
+
+void main() {
+    ivec2 pixel = ivec2(gl_FragCoord.xy);
+    ivec2 size = textureSize(u_PositionsTexture, 0);
+
+    // alpha component of normal is 0.0 if no entity drawn
+    vec4 normal = texelFetch(u_NormalsTexture, pixel, 0);
+    if(normal.a == 0.0f) {
+        o_Color = vec4(1.0f);
+        return;
+    }
+    vec3 position = texelFetch(u_PositionsTexture, pixel, 0).xyz;
+    vec3 n = normalize(normal.xyz);
+
+    // randomly rotated tangent space for every pixel
+    vec3 random_vector = normalize(vec3(random(gl_FragCoord.xy), random(gl_FragCoord.yx), random(gl_FragCoord.xy + 0.5f)) * 2.0f - 1.0f);
+    vec3 tangent = normalize(random_vector - n * dot(random_vector, n));
+    vec3 bitangent = cross(n, tangent);
+    mat3 tbn = mat3(tangent, bitangent, n);
+
+    float view_depth = (u_ViewMatrix * vec4(position, 1.0f)).z;
+    float occlusion = 0.0f;
+    for(int i = 0; i < SAMPLES; i++) {
+        // samples in hemisphere, more samples closer to the center
+        float fi = float(i);
+        vec3 kernel = normalize(vec3(random(vec2(fi, 0.31f)) * 2.0f - 1.0f, random(vec2(fi, 0.67f)) * 2.0f - 1.0f, random(vec2(fi, 0.93f))));
+        float scale = fi / float(SAMPLES);
+        scale = mix(0.1f, 1.0f, scale * scale);
+        vec3 sample_position = position + tbn * kernel * scale * u_Radius;
+
+        vec4 clip_position = u_ViewProjMatrix * vec4(sample_position, 1.0f);
+        vec2 uv = clip_position.xy / clip_position.w * 0.5f + 0.5f;
+        if(any(lessThan(uv, vec2(0.0f))) || any(greaterThan(uv, vec2(1.0f)))) {
+            continue;
+        }
+        ivec2 sample_pixel = min(ivec2(uv * vec2(size)), size - 1);
+        if(texelFetch(u_NormalsTexture, sample_pixel, 0).a == 0.0f) {
+            continue;
+        }
+
+        vec3 scene_position = texelFetch(u_PositionsTexture, sample_pixel, 0).xyz;
+        float scene_depth = (u_ViewMatrix * vec4(scene_position, 1.0f)).z;
+        float sample_depth = (u_ViewMatrix * vec4(sample_position, 1.0f)).z;
+        // ignores occluders far away from the fragment
+        float range = smoothstep(0.0f, 1.0f, u_Radius / abs(view_depth - scene_depth));
+        occlusion += (scene_depth >= sample_depth + u_Bias ? 1.0f : 0.0f) * range;
+    }
+
+    o_Color = vec4(vec3(1.0f - occlusion / float(SAMPLES)), 1.0f);
+}
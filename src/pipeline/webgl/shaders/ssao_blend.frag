@@ -0,0 +1,37 @@
+#version 300 es
+
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+precision highp int;
+precision highp sampler2D;
+#else
+precision mediump float;
+precision mediump int;
+precision mediump sampler2D;
+#endif
+
+#define BLUR_HALF_SIZE 2
+
+uniform sampler2D u_Texture;
+uniform sampler2D u_OcclusionTexture;
+uniform float u_Intensity;
+
+out vec4 o_Color;
+
+void main() {
+    ivec2 pixel = ivec2(gl_FragCoord.xy);
+    ivec2 max_pixel = textureSize(u_OcclusionTexture, 0) - 1;
+
+    // blurs noisy occlusion
+    float occlusion = 0.0f;
+    for(int t = -BLUR_HALF_SIZE; t <= BLUR_HALF_SIZE; t++) {
+        for(int s = -BLUR_HALF_SIZE; s <= BLUR_HALF_SIZE; s++) {
+            ivec2 sample_pixel = clamp(pixel + ivec2(s, t), ivec2(0), max_pixel);
+            occlusion += texelFetch(u_OcclusionTexture, sample_pixel, 0).r;
+        }
+    }
+    occlusion /= float((BLUR_HALF_SIZE * 2 + 1) * (BLUR_HALF_SIZE * 2 + 1));
+
+    vec4 color = texelFetch(u_Texture, pixel, 0);
+    o_Color = vec4(color.rgb * mix(1.0f, occlusion, u_Intensity), color.a);
+}
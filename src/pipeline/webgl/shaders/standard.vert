@@ -7,6 +7,12 @@ in vec3 a_Position;
 out vec3 v_Position;
 uniform mat4 u_ModelMatrix;
 
+#ifdef USE_INSTANCING
+in mat4 a_Entity_InstanceModelMatrix;
+in vec4 a_Entity_InstanceColor;
+out vec4 v_InstanceColor;
+#endif
+
 #ifdef USE_POSITION_EYE_SPACE
 out vec3 v_PositionES;
 #endif
@@ -36,22 +42,34 @@ out vec2 v_TexCoord;
 #endif
 
 void main() {
-    vec4 position = u_ModelMatrix * vec4(a_Position, 1.0f);
+    #ifdef USE_INSTANCING
+    mat4 model_matrix = u_ModelMatrix * a_Entity_InstanceModelMatrix;
+    v_InstanceColor = a_Entity_InstanceColor;
+    #else
+    mat4 model_matrix = u_ModelMatrix;
+    #endif
+
+    vec4 position = model_matrix * vec4(a_Position, 1.0f);
     v_Position = vec3(position);
     gl_Position = u_ViewProjMatrix * position;
     
     #ifdef USE_NORMAL
-    v_Normal = vec3(u_NormalMatrix * vec4(a_Normal, 0.0f));
+        #ifdef USE_INSTANCING
+        mat4 normal_matrix = transpose(inverse(model_matrix));
+        #else
+        mat4 normal_matrix = u_NormalMatrix;
+        #endif
+    v_Normal = vec3(normal_matrix * vec4(a_Normal, 0.0f));
 
         #ifdef USE_TBN
-        vec3 T = normalize(vec3(u_NormalMatrix * vec4(a_Tangent, 0.0f)));
+        vec3 T = normalize(vec3(normal_matrix * vec4(a_Tangent, 0.0f)));
         vec3 N = normalize(v_Normal);
         vec3 B;
         
         #ifdef USE_CALCULATED_BITANGENT
         B = cross(N, T);
         #else
-        B = normalize(vec3(u_NormalMatrix * vec4(a_Bitangent, 0.0f)));
+        B = normalize(vec3(normal_matrix * vec4(a_Bitangent, 0.0f)));
         #endif
 
         v_TBN = mat3(T, B, N);
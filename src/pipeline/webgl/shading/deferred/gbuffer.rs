@@ -39,6 +39,14 @@ impl StandardGBufferCollector {
                 .set_color_attachment2(AttachmentSource::new_renderbuffer(
                     RenderbufferInternalFormat::RGBA32F,
                 ))
+                // metallic, roughness, occlusion and physically based flag
+                .set_color_attachment3(AttachmentSource::new_renderbuffer(
+                    RenderbufferInternalFormat::RGBA32F,
+                ))
+                // emission
+                .set_color_attachment4(AttachmentSource::new_renderbuffer(
+                    RenderbufferInternalFormat::RGBA32F,
+                ))
                 .set_depth_stencil_attachment(AttachmentSource::new_renderbuffer(
                     RenderbufferInternalFormat::DEPTH32F_STENCIL8,
                 ))
@@ -56,6 +64,14 @@ impl StandardGBufferCollector {
                 .set_color_attachment2(AttachmentSource::new_texture(
                     TextureUncompressedInternalFormat::RGBA32F,
                 ))
+                // metallic, roughness, occlusion and physically based flag
+                .set_color_attachment3(AttachmentSource::new_texture(
+                    TextureUncompressedInternalFormat::RGBA32F,
+                ))
+                // emission
+                .set_color_attachment4(AttachmentSource::new_texture(
+                    TextureUncompressedInternalFormat::RGBA32F,
+                ))
                 .set_depth_stencil_attachment(AttachmentSource::new_texture(
                     TextureUncompressedInternalFormat::DEPTH32F_STENCIL8,
                 ))
@@ -68,7 +84,18 @@ impl StandardGBufferCollector {
         state: &mut FrameState,
         collected_entities: &CollectedEntities,
         multisamples: Option<usize>,
-    ) -> Result<(&WebGlTexture, &WebGlTexture, &WebGlTexture, &WebGlTexture), Error> {
+        lighting: bool,
+    ) -> Result<
+        (
+            &WebGlTexture,
+            &WebGlTexture,
+            &WebGlTexture,
+            &WebGlTexture,
+            &WebGlTexture,
+            &WebGlTexture,
+        ),
+        Error,
+    > {
         self.fbo_ms.init(state.gl())?;
         self.fbo.init(state.gl())?;
 
@@ -76,7 +103,7 @@ impl StandardGBufferCollector {
         self.fbo_ms
             .bind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
         self.fbo_ms.clear_buffers()?;
-        draw_opaque_entities(state, DrawState::GBuffer, collected_entities)?;
+        draw_opaque_entities(state, DrawState::GBuffer { lighting }, collected_entities)?;
         self.fbo_ms
             .unbind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
 
@@ -114,6 +141,37 @@ impl StandardGBufferCollector {
             BlitFlilter::LINEAR,
         )
         .blit()?;
+        Blit::with_buffers(
+            state.gl(),
+            &mut self.fbo_ms,
+            OperableBuffer::COLOR_ATTACHMENT3,
+            &mut self.fbo,
+            vec![
+                OperableBuffer::NONE,
+                OperableBuffer::NONE,
+                OperableBuffer::NONE,
+                OperableBuffer::COLOR_ATTACHMENT3,
+            ],
+            BlitMask::COLOR_BUFFER_BIT,
+            BlitFlilter::NEAREST,
+        )
+        .blit()?;
+        Blit::with_buffers(
+            state.gl(),
+            &mut self.fbo_ms,
+            OperableBuffer::COLOR_ATTACHMENT4,
+            &mut self.fbo,
+            vec![
+                OperableBuffer::NONE,
+                OperableBuffer::NONE,
+                OperableBuffer::NONE,
+                OperableBuffer::NONE,
+                OperableBuffer::COLOR_ATTACHMENT4,
+            ],
+            BlitMask::COLOR_BUFFER_BIT,
+            BlitFlilter::LINEAR,
+        )
+        .blit()?;
         Blit::with_params(
             state.gl(),
             &mut self.fbo_ms,
@@ -161,6 +219,12 @@ impl StandardGBufferCollector {
             self.fbo
                 .texture(FramebufferAttachmentTarget::COLOR_ATTACHMENT2)?
                 .unwrap(),
+            self.fbo
+                .texture(FramebufferAttachmentTarget::COLOR_ATTACHMENT3)?
+                .unwrap(),
+            self.fbo
+                .texture(FramebufferAttachmentTarget::COLOR_ATTACHMENT4)?
+                .unwrap(),
             self.fbo
                 .texture(FramebufferAttachmentTarget::DEPTH_STENCIL_ATTACHMENT)?
                 .unwrap(),
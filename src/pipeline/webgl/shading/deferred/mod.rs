@@ -7,8 +7,9 @@ use web_sys::WebGlTexture;
 
 use crate::{
     pipeline::webgl::{
-        UBO_LIGHTS_BLOCK_BINDING, UBO_LIGHTS_UNIFORM_BLOCK_MOUNT_POINT,
-        UBO_UNIVERSAL_UNIFORMS_BLOCK_BINDING, UBO_UNIVERSAL_UNIFORM_BLOCK_MOUNT_POINT,
+        cluster::mount_clusters, shadow::mount_shadows, UBO_LIGHTS_BLOCK_BINDING,
+        UBO_LIGHTS_UNIFORM_BLOCK_MOUNT_POINT, UBO_UNIVERSAL_UNIFORMS_BLOCK_BINDING,
+        UBO_UNIVERSAL_UNIFORM_BLOCK_MOUNT_POINT,
     },
     renderer::webgl::{
         error::Error,
@@ -56,7 +57,11 @@ impl StandardDeferredShading {
         positions_and_specular_shininess_texture: &WebGlTexture,
         normals_texture: &WebGlTexture,
         albedo_texture: &WebGlTexture,
+        metallic_roughness_occlusion_texture: &WebGlTexture,
+        emission_texture: &WebGlTexture,
         lighting: bool,
+        shadow: bool,
+        clustered: bool,
     ) -> Result<(), Error> {
         self.framebuffer.init(state.gl())?;
         self.framebuffer.bind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
@@ -64,6 +69,8 @@ impl StandardDeferredShading {
 
         let program = if lighting {
             self.shader.lighting = true;
+            self.shader.shadow = shadow;
+            self.shader.clustered = clustered;
             let program = state
                 .program_store_mut()
                 .get_or_compile_program(&self.shader)?;
@@ -73,6 +80,14 @@ impl StandardDeferredShading {
                 &UBO_LIGHTS_BLOCK_BINDING,
                 UBO_LIGHTS_UNIFORM_BLOCK_MOUNT_POINT,
             )?;
+            // binds atoy_Shadows and shadow maps
+            if shadow {
+                mount_shadows(&program)?;
+            }
+            // binds atoy_Clusters and cluster textures
+            if clustered {
+                mount_clusters(&program)?;
+            }
             program.bind_uniform_value_by_binding(
                 &POSITIONS_AND_SPECULAR_SHININESS_TEXTURE_UNIFORM_BINDING,
                 &UniformValue::Integer1(0),
@@ -87,6 +102,8 @@ impl StandardDeferredShading {
             program
         } else {
             self.shader.lighting = false;
+            self.shader.shadow = false;
+            self.shader.clustered = false;
             let program = state
                 .program_store_mut()
                 .get_or_compile_program(&self.shader)?;
@@ -105,6 +122,16 @@ impl StandardDeferredShading {
             &UniformValue::Integer1(2),
             None,
         )?;
+        program.bind_uniform_value_by_binding(
+            &METALLIC_ROUGHNESS_OCCLUSION_TEXTURE_UNIFORM_BINDING,
+            &UniformValue::Integer1(3),
+            None,
+        )?;
+        program.bind_uniform_value_by_binding(
+            &EMISSION_TEXTURE_UNIFORM_BINDING,
+            &UniformValue::Integer1(4),
+            None,
+        )?;
 
         state.do_computation([
             (
@@ -113,6 +140,8 @@ impl StandardDeferredShading {
             ),
             (normals_texture, TextureUnit::TEXTURE1),
             (albedo_texture, TextureUnit::TEXTURE2),
+            (metallic_roughness_occlusion_texture, TextureUnit::TEXTURE3),
+            (emission_texture, TextureUnit::TEXTURE4),
         ])?;
 
         self.framebuffer.unbind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
@@ -138,22 +167,40 @@ const ALBEDO_TEXTURE_UNIFORM_NAME: &'static str = "u_AlbedoTexture";
 const ALBEDO_TEXTURE_UNIFORM_BINDING: UniformBinding =
     UniformBinding::Custom(Cow::Borrowed(ALBEDO_TEXTURE_UNIFORM_NAME));
 
+const METALLIC_ROUGHNESS_OCCLUSION_TEXTURE_UNIFORM_NAME: &'static str =
+    "u_MetallicRoughnessOcclusionTexture";
+const METALLIC_ROUGHNESS_OCCLUSION_TEXTURE_UNIFORM_BINDING: UniformBinding = UniformBinding::Custom(
+    Cow::Borrowed(METALLIC_ROUGHNESS_OCCLUSION_TEXTURE_UNIFORM_NAME),
+);
+
+const EMISSION_TEXTURE_UNIFORM_NAME: &'static str = "u_EmissionTexture";
+const EMISSION_TEXTURE_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(EMISSION_TEXTURE_UNIFORM_NAME));
+
 struct DeferredShading {
     lighting: bool,
+    shadow: bool,
+    clustered: bool,
 }
 
 impl DeferredShading {
     pub fn new() -> Self {
-        Self { lighting: false }
+        Self {
+            lighting: false,
+            shadow: false,
+            clustered: false,
+        }
     }
 }
 
 impl ProgramSource for DeferredShading {
     fn name(&self) -> Cow<'_, str> {
-        if self.lighting {
-            Cow::Borrowed("DeferredShading")
-        } else {
-            Cow::Borrowed("DeferredShading_Lighting")
+        match (self.lighting, self.shadow, self.clustered) {
+            (true, true, true) => Cow::Borrowed("DeferredShading_Shadow_Clustered"),
+            (true, true, false) => Cow::Borrowed("DeferredShading_Shadow"),
+            (true, false, true) => Cow::Borrowed("DeferredShading_Clustered"),
+            (true, false, false) => Cow::Borrowed("DeferredShading"),
+            (false, _, _) => Cow::Borrowed("DeferredShading_Lighting"),
         }
     }
 
@@ -175,7 +222,8 @@ impl ProgramSource for DeferredShading {
 
     fn fragment_defines(&self) -> Cow<'_, [Define<'_>]> {
         if self.lighting {
-            let defines: &[Define<'_>] = &[
+            let mut defines = vec![
+                Define::WithoutValue(Cow::Borrowed("USE_PBR")),
                 Define::WithoutValue(Cow::Borrowed("USE_LIGHTING")),
                 Define::WithValue(
                     Cow::Borrowed(DIRECTIONAL_LIGHTS_COUNT_DEFINE),
@@ -194,9 +242,17 @@ impl ProgramSource for DeferredShading {
                     Cow::Borrowed(MAX_AREA_LIGHTS_STRING),
                 ),
             ];
-            Cow::Borrowed(&defines)
+            if self.shadow {
+                defines.push(Define::WithoutValue(Cow::Borrowed("USE_SHADOW")));
+            }
+            if self.clustered {
+                defines.push(Define::WithoutValue(Cow::Borrowed(
+                    "USE_CLUSTERED_LIGHTING",
+                )));
+            }
+            Cow::Owned(defines)
         } else {
-            Cow::Borrowed(&[])
+            Cow::Borrowed(&[Define::WithoutValue(Cow::Borrowed("USE_PBR"))])
         }
     }
 
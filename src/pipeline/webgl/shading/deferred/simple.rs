@@ -3,7 +3,7 @@ use web_sys::WebGlTexture;
 use crate::{
     pipeline::webgl::{
         collector::CollectedEntities,
-        shading::{draw_translucent_entities, DrawState},
+        shading::{draw_translucent_entities, oit::StandardWeightedBlendedOit, DrawState},
     },
     renderer::webgl::{
         error::Error,
@@ -42,6 +42,9 @@ impl StandardDeferredTransparentShading {
         depth_stencil: &WebGlTexture,
         collected_entities: &CollectedEntities,
         lighting: bool,
+        shadow: bool,
+        clustered: bool,
+        oit: Option<&mut StandardWeightedBlendedOit>,
     ) -> Result<(), Error> {
         self.framebuffer.init(state.gl())?;
         self.framebuffer.set_attachment(
@@ -56,14 +59,17 @@ impl StandardDeferredTransparentShading {
         // do not clear depth buffer!!!
         self.framebuffer
             .clear_buffer(FramebufferAttachmentTarget::COLOR_ATTACHMENT0)?;
-        draw_translucent_entities(
-            state,
-            DrawState::Draw {
-                lighting,
-                bloom: false,
-            },
-            collected_entities,
-        )?;
+        let draw_state = DrawState::Draw {
+            lighting,
+            bloom: false,
+            shadow,
+            clustered,
+            oit: false,
+        };
+        match oit {
+            Some(oit) => oit.draw(state, &mut self.framebuffer, draw_state, collected_entities)?,
+            None => draw_translucent_entities(state, draw_state, collected_entities)?,
+        }
         self.framebuffer
             .unbind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
         Ok(())
@@ -4,10 +4,10 @@ use crate::{
     pipeline::webgl::{
         collector::CollectedEntities,
         shading::{
-            draw_entities, BloomBlendMapping, DrawState, GaussianBlurMapping,
-            HdrExposureToneMapping, HdrReinhardToneMapping, BASE_TEXTURE_UNIFORM_BINDING,
-            BLOOM_BLUR_TEXTURE_UNIFORM_BINDING, HDR_EXPOSURE_UNIFORM_BINDING,
-            HDR_TEXTURE_UNIFORM_BINDING,
+            draw_entities, oit::StandardWeightedBlendedOit, BloomBlendMapping, DrawState,
+            GaussianBlurMapping, HdrExposureToneMapping, HdrReinhardToneMapping,
+            BASE_TEXTURE_UNIFORM_BINDING, BLOOM_BLUR_TEXTURE_UNIFORM_BINDING,
+            HDR_EXPOSURE_UNIFORM_BINDING, HDR_TEXTURE_UNIFORM_BINDING,
         },
         HdrToneMappingType, UBO_GAUSSIAN_BLUR_UNIFORM_BLOCK_MOUNT_POINT,
         UBO_GAUSSIAN_KERNEL_BLOCK_BINDING,
@@ -95,15 +95,18 @@ impl StandardHdrShading {
         tone_mapping_type: HdrToneMappingType,
         collected_entities: &CollectedEntities,
         lighting: bool,
+        shadow: bool,
+        clustered: bool,
+        oit: Option<&mut StandardWeightedBlendedOit>,
         gaussian_kernel_ubo: &Buffer,
     ) -> Result<(), Error> {
         if bloom {
-            self.draw_hdr_bloom(state, collected_entities, lighting)?;
+            self.draw_hdr_bloom(state, collected_entities, lighting, shadow, clustered, oit)?;
             self.blur_bloom(state, bloom_blur_epoch, gaussian_kernel_ubo)?;
             self.blend_bloom(state, bloom_blur_epoch)?;
             self.tone_mapping_bloom(state, tone_mapping_type)?;
         } else {
-            self.draw_hdr(state, collected_entities, lighting)?;
+            self.draw_hdr(state, collected_entities, lighting, shadow, clustered, oit)?;
             self.tone_mapping(state, tone_mapping_type)?;
         }
         Ok(())
@@ -114,6 +117,9 @@ impl StandardHdrShading {
         state: &mut FrameState,
         collected_entities: &CollectedEntities,
         lighting: bool,
+        shadow: bool,
+        clustered: bool,
+        oit: Option<&mut StandardWeightedBlendedOit>,
     ) -> Result<(), Error> {
         self.hdr_framebuffer.init(state.gl())?;
         self.hdr_framebuffer
@@ -124,8 +130,13 @@ impl StandardHdrShading {
             DrawState::Draw {
                 lighting,
                 bloom: false,
+                shadow,
+                clustered,
+                oit: false,
             },
             collected_entities,
+            &mut self.hdr_framebuffer,
+            oit,
         )?;
         self.hdr_framebuffer
             .unbind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
@@ -137,6 +148,9 @@ impl StandardHdrShading {
         state: &mut FrameState,
         collected_entities: &CollectedEntities,
         lighting: bool,
+        shadow: bool,
+        clustered: bool,
+        oit: Option<&mut StandardWeightedBlendedOit>,
     ) -> Result<(), Error> {
         self.hdr_bloom_framebuffer.init(state.gl())?;
         self.hdr_bloom_framebuffer
@@ -147,8 +161,13 @@ impl StandardHdrShading {
             DrawState::Draw {
                 lighting,
                 bloom: true,
+                shadow,
+                clustered,
+                oit: false,
             },
             collected_entities,
+            &mut self.hdr_bloom_framebuffer,
+            oit,
         )?;
         self.hdr_bloom_framebuffer
             .unbind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
@@ -4,10 +4,10 @@ use crate::{
     pipeline::webgl::{
         collector::CollectedEntities,
         shading::{
-            draw_entities, BloomBlendMapping, DrawState, GaussianBlurMapping,
-            HdrExposureToneMapping, HdrReinhardToneMapping, BASE_TEXTURE_UNIFORM_BINDING,
-            BLOOM_BLUR_TEXTURE_UNIFORM_BINDING, HDR_EXPOSURE_UNIFORM_BINDING,
-            HDR_TEXTURE_UNIFORM_BINDING,
+            draw_entities, oit::StandardWeightedBlendedOit, BloomBlendMapping, DrawState,
+            GaussianBlurMapping, HdrExposureToneMapping, HdrReinhardToneMapping,
+            BASE_TEXTURE_UNIFORM_BINDING, BLOOM_BLUR_TEXTURE_UNIFORM_BINDING,
+            HDR_EXPOSURE_UNIFORM_BINDING, HDR_TEXTURE_UNIFORM_BINDING,
         },
         HdrToneMappingType, UBO_GAUSSIAN_BLUR_UNIFORM_BLOCK_MOUNT_POINT,
         UBO_GAUSSIAN_KERNEL_BLOCK_BINDING,
@@ -112,16 +112,35 @@ impl StandardMultisamplesHdrShading {
         tone_mapping_type: HdrToneMappingType,
         collected_entities: &CollectedEntities,
         lighting: bool,
+        shadow: bool,
+        clustered: bool,
+        oit: Option<&mut StandardWeightedBlendedOit>,
         gaussian_kernel_ubo: &Buffer,
     ) -> Result<(), Error> {
         if bloom {
-            self.draw_hdr_multisamples_bloom(state, samples, collected_entities, lighting)?;
+            self.draw_hdr_multisamples_bloom(
+                state,
+                samples,
+                collected_entities,
+                lighting,
+                shadow,
+                clustered,
+                oit,
+            )?;
             self.blit_bloom(state)?;
             self.blur_bloom(state, bloom_blur_epoch, gaussian_kernel_ubo)?;
             self.blend_bloom(state, bloom_blur_epoch)?;
             self.tone_mapping_bloom(state, tone_mapping_type)?;
         } else {
-            self.draw_hdr_multisamples(state, samples, collected_entities, lighting)?;
+            self.draw_hdr_multisamples(
+                state,
+                samples,
+                collected_entities,
+                lighting,
+                shadow,
+                clustered,
+                oit,
+            )?;
             self.blit(state)?;
             self.tone_mapping(state, tone_mapping_type)?;
         }
@@ -134,6 +153,9 @@ impl StandardMultisamplesHdrShading {
         samples: usize,
         collected_entities: &CollectedEntities,
         lighting: bool,
+        shadow: bool,
+        clustered: bool,
+        oit: Option<&mut StandardWeightedBlendedOit>,
     ) -> Result<(), Error> {
         self.hdr_multisamples_framebuffer
             .set_renderbuffer_samples(Some(samples));
@@ -146,8 +168,13 @@ impl StandardMultisamplesHdrShading {
             DrawState::Draw {
                 lighting,
                 bloom: false,
+                shadow,
+                clustered,
+                oit: false,
             },
             collected_entities,
+            &mut self.hdr_multisamples_framebuffer,
+            oit,
         )?;
         self.hdr_multisamples_framebuffer
             .unbind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
@@ -160,6 +187,9 @@ impl StandardMultisamplesHdrShading {
         samples: usize,
         collected_entities: &CollectedEntities,
         lighting: bool,
+        shadow: bool,
+        clustered: bool,
+        oit: Option<&mut StandardWeightedBlendedOit>,
     ) -> Result<(), Error> {
         self.hdr_multisamples_bloom_framebuffer
             .set_renderbuffer_samples(Some(samples));
@@ -172,8 +202,13 @@ impl StandardMultisamplesHdrShading {
             DrawState::Draw {
                 lighting,
                 bloom: true,
+                shadow,
+                clustered,
+                oit: false,
             },
             collected_entities,
+            &mut self.hdr_multisamples_bloom_framebuffer,
+            oit,
         )?;
         self.hdr_multisamples_bloom_framebuffer
             .unbind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
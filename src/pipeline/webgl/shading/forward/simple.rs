@@ -3,7 +3,7 @@ use web_sys::WebGlTexture;
 use crate::{
     pipeline::webgl::{
         collector::CollectedEntities,
-        shading::{draw_entities, DrawState},
+        shading::{draw_entities, oit::StandardWeightedBlendedOit, DrawState},
     },
     renderer::webgl::{
         error::Error,
@@ -45,6 +45,9 @@ impl StandardSimpleShading {
         state: &mut FrameState,
         collected_entities: &CollectedEntities,
         lighting: bool,
+        shadow: bool,
+        clustered: bool,
+        oit: Option<&mut StandardWeightedBlendedOit>,
     ) -> Result<(), Error> {
         self.framebuffer.init(state.gl())?;
         self.framebuffer.bind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
@@ -54,8 +57,13 @@ impl StandardSimpleShading {
             DrawState::Draw {
                 lighting,
                 bloom: false,
+                shadow,
+                clustered,
+                oit: false,
             },
             collected_entities,
+            &mut self.framebuffer,
+            oit,
         )?;
         self.framebuffer.unbind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
         Ok(())
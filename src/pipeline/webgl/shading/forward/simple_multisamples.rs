@@ -3,7 +3,7 @@ use web_sys::WebGlTexture;
 use crate::{
     pipeline::webgl::{
         collector::CollectedEntities,
-        shading::{draw_entities, DrawState},
+        shading::{draw_entities, oit::StandardWeightedBlendedOit, DrawState},
     },
     renderer::webgl::{
         blit::{Blit, BlitFlilter, BlitMask},
@@ -53,8 +53,19 @@ impl StandardMultisamplesSimpleShading {
         samples: usize,
         collected_entities: &CollectedEntities,
         lighting: bool,
+        shadow: bool,
+        clustered: bool,
+        oit: Option<&mut StandardWeightedBlendedOit>,
     ) -> Result<(), Error> {
-        self.draw_multisamples(state, samples, collected_entities, lighting)?;
+        self.draw_multisamples(
+            state,
+            samples,
+            collected_entities,
+            lighting,
+            shadow,
+            clustered,
+            oit,
+        )?;
         self.blit(state)?;
         Ok(())
     }
@@ -65,6 +76,9 @@ impl StandardMultisamplesSimpleShading {
         samples: usize,
         collected_entities: &CollectedEntities,
         lighting: bool,
+        shadow: bool,
+        clustered: bool,
+        oit: Option<&mut StandardWeightedBlendedOit>,
     ) -> Result<(), Error> {
         self.multisample_framebuffer.init(state.gl())?;
         self.multisample_framebuffer
@@ -77,8 +91,13 @@ impl StandardMultisamplesSimpleShading {
             DrawState::Draw {
                 lighting,
                 bloom: false,
+                shadow,
+                clustered,
+                oit: false,
             },
             collected_entities,
+            &mut self.multisample_framebuffer,
+            oit,
         )?;
         self.multisample_framebuffer
             .unbind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
@@ -1,13 +1,19 @@
-use std::{borrow::Cow, cell::RefCell, rc::Rc};
+use std::{
+    borrow::Cow,
+    cell::{RefCell, RefMut},
+    rc::Rc,
+};
 
-use web_sys::WebGl2RenderingContext;
+use uuid::Uuid;
+use web_sys::{WebGl2RenderingContext, WebGlVertexArrayObject};
 
 use crate::{
     entity::Entity,
+    geometry::Geometry,
     material::webgl::StandardMaterial,
     renderer::webgl::{
         
-        conversion::ToGlEnum, draw::Draw, error::Error, program::{Define, Program, ProgramSource}, state::FrameState, uniform::{UniformBinding, UniformValue}
+        conversion::ToGlEnum, draw::Draw, error::Error, framebuffer::Framebuffer, program::{Define, Program, ProgramSource}, state::FrameState, uniform::{UniformBinding, UniformValue}
     },
     scene::{
         AREA_LIGHTS_COUNT_DEFINE, DIRECTIONAL_LIGHTS_COUNT_DEFINE, MAX_AREA_LIGHTS_STRING,
@@ -17,12 +23,16 @@ use crate::{
 };
 
 use super::{
-    collector::CollectedEntities, UBO_LIGHTS_BLOCK_BINDING, UBO_LIGHTS_UNIFORM_BLOCK_MOUNT_POINT,
+    batching::DrawStatistics, cluster::mount_clusters, collector::CollectedEntities,
+    shadow::mount_shadows, UBO_LIGHTS_BLOCK_BINDING, UBO_LIGHTS_UNIFORM_BLOCK_MOUNT_POINT,
     UBO_UNIVERSAL_UNIFORMS_BLOCK_BINDING, UBO_UNIVERSAL_UNIFORM_BLOCK_MOUNT_POINT,
 };
 
+use self::oit::StandardWeightedBlendedOit;
+
 pub mod deferred;
 pub mod forward;
+pub mod oit;
 pub mod picking;
 
 const BLOOM_THRESHOLD_VALUES: [f32; 3] = [0.2126, 0.7152, 0.0722];
@@ -48,17 +58,32 @@ const HDR_EXPOSURE_UNIFORM_BINDING: UniformBinding =
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub(self) enum DrawState {
-    Draw { lighting: bool, bloom: bool },
-    GBuffer,
+    Draw {
+        lighting: bool,
+        bloom: bool,
+        shadow: bool,
+        clustered: bool,
+        oit: bool,
+    },
+    GBuffer { lighting: bool },
 }
 
+/// Draws opaque entities and then translucent entities into `framebuffer`,
+/// which should be bound as draw framebuffer already.
+/// Translucent entities are drawn by weighted blended order-independent transparency if `oit` is provided,
+/// or are sorted from farthest to nearest otherwise.
 pub(self) fn draw_entities(
     state: &mut FrameState,
     draw_state: DrawState,
     collected_entities: &CollectedEntities,
+    framebuffer: &mut Framebuffer,
+    oit: Option<&mut StandardWeightedBlendedOit>,
 ) -> Result<(), Error> {
     draw_opaque_entities(state, draw_state, collected_entities)?;
-    draw_translucent_entities(state, draw_state, collected_entities)?;
+    match oit {
+        Some(oit) => oit.draw(state, framebuffer, draw_state, collected_entities)?,
+        None => draw_translucent_entities(state, draw_state, collected_entities)?,
+    }
     Ok(())
 }
 
@@ -70,13 +95,34 @@ fn draw_opaque_entities(
     state.gl().enable(WebGl2RenderingContext::DEPTH_TEST);
     state.gl().depth_mask(true);
 
+    let mut context = DrawContext::new(collected_entities);
+
+    // draws static batches first, each batch is drawn by its representative entity
+    for batch in collected_entities.static_batches() {
+        let Some(representative) = batch.representative() else {
+            continue;
+        };
+        let representative = representative.borrow();
+        draw_geometry(
+            state,
+            draw_state,
+            true,
+            &*representative,
+            batch.geometry(),
+            None,
+            &mut context,
+        )?;
+        context.statistics.add_static_batch(batch.members_count());
+    }
+
     // draws opaque enable DEPTH_TEST and disable BLEND and draws them from nearest to farthest first
     for entity in collected_entities.opaque_entities() {
         let Some(entity) = entity.upgrade() else {
             continue;
         };
-        draw_entity(state, draw_state, true, entity)?;
+        draw_entity(state, draw_state, true, entity, &mut context)?;
     }
+    context.finish()?;
 
     state.gl().disable(WebGl2RenderingContext::CULL_FACE);
     state.gl().cull_face(WebGl2RenderingContext::BACK);
@@ -99,14 +145,60 @@ fn draw_translucent_entities(
         WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
     );
 
+    let mut context = DrawContext::new(collected_entities);
+
     // draws translucents first with DEPTH_TEST unchangeable and enable BLEND and draws them from farthest to nearest
     for entity in collected_entities.translucent_entities().iter().rev() {
         // transparency entities never cull face
         let Some(entity) = entity.upgrade() else {
             continue;
         };
-        draw_entity(state, draw_state, false, entity)?;
+        draw_entity(state, draw_state, false, entity, &mut context)?;
+    }
+    context.finish()?;
+
+    state.gl().depth_mask(true);
+    state.gl().disable(WebGl2RenderingContext::DEPTH_TEST);
+    state.gl().disable(WebGl2RenderingContext::CULL_FACE);
+    state.gl().cull_face(WebGl2RenderingContext::BACK);
+    state.gl().disable(WebGl2RenderingContext::BLEND);
+    state
+        .gl()
+        .blend_func(WebGl2RenderingContext::ONE, WebGl2RenderingContext::ZERO);
+
+    Ok(())
+}
+
+/// Draws translucent entities into accumulation and weight buffers of weighted blended order-independent transparency.
+/// Colors and weights are summed up and revealages are multiplied,
+/// blending of both draw buffers shares a single blend function since WebGL 2 could not set blend function per draw buffer.
+fn draw_oit_entities(
+    state: &mut FrameState,
+    draw_state: DrawState,
+    collected_entities: &CollectedEntities,
+) -> Result<(), Error> {
+    state.gl().enable(WebGl2RenderingContext::DEPTH_TEST);
+    state.gl().depth_mask(false);
+    state.gl().enable(WebGl2RenderingContext::BLEND);
+    state.gl().blend_equation(WebGl2RenderingContext::FUNC_ADD);
+    state.gl().blend_func_separate(
+        WebGl2RenderingContext::ONE,
+        WebGl2RenderingContext::ONE,
+        WebGl2RenderingContext::ZERO,
+        WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+    );
+
+    let mut context = DrawContext::new(collected_entities);
+
+    // order of translucent entities does not matter
+    for entity in collected_entities.translucent_entities() {
+        // transparency entities never cull face
+        let Some(entity) = entity.upgrade() else {
+            continue;
+        };
+        draw_entity(state, draw_state, false, entity, &mut context)?;
     }
+    context.finish()?;
 
     state.gl().depth_mask(true);
     state.gl().disable(WebGl2RenderingContext::DEPTH_TEST);
@@ -120,15 +212,61 @@ fn draw_translucent_entities(
     Ok(())
 }
 
+/// Program and textures left in use by the previous draw,
+/// which are reused by the next draw sharing the same program to avoid state changes.
+struct DrawContext<'a> {
+    program: Option<Program>,
+    textures: Vec<Uuid>,
+    statistics: RefMut<'a, DrawStatistics>,
+}
+
+impl<'a> DrawContext<'a> {
+    fn new(collected_entities: &'a CollectedEntities) -> Self {
+        Self {
+            program: None,
+            textures: Vec::new(),
+            statistics: collected_entities.statistics_mut(),
+        }
+    }
+
+    /// Unuses program left by the previous draw.
+    fn finish(&mut self) -> Result<(), Error> {
+        self.textures.clear();
+        match self.program.take() {
+            Some(program) => program.unuse_program(),
+            None => Ok(()),
+        }
+    }
+}
+
 fn prepare_program<'a, 'b, 'c>(
     state: &'a mut FrameState,
     draw_state: DrawState,
     material: &'b dyn StandardMaterial,
+    instanced: bool,
+    context: &mut DrawContext,
 ) -> Result<Program, Error> {
-    let source = StandardMaterialProgramSource::new(material, draw_state);
+    let source = StandardMaterialProgramSource::new(material, draw_state, instanced);
     let program = state
         .program_store_mut()
         .get_or_compile_program(&source)?;
+
+    let textures = material.textures();
+    if let Some(using) = context.program.as_ref() {
+        if using.name() == program.name() {
+            // textures bound by the previous draw are kept if the same textures are used
+            if textures.is_empty() || textures != context.textures {
+                program.unbind_uniforms()?;
+            }
+            context.textures = textures;
+            return Ok(program);
+        }
+    }
+
+    context.finish()?;
+    context.program = Some(program.clone());
+    context.textures = textures;
+    context.statistics.add_program_switch();
     program.use_program()?;
     // binds atoy_Universal
     program.mount_uniform_block_by_binding(
@@ -136,13 +274,30 @@ fn prepare_program<'a, 'b, 'c>(
         UBO_UNIVERSAL_UNIFORM_BLOCK_MOUNT_POINT,
     )?;
 
-    if let DrawState::Draw { lighting, bloom } = draw_state {
+    if let DrawState::Draw {
+        lighting,
+        bloom,
+        shadow,
+        clustered,
+        ..
+    } = draw_state
+    {
         // binds atoy_Lights
         if lighting {
             program.mount_uniform_block_by_binding(
                 &UBO_LIGHTS_BLOCK_BINDING,
                 UBO_LIGHTS_UNIFORM_BLOCK_MOUNT_POINT,
             )?;
+
+            // binds atoy_Shadows and shadow maps
+            if shadow {
+                mount_shadows(&program)?;
+            }
+
+            // binds atoy_Clusters and cluster textures
+            if clustered {
+                mount_clusters(&program)?;
+            }
         }
 
         // binds bloom blur threshold
@@ -163,6 +318,7 @@ fn draw_entity(
     draw_state: DrawState,
     should_cull_face: bool,
     entity: Rc<RefCell<dyn Entity>>,
+    context: &mut DrawContext,
 ) -> Result<(), Error> {
     // tries vertex array object entity
     let vao = match entity.borrow_mut().as_vertex_array_object_entity_mut() {
@@ -186,7 +342,32 @@ fn draw_entity(
 
     let entity = entity.borrow_mut();
     let geometry = entity.geometry().unwrap();
+    draw_geometry(
+        state,
+        draw_state,
+        should_cull_face,
+        &*entity,
+        geometry,
+        vao,
+        context,
+    )
+}
+
+/// Draws a geometry using model matrix and material of an entity.
+/// Attributes are bound into `vao` if it is newly created.
+fn draw_geometry(
+    state: &mut FrameState,
+    draw_state: DrawState,
+    should_cull_face: bool,
+    entity: &dyn Entity,
+    geometry: &dyn Geometry,
+    vao: Option<(WebGlVertexArrayObject, bool)>,
+    context: &mut DrawContext,
+) -> Result<(), Error> {
     let material = entity.material().unwrap();
+    let instance_count = entity
+        .as_instanced_entity()
+        .map(|instanced| instanced.instance_count());
 
     // culls face
     if should_cull_face {
@@ -200,32 +381,41 @@ fn draw_entity(
         state.gl().disable(WebGl2RenderingContext::CULL_FACE);
     }
 
-    let program = prepare_program(state, draw_state, material)?;
+    let program = prepare_program(
+        state,
+        draw_state,
+        material,
+        instance_count.is_some(),
+        context,
+    )?;
     match vao {
         Some((vao, is_new)) => {
             program.bind_vertex_array_object(vao)?;
             if is_new {
                 program.bind_attributes(
                     Some(&state),
-                    Some(&*entity),
+                    Some(entity),
                     Some(geometry),
                     Some(material),
                 )?;
             }
         }
         None => {
-            program.bind_attributes(
-                Some(&state),
-                Some(&*entity),
-                Some(geometry),
-                Some(material),
-            )?;
+            program.bind_attributes(Some(&state), Some(entity), Some(geometry), Some(material))?;
         }
     };
-    program.bind_uniforms(Some(&state), Some(&*entity), Some(geometry), Some(material))?;
-    program.bind_uniform_blocks(Some(&state), Some(&*entity), Some(geometry), Some(material))?;
-    Draw::from_geometry(geometry).draw(state.gl(), Some(state.buffer_store()))?;
-    program.unuse_program()?;
+    program.bind_uniforms(Some(&state), Some(entity), Some(geometry), Some(material))?;
+    program.bind_uniform_blocks(Some(&state), Some(entity), Some(geometry), Some(material))?;
+    let draw = match instance_count {
+        Some(instance_count) => Draw::from_geometry_instanced(geometry, instance_count),
+        None => Draw::from_geometry(geometry),
+    };
+    draw.draw(state.gl(), Some(state.buffer_store()))?;
+    context.statistics.add_draw_call();
+
+    // program is left in use for the next draw
+    program.unbind_vertex_array_object()?;
+    program.unbind_attributes()?;
 
     Ok(())
 }
@@ -233,13 +423,15 @@ fn draw_entity(
 struct StandardMaterialProgramSource<'a> {
     material: &'a dyn StandardMaterial,
     draw_state: DrawState,
+    instanced: bool,
 }
 
 impl<'a> StandardMaterialProgramSource<'a> {
-    fn new(material: &'a dyn StandardMaterial, draw_state: DrawState) -> Self {
+    fn new(material: &'a dyn StandardMaterial, draw_state: DrawState, instanced: bool) -> Self {
         Self {
             material,
             draw_state,
+            instanced,
         }
     }
 }
@@ -270,7 +462,7 @@ impl<'a> ProgramSource for StandardMaterialProgramSource<'a> {
 
         let type_name = match self.draw_state {
             DrawState::Draw { .. } => "Draw",
-            DrawState::GBuffer => "GBuffer",
+            DrawState::GBuffer { .. } => "GBuffer",
         };
         let defines = self.universal_defines().as_ref().join_defines();
         let vertex_defines = self.vertex_defines().as_ref().join_defines();
@@ -301,7 +493,9 @@ impl<'a> ProgramSource for StandardMaterialProgramSource<'a> {
     fn fragment_source(&self) -> Cow<'_, str> {
         match self.draw_state {
             DrawState::Draw { .. } => Cow::Borrowed(include_str!("../shaders/draw.frag")),
-            DrawState::GBuffer => Cow::Borrowed(include_str!("../shaders/gbuffer.frag")),
+            DrawState::GBuffer { .. } => {
+                Cow::Borrowed(include_str!("../shaders/gbuffer.frag"))
+            }
         }
     }
 
@@ -314,7 +508,7 @@ impl<'a> ProgramSource for StandardMaterialProgramSource<'a> {
             )));
         }
         match (self.material.use_normal(), self.draw_state) {
-            (true, _) | (false, DrawState::GBuffer) => {
+            (true, _) | (false, DrawState::GBuffer { .. }) => {
                 defines.push(Define::WithoutValue(Cow::Borrowed("USE_NORMAL")));
             }
             (false, DrawState::Draw { lighting, .. }) => {
@@ -339,8 +533,18 @@ impl<'a> ProgramSource for StandardMaterialProgramSource<'a> {
                 "USE_CALCULATED_BITANGENT",
             )));
         }
+        if self.instanced {
+            defines.push(Define::WithoutValue(Cow::Borrowed("USE_INSTANCING")));
+        }
 
-        if let DrawState::Draw { lighting, bloom } = self.draw_state {
+        if let DrawState::Draw {
+            lighting,
+            bloom,
+            shadow,
+            clustered,
+            oit,
+        } = self.draw_state
+        {
             if lighting {
                 defines.extend([
                     Define::WithoutValue(Cow::Borrowed("USE_LIGHTING")),
@@ -361,11 +565,31 @@ impl<'a> ProgramSource for StandardMaterialProgramSource<'a> {
                         Cow::Borrowed(MAX_AREA_LIGHTS_STRING),
                     ),
                 ]);
+
+                if shadow {
+                    defines.push(Define::WithoutValue(Cow::Borrowed("USE_SHADOW")));
+                }
+
+                if clustered {
+                    defines.push(Define::WithoutValue(Cow::Borrowed(
+                        "USE_CLUSTERED_LIGHTING",
+                    )));
+                }
             }
 
             if bloom {
                 defines.push(Define::WithoutValue(Cow::Borrowed("USE_BLOOM")));
             }
+
+            if oit {
+                defines.push(Define::WithoutValue(Cow::Borrowed("USE_OIT")));
+            }
+        }
+
+        // lights are not applied in gbuffer, but material may still calculate lighting independent parts,
+        // such as image based lighting
+        if let DrawState::GBuffer { lighting: true } = self.draw_state {
+            defines.push(Define::WithoutValue(Cow::Borrowed("USE_LIGHTING")));
         }
 
         Cow::Owned(defines)
@@ -0,0 +1,229 @@
+use std::borrow::Cow;
+
+use web_sys::WebGl2RenderingContext;
+
+use crate::{
+    pipeline::webgl::collector::CollectedEntities,
+    renderer::webgl::{
+        blit::{Blit, BlitFlilter, BlitMask},
+        error::Error,
+        framebuffer::{
+            AttachmentSource, ClearPolicy, Framebuffer, FramebufferAttachmentTarget,
+            FramebufferBuilder, FramebufferTarget,
+        },
+        program::{Define, ProgramSource},
+        renderbuffer::RenderbufferInternalFormat,
+        state::FrameState,
+        texture::{TextureUncompressedInternalFormat, TextureUnit},
+        uniform::{UniformBinding, UniformValue},
+    },
+};
+
+use super::{
+    draw_oit_entities, DrawState, BLOOM_THRESHOLD_UNIFORM_BINDING, BLOOM_THRESHOLD_VALUES,
+};
+
+const ACCUMULATION_TEXTURE_UNIFORM_NAME: &'static str = "u_AccumulationTexture";
+const ACCUMULATION_TEXTURE_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(ACCUMULATION_TEXTURE_UNIFORM_NAME));
+
+const WEIGHT_TEXTURE_UNIFORM_NAME: &'static str = "u_WeightTexture";
+const WEIGHT_TEXTURE_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(WEIGHT_TEXTURE_UNIFORM_NAME));
+
+/// Weighted blended order-independent transparency for translucent entities.
+///
+/// Translucent entities are drawn in any order into an accumulation buffer,
+/// storing weighted colors in `rgb` and revealage in `a`,
+/// and a weight buffer, storing weighted transparencies in `r`.
+/// Both buffers are then composited over the framebuffer shaded opaque entities.
+/// Depth of opaque entities is copied from that framebuffer, so translucent entities behind them are discarded.
+///
+/// Buffers are half float textures, requiring `EXT_color_buffer_float`.
+pub struct StandardWeightedBlendedOit {
+    framebuffer: Framebuffer,
+    shader: WeightedBlendedOitComposite,
+}
+
+impl StandardWeightedBlendedOit {
+    pub fn new() -> Self {
+        Self {
+            framebuffer: FramebufferBuilder::new()
+                .set_color_attachment0(AttachmentSource::new_texture_with_clear_policy(
+                    TextureUncompressedInternalFormat::RGBA16F,
+                    ClearPolicy::ColorFloat([0.0, 0.0, 0.0, 1.0]),
+                ))
+                .set_color_attachment1(AttachmentSource::new_texture_with_clear_policy(
+                    TextureUncompressedInternalFormat::R16F,
+                    ClearPolicy::ColorFloat([0.0, 0.0, 0.0, 0.0]),
+                ))
+                .set_depth_stencil_attachment(AttachmentSource::new_renderbuffer(
+                    RenderbufferInternalFormat::DEPTH32F_STENCIL8,
+                ))
+                .build(),
+            shader: WeightedBlendedOitComposite { bloom: false },
+        }
+    }
+
+    /// Draws translucent entities and composites them into `framebuffer`.
+    ///
+    /// `framebuffer` should be bound as draw framebuffer already and is bound again after compositing.
+    /// Its depth stencil attachment should be [`RenderbufferInternalFormat::DEPTH32F_STENCIL8`]
+    /// or [`TextureUncompressedInternalFormat::DEPTH32F_STENCIL8`].
+    pub(super) fn draw(
+        &mut self,
+        state: &mut FrameState,
+        framebuffer: &mut Framebuffer,
+        draw_state: DrawState,
+        collected_entities: &CollectedEntities,
+    ) -> Result<(), Error> {
+        let DrawState::Draw {
+            lighting,
+            bloom,
+            shadow,
+            clustered,
+            ..
+        } = draw_state
+        else {
+            return Ok(());
+        };
+
+        // copies depth of opaque entities
+        framebuffer.unbind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
+        self.framebuffer.init(state.gl())?;
+        Blit::new(
+            state.gl(),
+            framebuffer,
+            &mut self.framebuffer,
+            BlitMask::DEPTH_BUFFER_BIT,
+            BlitFlilter::NEAREST,
+        )
+        .blit()?;
+
+        self.framebuffer.bind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
+        // do not clear depth buffer!!!
+        self.framebuffer
+            .clear_buffer(FramebufferAttachmentTarget::COLOR_ATTACHMENT0)?;
+        self.framebuffer
+            .clear_buffer(FramebufferAttachmentTarget::COLOR_ATTACHMENT1)?;
+        draw_oit_entities(
+            state,
+            DrawState::Draw {
+                lighting,
+                bloom: false,
+                shadow,
+                clustered,
+                oit: true,
+            },
+            collected_entities,
+        )?;
+        self.framebuffer
+            .unbind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
+
+        framebuffer.bind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
+        self.composite(state, bloom)?;
+
+        Ok(())
+    }
+
+    fn composite(&mut self, state: &mut FrameState, bloom: bool) -> Result<(), Error> {
+        self.shader.bloom = bloom;
+        let program = state
+            .program_store_mut()
+            .get_or_compile_program(&self.shader)?;
+        program.use_program()?;
+        program.bind_uniform_value_by_binding(
+            &ACCUMULATION_TEXTURE_UNIFORM_BINDING,
+            &UniformValue::Integer1(0),
+            None,
+        )?;
+        program.bind_uniform_value_by_binding(
+            &WEIGHT_TEXTURE_UNIFORM_BINDING,
+            &UniformValue::Integer1(1),
+            None,
+        )?;
+        if bloom {
+            program.bind_uniform_value_by_binding(
+                &BLOOM_THRESHOLD_UNIFORM_BINDING,
+                &UniformValue::FloatVector3(BLOOM_THRESHOLD_VALUES),
+                None,
+            )?;
+        }
+
+        // outputs colors not premultiplied, alpha is blended as premultiplied
+        state.gl().enable(WebGl2RenderingContext::BLEND);
+        state.gl().blend_equation(WebGl2RenderingContext::FUNC_ADD);
+        state.gl().blend_func_separate(
+            WebGl2RenderingContext::SRC_ALPHA,
+            WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+            WebGl2RenderingContext::ONE,
+            WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+        );
+
+        state.do_computation([
+            (
+                self.framebuffer
+                    .texture(FramebufferAttachmentTarget::COLOR_ATTACHMENT0)?
+                    .unwrap(),
+                TextureUnit::TEXTURE0,
+            ),
+            (
+                self.framebuffer
+                    .texture(FramebufferAttachmentTarget::COLOR_ATTACHMENT1)?
+                    .unwrap(),
+                TextureUnit::TEXTURE1,
+            ),
+        ])?;
+
+        state.gl().disable(WebGl2RenderingContext::BLEND);
+        state
+            .gl()
+            .blend_func(WebGl2RenderingContext::ONE, WebGl2RenderingContext::ZERO);
+
+        program.unuse_program()?;
+
+        Ok(())
+    }
+}
+
+struct WeightedBlendedOitComposite {
+    bloom: bool,
+}
+
+impl ProgramSource for WeightedBlendedOitComposite {
+    fn name(&self) -> Cow<'_, str> {
+        if self.bloom {
+            Cow::Borrowed("WeightedBlendedOitComposite_Bloom")
+        } else {
+            Cow::Borrowed("WeightedBlendedOitComposite")
+        }
+    }
+
+    fn vertex_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("../shaders/computation.vert"))
+    }
+
+    fn fragment_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("../shaders/oit_composite.frag"))
+    }
+
+    fn universal_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn vertex_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn fragment_defines(&self) -> Cow<'_, [Define<'_>]> {
+        if self.bloom {
+            Cow::Borrowed(&[Define::WithoutValue(Cow::Borrowed("USE_BLOOM"))])
+        } else {
+            Cow::Borrowed(&[])
+        }
+    }
+
+    fn snippet(&self, _: &str) -> Option<Cow<'_, str>> {
+        None
+    }
+}
@@ -1,26 +1,33 @@
 use std::{
     borrow::Cow,
     cell::RefCell,
+    ops::Range,
     rc::{Rc, Weak},
-    time::Duration,
 };
 
 use gl_matrix4rust::vec3::Vec3;
+use hashbrown::HashSet;
 use log::warn;
 use uuid::Uuid;
 use wasm_bindgen::JsCast;
-use web_sys::{js_sys::Uint32Array, HtmlCanvasElement, WebGl2RenderingContext};
+use web_sys::{
+    js_sys::{Uint32Array, Uint8Array},
+    HtmlCanvasElement, WebGl2RenderingContext,
+};
 
 use crate::{
-    anewthing::web::webgl::client_wait::WebGlClientWait,
     entity::Entity,
+    geometry::IndexedGeometry,
     material::Transparency,
     pipeline::webgl::{
         collector::CollectedEntities, UBO_UNIVERSAL_UNIFORMS_BLOCK_BINDING,
         UBO_UNIVERSAL_UNIFORM_BLOCK_MOUNT_POINT,
     },
     renderer::webgl::{
-        draw::Draw,
+        attribute::AttributeValue,
+        buffer::BufferDataType,
+        client_wait::client_wait_async,
+        draw::{Draw, DrawMode, ElementIndicesDataType},
         error::Error,
         framebuffer::{
             AttachmentSource, ClearPolicy, Framebuffer, FramebufferBuilder, FramebufferTarget,
@@ -37,6 +44,118 @@ use crate::{
     },
 };
 
+/// Retry interval in milliseconds when waiting for pixels reading back asynchronously.
+const CLIENT_WAIT_RETRY_INTERVAL: usize = 4;
+
+/// Screen region for picking multiple entities, in window coordinates with origin at top left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PickingRegion {
+    /// Rectangle region, width and height could be negative when dragging towards top left.
+    Rectangle {
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
+    /// Polygon region of lasso selection, closes from last point to first point implicitly.
+    Lasso(Vec<(i32, i32)>),
+}
+
+impl PickingRegion {
+    /// Returns bounding rectangle of the region as `(x, y, width, height)`.
+    pub fn bounds(&self) -> (i32, i32, i32, i32) {
+        match self {
+            PickingRegion::Rectangle {
+                x,
+                y,
+                width,
+                height,
+            } => (
+                (*x).min(x + width),
+                (*y).min(y + height),
+                width.abs(),
+                height.abs(),
+            ),
+            PickingRegion::Lasso(points) => {
+                if points.len() < 3 {
+                    return (0, 0, 0, 0);
+                }
+
+                let (mut min_x, mut min_y) = (i32::MAX, i32::MAX);
+                let (mut max_x, mut max_y) = (i32::MIN, i32::MIN);
+                for (x, y) in points {
+                    min_x = min_x.min(*x);
+                    min_y = min_y.min(*y);
+                    max_x = max_x.max(*x);
+                    max_y = max_y.max(*y);
+                }
+                (min_x, min_y, max_x - min_x, max_y - min_y)
+            }
+        }
+    }
+
+    /// Returns `true` if a position is inside the region.
+    /// Lasso region uses even-odd rule.
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        match self {
+            PickingRegion::Rectangle { .. } => {
+                let (bx, by, width, height) = self.bounds();
+                x >= bx as f64
+                    && x < (bx + width) as f64
+                    && y >= by as f64
+                    && y < (by + height) as f64
+            }
+            PickingRegion::Lasso(points) => {
+                if points.len() < 3 {
+                    return false;
+                }
+
+                let mut inside = false;
+                let mut j = points.len() - 1;
+                for i in 0..points.len() {
+                    let (xi, yi) = (points[i].0 as f64, points[i].1 as f64);
+                    let (xj, yj) = (points[j].0 as f64, points[j].1 as f64);
+                    if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+                        inside = !inside;
+                    }
+                    j = i;
+                }
+                inside
+            }
+        }
+    }
+}
+
+/// Picked sub element of an entity.
+pub struct PickedElement {
+    entity: Rc<RefCell<dyn Entity>>,
+    vertex: u32,
+    primitive: Option<u32>,
+}
+
+impl PickedElement {
+    /// Returns picked entity.
+    pub fn entity(&self) -> &Rc<RefCell<dyn Entity>> {
+        &self.entity
+    }
+
+    /// Returns index of the provoking vertex, which is the last vertex of the picked primitive.
+    /// For indexed geometry, it is the value of the element index rather than the position in indices.
+    pub fn vertex(&self) -> u32 {
+        self.vertex
+    }
+
+    /// Returns index of the picked primitive, such as triangle index when drawing triangles.
+    ///
+    /// For indexed geometry, a vertex could provoke multiple primitives,
+    /// the one closest to the picked position is taken, which requires positions of the geometry
+    /// in [`BufferDataType::FLOAT`]. Returns `None` if positions are unavailable
+    /// or the entity is instanced, unless only one primitive is provoked by the picked vertex.
+    pub fn primitive(&self) -> Option<u32> {
+        self.primitive
+    }
+}
+
 pub struct StandardPicking {
     framebuffer: Framebuffer,
     pixel: Uint32Array,
@@ -90,57 +209,79 @@ impl StandardPicking {
             );
         }
 
-        let program = state
-            .program_store_mut()
-            .get_or_compile_program(&PickingShaderProvider)?;
-        program.use_program()?;
-        program.mount_uniform_block_by_binding(
-            &UBO_UNIVERSAL_UNIFORMS_BLOCK_BINDING,
-            UBO_UNIVERSAL_UNIFORM_BLOCK_MOUNT_POINT,
-        )?;
-
-        // render each entity by picking material
-        for (index, entity) in entities.into_iter().enumerate() {
-            let Some(entity) = entity.upgrade() else {
+        // instanced entities are rendered by another program applying per instance model matrices
+        for instanced in [false, true] {
+            let has_entities = entities.iter().any(|entity| {
+                entity.upgrade().map_or(false, |entity| {
+                    entity.borrow().as_instanced_entity().is_some() == instanced
+                })
+            });
+            if !has_entities {
                 continue;
-            };
-            let entity = entity.borrow();
-
-            // skips if overflows
-            if index > max_entities_len {
-                break;
             }
 
-            let Some(geometry) = entity.geometry() else {
-                continue;
-            };
+            let program = state
+                .program_store_mut()
+                .get_or_compile_program(&PickingShaderProvider { instanced })?;
+            program.use_program()?;
+            program.mount_uniform_block_by_binding(
+                &UBO_UNIVERSAL_UNIFORMS_BLOCK_BINDING,
+                UBO_UNIVERSAL_UNIFORM_BLOCK_MOUNT_POINT,
+            )?;
 
-            // do not pick entity has no material or has transparent material or not ready
-            if let Some(material) = entity.material() {
-                if material.transparency() == Transparency::Transparent {
+            // render each entity by picking material
+            for (index, entity) in entities.into_iter().enumerate() {
+                let Some(entity) = entity.upgrade() else {
                     continue;
+                };
+                let entity = entity.borrow();
+
+                // skips if overflows
+                if index > max_entities_len {
+                    break;
                 }
-                if !material.ready() {
+
+                if entity.as_instanced_entity().is_some() != instanced {
                     continue;
                 }
-            } else {
-                continue;
-            };
 
-            program.bind_uniform_value_by_binding(
-                &INDEX_UNIFORM_BINDING,
-                &UniformValue::UnsignedInteger1((index + 1) as u32),
-                None,
-            )?;
-            program.bind_uniforms(Some(&state), Some(&*entity), Some(geometry), None)?;
-            program.bind_attributes(Some(&state), Some(&*entity), Some(geometry), None)?;
-            Draw::from_geometry(geometry).draw(state.gl(), Some(state.buffer_store()))?;
-            program.unbind_attributes()?;
+                let Some(geometry) = entity.geometry() else {
+                    continue;
+                };
+
+                // do not pick entity has no material or has transparent material or not ready
+                if let Some(material) = entity.material() {
+                    if material.transparency() == Transparency::Transparent {
+                        continue;
+                    }
+                    if !material.ready() {
+                        continue;
+                    }
+                } else {
+                    continue;
+                };
+
+                program.bind_uniform_value_by_binding(
+                    &INDEX_UNIFORM_BINDING,
+                    &UniformValue::UnsignedInteger1((index + 1) as u32),
+                    None,
+                )?;
+                program.bind_uniforms(Some(&state), Some(&*entity), Some(geometry), None)?;
+                program.bind_attributes(Some(&state), Some(&*entity), Some(geometry), None)?;
+                let draw = match entity.as_instanced_entity() {
+                    Some(instanced) => {
+                        Draw::from_geometry_instanced(geometry, instanced.instance_count())
+                    }
+                    None => Draw::from_geometry(geometry),
+                };
+                draw.draw(state.gl(), Some(state.buffer_store()))?;
+                program.unbind_attributes()?;
+            }
+            program.unuse_program()?;
         }
 
         self.framebuffer
             .unbind(FramebufferTarget::DRAW_FRAMEBUFFER)?;
-        program.unuse_program()?;
         state.gl().disable(WebGl2RenderingContext::DEPTH_TEST);
 
         self.gl = Some(state.gl().clone());
@@ -169,6 +310,8 @@ impl StandardPicking {
         };
 
         self.framebuffer.bind(FramebufferTarget::READ_FRAMEBUFFER)?;
+        self.framebuffer
+            .set_read_buffer(OperableBuffer::COLOR_ATTACHMENT0)?;
         self.framebuffer.read_pixels(
             window_position_x,
             canvas.height() as i32 - window_position_y,
@@ -194,6 +337,9 @@ impl StandardPicking {
     }
 
     /// Returns picked entity.
+    ///
+    /// Pixels are read back into a pixel pack buffer and fetched after GPU commands completed,
+    /// without stalling the pipeline.
     pub async fn pick_entity_async(
         &mut self,
         window_position_x: i32,
@@ -213,15 +359,163 @@ impl StandardPicking {
             return Ok(None);
         };
 
-        let client_await = WebGlClientWait::new(Duration::from_secs(0));
-        client_await.client_wait(&self.gl.as_ref().unwrap()).await.unwrap();
+        let pixels = self
+            .read_indices_async(
+                window_position_x,
+                canvas.height() as i32 - window_position_y,
+                1,
+                1,
+            )
+            .await?;
+
+        let index = pixels[0] as usize;
+        if index >= 1 {
+            Ok(last_entities
+                .get(index - 1)
+                .and_then(|entity| entity.upgrade())
+                .map(|entity| entity.borrow().id().clone()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns entities visible inside a region.
+    /// If `fully_inside` is `true`, entities having any visible pixel outside the region are excluded.
+    pub fn pick_entities(
+        &mut self,
+        region: &PickingRegion,
+        fully_inside: bool,
+        collected_entities: &CollectedEntities,
+    ) -> Result<Vec<Rc<RefCell<dyn Entity>>>, Error> {
+        if collected_entities.entities().len() == 0 {
+            return Ok(Vec::new());
+        }
+        let Some(gl) = self.gl.as_ref() else {
+            return Ok(Vec::new());
+        };
+        let Some(canvas) = gl
+            .canvas()
+            .and_then(|canvas| canvas.dyn_into::<HtmlCanvasElement>().ok())
+        else {
+            return Ok(Vec::new());
+        };
+        let (canvas_width, canvas_height) = (canvas.width() as i32, canvas.height() as i32);
+        let Some((x, y, width, height)) =
+            reading_rectangle(region, fully_inside, canvas_width, canvas_height)
+        else {
+            return Ok(Vec::new());
+        };
+
+        let pixels = Uint32Array::new_with_length((width * height) as u32);
+        self.framebuffer.bind(FramebufferTarget::READ_FRAMEBUFFER)?;
+        self.framebuffer
+            .set_read_buffer(OperableBuffer::COLOR_ATTACHMENT0)?;
+        self.framebuffer.read_pixels(
+            x,
+            canvas_height - y - height,
+            width,
+            height,
+            TextureUncompressedPixelFormat::RED_INTEGER,
+            TextureUncompressedPixelDataType::UNSIGNED_INT,
+            &pixels,
+            0,
+        )?;
+        self.framebuffer
+            .unbind(FramebufferTarget::READ_FRAMEBUFFER)?;
+
+        let indices = select_indices(
+            &pixels.to_vec(),
+            (x, y, width, height),
+            region,
+            fully_inside,
+        );
+        Ok(indices
+            .into_iter()
+            .filter_map(|index| {
+                collected_entities
+                    .entities()
+                    .get(index)
+                    .and_then(|entity| entity.upgrade())
+            })
+            .collect())
+    }
+
+    /// Returns ids of entities visible inside a region.
+    /// If `fully_inside` is `true`, entities having any visible pixel outside the region are excluded.
+    ///
+    /// Pixels are read back into a pixel pack buffer and fetched after GPU commands completed,
+    /// without stalling the pipeline.
+    pub async fn pick_entities_async(
+        &mut self,
+        region: PickingRegion,
+        fully_inside: bool,
+        last_entities: Vec<Weak<RefCell<dyn Entity>>>,
+    ) -> Result<Vec<Uuid>, Error> {
+        if last_entities.len() == 0 {
+            return Ok(Vec::new());
+        }
+        let Some(gl) = self.gl.as_ref() else {
+            return Ok(Vec::new());
+        };
+        let Some(canvas) = gl
+            .canvas()
+            .and_then(|canvas| canvas.dyn_into::<HtmlCanvasElement>().ok())
+        else {
+            return Ok(Vec::new());
+        };
+        let (canvas_width, canvas_height) = (canvas.width() as i32, canvas.height() as i32);
+        let Some((x, y, width, height)) =
+            reading_rectangle(&region, fully_inside, canvas_width, canvas_height)
+        else {
+            return Ok(Vec::new());
+        };
+
+        let pixels = self
+            .read_indices_async(x, canvas_height - y - height, width, height)
+            .await?;
+
+        let indices = select_indices(&pixels, (x, y, width, height), &region, fully_inside);
+        Ok(indices
+            .into_iter()
+            .filter_map(|index| {
+                last_entities
+                    .get(index)
+                    .and_then(|entity| entity.upgrade())
+                    .map(|entity| entity.borrow().id().clone())
+            })
+            .collect())
+    }
+
+    /// Returns picked entity and its picked vertex and primitive.
+    pub fn pick_element(
+        &mut self,
+        window_position_x: i32,
+        window_position_y: i32,
+        collected_entities: &CollectedEntities,
+    ) -> Result<Option<PickedElement>, Error> {
+        let Some(entity) =
+            self.pick_entity(window_position_x, window_position_y, collected_entities)?
+        else {
+            return Ok(None);
+        };
+        let Some(canvas) = self
+            .gl
+            .as_ref()
+            .and_then(|gl| gl.canvas())
+            .and_then(|canvas| canvas.dyn_into::<HtmlCanvasElement>().ok())
+        else {
+            return Ok(None);
+        };
+
         self.framebuffer.bind(FramebufferTarget::READ_FRAMEBUFFER)?;
+        self.framebuffer
+            .set_read_buffer(OperableBuffer::COLOR_ATTACHMENT1)?;
         self.framebuffer.read_pixels(
             window_position_x,
             canvas.height() as i32 - window_position_y,
             1,
             1,
-            TextureUncompressedPixelFormat::RED_INTEGER,
+            TextureUncompressedPixelFormat::RGBA_INTEGER,
             TextureUncompressedPixelDataType::UNSIGNED_INT,
             &self.pixel,
             0,
@@ -229,15 +523,31 @@ impl StandardPicking {
         self.framebuffer
             .unbind(FramebufferTarget::READ_FRAMEBUFFER)?;
 
-        let index = self.pixel.get_index(0) as usize;
-        if index >= 1 {
-            Ok(last_entities
-                .get(index - 1)
-                .and_then(|entity| entity.upgrade())
-                .map(|entity| entity.borrow().id().clone()))
-        } else {
-            Ok(None)
-        }
+        // vertex id is stored in w component plus 1
+        let Some(vertex) = self.pixel.get_index(3).checked_sub(1) else {
+            return Ok(None);
+        };
+        // picked position in world space is stored in xyz components
+        let position =
+            [0, 1, 2].map(|i| f32::from_ne_bytes(self.pixel.get_index(i).to_ne_bytes()) as f64);
+        let primitive = {
+            let entity = entity.borrow();
+            match entity.geometry() {
+                Some(geometry) => match geometry.as_indexed_geometry() {
+                    Some(indexed) => {
+                        resolve_indexed_primitive(&*entity, indexed, vertex, position)?
+                    }
+                    None => primitive_index(geometry.draw_mode(), geometry.draw_range(), vertex),
+                },
+                None => None,
+            }
+        };
+
+        Ok(Some(PickedElement {
+            entity,
+            vertex,
+            primitive,
+        }))
     }
 
     /// Returns picked position.
@@ -280,9 +590,10 @@ impl StandardPicking {
             f32::from_ne_bytes(self.pixel.get_index(0).to_ne_bytes()),
             f32::from_ne_bytes(self.pixel.get_index(1).to_ne_bytes()),
             f32::from_ne_bytes(self.pixel.get_index(2).to_ne_bytes()),
-            f32::from_ne_bytes(self.pixel.get_index(3).to_ne_bytes()),
         ]; // converts unsigned int back to float
-        if position != [0.0, 0.0, 0.0, 0.0] {
+
+        // w component is 0 if nothing rendered at this pixel
+        if self.pixel.get_index(3) != 0 {
             Ok(Some(Vec3::<f64>::new(
                 position[0] as f64,
                 position[1] as f64,
@@ -292,17 +603,357 @@ impl StandardPicking {
             Ok(None)
         }
     }
+
+    /// Reads entity indices of a rectangle in framebuffer coordinates into a pixel pack buffer
+    /// and fetches them after GPU commands completed.
+    async fn read_indices_async(
+        &mut self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) -> Result<Vec<u32>, Error> {
+        let gl = self.gl.clone().unwrap();
+
+        // creates a buffer for each reading, in case of reading concurrently
+        let buffer = gl.create_buffer().ok_or(Error::CreateBufferFailure)?;
+        gl.bind_buffer(WebGl2RenderingContext::PIXEL_PACK_BUFFER, Some(&buffer));
+        gl.buffer_data_with_i32(
+            WebGl2RenderingContext::PIXEL_PACK_BUFFER,
+            width * height * 4,
+            WebGl2RenderingContext::STREAM_READ,
+        );
+        self.framebuffer.bind(FramebufferTarget::READ_FRAMEBUFFER)?;
+        self.framebuffer
+            .set_read_buffer(OperableBuffer::COLOR_ATTACHMENT0)?;
+        let result = self.framebuffer.read_pixels_to_pixel_pack_buffer(
+            x,
+            y,
+            width,
+            height,
+            TextureUncompressedPixelFormat::RED_INTEGER,
+            TextureUncompressedPixelDataType::UNSIGNED_INT,
+            0,
+        );
+        self.framebuffer
+            .unbind(FramebufferTarget::READ_FRAMEBUFFER)?;
+        gl.bind_buffer(WebGl2RenderingContext::PIXEL_PACK_BUFFER, None);
+        if let Err(err) = result {
+            gl.delete_buffer(Some(&buffer));
+            return Err(err);
+        }
+
+        let result = client_wait_async(&gl, None, 0, Some(CLIENT_WAIT_RETRY_INTERVAL)).await;
+        if let Err(err) = result {
+            gl.delete_buffer(Some(&buffer));
+            return Err(err);
+        }
+
+        let pixels = Uint32Array::new_with_length((width * height) as u32);
+        gl.bind_buffer(WebGl2RenderingContext::PIXEL_PACK_BUFFER, Some(&buffer));
+        gl.get_buffer_sub_data_with_i32_and_array_buffer_view(
+            WebGl2RenderingContext::PIXEL_PACK_BUFFER,
+            0,
+            &pixels,
+        );
+        gl.bind_buffer(WebGl2RenderingContext::PIXEL_PACK_BUFFER, None);
+        gl.delete_buffer(Some(&buffer));
+
+        Ok(pixels.to_vec())
+    }
+}
+
+/// Returns rectangle in window coordinates to read for picking entities inside a region,
+/// clamped to the canvas.
+/// Entire canvas is read if `fully_inside` is `true`, for finding entities leaking out of the region.
+fn reading_rectangle(
+    region: &PickingRegion,
+    fully_inside: bool,
+    canvas_width: i32,
+    canvas_height: i32,
+) -> Option<(i32, i32, i32, i32)> {
+    let (x, y, width, height) = if fully_inside {
+        (0, 0, canvas_width, canvas_height)
+    } else {
+        region.bounds()
+    };
+    let (x0, y0) = (x.max(0), y.max(0));
+    let (x1, y1) = (
+        (x + width).min(canvas_width),
+        (y + height).min(canvas_height),
+    );
+    if x1 <= x0 || y1 <= y0 {
+        None
+    } else {
+        Some((x0, y0, x1 - x0, y1 - y0))
+    }
+}
+
+/// Selects entity indices, starting from 0, from pixels of the index attachment.
+///
+/// `pixels` are read from a rectangle `(x, y, width, height)` in window coordinates,
+/// rows ordered from bottom to top as framebuffer does.
+/// A pixel belongs to the region if its center is inside the region.
+fn select_indices(
+    pixels: &[u32],
+    (x, y, width, height): (i32, i32, i32, i32),
+    region: &PickingRegion,
+    fully_inside: bool,
+) -> Vec<usize> {
+    let mut inside = HashSet::new();
+    let mut outside = HashSet::new();
+    for row in 0..height {
+        let window_y = y + height - 1 - row;
+        for column in 0..width {
+            let index = pixels[(row * width + column) as usize];
+            if index == 0 {
+                continue;
+            }
+
+            let window_x = x + column;
+            if region.contains(window_x as f64 + 0.5, window_y as f64 + 0.5) {
+                inside.insert(index);
+            } else if fully_inside {
+                outside.insert(index);
+            }
+        }
+    }
+
+    let mut indices = inside
+        .difference(&outside)
+        .map(|index| (*index - 1) as usize)
+        .collect::<Vec<_>>();
+    indices.sort();
+    indices
+}
+
+/// Returns index of the primitive provoked by a vertex of a non-indexed geometry.
+/// WebGL always takes the last vertex of a primitive as the provoking vertex.
+fn primitive_index(draw_mode: DrawMode, draw_range: Range<usize>, vertex: u32) -> Option<u32> {
+    let vertex = vertex.checked_sub(draw_range.start as u32)?;
+    match draw_mode {
+        DrawMode::POINTS => Some(vertex),
+        DrawMode::LINES => Some(vertex / 2),
+        DrawMode::LINE_STRIP => vertex.checked_sub(1),
+        // closing line of a loop is provoked by the first vertex
+        DrawMode::LINE_LOOP => match vertex {
+            0 => (draw_range.len() as u32).checked_sub(1),
+            _ => Some(vertex - 1),
+        },
+        DrawMode::TRIANGLES => Some(vertex / 3),
+        DrawMode::TRIANGLE_STRIP | DrawMode::TRIANGLE_FAN => vertex.checked_sub(2),
+    }
+}
+
+/// Resolves the primitive of an indexed geometry provoked by a vertex on CPU,
+/// reading indices and positions back from WebGL runtime.
+/// The primitive closest to `position` in world space is taken if the vertex provokes more than one.
+fn resolve_indexed_primitive(
+    entity: &dyn Entity,
+    geometry: &dyn IndexedGeometry,
+    vertex: u32,
+    position: [f64; 3],
+) -> Result<Option<u32>, Error> {
+    let Some(indices) = geometry.indices().read_back()? else {
+        return Ok(None);
+    };
+    let data_type = geometry.indices_data_type();
+    // draw range of indexed geometry is byte offset and count of indices
+    let draw_range = geometry.draw_range();
+    let start = draw_range.start;
+    let end = start + draw_range.len() * data_type.byte_length();
+    let indices = Uint8Array::new(&indices).to_vec();
+    let Some(indices) = indices.get(start..end) else {
+        return Ok(None);
+    };
+    let indices = decode_indices(indices, data_type);
+
+    let candidates = provoked_primitives(geometry.draw_mode(), &indices, vertex);
+    match candidates.as_slice() {
+        [] => return Ok(None),
+        [(primitive, _)] => return Ok(Some(*primitive)),
+        _ => {}
+    }
+
+    // instance of the picked position is unknown
+    if entity.as_instanced_entity().is_some() {
+        return Ok(None);
+    }
+    let Some(AttributeValue::ArrayBuffer {
+        buffer,
+        component_size,
+        data_type: BufferDataType::FLOAT,
+        bytes_stride,
+        byte_offset,
+        ..
+    }) = geometry.positions()
+    else {
+        return Ok(None);
+    };
+    if (component_size as usize) < 3 {
+        return Ok(None);
+    }
+    let Some(positions) = buffer.read_back()? else {
+        return Ok(None);
+    };
+    let positions = Uint8Array::new(&positions).to_vec();
+    let stride = match bytes_stride {
+        0 => component_size as usize * 4,
+        stride => stride,
+    };
+    let model_matrix = *entity.compose_model_matrix();
+
+    Ok(closest_primitive(&candidates, position, |index| {
+        let offset = byte_offset + index as usize * stride;
+        let bytes = positions.get(offset..offset + 12)?;
+        let [x, y, z] = [0, 4, 8].map(|i| {
+            f32::from_ne_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]) as f64
+        });
+        let position = model_matrix * Vec3::<f64>::new(x, y, z);
+        Some([*position.x(), *position.y(), *position.z()])
+    }))
+}
+
+/// Decodes element indices from bytes.
+fn decode_indices(bytes: &[u8], data_type: ElementIndicesDataType) -> Vec<u32> {
+    match data_type {
+        ElementIndicesDataType::UNSIGNED_BYTE => bytes.iter().map(|index| *index as u32).collect(),
+        ElementIndicesDataType::UNSIGNED_SHORT => bytes
+            .chunks_exact(2)
+            .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]) as u32)
+            .collect(),
+        ElementIndicesDataType::UNSIGNED_INT => bytes
+            .chunks_exact(4)
+            .map(|bytes| u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect(),
+    }
+}
+
+/// Returns count of primitives drawn from `len` vertices.
+fn primitive_count(draw_mode: DrawMode, len: usize) -> usize {
+    match draw_mode {
+        DrawMode::POINTS => len,
+        DrawMode::LINES => len / 2,
+        DrawMode::LINE_STRIP => len.saturating_sub(1),
+        DrawMode::LINE_LOOP => match len {
+            0 | 1 => 0,
+            _ => len,
+        },
+        DrawMode::TRIANGLES => len / 3,
+        DrawMode::TRIANGLE_STRIP | DrawMode::TRIANGLE_FAN => len.saturating_sub(2),
+    }
+}
+
+/// Returns positions of vertices of a primitive drawn from `len` vertices.
+/// The provoking vertex comes last, and vertices are repeated for points and lines.
+fn primitive_vertices(draw_mode: DrawMode, len: usize, primitive: usize) -> [usize; 3] {
+    match draw_mode {
+        DrawMode::POINTS => [primitive; 3],
+        DrawMode::LINES => [primitive * 2, primitive * 2, primitive * 2 + 1],
+        // closing line of a loop is provoked by the first vertex
+        DrawMode::LINE_STRIP | DrawMode::LINE_LOOP => [primitive, primitive, (primitive + 1) % len],
+        DrawMode::TRIANGLES => [primitive * 3, primitive * 3 + 1, primitive * 3 + 2],
+        DrawMode::TRIANGLE_STRIP => [primitive, primitive + 1, primitive + 2],
+        DrawMode::TRIANGLE_FAN => [0, primitive + 1, primitive + 2],
+    }
+}
+
+/// Returns primitives of indexed geometry provoked by a vertex, and vertices of each primitive.
+fn provoked_primitives(draw_mode: DrawMode, indices: &[u32], vertex: u32) -> Vec<(u32, [u32; 3])> {
+    (0..primitive_count(draw_mode, indices.len()))
+        .filter_map(|primitive| {
+            let vertices = primitive_vertices(draw_mode, indices.len(), primitive);
+            if indices[vertices[2]] != vertex {
+                return None;
+            }
+            Some((primitive as u32, vertices.map(|i| indices[i])))
+        })
+        .collect()
+}
+
+/// Returns the primitive closest to `point`, skipping primitives of which vertex positions are unavailable.
+fn closest_primitive<F>(candidates: &[(u32, [u32; 3])], point: [f64; 3], position: F) -> Option<u32>
+where
+    F: Fn(u32) -> Option<[f64; 3]>,
+{
+    candidates
+        .iter()
+        .filter_map(|(primitive, [a, b, c])| {
+            let distance = triangle_distance(point, position(*a)?, position(*b)?, position(*c)?);
+            Some((*primitive, distance))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(primitive, _)| primitive)
+}
+
+/// Returns distance from a point to a triangle, which could be degenerated into a line or a point.
+fn triangle_distance(p: [f64; 3], a: [f64; 3], b: [f64; 3], c: [f64; 3]) -> f64 {
+    let ab = sub(b, a);
+    let ac = sub(c, a);
+    let ap = sub(p, a);
+    let normal = cross(ab, ac);
+    let denom = dot(normal, normal);
+    if denom > 0.0 {
+        // barycentric coordinates of the point projected onto the triangle plane
+        let (d00, d01, d11) = (dot(ab, ab), dot(ab, ac), dot(ac, ac));
+        let (d20, d21) = (dot(ap, ab), dot(ap, ac));
+        let v = (d11 * d20 - d01 * d21) / denom;
+        let w = (d00 * d21 - d01 * d20) / denom;
+        if v >= 0.0 && w >= 0.0 && v + w <= 1.0 {
+            return dot(ap, normal).abs() / denom.sqrt();
+        }
+    }
+
+    segment_distance(p, a, b)
+        .min(segment_distance(p, b, c))
+        .min(segment_distance(p, c, a))
+}
+
+/// Returns distance from a point to a line segment.
+fn segment_distance(p: [f64; 3], a: [f64; 3], b: [f64; 3]) -> f64 {
+    let ab = sub(b, a);
+    let length = dot(ab, ab);
+    let t = if length > 0.0 {
+        (dot(sub(p, a), ab) / length).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let closest = [a[0] + ab[0] * t, a[1] + ab[1] * t, a[2] + ab[2] * t];
+    let offset = sub(p, closest);
+    dot(offset, offset).sqrt()
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
 }
 
 const INDEX_UNIFORM_NAME: &'static str = "u_Index";
 const INDEX_UNIFORM_BINDING: UniformBinding =
     UniformBinding::Custom(Cow::Borrowed(INDEX_UNIFORM_NAME));
 
-struct PickingShaderProvider;
+struct PickingShaderProvider {
+    instanced: bool,
+}
 
 impl ProgramSource for PickingShaderProvider {
     fn name(&self) -> Cow<'_, str> {
-        Cow::Borrowed("Picking")
+        if self.instanced {
+            Cow::Borrowed("Picking_Instanced")
+        } else {
+            Cow::Borrowed("Picking")
+        }
     }
 
     fn vertex_source(&self) -> Cow<'_, str> {
@@ -314,7 +965,11 @@ impl ProgramSource for PickingShaderProvider {
     }
 
     fn universal_defines(&self) -> Cow<'_, [Define<'_>]> {
-        Cow::Borrowed(&[])
+        if self.instanced {
+            Cow::Borrowed(&[Define::WithoutValue(Cow::Borrowed("USE_INSTANCING"))])
+        } else {
+            Cow::Borrowed(&[])
+        }
     }
 
     fn vertex_defines(&self) -> Cow<'_, [Define<'_>]> {
@@ -329,3 +984,230 @@ impl ProgramSource for PickingShaderProvider {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::webgl::draw::DrawMode;
+
+    use crate::renderer::webgl::draw::ElementIndicesDataType;
+
+    use super::{
+        closest_primitive, decode_indices, primitive_index, provoked_primitives, reading_rectangle,
+        select_indices, triangle_distance, PickingRegion,
+    };
+
+    #[test]
+    fn test_region_bounds() {
+        let rectangle = PickingRegion::Rectangle {
+            x: 10,
+            y: 20,
+            width: -5,
+            height: 8,
+        };
+        assert_eq!(rectangle.bounds(), (5, 20, 5, 8));
+
+        let lasso = PickingRegion::Lasso(vec![(2, 3), (10, 1), (6, 9)]);
+        assert_eq!(lasso.bounds(), (2, 1, 8, 8));
+
+        assert_eq!(
+            PickingRegion::Lasso(vec![(2, 3), (10, 1)]).bounds(),
+            (0, 0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_region_contains() {
+        let rectangle = PickingRegion::Rectangle {
+            x: 0,
+            y: 0,
+            width: 4,
+            height: 4,
+        };
+        assert!(rectangle.contains(0.5, 3.5));
+        assert!(!rectangle.contains(4.5, 0.5));
+
+        // concave polygon shaped like a "U"
+        let lasso = PickingRegion::Lasso(vec![
+            (0, 0),
+            (3, 0),
+            (3, 6),
+            (6, 6),
+            (6, 0),
+            (9, 0),
+            (9, 9),
+            (0, 9),
+        ]);
+        assert!(lasso.contains(1.5, 1.5));
+        assert!(lasso.contains(7.5, 1.5));
+        assert!(lasso.contains(4.5, 7.5));
+        assert!(!lasso.contains(4.5, 1.5));
+        assert!(!lasso.contains(10.5, 1.5));
+
+        assert!(!PickingRegion::Lasso(vec![(0, 0), (9, 9)]).contains(4.5, 4.5));
+    }
+
+    #[test]
+    fn test_reading_rectangle() {
+        let region = PickingRegion::Rectangle {
+            x: -2,
+            y: 5,
+            width: 6,
+            height: 100,
+        };
+        assert_eq!(
+            reading_rectangle(&region, false, 50, 50),
+            Some((0, 5, 4, 45))
+        );
+        assert_eq!(
+            reading_rectangle(&region, true, 50, 50),
+            Some((0, 0, 50, 50))
+        );
+
+        let region = PickingRegion::Rectangle {
+            x: 60,
+            y: 0,
+            width: 5,
+            height: 5,
+        };
+        assert_eq!(reading_rectangle(&region, false, 50, 50), None);
+    }
+
+    #[test]
+    fn test_select_indices() {
+        // 4x2 pixels at window (0, 0), rows from bottom to top:
+        // window row 0: 1 1 2 0
+        // window row 1: 3 0 2 4
+        let pixels = [3, 0, 2, 4, 1, 1, 2, 0];
+        let rectangle = (0, 0, 4, 2);
+        let region = PickingRegion::Rectangle {
+            x: 0,
+            y: 0,
+            width: 2,
+            height: 2,
+        };
+
+        assert_eq!(
+            select_indices(&pixels, rectangle, &region, false),
+            vec![0, 2]
+        );
+        assert_eq!(
+            select_indices(&pixels, rectangle, &region, true),
+            vec![0, 2]
+        );
+
+        let region = PickingRegion::Rectangle {
+            x: 0,
+            y: 0,
+            width: 3,
+            height: 1,
+        };
+        assert_eq!(
+            select_indices(&pixels, rectangle, &region, false),
+            vec![0, 1]
+        );
+        // entity 1 leaks out at window row 1
+        assert_eq!(select_indices(&pixels, rectangle, &region, true), vec![0]);
+    }
+
+    #[test]
+    fn test_primitive_index() {
+        assert_eq!(primitive_index(DrawMode::TRIANGLES, 0..36, 5), Some(1));
+        assert_eq!(primitive_index(DrawMode::TRIANGLES, 6..36, 5), None);
+        assert_eq!(primitive_index(DrawMode::TRIANGLES, 6..36, 11), Some(1));
+        assert_eq!(primitive_index(DrawMode::TRIANGLE_STRIP, 0..8, 4), Some(2));
+        assert_eq!(primitive_index(DrawMode::TRIANGLE_FAN, 0..8, 1), None);
+        assert_eq!(primitive_index(DrawMode::LINES, 0..8, 7), Some(3));
+        assert_eq!(primitive_index(DrawMode::LINE_STRIP, 0..8, 7), Some(6));
+        assert_eq!(primitive_index(DrawMode::LINE_LOOP, 0..8, 0), Some(7));
+        assert_eq!(primitive_index(DrawMode::LINE_LOOP, 0..8, 3), Some(2));
+        assert_eq!(primitive_index(DrawMode::POINTS, 2..8, 3), Some(1));
+    }
+
+    #[test]
+    fn test_decode_indices() {
+        let indices = [1u32, 258, 3];
+        let bytes = indices
+            .iter()
+            .flat_map(|index| (*index as u16).to_ne_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decode_indices(&bytes, ElementIndicesDataType::UNSIGNED_SHORT),
+            indices
+        );
+        let bytes = indices
+            .iter()
+            .flat_map(|index| index.to_ne_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decode_indices(&bytes, ElementIndicesDataType::UNSIGNED_INT),
+            indices
+        );
+        assert_eq!(
+            decode_indices(&[4, 5], ElementIndicesDataType::UNSIGNED_BYTE),
+            vec![4, 5]
+        );
+    }
+
+    #[test]
+    fn test_provoked_primitives() {
+        // a quad of two triangles sharing vertices 0 and 2
+        let indices = [0, 1, 2, 3, 0, 2];
+        assert_eq!(
+            provoked_primitives(DrawMode::TRIANGLES, &indices, 2),
+            vec![(0, [0, 1, 2]), (1, [3, 0, 2])]
+        );
+        assert!(provoked_primitives(DrawMode::TRIANGLES, &indices, 1).is_empty());
+        assert_eq!(
+            provoked_primitives(DrawMode::TRIANGLE_STRIP, &[5, 6, 7, 8], 8),
+            vec![(1, [6, 7, 8])]
+        );
+        assert_eq!(
+            provoked_primitives(DrawMode::TRIANGLE_FAN, &[5, 6, 7, 8], 8),
+            vec![(1, [5, 7, 8])]
+        );
+        assert_eq!(
+            provoked_primitives(DrawMode::LINE_LOOP, &[5, 6, 7], 5),
+            vec![(2, [7, 7, 5])]
+        );
+        assert_eq!(
+            provoked_primitives(DrawMode::POINTS, &[5, 6, 5], 5),
+            vec![(0, [5, 5, 5]), (2, [5, 5, 5])]
+        );
+    }
+
+    #[test]
+    fn test_triangle_distance() {
+        let (a, b, c) = ([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+        assert_eq!(triangle_distance([0.25, 0.25, 2.0], a, b, c), 2.0);
+        assert_eq!(triangle_distance([2.0, 0.0, 0.0], a, b, c), 1.0);
+        // degenerated into a line
+        assert_eq!(triangle_distance([0.5, 1.0, 0.0], a, a, b), 1.0);
+        // degenerated into a point
+        assert_eq!(triangle_distance([0.0, 0.0, 3.0], a, a, a), 3.0);
+    }
+
+    #[test]
+    fn test_closest_primitive() {
+        // a quad on xy plane split along diagonal from vertex 0 to vertex 2
+        let positions = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+        ];
+        let position = |index: u32| positions.get(index as usize).copied();
+        let candidates = provoked_primitives(DrawMode::TRIANGLES, &[0, 1, 2, 3, 0, 2], 2);
+        assert_eq!(
+            closest_primitive(&candidates, [0.8, 0.2, 0.0], position),
+            Some(0)
+        );
+        assert_eq!(
+            closest_primitive(&candidates, [0.2, 0.8, 0.0], position),
+            Some(1)
+        );
+        assert_eq!(
+            closest_primitive(&[(0, [0, 1, 9])], [0.0, 0.0, 0.0], position),
+            None
+        );
+    }
+}
@@ -0,0 +1,992 @@
+use std::{borrow::Cow, cell::RefCell, f64::consts::FRAC_PI_2, rc::Rc};
+
+use gl_matrix4rust::{mat4::Mat4, vec3::Vec3};
+use web_sys::{WebGl2RenderingContext, WebGlFramebuffer};
+
+use crate::{
+    entity::{Entity, Group},
+    frustum::{cascade_splits, frustum_near_distance, frustum_slice_corners, FrustumCorners},
+    light::{
+        directional_light::DirectionalLight, point_light::PointLight, spot_light::SpotLight,
+        MAX_SHADOW_CASCADES,
+    },
+    material::Transparency,
+    renderer::webgl::{
+        buffer::Buffer,
+        draw::Draw,
+        error::Error,
+        matrix::GlF32,
+        program::{Define, Program, ProgramSource},
+        state::FrameState,
+        texture::{
+            Builder, SamplerParameter, Texture, Texture2D, TextureCompareFunction,
+            TextureCompareMode, TextureCubeMap, TextureInternalFormat, TextureMagnificationFilter,
+            TextureMinificationFilter, TextureUncompressedInternalFormat, TextureUnit,
+            TextureWrapMethod,
+        },
+        uniform::{UniformBinding, UniformValue},
+    },
+    scene::{Scene, MAX_DIRECTIONAL_LIGHTS, MAX_POINT_LIGHTS, MAX_SPOT_LIGHTS},
+};
+
+use super::{
+    UBO_SHADOWS_BLOCK_BINDING, UBO_SHADOWS_BYTE_LENGTH,
+    UBO_SHADOWS_DIRECTIONAL_SHADOW_CASCADES_BYTE_OFFSET,
+    UBO_SHADOWS_DIRECTIONAL_SHADOW_MATRICES_BYTE_OFFSET,
+    UBO_SHADOWS_DIRECTIONAL_SHADOW_REGIONS_BYTE_OFFSET, UBO_SHADOWS_POINT_SHADOWS_BYTE_OFFSET,
+    UBO_SHADOWS_SPOT_SHADOW_MATRICES_BYTE_OFFSET, UBO_SHADOWS_SPOT_SHADOW_REGIONS_BYTE_OFFSET,
+    UBO_SHADOWS_UNIFORM_BLOCK_MOUNT_POINT,
+};
+
+/// Default width and height of the shadow atlas.
+pub const DEFAULT_SHADOW_ATLAS_SIZE: usize = 4096;
+/// Default width and height of each face of a point light cube shadow map.
+pub const DEFAULT_POINT_SHADOW_MAP_SIZE: usize = 1024;
+/// Maximum point lights casting shadows at the same time.
+/// Point lights beyond this count are rendered without shadows.
+pub const MAX_POINT_LIGHT_SHADOWS: usize = 4;
+/// Near distance of perspective shadow projections of spot lights and point lights.
+pub const SHADOW_NEAR: f64 = 0.1;
+
+/// Texture unit the shadow atlas bound to.
+pub const SHADOW_ATLAS_TEXTURE_UNIT: TextureUnit = TextureUnit::TEXTURE10;
+/// Texture units the point light cube shadow maps bound to.
+pub const POINT_SHADOW_MAP_TEXTURE_UNITS: [TextureUnit; MAX_POINT_LIGHT_SHADOWS] = [
+    TextureUnit::TEXTURE11,
+    TextureUnit::TEXTURE12,
+    TextureUnit::TEXTURE13,
+    TextureUnit::TEXTURE14,
+];
+
+const SHADOW_ATLAS_UNIFORM_NAME: &'static str = "u_ShadowAtlas";
+const SHADOW_ATLAS_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(SHADOW_ATLAS_UNIFORM_NAME));
+
+const POINT_SHADOW_MAP_UNIFORM_BINDINGS: [UniformBinding; MAX_POINT_LIGHT_SHADOWS] = [
+    UniformBinding::Custom(Cow::Borrowed("u_PointShadowMap0")),
+    UniformBinding::Custom(Cow::Borrowed("u_PointShadowMap1")),
+    UniformBinding::Custom(Cow::Borrowed("u_PointShadowMap2")),
+    UniformBinding::Custom(Cow::Borrowed("u_PointShadowMap3")),
+];
+
+const SHADOW_MATRIX_UNIFORM_NAME: &'static str = "u_ShadowMatrix";
+const SHADOW_MATRIX_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(SHADOW_MATRIX_UNIFORM_NAME));
+
+const LIGHT_POSITION_UNIFORM_NAME: &'static str = "u_LightPosition";
+const LIGHT_POSITION_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(LIGHT_POSITION_UNIFORM_NAME));
+
+const SHADOW_FAR_UNIFORM_NAME: &'static str = "u_ShadowFar";
+const SHADOW_FAR_UNIFORM_BINDING: UniformBinding =
+    UniformBinding::Custom(Cow::Borrowed(SHADOW_FAR_UNIFORM_NAME));
+
+/// Cube map face targets with looking direction and upward direction of each face.
+const CUBE_MAP_FACES: [(u32, [f64; 3], [f64; 3]); 6] = [
+    (
+        WebGl2RenderingContext::TEXTURE_CUBE_MAP_POSITIVE_X,
+        [1.0, 0.0, 0.0],
+        [0.0, -1.0, 0.0],
+    ),
+    (
+        WebGl2RenderingContext::TEXTURE_CUBE_MAP_NEGATIVE_X,
+        [-1.0, 0.0, 0.0],
+        [0.0, -1.0, 0.0],
+    ),
+    (
+        WebGl2RenderingContext::TEXTURE_CUBE_MAP_POSITIVE_Y,
+        [0.0, 1.0, 0.0],
+        [0.0, 0.0, 1.0],
+    ),
+    (
+        WebGl2RenderingContext::TEXTURE_CUBE_MAP_NEGATIVE_Y,
+        [0.0, -1.0, 0.0],
+        [0.0, 0.0, -1.0],
+    ),
+    (
+        WebGl2RenderingContext::TEXTURE_CUBE_MAP_POSITIVE_Z,
+        [0.0, 0.0, 1.0],
+        [0.0, -1.0, 0.0],
+    ),
+    (
+        WebGl2RenderingContext::TEXTURE_CUBE_MAP_NEGATIVE_Z,
+        [0.0, 0.0, -1.0],
+        [0.0, -1.0, 0.0],
+    ),
+];
+
+/// Standard shadow mapping.
+///
+/// Directional lights and spot lights casting shadows share a single depth shadow atlas,
+/// each of them renders into its own region.
+/// Directional lights split camera view frustum into cascades,
+/// each cascade renders into its own region as well.
+/// Point lights casting shadows render linear distance to light into depth cube maps,
+/// up to [`MAX_POINT_LIGHT_SHADOWS`] point lights.
+///
+/// Only opaque entities cast shadows.
+pub struct StandardShadowMapping {
+    atlas_size: usize,
+    point_shadow_map_size: usize,
+
+    atlas: Option<Texture<Texture2D>>,
+    point_shadow_maps: Vec<Texture<TextureCubeMap>>,
+    framebuffer: Option<WebGlFramebuffer>,
+}
+
+impl StandardShadowMapping {
+    pub fn new() -> Self {
+        Self {
+            atlas_size: DEFAULT_SHADOW_ATLAS_SIZE,
+            point_shadow_map_size: DEFAULT_POINT_SHADOW_MAP_SIZE,
+
+            atlas: None,
+            point_shadow_maps: Vec::new(),
+            framebuffer: None,
+        }
+    }
+
+    /// Returns width and height of the shadow atlas.
+    pub fn atlas_size(&self) -> usize {
+        self.atlas_size
+    }
+
+    /// Sets width and height of the shadow atlas.
+    pub fn set_atlas_size(&mut self, size: usize) {
+        if self.atlas_size != size {
+            self.atlas_size = size;
+            self.atlas = None;
+        }
+    }
+
+    /// Returns width and height of each face of point light cube shadow maps.
+    pub fn point_shadow_map_size(&self) -> usize {
+        self.point_shadow_map_size
+    }
+
+    /// Sets width and height of each face of point light cube shadow maps.
+    pub fn set_point_shadow_map_size(&mut self, size: usize) {
+        if self.point_shadow_map_size != size {
+            self.point_shadow_map_size = size;
+            self.point_shadow_maps.clear();
+        }
+    }
+
+    fn shadow_sampler_parameters() -> [SamplerParameter; 7] {
+        [
+            SamplerParameter::MAG_FILTER(TextureMagnificationFilter::LINEAR),
+            SamplerParameter::MIN_FILTER(TextureMinificationFilter::LINEAR),
+            SamplerParameter::WRAP_S(TextureWrapMethod::CLAMP_TO_EDGE),
+            SamplerParameter::WRAP_T(TextureWrapMethod::CLAMP_TO_EDGE),
+            SamplerParameter::WRAP_R(TextureWrapMethod::CLAMP_TO_EDGE),
+            SamplerParameter::COMPARE_MODE(TextureCompareMode::COMPARE_REF_TO_TEXTURE),
+            SamplerParameter::COMPARE_FUNC(TextureCompareFunction::LEQUAL),
+        ]
+    }
+
+    fn init(&mut self, state: &FrameState) -> Result<(), Error> {
+        let internal_format = TextureInternalFormat::Uncompressed(
+            TextureUncompressedInternalFormat::DEPTH_COMPONENT32F,
+        );
+
+        if self.atlas.is_none() {
+            let mut builder =
+                Builder::<Texture2D>::new(internal_format, 1, self.atlas_size, self.atlas_size);
+            builder.set_sampler_parameters(Self::shadow_sampler_parameters());
+            let atlas = builder.build();
+            atlas.init(state.gl())?;
+            self.atlas = Some(atlas);
+        }
+
+        while self.point_shadow_maps.len() < MAX_POINT_LIGHT_SHADOWS {
+            let mut builder = Builder::<TextureCubeMap>::new(
+                internal_format,
+                1,
+                self.point_shadow_map_size,
+                self.point_shadow_map_size,
+            );
+            builder.set_sampler_parameters(Self::shadow_sampler_parameters());
+            let cube_map = builder.build();
+            cube_map.init(state.gl())?;
+            self.point_shadow_maps.push(cube_map);
+        }
+
+        if self.framebuffer.is_none() {
+            self.framebuffer = Some(
+                state
+                    .gl()
+                    .create_framebuffer()
+                    .ok_or(Error::CreateFramebufferFailure)?,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Renders shadow maps of all lights casting shadows in the scene
+    /// and updates Uniform Buffer Object `atoy_Shadows`.
+    ///
+    /// Shadow textures are not bound after rendering,
+    /// calls [`StandardShadowMapping::bind_textures`] before drawing shadow receivers.
+    pub fn render(
+        &mut self,
+        state: &mut FrameState,
+        scene: &Scene,
+        shadows_ubo: &mut Buffer,
+    ) -> Result<(), Error> {
+        let casters = shadow_casters(scene);
+
+        // instances culled by camera view frustum may still cast shadows into the view
+        draw_all_instances(&casters, true);
+        let result = self.render_casters(state, scene, &casters, shadows_ubo);
+        draw_all_instances(&casters, false);
+
+        result
+    }
+
+    fn render_casters(
+        &mut self,
+        state: &mut FrameState,
+        scene: &Scene,
+        casters: &[Rc<RefCell<dyn Entity>>],
+        shadows_ubo: &mut Buffer,
+    ) -> Result<(), Error> {
+        self.init(state)?;
+
+        let view_proj_matrix = state.camera().view_proj_matrix();
+        let camera_near = frustum_near_distance(&view_proj_matrix);
+
+        let mut data = vec![0.0f32; UBO_SHADOWS_BYTE_LENGTH / 4];
+        // point lights without shadow are marked by a negative slot
+        for index in 0..MAX_POINT_LIGHTS {
+            data[UBO_SHADOWS_POINT_SHADOWS_BYTE_OFFSET / 4 + index * 4] = -1.0;
+        }
+
+        // directional lights are skipped if camera view frustum is unavailable
+        let directional_lights = scene
+            .directional_lights()
+            .iter()
+            .enumerate()
+            .take(MAX_DIRECTIONAL_LIGHTS)
+            .filter(|(_, light)| camera_near.is_some() && light.enabled() && light.shadow_enabled())
+            .collect::<Vec<_>>();
+        let spot_lights = scene
+            .spot_lights()
+            .iter()
+            .enumerate()
+            .take(MAX_SPOT_LIGHTS)
+            .filter(|(_, light)| light.enabled() && light.shadow_enabled())
+            .collect::<Vec<_>>();
+
+        // each shadow cascade of directional lights takes a tile, so does each spot light
+        let regions = shadow_atlas_regions(
+            directional_lights
+                .iter()
+                .map(|(_, light)| light.shadow_cascades())
+                .sum::<usize>()
+                + spot_lights.len(),
+        );
+        let resolution = regions
+            .first()
+            .map(|region| (region[2] * self.atlas_size as f32) as usize)
+            .unwrap_or(self.atlas_size);
+
+        // collects all atlas tiles, directional lights first
+        let mut tiles = Vec::with_capacity(regions.len());
+        for (index, light) in directional_lights {
+            let splits = cascade_splits(
+                camera_near.unwrap_or(0.0),
+                light.shadow_distance() as f64,
+                light.shadow_cascades(),
+                light.shadow_cascade_lambda() as f64,
+            );
+            for (cascade, range) in splits.windows(2).enumerate() {
+                let Some(matrix) = directional_shadow_matrix(
+                    light,
+                    &view_proj_matrix,
+                    range[0],
+                    range[1],
+                    resolution,
+                ) else {
+                    continue;
+                };
+                let slot = index * MAX_SHADOW_CASCADES + cascade;
+                data[UBO_SHADOWS_DIRECTIONAL_SHADOW_CASCADES_BYTE_OFFSET / 4
+                    + index * 4
+                    + cascade] = range[1] as f32;
+                tiles.push((
+                    matrix,
+                    UBO_SHADOWS_DIRECTIONAL_SHADOW_MATRICES_BYTE_OFFSET / 4 + slot * 16,
+                    UBO_SHADOWS_DIRECTIONAL_SHADOW_REGIONS_BYTE_OFFSET / 4 + slot * 4,
+                ));
+            }
+        }
+        for (index, light) in spot_lights {
+            let matrix = spot_shadow_matrix(light);
+            tiles.push((
+                matrix,
+                UBO_SHADOWS_SPOT_SHADOW_MATRICES_BYTE_OFFSET / 4 + index * 16,
+                UBO_SHADOWS_SPOT_SHADOW_REGIONS_BYTE_OFFSET / 4 + index * 4,
+            ));
+        }
+
+        let gl = state.gl().clone();
+        gl.bind_framebuffer(
+            WebGl2RenderingContext::DRAW_FRAMEBUFFER,
+            self.framebuffer.as_ref(),
+        );
+        gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+        gl.depth_mask(true);
+        gl.disable(WebGl2RenderingContext::BLEND);
+        gl.disable(WebGl2RenderingContext::CULL_FACE);
+
+        // renders directional lights and spot lights into shadow atlas
+        let atlas = self.atlas.as_ref().unwrap().native()?;
+        gl.framebuffer_texture_2d(
+            WebGl2RenderingContext::DRAW_FRAMEBUFFER,
+            WebGl2RenderingContext::DEPTH_ATTACHMENT,
+            WebGl2RenderingContext::TEXTURE_2D,
+            Some(&atlas),
+            0,
+        );
+        gl.viewport(0, 0, self.atlas_size as i32, self.atlas_size as i32);
+        gl.clear(WebGl2RenderingContext::DEPTH_BUFFER_BIT);
+
+        if !tiles.is_empty() {
+            let programs = shadow_depth_programs(state, casters, false)?;
+            for ((matrix, matrix_offset, region_offset), region) in tiles.into_iter().zip(regions) {
+                let [x, y, width, height] = region;
+                let size = self.atlas_size as f32;
+                gl.viewport(
+                    (x * size) as i32,
+                    (y * size) as i32,
+                    (width * size) as i32,
+                    (height * size) as i32,
+                );
+
+                let matrix = matrix.to_f32_array();
+                draw_casters(
+                    state,
+                    &programs,
+                    &[(
+                        &SHADOW_MATRIX_UNIFORM_BINDING,
+                        UniformValue::Matrix4 {
+                            data: matrix,
+                            transpose: false,
+                        },
+                    )],
+                )?;
+
+                data[matrix_offset..matrix_offset + 16].copy_from_slice(&matrix);
+                data[region_offset..region_offset + 4].copy_from_slice(&region);
+            }
+        }
+
+        // renders point lights into cube shadow maps
+        let point_lights = scene
+            .point_lights()
+            .iter()
+            .enumerate()
+            .take(MAX_POINT_LIGHTS)
+            .filter(|(_, light)| light.enabled() && light.shadow_enabled())
+            .take(MAX_POINT_LIGHT_SHADOWS)
+            .collect::<Vec<_>>();
+        if !point_lights.is_empty() {
+            gl.framebuffer_texture_2d(
+                WebGl2RenderingContext::DRAW_FRAMEBUFFER,
+                WebGl2RenderingContext::DEPTH_ATTACHMENT,
+                WebGl2RenderingContext::TEXTURE_2D,
+                None,
+                0,
+            );
+            gl.viewport(
+                0,
+                0,
+                self.point_shadow_map_size as i32,
+                self.point_shadow_map_size as i32,
+            );
+
+            let programs = shadow_depth_programs(state, casters, true)?;
+            for (slot, (index, light)) in point_lights.into_iter().enumerate() {
+                let native = self.point_shadow_maps[slot].native()?;
+
+                for (face, matrix) in point_shadow_matrices(light).into_iter().enumerate() {
+                    gl.framebuffer_texture_2d(
+                        WebGl2RenderingContext::DRAW_FRAMEBUFFER,
+                        WebGl2RenderingContext::DEPTH_ATTACHMENT,
+                        CUBE_MAP_FACES[face].0,
+                        Some(&native),
+                        0,
+                    );
+                    gl.clear(WebGl2RenderingContext::DEPTH_BUFFER_BIT);
+                    draw_casters(
+                        state,
+                        &programs,
+                        &[
+                            (
+                                &SHADOW_MATRIX_UNIFORM_BINDING,
+                                UniformValue::Matrix4 {
+                                    data: matrix.to_f32_array(),
+                                    transpose: false,
+                                },
+                            ),
+                            (
+                                &LIGHT_POSITION_UNIFORM_BINDING,
+                                UniformValue::FloatVector3(light.position().to_f32_array()),
+                            ),
+                            (
+                                &SHADOW_FAR_UNIFORM_BINDING,
+                                UniformValue::Float1(light.shadow_far()),
+                            ),
+                        ],
+                    )?;
+                }
+
+                let offset = UBO_SHADOWS_POINT_SHADOWS_BYTE_OFFSET / 4 + index * 4;
+                data[offset] = slot as f32;
+                data[offset + 1] = light.shadow_far();
+            }
+        }
+
+        gl.framebuffer_texture_2d(
+            WebGl2RenderingContext::DRAW_FRAMEBUFFER,
+            WebGl2RenderingContext::DEPTH_ATTACHMENT,
+            WebGl2RenderingContext::TEXTURE_2D,
+            None,
+            0,
+        );
+        gl.bind_framebuffer(WebGl2RenderingContext::DRAW_FRAMEBUFFER, None);
+        gl.disable(WebGl2RenderingContext::DEPTH_TEST);
+        gl.viewport(
+            0,
+            0,
+            state.canvas().width() as i32,
+            state.canvas().height() as i32,
+        );
+
+        state.buffer_store().register(shadows_ubo)?;
+        shadows_ubo.buffer_sub_data(
+            data.into_iter()
+                .flat_map(|value| value.to_ne_bytes())
+                .collect::<Vec<_>>(),
+            0,
+        );
+        shadows_ubo.bind_ubo(UBO_SHADOWS_UNIFORM_BLOCK_MOUNT_POINT)?;
+
+        Ok(())
+    }
+
+    /// Binds shadow atlas and point light cube shadow maps to their texture units.
+    pub fn bind_textures(&self) -> Result<(), Error> {
+        if let Some(atlas) = self.atlas.as_ref() {
+            atlas.bind(SHADOW_ATLAS_TEXTURE_UNIT)?;
+        }
+        for (cube_map, unit) in self
+            .point_shadow_maps
+            .iter()
+            .zip(POINT_SHADOW_MAP_TEXTURE_UNITS)
+        {
+            cube_map.bind(unit)?;
+        }
+        Ok(())
+    }
+
+    /// Unbinds shadow atlas and point light cube shadow maps from their texture units.
+    pub fn unbind_textures(&self) -> Result<(), Error> {
+        if let Some(atlas) = self.atlas.as_ref() {
+            atlas.unbind(SHADOW_ATLAS_TEXTURE_UNIT)?;
+        }
+        for (cube_map, unit) in self
+            .point_shadow_maps
+            .iter()
+            .zip(POINT_SHADOW_MAP_TEXTURE_UNITS)
+        {
+            cube_map.unbind(unit)?;
+        }
+        Ok(())
+    }
+}
+
+/// Mounts Uniform Buffer Object `atoy_Shadows` and binds shadow samplers to a shadow receiving program.
+pub(super) fn mount_shadows(program: &Program) -> Result<(), Error> {
+    program.mount_uniform_block_by_binding(
+        &UBO_SHADOWS_BLOCK_BINDING,
+        UBO_SHADOWS_UNIFORM_BLOCK_MOUNT_POINT,
+    )?;
+    program.bind_uniform_value_by_binding(
+        &SHADOW_ATLAS_UNIFORM_BINDING,
+        &UniformValue::Integer1(SHADOW_ATLAS_TEXTURE_UNIT.unit_index() as i32),
+        None,
+    )?;
+    for (binding, unit) in POINT_SHADOW_MAP_UNIFORM_BINDINGS
+        .iter()
+        .zip(POINT_SHADOW_MAP_TEXTURE_UNITS)
+    {
+        program.bind_uniform_value_by_binding(
+            binding,
+            &UniformValue::Integer1(unit.unit_index() as i32),
+            None,
+        )?;
+    }
+    Ok(())
+}
+
+/// Collects all opaque entities in the scene, including entities outside of the view frustum.
+fn shadow_casters(scene: &Scene) -> Vec<Rc<RefCell<dyn Entity>>> {
+    scene
+        .entities()
+        .borrow()
+        .entities_hierarchy()
+        .filter(|entity| {
+            let entity = entity.borrow();
+            if entity.geometry().is_none() {
+                return false;
+            }
+            match entity.material() {
+                Some(material) => {
+                    material.ready() && material.transparency() == Transparency::Opaque
+                }
+                None => false,
+            }
+        })
+        .collect()
+}
+
+/// Splits shadow casters into regular casters and instanced casters,
+/// paired with the depth program drawing them.
+/// Groups without any caster are dropped.
+fn shadow_depth_programs(
+    state: &mut FrameState,
+    casters: &[Rc<RefCell<dyn Entity>>],
+    linear: bool,
+) -> Result<Vec<(Program, Vec<Rc<RefCell<dyn Entity>>>)>, Error> {
+    let (instanced_casters, casters): (Vec<_>, Vec<_>) = casters
+        .iter()
+        .cloned()
+        .partition(|entity| entity.borrow().as_instanced_entity().is_some());
+
+    let mut programs = Vec::with_capacity(2);
+    for (instanced, casters) in [(false, casters), (true, instanced_casters)] {
+        if casters.is_empty() {
+            continue;
+        }
+        let program = state
+            .program_store_mut()
+            .get_or_compile_program(&ShadowDepth { linear, instanced })?;
+        programs.push((program, casters));
+    }
+    Ok(programs)
+}
+
+/// Switches instanced casters between drawing all instances and drawing visible instances only.
+fn draw_all_instances(casters: &[Rc<RefCell<dyn Entity>>], enable: bool) {
+    for entity in casters {
+        if let Some(instanced) = entity.borrow_mut().as_instanced_entity_mut() {
+            instanced.draw_all_instances(enable);
+        }
+    }
+}
+
+fn draw_casters(
+    state: &FrameState,
+    programs: &[(Program, Vec<Rc<RefCell<dyn Entity>>>)],
+    uniforms: &[(&UniformBinding, UniformValue)],
+) -> Result<(), Error> {
+    for (program, casters) in programs {
+        program.use_program()?;
+        for (binding, value) in uniforms {
+            program.bind_uniform_value_by_binding(binding, value, None)?;
+        }
+
+        for entity in casters {
+            let entity = entity.borrow();
+            let Some(geometry) = entity.geometry() else {
+                continue;
+            };
+
+            program.bind_uniforms(Some(state), Some(&*entity), Some(geometry), None)?;
+            program.bind_attributes(Some(state), Some(&*entity), Some(geometry), None)?;
+            let draw = match entity.as_instanced_entity() {
+                Some(instanced) => {
+                    Draw::from_geometry_instanced(geometry, instanced.instance_count())
+                }
+                None => Draw::from_geometry(geometry),
+            };
+            draw.draw(state.gl(), Some(state.buffer_store()))?;
+            program.unbind_attributes()?;
+        }
+        program.unuse_program()?;
+    }
+    Ok(())
+}
+
+/// Splits shadow atlas into a square grid holding `count` tiles.
+/// Returns regions in `[x, y, width, height]` in texture coordinate for each tile, row by row.
+pub fn shadow_atlas_regions(count: usize) -> Vec<[f32; 4]> {
+    if count == 0 {
+        return Vec::new();
+    }
+
+    let grid = (count as f64).sqrt().ceil() as usize;
+    let tile = 1.0 / grid as f32;
+    (0..count)
+        .map(|index| {
+            let column = index % grid;
+            let row = index / grid;
+            [column as f32 * tile, row as f32 * tile, tile, tile]
+        })
+        .collect()
+}
+
+/// Returns an upward direction which is never parallel with `direction`.
+fn shadow_up(direction: &Vec3<f64>) -> Vec3<f64> {
+    if direction.y().abs() > 0.99 {
+        Vec3::<f64>::new(0.0, 0.0, 1.0)
+    } else {
+        Vec3::<f64>::new(0.0, 1.0, 0.0)
+    }
+}
+
+fn to_f64(vec: &Vec3<f32>) -> Vec3<f64> {
+    Vec3::<f64>::new(*vec.x() as f64, *vec.y() as f64, *vec.z() as f64)
+}
+
+/// Returns light space projection matrix of a shadow cascade fitted to frustum slice `corners`.
+///
+/// Shadow casting box is an orthogonal box bounding the sphere of the slice,
+/// so its size never changes while camera rotating.
+/// Center of the box is snapped to texels of a shadow map in `resolution`,
+/// so shadow edges never shimmer while camera moving.
+/// Shadow casting box is extended by `caster_distance` towards the light,
+/// catching casters standing between the light and the slice.
+pub fn cascade_shadow_matrix(
+    corners: &FrustumCorners,
+    direction: &Vec3<f64>,
+    resolution: usize,
+    caster_distance: f64,
+) -> Mat4<f64> {
+    let center = corners
+        .iter()
+        .fold(Vec3::<f64>::new(0.0, 0.0, 0.0), |acc, corner| acc + *corner)
+        / corners.len() as f64;
+    let radius = corners
+        .iter()
+        .map(|corner| (*corner - center).length())
+        .fold(0.0, f64::max);
+    // rounds radius up, avoiding float error changing radius slightly frame by frame
+    let radius = (radius * 16.0).ceil() / 16.0;
+    // pads a texel on each side, snapping never pushes the sphere out of the box
+    let resolution = resolution.max(3) as f64;
+    let texel = radius * 2.0 / (resolution - 2.0);
+    let extent = texel * resolution / 2.0;
+
+    // light view without translation, snapping is applied in light space then
+    let origin = Vec3::<f64>::new(0.0, 0.0, 0.0);
+    let view = Mat4::<f64>::from_look_at(&origin, direction, &shadow_up(direction));
+    let center = view * center;
+    let x = (center.x() / texel).floor() * texel;
+    let y = (center.y() / texel).floor() * texel;
+    // light looks at -Z in light space
+    let z = -*center.z();
+
+    let proj = Mat4::<f64>::from_ortho(
+        x - extent,
+        x + extent,
+        y - extent,
+        y + extent,
+        z - radius - caster_distance,
+        z + radius,
+    );
+    proj * view
+}
+
+/// Returns light space projection matrix of a directional light for a shadow cascade,
+/// which covers view distances from `near` to `far` of camera with view projection matrix `view_proj_matrix`.
+/// Casters from [`DirectionalLight::shadow_distance`] away towards the light are included.
+///
+/// Returns `None` if view projection matrix is not invertible.
+pub fn directional_shadow_matrix(
+    light: &DirectionalLight,
+    view_proj_matrix: &Mat4<f64>,
+    near: f64,
+    far: f64,
+    resolution: usize,
+) -> Option<Mat4<f64>> {
+    let corners = frustum_slice_corners(view_proj_matrix, near, far)?;
+    Some(cascade_shadow_matrix(
+        &corners,
+        &to_f64(&light.direction()).normalize(),
+        resolution,
+        light.shadow_distance() as f64,
+    ))
+}
+
+/// Returns light space projection matrix of a spot light.
+/// Field of view of the projection covers the outer cutoff.
+pub fn spot_shadow_matrix(light: &SpotLight) -> Mat4<f64> {
+    let direction = to_f64(&light.direction()).normalize();
+    let position = light.position();
+    let view =
+        Mat4::<f64>::from_look_at(&position, &(position + direction), &shadow_up(&direction));
+    let proj = Mat4::<f64>::from_perspective(
+        (light.outer_cutoff() as f64 * 2.0).min(std::f64::consts::PI - 0.01),
+        1.0,
+        SHADOW_NEAR,
+        Some(light.shadow_far() as f64),
+    );
+    proj * view
+}
+
+/// Returns light space projection matrices of a point light for each cube map face,
+/// in order of `+X`, `-X`, `+Y`, `-Y`, `+Z` and `-Z`.
+pub fn point_shadow_matrices(light: &PointLight) -> [Mat4<f64>; 6] {
+    let position = light.position();
+    let proj =
+        Mat4::<f64>::from_perspective(FRAC_PI_2, 1.0, SHADOW_NEAR, Some(light.shadow_far() as f64));
+    CUBE_MAP_FACES.map(|(_, [dx, dy, dz], [ux, uy, uz])| {
+        let view = Mat4::<f64>::from_look_at(
+            &position,
+            &(position + Vec3::<f64>::new(dx, dy, dz)),
+            &Vec3::<f64>::new(ux, uy, uz),
+        );
+        proj * view
+    })
+}
+
+/// Depth only program rendering shadow casters.
+/// Linear distance to light divided by shadow far distance is written as depth if `linear` is `true`.
+/// Per instance model matrices are applied if `instanced` is `true`.
+struct ShadowDepth {
+    linear: bool,
+    instanced: bool,
+}
+
+impl ProgramSource for ShadowDepth {
+    fn name(&self) -> Cow<'_, str> {
+        match (self.linear, self.instanced) {
+            (false, false) => Cow::Borrowed("ShadowDepth"),
+            (true, false) => Cow::Borrowed("ShadowDepth_Linear"),
+            (false, true) => Cow::Borrowed("ShadowDepth_Instanced"),
+            (true, true) => Cow::Borrowed("ShadowDepth_Linear_Instanced"),
+        }
+    }
+
+    fn vertex_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("./shaders/shadow.vert"))
+    }
+
+    fn fragment_source(&self) -> Cow<'_, str> {
+        Cow::Borrowed(include_str!("./shaders/shadow.frag"))
+    }
+
+    fn universal_defines(&self) -> Cow<'_, [Define<'_>]> {
+        let mut defines = Vec::with_capacity(2);
+        if self.linear {
+            defines.push(Define::WithoutValue(Cow::Borrowed("USE_LINEAR_DEPTH")));
+        }
+        if self.instanced {
+            defines.push(Define::WithoutValue(Cow::Borrowed("USE_INSTANCING")));
+        }
+        Cow::Owned(defines)
+    }
+
+    fn vertex_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn fragment_defines(&self) -> Cow<'_, [Define<'_>]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn snippet(&self, _: &str) -> Option<Cow<'_, str>> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gl_matrix4rust::{mat4::Mat4, vec3::Vec3};
+
+    use crate::{
+        frustum::{cascade_splits, FrustumCorners},
+        light::{
+            directional_light::DirectionalLight, point_light::PointLight, spot_light::SpotLight,
+        },
+    };
+
+    use super::{
+        cascade_shadow_matrix, directional_shadow_matrix, point_shadow_matrices,
+        shadow_atlas_regions, spot_shadow_matrix,
+    };
+
+    /// Transforms a point by a projection matrix, perspective division included.
+    fn project(matrix: &Mat4<f64>, point: Vec3<f64>) -> [f64; 3] {
+        let ndc = *matrix * point;
+        [*ndc.x(), *ndc.y(), *ndc.z()]
+    }
+
+    #[test]
+    fn test_shadow_atlas_regions() {
+        assert!(shadow_atlas_regions(0).is_empty());
+        assert_eq!(shadow_atlas_regions(1), vec![[0.0, 0.0, 1.0, 1.0]]);
+
+        let regions = shadow_atlas_regions(2);
+        assert_eq!(regions, vec![[0.0, 0.0, 0.5, 0.5], [0.5, 0.0, 0.5, 0.5]]);
+
+        let regions = shadow_atlas_regions(5);
+        assert_eq!(regions.len(), 5);
+        assert_eq!(regions[3], [0.0, 1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0]);
+        for (i, a) in regions.iter().enumerate() {
+            assert!(a[0] + a[2] <= 1.0 && a[1] + a[3] <= 1.0);
+            for b in regions.iter().skip(i + 1) {
+                let overlapped = a[0] < b[0] + b[2]
+                    && b[0] < a[0] + a[2]
+                    && a[1] < b[1] + b[3]
+                    && b[1] < a[1] + a[3];
+                assert!(!overlapped);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cascade_shadow_matrix() {
+        let direction = Vec3::<f64>::new(1.0, -2.0, 0.5).normalize();
+        let corners = |offset: Vec3<f64>| -> FrustumCorners {
+            [
+                [-1.0, -1.0, -1.0],
+                [1.0, -1.0, -1.0],
+                [1.0, 1.0, -1.0],
+                [-1.0, 1.0, -1.0],
+                [-4.0, -4.0, -8.0],
+                [4.0, -4.0, -8.0],
+                [4.0, 4.0, -8.0],
+                [-4.0, 4.0, -8.0],
+            ]
+            .map(|[x, y, z]| Vec3::<f64>::new(x, y, z) + offset)
+        };
+
+        let resolution = 1024;
+        for offset in [
+            Vec3::<f64>::new(0.0, 0.0, 0.0),
+            Vec3::<f64>::new(0.013, 0.0, 0.0),
+            Vec3::<f64>::new(0.0, 0.021, -0.007),
+            Vec3::<f64>::new(37.5, -3.1, 12.9),
+        ] {
+            let matrix = cascade_shadow_matrix(&corners(offset), &direction, resolution, 10.0);
+
+            // slice stays inside the shadow casting box
+            for corner in corners(offset) {
+                let [x, y, z] = project(&matrix, corner);
+                assert!(x.abs() < 1.0 && y.abs() < 1.0 && z.abs() <= 1.0 + 1e-9);
+            }
+
+            // world origin always lands on a texel corner, no matter how the slice moves
+            let [x, y, _] = project(&matrix, Vec3::<f64>::new(0.0, 0.0, 0.0));
+            for texel in [x, y] {
+                let texel = texel * resolution as f64 / 2.0;
+                assert!((texel - texel.round()).abs() < 1e-6);
+            }
+        }
+
+        // casters between the light and the slice are kept inside the shadow casting box
+        let matrix =
+            cascade_shadow_matrix(&corners(Vec3::new(0.0, 0.0, 0.0)), &direction, 1024, 10.0);
+        let [_, _, z] = project(&matrix, direction * -12.0);
+        assert!(z > -1.0 && z < 1.0);
+    }
+
+    #[test]
+    fn test_directional_shadow_matrix() {
+        let light = DirectionalLight::new(
+            Vec3::new(0.0, -1.0, 0.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+        );
+        let view = Mat4::<f64>::from_look_at(
+            &Vec3::new(0.0, 2.0, 0.0),
+            &Vec3::new(0.0, 2.0, -1.0),
+            &Vec3::new(0.0, 1.0, 0.0),
+        );
+        let proj = Mat4::<f64>::from_perspective(std::f64::consts::FRAC_PI_2, 1.0, 0.1, None);
+        let view_proj_matrix = proj * view;
+
+        let splits = cascade_splits(0.1, 50.0, 4, 0.75);
+        let matrices = splits
+            .windows(2)
+            .map(|range| {
+                directional_shadow_matrix(&light, &view_proj_matrix, range[0], range[1], 1024)
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // a point in front of camera is covered by the cascade its view distance falls in
+        for (cascade, range) in splits.windows(2).enumerate() {
+            let distance = (range[0] + range[1]) / 2.0;
+            let [x, y, z] = project(&matrices[cascade], Vec3::<f64>::new(0.0, 0.0, -distance));
+            assert!(x.abs() <= 1.0 && y.abs() <= 1.0 && z.abs() <= 1.0);
+        }
+
+        // nearer cascades get finer shadow casting boxes
+        let [x0, _, _] = project(&matrices[0], Vec3::<f64>::new(1.0, 0.0, 0.0));
+        let [x1, _, _] = project(&matrices[0], Vec3::<f64>::new(0.0, 0.0, 0.0));
+        let [x2, _, _] = project(&matrices[3], Vec3::<f64>::new(1.0, 0.0, 0.0));
+        let [x3, _, _] = project(&matrices[3], Vec3::<f64>::new(0.0, 0.0, 0.0));
+        assert!((x0 - x1).abs() > (x2 - x3).abs());
+    }
+
+    #[test]
+    fn test_spot_shadow_matrix() {
+        let light = SpotLight::new(
+            Vec3::new(0.0, 5.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+            std::f32::consts::PI / 8.0,
+            std::f32::consts::PI / 4.0,
+        );
+        let matrix = spot_shadow_matrix(&light);
+
+        let [x, y, z] = project(&matrix, Vec3::<f64>::new(0.0, 0.0, 0.0));
+        assert!(x.abs() < 1e-9 && y.abs() < 1e-9);
+        assert!(z > -1.0 && z < 1.0);
+
+        // a point on the outer cutoff cone lies on the edge of the projection
+        let [x, _, _] = project(&matrix, Vec3::<f64>::new(5.0, 0.0, 0.0));
+        assert!((x.abs() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_point_shadow_matrices() {
+        let light = PointLight::new(
+            Vec3::new(1.0, 2.0, 3.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+        );
+        let matrices = point_shadow_matrices(&light);
+        let directions = [
+            [1.0, 0.0, 0.0],
+            [-1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, -1.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [0.0, 0.0, -1.0],
+        ];
+
+        // a point in front of each face is projected to the center of that face only
+        for (face, matrix) in matrices.iter().enumerate() {
+            for (other, [dx, dy, dz]) in directions.iter().enumerate() {
+                let point = Vec3::<f64>::new(1.0 + dx * 5.0, 2.0 + dy * 5.0, 3.0 + dz * 5.0);
+                let [x, y, z] = project(matrix, point);
+                let inside = x.abs() <= 1.0 && y.abs() <= 1.0 && z.abs() <= 1.0;
+                if face == other {
+                    assert!(x.abs() < 1e-9 && y.abs() < 1e-9 && inside);
+                } else {
+                    assert!(!inside);
+                }
+            }
+        }
+    }
+}
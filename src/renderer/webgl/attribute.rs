@@ -117,14 +117,32 @@ where
 pub struct VertexAttributeArrayUnbinder {
     gl: WebGl2RenderingContext,
     location: u32,
+    instanced: bool,
 }
 
 impl VertexAttributeArrayUnbinder {
     pub(crate) fn new(location: u32, gl: WebGl2RenderingContext) -> Self {
-        Self { gl, location }
+        Self {
+            gl,
+            location,
+            instanced: false,
+        }
+    }
+
+    /// Constructs a new unbinder for an instanced vertex attribute array,
+    /// which resets vertex attribute divisor as well when unbinding.
+    pub(crate) fn new_instanced(location: u32, gl: WebGl2RenderingContext) -> Self {
+        Self {
+            gl,
+            location,
+            instanced: true,
+        }
     }
 
     pub fn unbind(self) {
+        if self.instanced {
+            self.gl.vertex_attrib_divisor(self.location, 0);
+        }
         self.gl.disable_vertex_attrib_array(self.location)
     }
 }
@@ -376,21 +376,23 @@ impl BufferRuntime {
 
         let gl = &self.gl;
 
+        // element array buffers could not be bound to ARRAY_BUFFER in WebGL,
+        // while COPY_READ_BUFFER accepts buffers of any kind
         let binding = if cfg!(feature = "rebind") {
-            gl.array_buffer_binding()
+            gl.copy_read_buffer_binding()
         } else {
             None
         };
 
         let data = Uint8Array::new_with_length(self.buffer_byte_length as u32);
-        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(buffer));
+        gl.bind_buffer(WebGl2RenderingContext::COPY_READ_BUFFER, Some(buffer));
         gl.get_buffer_sub_data_with_i32_and_array_buffer_view(
-            WebGl2RenderingContext::ARRAY_BUFFER,
+            WebGl2RenderingContext::COPY_READ_BUFFER,
             0,
             &data,
         );
 
-        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, binding.as_ref());
+        gl.bind_buffer(WebGl2RenderingContext::COPY_READ_BUFFER, binding.as_ref());
 
         Some(data.buffer())
     }
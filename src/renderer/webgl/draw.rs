@@ -40,6 +40,17 @@ pub enum ElementIndicesDataType {
     UNSIGNED_INT,
 }
 
+impl ElementIndicesDataType {
+    /// Gets bytes length of a data type.
+    pub fn byte_length(&self) -> usize {
+        match self {
+            ElementIndicesDataType::UNSIGNED_BYTE => 1,
+            ElementIndicesDataType::UNSIGNED_SHORT => 2,
+            ElementIndicesDataType::UNSIGNED_INT => 4,
+        }
+    }
+}
+
 #[allow(non_camel_case_types)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum DrawMode {
@@ -107,10 +118,11 @@ impl<'a> DrawParams<'a> {
 }
 
 pub struct Draw<'a> {
+    params: DrawParams<'a>,
+    instance_count: Option<usize>,
     // framebuffer: Option<&'a Framebuffer>,
     // draw_buffers: Option<Vec<OperableBuffer>>,
     // program: Option<&'a Program>,
-    params: DrawParams<'a>,
     // entity: Option<&'a dyn Entity>,
     // geometry: Option<&'a dyn Geometry>,
     // material: Option<&'a dyn StandardMaterial>,
@@ -157,6 +169,17 @@ impl<'a> Draw<'a> {
     pub fn from_geometry(geometry: &'a dyn Geometry) -> Self {
         Self {
             params: DrawParams::FromGeometry(geometry),
+            instance_count: None,
+        }
+    }
+
+    /// Constructs a new instanced draw command from a [`Geometry`],
+    /// using [`WebGl2RenderingContext::draw_arrays_instanced`] or
+    /// [`WebGl2RenderingContext::draw_elements_instanced_with_i32`].
+    pub fn from_geometry_instanced(geometry: &'a dyn Geometry, instance_count: usize) -> Self {
+        Self {
+            params: DrawParams::FromGeometry(geometry),
+            instance_count: Some(instance_count),
         }
     }
 
@@ -182,8 +205,16 @@ impl<'a> Draw<'a> {
                 };
                 indices.bind(BufferTarget::ELEMENT_ARRAY_BUFFER)?;
                 let indices_type = indices_type.gl_enum();
-                match indices_range {
-                    Some(indices_range) => {
+                match (indices_range, self.instance_count) {
+                    // range hint is not available for instanced drawing
+                    (_, Some(instance_count)) => gl.draw_elements_instanced_with_i32(
+                        mode,
+                        count,
+                        indices_type,
+                        offset,
+                        instance_count as i32,
+                    ),
+                    (Some(indices_range), None) => {
                         let start = indices_range.start as u32;
                         let end = indices_range.end as u32;
                         gl.draw_range_elements_with_i32(
@@ -195,14 +226,17 @@ impl<'a> Draw<'a> {
                             offset,
                         );
                     }
-                    None => gl.draw_elements_with_i32(mode, count, indices_type, offset),
+                    (None, None) => gl.draw_elements_with_i32(mode, count, indices_type, offset),
                 }
 
                 indices.unbind(BufferTarget::ELEMENT_ARRAY_BUFFER)?;
             }
-            None => {
-                gl.draw_arrays(mode, offset, count);
-            }
+            None => match self.instance_count {
+                Some(instance_count) => {
+                    gl.draw_arrays_instanced(mode, offset, count, instance_count as i32)
+                }
+                None => gl.draw_arrays(mode, offset, count),
+            },
         }
 
         Ok(())
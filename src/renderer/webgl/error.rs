@@ -58,6 +58,16 @@ pub enum Error {
     FramebufferTargetOccupied(FramebufferTarget),
     FramebufferUnboundAsRead,
     FramebufferUnboundAsDraw,
+    RenderGraphDuplicatePass(String),
+    RenderGraphNoSuchPass(String),
+    RenderGraphDuplicateResource(String),
+    RenderGraphNoSuchResource(String),
+    RenderGraphReadBeforeWrite {
+        pass: String,
+        resource: String,
+    },
+    RenderGraphCyclicPasses(Vec<String>),
+    RenderGraphTextureUnavailable(String),
     CommonWebGLError(Option<String>),
 }
 
@@ -65,8 +75,123 @@ impl Error {}
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        log::error!("{:?}", self);
-        todo!()
+        fn with_reason(
+            f: &mut std::fmt::Formatter<'_>,
+            msg: &str,
+            reason: &Option<String>,
+        ) -> std::fmt::Result {
+            match reason {
+                Some(reason) => write!(f, "{}: {}", msg, reason),
+                None => f.write_str(msg),
+            }
+        }
+
+        match self {
+            Error::WebGL2Unsupported => f.write_str("WebGL2 is not supported"),
+            Error::CreateProgramFailure => f.write_str("failed to create program"),
+            Error::CreateBufferFailure => f.write_str("failed to create buffer"),
+            Error::CreateFramebufferFailure => f.write_str("failed to create framebuffer"),
+            Error::CreateRenderbufferFailure => f.write_str("failed to create renderbuffer"),
+            Error::CreateTextureFailure => f.write_str("failed to create texture"),
+            Error::CreateSamplerFailure => f.write_str("failed to create sampler"),
+            Error::CreateVertexShaderFailure => f.write_str("failed to create vertex shader"),
+            Error::CreateFragmentShaderFailure => f.write_str("failed to create fragment shader"),
+            Error::CreateFenceSyncFailure => f.write_str("failed to create fence sync"),
+            Error::CreateVertexArrayObjectFailure => {
+                f.write_str("failed to create vertex array object")
+            }
+            Error::ExtensionUnsupported(name) => write!(f, "extension {} is not supported", name),
+            Error::ReadPixelsFailure(reason) => with_reason(f, "failed to read pixels", reason),
+            Error::ClientWaitFailure(reason) => with_reason(f, "failed to wait for sync", reason),
+            Error::CompileShaderFailure(reason) => {
+                with_reason(f, "failed to compile shader", reason)
+            }
+            Error::CompileProgramFailure(reason) => {
+                with_reason(f, "failed to compile program", reason)
+            }
+            Error::ProgramOccupied => f.write_str("program is occupied by another store"),
+            Error::ProgramUnused => f.write_str("program is not in use"),
+            Error::ProgramUsing => f.write_str("program is in use already"),
+            Error::VertexArrayObjectOccupied => f.write_str("vertex array object is occupied"),
+            Error::NoSuchAttribute(binding) => write!(f, "no attribute {:?}", binding),
+            Error::NoSuchUniform(binding) => write!(f, "no uniform {:?}", binding),
+            Error::NoSuchUniformBlock(binding) => write!(f, "no uniform block {:?}", binding),
+            Error::BufferUninitialized => f.write_str("buffer is not initialized"),
+            Error::BufferAlreadyInitialized => f.write_str("buffer is initialized already"),
+            Error::BufferTargetOccupied(target) => {
+                write!(f, "buffer target {:?} is occupied", target)
+            }
+            Error::UniformBufferObjectMountPointOccupied(mount_point) => write!(
+                f,
+                "uniform buffer object mount point {} is occupied",
+                mount_point
+            ),
+            Error::RegisterBufferToMultipleStore => {
+                f.write_str("buffer could not be registered to multiple stores")
+            }
+            Error::TextureUninitialized => f.write_str("texture is not initialized"),
+            Error::TextureAlreadyInitialized => f.write_str("texture is initialized already"),
+            Error::TextureTargetOccupied(unit, target) => write!(
+                f,
+                "texture target {:?} of texture unit {:?} is occupied",
+                target, unit
+            ),
+            Error::TextureInternalFormatMismatched => {
+                f.write_str("texture internal format mismatched")
+            }
+            Error::TextureInternalFormatUnsupported(format) => {
+                write!(f, "texture internal format {:?} is not supported", format)
+            }
+            Error::TextureUploadImageFailure(reason) => {
+                with_reason(f, "failed to upload texture image", reason)
+            }
+            Error::RegisterTextureToMultipleStore => {
+                f.write_str("texture could not be registered to multiple stores")
+            }
+            Error::TextureSizeOverflowed { max, value } => write!(
+                f,
+                "texture size {}x{} exceeds maximum {}x{}",
+                value.0, value.1, max.0, max.1
+            ),
+            Error::TextureUnitOverflowed { max, value } => {
+                write!(f, "texture unit {} exceeds maximum {}", value, max)
+            }
+            Error::FramebufferUninitialized => f.write_str("framebuffer is not initialized"),
+            Error::FramebufferAlreadyInitialized => {
+                f.write_str("framebuffer is initialized already")
+            }
+            Error::FramebufferTargetOccupied(target) => {
+                write!(f, "framebuffer target {:?} is occupied", target)
+            }
+            Error::FramebufferUnboundAsRead => f.write_str("framebuffer is not bound as read"),
+            Error::FramebufferUnboundAsDraw => f.write_str("framebuffer is not bound as draw"),
+            Error::RenderGraphDuplicatePass(label) => {
+                write!(f, "render pass {} exists already", label)
+            }
+            Error::RenderGraphNoSuchPass(label) => write!(f, "no render pass {}", label),
+            Error::RenderGraphDuplicateResource(name) => {
+                write!(f, "render graph resource {} exists already", name)
+            }
+            Error::RenderGraphNoSuchResource(name) => {
+                write!(f, "no render graph resource {}", name)
+            }
+            Error::RenderGraphReadBeforeWrite { pass, resource } => write!(
+                f,
+                "render pass {} reads resource {} before any pass writes it",
+                pass, resource
+            ),
+            Error::RenderGraphCyclicPasses(passes) => {
+                write!(f, "render passes {} are cyclic", passes.join(", "))
+            }
+            Error::RenderGraphTextureUnavailable(name) => {
+                write!(
+                    f,
+                    "texture of render graph resource {} is unavailable",
+                    name
+                )
+            }
+            Error::CommonWebGLError(reason) => with_reason(f, "WebGL error", reason),
+        }
     }
 }
 
@@ -193,7 +193,8 @@ pub enum SizePolicy {
 }
 
 impl SizePolicy {
-    fn size(&self, gl: &WebGl2RenderingContext) -> (usize, usize) {
+    /// Returns width and height under this policy.
+    pub fn size(&self, gl: &WebGl2RenderingContext) -> (usize, usize) {
         match self {
             Self::FollowDrawingBuffer => {
                 let width = gl.drawing_buffer_width() as usize;
@@ -1037,6 +1038,39 @@ impl Framebuffer {
         Ok(())
     }
 
+    /// Reads pixels into the buffer currently bound to [`WebGl2RenderingContext::PIXEL_PACK_BUFFER`].
+    /// Pixels are written asynchronously, fetches them after the GPU commands completed.
+    pub fn read_pixels_to_pixel_pack_buffer(
+        &mut self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        pixel_format: TextureUncompressedPixelFormat,
+        data_type: TextureUncompressedPixelDataType,
+        dst_offset: i32,
+    ) -> Result<(), Error> {
+        let runtime = self
+            .runtime
+            .as_ref()
+            .ok_or(Error::FramebufferUninitialized)?;
+        runtime.is_bound_as_read()?;
+        runtime
+            .gl
+            .read_pixels_with_i32(
+                x,
+                y,
+                width,
+                height,
+                pixel_format.gl_enum(),
+                data_type.gl_enum(),
+                dst_offset,
+            )
+            .or_else(|err| Err(Error::ReadPixelsFailure(err.as_string())))?;
+
+        Ok(())
+    }
+
     /// Returns number of sample of the render buffers if multisample is enabled.
     pub fn renderbuffer_samples(&self) -> Option<usize> {
         self.renderbuffer_samples
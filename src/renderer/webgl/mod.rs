@@ -87,7 +87,7 @@ pub enum WebGL2ContextPowerPerformance {
     LowPower,
 }
 
-const DEFAULT_GLSL_SHADER_CODE_SNIPPETS: [(Cow<'static, str>, Cow<'static, str>); 4] = [
+const DEFAULT_GLSL_SHADER_CODE_SNIPPETS: [(Cow<'static, str>, Cow<'static, str>); 5] = [
     (
         Cow::Borrowed("UniversalUniforms"),
         Cow::Borrowed(include_str!(
@@ -100,6 +100,12 @@ const DEFAULT_GLSL_SHADER_CODE_SNIPPETS: [(Cow<'static, str>, Cow<'static, str>)
             "../../pipeline/webgl/shaders/snippets/lighting.glsl"
         )),
     ),
+    (
+        Cow::Borrowed("Pbr"),
+        Cow::Borrowed(include_str!(
+            "../../pipeline/webgl/shaders/snippets/pbr.glsl"
+        )),
+    ),
     (
         Cow::Borrowed("Gamma"),
         Cow::Borrowed(include_str!(
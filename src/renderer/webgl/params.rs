@@ -14,6 +14,8 @@ pub trait GetWebGlParameters {
 
     fn uniform_buffer_binding(&self) -> Option<WebGlBuffer>;
 
+    fn copy_read_buffer_binding(&self) -> Option<WebGlBuffer>;
+
     fn pixel_unpack_buffer_binding(&self) -> Option<WebGlBuffer>;
 
     fn renderbuffer_binding(&self) -> Option<WebGlRenderbuffer>;
@@ -82,6 +84,12 @@ impl GetWebGlParameters for WebGl2RenderingContext {
             .cast_into_unchecked::<WebGlBuffer>()
     }
 
+    fn copy_read_buffer_binding(&self) -> Option<WebGlBuffer> {
+        self.get_parameter(WebGl2RenderingContext::COPY_READ_BUFFER_BINDING)
+            .unwrap()
+            .cast_into_unchecked::<WebGlBuffer>()
+    }
+
     fn pixel_unpack_buffer_binding(&self) -> Option<WebGlBuffer> {
         self.get_parameter(WebGl2RenderingContext::PIXEL_UNPACK_BUFFER_BINDING)
             .unwrap()
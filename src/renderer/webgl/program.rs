@@ -264,7 +264,7 @@ impl Program {
                     self.gl
                         .vertex_attrib_divisor(offset_location, *divisor as u32);
 
-                    unbinders.push(VertexAttributeArrayUnbinder::new(
+                    unbinders.push(VertexAttributeArrayUnbinder::new_instanced(
                         offset_location,
                         self.gl.clone(),
                     ));
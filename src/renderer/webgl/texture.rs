@@ -2292,6 +2292,16 @@ impl TextureShared {
         Ok(())
     }
 
+    fn native(&mut self) -> Result<WebGlTexture, Error> {
+        self.upload()?;
+        let runtime = self.runtime.as_ref().ok_or(Error::TextureUninitialized)?;
+        let (texture, _) = runtime
+            .texture
+            .as_ref()
+            .ok_or(Error::TextureUninitialized)?;
+        Ok(texture.clone())
+    }
+
     fn set_texture_parameter(&mut self, texture_param: TextureParameter) {
         let index = self
             .texture_params
@@ -2499,6 +2509,15 @@ impl<L> Texture<L> {
         self.shared.borrow_mut().upload()
     }
 
+    /// Returns native [`WebGlTexture`] of this texture.
+    /// Texture is created and pending data are uploaded if not yet.
+    ///
+    /// Native texture is useful when attaching it to a framebuffer,
+    /// developer should never delete it manually.
+    pub fn native(&self) -> Result<WebGlTexture, Error> {
+        self.shared.borrow_mut().native()
+    }
+
     /// Returns a list of texture parameters.
     pub fn texture_parameters(&self) -> Vec<TextureParameter> {
         self.shared.borrow().texture_params.clone()
@@ -26,11 +26,13 @@ pub(crate) const AREA_LIGHTS_COUNT_DEFINE: &'static str = "AREA_LIGHTS_COUNT";
 pub const MAX_DIRECTIONAL_LIGHTS: usize = 12;
 pub(crate) const MAX_DIRECTIONAL_LIGHTS_STRING: &'static str = "12";
 pub(crate) const DIRECTIONAL_LIGHTS_COUNT_DEFINE: &'static str = "DIRECTIONAL_LIGHTS_COUNT";
-/// Maximum point lights.
+/// Maximum point lights packed into Uniform Buffer Object `atoy_Lights`.
+/// Point lights beyond are applied by clustered lighting only.
 pub const MAX_POINT_LIGHTS: usize = 40;
 pub(crate) const MAX_POINT_LIGHTS_STRING: &'static str = "40";
 pub(crate) const POINT_LIGHTS_COUNT_DEFINE: &'static str = "POINT_LIGHTS_COUNT";
-/// Maximum spot lights.
+/// Maximum spot lights packed into Uniform Buffer Object `atoy_Lights`.
+/// Spot lights beyond are applied by clustered lighting only.
 pub const MAX_SPOT_LIGHTS: usize = 12;
 pub(crate) const MAX_SPOT_LIGHTS_STRING: &'static str = "12";
 pub(crate) const SPOT_LIGHTS_COUNT_DEFINE: &'static str = "SPOT_LIGHTS_COUNT";
@@ -187,15 +189,8 @@ impl Scene {
     }
 
     /// Adds a point light.
+    /// Point lights beyond [`MAX_POINT_LIGHTS`] are applied by clustered lighting only.
     pub fn add_point_light(&mut self, light: PointLight) {
-        if self.point_lights.len() >= MAX_POINT_LIGHTS {
-            warn!(
-                "only {} point lights are available, ignored",
-                MAX_POINT_LIGHTS
-            );
-            return;
-        }
-
         self.point_lights.push(light);
     }
 
@@ -219,15 +214,8 @@ impl Scene {
     }
 
     /// Adds a spot light.
+    /// Spot lights beyond [`MAX_SPOT_LIGHTS`] are applied by clustered lighting only.
     pub fn add_spot_light(&mut self, light: SpotLight) {
-        if self.spot_lights.len() == MAX_SPOT_LIGHTS {
-            warn!(
-                "only {} spot lights are available, ignored",
-                MAX_SPOT_LIGHTS
-            );
-            return;
-        }
-
         self.spot_lights.push(light);
     }
 
@@ -12,8 +12,17 @@ use crate::{
     controller::Controller,
     entity::Entity,
     error::Error,
-    pipeline::webgl::{HdrToneMappingType, StandardPipeline, StandardPipelineShading},
-    renderer::{webgl::WebGL2Renderer, Renderer},
+    pipeline::webgl::{
+        shading::picking::{PickedElement, PickingRegion},
+        HdrToneMappingType, StandardPipeline, StandardPipelineShading,
+    },
+    renderer::{
+        webgl::{
+            texture::{Texture, Texture3D},
+            WebGL2Renderer,
+        },
+        Renderer,
+    },
     request_animation_frame,
     scene::Scene,
 };
@@ -326,6 +335,76 @@ impl Viewer {
         }
     }
 
+    pub fn fxaa_enabled(&self) -> bool {
+        unsafe { (*self.standard_pipeline).fxaa_enabled() }
+    }
+
+    pub fn enable_fxaa(&mut self) {
+        unsafe {
+            (*self.standard_pipeline).enable_fxaa();
+        }
+    }
+
+    pub fn disable_fxaa(&mut self) {
+        unsafe {
+            (*self.standard_pipeline).disable_fxaa();
+        }
+    }
+
+    pub fn ssao_enabled(&self) -> bool {
+        unsafe { (*self.standard_pipeline).ssao_enabled() }
+    }
+
+    pub fn enable_ssao(&mut self) {
+        unsafe {
+            (*self.standard_pipeline).enable_ssao();
+        }
+    }
+
+    pub fn disable_ssao(&mut self) {
+        unsafe {
+            (*self.standard_pipeline).disable_ssao();
+        }
+    }
+
+    pub fn depth_of_field_enabled(&self) -> bool {
+        unsafe { (*self.standard_pipeline).depth_of_field_enabled() }
+    }
+
+    pub fn enable_depth_of_field(&mut self) {
+        unsafe {
+            (*self.standard_pipeline).enable_depth_of_field();
+        }
+    }
+
+    pub fn disable_depth_of_field(&mut self) {
+        unsafe {
+            (*self.standard_pipeline).disable_depth_of_field();
+        }
+    }
+
+    pub fn color_grading_enabled(&self) -> bool {
+        unsafe { (*self.standard_pipeline).color_grading_enabled() }
+    }
+
+    pub fn enable_color_grading(&mut self) {
+        unsafe {
+            (*self.standard_pipeline).enable_color_grading();
+        }
+    }
+
+    pub fn disable_color_grading(&mut self) {
+        unsafe {
+            (*self.standard_pipeline).disable_color_grading();
+        }
+    }
+
+    pub fn set_color_grading_lut(&mut self, lut: Option<Texture<Texture3D>>) {
+        unsafe {
+            (*self.standard_pipeline).set_color_grading_lut(lut);
+        }
+    }
+
     pub fn add_controller<C>(&mut self, mut controller: C)
     where
         C: Controller + 'static,
@@ -404,6 +483,73 @@ impl Viewer {
         }
     }
 
+    pub async fn pick_entities_async(
+        &mut self,
+        region: PickingRegion,
+        fully_inside: bool,
+    ) -> Result<Vec<Uuid>, Error> {
+        unsafe {
+            let timestamp = *self.timestamp;
+            let mut scene = self.scene.borrow_mut();
+            let mut camera = self.camera.borrow_mut();
+            let mut renderer = self.renderer.borrow_mut();
+            let pipeline = &mut *self.standard_pipeline;
+
+            let previous_pipeline_shading = pipeline.pipeline_shading();
+            pipeline.set_pipeline_shading(StandardPipelineShading::Picking);
+            renderer.render(pipeline, &mut *camera, &mut *scene, timestamp)?;
+            pipeline.set_pipeline_shading(previous_pipeline_shading);
+
+            drop(scene);
+            drop(camera);
+            drop(renderer);
+
+            pipeline.pick_entities_async(region, fully_inside).await
+        }
+    }
+
+    pub fn pick_entities(
+        &mut self,
+        region: &PickingRegion,
+        fully_inside: bool,
+    ) -> Result<Vec<Rc<RefCell<dyn Entity>>>, Error> {
+        unsafe {
+            let timestamp = *self.timestamp;
+            let mut scene = self.scene.borrow_mut();
+            let mut camera = self.camera.borrow_mut();
+            let mut renderer = self.renderer.borrow_mut();
+            let pipeline = &mut *self.standard_pipeline;
+
+            let previous_pipeline_shading = pipeline.pipeline_shading();
+            pipeline.set_pipeline_shading(StandardPipelineShading::Picking);
+            renderer.render(pipeline, &mut *camera, &mut *scene, timestamp)?;
+            pipeline.set_pipeline_shading(previous_pipeline_shading);
+
+            pipeline.pick_entities(region, fully_inside)
+        }
+    }
+
+    pub fn pick_element(
+        &mut self,
+        window_position_x: i32,
+        window_position_y: i32,
+    ) -> Result<Option<PickedElement>, Error> {
+        unsafe {
+            let timestamp = *self.timestamp;
+            let mut scene = self.scene.borrow_mut();
+            let mut camera = self.camera.borrow_mut();
+            let mut renderer = self.renderer.borrow_mut();
+            let pipeline = &mut *self.standard_pipeline;
+
+            let previous_pipeline_shading = pipeline.pipeline_shading();
+            pipeline.set_pipeline_shading(StandardPipelineShading::Picking);
+            renderer.render(pipeline, &mut *camera, &mut *scene, timestamp)?;
+            pipeline.set_pipeline_shading(previous_pipeline_shading);
+
+            pipeline.pick_element(window_position_x, window_position_y)
+        }
+    }
+
     pub fn pick_position(
         &mut self,
         window_position_x: i32,